@@ -0,0 +1,20 @@
+-- Effective-dated customs tariff per 8-digit HSN (replaces free-text items.bcd/sws/igst for duty lookups).
+
+CREATE TABLE IF NOT EXISTS hsn_tariff_rates (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    hsn_code TEXT NOT NULL CHECK (length(hsn_code) = 8),
+    description TEXT,
+    effective_from TEXT NOT NULL,
+    effective_to TEXT,
+    bcd_rate REAL NOT NULL DEFAULT 0,
+    sws_rate REAL NOT NULL DEFAULT 0,
+    igst_rate REAL NOT NULL DEFAULT 0,
+    cess_rate REAL NOT NULL DEFAULT 0,
+    notification_ref TEXT,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%S', 'now', 'localtime')),
+    updated_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%S', 'now', 'localtime')),
+    UNIQUE (hsn_code, effective_from)
+);
+
+CREATE INDEX IF NOT EXISTS idx_hsn_tariff_rates_lookup
+    ON hsn_tariff_rates (hsn_code, effective_from, effective_to);
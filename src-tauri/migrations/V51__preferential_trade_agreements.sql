@@ -0,0 +1,83 @@
+-- Preferential trade agreements (FTA/CEPA): member countries, concessional BCD per HSN, certificates of origin per shipment.
+
+CREATE TABLE IF NOT EXISTS trade_agreements (
+    code TEXT PRIMARY KEY NOT NULL,
+    name TEXT NOT NULL,
+    notification_ref TEXT,
+    effective_from TEXT NOT NULL,
+    effective_to TEXT,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%S', 'now', 'localtime')),
+    updated_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%S', 'now', 'localtime'))
+);
+
+-- One row per accepted spelling of a member country; matching ignores case and punctuation.
+CREATE TABLE IF NOT EXISTS trade_agreement_members (
+    agreement_code TEXT NOT NULL,
+    country_code TEXT NOT NULL,
+    country_name TEXT NOT NULL,
+    PRIMARY KEY (agreement_code, country_name),
+    FOREIGN KEY (agreement_code) REFERENCES trade_agreements(code) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS trade_agreement_rates (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    agreement_code TEXT NOT NULL,
+    hsn_code TEXT NOT NULL CHECK (length(hsn_code) = 8),
+    concessional_bcd_rate REAL NOT NULL CHECK (concessional_bcd_rate >= 0),
+    effective_from TEXT NOT NULL,
+    effective_to TEXT,
+    notification_ref TEXT,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%S', 'now', 'localtime')),
+    updated_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%S', 'now', 'localtime')),
+    UNIQUE (agreement_code, hsn_code, effective_from),
+    FOREIGN KEY (agreement_code) REFERENCES trade_agreements(code) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_trade_agreement_rates_lookup
+    ON trade_agreement_rates (hsn_code, agreement_code, effective_from, effective_to);
+
+CREATE TABLE IF NOT EXISTS certificates_of_origin (
+    id TEXT PRIMARY KEY NOT NULL,
+    shipment_id TEXT NOT NULL,
+    agreement_code TEXT NOT NULL,
+    certificate_number TEXT NOT NULL,
+    country_of_origin TEXT NOT NULL,
+    issue_date TEXT NOT NULL,
+    valid_until TEXT,
+    attachment_path TEXT,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%S', 'now', 'localtime')),
+    updated_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%S', 'now', 'localtime')),
+    UNIQUE (shipment_id, certificate_number),
+    FOREIGN KEY (shipment_id) REFERENCES shipments(id) ON DELETE CASCADE,
+    FOREIGN KEY (agreement_code) REFERENCES trade_agreements(code)
+);
+
+CREATE INDEX IF NOT EXISTS idx_certificates_of_origin_shipment
+    ON certificates_of_origin (shipment_id);
+
+INSERT OR IGNORE INTO trade_agreements (code, name, notification_ref, effective_from) VALUES
+    ('IN-AE-CEPA', 'India-UAE Comprehensive Economic Partnership Agreement', '22/2022-Customs', '2022-05-01'),
+    ('IN-KR-CEPA', 'India-Korea Comprehensive Economic Partnership Agreement', '152/2009-Customs', '2010-01-01'),
+    ('IN-JP-CEPA', 'India-Japan Comprehensive Economic Partnership Agreement', '69/2011-Customs', '2011-08-01'),
+    ('AIFTA', 'ASEAN-India Free Trade Agreement', '46/2011-Customs', '2010-01-01'),
+    ('IN-AU-ECTA', 'India-Australia Economic Cooperation and Trade Agreement', '112/2022-Customs', '2022-12-29');
+
+INSERT OR IGNORE INTO trade_agreement_members (agreement_code, country_code, country_name) VALUES
+    ('IN-AE-CEPA', 'AE', 'United Arab Emirates'),
+    ('IN-AE-CEPA', 'AE', 'UAE'),
+    ('IN-KR-CEPA', 'KR', 'Korea'),
+    ('IN-KR-CEPA', 'KR', 'South Korea'),
+    ('IN-KR-CEPA', 'KR', 'S.Korea'),
+    ('IN-KR-CEPA', 'KR', 'Republic of Korea'),
+    ('IN-JP-CEPA', 'JP', 'Japan'),
+    ('AIFTA', 'BN', 'Brunei'),
+    ('AIFTA', 'KH', 'Cambodia'),
+    ('AIFTA', 'ID', 'Indonesia'),
+    ('AIFTA', 'LA', 'Laos'),
+    ('AIFTA', 'MY', 'Malaysia'),
+    ('AIFTA', 'MM', 'Myanmar'),
+    ('AIFTA', 'PH', 'Philippines'),
+    ('AIFTA', 'SG', 'Singapore'),
+    ('AIFTA', 'TH', 'Thailand'),
+    ('AIFTA', 'VN', 'Vietnam'),
+    ('IN-AU-ECTA', 'AU', 'Australia');
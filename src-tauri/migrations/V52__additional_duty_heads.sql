@@ -0,0 +1,103 @@
+-- Extra duty heads: AIDC, safeguard duty and SWS exemption on the tariff; anti-dumping duty by origin and exporter.
+
+ALTER TABLE hsn_tariff_rates ADD COLUMN aidc_rate REAL NOT NULL DEFAULT 0;
+ALTER TABLE hsn_tariff_rates ADD COLUMN safeguard_rate REAL NOT NULL DEFAULT 0;
+-- 1 when the goods are exempt from Social Welfare Surcharge.
+ALTER TABLE hsn_tariff_rates ADD COLUMN sws_exempt INTEGER NOT NULL DEFAULT 0;
+
+-- supplier_id '' is the "any other exporter" residual rate for the origin country.
+CREATE TABLE IF NOT EXISTS anti_dumping_duties (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    hsn_code TEXT NOT NULL CHECK (length(hsn_code) = 8),
+    country_of_origin TEXT NOT NULL,
+    supplier_id TEXT NOT NULL DEFAULT '',
+    rate_percent REAL NOT NULL DEFAULT 0 CHECK (rate_percent >= 0),
+    specific_per_unit REAL NOT NULL DEFAULT 0 CHECK (specific_per_unit >= 0),
+    effective_from TEXT NOT NULL,
+    effective_to TEXT,
+    notification_ref TEXT,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%S', 'now', 'localtime')),
+    updated_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%S', 'now', 'localtime')),
+    UNIQUE (hsn_code, country_of_origin, supplier_id, effective_from)
+);
+
+CREATE INDEX IF NOT EXISTS idx_anti_dumping_duties_lookup
+    ON anti_dumping_duties (hsn_code, effective_from, effective_to);
+
+-- Expose the new heads on report_view; AIDC, anti-dumping and safeguard duty join the landed cost, cess stays creditable.
+DROP VIEW IF EXISTS report_view;
+CREATE VIEW report_view AS
+WITH
+boe_items AS (
+    SELECT
+        bc.id AS boe_calc_id,
+        bc.shipment_id,
+        bc.boe_id,
+        bc.supplier_name,
+        bc.invoice_number,
+        json_extract(item.value, '$.partNo') AS part_no,
+        json_extract(item.value, '$.description') AS boe_description,
+        CAST(json_extract(item.value, '$.assessableValue') AS REAL) AS boe_assessable_value,
+        CAST(json_extract(item.value, '$.bcdValue') AS REAL) AS boe_bcd_amount,
+        CAST(json_extract(item.value, '$.swsValue') AS REAL) AS boe_sws_amount,
+        CAST(json_extract(item.value, '$.igstValue') AS REAL) AS boe_igst_amount,
+        COALESCE(CAST(json_extract(item.value, '$.aidcValue') AS REAL), 0.0) AS boe_aidc_amount,
+        COALESCE(CAST(json_extract(item.value, '$.addValue') AS REAL), 0.0) AS boe_add_amount,
+        COALESCE(CAST(json_extract(item.value, '$.safeguardValue') AS REAL), 0.0) AS boe_safeguard_amount,
+        COALESCE(CAST(json_extract(item.value, '$.cessValue') AS REAL), 0.0) AS boe_cess_amount,
+        CAST(json_extract(bc.form_values_json, '$.exchangeRate') AS REAL) AS boe_exchange_rate
+    FROM boe_calculations bc
+    JOIN json_each(json_extract(bc.calculation_result_json, '$.calculatedItems')) AS item
+),
+shipment_expenses AS (
+    SELECT ei.shipment_id,
+           SUM(e.amount) AS shipment_expenses_basic,
+           SUM(e.total_amount) AS shipment_expenses_total
+    FROM expense_invoices ei
+    JOIN expenses e ON e.expense_invoice_id = ei.id
+    GROUP BY ei.shipment_id
+),
+boe_assessable AS (
+    SELECT shipment_id, SUM(boe_assessable_value) AS shipment_boe_assessable_total
+    FROM boe_items
+    GROUP BY shipment_id
+)
+SELECT
+    sup.supplier_name AS supplier,
+    s.supplier_id AS supplier_id,
+    s.invoice_number AS invoice_no,
+    s.invoice_date AS invoice_date,
+    bi.shipment_id AS shipment_id,
+    bi.boe_id AS boe_id,
+    s.invoice_currency AS currency,
+    bi.boe_exchange_rate AS exchange_rate,
+    bi.part_no AS part_no,
+    COALESCE(i.item_description, bi.boe_description) AS description,
+    i.unit AS unit,
+    ili.quantity AS qty,
+    ili.unit_price AS unit_price,
+    bi.boe_assessable_value AS assessable_value,
+    bi.boe_bcd_amount AS bcd_amount,
+    bi.boe_sws_amount AS sws_amount,
+    bi.boe_igst_amount AS igst_amount,
+    bi.boe_aidc_amount AS aidc_amount,
+    bi.boe_add_amount AS add_amount,
+    bi.boe_safeguard_amount AS safeguard_amount,
+    bi.boe_cess_amount AS cess_amount,
+    -- Expense allocation proportional by BOE assessable value per shipment (basic value, excluding GST)
+    COALESCE(se.shipment_expenses_basic, 0.0) *
+      (bi.boe_assessable_value / NULLIF(ba.shipment_boe_assessable_total, 0)) AS expenses_total,
+    -- LDC per qty: (assessable + non-creditable duties + expenses_basic) / qty; IGST and cess are creditable
+    (
+      (bi.boe_assessable_value + bi.boe_bcd_amount + bi.boe_sws_amount
+       + bi.boe_aidc_amount + bi.boe_add_amount + bi.boe_safeguard_amount
+       + (COALESCE(se.shipment_expenses_basic, 0.0) * (bi.boe_assessable_value / NULLIF(ba.shipment_boe_assessable_total, 0))))
+    ) / NULLIF(ili.quantity, 0) AS ldc_per_qty
+FROM boe_items bi
+JOIN shipments s ON s.id = bi.shipment_id
+JOIN suppliers sup ON sup.id = s.supplier_id
+JOIN invoices inv ON inv.shipment_id = s.id
+JOIN items i ON i.part_number = bi.part_no
+JOIN invoice_line_items ili ON ili.invoice_id = inv.id AND ili.item_id = i.id
+LEFT JOIN shipment_expenses se ON se.shipment_id = s.id
+LEFT JOIN boe_assessable ba ON ba.shipment_id = s.id;
@@ -0,0 +1,19 @@
+-- Reconciliation tolerances per duty head (and assessable value); a difference within either bound is accepted.
+
+CREATE TABLE IF NOT EXISTS boe_reconciliation_tolerances (
+    duty_head TEXT PRIMARY KEY NOT NULL,
+    absolute_tolerance REAL NOT NULL DEFAULT 0 CHECK (absolute_tolerance >= 0),
+    percent_tolerance REAL NOT NULL DEFAULT 0 CHECK (percent_tolerance >= 0),
+    updated_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%S', 'now', 'localtime'))
+);
+
+-- Defaults absorb rupee rounding on duty and exchange-rate rounding on assessable value.
+INSERT OR IGNORE INTO boe_reconciliation_tolerances (duty_head, absolute_tolerance, percent_tolerance) VALUES
+    ('ASSESSABLE_VALUE', 1.0, 0.1),
+    ('BCD', 1.0, 0.0),
+    ('AIDC', 1.0, 0.0),
+    ('SWS', 1.0, 0.0),
+    ('ADD', 1.0, 0.0),
+    ('SAFEGUARD', 1.0, 0.0),
+    ('IGST', 1.0, 0.0),
+    ('CESS', 1.0, 0.0);
@@ -0,0 +1,36 @@
+-- Duty drawback and RoDTEP entitlements per shipment line, with utilisation against later BOEs.
+
+CREATE TABLE IF NOT EXISTS duty_credit_entitlements (
+    id TEXT PRIMARY KEY NOT NULL,
+    scheme TEXT NOT NULL CHECK (scheme IN ('DRAWBACK', 'RODTEP')),
+    shipment_id TEXT NOT NULL,
+    part_no TEXT,
+    -- e-scrip number (RoDTEP) or credit-ledger / drawback reference.
+    scrip_number TEXT,
+    entitled_amount REAL NOT NULL CHECK (entitled_amount >= 0),
+    issue_date TEXT NOT NULL,
+    expiry_date TEXT,
+    notes TEXT,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%S', 'now', 'localtime')),
+    updated_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%S', 'now', 'localtime')),
+    FOREIGN KEY (shipment_id) REFERENCES shipments(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_duty_credit_entitlements_shipment
+    ON duty_credit_entitlements (shipment_id);
+
+-- One row per entitlement, BOE and line the credit was debited against.
+CREATE TABLE IF NOT EXISTS duty_credit_utilisations (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    entitlement_id TEXT NOT NULL,
+    saved_boe_id TEXT NOT NULL,
+    part_no TEXT NOT NULL,
+    amount REAL NOT NULL CHECK (amount > 0),
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%S', 'now', 'localtime')),
+    UNIQUE (entitlement_id, saved_boe_id, part_no),
+    FOREIGN KEY (entitlement_id) REFERENCES duty_credit_entitlements(id) ON DELETE CASCADE,
+    FOREIGN KEY (saved_boe_id) REFERENCES boe_calculations(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_duty_credit_utilisations_boe
+    ON duty_credit_utilisations (saved_boe_id);
@@ -0,0 +1,30 @@
+-- GSTR-2B imports (IMPG section): IGST paid on Bills of Entry as reflected in the GST portal.
+
+CREATE TABLE IF NOT EXISTS gstr2b_imports (
+    id TEXT PRIMARY KEY NOT NULL,
+    gstin TEXT,
+    -- `MMYYYY` as in the 2B file, when present.
+    return_period TEXT,
+    file_name TEXT,
+    -- BE date range the reconciliation covers on our side.
+    period_from TEXT NOT NULL,
+    period_to TEXT NOT NULL,
+    imported_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%S', 'now', 'localtime'))
+);
+
+CREATE TABLE IF NOT EXISTS gstr2b_impg_rows (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    import_id TEXT NOT NULL,
+    be_number TEXT NOT NULL,
+    be_date TEXT NOT NULL,
+    port_code TEXT,
+    reference_date TEXT,
+    taxable_value REAL NOT NULL DEFAULT 0,
+    igst REAL NOT NULL DEFAULT 0,
+    cess REAL NOT NULL DEFAULT 0,
+    is_amended INTEGER NOT NULL DEFAULT 0,
+    FOREIGN KEY (import_id) REFERENCES gstr2b_imports(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_gstr2b_impg_rows_import ON gstr2b_impg_rows(import_id);
+CREATE INDEX IF NOT EXISTS idx_gstr2b_impg_rows_be ON gstr2b_impg_rows(be_number, be_date);
@@ -0,0 +1,8 @@
+-- Bonded warehousing (Section 49 / MOOWR): BOE type on saved calculations and the ex-bond to into-bond link.
+
+ALTER TABLE boe_calculations ADD COLUMN boe_type TEXT NOT NULL DEFAULT 'HOME_CONSUMPTION';
+
+-- Set on ex-bond BOEs: the into-bond (warehousing) BOE whose goods they clear.
+ALTER TABLE boe_calculations ADD COLUMN into_bond_boe_id TEXT REFERENCES boe_calculations(id);
+
+CREATE INDEX IF NOT EXISTS idx_boe_calculations_into_bond ON boe_calculations(into_bond_boe_id);
@@ -0,0 +1,24 @@
+-- Statutory charges on Bills of Entry: interest on late duty payment and late-filing charges, with configurable slabs.
+
+CREATE TABLE IF NOT EXISTS boe_charge_slabs (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    charge_type TEXT NOT NULL CHECK (charge_type IN ('LATE_PAYMENT_INTEREST', 'LATE_FILING')),
+    -- Days late this slab covers, inclusive; open-ended when to_day is NULL.
+    from_day INTEGER NOT NULL CHECK (from_day >= 1),
+    to_day INTEGER CHECK (to_day IS NULL OR to_day >= from_day),
+    -- Percent per annum on unpaid duty for interest; INR per day for late filing.
+    rate REAL NOT NULL CHECK (rate >= 0),
+    updated_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%S', 'now', 'localtime')),
+    UNIQUE (charge_type, from_day)
+);
+
+-- Section 47(2) interest at 15% p.a.; Section 46(3) charges of INR 5,000/day for three days, 10,000/day after.
+INSERT OR IGNORE INTO boe_charge_slabs (charge_type, from_day, to_day, rate) VALUES
+    ('LATE_PAYMENT_INTEREST', 1, NULL, 15.0),
+    ('LATE_FILING', 1, 3, 5000.0),
+    ('LATE_FILING', 4, NULL, 10000.0);
+
+-- Vessel / aircraft arrival (IGM inward date); a BE filed after the day before arrival is late.
+ALTER TABLE boe_details ADD COLUMN arrival_date TEXT;
+ALTER TABLE boe_details ADD COLUMN late_payment_interest REAL NOT NULL DEFAULT 0;
+ALTER TABLE boe_details ADD COLUMN late_filing_charge REAL NOT NULL DEFAULT 0;
@@ -0,0 +1,141 @@
+-- Landed cost allocation: per-expense-type basis, per-line allocations and persisted landed cost per unit.
+
+-- ASSESSABLE_VALUE, GROSS_WEIGHT, NET_WEIGHT, QUANTITY or EQUAL.
+ALTER TABLE expense_types ADD COLUMN allocation_basis TEXT NOT NULL DEFAULT 'ASSESSABLE_VALUE'
+    CHECK (allocation_basis IN ('ASSESSABLE_VALUE', 'GROSS_WEIGHT', 'NET_WEIGHT', 'QUANTITY', 'EQUAL'));
+
+-- Freight-like charges follow weight by default; everything else stays on assessable value.
+UPDATE expense_types SET allocation_basis = 'GROSS_WEIGHT'
+WHERE LOWER(name) LIKE '%freight%' OR LOWER(name) LIKE '%transport%';
+
+-- One row per expense line and invoice line it was spread over (basic amount, excluding GST).
+CREATE TABLE IF NOT EXISTS landed_cost_allocations (
+    expense_id TEXT NOT NULL,
+    invoice_line_item_id TEXT NOT NULL,
+    shipment_id TEXT NOT NULL,
+    -- Basis actually applied; falls back to assessable value, then equal split, when the lines lack the data.
+    basis TEXT NOT NULL,
+    amount REAL NOT NULL,
+    PRIMARY KEY (expense_id, invoice_line_item_id),
+    FOREIGN KEY (expense_id) REFERENCES expenses(id) ON DELETE CASCADE,
+    FOREIGN KEY (invoice_line_item_id) REFERENCES invoice_line_items(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_landed_cost_allocations_shipment ON landed_cost_allocations(shipment_id);
+
+-- Landed cost per invoice line: assessable value + non-creditable duty + allocated expenses.
+CREATE TABLE IF NOT EXISTS item_landed_costs (
+    invoice_line_item_id TEXT PRIMARY KEY NOT NULL,
+    shipment_id TEXT NOT NULL,
+    item_id TEXT NOT NULL,
+    part_no TEXT NOT NULL,
+    quantity REAL NOT NULL,
+    assessable_value REAL NOT NULL DEFAULT 0,
+    duty_cost REAL NOT NULL DEFAULT 0,
+    expense_cost REAL NOT NULL DEFAULT 0,
+    landed_cost REAL NOT NULL DEFAULT 0,
+    landed_cost_per_unit REAL,
+    computed_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%S', 'now', 'localtime')),
+    FOREIGN KEY (invoice_line_item_id) REFERENCES invoice_line_items(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_item_landed_costs_shipment ON item_landed_costs(shipment_id);
+CREATE INDEX IF NOT EXISTS idx_item_landed_costs_item ON item_landed_costs(item_id);
+
+-- Report rows take expenses and landed cost per unit from the persisted allocation when present.
+DROP VIEW IF EXISTS report_view;
+CREATE VIEW report_view AS
+WITH
+boe_items AS (
+    SELECT
+        bc.id AS boe_calc_id,
+        bc.shipment_id,
+        bc.boe_id,
+        bc.supplier_name,
+        bc.invoice_number,
+        json_extract(item.value, '$.partNo') AS part_no,
+        json_extract(item.value, '$.description') AS boe_description,
+        CAST(json_extract(item.value, '$.assessableValue') AS REAL) AS boe_assessable_value,
+        CAST(json_extract(item.value, '$.bcdValue') AS REAL) AS boe_bcd_amount,
+        CAST(json_extract(item.value, '$.swsValue') AS REAL) AS boe_sws_amount,
+        CAST(json_extract(item.value, '$.igstValue') AS REAL) AS boe_igst_amount,
+        COALESCE(CAST(json_extract(item.value, '$.aidcValue') AS REAL), 0.0) AS boe_aidc_amount,
+        COALESCE(CAST(json_extract(item.value, '$.addValue') AS REAL), 0.0) AS boe_add_amount,
+        COALESCE(CAST(json_extract(item.value, '$.safeguardValue') AS REAL), 0.0) AS boe_safeguard_amount,
+        COALESCE(CAST(json_extract(item.value, '$.cessValue') AS REAL), 0.0) AS boe_cess_amount,
+        CAST(json_extract(bc.form_values_json, '$.exchangeRate') AS REAL) AS boe_exchange_rate,
+        -- Position among the BOE's items for the same part; pairs the item with its invoice line.
+        ROW_NUMBER() OVER (
+            PARTITION BY bc.id, json_extract(item.value, '$.partNo') ORDER BY CAST(item.key AS INTEGER)
+        ) AS part_seq
+    FROM boe_calculations bc
+    JOIN json_each(json_extract(bc.calculation_result_json, '$.calculatedItems')) AS item
+),
+-- Invoice lines in the duty engine's order, so a part billed on several lines keeps one row per line.
+shipment_lines AS (
+    SELECT inv.shipment_id, ili.id AS invoice_line_item_id, ili.item_id, i.part_number AS part_no,
+           ili.quantity, ili.unit_price,
+           ROW_NUMBER() OVER (PARTITION BY inv.shipment_id, i.part_number ORDER BY ili.rowid) AS part_seq
+    FROM invoices inv
+    JOIN invoice_line_items ili ON ili.invoice_id = inv.id
+    JOIN items i ON i.id = ili.item_id
+),
+shipment_expenses AS (
+    SELECT ei.shipment_id,
+           SUM(e.amount) AS shipment_expenses_basic,
+           SUM(e.total_amount) AS shipment_expenses_total
+    FROM expense_invoices ei
+    JOIN expenses e ON e.expense_invoice_id = ei.id
+    GROUP BY ei.shipment_id
+),
+boe_assessable AS (
+    SELECT shipment_id, SUM(boe_assessable_value) AS shipment_boe_assessable_total
+    FROM boe_items
+    GROUP BY shipment_id
+)
+SELECT
+    sup.supplier_name AS supplier,
+    s.supplier_id AS supplier_id,
+    s.invoice_number AS invoice_no,
+    s.invoice_date AS invoice_date,
+    bi.shipment_id AS shipment_id,
+    bi.boe_id AS boe_id,
+    s.invoice_currency AS currency,
+    bi.boe_exchange_rate AS exchange_rate,
+    bi.part_no AS part_no,
+    COALESCE(i.item_description, bi.boe_description) AS description,
+    i.unit AS unit,
+    sl.quantity AS qty,
+    sl.unit_price AS unit_price,
+    bi.boe_assessable_value AS assessable_value,
+    bi.boe_bcd_amount AS bcd_amount,
+    bi.boe_sws_amount AS sws_amount,
+    bi.boe_igst_amount AS igst_amount,
+    bi.boe_aidc_amount AS aidc_amount,
+    bi.boe_add_amount AS add_amount,
+    bi.boe_safeguard_amount AS safeguard_amount,
+    bi.boe_cess_amount AS cess_amount,
+    -- Allocated expenses (basic value, excluding GST); a partial BOE carries its share of the line's allocation.
+    COALESCE(
+        ilc.expense_cost * COALESCE(bi.boe_assessable_value / NULLIF(ilc.assessable_value, 0), 1.0),
+        COALESCE(se.shipment_expenses_basic, 0.0) *
+          (bi.boe_assessable_value / NULLIF(ba.shipment_boe_assessable_total, 0))
+    ) AS expenses_total,
+    -- LDC per qty: (assessable + non-creditable duties + expenses_basic) / qty; IGST and cess are creditable
+    COALESCE(
+        ilc.landed_cost_per_unit,
+        (
+          (bi.boe_assessable_value + bi.boe_bcd_amount + bi.boe_sws_amount
+           + bi.boe_aidc_amount + bi.boe_add_amount + bi.boe_safeguard_amount
+           + (COALESCE(se.shipment_expenses_basic, 0.0) * (bi.boe_assessable_value / NULLIF(ba.shipment_boe_assessable_total, 0))))
+        ) / NULLIF(sl.quantity, 0)
+    ) AS ldc_per_qty
+FROM boe_items bi
+JOIN shipments s ON s.id = bi.shipment_id
+JOIN suppliers sup ON sup.id = s.supplier_id
+JOIN shipment_lines sl
+    ON sl.shipment_id = bi.shipment_id AND sl.part_no = bi.part_no AND sl.part_seq = bi.part_seq
+JOIN items i ON i.id = sl.item_id
+LEFT JOIN item_landed_costs ilc ON ilc.invoice_line_item_id = sl.invoice_line_item_id
+LEFT JOIN shipment_expenses se ON se.shipment_id = s.id
+LEFT JOIN boe_assessable ba ON ba.shipment_id = s.id;
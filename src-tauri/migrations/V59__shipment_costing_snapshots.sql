@@ -0,0 +1,44 @@
+-- Frozen costing snapshots: an immutable copy of a shipment's landed cost taken when it is frozen, and the reopen log.
+
+CREATE TABLE IF NOT EXISTS shipment_costing_snapshots (
+    id TEXT PRIMARY KEY NOT NULL,
+    shipment_id TEXT NOT NULL,
+    -- 1 for the first freeze, incremented on every re-freeze after a reopen.
+    version INTEGER NOT NULL,
+    -- Per-item landed cost, exchange rates, duty heads and expense allocations as serialized JSON.
+    snapshot_json TEXT NOT NULL,
+    -- SHA-256 (hex) of snapshot_json.
+    content_hash TEXT NOT NULL,
+    total_landed_cost REAL NOT NULL DEFAULT 0,
+    frozen_by TEXT,
+    frozen_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%S', 'now', 'localtime')),
+    UNIQUE (shipment_id, version)
+);
+
+CREATE INDEX IF NOT EXISTS idx_shipment_costing_snapshots_shipment ON shipment_costing_snapshots(shipment_id);
+
+-- Snapshots are write-once; reopening a shipment records a row below instead of touching them.
+CREATE TRIGGER IF NOT EXISTS trg_shipment_costing_snapshots_no_update
+BEFORE UPDATE ON shipment_costing_snapshots
+BEGIN
+    SELECT RAISE(ABORT, 'costing snapshots are immutable');
+END;
+
+CREATE TRIGGER IF NOT EXISTS trg_shipment_costing_snapshots_no_delete
+BEFORE DELETE ON shipment_costing_snapshots
+BEGIN
+    SELECT RAISE(ABORT, 'costing snapshots are immutable');
+END;
+
+CREATE TABLE IF NOT EXISTS shipment_reopenings (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    shipment_id TEXT NOT NULL,
+    -- Snapshot in force when the shipment was reopened; NULL if it was frozen before snapshots existed.
+    snapshot_id TEXT,
+    reason TEXT NOT NULL,
+    reopened_by TEXT,
+    reopened_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%S', 'now', 'localtime')),
+    FOREIGN KEY (snapshot_id) REFERENCES shipment_costing_snapshots(id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_shipment_reopenings_shipment ON shipment_reopenings(shipment_id);
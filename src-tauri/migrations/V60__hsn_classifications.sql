@@ -0,0 +1,19 @@
+-- HSN classification audit: every code chosen for an item, with the rationale and the candidates offered.
+
+CREATE TABLE IF NOT EXISTS hsn_classifications (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    item_id TEXT NOT NULL,
+    hsn_code TEXT NOT NULL,
+    previous_hsn_code TEXT,
+    rationale TEXT NOT NULL,
+    -- AI, TARIFF, HISTORY or MANUAL: where the chosen candidate came from.
+    source TEXT NOT NULL DEFAULT 'MANUAL',
+    provider TEXT,
+    -- Ranked candidates shown to the user when the choice was made (JSON array).
+    candidates_json TEXT,
+    classified_by TEXT,
+    classified_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%S', 'now', 'localtime')),
+    FOREIGN KEY (item_id) REFERENCES items(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_hsn_classifications_item ON hsn_classifications(item_id, classified_at);
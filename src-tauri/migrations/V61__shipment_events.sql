@@ -0,0 +1,40 @@
+-- Shipment milestone timeline; shipments.status is derived from the furthest milestone reached.
+
+CREATE TABLE IF NOT EXISTS shipment_events (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    shipment_id TEXT NOT NULL,
+    event_type TEXT NOT NULL CHECK (event_type IN (
+        'BOOKED', 'DEPARTED', 'ARRIVED_AT_PORT', 'IGM_FILED', 'BOE_FILED', 'OUT_OF_CHARGE', 'DELIVERED'
+    )),
+    -- When the milestone happened (YYYY-MM-DD HH:MM:SS), not when it was entered.
+    occurred_at TEXT NOT NULL,
+    -- MANUAL (user), SYSTEM (derived from BOE / delivery data) or an integration name.
+    source TEXT NOT NULL DEFAULT 'MANUAL',
+    notes TEXT,
+    recorded_by TEXT,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%S', 'now', 'localtime')),
+    updated_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%S', 'now', 'localtime')),
+    UNIQUE (shipment_id, event_type),
+    FOREIGN KEY (shipment_id) REFERENCES shipments(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_shipment_events_type ON shipment_events(event_type, occurred_at);
+
+-- Backfill what existing data already tells us.
+INSERT OR IGNORE INTO shipment_events (shipment_id, event_type, occurred_at, source)
+SELECT s.id, 'DEPARTED', s.etd || ' 00:00:00', 'SYSTEM'
+FROM shipments s
+WHERE s.etd IS NOT NULL AND s.etd GLOB '[0-9][0-9][0-9][0-9]-[0-9][0-9]-[0-9][0-9]'
+  AND date(s.etd) <= date('now', 'localtime');
+
+INSERT OR IGNORE INTO shipment_events (shipment_id, event_type, occurred_at, source)
+SELECT bc.shipment_id, 'BOE_FILED', MIN(bd.be_date) || ' 00:00:00', 'SYSTEM'
+FROM boe_calculations bc
+JOIN boe_details bd ON bd.id = bc.boe_id
+WHERE bd.be_date GLOB '[0-9][0-9][0-9][0-9]-[0-9][0-9]-[0-9][0-9]'
+GROUP BY bc.shipment_id;
+
+INSERT OR IGNORE INTO shipment_events (shipment_id, event_type, occurred_at, source)
+SELECT s.id, 'DELIVERED', s.date_of_delivery || ' 00:00:00', 'SYSTEM'
+FROM shipments s
+WHERE s.date_of_delivery GLOB '[0-9][0-9][0-9][0-9]-[0-9][0-9]-[0-9][0-9]';
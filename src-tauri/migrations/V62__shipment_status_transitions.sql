@@ -0,0 +1,17 @@
+-- Shipment status state machine: every accepted status change, who made it and why.
+
+CREATE TABLE IF NOT EXISTS shipment_status_transitions (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    shipment_id TEXT NOT NULL,
+    -- NULL when the shipment had no status (or an unrecognised one) before the change.
+    from_status TEXT,
+    to_status TEXT NOT NULL,
+    reason TEXT,
+    -- MANUAL (user action), SYSTEM (invoice / BOE / bonded warehouse / delivery check) or MILESTONE.
+    source TEXT NOT NULL DEFAULT 'MANUAL',
+    changed_by TEXT,
+    changed_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%S', 'now', 'localtime')),
+    FOREIGN KEY (shipment_id) REFERENCES shipments(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_shipment_status_transitions_shipment ON shipment_status_transitions(shipment_id);
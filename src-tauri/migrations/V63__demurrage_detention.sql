@@ -0,0 +1,96 @@
+-- Demurrage and detention: free-time terms per shipping line / service provider, tiered rates, daily accruals.
+
+-- Gate-out and empty-return milestones bound the port and line free-time clocks; SQLite cannot
+-- alter a CHECK constraint, so shipment_events is rebuilt with the wider list.
+CREATE TABLE shipment_events_new (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    shipment_id TEXT NOT NULL,
+    event_type TEXT NOT NULL CHECK (event_type IN (
+        'BOOKED', 'DEPARTED', 'ARRIVED_AT_PORT', 'IGM_FILED', 'BOE_FILED', 'OUT_OF_CHARGE',
+        'GATE_OUT', 'DELIVERED', 'EMPTY_RETURNED'
+    )),
+    -- When the milestone happened (YYYY-MM-DD HH:MM:SS), not when it was entered.
+    occurred_at TEXT NOT NULL,
+    -- MANUAL (user), SYSTEM (derived from BOE / delivery data) or an integration name.
+    source TEXT NOT NULL DEFAULT 'MANUAL',
+    notes TEXT,
+    recorded_by TEXT,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%S', 'now', 'localtime')),
+    updated_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%S', 'now', 'localtime')),
+    UNIQUE (shipment_id, event_type),
+    FOREIGN KEY (shipment_id) REFERENCES shipments(id) ON DELETE CASCADE
+);
+
+INSERT INTO shipment_events_new (id, shipment_id, event_type, occurred_at, source, notes, recorded_by, created_at, updated_at)
+SELECT id, shipment_id, event_type, occurred_at, source, notes, recorded_by, created_at, updated_at
+FROM shipment_events;
+
+DROP TABLE shipment_events;
+ALTER TABLE shipment_events_new RENAME TO shipment_events;
+
+CREATE INDEX IF NOT EXISTS idx_shipment_events_type ON shipment_events(event_type, occurred_at);
+
+CREATE TABLE IF NOT EXISTS free_time_terms (
+    id TEXT PRIMARY KEY NOT NULL,
+    name TEXT NOT NULL,
+    -- Terms belong to a shipping line (free text) and/or a service provider (CFS, forwarder).
+    shipping_line TEXT,
+    service_provider_id TEXT,
+    -- Days at the port / CFS before demurrage starts, counted from arrival (arrival day is day 1).
+    port_free_days INTEGER NOT NULL DEFAULT 0,
+    -- Days the line lets a container stay out before detention starts, counted from arrival.
+    detention_free_days INTEGER NOT NULL DEFAULT 0,
+    currency TEXT NOT NULL DEFAULT 'INR',
+    is_active INTEGER NOT NULL DEFAULT 1,
+    notes TEXT,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%S', 'now', 'localtime')),
+    updated_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%S', 'now', 'localtime')),
+    FOREIGN KEY (service_provider_id) REFERENCES service_providers(id)
+);
+
+CREATE TABLE IF NOT EXISTS free_time_rate_tiers (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    terms_id TEXT NOT NULL,
+    charge_type TEXT NOT NULL CHECK (charge_type IN ('DEMURRAGE', 'DETENTION')),
+    -- Chargeable days after free time, 1-based and inclusive; to_day NULL means open-ended.
+    from_day INTEGER NOT NULL,
+    to_day INTEGER,
+    -- Per container per day, in the terms' currency.
+    rate_per_day REAL NOT NULL,
+    FOREIGN KEY (terms_id) REFERENCES free_time_terms(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_free_time_rate_tiers_terms ON free_time_rate_tiers(terms_id, charge_type, from_day);
+
+CREATE TABLE IF NOT EXISTS shipment_free_time (
+    shipment_id TEXT PRIMARY KEY NOT NULL,
+    terms_id TEXT NOT NULL,
+    assigned_by TEXT,
+    assigned_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%S', 'now', 'localtime')),
+    FOREIGN KEY (shipment_id) REFERENCES shipments(id) ON DELETE CASCADE,
+    FOREIGN KEY (terms_id) REFERENCES free_time_terms(id)
+);
+
+-- One row per shipment, charge and day the accrual job ran; a stopped clock keeps its final row.
+CREATE TABLE IF NOT EXISTS demurrage_accruals (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    shipment_id TEXT NOT NULL,
+    charge_type TEXT NOT NULL CHECK (charge_type IN ('DEMURRAGE', 'DETENTION')),
+    accrual_date TEXT NOT NULL,
+    containers INTEGER NOT NULL,
+    days_used INTEGER NOT NULL,
+    free_days INTEGER NOT NULL,
+    chargeable_days INTEGER NOT NULL,
+    accrued_amount REAL NOT NULL,
+    -- What one more day would add, across all containers.
+    next_day_charge REAL NOT NULL,
+    currency TEXT NOT NULL,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%S', 'now', 'localtime')),
+    UNIQUE (shipment_id, charge_type, accrual_date),
+    FOREIGN KEY (shipment_id) REFERENCES shipments(id) ON DELETE CASCADE
+);
+
+INSERT OR IGNORE INTO app_metadata (key, value) VALUES ('free_time_warning_days', '2');
+
+INSERT OR IGNORE INTO exception_sla_escalation_rules (exception_type, sla_hours, escalation_level, notify_role) VALUES
+    ('FREE_TIME_EXPIRING', 0, 1, 'admin');
@@ -0,0 +1,54 @@
+-- Purchase orders upstream of shipments: lines, delivery schedule, and PO line references on invoice lines.
+
+CREATE TABLE IF NOT EXISTS purchase_orders (
+    id TEXT PRIMARY KEY NOT NULL,
+    po_number TEXT NOT NULL UNIQUE,
+    supplier_id TEXT NOT NULL,
+    po_date TEXT NOT NULL,
+    currency TEXT NOT NULL,
+    incoterm TEXT,
+    -- DRAFT, OPEN, PARTIALLY_SHIPPED, SHIPPED, CLOSED or CANCELLED; the shipped states follow invoiced quantities.
+    status TEXT NOT NULL DEFAULT 'DRAFT',
+    notes TEXT,
+    created_by TEXT,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%S', 'now', 'localtime')),
+    updated_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%S', 'now', 'localtime')),
+    FOREIGN KEY (supplier_id) REFERENCES suppliers(id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_purchase_orders_supplier ON purchase_orders(supplier_id, status);
+
+CREATE TABLE IF NOT EXISTS purchase_order_lines (
+    id TEXT PRIMARY KEY NOT NULL,
+    po_id TEXT NOT NULL,
+    line_no INTEGER NOT NULL,
+    item_id TEXT NOT NULL,
+    quantity REAL NOT NULL,
+    unit_price REAL NOT NULL,
+    currency TEXT NOT NULL,
+    UNIQUE (po_id, line_no),
+    FOREIGN KEY (po_id) REFERENCES purchase_orders(id) ON DELETE CASCADE,
+    FOREIGN KEY (item_id) REFERENCES items(id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_purchase_order_lines_item ON purchase_order_lines(item_id);
+
+CREATE TABLE IF NOT EXISTS purchase_order_schedules (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    po_line_id TEXT NOT NULL,
+    due_date TEXT NOT NULL,
+    quantity REAL NOT NULL,
+    FOREIGN KEY (po_line_id) REFERENCES purchase_order_lines(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_purchase_order_schedules_line ON purchase_order_schedules(po_line_id, due_date);
+
+ALTER TABLE invoice_line_items ADD COLUMN po_line_id TEXT REFERENCES purchase_order_lines(id);
+
+CREATE INDEX IF NOT EXISTS idx_invoice_line_items_po_line ON invoice_line_items(po_line_id);
+
+INSERT OR IGNORE INTO app_metadata (key, value) VALUES ('po_price_tolerance_percent', '2');
+INSERT OR IGNORE INTO app_metadata (key, value) VALUES ('po_quantity_tolerance_percent', '0');
+
+INSERT OR IGNORE INTO exception_sla_escalation_rules (exception_type, sla_hours, escalation_level, notify_role) VALUES
+    ('PO_VARIANCE', 0, 1, 'admin');
@@ -0,0 +1,62 @@
+-- Goods receipt notes: what actually arrived per invoice line, with damage, photos and received-quantity landed cost.
+
+CREATE TABLE IF NOT EXISTS goods_receipts (
+    id TEXT PRIMARY KEY NOT NULL,
+    grn_number TEXT NOT NULL UNIQUE,
+    shipment_id TEXT NOT NULL,
+    received_date TEXT NOT NULL,
+    warehouse TEXT,
+    -- DRAFT while being counted, POSTED once it counts towards variances, CANCELLED when withdrawn.
+    status TEXT NOT NULL DEFAULT 'DRAFT' CHECK (status IN ('DRAFT', 'POSTED', 'CANCELLED')),
+    notes TEXT,
+    received_by TEXT,
+    posted_at TEXT,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%S', 'now', 'localtime')),
+    updated_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%S', 'now', 'localtime')),
+    FOREIGN KEY (shipment_id) REFERENCES shipments(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_goods_receipts_shipment ON goods_receipts(shipment_id, status);
+
+CREATE TABLE IF NOT EXISTS goods_receipt_lines (
+    id TEXT PRIMARY KEY NOT NULL,
+    grn_id TEXT NOT NULL,
+    invoice_line_item_id TEXT NOT NULL,
+    item_id TEXT NOT NULL,
+    -- Invoiced quantity not yet received on earlier posted GRNs, fixed when this GRN is posted.
+    expected_quantity REAL NOT NULL,
+    -- Everything counted in, damaged units included.
+    received_quantity REAL NOT NULL DEFAULT 0,
+    damaged_quantity REAL NOT NULL DEFAULT 0,
+    short_quantity REAL NOT NULL DEFAULT 0,
+    excess_quantity REAL NOT NULL DEFAULT 0,
+    remarks TEXT,
+    UNIQUE (grn_id, invoice_line_item_id),
+    FOREIGN KEY (grn_id) REFERENCES goods_receipts(id) ON DELETE CASCADE,
+    FOREIGN KEY (invoice_line_item_id) REFERENCES invoice_line_items(id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_goods_receipt_lines_invoice_line ON goods_receipt_lines(invoice_line_item_id);
+
+-- Files live under attachments/grn/<GRN id>/ in app data; a photo may belong to one line or the whole GRN.
+CREATE TABLE IF NOT EXISTS goods_receipt_photos (
+    id TEXT PRIMARY KEY NOT NULL,
+    grn_id TEXT NOT NULL,
+    grn_line_id TEXT,
+    file_name TEXT NOT NULL,
+    file_path TEXT NOT NULL,
+    caption TEXT,
+    uploaded_by TEXT,
+    uploaded_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%S', 'now', 'localtime')),
+    FOREIGN KEY (grn_id) REFERENCES goods_receipts(id) ON DELETE CASCADE,
+    FOREIGN KEY (grn_line_id) REFERENCES goods_receipt_lines(id) ON DELETE SET NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_goods_receipt_photos_grn ON goods_receipt_photos(grn_id);
+
+-- Good units received (received less damaged) on posted GRNs, and landed cost spread over them.
+ALTER TABLE item_landed_costs ADD COLUMN received_quantity REAL;
+ALTER TABLE item_landed_costs ADD COLUMN landed_cost_per_received_unit REAL;
+
+INSERT OR IGNORE INTO exception_sla_escalation_rules (exception_type, sla_hours, escalation_level, notify_role) VALUES
+    ('GRN_VARIANCE', 0, 1, 'admin');
@@ -0,0 +1,28 @@
+-- Supplier payments: advance and against-document remittances per import invoice, with remittance rate and bank charges.
+
+CREATE TABLE IF NOT EXISTS supplier_payments (
+    id TEXT PRIMARY KEY NOT NULL,
+    supplier_id TEXT NOT NULL,
+    -- The import invoice (shipment) being paid; NULL for an advance not yet set against an invoice.
+    shipment_id TEXT,
+    payment_type TEXT NOT NULL CHECK (payment_type IN ('ADVANCE', 'AGAINST_DOCUMENTS')),
+    payment_date TEXT NOT NULL,
+    -- Remitted amount in `currency`, which must match the invoice currency once linked.
+    currency TEXT NOT NULL,
+    amount REAL NOT NULL,
+    -- INR per unit of currency the bank applied to the remittance.
+    exchange_rate REAL NOT NULL,
+    -- INR bank and remittance charges, outside the forex gain/loss.
+    bank_charges REAL NOT NULL DEFAULT 0,
+    bank_reference TEXT,
+    bank_name TEXT,
+    notes TEXT,
+    created_by TEXT,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%S', 'now', 'localtime')),
+    updated_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%S', 'now', 'localtime')),
+    FOREIGN KEY (supplier_id) REFERENCES suppliers(id),
+    FOREIGN KEY (shipment_id) REFERENCES shipments(id) ON DELETE SET NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_supplier_payments_supplier ON supplier_payments(supplier_id, payment_date);
+CREATE INDEX IF NOT EXISTS idx_supplier_payments_shipment ON supplier_payments(shipment_id);
@@ -0,0 +1,53 @@
+-- Hedge book: forward contracts covering import payables, their utilisation against supplier payments, and market rates for MTM.
+
+CREATE TABLE IF NOT EXISTS forward_contracts (
+    id TEXT PRIMARY KEY NOT NULL,
+    contract_number TEXT NOT NULL UNIQUE,
+    bank_name TEXT NOT NULL,
+    currency TEXT NOT NULL,
+    -- Foreign currency bought forward.
+    amount REAL NOT NULL,
+    -- INR per unit of currency.
+    forward_rate REAL NOT NULL,
+    booking_date TEXT NOT NULL,
+    -- Option window in which the contract can be utilised (both dates inclusive).
+    maturity_from TEXT NOT NULL,
+    maturity_to TEXT NOT NULL,
+    -- ACTIVE or CANCELLED; utilisation and expiry are derived from allocations and the window.
+    status TEXT NOT NULL DEFAULT 'ACTIVE' CHECK (status IN ('ACTIVE', 'CANCELLED')),
+    notes TEXT,
+    created_by TEXT,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%S', 'now', 'localtime')),
+    updated_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%S', 'now', 'localtime'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_forward_contracts_currency ON forward_contracts(currency, maturity_to);
+
+CREATE TABLE IF NOT EXISTS forward_contract_allocations (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    contract_id TEXT NOT NULL,
+    payment_id TEXT NOT NULL,
+    amount REAL NOT NULL,
+    allocated_by TEXT,
+    allocated_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%S', 'now', 'localtime')),
+    UNIQUE (contract_id, payment_id),
+    FOREIGN KEY (contract_id) REFERENCES forward_contracts(id) ON DELETE CASCADE,
+    FOREIGN KEY (payment_id) REFERENCES supplier_payments(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_forward_contract_allocations_payment ON forward_contract_allocations(payment_id);
+
+-- Locally maintained INR rates for mark-to-market: maturity_month 'SPOT' for the spot rate, else the
+-- forward rate for delivery in that month (YYYY-MM).
+CREATE TABLE IF NOT EXISTS forex_market_rates (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    currency TEXT NOT NULL,
+    rate_date TEXT NOT NULL,
+    maturity_month TEXT NOT NULL DEFAULT 'SPOT',
+    rate REAL NOT NULL CHECK (rate > 0),
+    source TEXT,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%S', 'now', 'localtime')),
+    UNIQUE (currency, rate_date, maturity_month)
+);
+
+CREATE INDEX IF NOT EXISTS idx_forex_market_rates_lookup ON forex_market_rates(currency, maturity_month, rate_date);
@@ -0,0 +1,81 @@
+-- Shipment document checklists: document types, templates by mode / incoterm / goods category, and per-shipment document slots.
+
+CREATE TABLE IF NOT EXISTS document_types (
+    code TEXT PRIMARY KEY NOT NULL,
+    label TEXT NOT NULL,
+    sort_order INTEGER NOT NULL DEFAULT 100,
+    is_active INTEGER NOT NULL DEFAULT 1
+);
+
+INSERT OR IGNORE INTO document_types (code, label, sort_order) VALUES
+    ('COMMERCIAL_INVOICE', 'Commercial invoice', 10),
+    ('PACKING_LIST', 'Packing list', 20),
+    ('BILL_OF_LADING', 'Bill of lading', 30),
+    ('AIRWAY_BILL', 'Airway bill', 31),
+    ('CERTIFICATE_OF_ORIGIN', 'Certificate of origin', 40),
+    ('INSURANCE_CERTIFICATE', 'Insurance certificate', 50),
+    ('TEST_CERTIFICATE', 'Test certificate', 60);
+
+-- A template applies to every shipment matching all of its non-NULL filters (case-insensitive);
+-- a shipment's checklist is the union of all matching templates.
+CREATE TABLE IF NOT EXISTS document_checklist_templates (
+    id TEXT PRIMARY KEY NOT NULL,
+    name TEXT NOT NULL,
+    shipment_mode TEXT,
+    incoterm TEXT,
+    goods_category TEXT,
+    is_active INTEGER NOT NULL DEFAULT 1,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%S', 'now', 'localtime')),
+    updated_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%S', 'now', 'localtime'))
+);
+
+CREATE TABLE IF NOT EXISTS document_checklist_template_items (
+    template_id TEXT NOT NULL,
+    document_type TEXT NOT NULL,
+    is_mandatory INTEGER NOT NULL DEFAULT 1,
+    sort_order INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (template_id, document_type),
+    FOREIGN KEY (template_id) REFERENCES document_checklist_templates(id) ON DELETE CASCADE,
+    FOREIGN KEY (document_type) REFERENCES document_types(code)
+);
+
+INSERT OR IGNORE INTO document_checklist_templates (id, name, shipment_mode, incoterm, goods_category) VALUES
+    ('DCT-STANDARD', 'All imports', NULL, NULL, NULL),
+    ('DCT-SEA', 'Sea freight', 'sea', NULL, NULL),
+    ('DCT-AIR', 'Air freight', 'air', NULL, NULL),
+    ('DCT-CIF', 'Seller-insured (CIF)', NULL, 'CIF', NULL),
+    ('DCT-CIP', 'Seller-insured (CIP)', NULL, 'CIP', NULL);
+
+INSERT OR IGNORE INTO document_checklist_template_items (template_id, document_type, is_mandatory, sort_order) VALUES
+    ('DCT-STANDARD', 'COMMERCIAL_INVOICE', 1, 10),
+    ('DCT-STANDARD', 'PACKING_LIST', 1, 20),
+    ('DCT-STANDARD', 'CERTIFICATE_OF_ORIGIN', 0, 40),
+    ('DCT-SEA', 'BILL_OF_LADING', 1, 30),
+    ('DCT-AIR', 'AIRWAY_BILL', 1, 30),
+    ('DCT-CIF', 'INSURANCE_CERTIFICATE', 1, 50),
+    ('DCT-CIP', 'INSURANCE_CERTIFICATE', 1, 50);
+
+CREATE TABLE IF NOT EXISTS shipment_documents (
+    id TEXT PRIMARY KEY NOT NULL,
+    shipment_id TEXT NOT NULL,
+    document_type TEXT NOT NULL,
+    is_mandatory INTEGER NOT NULL DEFAULT 1,
+    -- TEMPLATE slots follow the matching templates; MANUAL slots were added by hand and are never pruned.
+    source TEXT NOT NULL DEFAULT 'TEMPLATE' CHECK (source IN ('TEMPLATE', 'MANUAL')),
+    status TEXT NOT NULL DEFAULT 'PENDING' CHECK (status IN ('PENDING', 'ATTACHED', 'NOT_APPLICABLE', 'MISSING')),
+    file_name TEXT,
+    file_path TEXT,
+    reference_number TEXT,
+    remarks TEXT,
+    updated_by TEXT,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%S', 'now', 'localtime')),
+    updated_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%S', 'now', 'localtime')),
+    UNIQUE (shipment_id, document_type),
+    FOREIGN KEY (shipment_id) REFERENCES shipments(id) ON DELETE CASCADE,
+    FOREIGN KEY (document_type) REFERENCES document_types(code)
+);
+
+CREATE INDEX IF NOT EXISTS idx_shipment_documents_shipment ON shipment_documents(shipment_id);
+
+INSERT OR IGNORE INTO exception_sla_escalation_rules (exception_type, sla_hours, escalation_level, notify_role) VALUES
+    ('MISSING_DOCUMENTS', 0, 1, 'admin');
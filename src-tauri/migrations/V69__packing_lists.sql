@@ -0,0 +1,48 @@
+-- Imported packing lists: per-part quantities, packages, weights and dimensions matched to invoice lines.
+
+CREATE TABLE IF NOT EXISTS packing_lists (
+    id TEXT PRIMARY KEY NOT NULL,
+    shipment_id TEXT NOT NULL,
+    file_name TEXT NOT NULL,
+    -- EXCEL, TEXT or OCR (PDF / image).
+    source_format TEXT NOT NULL,
+    total_packages REAL,
+    total_net_weight_kg REAL,
+    total_gross_weight_kg REAL,
+    -- shipments.gross_weight_kg before this list was applied.
+    previous_gross_weight_kg REAL,
+    -- Item master net / gross unit weights that were blank and filled from this list.
+    item_weights_filled INTEGER NOT NULL DEFAULT 0,
+    -- JSON array of parser and matching warnings.
+    warnings_json TEXT NOT NULL DEFAULT '[]',
+    created_by TEXT,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%S', 'now', 'localtime')),
+    FOREIGN KEY (shipment_id) REFERENCES shipments(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_packing_lists_shipment ON packing_lists(shipment_id, created_at);
+
+CREATE TABLE IF NOT EXISTS packing_list_lines (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    packing_list_id TEXT NOT NULL,
+    part_number TEXT NOT NULL,
+    description TEXT,
+    -- First invoice line of the shipment with this part number; NULL when unmatched.
+    invoice_line_item_id TEXT,
+    item_id TEXT,
+    invoiced_quantity REAL,
+    quantity REAL,
+    packages REAL,
+    net_weight_kg REAL,
+    gross_weight_kg REAL,
+    dimensions TEXT,
+    -- Item master weight per unit × quantity.
+    expected_net_weight_kg REAL,
+    expected_gross_weight_kg REAL,
+    -- Comma-separated: UNMATCHED, QUANTITY_MISMATCH, NET_WEIGHT_VARIANCE, GROSS_WEIGHT_VARIANCE,
+    -- GROSS_BELOW_NET, NO_MASTER_WEIGHT.
+    flags TEXT NOT NULL DEFAULT '',
+    FOREIGN KEY (packing_list_id) REFERENCES packing_lists(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_packing_list_lines_list ON packing_list_lines(packing_list_id);
@@ -0,0 +1,37 @@
+-- ETD / ETA change history and predicted ETAs from historical slippage.
+
+CREATE TABLE IF NOT EXISTS shipment_schedule_changes (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    shipment_id TEXT NOT NULL,
+    field TEXT NOT NULL CHECK (field IN ('ETD', 'ETA')),
+    old_value TEXT,
+    new_value TEXT,
+    -- new − old in days when both are dates; positive is a delay.
+    slip_days INTEGER,
+    -- MANUAL (shipment edit), or whoever sent the update (e.g. CARRIER, FORWARDER).
+    source TEXT NOT NULL DEFAULT 'MANUAL',
+    reason TEXT,
+    changed_by TEXT,
+    changed_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%S', 'now', 'localtime')),
+    FOREIGN KEY (shipment_id) REFERENCES shipments(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_shipment_schedule_changes_shipment ON shipment_schedule_changes(shipment_id, field, id);
+
+-- Refreshed on schedule changes and by daily maintenance for shipments that have not arrived.
+CREATE TABLE IF NOT EXISTS shipment_eta_predictions (
+    shipment_id TEXT PRIMARY KEY NOT NULL,
+    original_eta TEXT,
+    current_eta TEXT NOT NULL,
+    predicted_eta TEXT NOT NULL,
+    expected_slip_days REAL,
+    -- Grouping whose history was used: SHIPPING_LINE, SUPPLIER, PORT, MODE, ALL or NONE.
+    basis TEXT NOT NULL,
+    basis_key TEXT,
+    sample_size INTEGER NOT NULL DEFAULT 0,
+    computed_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%S', 'now', 'localtime')),
+    FOREIGN KEY (shipment_id) REFERENCES shipments(id) ON DELETE CASCADE
+);
+
+-- ETA (default) or PREDICTED: which date the overdue-ETA exception compares with today.
+INSERT OR IGNORE INTO app_metadata (key, value) VALUES ('overdue_eta_basis', 'ETA');
@@ -735,6 +735,7 @@ pub fn save_ai_extracted_invoice_in_conn(
                     duty_percent: None,
                     sws_percent: None,
                     igst_percent: None,
+                    po_line_id: None,
                 });
             }
             Ok(None) => {
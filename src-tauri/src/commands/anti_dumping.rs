@@ -0,0 +1,311 @@
+//! Anti-dumping duty notifications per HSN, origin country and exporter; CSV import and line lookup.
+
+use crate::commands::dashboard_cache;
+use crate::commands::tariff::{normalize_date, normalize_hsn, today};
+use crate::commands::trade_agreements::country_key;
+use crate::db::DbState;
+use crate::utils::csv;
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct AntiDumpingDuty {
+    pub id: i64,
+    pub hsn_code: String,
+    pub country_of_origin: String,
+    /// Empty for the residual rate that applies to any other exporter from the country.
+    pub supplier_id: String,
+    /// Ad valorem rate on assessable value.
+    pub rate_percent: f64,
+    /// Specific duty per invoice unit, in invoice currency (converted at the BOE exchange rate).
+    pub specific_per_unit: f64,
+    pub effective_from: String,
+    pub effective_to: Option<String>,
+    pub notification_ref: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct AntiDumpingImportSummary {
+    pub inserted: u32,
+    pub updated: u32,
+    pub errors: Vec<String>,
+}
+
+const SELECT_COLUMNS: &str = "id, hsn_code, country_of_origin, supplier_id, rate_percent, specific_per_unit, effective_from, effective_to, notification_ref";
+
+fn map_row(row: &rusqlite::Row) -> rusqlite::Result<AntiDumpingDuty> {
+    Ok(AntiDumpingDuty {
+        id: row.get(0)?,
+        hsn_code: row.get(1)?,
+        country_of_origin: row.get(2)?,
+        supplier_id: row.get(3)?,
+        rate_percent: row.get(4)?,
+        specific_per_unit: row.get(5)?,
+        effective_from: row.get(6)?,
+        effective_to: row.get(7)?,
+        notification_ref: row.get(8)?,
+    })
+}
+
+/// Duty in force on `as_of` for goods of `hsn_code` from `country`. An exporter-specific rate for
+/// `supplier_id` wins over the country's residual rate.
+pub fn duty_for(
+    conn: &Connection,
+    hsn_code: &str,
+    country: Option<&str>,
+    supplier_id: Option<&str>,
+    as_of: &str,
+) -> rusqlite::Result<Option<AntiDumpingDuty>> {
+    let (Some(hsn), Some(country)) = (normalize_hsn(hsn_code), country) else {
+        return Ok(None);
+    };
+    let key = country_key(country);
+    if key.is_empty() {
+        return Ok(None);
+    }
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {SELECT_COLUMNS} FROM anti_dumping_duties
+         WHERE hsn_code = ?1
+           AND date(effective_from) <= date(?2)
+           AND (effective_to IS NULL OR date(effective_to) >= date(?2))
+         ORDER BY date(effective_from) DESC"
+    ))?;
+    let candidates = stmt
+        .query_map(params![hsn, as_of], map_row)?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    let (specific, residual): (Vec<_>, Vec<_>) = candidates
+        .into_iter()
+        .filter(|d| country_key(&d.country_of_origin) == key)
+        .partition(|d| !d.supplier_id.is_empty());
+    Ok(specific
+        .into_iter()
+        .find(|d| Some(d.supplier_id.as_str()) == supplier_id)
+        .or_else(|| residual.into_iter().next()))
+}
+
+/// Upserts notifications keyed by (HSN, country, exporter, effective-from); an open-ended earlier
+/// notification for the same key is closed the day before the new one takes effect.
+pub fn import_duties_csv(
+    conn: &mut Connection,
+    csv_text: &str,
+) -> Result<AntiDumpingImportSummary, String> {
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let mut summary = AntiDumpingImportSummary::default();
+
+    csv::for_each_row(csv_text, |row| {
+        let line = row.line;
+        let Some(hsn) = row
+            .get(&["hsn_code", "hsn", "tariff_item"])
+            .and_then(normalize_hsn)
+        else {
+            summary
+                .errors
+                .push(format!("line {line}: missing or invalid 8-digit HSN"));
+            return Ok(());
+        };
+        let Some(country) = row.get(&["country_of_origin", "country", "origin"]) else {
+            summary
+                .errors
+                .push(format!("line {line}: missing country of origin"));
+            return Ok(());
+        };
+        let supplier = row
+            .get(&["supplier_id", "exporter_id", "supplier"])
+            .unwrap_or("");
+        let amounts = (|| -> Result<(f64, f64), String> {
+            Ok((
+                row.get_f64(&["rate_percent", "rate", "add_rate", "ad_valorem"])?
+                    .unwrap_or(0.0),
+                row.get_f64(&["specific_per_unit", "specific", "per_unit"])?
+                    .unwrap_or(0.0),
+            ))
+        })();
+        let (rate, specific) = match amounts {
+            Ok((r, s)) if r >= 0.0 && s >= 0.0 && (r > 0.0 || s > 0.0) => (r, s),
+            Ok(_) => {
+                summary.errors.push(format!(
+                    "line {line}: needs a positive rate or specific duty"
+                ));
+                return Ok(());
+            }
+            Err(e) => {
+                summary.errors.push(e);
+                return Ok(());
+            }
+        };
+        let Some(from) = row
+            .get(&["effective_from", "from", "valid_from"])
+            .and_then(normalize_date)
+        else {
+            summary
+                .errors
+                .push(format!("line {line}: missing or invalid effective_from"));
+            return Ok(());
+        };
+        let to = match row.get(&["effective_to", "to", "valid_to"]) {
+            None => None,
+            Some(raw) => match normalize_date(raw) {
+                Some(d) if d >= from => Some(d),
+                _ => {
+                    summary
+                        .errors
+                        .push(format!("line {line}: invalid effective_to '{raw}'"));
+                    return Ok(());
+                }
+            },
+        };
+        let notification = row.get(&["notification_ref", "notification", "notification_no"]);
+
+        let existed: bool = tx
+            .query_row(
+                "SELECT COUNT(*) FROM anti_dumping_duties
+                 WHERE hsn_code = ?1 AND country_of_origin = ?2 AND supplier_id = ?3 AND effective_from = ?4",
+                params![hsn, country, supplier, from],
+                |r| r.get::<_, i64>(0),
+            )
+            .map_err(|e| e.to_string())?
+            > 0;
+        tx.execute(
+            "UPDATE anti_dumping_duties
+             SET effective_to = date(?4, '-1 day'),
+                 updated_at = strftime('%Y-%m-%d %H:%M:%S', 'now', 'localtime')
+             WHERE hsn_code = ?1 AND country_of_origin = ?2 AND supplier_id = ?3
+               AND effective_to IS NULL AND date(effective_from) < date(?4)",
+            params![hsn, country, supplier, from],
+        )
+        .map_err(|e| e.to_string())?;
+        tx.execute(
+            "INSERT INTO anti_dumping_duties
+                (hsn_code, country_of_origin, supplier_id, rate_percent, specific_per_unit, effective_from, effective_to, notification_ref)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+             ON CONFLICT(hsn_code, country_of_origin, supplier_id, effective_from) DO UPDATE SET
+                rate_percent = excluded.rate_percent,
+                specific_per_unit = excluded.specific_per_unit,
+                effective_to = excluded.effective_to,
+                notification_ref = excluded.notification_ref,
+                updated_at = strftime('%Y-%m-%d %H:%M:%S', 'now', 'localtime')",
+            params![hsn, country, supplier, rate, specific, from, to, notification],
+        )
+        .map_err(|e| e.to_string())?;
+        if existed {
+            summary.updated += 1;
+        } else {
+            summary.inserted += 1;
+        }
+        Ok(())
+    })?;
+
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(summary)
+}
+
+#[tauri::command]
+pub fn import_anti_dumping_csv(
+    csv_content: String,
+    state: State<DbState>,
+) -> Result<AntiDumpingImportSummary, String> {
+    let mut conn = state.db.lock().map_err(|e| e.to_string())?;
+    let summary = import_duties_csv(&mut conn, &csv_content)?;
+    let _ = dashboard_cache::invalidate_dashboard_metrics_cache(&conn);
+    Ok(summary)
+}
+
+#[tauri::command]
+pub fn list_anti_dumping_duties(
+    hsn_code: Option<String>,
+    state: State<DbState>,
+) -> Result<Vec<AntiDumpingDuty>, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    let hsn = hsn_code.as_deref().and_then(normalize_hsn);
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {SELECT_COLUMNS} FROM anti_dumping_duties
+             WHERE (?1 IS NULL OR hsn_code = ?1)
+             ORDER BY hsn_code, country_of_origin, supplier_id, date(effective_from) DESC
+             LIMIT 5000"
+        ))
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![hsn], map_row)
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())
+}
+
+/// Duty applicable to an HSN for a given origin and supplier on `as_of` (defaults to today).
+#[tauri::command]
+pub fn get_anti_dumping_duty(
+    hsn_code: String,
+    country_of_origin: String,
+    supplier_id: Option<String>,
+    as_of: Option<String>,
+    state: State<DbState>,
+) -> Result<Option<AntiDumpingDuty>, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    let as_of = as_of.unwrap_or_else(today);
+    duty_for(
+        &conn,
+        &hsn_code,
+        Some(&country_of_origin),
+        supplier_id.as_deref(),
+        &as_of,
+    )
+    .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exporter_rate_beats_residual_rate() {
+        let mut c = Connection::open_in_memory().unwrap();
+        c.execute_batch("CREATE TABLE hsn_tariff_rates (id INTEGER PRIMARY KEY);")
+            .unwrap();
+        c.execute_batch(include_str!(
+            "../../migrations/V52__additional_duty_heads.sql"
+        ))
+        .unwrap();
+        let s = import_duties_csv(
+            &mut c,
+            "HSN,Country,Supplier ID,Rate Percent,Specific Per Unit,Effective From\n\
+             84821011,China,,35,,2024-01-01\n\
+             84821011,China,Sup-009,12.5,,2024-01-01\n\
+             84821011,Korea,,,0.75,2024-01-01\n\
+             84821011,Japan,,0,0,2024-01-01\n",
+        )
+        .unwrap();
+        assert_eq!((s.inserted, s.errors.len()), (3, 1));
+
+        let d = |country: &str, sup: Option<&str>| {
+            duty_for(&c, "8482.10.11", Some(country), sup, "2024-06-01").unwrap()
+        };
+        assert_eq!(d("CHINA", Some("Sup-009")).unwrap().rate_percent, 12.5);
+        assert_eq!(d("China", Some("Sup-001")).unwrap().rate_percent, 35.0);
+        assert_eq!(d("Korea", None).unwrap().specific_per_unit, 0.75);
+        assert!(d("Japan", None).is_none());
+
+        // A revised residual rate closes the open one; a bad end date is a row error.
+        let s = import_duties_csv(
+            &mut c,
+            "HSN,Country,Supplier ID,Rate Percent,Effective From,Effective To\n\
+             84821011,China,,40,2024-07-01,\n\
+             84821011,Korea,,20,2024-07-01,30/02/2024\n\
+             84821011,Korea,,20,2024-07-01,2024-06-30\n",
+        )
+        .unwrap();
+        assert_eq!((s.inserted, s.errors.len()), (1, 2));
+        assert_eq!(d("China", None).unwrap().rate_percent, 35.0);
+        let closed = duty_for(&c, "84821011", Some("China"), None, "2024-06-30")
+            .unwrap()
+            .unwrap();
+        assert_eq!(closed.effective_to.as_deref(), Some("2024-06-30"));
+        let revised = duty_for(&c, "84821011", Some("China"), None, "2024-07-01")
+            .unwrap()
+            .unwrap();
+        assert_eq!(revised.rate_percent, 40.0);
+    }
+}
@@ -1,10 +1,22 @@
+use crate::commands::boe_charges;
+use crate::commands::boe_reconciliation;
+use crate::commands::bonded_warehouse;
 use crate::commands::dashboard_cache;
-use crate::commands::utils::{generate_id, BoeShipmentMap};
+use crate::commands::duty_credits;
+use crate::commands::exchange_rates;
+use crate::commands::landed_cost;
+use crate::commands::shipment_events;
+use crate::commands::shipment_status::{self, TransitionContext};
+use crate::commands::tariff;
+use crate::commands::trade_agreements;
+use crate::commands::utils::generate_id;
 use crate::db::{
-    Attachment, BoeDetails, BoeReconciliationReport, BoeShipment, BoeShipmentItem, DbState,
-    NewBoePayload, ReconciledItemRow, ReconciliationTotals, SavedBoe,
+    Attachment, BoeDetails, BoeReconciliationReport, BoeShipment, BoeShipmentItem,
+    CalculationResult, DbState, NewBoePayload, ReconciledItemRow, ReconciliationTotals, SavedBoe,
 };
-use rusqlite::{params, Error as RusqliteError};
+use crate::duty_engine;
+use crate::valuation;
+use rusqlite::{params, Error as RusqliteError, OptionalExtension};
 use std::collections::HashMap;
 use tauri::Manager;
 use tauri::State;
@@ -29,6 +41,9 @@ pub fn get_boes(state: State<DbState>) -> Result<Vec<BoeDetails>, String> {
                 challan_number: row.get(8)?,
                 ref_id: row.get(9)?,
                 transaction_id: row.get(10)?,
+                arrival_date: row.get("arrival_date")?,
+                late_payment_interest: row.get("late_payment_interest")?,
+                late_filing_charge: row.get("late_filing_charge")?,
             })
         })
         .map_err(|e| e.to_string())?;
@@ -42,14 +57,15 @@ pub fn add_boe(payload: NewBoePayload, state: State<DbState>) -> Result<String,
     let conn = state.db.lock().unwrap();
     let new_id = generate_id(Some("BOE".to_string()));
     conn.execute(
-        "INSERT INTO boe_details (id, be_number, be_date, location, total_assessment_value, duty_amount, payment_date, duty_paid, challan_number, ref_id, transaction_id) 
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+        "INSERT INTO boe_details (id, be_number, be_date, location, total_assessment_value, duty_amount, payment_date, duty_paid, challan_number, ref_id, transaction_id, arrival_date) 
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
         params![
             new_id, payload.be_number, payload.be_date, payload.location, payload.total_assessment_value,
             payload.duty_amount, payload.payment_date, payload.duty_paid, payload.challan_number,
-            payload.ref_id, payload.transaction_id
+            payload.ref_id, payload.transaction_id, payload.arrival_date
         ],
     ).map_err(|e| e.to_string())?;
+    boe_charges::refresh_boe_details(&conn, &new_id)?;
     let _ = dashboard_cache::invalidate_dashboard_metrics_cache(&conn);
     Ok(new_id)
 }
@@ -58,14 +74,26 @@ pub fn add_boe(payload: NewBoePayload, state: State<DbState>) -> Result<String,
 pub fn update_boe(boe: BoeDetails, state: State<DbState>) -> Result<(), String> {
     let conn = state.db.lock().unwrap();
     conn.execute(
-        "UPDATE boe_details SET be_number = ?2, be_date = ?3, location = ?4, total_assessment_value = ?5, duty_amount = ?6, payment_date = ?7, duty_paid = ?8, challan_number = ?9, ref_id = ?10, transaction_id = ?11
+        "UPDATE boe_details SET be_number = ?2, be_date = ?3, location = ?4, total_assessment_value = ?5, duty_amount = ?6, payment_date = ?7, duty_paid = ?8, challan_number = ?9, ref_id = ?10, transaction_id = ?11, arrival_date = ?12
          WHERE id = ?1",
         params![
             boe.id, boe.be_number, boe.be_date, boe.location, boe.total_assessment_value,
             boe.duty_amount, boe.payment_date, boe.duty_paid, boe.challan_number,
-            boe.ref_id, boe.transaction_id
+            boe.ref_id, boe.transaction_id, boe.arrival_date
         ],
     ).map_err(|e| e.to_string())?;
+    boe_charges::refresh_boe_details(&conn, &boe.id)?;
+    let mut stmt = conn
+        .prepare("SELECT DISTINCT shipment_id FROM boe_calculations WHERE boe_id = ?1")
+        .map_err(|e| e.to_string())?;
+    let shipment_ids = stmt
+        .query_map(params![boe.id], |r| r.get::<_, String>(0))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    for shipment_id in &shipment_ids {
+        shipment_events::record_boe_milestones(&conn, shipment_id);
+    }
     let _ = dashboard_cache::invalidate_dashboard_metrics_cache(&conn);
     Ok(())
 }
@@ -83,7 +111,7 @@ pub fn delete_boe(id: String, state: State<DbState>) -> Result<(), String> {
 // --- BOE CALCULATION COMMANDS ---
 // ============================================================================
 
-fn map_row_to_saved_boe(row: &rusqlite::Row) -> Result<SavedBoe, RusqliteError> {
+pub(crate) fn map_row_to_saved_boe(row: &rusqlite::Row) -> Result<SavedBoe, RusqliteError> {
     // Read the plain fields from the database row
     let id: String = row.get("id")?;
     let shipment_id: String = row.get("shipment_id")?;
@@ -97,6 +125,8 @@ fn map_row_to_saved_boe(row: &rusqlite::Row) -> Result<SavedBoe, RusqliteError>
     let item_inputs_json: String = row.get("item_inputs_json")?;
     let calculation_result_json: String = row.get("calculation_result_json")?;
     let attachments_json: Option<String> = row.get("attachments_json").ok();
+    let boe_type: Option<String> = row.get("boe_type").ok();
+    let into_bond_boe_id: Option<String> = row.get("into_bond_boe_id").ok().flatten();
 
     // Deserialize the JSON strings back into their respective Rust structs
     let form_values = serde_json::from_str(&form_values_json).map_err(|e| {
@@ -121,6 +151,9 @@ fn map_row_to_saved_boe(row: &rusqlite::Row) -> Result<SavedBoe, RusqliteError>
         item_inputs,
         calculation_result,
         attachments: attachments_json.and_then(|s| serde_json::from_str(&s).ok()),
+        boe_type: boe_type
+            .unwrap_or_else(|| bonded_warehouse::BOE_TYPE_HOME_CONSUMPTION.to_string()),
+        into_bond_boe_id,
     })
 }
 
@@ -140,9 +173,112 @@ pub fn get_boe_calculations(state: State<DbState>) -> Result<Vec<SavedBoe>, Stri
         .map_err(|e| e.to_string())
 }
 
+/// Runs the server-side duty engine on a BOE being entered without saving anything: the same
+/// valuation, rate and settlement as saving it, so the preview matches what is stored.
 #[tauri::command]
-pub fn add_boe_calculation(payload: SavedBoe, state: State<DbState>) -> Result<String, String> {
+pub fn calculate_boe_duties(
+    mut payload: SavedBoe,
+    state: State<DbState>,
+) -> Result<CalculationResult, String> {
     let conn = state.db.lock().unwrap();
+    payload.calculation_result = engine_result(&conn, &mut payload)?;
+    settle_payable(&conn, &mut payload)?;
+    Ok(payload.calculation_result)
+}
+
+/// Engine result for a BOE on its own BE date; an ex-bond BOE takes the into-bond form values.
+fn engine_result(
+    conn: &rusqlite::Connection,
+    payload: &mut SavedBoe,
+) -> Result<CalculationResult, String> {
+    let be_date = tariff::be_date_for_boe(conn, payload.boe_id.as_deref());
+    bonded_warehouse::prepare_clearance(conn, payload)?;
+    valuation::apply_shipment_incoterm(conn, &payload.shipment_id, &mut payload.form_values)?;
+    // Ex-bond BOEs keep the into-bond valuation, exchange rate included.
+    if payload.boe_type != bonded_warehouse::BOE_TYPE_EX_BOND {
+        exchange_rates::apply_notified_rate(
+            conn,
+            &payload.shipment_id,
+            be_date.as_deref(),
+            &mut payload.form_values,
+        )?;
+    }
+    duty_engine::calculate_for_shipment(
+        conn,
+        &payload.shipment_id,
+        be_date.as_deref(),
+        &payload.form_values,
+        &payload.item_inputs,
+    )
+}
+
+/// Replaces the client-computed result with the engine's, so stored duty is always reproducible.
+pub(crate) fn apply_server_calculation(
+    conn: &rusqlite::Connection,
+    payload: &mut SavedBoe,
+) -> Result<(), String> {
+    let computed = engine_result(conn, payload)?;
+    if (computed.customs_duty_total - payload.calculation_result.customs_duty_total).abs() >= 1.0 {
+        log::warn!(
+            "BOE {}: client duty total {} differs from engine total {}; storing engine result",
+            payload.id,
+            payload.calculation_result.customs_duty_total,
+            computed.customs_duty_total
+        );
+    }
+    duty_credits::revalidate_utilisations(
+        conn,
+        &payload.id,
+        &computed.calculated_items,
+        &payload.item_inputs,
+    )?;
+    payload.calculation_result = computed;
+    settle_payable(conn, payload)
+}
+
+/// Sets the payable figures on a saved BOE from its duty heads: credits debited against it come
+/// off the duty first, and statutory interest and late-filing charges are levied on what remains.
+/// `customs_duty_total` is therefore what is actually paid in cash.
+pub(crate) fn settle_payable(
+    conn: &rusqlite::Connection,
+    saved: &mut SavedBoe,
+) -> Result<(), String> {
+    let result = &mut saved.calculation_result;
+    let gross = result.bcd_total
+        + result.sws_total
+        + result.igst_total
+        + result.aidc_total
+        + result.add_total
+        + result.safeguard_total
+        + result.cess_total;
+    result.duty_credit_total = duty_engine::round_paise(
+        duty_credits::utilised_on_boe(conn, &saved.id).map_err(|e| e.to_string())?,
+    );
+    let duty = duty_engine::round_rupee((gross - result.duty_credit_total).max(0.0));
+    result.interest = duty_engine::round_rupee(saved.form_values.interest.unwrap_or(0.0));
+    result.statutory_charges = None;
+    // Statutory charges replace the manual interest once the BE details are known; into-bond
+    // duty is deferred, so nothing is payable on it yet.
+    let boe_id = saved
+        .boe_id
+        .as_deref()
+        .filter(|_| saved.boe_type != bonded_warehouse::BOE_TYPE_INTO_BOND);
+    let mut late_filing = 0.0;
+    if let Some(boe_id) = boe_id {
+        if let Some(charges) = boe_charges::charges_for_boe(conn, boe_id, Some(duty))? {
+            result.interest = charges.late_payment_interest;
+            late_filing = charges.late_filing_charge;
+            result.statutory_charges = Some(charges);
+        }
+    }
+    result.customs_duty_total = duty + result.interest + late_filing;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn add_boe_calculation(mut payload: SavedBoe, state: State<DbState>) -> Result<String, String> {
+    let conn = state.db.lock().unwrap();
+    apply_server_calculation(&conn, &mut payload)?;
 
     // Serialize the nested structs into JSON strings
     let form_values_json =
@@ -155,8 +291,8 @@ pub fn add_boe_calculation(payload: SavedBoe, state: State<DbState>) -> Result<S
     let new_id = payload.id; // Use the ID generated by the frontend
 
     conn.execute(
-        "INSERT INTO boe_calculations (id, shipment_id, boe_id, supplier_name, invoice_number, status, form_values_json, item_inputs_json, calculation_result_json, attachments_json)
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+        "INSERT INTO boe_calculations (id, shipment_id, boe_id, supplier_name, invoice_number, status, form_values_json, item_inputs_json, calculation_result_json, attachments_json, boe_type, into_bond_boe_id)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
         params![
             new_id,
             payload.shipment_id,
@@ -168,24 +304,33 @@ pub fn add_boe_calculation(payload: SavedBoe, state: State<DbState>) -> Result<S
             item_inputs_json,
             calculation_result_json,
             serde_json::to_string(&payload.attachments).unwrap_or("null".into()),
+            payload.boe_type,
+            payload.into_bond_boe_id,
         ],
     ).map_err(|e| e.to_string())?;
 
     // Automatically update shipment status to "Custom Clearance" when BOE entry is added
-    // Only update if current status is not "delivered"
-    conn.execute(
-        "UPDATE shipments SET status = 'customs-clearance' WHERE id = ?1 AND status != 'delivered'",
-        params![payload.shipment_id],
-    )
-    .map_err(|e| e.to_string())?;
+    // Bonded shipments follow their clearances.
+    if !bonded_warehouse::sync_shipment_status(&conn, &payload.shipment_id)? {
+        shipment_status::transition_or_log(
+            &conn,
+            &payload.shipment_id,
+            shipment_status::CUSTOMS_CLEARANCE,
+            &TransitionContext::system("Bill of Entry added"),
+        );
+    }
 
+    boe_reconciliation::review_shipment_after_save(&conn, &payload.shipment_id);
+    landed_cost::recompute_after_change(&conn, &payload.shipment_id);
+    shipment_events::record_boe_milestones(&conn, &payload.shipment_id);
     let _ = dashboard_cache::invalidate_dashboard_metrics_cache(&conn);
     Ok(new_id.to_string())
 }
 
 #[tauri::command]
-pub fn update_boe_calculation(payload: SavedBoe, state: State<DbState>) -> Result<(), String> {
+pub fn update_boe_calculation(mut payload: SavedBoe, state: State<DbState>) -> Result<(), String> {
     let conn = state.db.lock().unwrap();
+    apply_server_calculation(&conn, &mut payload)?;
 
     // Serialize the nested structs into JSON strings
     let form_values_json =
@@ -197,7 +342,7 @@ pub fn update_boe_calculation(payload: SavedBoe, state: State<DbState>) -> Resul
 
     conn.execute(
         "UPDATE boe_calculations 
-         SET shipment_id = ?2, boe_id = ?3, supplier_name = ?4, invoice_number = ?5, status = ?6, form_values_json = ?7, item_inputs_json = ?8, calculation_result_json = ?9, attachments_json = ?10
+         SET shipment_id = ?2, boe_id = ?3, supplier_name = ?4, invoice_number = ?5, status = ?6, form_values_json = ?7, item_inputs_json = ?8, calculation_result_json = ?9, attachments_json = ?10, boe_type = ?11, into_bond_boe_id = ?12
          WHERE id = ?1",
         params![
             payload.id,
@@ -210,9 +355,15 @@ pub fn update_boe_calculation(payload: SavedBoe, state: State<DbState>) -> Resul
             item_inputs_json,
             calculation_result_json,
             serde_json::to_string(&payload.attachments).unwrap_or("null".into()),
+            payload.boe_type,
+            payload.into_bond_boe_id,
         ],
     ).map_err(|e| e.to_string())?;
 
+    bonded_warehouse::sync_shipment_status(&conn, &payload.shipment_id)?;
+    boe_reconciliation::review_shipment_after_save(&conn, &payload.shipment_id);
+    landed_cost::recompute_after_change(&conn, &payload.shipment_id);
+    shipment_events::record_boe_milestones(&conn, &payload.shipment_id);
     let _ = dashboard_cache::invalidate_dashboard_metrics_cache(&conn);
     Ok(())
 }
@@ -220,8 +371,22 @@ pub fn update_boe_calculation(payload: SavedBoe, state: State<DbState>) -> Resul
 #[tauri::command]
 pub fn delete_boe_calculation(id: String, state: State<DbState>) -> Result<(), String> {
     let conn = state.db.lock().unwrap();
+    let shipment_id: Option<String> = conn
+        .query_row(
+            "SELECT shipment_id FROM boe_calculations WHERE id = ?1",
+            params![id],
+            |r| r.get(0),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+    bonded_warehouse::ensure_no_clearances(&conn, &id)?;
     conn.execute("DELETE FROM boe_calculations WHERE id = ?1", params![id])
         .map_err(|e| e.to_string())?;
+    if let Some(shipment_id) = shipment_id {
+        bonded_warehouse::sync_shipment_status(&conn, &shipment_id)?;
+        boe_reconciliation::review_shipment_after_save(&conn, &shipment_id);
+        landed_cost::recompute_after_change(&conn, &shipment_id);
+    }
     let _ = dashboard_cache::invalidate_dashboard_metrics_cache(&conn);
     Ok(())
 }
@@ -239,15 +404,19 @@ pub fn get_shipments_for_boe_entry(state: State<DbState>) -> Result<Vec<BoeShipm
             ili.quantity, ili.unit_price,
             i.hsn_code,
             (ili.quantity * ili.unit_price) as line_total,
-            COALESCE(ili.duty_percent, CAST(REPLACE(COALESCE(i.bcd, '0'), '%', '') AS REAL)) as actual_bcd_rate,
-            COALESCE(ili.sws_percent, CAST(REPLACE(COALESCE(i.sws, '0'), '%', '') AS REAL)) as actual_sws_rate,
-            COALESCE(ili.igst_percent, CAST(REPLACE(COALESCE(i.igst, '0'), '%', '') AS REAL)) as actual_igst_rate
+            ili.duty_percent as actual_bcd_rate,
+            ili.sws_percent as actual_sws_rate,
+            ili.igst_percent as actual_igst_rate
         FROM shipments s
         JOIN suppliers sup ON s.supplier_id = sup.id
         JOIN invoices inv ON inv.shipment_id = s.id
         JOIN invoice_line_items ili ON ili.invoice_id = inv.id
         JOIN items i ON ili.item_id = i.id
-        WHERE s.id NOT IN (SELECT shipment_id FROM boe_calculations)
+        -- Bonded shipments stay available for ex-bond BOEs until nothing is left in bond.
+        WHERE s.id NOT IN (
+                SELECT shipment_id FROM boe_calculations
+                WHERE boe_type = 'HOME_CONSUMPTION' OR s.status NOT IN ('warehoused', 'partially-cleared')
+              )
           AND inv.status = 'Finalized'
         ORDER BY s.invoice_date DESC, s.id, i.part_number;
     ";
@@ -255,6 +424,7 @@ pub fn get_shipments_for_boe_entry(state: State<DbState>) -> Result<Vec<BoeShipm
     let mut stmt = conn.prepare(sql).map_err(|e| e.to_string())?;
 
     let mut shipments_map: HashMap<String, BoeShipment> = HashMap::new();
+    let today = tariff::today();
 
     let rows = stmt
         .query_map([], |row| {
@@ -315,6 +485,13 @@ pub fn get_shipments_for_boe_entry(state: State<DbState>) -> Result<Vec<BoeShipm
                 items: Vec::new(),
             });
 
+        // Not filed yet: show the tariff in force today.
+        let rates = tariff::resolve_duty_rates(
+            &conn,
+            &hs_code,
+            &today,
+            (actual_bcd_rate, actual_sws_rate, actual_igst_rate),
+        );
         shipment.items.push(BoeShipmentItem {
             part_no,
             description,
@@ -322,9 +499,9 @@ pub fn get_shipments_for_boe_entry(state: State<DbState>) -> Result<Vec<BoeShipm
             unit_price,
             hs_code,
             line_total,
-            actual_bcd_rate: actual_bcd_rate.unwrap_or(0.0),
-            actual_sws_rate: actual_sws_rate.unwrap_or(0.0),
-            actual_igst_rate: actual_igst_rate.unwrap_or(0.0),
+            actual_bcd_rate: rates.bcd,
+            actual_sws_rate: rates.sws,
+            actual_igst_rate: rates.igst,
         });
     }
 
@@ -343,9 +520,9 @@ pub fn get_shipments_for_boe_summary(state: State<DbState>) -> Result<Vec<BoeShi
             ili.quantity, ili.unit_price,
             i.hsn_code,
             (ili.quantity * ili.unit_price) as line_total,
-            COALESCE(ili.duty_percent, CAST(REPLACE(COALESCE(i.bcd, '0'), '%', '') AS REAL)) as actual_bcd_rate,
-            COALESCE(ili.sws_percent, CAST(REPLACE(COALESCE(i.sws, '0'), '%', '') AS REAL)) as actual_sws_rate,
-            COALESCE(ili.igst_percent, CAST(REPLACE(COALESCE(i.igst, '0'), '%', '') AS REAL)) as actual_igst_rate
+            ili.duty_percent as actual_bcd_rate,
+            ili.sws_percent as actual_sws_rate,
+            ili.igst_percent as actual_igst_rate
         FROM shipments s
         JOIN suppliers sup ON s.supplier_id = sup.id
         JOIN invoices inv ON inv.shipment_id = s.id
@@ -356,6 +533,8 @@ pub fn get_shipments_for_boe_summary(state: State<DbState>) -> Result<Vec<BoeShi
 
     let mut stmt = conn.prepare(sql).map_err(|e| e.to_string())?;
     let mut shipments_map: HashMap<String, BoeShipment> = HashMap::new();
+    let today = tariff::today();
+    let mut as_of_by_shipment: HashMap<String, String> = HashMap::new();
     let rows = stmt
         .query_map([], |row| {
             Ok((
@@ -415,6 +594,17 @@ pub fn get_shipments_for_boe_summary(state: State<DbState>) -> Result<Vec<BoeShi
                 items: Vec::new(),
             });
 
+        let as_of = as_of_by_shipment
+            .entry(shipment.id.clone())
+            .or_insert_with(|| {
+                tariff::be_date_for_shipment(&conn, &shipment.id).unwrap_or_else(|| today.clone())
+            });
+        let rates = tariff::resolve_duty_rates(
+            &conn,
+            &hs_code,
+            as_of,
+            (actual_bcd_rate, actual_sws_rate, actual_igst_rate),
+        );
         shipment.items.push(BoeShipmentItem {
             part_no,
             description,
@@ -422,9 +612,9 @@ pub fn get_shipments_for_boe_summary(state: State<DbState>) -> Result<Vec<BoeShi
             unit_price,
             hs_code,
             line_total,
-            actual_bcd_rate: actual_bcd_rate.unwrap_or(0.0),
-            actual_sws_rate: actual_sws_rate.unwrap_or(0.0),
-            actual_igst_rate: actual_igst_rate.unwrap_or(0.0),
+            actual_bcd_rate: rates.bcd,
+            actual_sws_rate: rates.sws,
+            actual_igst_rate: rates.igst,
         });
     }
 
@@ -495,122 +685,105 @@ pub fn get_boe_reconciliation(
         .ok_or("Saved BOE not found")?;
     let saved = map_row_to_saved_boe(row).map_err(|e| e.to_string())?;
 
-    // Fetch shipment items with actual rates and qty/unit price/hs code
-    let shipment = {
-        let sql = "
-            SELECT 
-                i.part_number, i.item_description,
-                ili.quantity, ili.unit_price,
-                i.hsn_code,
-                (ili.quantity * ili.unit_price) as line_total,
-                COALESCE(ili.duty_percent, CAST(REPLACE(COALESCE(i.bcd, '0'), '%', '') AS REAL)) as actual_bcd_rate,
-                COALESCE(ili.sws_percent, CAST(REPLACE(COALESCE(i.sws, '0'), '%', '') AS REAL)) as actual_sws_rate,
-                COALESCE(ili.igst_percent, CAST(REPLACE(COALESCE(i.igst, '0'), '%', '') AS REAL)) as actual_igst_rate
-            FROM invoices inv
-            JOIN invoice_line_items ili ON ili.invoice_id = inv.id
-            JOIN items i ON ili.item_id = i.id
-            WHERE inv.shipment_id = ?1
-            ORDER BY i.part_number
-        ";
-        let mut st = conn.prepare(sql).map_err(|e| e.to_string())?;
-        let it = st
-            .query_map(params![&saved.shipment_id], |row| {
-                Ok((
-                    row.get::<_, String>(0)?,      // part
-                    row.get::<_, String>(1)?,      // desc
-                    row.get::<_, f64>(2)?,         // qty
-                    row.get::<_, f64>(3)?,         // unit_price
-                    row.get::<_, String>(4)?,      // hsn
-                    row.get::<_, f64>(5)?,         // line_total
-                    row.get::<_, Option<f64>>(6)?, // bcd
-                    row.get::<_, Option<f64>>(7)?, // sws
-                    row.get::<_, Option<f64>>(8)?, // igst
-                ))
-            })
-            .map_err(|e| e.to_string())?;
-        let mut map: BoeShipmentMap = HashMap::new();
-        for r in it {
-            let (part, desc, qty, price, hsn, line_total, bcd, sws, igst) =
-                r.map_err(|e| e.to_string())?;
-            map.insert(
-                part.clone(),
-                (
-                    desc,
-                    qty,
-                    price,
-                    hsn,
-                    line_total,
-                    bcd.unwrap_or(0.0),
-                    sws.unwrap_or(0.0),
-                    igst.unwrap_or(0.0),
-                ),
+    // Shipment lines with actual (tariff) rates; the BOE side is re-derived by the duty engine from the
+    // saved inputs so reconciliation never trusts stored numbers it cannot reproduce.
+    let as_of =
+        tariff::be_date_for_boe(&conn, saved.boe_id.as_deref()).unwrap_or_else(tariff::today);
+    let lines = duty_engine::load_shipment_lines(&conn, &saved.shipment_id, &as_of)
+        .map_err(|e| e.to_string())?;
+    let calculation = duty_engine::calculate(&lines, &saved.form_values, &saved.item_inputs)
+        .unwrap_or_else(|e| {
+            log::warn!(
+                "Reconciliation for BOE {} falls back to stored result: {}",
+                saved.id,
+                e
             );
-        }
-        map
-    };
+            saved.calculation_result.clone()
+        });
 
-    // Build reconciliation rows by matching calculated items to shipment items and itemInputs (for method)
     let mut rows_out: Vec<ReconciledItemRow> = Vec::new();
     let mut actual_total_sum = 0.0;
     let mut boe_total_sum = 0.0;
     let mut savings_sum = 0.0;
 
-    for it in &saved.calculation_result.calculated_items {
-        if let Some((
-            desc,
-            qty,
-            unit_price,
-            hs_code,
-            _line_total,
-            act_bcd_rate,
-            act_sws_rate,
-            act_igst_rate,
-        )) = shipment.get(&it.part_no).cloned()
-        {
-            let assessable = it.assessable_value;
-            let actual_bcd = assessable * (act_bcd_rate / 100.0);
-            let actual_sws = actual_bcd * (act_sws_rate / 100.0);
-            let actual_igst = (assessable + actual_bcd + actual_sws) * (act_igst_rate / 100.0);
-            let actual_total = actual_bcd + actual_sws + actual_igst;
-
-            let boe_total = it.bcd_value + it.sws_value + it.igst_value;
-            let method = saved
-                .item_inputs
-                .iter()
-                .find(|ii| ii.part_no == it.part_no)
-                .map(|ii| ii.calculation_method.clone())
-                .unwrap_or_else(|| "Standard".to_string());
-            let savings = if method == "Standard" {
-                0.0
-            } else {
-                (actual_total - boe_total).max(0.0)
-            };
-
-            actual_total_sum += actual_total;
-            boe_total_sum += boe_total;
-            savings_sum += savings;
-
-            rows_out.push(ReconciledItemRow {
-                part_no: it.part_no.clone(),
-                description: desc,
-                qty,
-                unit_price,
-                hs_code,
-                assessable_value: assessable,
-                actual_bcd,
-                actual_sws,
-                actual_igst,
-                actual_total,
-                boe_bcd: it.bcd_value,
-                boe_sws: it.sws_value,
-                boe_igst: it.igst_value,
-                boe_total,
-                method,
-                savings,
-            });
+    let items = &calculation.calculated_items;
+    let line_of_item = duty_engine::pair_by_part(items, &lines, |i| &i.part_no, |l| &l.part_no);
+    let input_of_item =
+        duty_engine::pair_by_part(items, &saved.item_inputs, |i| &i.part_no, |ii| &ii.part_no);
+    for ((it, line), input) in items.iter().zip(line_of_item).zip(input_of_item) {
+        let Some(line) = line else {
+            continue;
+        };
+        let assessable = it.assessable_value;
+        // Specific duty on a partial or ex-bond BOE runs on the cleared quantity only.
+        let (portion, _) = duty_engine::line_portion(line, input.and_then(|ii| ii.quantity));
+        let actual =
+            duty_engine::standard_line_duty(assessable, saved.form_values.exchange_rate, &portion);
+        let actual_total = actual.total();
+
+        let boe_total = it.bcd_value
+            + it.sws_value
+            + it.igst_value
+            + it.aidc_value
+            + it.add_value
+            + it.safeguard_value
+            + it.cess_value;
+        let method = input
+            .map(|ii| ii.calculation_method.clone())
+            .unwrap_or_else(|| duty_engine::METHOD_STANDARD.to_string());
+        // CEPA savings only count when origin, a valid certificate and a concession back them.
+        let mut claim = None;
+        let mut preference_issue = None;
+        if method == duty_engine::METHOD_CEPA {
+            match trade_agreements::check_preference(&conn, &saved.shipment_id, line, &as_of) {
+                Ok(c) => claim = Some(c),
+                Err(e) => preference_issue = Some(e),
+            }
         }
+        let savings = if method == duty_engine::METHOD_STANDARD || preference_issue.is_some() {
+            0.0
+        } else {
+            (actual_total - boe_total).max(0.0)
+        };
+
+        actual_total_sum += actual_total;
+        boe_total_sum += boe_total;
+        savings_sum += savings;
+
+        rows_out.push(ReconciledItemRow {
+            part_no: it.part_no.clone(),
+            description: line.description.clone(),
+            qty: input.and_then(|ii| ii.quantity).unwrap_or(line.qty),
+            unit_price: line.unit_price,
+            hs_code: line.hs_code.clone(),
+            assessable_value: assessable,
+            actual_bcd: actual.bcd,
+            actual_sws: actual.sws,
+            actual_igst: actual.igst,
+            actual_aidc: actual.aidc,
+            actual_add: actual.add,
+            actual_safeguard: actual.safeguard,
+            actual_cess: actual.cess,
+            actual_total,
+            boe_bcd: it.bcd_value,
+            boe_sws: it.sws_value,
+            boe_igst: it.igst_value,
+            boe_aidc: it.aidc_value,
+            boe_add: it.add_value,
+            boe_safeguard: it.safeguard_value,
+            boe_cess: it.cess_value,
+            boe_total,
+            method,
+            savings,
+            agreement_code: claim.as_ref().map(|c| c.agreement_code.clone()),
+            certificate_number: claim.map(|c| c.certificate_number),
+            preference_issue,
+        });
     }
 
+    // Read-only: the mismatch case is kept in step by the save hooks and the automation run.
+    let mismatches = boe_reconciliation::check_shipment(&conn, &saved.shipment_id)?;
+    let exception_case_id = boe_reconciliation::open_mismatch_case_id(&conn, &saved.shipment_id)?;
     let report = BoeReconciliationReport {
         saved_boe_id: saved.id,
         shipment_id: saved.shipment_id,
@@ -622,6 +795,8 @@ pub fn get_boe_reconciliation(
             boe_total: boe_total_sum,
             savings_total: savings_sum,
         },
+        mismatches,
+        exception_case_id,
     };
 
     Ok(report)
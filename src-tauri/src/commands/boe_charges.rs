@@ -0,0 +1,446 @@
+//! Statutory charges on Bills of Entry: interest on late duty payment and late-filing charges.
+
+use crate::commands::dashboard_cache;
+use crate::commands::tariff;
+use crate::db::{DbState, StatutoryChargeLine, StatutoryCharges};
+use crate::duty_engine::{round_paise, round_rupee};
+use chrono::NaiveDate;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+pub const LATE_PAYMENT_INTEREST: &str = "LATE_PAYMENT_INTEREST";
+pub const LATE_FILING: &str = "LATE_FILING";
+
+const FREE_DAYS_KEY: &str = "boe_duty_payment_free_days";
+const ALERT_DAYS_KEY: &str = "boe_duty_payment_alert_days";
+const DEFAULT_FREE_DAYS: i64 = 1;
+const DEFAULT_ALERT_DAYS: i64 = 1;
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct BoeChargeSlab {
+    /// `LATE_PAYMENT_INTEREST` or `LATE_FILING`.
+    pub charge_type: String,
+    /// First day late this slab covers (1-based, inclusive).
+    pub from_day: i64,
+    /// Last day late this slab covers; `None` for the open-ended final slab.
+    pub to_day: Option<i64>,
+    /// Percent per annum for interest, INR per day for late filing.
+    pub rate: f64,
+    #[serde(default)]
+    pub updated_at: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct BoeChargeSettings {
+    /// Days after the BE date duty can be paid without interest.
+    pub payment_free_days: i64,
+    /// How many days before the free window closes an unpaid BOE is flagged on the dashboard.
+    pub alert_days: i64,
+}
+
+fn meta_get(conn: &Connection, key: &str) -> String {
+    conn.query_row(
+        "SELECT value FROM app_metadata WHERE key = ?1",
+        params![key],
+        |r| r.get(0),
+    )
+    .unwrap_or_default()
+}
+
+fn meta_set(conn: &Connection, key: &str, value: &str) -> Result<(), String> {
+    conn.execute(
+        "INSERT INTO app_metadata (key, value) VALUES (?1, ?2)
+         ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+        params![key, value],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+pub(crate) fn load_settings(conn: &Connection) -> BoeChargeSettings {
+    let days = |key: &str, default: i64| {
+        meta_get(conn, key)
+            .trim()
+            .parse::<i64>()
+            .ok()
+            .filter(|d| *d >= 0)
+            .unwrap_or(default)
+    };
+    BoeChargeSettings {
+        payment_free_days: days(FREE_DAYS_KEY, DEFAULT_FREE_DAYS),
+        alert_days: days(ALERT_DAYS_KEY, DEFAULT_ALERT_DAYS),
+    }
+}
+
+pub(crate) fn load_slabs(conn: &Connection) -> rusqlite::Result<Vec<BoeChargeSlab>> {
+    let mut stmt = conn.prepare(
+        "SELECT charge_type, from_day, to_day, rate, updated_at
+         FROM boe_charge_slabs ORDER BY charge_type, from_day",
+    )?;
+    let rows = stmt.query_map([], |r| {
+        Ok(BoeChargeSlab {
+            charge_type: r.get(0)?,
+            from_day: r.get(1)?,
+            to_day: r.get(2)?,
+            rate: r.get(3)?,
+            updated_at: r.get(4)?,
+        })
+    })?;
+    rows.collect()
+}
+
+fn parse_date(raw: &str) -> Option<NaiveDate> {
+    let iso = tariff::normalize_date(raw)?;
+    NaiveDate::parse_from_str(&iso, "%Y-%m-%d").ok()
+}
+
+fn fmt_date(d: NaiveDate) -> String {
+    d.format("%Y-%m-%d").to_string()
+}
+
+/// Splits `days_late` across the slabs of `charge_type`; `amount_for(rate, days)` prices each slab.
+fn slab_lines(
+    slabs: &[BoeChargeSlab],
+    charge_type: &str,
+    days_late: i64,
+    amount_for: impl Fn(f64, i64) -> f64,
+) -> Vec<StatutoryChargeLine> {
+    slabs
+        .iter()
+        .filter(|s| s.charge_type == charge_type)
+        .filter_map(|s| {
+            let last = s.to_day.map_or(days_late, |t| t.min(days_late));
+            let days = last - s.from_day + 1;
+            (days > 0).then(|| StatutoryChargeLine {
+                charge_type: charge_type.to_string(),
+                from_day: s.from_day,
+                to_day: s.to_day,
+                days,
+                rate: s.rate,
+                amount: round_paise(amount_for(s.rate, days)),
+            })
+        })
+        .collect()
+}
+
+/// Interest runs from the day after the free payment window to the payment date (or `today` while
+/// unpaid) on `duty`; late-filing charges run from the day after the filing deadline (the day
+/// before arrival) to the BE date. Returns `None` when the BE date cannot be read.
+pub fn compute(
+    be_date: &str,
+    arrival_date: Option<&str>,
+    payment_date: Option<&str>,
+    today: &str,
+    duty: f64,
+    settings: &BoeChargeSettings,
+    slabs: &[BoeChargeSlab],
+) -> Option<StatutoryCharges> {
+    let be = parse_date(be_date)?;
+    let due = be + chrono::Duration::days(settings.payment_free_days);
+    let paid_on = payment_date.and_then(parse_date);
+    let until = paid_on.or_else(|| parse_date(today)).unwrap_or(due);
+    let days_paid_late = if duty > 0.0 {
+        (until - due).num_days().max(0)
+    } else {
+        0
+    };
+    let mut lines = slab_lines(
+        slabs,
+        LATE_PAYMENT_INTEREST,
+        days_paid_late,
+        |rate, days| duty * rate / 100.0 * days as f64 / 365.0,
+    );
+    let late_payment_interest = round_rupee(lines.iter().map(|l| l.amount).sum());
+
+    let deadline = arrival_date
+        .and_then(parse_date)
+        .map(|a| a - chrono::Duration::days(1));
+    let days_filed_late = deadline.map_or(0, |d| (be - d).num_days().max(0));
+    let filing_lines = slab_lines(slabs, LATE_FILING, days_filed_late, |rate, days| {
+        rate * days as f64
+    });
+    let late_filing_charge = round_rupee(filing_lines.iter().map(|l| l.amount).sum());
+    lines.extend(filing_lines);
+
+    Some(StatutoryCharges {
+        payment_due_date: fmt_date(due),
+        interest_until: fmt_date(until),
+        duty_paid: paid_on.is_some(),
+        days_paid_late,
+        late_payment_interest,
+        filing_deadline: deadline.map(fmt_date),
+        days_filed_late,
+        late_filing_charge,
+        lines,
+    })
+}
+
+/// Charges for a `boe_details` row as of today; `duty` overrides the row's duty amount (e.g. the
+/// engine's figure for a saved calculation).
+pub(crate) fn charges_for_boe(
+    conn: &Connection,
+    boe_id: &str,
+    duty: Option<f64>,
+) -> Result<Option<StatutoryCharges>, String> {
+    let row = conn
+        .query_row(
+            "SELECT be_date, arrival_date, payment_date, duty_amount FROM boe_details WHERE id = ?1",
+            params![boe_id],
+            |r| {
+                Ok((
+                    r.get::<_, String>(0)?,
+                    r.get::<_, Option<String>>(1)?,
+                    r.get::<_, Option<String>>(2)?,
+                    r.get::<_, f64>(3)?,
+                ))
+            },
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+    let Some((be_date, arrival_date, payment_date, duty_amount)) = row else {
+        return Ok(None);
+    };
+    let slabs = load_slabs(conn).map_err(|e| e.to_string())?;
+    Ok(compute(
+        &be_date,
+        arrival_date.as_deref().filter(|s| !s.trim().is_empty()),
+        payment_date.as_deref().filter(|s| !s.trim().is_empty()),
+        &tariff::today(),
+        duty.unwrap_or(duty_amount),
+        &load_settings(conn),
+        &slabs,
+    ))
+}
+
+/// Stores the computed charges on the `boe_details` row.
+pub(crate) fn refresh_boe_details(conn: &Connection, boe_id: &str) -> Result<(), String> {
+    let charges = charges_for_boe(conn, boe_id, None)?.unwrap_or_default();
+    conn.execute(
+        "UPDATE boe_details SET late_payment_interest = ?2, late_filing_charge = ?3 WHERE id = ?1",
+        params![
+            boe_id,
+            charges.late_payment_interest,
+            charges.late_filing_charge
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn list_boe_charge_slabs(state: State<DbState>) -> Result<Vec<BoeChargeSlab>, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    load_slabs(&conn).map_err(|e| e.to_string())
+}
+
+/// Replaces the slabs of one charge type. Slabs must start at day 1 and be contiguous; only the
+/// last one may be open-ended.
+#[tauri::command]
+pub fn save_boe_charge_slabs(
+    charge_type: String,
+    mut slabs: Vec<BoeChargeSlab>,
+    state: State<DbState>,
+) -> Result<(), String> {
+    let charge_type = charge_type.trim().to_uppercase();
+    if charge_type != LATE_PAYMENT_INTEREST && charge_type != LATE_FILING {
+        return Err(format!("Unknown charge type '{charge_type}'"));
+    }
+    slabs.sort_by_key(|s| s.from_day);
+    let mut next_day = 1;
+    for (i, slab) in slabs.iter().enumerate() {
+        if slab.from_day != next_day {
+            return Err(format!(
+                "Slabs must be contiguous; expected one starting at day {next_day}"
+            ));
+        }
+        if slab.rate.is_nan() || slab.rate < 0.0 {
+            return Err("Rates cannot be negative".to_string());
+        }
+        match slab.to_day {
+            Some(to) if to < slab.from_day => {
+                return Err(format!(
+                    "Slab starting at day {} ends before it starts",
+                    slab.from_day
+                ));
+            }
+            Some(to) => next_day = to + 1,
+            None if i + 1 < slabs.len() => {
+                return Err("Only the last slab can be open-ended".to_string());
+            }
+            None => {}
+        }
+    }
+
+    let mut conn = state.db.lock().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    tx.execute(
+        "DELETE FROM boe_charge_slabs WHERE charge_type = ?1",
+        params![charge_type],
+    )
+    .map_err(|e| e.to_string())?;
+    for slab in &slabs {
+        tx.execute(
+            "INSERT INTO boe_charge_slabs (charge_type, from_day, to_day, rate) VALUES (?1, ?2, ?3, ?4)",
+            params![charge_type, slab.from_day, slab.to_day, slab.rate],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_boe_charge_settings(state: State<DbState>) -> Result<BoeChargeSettings, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    Ok(load_settings(&conn))
+}
+
+#[tauri::command]
+pub fn save_boe_charge_settings(
+    settings: BoeChargeSettings,
+    state: State<DbState>,
+) -> Result<(), String> {
+    if settings.payment_free_days < 0 || settings.alert_days < 0 {
+        return Err("Day counts cannot be negative".to_string());
+    }
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    meta_set(
+        &conn,
+        FREE_DAYS_KEY,
+        &settings.payment_free_days.to_string(),
+    )?;
+    meta_set(&conn, ALERT_DAYS_KEY, &settings.alert_days.to_string())?;
+    let _ = dashboard_cache::invalidate_dashboard_metrics_cache(&conn);
+    Ok(())
+}
+
+/// Itemised charges for a BOE as of today.
+#[tauri::command]
+pub fn get_boe_charges(
+    boe_id: String,
+    state: State<DbState>,
+) -> Result<Option<StatutoryCharges>, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    charges_for_boe(&conn, &boe_id, None)
+}
+
+/// Re-prices every BOE (e.g. after changing slabs, or to bring accruing interest up to date);
+/// returns how many BOEs carry charges.
+#[tauri::command]
+pub fn recompute_boe_charges(state: State<DbState>) -> Result<u32, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    let mut stmt = conn
+        .prepare("SELECT id FROM boe_details")
+        .map_err(|e| e.to_string())?;
+    let ids = stmt
+        .query_map([], |r| r.get::<_, String>(0))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    for id in &ids {
+        refresh_boe_details(&conn, id)?;
+    }
+    let charged: i64 = conn
+        .query_row(
+            "SELECT COUNT(*) FROM boe_details WHERE late_payment_interest > 0 OR late_filing_charge > 0",
+            [],
+            |r| r.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+    let _ = dashboard_cache::invalidate_dashboard_metrics_cache(&conn);
+    Ok(charged as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::test_support;
+
+    #[test]
+    fn interest_and_filing_charges_follow_configured_slabs() {
+        let conn = test_support::migrated_db();
+        let slabs = load_slabs(&conn).unwrap();
+        let settings = load_settings(&conn);
+        assert_eq!(settings.payment_free_days, 1);
+
+        // Due 2024-03-02, paid 2024-03-12: ten days at 15% p.a. on 73,000 = 300.
+        let paid = compute(
+            "2024-03-01",
+            Some("2024-03-01"),
+            Some("12/03/2024"),
+            "2024-06-30",
+            73_000.0,
+            &settings,
+            &slabs,
+        )
+        .unwrap();
+        assert_eq!(paid.payment_due_date, "2024-03-02");
+        assert!(paid.duty_paid);
+        assert_eq!(paid.days_paid_late, 10);
+        assert_eq!(paid.late_payment_interest, 300.0);
+        // Filed on arrival day: one day late at 5,000.
+        assert_eq!(paid.filing_deadline.as_deref(), Some("2024-02-29"));
+        assert_eq!(paid.days_filed_late, 1);
+        assert_eq!(paid.late_filing_charge, 5_000.0);
+
+        // Unpaid duty accrues to today; filing five days late spans both filing slabs.
+        let unpaid = compute(
+            "2024-03-06",
+            Some("2024-03-02"),
+            None,
+            "2024-03-12",
+            73_000.0,
+            &settings,
+            &slabs,
+        )
+        .unwrap();
+        assert!(!unpaid.duty_paid);
+        assert_eq!(unpaid.interest_until, "2024-03-12");
+        assert_eq!(unpaid.days_paid_late, 5);
+        assert_eq!(unpaid.late_payment_interest, 150.0);
+        assert_eq!(unpaid.days_filed_late, 5);
+        assert_eq!(unpaid.late_filing_charge, 3.0 * 5_000.0 + 2.0 * 10_000.0);
+        assert_eq!(
+            unpaid
+                .lines
+                .iter()
+                .filter(|l| l.charge_type == LATE_FILING)
+                .map(|l| l.days)
+                .collect::<Vec<_>>(),
+            vec![3, 2]
+        );
+
+        // Paid within the window and filed in advance: nothing to pay.
+        let clean = compute(
+            "2024-03-01",
+            Some("2024-03-05"),
+            Some("2024-03-02"),
+            "2024-06-30",
+            73_000.0,
+            &settings,
+            &slabs,
+        )
+        .unwrap();
+        assert_eq!(clean.late_payment_interest, 0.0);
+        assert_eq!(clean.late_filing_charge, 0.0);
+        assert!(clean.lines.is_empty());
+
+        conn.execute_batch(
+            "INSERT INTO boe_details (id, be_number, be_date, location, total_assessment_value, duty_amount, payment_date, arrival_date)
+             VALUES ('BOE-1', '1234567', '2024-03-01', 'INNSA1', 500000, 73000, '2024-03-12', '2024-03-01');",
+        )
+        .unwrap();
+        refresh_boe_details(&conn, "BOE-1").unwrap();
+        let stored: (f64, f64) = conn
+            .query_row(
+                "SELECT late_payment_interest, late_filing_charge FROM boe_details WHERE id = 'BOE-1'",
+                [],
+                |r| Ok((r.get(0)?, r.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(stored, (300.0, 5_000.0));
+    }
+}
@@ -0,0 +1,625 @@
+//! ICEGATE Bill of Entry import: parse a broker document, preview the BOE and a draft calculation, then commit.
+
+use crate::commands::boe;
+use crate::commands::boe_charges;
+use crate::commands::boe_reconciliation;
+use crate::commands::bonded_warehouse;
+use crate::commands::dashboard_cache;
+use crate::commands::exchange_rates;
+use crate::commands::landed_cost;
+use crate::commands::shipment_events;
+use crate::commands::shipment_status::{self, TransitionContext};
+use crate::commands::tariff::{normalize_hsn, today};
+use crate::commands::utils::generate_id;
+use crate::db::{
+    BoeItemInput, CalculatedDutyItem, CalculationResult, DbState, FormValues, SavedBoe,
+};
+use crate::duty_engine::{self, round_rupee, ShipmentDutyLine};
+use crate::icegate::{self, ParsedBillOfEntry, ParsedBoeItem};
+use crate::valuation;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+/// Status of a saved BOE created from an imported document and not yet reviewed.
+pub const DRAFT_STATUS: &str = "Draft";
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct BoeImportPreview {
+    pub document: ParsedBillOfEntry,
+    pub shipment_id: Option<String>,
+    /// `boe_details` row with the same BE number and date; it is updated instead of duplicated.
+    pub existing_boe_id: Option<String>,
+    /// Draft (or still awaiting) BOE calculation of the shipment that the import overwrites.
+    pub existing_saved_boe_id: Option<String>,
+    /// Serial numbers of BE items that match no invoice line of the shipment.
+    pub unmatched_items: Vec<u32>,
+    pub draft: Option<SavedBoe>,
+    pub warnings: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct BoeImportResult {
+    pub boe_id: String,
+    pub saved_boe_id: String,
+    pub boe_created: bool,
+    pub saved_boe_created: bool,
+}
+
+/// Rate that reproduces `amount` on `base`, in percent.
+fn implied_rate(amount: f64, base: f64) -> f64 {
+    if base.abs() < f64::EPSILON {
+        return 0.0;
+    }
+    (amount / base * 100.0 * 10_000.0).round() / 10_000.0
+}
+
+fn find_shipment(
+    conn: &Connection,
+    invoice_number: &str,
+    warnings: &mut Vec<String>,
+) -> Result<Option<String>, String> {
+    let mut stmt = conn
+        .prepare("SELECT id FROM shipments WHERE lower(trim(invoice_number)) = lower(trim(?1))")
+        .map_err(|e| e.to_string())?;
+    let ids = stmt
+        .query_map(params![invoice_number], |r| r.get::<_, String>(0))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    match ids.as_slice() {
+        [id] => Ok(Some(id.clone())),
+        [] => {
+            warnings.push(format!("No shipment has invoice number {invoice_number}"));
+            Ok(None)
+        }
+        _ => {
+            warnings.push(format!(
+                "Invoice number {invoice_number} is on {} shipments; choose one",
+                ids.len()
+            ));
+            Ok(None)
+        }
+    }
+}
+
+/// Pairs BE items with invoice lines: by part number first, then by HSN among the lines still free.
+fn match_items<'a>(
+    items: &[ParsedBoeItem],
+    lines: &'a [ShipmentDutyLine],
+) -> Vec<(usize, Option<&'a ShipmentDutyLine>)> {
+    let mut used = vec![false; lines.len()];
+    let mut take = |pred: &dyn Fn(&ShipmentDutyLine) -> bool| {
+        let at = (0..lines.len()).find(|&i| !used[i] && pred(&lines[i]))?;
+        used[at] = true;
+        Some(&lines[at])
+    };
+    let mut matched: Vec<(usize, Option<&ShipmentDutyLine>)> = items
+        .iter()
+        .enumerate()
+        .map(|(i, item)| {
+            let line = item.part_no.as_deref().and_then(|part| {
+                take(&|l: &ShipmentDutyLine| l.part_no.trim().eq_ignore_ascii_case(part.trim()))
+            });
+            (i, line)
+        })
+        .collect();
+    for (i, line) in matched.iter_mut().filter(|(_, l)| l.is_none()) {
+        if let Some(hsn) = items[*i].hsn_code.as_deref() {
+            *line = take(&|l: &ShipmentDutyLine| normalize_hsn(&l.hs_code).as_deref() == Some(hsn));
+        }
+    }
+    matched
+}
+
+/// Freight and insurance rate that bring the matched lines' invoice value up to the assessable
+/// value customs assessed, so the duty engine reproduces it. Freight is an invoice total spread by
+/// value, so it is scaled up to the whole invoice; insurance keeps the notional rate when the
+/// incoterm leaves both to be added.
+fn freight_and_insurance(
+    doc: &ParsedBillOfEntry,
+    matched: &[(usize, Option<&ShipmentDutyLine>)],
+    lines: &[ShipmentDutyLine],
+    exchange_rate: f64,
+    incoterm: Option<&str>,
+    warnings: &mut Vec<String>,
+) -> (f64, f64) {
+    let (mut invoiced, mut assessed) = (0.0, 0.0);
+    for (i, line) in matched {
+        if let Some(line) = line {
+            invoiced += line.line_total;
+            assessed += doc.items[*i].assessable_value;
+        }
+    }
+    if invoiced <= 0.0 || exchange_rate <= 0.0 {
+        return (0.0, 0.0);
+    }
+    let cif = assessed / exchange_rate;
+    let invoice_total: f64 = lines.iter().map(|l| l.line_total).sum();
+    let missing = incoterm.and_then(valuation::missing_elements);
+    let (freight, insurance_rate) = match missing {
+        Some(m) if m.freight => {
+            let insurance_rate = if m.insurance {
+                valuation::NOTIONAL_INSURANCE_PERCENT
+            } else {
+                0.0
+            };
+            let freight = cif - invoiced * (1.0 + insurance_rate / 100.0);
+            (freight / invoiced * invoice_total, insurance_rate)
+        }
+        Some(m) if m.insurance => (0.0, (cif / invoiced - 1.0) * 100.0),
+        Some(_) => (0.0, 0.0),
+        None => ((cif - invoiced) / invoiced * invoice_total, 0.0),
+    };
+    if freight < 0.0 || insurance_rate < 0.0 {
+        warnings.push(
+            "The assessed value is below the invoice value; freight and insurance are left to the valuation rules"
+                .to_string(),
+        );
+    }
+    (freight.max(0.0), insurance_rate.max(0.0))
+}
+
+#[allow(clippy::too_many_arguments)]
+fn draft_for(
+    conn: &Connection,
+    doc: &ParsedBillOfEntry,
+    shipment_id: &str,
+    existing_saved_boe_id: Option<&str>,
+    existing_boe_id: Option<&str>,
+    matched: &[(usize, Option<&ShipmentDutyLine>)],
+    lines: &[ShipmentDutyLine],
+    warnings: &mut Vec<String>,
+) -> Result<SavedBoe, String> {
+    let (invoice_number, supplier_name, incoterm): (String, String, Option<String>) = conn
+        .query_row(
+            "SELECT s.invoice_number, COALESCE(sup.supplier_name, ''), s.incoterm
+             FROM shipments s LEFT JOIN suppliers sup ON sup.id = s.supplier_id
+             WHERE s.id = ?1",
+            params![shipment_id],
+            |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)),
+        )
+        .map_err(|e| e.to_string())?;
+    let incoterm = incoterm
+        .map(|t| t.trim().to_uppercase())
+        .filter(|t| !t.is_empty());
+
+    let exchange_rate = match doc.exchange_rate {
+        Some(rate) => rate,
+        None => {
+            match exchange_rates::resolve_for_shipment(conn, shipment_id, doc.be_date.as_deref())? {
+                Some(notified) => notified.rate,
+                None => {
+                    warnings.push(
+                        "No exchange rate on the document or in the customs rate master; using 1.0"
+                            .to_string(),
+                    );
+                    1.0
+                }
+            }
+        }
+    };
+
+    let (freight_cost, insurance_rate) = freight_and_insurance(
+        doc,
+        matched,
+        lines,
+        exchange_rate,
+        incoterm.as_deref(),
+        warnings,
+    );
+
+    let mut item_inputs = Vec::new();
+    let mut calculated_items = Vec::new();
+    for (i, line) in matched {
+        let Some(line) = line else { continue };
+        let item = &doc.items[*i];
+        let av = item.assessable_value;
+        let igst_base = av + item.bcd + item.aidc + item.sws + item.add + item.safeguard;
+        item_inputs.push(BoeItemInput {
+            part_no: line.part_no.clone(),
+            calculation_method: duty_engine::METHOD_STANDARD.to_string(),
+            boe_bcd_rate: implied_rate(item.bcd, av),
+            boe_sws_rate: implied_rate(item.sws, item.bcd),
+            boe_igst_rate: implied_rate(item.igst, igst_base),
+            boe_aidc_rate: Some(implied_rate(item.aidc, av)),
+            boe_add_rate: Some(implied_rate(item.add, av)),
+            boe_safeguard_rate: Some(implied_rate(item.safeguard, av)),
+            boe_cess_rate: Some(implied_rate(item.cess, igst_base)),
+            quantity: None,
+        });
+        calculated_items.push(CalculatedDutyItem {
+            part_no: line.part_no.clone(),
+            description: line.description.clone(),
+            assessable_value: av,
+            bcd_value: item.bcd,
+            sws_value: item.sws,
+            igst_value: item.igst,
+            aidc_value: item.aidc,
+            add_value: item.add,
+            safeguard_value: item.safeguard,
+            cess_value: item.cess,
+            valuation: None,
+        });
+    }
+
+    let total =
+        |head: fn(&CalculatedDutyItem) -> f64| round_rupee(calculated_items.iter().map(head).sum());
+    let bcd_total = total(|i| i.bcd_value);
+    let sws_total = total(|i| i.sws_value);
+    let igst_total = total(|i| i.igst_value);
+    let aidc_total = total(|i| i.aidc_value);
+    let add_total = total(|i| i.add_value);
+    let safeguard_total = total(|i| i.safeguard_value);
+    let cess_total = total(|i| i.cess_value);
+    let interest = round_rupee(doc.interest.unwrap_or(0.0));
+    let calculation_result = CalculationResult {
+        calculated_items,
+        bcd_total,
+        sws_total,
+        igst_total,
+        aidc_total,
+        add_total,
+        safeguard_total,
+        cess_total,
+        interest,
+        customs_duty_total: bcd_total
+            + sws_total
+            + igst_total
+            + aidc_total
+            + add_total
+            + safeguard_total
+            + cess_total
+            + interest,
+        duty_credit_total: 0.0,
+        statutory_charges: None,
+    };
+
+    Ok(SavedBoe {
+        id: existing_saved_boe_id
+            .map(str::to_string)
+            .unwrap_or_else(|| generate_id(Some("BOECALC".to_string()))),
+        shipment_id: shipment_id.to_string(),
+        boe_id: existing_boe_id.map(str::to_string),
+        invoice_number,
+        supplier_name: supplier_name.clone(),
+        status: DRAFT_STATUS.to_string(),
+        form_values: FormValues {
+            supplier_name,
+            shipment_id: shipment_id.to_string(),
+            exchange_rate,
+            freight_cost,
+            exw_cost: 0.0,
+            insurance_rate,
+            interest: doc.interest,
+            incoterm,
+            apportionment_basis: None,
+        },
+        item_inputs,
+        calculation_result,
+        attachments: None,
+        boe_type: bonded_warehouse::BOE_TYPE_HOME_CONSUMPTION.to_string(),
+        into_bond_boe_id: None,
+    })
+}
+
+/// Resolves the shipment (given, or by the document's invoice number), finds the BOE and saved
+/// calculation the import would overwrite, and builds the draft from the assessed figures.
+pub fn build_preview(
+    conn: &Connection,
+    document: ParsedBillOfEntry,
+    shipment_id: Option<&str>,
+) -> Result<BoeImportPreview, String> {
+    let mut warnings = document.warnings.clone();
+    let shipment_id = match shipment_id.filter(|s| !s.trim().is_empty()) {
+        Some(id) => {
+            let exists: bool = conn
+                .query_row(
+                    "SELECT COUNT(*) FROM shipments WHERE id = ?1",
+                    params![id],
+                    |r| r.get::<_, i64>(0),
+                )
+                .map_err(|e| e.to_string())?
+                > 0;
+            if !exists {
+                return Err(format!("Shipment {id} not found"));
+            }
+            Some(id.to_string())
+        }
+        None => match document.invoice_number.as_deref() {
+            Some(inv) => find_shipment(conn, inv, &mut warnings)?,
+            None => {
+                warnings.push("No invoice number on the document; choose the shipment".to_string());
+                None
+            }
+        },
+    };
+
+    let existing_boe_id: Option<String> = match (&document.be_number, &document.be_date) {
+        (Some(number), Some(date)) => conn
+            .query_row(
+                "SELECT id FROM boe_details WHERE be_number = ?1 AND be_date = ?2",
+                params![number, date],
+                |r| r.get(0),
+            )
+            .optional()
+            .map_err(|e| e.to_string())?,
+        _ => None,
+    };
+
+    let Some(shipment_id) = shipment_id else {
+        return Ok(BoeImportPreview {
+            document,
+            shipment_id: None,
+            existing_boe_id,
+            existing_saved_boe_id: None,
+            unmatched_items: Vec::new(),
+            draft: None,
+            warnings,
+        });
+    };
+
+    let existing_saved_boe_id: Option<String> = conn
+        .query_row(
+            "SELECT id FROM boe_calculations
+             WHERE shipment_id = ?1 AND status IN (?3, 'Awaiting BOE Data')
+               AND (boe_id IS NULL OR boe_id = '' OR boe_id = ?2)
+             ORDER BY (boe_id = ?2) DESC
+             LIMIT 1",
+            params![shipment_id, existing_boe_id, DRAFT_STATUS],
+            |r| r.get(0),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+
+    let as_of = document.be_date.clone().unwrap_or_else(today);
+    let lines =
+        duty_engine::load_shipment_lines(conn, &shipment_id, &as_of).map_err(|e| e.to_string())?;
+    let matched = match_items(&document.items, &lines);
+    let unmatched_items: Vec<u32> = matched
+        .iter()
+        .filter(|(_, l)| l.is_none())
+        .map(|(i, _)| document.items[*i].serial)
+        .collect();
+    if !unmatched_items.is_empty() {
+        warnings.push(format!(
+            "{} BE item(s) match no invoice line of shipment {shipment_id} and are left out of the draft",
+            unmatched_items.len()
+        ));
+    }
+
+    let draft = draft_for(
+        conn,
+        &document,
+        &shipment_id,
+        existing_saved_boe_id.as_deref(),
+        existing_boe_id.as_deref(),
+        &matched,
+        &lines,
+        &mut warnings,
+    )?;
+    Ok(BoeImportPreview {
+        document,
+        shipment_id: Some(shipment_id),
+        existing_boe_id,
+        existing_saved_boe_id,
+        unmatched_items,
+        draft: Some(draft),
+        warnings,
+    })
+}
+
+/// Writes the BOE header and the draft calculation for a previewed document. The draft goes through
+/// the same server calculation as a BOE saved from the entry screen.
+pub fn commit_import(
+    conn: &mut Connection,
+    document: ParsedBillOfEntry,
+    shipment_id: Option<&str>,
+) -> Result<BoeImportResult, String> {
+    let (Some(be_number), Some(be_date)) = (document.be_number.clone(), document.be_date.clone())
+    else {
+        return Err("BE number and BE date are required to import a Bill of Entry".to_string());
+    };
+    let preview = build_preview(conn, document, shipment_id)?;
+    let Some(mut draft) = preview.draft else {
+        return Err("Choose the shipment this Bill of Entry belongs to".to_string());
+    };
+    if draft.item_inputs.is_empty() {
+        return Err("No BE item matches an invoice line of the shipment".to_string());
+    }
+    let doc = &preview.document;
+    let location = doc.port_code.clone().unwrap_or_default();
+    let total_assessment_value = doc
+        .total_assessable_value
+        .unwrap_or_else(|| doc.items.iter().map(|i| i.assessable_value).sum());
+    let duty_amount = doc
+        .total_duty
+        .unwrap_or(draft.calculation_result.customs_duty_total);
+
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let boe_id = match &preview.existing_boe_id {
+        Some(id) => {
+            tx.execute(
+                "UPDATE boe_details SET location = ?2, total_assessment_value = ?3, duty_amount = ?4 WHERE id = ?1",
+                params![id, location, total_assessment_value, duty_amount],
+            )
+            .map_err(|e| e.to_string())?;
+            id.clone()
+        }
+        None => {
+            let id = generate_id(Some("BOE".to_string()));
+            tx.execute(
+                "INSERT INTO boe_details (id, be_number, be_date, location, total_assessment_value, duty_amount)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![id, be_number, be_date, location, total_assessment_value, duty_amount],
+            )
+            .map_err(|e| e.to_string())?;
+            id
+        }
+    };
+    boe_charges::refresh_boe_details(&tx, &boe_id)?;
+    draft.boe_id = Some(boe_id.clone());
+    boe::apply_server_calculation(&tx, &mut draft)?;
+
+    let form_values_json = serde_json::to_string(&draft.form_values).map_err(|e| e.to_string())?;
+    let item_inputs_json = serde_json::to_string(&draft.item_inputs).map_err(|e| e.to_string())?;
+    let calculation_result_json =
+        serde_json::to_string(&draft.calculation_result).map_err(|e| e.to_string())?;
+    if preview.existing_saved_boe_id.is_some() {
+        tx.execute(
+            "UPDATE boe_calculations
+             SET boe_id = ?2, supplier_name = ?3, invoice_number = ?4, status = ?5, form_values_json = ?6, item_inputs_json = ?7, calculation_result_json = ?8
+             WHERE id = ?1",
+            params![
+                draft.id,
+                draft.boe_id,
+                draft.supplier_name,
+                draft.invoice_number,
+                draft.status,
+                form_values_json,
+                item_inputs_json,
+                calculation_result_json,
+            ],
+        )
+        .map_err(|e| e.to_string())?;
+    } else {
+        tx.execute(
+            "INSERT INTO boe_calculations (id, shipment_id, boe_id, supplier_name, invoice_number, status, form_values_json, item_inputs_json, calculation_result_json, attachments_json)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, 'null')",
+            params![
+                draft.id,
+                draft.shipment_id,
+                draft.boe_id,
+                draft.supplier_name,
+                draft.invoice_number,
+                draft.status,
+                form_values_json,
+                item_inputs_json,
+                calculation_result_json,
+            ],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    shipment_status::transition_or_log(
+        &tx,
+        &draft.shipment_id,
+        shipment_status::CUSTOMS_CLEARANCE,
+        &TransitionContext::system("Bill of Entry imported"),
+    );
+    tx.commit().map_err(|e| e.to_string())?;
+    boe_reconciliation::review_shipment_after_save(conn, &draft.shipment_id);
+    landed_cost::recompute_after_change(conn, &draft.shipment_id);
+    shipment_events::record_boe_milestones(conn, &draft.shipment_id);
+
+    Ok(BoeImportResult {
+        boe_id,
+        saved_boe_id: draft.id,
+        boe_created: preview.existing_boe_id.is_none(),
+        saved_boe_created: preview.existing_saved_boe_id.is_none(),
+    })
+}
+
+/// Parses an ICEGATE checklist PDF or flat file and previews what importing it would write.
+#[tauri::command]
+pub fn preview_icegate_boe(
+    file_path: String,
+    shipment_id: Option<String>,
+    state: State<DbState>,
+) -> Result<BoeImportPreview, String> {
+    let bytes = std::fs::read(&file_path).map_err(|e| format!("{file_path}: {e}"))?;
+    let document = icegate::parse_bytes(&bytes)?;
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    build_preview(&conn, document, shipment_id.as_deref())
+}
+
+/// Commits a previewed (and possibly corrected) document: upserts the BOE and the draft calculation.
+#[tauri::command]
+pub fn import_icegate_boe(
+    document: ParsedBillOfEntry,
+    shipment_id: Option<String>,
+    state: State<DbState>,
+) -> Result<BoeImportResult, String> {
+    let mut conn = state.db.lock().map_err(|e| e.to_string())?;
+    let result = commit_import(&mut conn, document, shipment_id.as_deref())?;
+    let _ = dashboard_cache::invalidate_dashboard_metrics_cache(&conn);
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::test_support;
+
+    #[test]
+    fn import_creates_then_updates_boe_and_draft() {
+        let mut c = test_support::migrated_db();
+        test_support::add_shipment(&c, "SHP-1", "2024-02-01", 1500.0, "USD", "in-transit");
+        test_support::add_invoice(&c, "INV-1", "SHP-1");
+        c.execute_batch(
+            "INSERT INTO items (id, part_number, item_description, unit, currency, unit_price, hsn_code, is_active)
+                 VALUES ('IT-1', 'BRG-6205', 'Bearing', 'PCS', 'USD', 2, '84821011', 1),
+                        ('IT-2', 'SEAL-01', 'Seal', 'PCS', 'USD', 5, '40169320', 1);
+             INSERT INTO invoice_line_items (id, invoice_id, item_id, quantity, unit_price)
+                 VALUES ('L1', 'INV-1', 'IT-1', 500, 2), ('L2', 'INV-1', 'IT-2', 100, 5);",
+        )
+        .unwrap();
+
+        let doc = icegate::parse_document(
+            "BE_NO,BE_DATE,PORT_CODE,INVOICE_NO,EXCH_RATE,CTH,ASSESSABLE_VALUE,BCD_AMT,SWS_AMT,IGST_AMT\n\
+             2345678,12/03/2024,INNSA1,inv-1,80,84821011,100000,10000,1000,19980\n\
+             2345678,12/03/2024,INNSA1,inv-1,80,40169320,50000,5000,500,9990\n",
+        )
+        .unwrap();
+        let preview = build_preview(&c, doc.clone(), None).unwrap();
+        assert_eq!(preview.shipment_id.as_deref(), Some("SHP-1"));
+        assert!(preview.unmatched_items.is_empty());
+        let draft = preview.draft.unwrap();
+        assert_eq!(draft.item_inputs[0].part_no, "BRG-6205");
+        assert_eq!(draft.item_inputs[0].boe_bcd_rate, 10.0);
+        assert_eq!(draft.item_inputs[0].boe_igst_rate, 18.0);
+        assert_eq!(draft.calculation_result.customs_duty_total, 46470.0);
+        // FOB 1,500 USD assessed at 1,875 USD CIF: notional insurance, the rest is freight.
+        assert_eq!(draft.form_values.insurance_rate, 1.125);
+        assert_eq!(draft.form_values.freight_cost, 358.125);
+
+        let first = commit_import(&mut c, doc.clone(), None).unwrap();
+        assert!(first.boe_created && first.saved_boe_created);
+        let again = commit_import(&mut c, doc, None).unwrap();
+        assert_eq!(
+            (
+                again.boe_id,
+                again.saved_boe_id,
+                again.boe_created,
+                again.saved_boe_created
+            ),
+            (first.boe_id, first.saved_boe_id, false, false)
+        );
+        let status: String = c
+            .query_row("SELECT status FROM boe_calculations", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(status, DRAFT_STATUS);
+
+        // The stored draft is the engine's result, settled like any saved BOE.
+        let saved = c
+            .query_row(
+                "SELECT * FROM boe_calculations",
+                [],
+                boe::map_row_to_saved_boe,
+            )
+            .unwrap();
+        let result = &saved.calculation_result;
+        assert_eq!(
+            result
+                .calculated_items
+                .iter()
+                .map(|i| i.assessable_value)
+                .collect::<Vec<_>>(),
+            vec![100000.0, 50000.0]
+        );
+        assert!(result.calculated_items[0].valuation.is_some());
+        assert_eq!((result.bcd_total, result.igst_total), (15000.0, 29970.0));
+        assert!(result.statutory_charges.is_some());
+    }
+}
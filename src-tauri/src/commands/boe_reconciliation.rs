@@ -0,0 +1,497 @@
+//! Tolerance-based BOE reconciliation: per-head tolerances, line mismatch classes and `BOE_MISMATCH` cases.
+
+use crate::commands::boe::map_row_to_saved_boe;
+use crate::commands::dashboard_cache;
+use crate::commands::exception_workflow;
+use crate::commands::tariff;
+use crate::commands::trade_agreements;
+use crate::db::{BoeItemInput, CalculatedDutyItem, DbState, ReconciliationMismatch, SavedBoe};
+use crate::duty_engine::{self, LineDuty, ShipmentDutyLine};
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use tauri::State;
+
+pub const BOE_MISMATCH: &str = "BOE_MISMATCH";
+
+pub const RATE_DIFFERS: &str = "RATE_DIFFERS";
+pub const ASSESSABLE_VALUE_DIFFERS: &str = "ASSESSABLE_VALUE_DIFFERS";
+pub const MISSING_ON_BOE: &str = "MISSING_ON_BOE";
+pub const MISSING_ON_INVOICE: &str = "MISSING_ON_INVOICE";
+
+const ASSESSABLE_VALUE: &str = "ASSESSABLE_VALUE";
+const DUTY_HEADS: [&str; 7] = ["BCD", "AIDC", "SWS", "ADD", "SAFEGUARD", "IGST", "CESS"];
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ReconciliationTolerance {
+    /// `ASSESSABLE_VALUE` or a duty head (`BCD`, `AIDC`, `SWS`, `ADD`, `SAFEGUARD`, `IGST`, `CESS`).
+    pub duty_head: String,
+    /// Accepted difference in INR.
+    pub absolute_tolerance: f64,
+    /// Accepted difference as a percentage of the expected amount.
+    pub percent_tolerance: f64,
+    #[serde(default)]
+    pub updated_at: Option<String>,
+}
+
+impl ReconciliationTolerance {
+    /// A difference is accepted when it is within either the absolute or the percentage bound.
+    pub fn accepts(&self, expected: f64, boe: f64) -> bool {
+        let diff = (boe - expected).abs();
+        diff <= self.absolute_tolerance + 1e-6
+            || diff <= expected.abs() * self.percent_tolerance / 100.0 + 1e-6
+    }
+}
+
+pub(crate) fn load_tolerances(
+    conn: &Connection,
+) -> rusqlite::Result<HashMap<String, ReconciliationTolerance>> {
+    let mut stmt = conn.prepare(
+        "SELECT duty_head, absolute_tolerance, percent_tolerance, updated_at
+         FROM boe_reconciliation_tolerances ORDER BY duty_head",
+    )?;
+    let rows = stmt.query_map([], |r| {
+        Ok(ReconciliationTolerance {
+            duty_head: r.get(0)?,
+            absolute_tolerance: r.get(1)?,
+            percent_tolerance: r.get(2)?,
+            updated_at: r.get(3)?,
+        })
+    })?;
+    rows.map(|t| t.map(|t| (t.duty_head.clone(), t))).collect()
+}
+
+/// Heads without a configured tolerance must match to the paisa.
+pub(crate) fn within(
+    tolerances: &HashMap<String, ReconciliationTolerance>,
+    head: &str,
+    expected: f64,
+    boe: f64,
+) -> bool {
+    match tolerances.get(head) {
+        Some(t) => t.accepts(expected, boe),
+        None => (boe - expected).abs() < 0.005,
+    }
+}
+
+fn line_heads(d: &LineDuty) -> [f64; 7] {
+    [d.bcd, d.aidc, d.sws, d.add, d.safeguard, d.igst, d.cess]
+}
+
+fn item_heads(i: &CalculatedDutyItem) -> [f64; 7] {
+    [
+        i.bcd_value,
+        i.aidc_value,
+        i.sws_value,
+        i.add_value,
+        i.safeguard_value,
+        i.igst_value,
+        i.cess_value,
+    ]
+}
+
+/// Duty the line should pay on the BOE's assessable value: tariff rates, with the concessional BCD
+/// when a CEPA claim is backed by origin, certificate and concession.
+fn expected_line_duty(
+    conn: &Connection,
+    saved: &SavedBoe,
+    line: &ShipmentDutyLine,
+    assessable_value: f64,
+    as_of: &str,
+    method: &str,
+) -> LineDuty {
+    let rate = saved.form_values.exchange_rate;
+    let standard = duty_engine::standard_line_duty(assessable_value, rate, line);
+    if method != duty_engine::METHOD_CEPA {
+        return standard;
+    }
+    let Ok(claim) = trade_agreements::check_preference(conn, &saved.shipment_id, line, as_of)
+    else {
+        return standard;
+    };
+    let input = BoeItemInput {
+        part_no: line.part_no.clone(),
+        calculation_method: duty_engine::METHOD_CEPA.to_string(),
+        boe_bcd_rate: claim.concessional_bcd_rate,
+        boe_sws_rate: line.actual_sws_rate,
+        boe_igst_rate: line.actual_igst_rate,
+        boe_aidc_rate: None,
+        boe_add_rate: None,
+        boe_safeguard_rate: None,
+        boe_cess_rate: None,
+        quantity: None,
+    };
+    duty_engine::compute_line_duty(assessable_value, rate, &input, line).unwrap_or(standard)
+}
+
+/// Compares the BOE as saved (what was declared and assessed) with what the invoice and tariff
+/// support. Lines of the shipment absent from this BOE are not reported here; see [`check_shipment`].
+pub fn classify_saved_boe(
+    conn: &Connection,
+    saved: &SavedBoe,
+    lines: &[ShipmentDutyLine],
+    as_of: &str,
+    tolerances: &HashMap<String, ReconciliationTolerance>,
+) -> Vec<ReconciliationMismatch> {
+    let mut out = Vec::new();
+    let items = &saved.calculation_result.calculated_items;
+    let line_of_item = duty_engine::pair_by_part(items, lines, |i| &i.part_no, |l| &l.part_no);
+    let input_of_item =
+        duty_engine::pair_by_part(items, &saved.item_inputs, |i| &i.part_no, |ii| &ii.part_no);
+    for ((item, line), input) in items.iter().zip(line_of_item).zip(input_of_item) {
+        let Some(line) = line else {
+            out.push(ReconciliationMismatch {
+                part_no: item.part_no.clone(),
+                kind: MISSING_ON_INVOICE.to_string(),
+                duty_head: None,
+                expected: 0.0,
+                boe: item.assessable_value,
+            });
+            continue;
+        };
+
+        // Warehoused and ex-bond BOEs cover part of the line; expectations scale to that portion.
+        let whole_av = duty_engine::line_assessable_value(line, lines, &saved.form_values);
+        let (line, share) = duty_engine::line_portion(line, input.and_then(|ii| ii.quantity));
+        let expected_av = duty_engine::round_paise(whole_av * share);
+        if !within(
+            tolerances,
+            ASSESSABLE_VALUE,
+            expected_av,
+            item.assessable_value,
+        ) {
+            out.push(ReconciliationMismatch {
+                part_no: item.part_no.clone(),
+                kind: ASSESSABLE_VALUE_DIFFERS.to_string(),
+                duty_head: Some(ASSESSABLE_VALUE.to_string()),
+                expected: expected_av,
+                boe: item.assessable_value,
+            });
+        }
+
+        let method = input.map_or(duty_engine::METHOD_STANDARD, |ii| {
+            ii.calculation_method.as_str()
+        });
+        // Duty is re-derived on the BOE's own assessable value so a value difference is not
+        // reported a second time as a rate difference on every head.
+        let expected = expected_line_duty(conn, saved, &line, item.assessable_value, as_of, method);
+        for ((head, want), got) in DUTY_HEADS
+            .iter()
+            .zip(line_heads(&expected))
+            .zip(item_heads(item))
+        {
+            // RoDTEP lines debit BCD from the scrip, so the cash BCD on the BOE is not comparable.
+            if *head == "BCD" && method == duty_engine::METHOD_RODTEP {
+                continue;
+            }
+            if !within(tolerances, head, want, got) {
+                out.push(ReconciliationMismatch {
+                    part_no: item.part_no.clone(),
+                    kind: RATE_DIFFERS.to_string(),
+                    duty_head: Some(head.to_string()),
+                    expected: want,
+                    boe: got,
+                });
+            }
+        }
+    }
+    out
+}
+
+/// All mismatches across the shipment's saved BOEs, plus invoice lines that no BOE covers.
+pub fn check_shipment(
+    conn: &Connection,
+    shipment_id: &str,
+) -> Result<Vec<ReconciliationMismatch>, String> {
+    let mut stmt = conn
+        .prepare("SELECT * FROM boe_calculations WHERE shipment_id = ?1 ORDER BY id")
+        .map_err(|e| e.to_string())?;
+    let saved_boes = stmt
+        .query_map(params![shipment_id], map_row_to_saved_boe)
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    let Some(first) = saved_boes.first() else {
+        return Ok(Vec::new());
+    };
+    let tolerances = load_tolerances(conn).map_err(|e| e.to_string())?;
+
+    let mut out = Vec::new();
+    for saved in &saved_boes {
+        let as_of =
+            tariff::be_date_for_boe(conn, saved.boe_id.as_deref()).unwrap_or_else(tariff::today);
+        let lines = duty_engine::load_shipment_lines(conn, shipment_id, &as_of)
+            .map_err(|e| e.to_string())?;
+        out.extend(classify_saved_boe(conn, saved, &lines, &as_of, &tolerances));
+    }
+
+    let lines = duty_engine::load_shipment_lines(conn, shipment_id, &tariff::today())
+        .map_err(|e| e.to_string())?;
+    for line in &lines {
+        let covered = saved_boes.iter().any(|b| {
+            b.calculation_result
+                .calculated_items
+                .iter()
+                .any(|i| i.part_no == line.part_no)
+        });
+        if !covered {
+            out.push(ReconciliationMismatch {
+                part_no: line.part_no.clone(),
+                kind: MISSING_ON_BOE.to_string(),
+                duty_head: None,
+                expected: duty_engine::line_assessable_value(line, &lines, &first.form_values),
+                boe: 0.0,
+            });
+        }
+    }
+    Ok(out)
+}
+
+/// Opens a `BOE_MISMATCH` case when the shipment has mismatches and resolves it once they clear.
+/// Returns the open case id.
+pub fn sync_mismatch_case(
+    conn: &Connection,
+    shipment_id: &str,
+    mismatches: &[ReconciliationMismatch],
+) -> Result<Option<String>, String> {
+    if mismatches.is_empty() {
+        exception_workflow::auto_resolve_case_for_entity(conn, BOE_MISMATCH, shipment_id)?;
+        return Ok(None);
+    }
+    let mut by_kind: BTreeMap<&str, usize> = BTreeMap::new();
+    for m in mismatches {
+        *by_kind.entry(m.kind.as_str()).or_default() += 1;
+    }
+    // Lines missing from either side usually mean a wrong or partial BOE, not a rounding slip.
+    let priority =
+        if by_kind.contains_key(MISSING_ON_BOE) || by_kind.contains_key(MISSING_ON_INVOICE) {
+            "HIGH"
+        } else {
+            "MEDIUM"
+        };
+    let details = serde_json::json!({
+        "exceptionType": BOE_MISMATCH,
+        "mismatches": mismatches.len(),
+        "byKind": by_kind,
+    });
+    exception_workflow::open_case_for_entity(
+        conn,
+        BOE_MISMATCH,
+        shipment_id,
+        priority,
+        &details.to_string(),
+    )
+}
+
+/// The shipment's open `BOE_MISMATCH` case, if any.
+pub fn open_mismatch_case_id(
+    conn: &Connection,
+    shipment_id: &str,
+) -> Result<Option<String>, String> {
+    exception_workflow::open_case_id_for_entity(conn, BOE_MISMATCH, shipment_id)
+}
+
+/// Re-checks one shipment and brings its `BOE_MISMATCH` case in line.
+pub fn review_shipment(
+    conn: &Connection,
+    shipment_id: &str,
+) -> Result<(Vec<ReconciliationMismatch>, Option<String>), String> {
+    let mismatches = check_shipment(conn, shipment_id)?;
+    let case_id = sync_mismatch_case(conn, shipment_id, &mismatches)?;
+    Ok((mismatches, case_id))
+}
+
+/// [`review_shipment`] for callers whose own write already succeeded; failures are only logged.
+pub fn review_shipment_after_save(conn: &Connection, shipment_id: &str) {
+    if let Err(e) = review_shipment(conn, shipment_id) {
+        log::warn!("BOE tolerance check for shipment {shipment_id} failed: {e}");
+    }
+}
+
+#[tauri::command]
+pub fn list_boe_reconciliation_tolerances(
+    state: State<DbState>,
+) -> Result<Vec<ReconciliationTolerance>, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    let tolerances = load_tolerances(&conn).map_err(|e| e.to_string())?;
+    let mut tolerances: Vec<_> = tolerances.into_values().collect();
+    tolerances.sort_by(|a, b| a.duty_head.cmp(&b.duty_head));
+    Ok(tolerances)
+}
+
+#[tauri::command]
+pub fn save_boe_reconciliation_tolerance(
+    tolerance: ReconciliationTolerance,
+    state: State<DbState>,
+) -> Result<(), String> {
+    let head = tolerance.duty_head.trim().to_uppercase();
+    if head != ASSESSABLE_VALUE && !DUTY_HEADS.contains(&head.as_str()) {
+        return Err(format!("Unknown duty head '{}'", tolerance.duty_head));
+    }
+    if !(tolerance.absolute_tolerance >= 0.0 && tolerance.percent_tolerance >= 0.0) {
+        return Err("Tolerances cannot be negative".to_string());
+    }
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    conn.execute(
+        "INSERT INTO boe_reconciliation_tolerances (duty_head, absolute_tolerance, percent_tolerance)
+         VALUES (?1, ?2, ?3)
+         ON CONFLICT(duty_head) DO UPDATE SET
+            absolute_tolerance = excluded.absolute_tolerance,
+            percent_tolerance = excluded.percent_tolerance,
+            updated_at = strftime('%Y-%m-%d %H:%M:%S', 'now', 'localtime')",
+        params![head, tolerance.absolute_tolerance, tolerance.percent_tolerance],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Re-checks every shipment with a saved BOE (e.g. after changing tolerances); returns how many
+/// shipments are out of tolerance.
+#[tauri::command]
+pub fn recheck_boe_reconciliations(state: State<DbState>) -> Result<u32, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    let mut stmt = conn
+        .prepare("SELECT DISTINCT shipment_id FROM boe_calculations")
+        .map_err(|e| e.to_string())?;
+    let shipment_ids = stmt
+        .query_map([], |r| r.get::<_, String>(0))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    let mut out_of_tolerance = 0;
+    for shipment_id in shipment_ids {
+        let (mismatches, _) = review_shipment(&conn, &shipment_id)?;
+        if !mismatches.is_empty() {
+            out_of_tolerance += 1;
+        }
+    }
+    let _ = dashboard_cache::invalidate_dashboard_metrics_cache(&conn);
+    Ok(out_of_tolerance)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::test_support;
+    use crate::db::{CalculationResult, FormValues};
+
+    fn item(part_no: &str, av: f64, bcd: f64, sws: f64, igst: f64) -> CalculatedDutyItem {
+        CalculatedDutyItem {
+            part_no: part_no.to_string(),
+            description: String::new(),
+            assessable_value: av,
+            bcd_value: bcd,
+            sws_value: sws,
+            igst_value: igst,
+            aidc_value: 0.0,
+            add_value: 0.0,
+            safeguard_value: 0.0,
+            cess_value: 0.0,
+            valuation: None,
+        }
+    }
+
+    fn save(conn: &Connection, items: Vec<CalculatedDutyItem>) {
+        let form = FormValues {
+            supplier_name: "Acme".to_string(),
+            shipment_id: "SHP-1".to_string(),
+            exchange_rate: 80.0,
+            freight_cost: 0.0,
+            exw_cost: 0.0,
+            insurance_rate: 0.0,
+            interest: None,
+            incoterm: None,
+            apportionment_basis: None,
+        };
+        let result = CalculationResult {
+            calculated_items: items,
+            bcd_total: 0.0,
+            sws_total: 0.0,
+            igst_total: 0.0,
+            aidc_total: 0.0,
+            add_total: 0.0,
+            safeguard_total: 0.0,
+            cess_total: 0.0,
+            interest: 0.0,
+            customs_duty_total: 0.0,
+            duty_credit_total: 0.0,
+            statutory_charges: None,
+        };
+        conn.execute(
+            "INSERT OR REPLACE INTO boe_calculations (id, shipment_id, supplier_name, invoice_number, form_values_json, item_inputs_json, calculation_result_json)
+             VALUES ('BC-1', 'SHP-1', 'Acme', 'INV-1', ?1, '[]', ?2)",
+            params![
+                serde_json::to_string(&form).unwrap(),
+                serde_json::to_string(&result).unwrap()
+            ],
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn mismatches_open_a_case_that_resolves_when_fixed() {
+        let c = test_support::migrated_db();
+        test_support::add_shipment(&c, "SHP-1", "2024-02-01", 1500.0, "USD", "in-transit");
+        test_support::add_invoice(&c, "INV-1", "SHP-1");
+        c.execute_batch(
+            "INSERT INTO items (id, part_number, item_description, unit, currency, unit_price, hsn_code, is_active)
+                 VALUES ('IT-1', 'P1', 'Bearing', 'PCS', 'USD', 10, '84821011', 1),
+                        ('IT-2', 'P2', 'Seal', 'PCS', 'USD', 5, '40169320', 1);
+             INSERT INTO invoice_line_items (id, invoice_id, item_id, quantity, unit_price, duty_percent, sws_percent, igst_percent)
+                 VALUES ('L1', 'INV-1', 'IT-1', 100, 10, 10, 10, 18), ('L2', 'INV-1', 'IT-2', 100, 5, 10, 10, 18);",
+        )
+        .unwrap();
+
+        // P1: AV 80,000 → BCD 8,000, SWS 800, IGST 15,984. IGST is short; P2 is missing; P9 is extra.
+        save(
+            &c,
+            vec![
+                item("P1", 80_000.0, 8_000.0, 800.0, 14_400.0),
+                item("P9", 1_000.0, 0.0, 0.0, 0.0),
+            ],
+        );
+        let (mismatches, case_id) = review_shipment(&c, "SHP-1").unwrap();
+        let kinds: Vec<(&str, Option<&str>)> = mismatches
+            .iter()
+            .map(|m| (m.kind.as_str(), m.duty_head.as_deref()))
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                (RATE_DIFFERS, Some("IGST")),
+                (MISSING_ON_INVOICE, None),
+                (MISSING_ON_BOE, None),
+            ]
+        );
+        let case_id = case_id.expect("case opened");
+        let priority: String = c
+            .query_row(
+                "SELECT priority FROM exception_cases WHERE id = ?1 AND exception_type = 'BOE_MISMATCH'",
+                params![case_id],
+                |r| r.get(0),
+            )
+            .unwrap();
+        assert_eq!(priority, "HIGH");
+
+        // Rounding within the default ₹1 tolerance reconciles and closes the case.
+        save(
+            &c,
+            vec![
+                item("P1", 80_000.0, 8_000.0, 800.0, 15_984.4),
+                item("P2", 40_000.0, 4_000.0, 400.0, 7_992.0),
+            ],
+        );
+        let (mismatches, case_id) = review_shipment(&c, "SHP-1").unwrap();
+        assert!(mismatches.is_empty(), "{mismatches:?}");
+        assert!(case_id.is_none());
+        let status: String = c
+            .query_row(
+                "SELECT status FROM exception_cases WHERE exception_type = 'BOE_MISMATCH'",
+                [],
+                |r| r.get(0),
+            )
+            .unwrap();
+        assert_eq!(status, "RESOLVED");
+    }
+}
@@ -0,0 +1,511 @@
+//! Bonded warehousing (Section 49 / MOOWR): into-bond and ex-bond BOEs, quantities left in bond, partial-clearance status.
+
+use crate::commands::boe::map_row_to_saved_boe;
+use crate::commands::shipment_status::{self, TransitionContext};
+use crate::commands::tariff;
+use crate::db::{DbState, SavedBoe};
+use crate::duty_engine::{self, ShipmentDutyLine};
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+pub const BOE_TYPE_HOME_CONSUMPTION: &str = "HOME_CONSUMPTION";
+/// Warehousing BOE: goods are assessed on entry but duty is deferred until they are cleared ex-bond.
+pub const BOE_TYPE_INTO_BOND: &str = "INTO_BOND";
+/// Clearance of part of a warehoused consignment for home consumption; duty is paid here.
+pub const BOE_TYPE_EX_BOND: &str = "EX_BOND";
+
+pub const STATUS_WAREHOUSED: &str = "warehoused";
+pub const STATUS_PARTIALLY_CLEARED: &str = "partially-cleared";
+
+/// Quantities cleared may differ from the warehoused figure by float noise only.
+const QTY_EPSILON: f64 = 1e-6;
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct BondedItemBalance {
+    pub part_no: String,
+    pub description: String,
+    pub warehoused_qty: f64,
+    pub cleared_qty: f64,
+    pub remaining_qty: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct BondedStock {
+    pub into_bond_boe_id: String,
+    pub shipment_id: String,
+    pub invoice_number: String,
+    pub items: Vec<BondedItemBalance>,
+    pub ex_bond_boe_ids: Vec<String>,
+}
+
+fn saved_boes(conn: &Connection, shipment_id: &str) -> Result<Vec<SavedBoe>, String> {
+    let mut stmt = conn
+        .prepare("SELECT * FROM boe_calculations WHERE shipment_id = ?1 ORDER BY created_at, id")
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![shipment_id], map_row_to_saved_boe)
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())
+}
+
+/// Invoice line each BOE input applies to; inputs for a repeated part pair with its lines in order,
+/// as in the duty engine.
+fn lines_of<'a>(
+    boe: &SavedBoe,
+    lines: &'a [ShipmentDutyLine],
+) -> Vec<Option<&'a ShipmentDutyLine>> {
+    duty_engine::pair_by_part(&boe.item_inputs, lines, |ii| &ii.part_no, |l| &l.part_no)
+}
+
+/// Balance per warehoused invoice line, with the line it belongs to.
+fn line_balances<'a>(
+    into_bond: &SavedBoe,
+    ex_bonds: &[&SavedBoe],
+    lines: &'a [ShipmentDutyLine],
+) -> Vec<(&'a ShipmentDutyLine, BondedItemBalance)> {
+    let cleared: Vec<_> = ex_bonds
+        .iter()
+        .flat_map(|b| b.item_inputs.iter().zip(lines_of(b, lines)))
+        .collect();
+    into_bond
+        .item_inputs
+        .iter()
+        .zip(lines_of(into_bond, lines))
+        .filter_map(|(input, line)| {
+            let line = line?;
+            let warehoused_qty = input.quantity.unwrap_or(line.qty);
+            let cleared_qty: f64 = cleared
+                .iter()
+                .filter(|(_, l)| l.is_some_and(|l| std::ptr::eq(l, line)))
+                .map(|(ii, _)| ii.quantity.unwrap_or(warehoused_qty))
+                .sum();
+            let balance = BondedItemBalance {
+                part_no: input.part_no.clone(),
+                description: line.description.clone(),
+                warehoused_qty,
+                cleared_qty,
+                remaining_qty: warehoused_qty - cleared_qty,
+            };
+            Some((line, balance))
+        })
+        .collect()
+}
+
+fn balances(
+    into_bond: &SavedBoe,
+    ex_bonds: &[&SavedBoe],
+    lines: &[ShipmentDutyLine],
+) -> Vec<BondedItemBalance> {
+    line_balances(into_bond, ex_bonds, lines)
+        .into_iter()
+        .map(|(_, b)| b)
+        .collect()
+}
+
+fn ex_bonds_of<'a>(
+    boes: &'a [SavedBoe],
+    into_bond_id: &str,
+    excluding: Option<&str>,
+) -> Vec<&'a SavedBoe> {
+    boes.iter()
+        .filter(|b| {
+            b.boe_type == BOE_TYPE_EX_BOND
+                && b.into_bond_boe_id.as_deref() == Some(into_bond_id)
+                && Some(b.id.as_str()) != excluding
+        })
+        .collect()
+}
+
+/// Warehoused, cleared and remaining quantities per into-bond BOE of the shipment.
+pub fn bonded_stock(conn: &Connection, shipment_id: &str) -> Result<Vec<BondedStock>, String> {
+    let boes = saved_boes(conn, shipment_id)?;
+    if !boes.iter().any(|b| b.boe_type == BOE_TYPE_INTO_BOND) {
+        return Ok(Vec::new());
+    }
+    let lines = duty_engine::load_shipment_lines(conn, shipment_id, &tariff::today())
+        .map_err(|e| e.to_string())?;
+    Ok(boes
+        .iter()
+        .filter(|b| b.boe_type == BOE_TYPE_INTO_BOND)
+        .map(|into_bond| {
+            let ex_bonds = ex_bonds_of(&boes, &into_bond.id, None);
+            BondedStock {
+                into_bond_boe_id: into_bond.id.clone(),
+                shipment_id: shipment_id.to_string(),
+                invoice_number: into_bond.invoice_number.clone(),
+                items: balances(into_bond, &ex_bonds, &lines),
+                ex_bond_boe_ids: ex_bonds.iter().map(|b| b.id.clone()).collect(),
+            }
+        })
+        .collect())
+}
+
+/// Checks the BOE type before the duty engine runs. An ex-bond BOE must clear no more than is left
+/// in bond and takes its valuation (exchange rate, freight, insurance) from the into-bond BOE; only
+/// the rate of duty follows its own BE date. An into-bond BOE cannot drop below what is already cleared.
+pub fn prepare_clearance(conn: &Connection, payload: &mut SavedBoe) -> Result<(), String> {
+    let boes = saved_boes(conn, &payload.shipment_id)?;
+    let clearing_this = ex_bonds_of(&boes, &payload.id, None);
+    match payload.boe_type.as_str() {
+        BOE_TYPE_HOME_CONSUMPTION | BOE_TYPE_INTO_BOND => {
+            if payload.into_bond_boe_id.is_some() {
+                return Err("Only ex-bond BOEs reference an into-bond BOE".to_string());
+            }
+            if clearing_this.is_empty() {
+                return Ok(());
+            }
+            if payload.boe_type != BOE_TYPE_INTO_BOND {
+                return Err(format!(
+                    "{} ex-bond BOE(s) clear goods from this BOE; it must stay into-bond",
+                    clearing_this.len()
+                ));
+            }
+            let lines =
+                duty_engine::load_shipment_lines(conn, &payload.shipment_id, &tariff::today())
+                    .map_err(|e| e.to_string())?;
+            for b in balances(payload, &clearing_this, &lines) {
+                if b.remaining_qty < -QTY_EPSILON {
+                    return Err(format!(
+                        "Part {}: {} already cleared ex-bond, more than the {} warehoused",
+                        b.part_no, b.cleared_qty, b.warehoused_qty
+                    ));
+                }
+            }
+            Ok(())
+        }
+        BOE_TYPE_EX_BOND => {
+            let into_id = payload
+                .into_bond_boe_id
+                .clone()
+                .ok_or("An ex-bond BOE must reference its into-bond BOE")?;
+            let into_bond = boes
+                .iter()
+                .find(|b| b.id == into_id && b.boe_type == BOE_TYPE_INTO_BOND)
+                .ok_or_else(|| {
+                    format!(
+                        "{into_id} is not an into-bond BOE of shipment {}",
+                        payload.shipment_id
+                    )
+                })?;
+            let others = ex_bonds_of(&boes, &into_id, Some(&payload.id));
+            let lines =
+                duty_engine::load_shipment_lines(conn, &payload.shipment_id, &tariff::today())
+                    .map_err(|e| e.to_string())?;
+            let left = line_balances(into_bond, &others, &lines);
+            for (input, line) in payload.item_inputs.iter().zip(lines_of(payload, &lines)) {
+                let qty = input.quantity.ok_or_else(|| {
+                    format!("Enter the quantity cleared for part {}", input.part_no)
+                })?;
+                let bal = left
+                    .iter()
+                    .find(|(l, _)| line.is_some_and(|line| std::ptr::eq(*l, line)))
+                    .map(|(_, b)| b)
+                    .ok_or_else(|| {
+                        format!("Part {} was not warehoused under {into_id}", input.part_no)
+                    })?;
+                if qty > bal.remaining_qty + QTY_EPSILON {
+                    return Err(format!(
+                        "Part {}: clearing {qty} exceeds the {} left in bond",
+                        input.part_no, bal.remaining_qty
+                    ));
+                }
+            }
+            let interest = payload.form_values.interest;
+            payload.form_values = into_bond.form_values.clone();
+            payload.form_values.interest = interest;
+            Ok(())
+        }
+        other => Err(format!("Unknown BOE type '{other}'")),
+    }
+}
+
+/// Fails when ex-bond BOEs still clear goods from `saved_boe_id`.
+pub fn ensure_no_clearances(conn: &Connection, saved_boe_id: &str) -> Result<(), String> {
+    let n: i64 = conn
+        .query_row(
+            "SELECT COUNT(*) FROM boe_calculations WHERE into_bond_boe_id = ?1",
+            params![saved_boe_id],
+            |r| r.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+    if n > 0 {
+        return Err(format!(
+            "{n} ex-bond BOE(s) clear goods from this BOE; delete them first"
+        ));
+    }
+    Ok(())
+}
+
+/// Moves a shipment with into-bond BOEs to `warehoused`, `partially-cleared` or (once nothing is
+/// left in bond) `customs-clearance`. Returns `false`, leaving the status alone, for non-bonded shipments.
+pub fn sync_shipment_status(conn: &Connection, shipment_id: &str) -> Result<bool, String> {
+    let stock = bonded_stock(conn, shipment_id)?;
+    if stock.is_empty() {
+        return Ok(false);
+    }
+    let items = stock.iter().flat_map(|s| s.items.iter());
+    let (cleared, remaining) = items.fold((0.0, 0.0), |(c, r), i| {
+        (c + i.cleared_qty, r + i.remaining_qty.max(0.0))
+    });
+    let status = if remaining <= QTY_EPSILON {
+        "customs-clearance"
+    } else if cleared > QTY_EPSILON {
+        STATUS_PARTIALLY_CLEARED
+    } else {
+        STATUS_WAREHOUSED
+    };
+    shipment_status::transition_or_log(
+        conn,
+        shipment_id,
+        status,
+        &TransitionContext::system("Bonded stock changed"),
+    );
+    Ok(true)
+}
+
+/// Bonded stock for one shipment, or for every shipment with an into-bond BOE.
+#[tauri::command]
+pub fn get_bonded_stock(
+    shipment_id: Option<String>,
+    state: State<DbState>,
+) -> Result<Vec<BondedStock>, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    let shipment_ids: Vec<String> = match shipment_id {
+        Some(id) => vec![id],
+        None => {
+            let mut stmt = conn
+                .prepare(
+                    "SELECT DISTINCT shipment_id FROM boe_calculations WHERE boe_type = ?1 ORDER BY shipment_id",
+                )
+                .map_err(|e| e.to_string())?;
+            let rows = stmt
+                .query_map(params![BOE_TYPE_INTO_BOND], |r| r.get(0))
+                .map_err(|e| e.to_string())?;
+            rows.collect::<Result<Vec<_>, _>>()
+                .map_err(|e| e.to_string())?
+        }
+    };
+    let mut out = Vec::new();
+    for id in shipment_ids {
+        out.extend(bonded_stock(&conn, &id)?);
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::{boe_reconciliation, test_support};
+    use crate::db::{BoeItemInput, CalculationResult, FormValues};
+
+    fn boe(id: &str, boe_type: &str, into_bond: Option<&str>, qty: Option<f64>) -> SavedBoe {
+        SavedBoe {
+            id: id.to_string(),
+            shipment_id: "SHP-1".to_string(),
+            boe_id: None,
+            invoice_number: "INV-1".to_string(),
+            supplier_name: "Acme".to_string(),
+            status: "Awaiting BOE Data".to_string(),
+            form_values: FormValues {
+                supplier_name: "Acme".to_string(),
+                shipment_id: "SHP-1".to_string(),
+                // The into-bond rate is 80; ex-bond BOEs must not use their own.
+                exchange_rate: if into_bond.is_some() { 95.0 } else { 80.0 },
+                freight_cost: 0.0,
+                exw_cost: 0.0,
+                insurance_rate: 0.0,
+                interest: None,
+                incoterm: None,
+                apportionment_basis: None,
+            },
+            item_inputs: vec![BoeItemInput {
+                part_no: "P1".to_string(),
+                calculation_method: duty_engine::METHOD_STANDARD.to_string(),
+                boe_bcd_rate: 10.0,
+                boe_sws_rate: 10.0,
+                boe_igst_rate: 18.0,
+                boe_aidc_rate: None,
+                boe_add_rate: None,
+                boe_safeguard_rate: None,
+                boe_cess_rate: None,
+                quantity: qty,
+            }],
+            calculation_result: CalculationResult {
+                calculated_items: Vec::new(),
+                bcd_total: 0.0,
+                sws_total: 0.0,
+                igst_total: 0.0,
+                aidc_total: 0.0,
+                add_total: 0.0,
+                safeguard_total: 0.0,
+                cess_total: 0.0,
+                interest: 0.0,
+                customs_duty_total: 0.0,
+                duty_credit_total: 0.0,
+                statutory_charges: None,
+            },
+            attachments: None,
+            boe_type: boe_type.to_string(),
+            into_bond_boe_id: into_bond.map(str::to_string),
+        }
+    }
+
+    /// The save path of `add_boe_calculation` without the Tauri state.
+    fn save(c: &Connection, mut payload: SavedBoe) -> Result<SavedBoe, String> {
+        prepare_clearance(c, &mut payload)?;
+        payload.calculation_result = duty_engine::calculate_for_shipment(
+            c,
+            &payload.shipment_id,
+            None,
+            &payload.form_values,
+            &payload.item_inputs,
+        )?;
+        c.execute(
+            "INSERT INTO boe_calculations (id, shipment_id, supplier_name, invoice_number, form_values_json, item_inputs_json, calculation_result_json, boe_type, into_bond_boe_id)
+             VALUES (?1, ?2, 'Acme', 'INV-1', ?3, ?4, ?5, ?6, ?7)",
+            params![
+                payload.id,
+                payload.shipment_id,
+                serde_json::to_string(&payload.form_values).unwrap(),
+                serde_json::to_string(&payload.item_inputs).unwrap(),
+                serde_json::to_string(&payload.calculation_result).unwrap(),
+                payload.boe_type,
+                payload.into_bond_boe_id,
+            ],
+        )
+        .map_err(|e| e.to_string())?;
+        sync_shipment_status(c, &payload.shipment_id)?;
+        Ok(payload)
+    }
+
+    fn shipment_status(c: &Connection) -> String {
+        c.query_row("SELECT status FROM shipments WHERE id = 'SHP-1'", [], |r| {
+            r.get(0)
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn ex_bond_clearances_draw_down_the_bond() {
+        let c = test_support::migrated_db();
+        test_support::add_shipment(&c, "SHP-1", "2024-02-01", 1000.0, "USD", "in-transit");
+        test_support::add_item(&c, "IT-1", "P1", "Bearing", None);
+        test_support::add_invoice(&c, "INV-1", "SHP-1");
+        c.execute_batch(
+            "INSERT INTO invoice_line_items (id, invoice_id, item_id, quantity, unit_price, duty_percent, sws_percent, igst_percent)
+                 VALUES ('L1', 'INV-1', 'IT-1', 100, 10, 10, 10, 18);",
+        )
+        .unwrap();
+
+        save(&c, boe("IB", BOE_TYPE_INTO_BOND, None, None)).unwrap();
+        assert_eq!(shipment_status(&c), STATUS_WAREHOUSED);
+
+        // 40 of 100 units at the into-bond rate: AV 32,000 → BCD 3,200, SWS 320, IGST 6,393.60.
+        let first = save(&c, boe("EB1", BOE_TYPE_EX_BOND, Some("IB"), Some(40.0))).unwrap();
+        let item = &first.calculation_result.calculated_items[0];
+        assert_eq!(first.form_values.exchange_rate, 80.0);
+        assert_eq!(
+            (
+                item.assessable_value,
+                item.bcd_value,
+                item.sws_value,
+                item.igst_value
+            ),
+            (32_000.0, 3_200.0, 320.0, 6_393.6)
+        );
+        assert_eq!(shipment_status(&c), STATUS_PARTIALLY_CLEARED);
+        assert!(boe_reconciliation::check_shipment(&c, "SHP-1")
+            .unwrap()
+            .is_empty());
+
+        let err = save(&c, boe("EB2", BOE_TYPE_EX_BOND, Some("IB"), Some(70.0))).unwrap_err();
+        assert!(err.contains("60 left in bond"), "{err}");
+        assert!(save(&c, boe("EB2", BOE_TYPE_EX_BOND, Some("IB"), None)).is_err());
+
+        save(&c, boe("EB2", BOE_TYPE_EX_BOND, Some("IB"), Some(60.0))).unwrap();
+        assert_eq!(shipment_status(&c), "customs-clearance");
+        let stock = bonded_stock(&c, "SHP-1").unwrap();
+        assert_eq!(stock[0].items[0].remaining_qty, 0.0);
+        assert_eq!(stock[0].ex_bond_boe_ids, vec!["EB1", "EB2"]);
+        assert!(ensure_no_clearances(&c, "IB").is_err());
+    }
+
+    #[test]
+    fn repeated_part_lines_keep_their_own_bond_balance() {
+        let c = test_support::migrated_db();
+        test_support::add_shipment(&c, "SHP-1", "2024-02-01", 1500.0, "USD", "in-transit");
+        test_support::add_item(&c, "IT-1", "P1", "Bearing", None);
+        test_support::add_invoice(&c, "INV-1", "SHP-1");
+        c.execute_batch(
+            "INSERT INTO invoice_line_items (id, invoice_id, item_id, quantity, unit_price, duty_percent, sws_percent, igst_percent)
+                 VALUES ('L1', 'INV-1', 'IT-1', 100, 10, 10, 10, 18),
+                        ('L2', 'INV-1', 'IT-1', 50, 10, 10, 10, 18);",
+        )
+        .unwrap();
+        // One BOE item per invoice line of the repeated part.
+        let twice = |mut b: SavedBoe, second_qty: Option<f64>| {
+            let mut second = b.item_inputs[0].clone();
+            second.quantity = second_qty;
+            b.item_inputs.push(second);
+            b
+        };
+        save(&c, twice(boe("IB", BOE_TYPE_INTO_BOND, None, None), None)).unwrap();
+        save(
+            &c,
+            twice(
+                boe("EB1", BOE_TYPE_EX_BOND, Some("IB"), Some(100.0)),
+                Some(20.0),
+            ),
+        )
+        .unwrap();
+
+        let stock = bonded_stock(&c, "SHP-1").unwrap();
+        let left: Vec<(f64, f64, f64)> = stock[0]
+            .items
+            .iter()
+            .map(|b| (b.warehoused_qty, b.cleared_qty, b.remaining_qty))
+            .collect();
+        assert_eq!(left, vec![(100.0, 100.0, 0.0), (50.0, 20.0, 30.0)]);
+        assert_eq!(shipment_status(&c), STATUS_PARTIALLY_CLEARED);
+
+        // The second line has 30 left, whatever the first line cleared.
+        let err = save(
+            &c,
+            twice(
+                boe("EB2", BOE_TYPE_EX_BOND, Some("IB"), Some(1.0)),
+                Some(10.0),
+            ),
+        )
+        .unwrap_err();
+        assert!(err.contains("exceeds the 0 left in bond"), "{err}");
+    }
+
+    #[test]
+    fn report_counts_only_ex_bond_clearances() {
+        let c = test_support::migrated_db();
+        test_support::add_shipment(&c, "SHP-1", "2024-02-01", 1000.0, "USD", "in-transit");
+        test_support::add_item(&c, "IT-1", "P1", "Bearing", None);
+        test_support::add_invoice(&c, "INV-1", "SHP-1");
+        c.execute_batch(
+            "INSERT INTO invoice_line_items (id, invoice_id, item_id, quantity, unit_price, duty_percent, sws_percent, igst_percent)
+                 VALUES ('L1', 'INV-1', 'IT-1', 100, 10, 10, 10, 18);",
+        )
+        .unwrap();
+        save(&c, boe("IB", BOE_TYPE_INTO_BOND, None, None)).unwrap();
+        save(&c, boe("EB1", BOE_TYPE_EX_BOND, Some("IB"), Some(40.0))).unwrap();
+
+        // Only the 40 cleared units: AV 32,000 and BCD 3,200, not the 80,000 assessed into bond on top.
+        let (rows, assessable, bcd): (i64, f64, f64) = c
+            .query_row(
+                "SELECT COUNT(*), SUM(assessable_value), SUM(bcd_amount) FROM report_view WHERE shipment_id = 'SHP-1'",
+                [],
+                |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)),
+            )
+            .unwrap();
+        assert_eq!((rows, assessable, bcd), (1, 32_000.0, 3_200.0));
+    }
+}
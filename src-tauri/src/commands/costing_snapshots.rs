@@ -0,0 +1,594 @@
+//! Frozen costing snapshots: freezing a shipment records its landed cost immutably; reopening needs a reason.
+
+use crate::commands::boe::map_row_to_saved_boe;
+use crate::commands::dashboard_cache;
+use crate::commands::landed_cost::{self, ItemLandedCost, LandedCostAllocation};
+use crate::db::DbState;
+use crate::duty_engine::round_paise;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tauri::State;
+
+/// Differences below half a paisa are rounding noise.
+const TOLERANCE: f64 = 0.005;
+
+/// Duty heads and exchange rate of one saved BOE calculation.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SnapshotBoe {
+    pub boe_calculation_id: String,
+    pub boe_id: Option<String>,
+    pub boe_type: String,
+    pub exchange_rate: f64,
+    pub assessable_value: f64,
+    pub bcd: f64,
+    pub sws: f64,
+    pub igst: f64,
+    pub aidc: f64,
+    pub add: f64,
+    pub safeguard: f64,
+    pub cess: f64,
+    pub interest: f64,
+    pub customs_duty_total: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SnapshotTotals {
+    pub assessable_value: f64,
+    pub duty_cost: f64,
+    pub expense_cost: f64,
+    pub landed_cost: f64,
+}
+
+/// What gets hashed and stored; built the same way for a freeze and for a live comparison.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CostingSnapshotContent {
+    pub shipment_id: String,
+    pub invoice_number: String,
+    pub invoice_currency: String,
+    pub boes: Vec<SnapshotBoe>,
+    pub items: Vec<ItemLandedCost>,
+    pub allocations: Vec<LandedCostAllocation>,
+    pub totals: SnapshotTotals,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CostingSnapshot {
+    pub id: String,
+    pub shipment_id: String,
+    pub version: i64,
+    pub content_hash: String,
+    pub total_landed_cost: f64,
+    pub frozen_by: Option<String>,
+    pub frozen_at: String,
+    /// Whether `content_hash` still matches the stored JSON.
+    pub hash_verified: bool,
+    /// Left out of listings.
+    #[serde(default)]
+    pub content: Option<CostingSnapshotContent>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SnapshotItemDiff {
+    pub invoice_line_item_id: String,
+    pub part_no: String,
+    /// `None` when the line was added after the freeze.
+    pub snapshot: Option<ItemLandedCost>,
+    /// `None` when the line no longer exists.
+    pub live: Option<ItemLandedCost>,
+    pub landed_cost_delta: f64,
+    pub landed_cost_per_unit_delta: Option<f64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SnapshotDiff {
+    pub snapshot_id: String,
+    pub snapshot_hash: String,
+    pub live_hash: String,
+    pub unchanged: bool,
+    pub items: Vec<SnapshotItemDiff>,
+    /// BOE calculations added, removed or re-assessed since the freeze.
+    pub boes_changed: Vec<String>,
+    pub snapshot_totals: SnapshotTotals,
+    pub live_totals: SnapshotTotals,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ShipmentReopening {
+    pub id: i64,
+    pub shipment_id: String,
+    pub snapshot_id: Option<String>,
+    pub reason: String,
+    pub reopened_by: Option<String>,
+    pub reopened_at: String,
+}
+
+pub(crate) fn is_frozen(conn: &Connection, shipment_id: &str) -> Result<bool, String> {
+    conn.query_row(
+        "SELECT is_frozen FROM shipments WHERE id = ?1",
+        params![shipment_id],
+        |r| r.get::<_, bool>(0),
+    )
+    .optional()
+    .map(|v| v.unwrap_or(false))
+    .map_err(|e| e.to_string())
+}
+
+/// Frozen shipments only change through [`reopen`], so the unfreeze carries a reason.
+pub(crate) fn ensure_not_frozen(conn: &Connection, shipment_id: &str) -> Result<(), String> {
+    if is_frozen(conn, shipment_id)? {
+        return Err(format!(
+            "Shipment {shipment_id} is frozen; reopen it with a reason before editing"
+        ));
+    }
+    Ok(())
+}
+
+fn content_hash(json: &str) -> String {
+    format!("{:x}", Sha256::digest(json.as_bytes()))
+}
+
+/// The shipment's costing as the live data stands now.
+pub fn build_content(
+    conn: &Connection,
+    shipment_id: &str,
+) -> Result<CostingSnapshotContent, String> {
+    let (invoice_number, invoice_currency): (String, String) = conn
+        .query_row(
+            "SELECT invoice_number, COALESCE(invoice_currency, '') FROM shipments WHERE id = ?1",
+            params![shipment_id],
+            |r| Ok((r.get(0)?, r.get(1)?)),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Shipment {shipment_id} not found"))?;
+
+    let mut stmt = conn
+        .prepare("SELECT * FROM boe_calculations WHERE shipment_id = ?1 ORDER BY id")
+        .map_err(|e| e.to_string())?;
+    let boes = stmt
+        .query_map(params![shipment_id], map_row_to_saved_boe)
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .map(|saved| {
+            let r = &saved.calculation_result;
+            SnapshotBoe {
+                boe_calculation_id: saved.id,
+                boe_id: saved.boe_id,
+                boe_type: saved.boe_type,
+                exchange_rate: saved.form_values.exchange_rate,
+                assessable_value: round_paise(
+                    r.calculated_items.iter().map(|i| i.assessable_value).sum(),
+                ),
+                bcd: r.bcd_total,
+                sws: r.sws_total,
+                igst: r.igst_total,
+                aidc: r.aidc_total,
+                add: r.add_total,
+                safeguard: r.safeguard_total,
+                cess: r.cess_total,
+                interest: r.interest,
+                customs_duty_total: r.customs_duty_total,
+            }
+        })
+        .collect();
+
+    let costing = landed_cost::compute_shipment(conn, shipment_id)?;
+    let totals = costing
+        .items
+        .iter()
+        .fold(SnapshotTotals::default(), |mut t, i| {
+            t.assessable_value += i.assessable_value;
+            t.duty_cost += i.duty_cost;
+            t.expense_cost += i.expense_cost;
+            t.landed_cost += i.landed_cost;
+            t
+        });
+    Ok(CostingSnapshotContent {
+        shipment_id: shipment_id.to_string(),
+        invoice_number,
+        invoice_currency,
+        boes,
+        items: costing.items,
+        allocations: costing.allocations,
+        totals: SnapshotTotals {
+            assessable_value: round_paise(totals.assessable_value),
+            duty_cost: round_paise(totals.duty_cost),
+            expense_cost: round_paise(totals.expense_cost),
+            landed_cost: round_paise(totals.landed_cost),
+        },
+    })
+}
+
+/// Marks the shipment frozen and writes the next snapshot version. Freezing an already frozen
+/// shipment returns its latest snapshot unchanged.
+pub fn freeze(
+    conn: &Connection,
+    shipment_id: &str,
+    frozen_by: Option<&str>,
+) -> Result<CostingSnapshot, String> {
+    if is_frozen(conn, shipment_id)? {
+        if let Some(latest) = latest_snapshot(conn, shipment_id)? {
+            return Ok(latest);
+        }
+    }
+    // Persisted allocations should match the snapshot the shipment is frozen with.
+    landed_cost::recompute_shipment(conn, shipment_id)?;
+    let content = build_content(conn, shipment_id)?;
+    let json = serde_json::to_string(&content).map_err(|e| e.to_string())?;
+    let hash = content_hash(&json);
+    let id = uuid::Uuid::new_v4().to_string();
+
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    let version: i64 = tx
+        .query_row(
+            "SELECT COALESCE(MAX(version), 0) + 1 FROM shipment_costing_snapshots WHERE shipment_id = ?1",
+            params![shipment_id],
+            |r| r.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+    tx.execute(
+        "INSERT INTO shipment_costing_snapshots (id, shipment_id, version, snapshot_json, content_hash, total_landed_cost, frozen_by)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![id, shipment_id, version, json, hash, content.totals.landed_cost, frozen_by],
+    )
+    .map_err(|e| e.to_string())?;
+    tx.execute(
+        "UPDATE shipments SET is_frozen = 1 WHERE id = ?1",
+        params![shipment_id],
+    )
+    .map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    load_snapshot(conn, &id, true)?.ok_or_else(|| "Snapshot not written".to_string())
+}
+
+/// Unfreezes the shipment, logging `reason` against the snapshot in force, and brings its
+/// persisted landed cost back in line with the live data.
+pub fn reopen(
+    conn: &Connection,
+    shipment_id: &str,
+    reason: &str,
+    reopened_by: Option<&str>,
+) -> Result<ShipmentReopening, String> {
+    let reason = reason.trim();
+    if reason.is_empty() {
+        return Err("A reason is required to reopen a frozen shipment".to_string());
+    }
+    if !is_frozen(conn, shipment_id)? {
+        return Err(format!("Shipment {shipment_id} is not frozen"));
+    }
+    let snapshot_id = latest_snapshot(conn, shipment_id)?.map(|s| s.id);
+
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    tx.execute(
+        "INSERT INTO shipment_reopenings (shipment_id, snapshot_id, reason, reopened_by) VALUES (?1, ?2, ?3, ?4)",
+        params![shipment_id, snapshot_id, reason, reopened_by],
+    )
+    .map_err(|e| e.to_string())?;
+    let id = tx.last_insert_rowid();
+    tx.execute(
+        "UPDATE shipments SET is_frozen = 0 WHERE id = ?1",
+        params![shipment_id],
+    )
+    .map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    landed_cost::recompute_after_change(conn, shipment_id);
+    conn.query_row(
+        "SELECT id, shipment_id, snapshot_id, reason, reopened_by, reopened_at FROM shipment_reopenings WHERE id = ?1",
+        params![id],
+        map_reopening,
+    )
+    .map_err(|e| e.to_string())
+}
+
+fn map_reopening(r: &rusqlite::Row) -> rusqlite::Result<ShipmentReopening> {
+    Ok(ShipmentReopening {
+        id: r.get(0)?,
+        shipment_id: r.get(1)?,
+        snapshot_id: r.get(2)?,
+        reason: r.get(3)?,
+        reopened_by: r.get(4)?,
+        reopened_at: r.get(5)?,
+    })
+}
+
+const SNAPSHOT_COLUMNS: &str =
+    "id, shipment_id, version, content_hash, total_landed_cost, frozen_by, frozen_at, snapshot_json";
+
+fn map_snapshot(r: &rusqlite::Row, with_content: bool) -> rusqlite::Result<CostingSnapshot> {
+    let json: String = r.get(7)?;
+    let hash: String = r.get(3)?;
+    Ok(CostingSnapshot {
+        id: r.get(0)?,
+        shipment_id: r.get(1)?,
+        version: r.get(2)?,
+        hash_verified: content_hash(&json) == hash,
+        content_hash: hash,
+        total_landed_cost: r.get(4)?,
+        frozen_by: r.get(5)?,
+        frozen_at: r.get(6)?,
+        content: if with_content {
+            serde_json::from_str(&json).ok()
+        } else {
+            None
+        },
+    })
+}
+
+fn load_snapshot(
+    conn: &Connection,
+    snapshot_id: &str,
+    with_content: bool,
+) -> Result<Option<CostingSnapshot>, String> {
+    conn.query_row(
+        &format!("SELECT {SNAPSHOT_COLUMNS} FROM shipment_costing_snapshots WHERE id = ?1"),
+        params![snapshot_id],
+        |r| map_snapshot(r, with_content),
+    )
+    .optional()
+    .map_err(|e| e.to_string())
+}
+
+fn latest_snapshot(
+    conn: &Connection,
+    shipment_id: &str,
+) -> Result<Option<CostingSnapshot>, String> {
+    conn.query_row(
+        &format!(
+            "SELECT {SNAPSHOT_COLUMNS} FROM shipment_costing_snapshots
+             WHERE shipment_id = ?1 ORDER BY version DESC LIMIT 1"
+        ),
+        params![shipment_id],
+        |r| map_snapshot(r, false),
+    )
+    .optional()
+    .map_err(|e| e.to_string())
+}
+
+/// Compares a snapshot with a fresh calculation of the same shipment.
+pub fn diff(conn: &Connection, snapshot_id: &str) -> Result<SnapshotDiff, String> {
+    let snapshot = load_snapshot(conn, snapshot_id, true)?
+        .ok_or_else(|| format!("Snapshot {snapshot_id} not found"))?;
+    let frozen = snapshot
+        .content
+        .ok_or_else(|| format!("Snapshot {snapshot_id} content is unreadable"))?;
+    let live = build_content(conn, &snapshot.shipment_id)?;
+    let live_hash = content_hash(&serde_json::to_string(&live).map_err(|e| e.to_string())?);
+
+    let mut items = Vec::new();
+    for before in &frozen.items {
+        let after = live
+            .items
+            .iter()
+            .find(|i| i.invoice_line_item_id == before.invoice_line_item_id);
+        let changed = match after {
+            Some(after) => {
+                (after.landed_cost - before.landed_cost).abs() >= TOLERANCE
+                    || (after.quantity - before.quantity).abs() >= TOLERANCE
+                    || after.landed_cost_per_unit.is_some() != before.landed_cost_per_unit.is_some()
+                    || matches!(
+                        (after.landed_cost_per_unit, before.landed_cost_per_unit),
+                        (Some(a), Some(b)) if (a - b).abs() >= TOLERANCE
+                    )
+            }
+            None => true,
+        };
+        if changed {
+            items.push(item_diff(match after {
+                Some(after) => ItemSides::Both(before, after),
+                None => ItemSides::Removed(before),
+            }));
+        }
+    }
+    for after in &live.items {
+        if !frozen
+            .items
+            .iter()
+            .any(|i| i.invoice_line_item_id == after.invoice_line_item_id)
+        {
+            items.push(item_diff(ItemSides::Added(after)));
+        }
+    }
+
+    let mut boes_changed: Vec<String> = frozen
+        .boes
+        .iter()
+        .filter(|b| !live.boes.contains(b))
+        .map(|b| b.boe_calculation_id.clone())
+        .collect();
+    boes_changed.extend(
+        live.boes
+            .iter()
+            .filter(|b| {
+                !frozen
+                    .boes
+                    .iter()
+                    .any(|f| f.boe_calculation_id == b.boe_calculation_id)
+            })
+            .map(|b| b.boe_calculation_id.clone()),
+    );
+
+    Ok(SnapshotDiff {
+        snapshot_id: snapshot.id,
+        unchanged: live_hash == snapshot.content_hash,
+        snapshot_hash: snapshot.content_hash,
+        live_hash,
+        items,
+        boes_changed,
+        snapshot_totals: frozen.totals,
+        live_totals: live.totals,
+    })
+}
+
+/// An invoice line as found in the snapshot, the live calculation, or both.
+enum ItemSides<'a> {
+    Both(&'a ItemLandedCost, &'a ItemLandedCost),
+    Removed(&'a ItemLandedCost),
+    Added(&'a ItemLandedCost),
+}
+
+fn item_diff(sides: ItemSides) -> SnapshotItemDiff {
+    let (either, before, after) = match sides {
+        ItemSides::Both(before, after) => (before, Some(before), Some(after)),
+        ItemSides::Removed(before) => (before, Some(before), None),
+        ItemSides::Added(after) => (after, None, Some(after)),
+    };
+    let landed = |i: Option<&ItemLandedCost>| i.map_or(0.0, |i| i.landed_cost);
+    SnapshotItemDiff {
+        invoice_line_item_id: either.invoice_line_item_id.clone(),
+        part_no: either.part_no.clone(),
+        landed_cost_delta: round_paise(landed(after) - landed(before)),
+        landed_cost_per_unit_delta: match (
+            after.and_then(|i| i.landed_cost_per_unit),
+            before.and_then(|i| i.landed_cost_per_unit),
+        ) {
+            (Some(a), Some(b)) => Some(round_paise(a - b)),
+            _ => None,
+        },
+        snapshot: before.cloned(),
+        live: after.cloned(),
+    }
+}
+
+#[tauri::command]
+pub fn list_costing_snapshots(
+    shipment_id: String,
+    state: State<DbState>,
+) -> Result<Vec<CostingSnapshot>, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {SNAPSHOT_COLUMNS} FROM shipment_costing_snapshots
+             WHERE shipment_id = ?1 ORDER BY version DESC"
+        ))
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![shipment_id], |r| map_snapshot(r, false))
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn get_costing_snapshot(
+    snapshot_id: String,
+    state: State<DbState>,
+) -> Result<CostingSnapshot, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    load_snapshot(&conn, &snapshot_id, true)?
+        .ok_or_else(|| format!("Snapshot {snapshot_id} not found"))
+}
+
+#[tauri::command]
+pub fn diff_costing_snapshot(
+    snapshot_id: String,
+    state: State<DbState>,
+) -> Result<SnapshotDiff, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    diff(&conn, &snapshot_id)
+}
+
+#[tauri::command]
+pub fn reopen_shipment(
+    shipment_id: String,
+    reason: String,
+    user_id: Option<String>,
+    state: State<DbState>,
+) -> Result<ShipmentReopening, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    let reopening = reopen(&conn, &shipment_id, &reason, user_id.as_deref())?;
+    let _ = dashboard_cache::invalidate_dashboard_metrics_cache(&conn);
+    Ok(reopening)
+}
+
+#[tauri::command]
+pub fn list_shipment_reopenings(
+    shipment_id: String,
+    state: State<DbState>,
+) -> Result<Vec<ShipmentReopening>, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, shipment_id, snapshot_id, reason, reopened_by, reopened_at
+             FROM shipment_reopenings WHERE shipment_id = ?1 ORDER BY id DESC",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![shipment_id], map_reopening)
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::test_support;
+
+    #[test]
+    fn freeze_snapshots_costs_and_diff_shows_later_edits() {
+        let conn = test_support::migrated_db();
+        test_support::add_shipment(&conn, "SHP-1", "2024-03-01", 1000.0, "USD", "delivered");
+        test_support::add_item(&conn, "IT-A", "A", "Bearing", None);
+        test_support::add_invoice(&conn, "INVC-1", "SHP-1");
+        conn.execute_batch(
+            "UPDATE shipments SET incoterm = 'CIF' WHERE id = 'SHP-1';
+             INSERT INTO invoice_line_items (id, invoice_id, item_id, quantity, unit_price, duty_percent, sws_percent, igst_percent)
+                VALUES ('L-A', 'INVC-1', 'IT-A', 10, 100, 10, 10, 18);
+             INSERT INTO service_providers (id, name) VALUES ('SP-1', 'Forwarder');
+             INSERT INTO expense_types (id, name) VALUES ('ET-CHA', 'CHA Test');
+             INSERT INTO expense_invoices (id, shipment_id, service_provider_id, invoice_no, invoice_date, total_amount)
+                VALUES ('EI-1', 'SHP-1', 'SP-1', 'F-1', '2024-03-05', 0);
+             INSERT INTO expenses (id, expense_invoice_id, shipment_id, service_provider_id, invoice_no, invoice_date, expense_type_id, amount)
+                VALUES ('E-1', 'EI-1', 'SHP-1', 'SP-1', 'F-1', '2024-03-05', 'ET-CHA', 500);",
+        )
+        .unwrap();
+
+        assert!(reopen(&conn, "SHP-1", "typo", None).is_err());
+        let snap = freeze(&conn, "SHP-1", Some("u1")).unwrap();
+        assert_eq!((snap.version, snap.hash_verified), (1, true));
+        // No BOE yet, so the landed cost is the expense alone.
+        assert_eq!(snap.total_landed_cost, 500.0);
+        assert!(is_frozen(&conn, "SHP-1").unwrap());
+        assert!(ensure_not_frozen(&conn, "SHP-1").is_err());
+        // Freezing again is a no-op.
+        assert_eq!(freeze(&conn, "SHP-1", None).unwrap().id, snap.id);
+        assert!(diff(&conn, &snap.id).unwrap().unchanged);
+
+        // A late expense edit moves the live figure; the snapshot keeps the frozen one.
+        conn.execute("UPDATE expenses SET amount = 800 WHERE id = 'E-1'", [])
+            .unwrap();
+        let d = diff(&conn, &snap.id).unwrap();
+        assert!(!d.unchanged);
+        assert_eq!(d.items.len(), 1);
+        assert_eq!(d.items[0].landed_cost_delta, 300.0);
+        assert_eq!(d.items[0].landed_cost_per_unit_delta, Some(30.0));
+        assert_eq!(d.snapshot_totals.landed_cost, 500.0);
+
+        // Snapshots cannot be rewritten.
+        assert!(conn
+            .execute(
+                "UPDATE shipment_costing_snapshots SET total_landed_cost = 0",
+                []
+            )
+            .is_err());
+
+        assert!(reopen(&conn, "SHP-1", "  ", None).is_err());
+        let reopening = reopen(&conn, "SHP-1", "Late CHA invoice", Some("u2")).unwrap();
+        assert_eq!(reopening.snapshot_id.as_deref(), Some(snap.id.as_str()));
+        assert!(ensure_not_frozen(&conn, "SHP-1").is_ok());
+        let again = freeze(&conn, "SHP-1", None).unwrap();
+        assert_eq!((again.version, again.total_landed_cost), (2, 800.0));
+    }
+}
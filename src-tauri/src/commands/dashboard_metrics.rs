@@ -4,7 +4,9 @@
 use crate::commands::dashboard_cache::{
     read_cached_metrics_json, write_metrics_cache,
 };
+use crate::commands::eta_slippage;
 use crate::commands::exception_reliability::log_integrity_issue;
+use crate::commands::exchange_rates;
 use crate::commands::utils::dashboard_activity_checksum;
 use crate::db::DbState;
 use chrono::Utc;
@@ -168,6 +170,9 @@ pub struct DashboardMetricsResponse {
     pub delivered_shipments: i64,
     pub reconciled_boes: i64,
     pub total_invoice_value: f64,
+    /// Invoice value converted at the customs-notified rate for each shipment's BE date.
+    #[serde(default)]
+    pub total_invoice_value_inr: f64,
     pub avg_transit_days: Option<f64>,
     pub expense_total: f64,
     pub duty_total: f64,
@@ -661,20 +666,49 @@ pub fn get_dashboard_metrics(
         &p_ship,
     )?;
 
+    // INR conversion: notified rate on the BE date, else the rate typed on the saved BOE, else 1:1
+    let notified_rate = exchange_rates::shipment_inr_rate_sql();
+    let invoice_inr = format!(
+        "(s.invoice_value * COALESCE({notified_rate},
+            (SELECT CAST(json_extract(bc.form_values_json, '$.exchangeRate') AS REAL)
+             FROM boe_calculations bc WHERE bc.shipment_id = s.id LIMIT 1),
+            1.0))"
+    );
+    let total_invoice_value_inr = query_f64(
+        &conn,
+        &format!("SELECT COALESCE(SUM({invoice_inr}), 0) FROM shipments s WHERE {w}"),
+        &p_ship,
+    )?;
+    let shipments_without_rate = query_i64(
+        &conn,
+        &format!("SELECT COUNT(*) FROM shipments s WHERE {w} AND {notified_rate} IS NULL"),
+        &p_ship,
+    )?;
+
     let reconciled_boes = query_i64(
         &conn,
         "SELECT COUNT(*) FROM boe_calculations WHERE status = 'Reconciled'",
         &[],
     )?;
 
-    // Align duty with consolidated report: BCD + SWS + IGST from `report_view`, scoped to shipments filter.
+    // Align duty with consolidated report: every duty head from `report_view`, scoped to shipments filter.
     let duty_sql = format!(
-        "SELECT COALESCE(SUM(rv.bcd_amount + rv.sws_amount + rv.igst_amount), 0)
+        "SELECT COALESCE(SUM(rv.bcd_amount + rv.sws_amount + rv.igst_amount
+                + rv.aidc_amount + rv.add_amount + rv.safeguard_amount + rv.cess_amount), 0)
          FROM report_view rv
          JOIN shipments s ON s.invoice_number = rv.invoice_no AND s.supplier_id = rv.supplier_id
          WHERE {w}"
     );
-    let duty_total = query_f64(&conn, &duty_sql, &p_ship)?;
+    // Drawback / RoDTEP credits pay part of that duty, so only the cash outlay counts.
+    let credit_sql = format!(
+        "SELECT COALESCE(SUM(u.amount), 0)
+         FROM duty_credit_utilisations u
+         JOIN boe_calculations bc ON bc.id = u.saved_boe_id
+         JOIN shipments s ON s.id = bc.shipment_id
+         WHERE {w}"
+    );
+    let duty_total =
+        query_f64(&conn, &duty_sql, &p_ship)? - query_f64(&conn, &credit_sql, &p_ship)?;
 
     let expense_sql = format!(
         "SELECT COALESCE(SUM(e.total_amount), 0) FROM expenses e
@@ -710,7 +744,7 @@ pub fn get_dashboard_metrics(
                     COALESCE(SUM(s.invoice_value), 0) AS val,
                     COALESCE(SUM(
                         max(0.0,
-                            s.invoice_value * 0.2 - COALESCE(
+                            {invoice_inr} * 0.2 - COALESCE(
                                 (SELECT CAST(json_extract(bc.calculation_result_json, '$.customsDutyTotal') AS REAL)
                                  FROM boe_calculations bc
                                  WHERE bc.shipment_id = s.id AND bc.status = 'Reconciled' LIMIT 1),
@@ -722,7 +756,8 @@ pub fn get_dashboard_metrics(
              WHERE {w} AND strftime('%Y-%m', s.invoice_date) IS NOT NULL
              GROUP BY period
              ORDER BY period",
-            w = w
+            w = w,
+            invoice_inr = invoice_inr
         ))
         .map_err(|e| e.to_string())?;
     let rows = stmt
@@ -744,11 +779,24 @@ pub fn get_dashboard_metrics(
         ));
         monthly_summary.truncate(2000);
     }
+    if shipments_without_rate > 0 {
+        safety_warnings.push(format!(
+            "{} shipment(s) have no customs-notified exchange rate; INR values use the BOE rate or 1:1",
+            shipments_without_rate
+        ));
+    }
+    let rate_mismatches = exchange_rates::exchange_rate_mismatches(&conn)?;
+    if !rate_mismatches.is_empty() {
+        safety_warnings.push(format!(
+            "{} saved BOE(s) used an exchange rate different from the customs notification",
+            rate_mismatches.len()
+        ));
+    }
 
     // Simplified duty savings estimate (matches legacy dashboard intent): max(0, 0.2*invoice - duty) per reconciled BOE shipment in scope
     let savings_sql = format!(
         "SELECT COALESCE(SUM(
-            max(0.0, s.invoice_value * 0.2 - COALESCE(
+            max(0.0, {invoice_inr} * 0.2 - COALESCE(
                 (SELECT CAST(json_extract(bc.calculation_result_json, '$.customsDutyTotal') AS REAL) FROM boe_calculations bc WHERE bc.shipment_id = s.id AND bc.status = 'Reconciled' LIMIT 1),
                 0.0
             ))
@@ -756,10 +804,12 @@ pub fn get_dashboard_metrics(
     );
     let total_duty_savings_estimate = query_f64(&conn, &savings_sql, &p_ship).unwrap_or(0.0);
 
-    let landed_cost_total = total_invoice_value + duty_total + expense_total;
+    let landed_cost_total = total_invoice_value_inr + duty_total + expense_total;
 
     let mut exceptions = Vec::new();
 
+    let overdue_basis = eta_slippage::overdue_basis(&conn);
+    let overdue_cond = eta_slippage::overdue_eta_condition(&conn);
     let overdue = query_i64(
         &conn,
         &format!(
@@ -767,7 +817,7 @@ pub fn get_dashboard_metrics(
              AND s.eta IS NOT NULL AND TRIM(s.eta) != ''
              AND s.status IS NOT NULL AND LOWER(s.status) NOT IN ('delivered', 'completed')
              AND length(s.eta) >= 10 AND s.eta GLOB '[0-9][0-9][0-9][0-9]-[0-9][0-9]-[0-9][0-9]'
-             AND date(s.eta) < date('now')",
+             AND {overdue_cond}",
             w = w
         ),
         &p_ship,
@@ -783,7 +833,7 @@ pub fn get_dashboard_metrics(
              AND s.eta IS NOT NULL AND TRIM(s.eta) != ''
              AND s.status IS NOT NULL AND LOWER(s.status) NOT IN ('delivered', 'completed')
              AND length(s.eta) >= 10 AND s.eta GLOB '[0-9][0-9][0-9][0-9]-[0-9][0-9]-[0-9][0-9]'
-             AND date(s.eta) < date('now') ORDER BY s.eta ASC LIMIT 25",
+             AND {overdue_cond} ORDER BY s.eta ASC LIMIT 25",
             w = w
         );
         let sample_shipment_ids =
@@ -792,7 +842,11 @@ pub fn get_dashboard_metrics(
         exceptions.push(DashboardException {
             kind: "overdue_eta".into(),
             severity: "warning".into(),
-            message: "Shipments have ETA in the past but are not delivered.".into(),
+            message: if overdue_basis == eta_slippage::BASIS_PREDICTED {
+                "Shipments have a predicted ETA in the past but are not delivered.".into()
+            } else {
+                "Shipments have ETA in the past but are not delivered.".into()
+            },
             count: overdue,
             exception_type: "OVERDUE_ETA".into(),
             entity_type: "aggregate".into(),
@@ -843,6 +897,75 @@ pub fn get_dashboard_metrics(
         });
     }
 
+    // Unpaid BOE duty: free payment window closing soon, or already past with interest accruing.
+    let charge_settings = crate::commands::boe_charges::load_settings(&conn);
+    let unpaid_boe_sql = format!(
+        "FROM boe_details bd
+         JOIN boe_calculations bc ON bc.boe_id = bd.id
+         JOIN shipments s ON s.id = bc.shipment_id
+         WHERE {w}
+         AND COALESCE(bc.boe_type, 'HOME_CONSUMPTION') != 'INTO_BOND'
+         AND (bd.payment_date IS NULL OR TRIM(bd.payment_date) = '')
+         AND bd.duty_amount > 0",
+        w = w
+    );
+    let due_date = format!(
+        "date(bd.be_date, '+{} days')",
+        charge_settings.payment_free_days
+    );
+    for (kind, exception_type, severity, window, message) in [
+        (
+            "duty_payment_due",
+            "DUTY_PAYMENT_DUE",
+            "warning",
+            format!(
+                "{due_date} BETWEEN date('now') AND date('now', '+{} days')",
+                charge_settings.alert_days
+            ),
+            "BOEs with unpaid duty whose interest-free payment window is about to close.",
+        ),
+        (
+            "duty_payment_overdue",
+            "DUTY_PAYMENT_OVERDUE",
+            "warning",
+            format!("{due_date} < date('now')"),
+            "BOEs with unpaid duty past the free payment window; late-payment interest is accruing.",
+        ),
+    ] {
+        let count = query_i64(
+            &conn,
+            &format!("SELECT COUNT(DISTINCT bd.id) {unpaid_boe_sql} AND {window}"),
+            &p_ship,
+        )
+        .unwrap_or(0);
+        if count == 0 {
+            continue;
+        }
+        let mut fp = serde_json::Map::new();
+        fp.insert(kind.into(), json!("true"));
+        let nav_target = "/boe".to_string();
+        let navigation_url = shipment_exception_navigation_url(&nav_target, &fp);
+        let sample_sql = format!(
+            "SELECT DISTINCT s.id {unpaid_boe_sql} AND {window} ORDER BY bd.be_date ASC LIMIT 25"
+        );
+        let sample_shipment_ids =
+            crate::commands::exception_workflow::query_shipment_ids(&conn, &sample_sql, &p_ship)
+                .unwrap_or_default();
+        exceptions.push(DashboardException {
+            kind: kind.into(),
+            severity: severity.into(),
+            message: message.into(),
+            count,
+            exception_type: exception_type.into(),
+            entity_type: "aggregate".into(),
+            navigation_target: nav_target,
+            navigation_url,
+            filter_parameters: fp,
+            entity_id: None,
+            sample_shipment_ids,
+        });
+    }
+
     let no_exp = query_i64(
         &conn,
         &format!(
@@ -919,6 +1042,7 @@ pub fn get_dashboard_metrics(
         delivered_shipments,
         reconciled_boes,
         total_invoice_value,
+        total_invoice_value_inr,
         avg_transit_days,
         expense_total,
         duty_total,
@@ -0,0 +1,976 @@
+//! Demurrage and detention: free-time terms, clocks from arrival / gate-out milestones, daily accruals and expiry cases.
+
+use crate::commands::dashboard_cache;
+use crate::commands::exception_workflow;
+use crate::commands::shipment_events::{ARRIVED_AT_PORT, DELIVERED, EMPTY_RETURNED, GATE_OUT};
+use crate::commands::tariff;
+use crate::db::DbState;
+use crate::duty_engine::round_paise;
+use chrono::NaiveDate;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+pub const DEMURRAGE: &str = "DEMURRAGE";
+pub const DETENTION: &str = "DETENTION";
+
+pub const FREE_TIME_EXPIRING: &str = "FREE_TIME_EXPIRING";
+
+const WARNING_DAYS_KEY: &str = "free_time_warning_days";
+const DEFAULT_WARNING_DAYS: i64 = 2;
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct FreeTimeRateTier {
+    /// `DEMURRAGE` or `DETENTION`.
+    pub charge_type: String,
+    /// First chargeable day after free time (1-based).
+    pub from_day: i64,
+    /// Last day of the tier, inclusive; `None` for the open-ended last tier.
+    pub to_day: Option<i64>,
+    /// Per container per day.
+    pub rate_per_day: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct FreeTimeTerms {
+    pub id: String,
+    pub name: String,
+    pub shipping_line: Option<String>,
+    pub service_provider_id: Option<String>,
+    pub port_free_days: i64,
+    pub detention_free_days: i64,
+    pub currency: String,
+    pub is_active: bool,
+    pub notes: Option<String>,
+    pub tiers: Vec<FreeTimeRateTier>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SaveFreeTimeTermsPayload {
+    /// Omit to create new terms.
+    pub id: Option<String>,
+    pub name: String,
+    pub shipping_line: Option<String>,
+    pub service_provider_id: Option<String>,
+    pub port_free_days: i64,
+    pub detention_free_days: i64,
+    pub currency: Option<String>,
+    pub is_active: Option<bool>,
+    pub notes: Option<String>,
+    pub tiers: Vec<FreeTimeRateTier>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TierCharge {
+    pub from_day: i64,
+    pub to_day: Option<i64>,
+    pub days: i64,
+    pub rate_per_day: f64,
+    /// Across all containers.
+    pub amount: f64,
+}
+
+/// One free-time clock: port storage (demurrage) or container use (detention).
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct FreeTimeClock {
+    pub charge_type: String,
+    /// Arrival date the clock runs from; projected from the ETA until arrival is recorded.
+    pub clock_start: Option<String>,
+    /// `MILESTONE` or `ETA`.
+    pub clock_start_source: Option<String>,
+    /// Gate-out (demurrage) or empty return (detention); `None` while the clock runs.
+    pub clock_end: Option<String>,
+    pub running: bool,
+    pub free_days: i64,
+    /// Last free day.
+    pub free_time_ends: Option<String>,
+    pub days_used: i64,
+    pub days_remaining: i64,
+    pub chargeable_days: i64,
+    pub tiers: Vec<TierCharge>,
+    pub accrued_amount: f64,
+    /// What one more day would add, across all containers.
+    pub next_day_charge: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ShipmentFreeTime {
+    pub shipment_id: String,
+    pub terms_id: String,
+    pub terms_name: String,
+    pub currency: String,
+    pub container_numbers: Vec<String>,
+    /// Containers charged for; at least one.
+    pub containers: i64,
+    pub as_of: String,
+    pub clocks: Vec<FreeTimeClock>,
+    pub total_accrued: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DemurrageAccrual {
+    pub shipment_id: String,
+    pub charge_type: String,
+    pub accrual_date: String,
+    pub containers: i64,
+    pub days_used: i64,
+    pub free_days: i64,
+    pub chargeable_days: i64,
+    pub accrued_amount: f64,
+    pub next_day_charge: f64,
+    pub currency: String,
+}
+
+/// Container numbers in `Shipment.container_number`, which may list several.
+pub fn parse_container_numbers(raw: Option<&str>) -> Vec<String> {
+    let mut out: Vec<String> = Vec::new();
+    for c in raw
+        .unwrap_or_default()
+        .split(|ch: char| ch == ',' || ch == ';' || ch == '/' || ch.is_whitespace())
+        .map(|c| c.trim().to_uppercase())
+        .filter(|c| !c.is_empty())
+    {
+        if !out.contains(&c) {
+            out.push(c);
+        }
+    }
+    out
+}
+
+fn validate_tiers(tiers: &mut [FreeTimeRateTier]) -> Result<(), String> {
+    for charge_type in [DEMURRAGE, DETENTION] {
+        let mut next_day = 1;
+        let mut sorted: Vec<&FreeTimeRateTier> = tiers
+            .iter()
+            .filter(|t| t.charge_type == charge_type)
+            .collect();
+        sorted.sort_by_key(|t| t.from_day);
+        for (i, tier) in sorted.iter().enumerate() {
+            if tier.from_day != next_day {
+                return Err(format!(
+                    "{charge_type} tiers must be contiguous; expected one starting at day {next_day}"
+                ));
+            }
+            if tier.rate_per_day.is_nan() || tier.rate_per_day < 0.0 {
+                return Err("Rates cannot be negative".to_string());
+            }
+            match tier.to_day {
+                Some(to) if to < tier.from_day => {
+                    return Err(format!(
+                        "{charge_type} tier starting at day {} ends before it starts",
+                        tier.from_day
+                    ));
+                }
+                Some(to) => next_day = to + 1,
+                None if i + 1 < sorted.len() => {
+                    return Err(format!(
+                        "Only the last {charge_type} tier can be open-ended"
+                    ));
+                }
+                None => {}
+            }
+        }
+    }
+    if let Some(t) = tiers
+        .iter()
+        .find(|t| t.charge_type != DEMURRAGE && t.charge_type != DETENTION)
+    {
+        return Err(format!("Unknown charge type '{}'", t.charge_type));
+    }
+    tiers.sort_by(|a, b| (&a.charge_type, a.from_day).cmp(&(&b.charge_type, b.from_day)));
+    Ok(())
+}
+
+/// Splits `chargeable_days` across the tiers of one charge; days past a closed last tier are
+/// billed at its rate.
+pub fn tier_charges(
+    tiers: &[FreeTimeRateTier],
+    charge_type: &str,
+    chargeable_days: i64,
+    containers: i64,
+) -> Vec<TierCharge> {
+    let of_type: Vec<&FreeTimeRateTier> = tiers
+        .iter()
+        .filter(|t| t.charge_type == charge_type)
+        .collect();
+    let last = of_type.len().saturating_sub(1);
+    of_type
+        .iter()
+        .enumerate()
+        .filter_map(|(i, t)| {
+            let to_day = if i == last { None } else { t.to_day };
+            let end = to_day.map_or(chargeable_days, |to| to.min(chargeable_days));
+            let days = end - t.from_day + 1;
+            (days > 0).then(|| TierCharge {
+                from_day: t.from_day,
+                to_day,
+                days,
+                rate_per_day: t.rate_per_day,
+                amount: round_paise(t.rate_per_day * days as f64 * containers as f64),
+            })
+        })
+        .collect()
+}
+
+fn load_tiers(conn: &Connection, terms_id: &str) -> rusqlite::Result<Vec<FreeTimeRateTier>> {
+    let mut stmt = conn.prepare(
+        "SELECT charge_type, from_day, to_day, rate_per_day FROM free_time_rate_tiers
+         WHERE terms_id = ?1 ORDER BY charge_type, from_day",
+    )?;
+    let rows = stmt.query_map(params![terms_id], |r| {
+        Ok(FreeTimeRateTier {
+            charge_type: r.get(0)?,
+            from_day: r.get(1)?,
+            to_day: r.get(2)?,
+            rate_per_day: r.get(3)?,
+        })
+    })?;
+    rows.collect()
+}
+
+const TERMS_COLUMNS: &str = "id, name, shipping_line, service_provider_id, port_free_days, detention_free_days, currency, is_active, notes";
+
+fn map_terms(r: &rusqlite::Row) -> rusqlite::Result<FreeTimeTerms> {
+    Ok(FreeTimeTerms {
+        id: r.get(0)?,
+        name: r.get(1)?,
+        shipping_line: r.get(2)?,
+        service_provider_id: r.get(3)?,
+        port_free_days: r.get(4)?,
+        detention_free_days: r.get(5)?,
+        currency: r.get(6)?,
+        is_active: r.get::<_, i64>(7)? != 0,
+        notes: r.get(8)?,
+        tiers: Vec::new(),
+    })
+}
+
+pub fn load_terms(conn: &Connection, id: &str) -> Result<Option<FreeTimeTerms>, String> {
+    let terms = conn
+        .query_row(
+            &format!("SELECT {TERMS_COLUMNS} FROM free_time_terms WHERE id = ?1"),
+            params![id],
+            map_terms,
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+    let Some(mut terms) = terms else {
+        return Ok(None);
+    };
+    terms.tiers = load_tiers(conn, id).map_err(|e| e.to_string())?;
+    Ok(Some(terms))
+}
+
+pub fn save_terms(
+    conn: &Connection,
+    mut payload: SaveFreeTimeTermsPayload,
+) -> Result<FreeTimeTerms, String> {
+    let name = payload.name.trim().to_string();
+    if name.is_empty() {
+        return Err("Free-time terms need a name".to_string());
+    }
+    if payload.port_free_days < 0 || payload.detention_free_days < 0 {
+        return Err("Free days cannot be negative".to_string());
+    }
+    for t in payload.tiers.iter_mut() {
+        t.charge_type = t.charge_type.trim().to_uppercase();
+    }
+    validate_tiers(&mut payload.tiers)?;
+    let id = payload
+        .id
+        .filter(|id| !id.trim().is_empty())
+        .unwrap_or_else(|| format!("FTT-{}", uuid::Uuid::new_v4()));
+    let currency = payload
+        .currency
+        .map(|c| c.trim().to_uppercase())
+        .filter(|c| !c.is_empty())
+        .unwrap_or_else(|| "INR".to_string());
+
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    tx.execute(
+        "INSERT INTO free_time_terms (id, name, shipping_line, service_provider_id, port_free_days, detention_free_days, currency, is_active, notes)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+         ON CONFLICT (id) DO UPDATE SET
+            name = excluded.name,
+            shipping_line = excluded.shipping_line,
+            service_provider_id = excluded.service_provider_id,
+            port_free_days = excluded.port_free_days,
+            detention_free_days = excluded.detention_free_days,
+            currency = excluded.currency,
+            is_active = excluded.is_active,
+            notes = excluded.notes,
+            updated_at = strftime('%Y-%m-%d %H:%M:%S', 'now', 'localtime')",
+        params![
+            id,
+            name,
+            payload.shipping_line.filter(|s| !s.trim().is_empty()),
+            payload.service_provider_id.filter(|s| !s.trim().is_empty()),
+            payload.port_free_days,
+            payload.detention_free_days,
+            currency,
+            payload.is_active.unwrap_or(true),
+            payload.notes,
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    tx.execute(
+        "DELETE FROM free_time_rate_tiers WHERE terms_id = ?1",
+        params![id],
+    )
+    .map_err(|e| e.to_string())?;
+    for t in &payload.tiers {
+        tx.execute(
+            "INSERT INTO free_time_rate_tiers (terms_id, charge_type, from_day, to_day, rate_per_day)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![id, t.charge_type, t.from_day, t.to_day, t.rate_per_day],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    tx.commit().map_err(|e| e.to_string())?;
+    load_terms(conn, &id)?.ok_or_else(|| format!("Free-time terms {id} not found"))
+}
+
+fn parse_date(raw: &str) -> Option<NaiveDate> {
+    let iso = tariff::normalize_date(raw.get(..10).unwrap_or(raw))?;
+    NaiveDate::parse_from_str(&iso, "%Y-%m-%d").ok()
+}
+
+fn fmt_date(d: NaiveDate) -> String {
+    d.format("%Y-%m-%d").to_string()
+}
+
+fn milestone_date(
+    conn: &Connection,
+    shipment_id: &str,
+    event_type: &str,
+) -> Result<Option<NaiveDate>, String> {
+    let at: Option<String> = conn
+        .query_row(
+            "SELECT occurred_at FROM shipment_events WHERE shipment_id = ?1 AND event_type = ?2",
+            params![shipment_id, event_type],
+            |r| r.get(0),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+    Ok(at.as_deref().and_then(parse_date))
+}
+
+fn clock(
+    charge_type: &str,
+    start: Option<(NaiveDate, &str)>,
+    end: Option<NaiveDate>,
+    today: NaiveDate,
+    free_days: i64,
+    tiers: &[FreeTimeRateTier],
+    containers: i64,
+) -> FreeTimeClock {
+    let started = start.filter(|(d, _)| *d <= today);
+    let days_used = started.map_or(0, |(s, _)| {
+        let until = end.unwrap_or(today).min(today);
+        ((until - s).num_days() + 1).max(0)
+    });
+    let chargeable_days = (days_used - free_days).max(0);
+    let charges = tier_charges(tiers, charge_type, chargeable_days, containers);
+    let accrued_amount = round_paise(charges.iter().map(|c| c.amount).sum());
+    let running = started.is_some() && end.is_none();
+    let next_day_charge = if running && days_used + 1 > free_days {
+        let next = tier_charges(tiers, charge_type, chargeable_days + 1, containers);
+        round_paise(next.iter().map(|c| c.amount).sum::<f64>() - accrued_amount)
+    } else {
+        0.0
+    };
+    FreeTimeClock {
+        charge_type: charge_type.to_string(),
+        clock_start: start.map(|(d, _)| fmt_date(d)),
+        clock_start_source: start.map(|(_, source)| source.to_string()),
+        clock_end: end.map(fmt_date),
+        running,
+        free_days,
+        free_time_ends: start.map(|(d, _)| fmt_date(d + chrono::Duration::days(free_days - 1))),
+        days_used,
+        days_remaining: (free_days - days_used).max(0),
+        chargeable_days,
+        tiers: charges,
+        accrued_amount,
+        next_day_charge,
+    }
+}
+
+/// Free-time position of a shipment on `today`, or `None` if it has no terms assigned.
+/// Clocks start at the arrival milestone (or the ETA until arrival is recorded); demurrage stops at
+/// gate-out (or delivery), detention at the empty return.
+pub fn compute(
+    conn: &Connection,
+    shipment_id: &str,
+    today: NaiveDate,
+) -> Result<Option<ShipmentFreeTime>, String> {
+    let row: Option<(String, Option<String>, Option<String>)> = conn
+        .query_row(
+            "SELECT sft.terms_id, s.container_number, s.eta
+             FROM shipment_free_time sft JOIN shipments s ON s.id = sft.shipment_id
+             WHERE sft.shipment_id = ?1",
+            params![shipment_id],
+            |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+    let Some((terms_id, container_number, eta)) = row else {
+        return Ok(None);
+    };
+    let terms = load_terms(conn, &terms_id)?
+        .ok_or_else(|| format!("Free-time terms {terms_id} not found"))?;
+    let container_numbers = parse_container_numbers(container_number.as_deref());
+    let containers = (container_numbers.len() as i64).max(1);
+
+    let start = match milestone_date(conn, shipment_id, ARRIVED_AT_PORT)? {
+        Some(d) => Some((d, "MILESTONE")),
+        None => eta.as_deref().and_then(parse_date).map(|d| (d, "ETA")),
+    };
+    let gate_out = match milestone_date(conn, shipment_id, GATE_OUT)? {
+        Some(d) => Some(d),
+        None => milestone_date(conn, shipment_id, DELIVERED)?,
+    };
+    let empty_returned = milestone_date(conn, shipment_id, EMPTY_RETURNED)?;
+
+    let clocks = vec![
+        clock(
+            DEMURRAGE,
+            start,
+            gate_out,
+            today,
+            terms.port_free_days,
+            &terms.tiers,
+            containers,
+        ),
+        clock(
+            DETENTION,
+            start,
+            empty_returned,
+            today,
+            terms.detention_free_days,
+            &terms.tiers,
+            containers,
+        ),
+    ];
+    let total_accrued = round_paise(clocks.iter().map(|c| c.accrued_amount).sum());
+    Ok(Some(ShipmentFreeTime {
+        shipment_id: shipment_id.to_string(),
+        terms_id,
+        terms_name: terms.name,
+        currency: terms.currency,
+        container_numbers,
+        containers,
+        as_of: fmt_date(today),
+        clocks,
+        total_accrued,
+    }))
+}
+
+fn warning_days(conn: &Connection) -> i64 {
+    conn.query_row(
+        "SELECT value FROM app_metadata WHERE key = ?1",
+        params![WARNING_DAYS_KEY],
+        |r| r.get::<_, String>(0),
+    )
+    .ok()
+    .and_then(|v| v.trim().parse::<i64>().ok())
+    .filter(|d| *d >= 0)
+    .unwrap_or(DEFAULT_WARNING_DAYS)
+}
+
+/// Opens a `FREE_TIME_EXPIRING` case while a running clock is within `warning_days` of its last
+/// free day (or past it), and resolves it once every clock has stopped or has room again.
+fn sync_expiry_case(
+    conn: &Connection,
+    free_time: &ShipmentFreeTime,
+    warning_days: i64,
+) -> Result<Option<String>, String> {
+    let at_risk: Vec<&FreeTimeClock> = free_time
+        .clocks
+        .iter()
+        .filter(|c| c.running && c.clock_start_source.as_deref() == Some("MILESTONE"))
+        .filter(|c| c.days_remaining <= warning_days)
+        .collect();
+    if at_risk.is_empty() {
+        exception_workflow::auto_resolve_case_for_entity(
+            conn,
+            FREE_TIME_EXPIRING,
+            &free_time.shipment_id,
+        )?;
+        return Ok(None);
+    }
+    let charging = at_risk.iter().any(|c| c.chargeable_days > 0);
+    let details = serde_json::json!({
+        "exceptionType": FREE_TIME_EXPIRING,
+        "currency": free_time.currency,
+        "containers": free_time.containers,
+        "charges": at_risk.iter().map(|c| serde_json::json!({
+            "chargeType": c.charge_type,
+            "freeTimeEnds": c.free_time_ends,
+            "daysRemaining": c.days_remaining,
+            "accruedAmount": c.accrued_amount,
+            "nextDayCharge": c.next_day_charge,
+        })).collect::<Vec<_>>(),
+    });
+    let case_id = exception_workflow::open_case_for_entity(
+        conn,
+        FREE_TIME_EXPIRING,
+        &free_time.shipment_id,
+        if charging { "CRITICAL" } else { "HIGH" },
+        &details.to_string(),
+    )?;
+    // A case opened as a warning escalates once charges actually start.
+    if let (Some(id), true) = (&case_id, charging) {
+        conn.execute(
+            "UPDATE exception_cases SET priority = 'CRITICAL', updated_at = strftime('%Y-%m-%d %H:%M:%S', 'now', 'localtime')
+             WHERE id = ?1 AND priority != 'CRITICAL'",
+            params![id],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    Ok(case_id)
+}
+
+/// Records today's accrual for each started clock of the shipment (a stopped clock keeps one row
+/// dated the day it stopped) and syncs its expiry case.
+pub fn accrue_shipment(
+    conn: &Connection,
+    shipment_id: &str,
+    today: NaiveDate,
+) -> Result<Option<ShipmentFreeTime>, String> {
+    let Some(free_time) = compute(conn, shipment_id, today)? else {
+        exception_workflow::auto_resolve_case_for_entity(conn, FREE_TIME_EXPIRING, shipment_id)?;
+        return Ok(None);
+    };
+    for c in free_time
+        .clocks
+        .iter()
+        .filter(|c| c.days_used > 0 && c.clock_start_source.as_deref() == Some("MILESTONE"))
+    {
+        let accrual_date = c
+            .clock_end
+            .clone()
+            .unwrap_or_else(|| free_time.as_of.clone());
+        conn.execute(
+            "INSERT INTO demurrage_accruals (shipment_id, charge_type, accrual_date, containers, days_used, free_days, chargeable_days, accrued_amount, next_day_charge, currency)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)
+             ON CONFLICT (shipment_id, charge_type, accrual_date) DO UPDATE SET
+                containers = excluded.containers,
+                days_used = excluded.days_used,
+                free_days = excluded.free_days,
+                chargeable_days = excluded.chargeable_days,
+                accrued_amount = excluded.accrued_amount,
+                next_day_charge = excluded.next_day_charge,
+                currency = excluded.currency",
+            params![
+                shipment_id,
+                c.charge_type,
+                accrual_date,
+                free_time.containers,
+                c.days_used,
+                c.free_days,
+                c.chargeable_days,
+                c.accrued_amount,
+                c.next_day_charge,
+                free_time.currency
+            ],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    sync_expiry_case(conn, &free_time, warning_days(conn))?;
+    Ok(Some(free_time))
+}
+
+/// Daily accrual over every shipment with free-time terms; returns how many were accrued. A shipment
+/// that fails is logged and skipped so one bad record does not hold up the others.
+pub fn accrue_all(conn: &Connection, today: NaiveDate) -> Result<u32, String> {
+    let mut stmt = conn
+        .prepare("SELECT shipment_id FROM shipment_free_time ORDER BY shipment_id")
+        .map_err(|e| e.to_string())?;
+    let ids = stmt
+        .query_map([], |r| r.get::<_, String>(0))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    let mut n = 0;
+    for id in ids {
+        match accrue_shipment(conn, &id, today) {
+            Ok(Some(_)) => n += 1,
+            Ok(None) => {}
+            Err(e) => log::warn!("Demurrage / detention accrual for shipment {id} failed: {e}"),
+        }
+    }
+    Ok(n)
+}
+
+fn today() -> NaiveDate {
+    chrono::Local::now().date_naive()
+}
+
+/// [`accrue_shipment`] after a milestone or terms change; failures are logged.
+pub fn refresh_after_change(conn: &Connection, shipment_id: &str) {
+    if let Err(e) = accrue_shipment(conn, shipment_id, today()) {
+        log::warn!("Free-time refresh for shipment {shipment_id} failed: {e}");
+    }
+}
+
+/// Accrues today's demurrage and detention for the daily maintenance run.
+pub fn run_daily_accrual(conn: &Connection) {
+    if let Err(e) = accrue_all(conn, today()) {
+        log::warn!("Daily demurrage / detention accrual failed: {e}");
+    }
+}
+
+#[tauri::command]
+pub fn list_free_time_terms(state: State<DbState>) -> Result<Vec<FreeTimeTerms>, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {TERMS_COLUMNS} FROM free_time_terms ORDER BY is_active DESC, name"
+        ))
+        .map_err(|e| e.to_string())?;
+    let mut terms = stmt
+        .query_map([], map_terms)
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    for t in terms.iter_mut() {
+        t.tiers = load_tiers(&conn, &t.id).map_err(|e| e.to_string())?;
+    }
+    Ok(terms)
+}
+
+#[tauri::command]
+pub fn save_free_time_terms(
+    payload: SaveFreeTimeTermsPayload,
+    state: State<DbState>,
+) -> Result<FreeTimeTerms, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    let terms = save_terms(&conn, payload)?;
+    let mut stmt = conn
+        .prepare("SELECT shipment_id FROM shipment_free_time WHERE terms_id = ?1")
+        .map_err(|e| e.to_string())?;
+    let shipment_ids = stmt
+        .query_map(params![terms.id], |r| r.get::<_, String>(0))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    for id in shipment_ids {
+        refresh_after_change(&conn, &id);
+    }
+    Ok(terms)
+}
+
+/// Assigns free-time terms to a shipment, or removes them when `terms_id` is `None`.
+#[tauri::command]
+pub fn assign_shipment_free_time_terms(
+    shipment_id: String,
+    terms_id: Option<String>,
+    user_id: Option<String>,
+    state: State<DbState>,
+) -> Result<Option<ShipmentFreeTime>, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    match terms_id.filter(|t| !t.trim().is_empty()) {
+        Some(terms_id) => {
+            let active = load_terms(&conn, &terms_id)?
+                .ok_or_else(|| format!("Free-time terms {terms_id} not found"))?
+                .is_active;
+            if !active {
+                return Err(format!("Free-time terms {terms_id} are inactive"));
+            }
+            conn.execute(
+                "INSERT INTO shipment_free_time (shipment_id, terms_id, assigned_by) VALUES (?1, ?2, ?3)
+                 ON CONFLICT (shipment_id) DO UPDATE SET
+                    terms_id = excluded.terms_id,
+                    assigned_by = excluded.assigned_by,
+                    assigned_at = strftime('%Y-%m-%d %H:%M:%S', 'now', 'localtime')",
+                params![shipment_id, terms_id, user_id],
+            )
+            .map_err(|e| e.to_string())?;
+        }
+        None => {
+            conn.execute(
+                "DELETE FROM shipment_free_time WHERE shipment_id = ?1",
+                params![shipment_id],
+            )
+            .map_err(|e| e.to_string())?;
+        }
+    }
+    let free_time = accrue_shipment(&conn, &shipment_id, today())?;
+    let _ = dashboard_cache::invalidate_dashboard_metrics_cache(&conn);
+    Ok(free_time)
+}
+
+#[tauri::command]
+pub fn get_shipment_free_time(
+    shipment_id: String,
+    state: State<DbState>,
+) -> Result<Option<ShipmentFreeTime>, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    compute(&conn, &shipment_id, today())
+}
+
+#[tauri::command]
+pub fn get_demurrage_accruals(
+    shipment_id: String,
+    state: State<DbState>,
+) -> Result<Vec<DemurrageAccrual>, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT shipment_id, charge_type, accrual_date, containers, days_used, free_days, chargeable_days, accrued_amount, next_day_charge, currency
+             FROM demurrage_accruals WHERE shipment_id = ?1 ORDER BY accrual_date, charge_type",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![shipment_id], |r| {
+            Ok(DemurrageAccrual {
+                shipment_id: r.get(0)?,
+                charge_type: r.get(1)?,
+                accrual_date: r.get(2)?,
+                containers: r.get(3)?,
+                days_used: r.get(4)?,
+                free_days: r.get(5)?,
+                chargeable_days: r.get(6)?,
+                accrued_amount: r.get(7)?,
+                next_day_charge: r.get(8)?,
+                currency: r.get(9)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())
+}
+
+/// Runs the daily accrual now; returns how many shipments were accrued.
+#[tauri::command]
+pub fn run_free_time_accrual(state: State<DbState>) -> Result<u32, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    let n = accrue_all(&conn, today())?;
+    let _ = dashboard_cache::invalidate_dashboard_metrics_cache(&conn);
+    Ok(n)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::test_support;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    fn case(conn: &Connection) -> Option<(String, String)> {
+        test_support::exception_case(conn, FREE_TIME_EXPIRING, "SHP-1")
+    }
+
+    fn tier(
+        charge_type: &str,
+        from_day: i64,
+        to_day: Option<i64>,
+        rate_per_day: f64,
+    ) -> FreeTimeRateTier {
+        FreeTimeRateTier {
+            charge_type: charge_type.into(),
+            from_day,
+            to_day,
+            rate_per_day,
+        }
+    }
+
+    fn terms(tiers: Vec<FreeTimeRateTier>) -> SaveFreeTimeTermsPayload {
+        SaveFreeTimeTermsPayload {
+            id: None,
+            name: "MSC Nhava Sheva".into(),
+            shipping_line: Some("MSC".into()),
+            service_provider_id: None,
+            port_free_days: 3,
+            detention_free_days: 7,
+            currency: Some("usd".into()),
+            is_active: None,
+            notes: None,
+            tiers,
+        }
+    }
+
+    /// Two containers due on 2024-03-10 under 3 free port days and 7 free detention days;
+    /// demurrage runs 10/day for days 1-5 and 20/day after, detention 50/day.
+    fn two_container_shipment() -> Connection {
+        let conn = test_support::migrated_db();
+        test_support::add_shipment(&conn, "SHP-1", "2024-03-01", 1000.0, "USD", "in-transit");
+        conn.execute(
+            "UPDATE shipments SET eta = '2024-03-10', container_number = 'MSCU1234567, MSCU7654321' WHERE id = 'SHP-1'",
+            [],
+        )
+        .unwrap();
+        let terms = save_terms(
+            &conn,
+            terms(vec![
+                tier("DEMURRAGE", 6, None, 20.0),
+                tier("demurrage", 1, Some(5), 10.0),
+                tier("DETENTION", 1, None, 50.0),
+            ]),
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO shipment_free_time (shipment_id, terms_id) VALUES ('SHP-1', ?1)",
+            params![terms.id],
+        )
+        .unwrap();
+        conn
+    }
+
+    fn record_event(conn: &Connection, event_type: &str, occurred_at: &str) {
+        conn.execute(
+            "INSERT INTO shipment_events (shipment_id, event_type, occurred_at) VALUES ('SHP-1', ?1, ?2)",
+            params![event_type, occurred_at],
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn tiers_must_start_at_day_one_and_run_without_gaps() {
+        let conn = test_support::migrated_db();
+        let gap = save_terms(
+            &conn,
+            terms(vec![
+                tier("DEMURRAGE", 1, Some(5), 10.0),
+                tier("DEMURRAGE", 7, None, 20.0),
+            ]),
+        );
+        assert!(gap.unwrap_err().contains("starting at day 6"));
+        let late_start = save_terms(&conn, terms(vec![tier("DETENTION", 2, None, 50.0)]));
+        assert!(late_start.unwrap_err().contains("starting at day 1"));
+        let backwards = save_terms(&conn, terms(vec![tier("DEMURRAGE", 1, Some(0), 10.0)]));
+        assert!(backwards.unwrap_err().contains("ends before it starts"));
+    }
+
+    #[test]
+    fn only_the_last_tier_of_a_charge_can_be_open_ended() {
+        let conn = test_support::migrated_db();
+        let open_first = save_terms(
+            &conn,
+            terms(vec![
+                tier("DEMURRAGE", 1, None, 10.0),
+                tier("DEMURRAGE", 2, None, 20.0),
+            ]),
+        );
+        assert!(open_first
+            .unwrap_err()
+            .contains("Only the last DEMURRAGE tier"));
+
+        // Each charge has its own open-ended tail; tiers come back sorted and upper-cased.
+        let saved = save_terms(
+            &conn,
+            terms(vec![
+                tier("detention", 1, None, 50.0),
+                tier("demurrage", 4, None, 20.0),
+                tier("Demurrage", 1, Some(3), 10.0),
+            ]),
+        )
+        .unwrap();
+        assert_eq!(saved.currency, "USD");
+        assert_eq!(
+            saved.tiers,
+            vec![
+                tier(DEMURRAGE, 1, Some(3), 10.0),
+                tier(DEMURRAGE, 4, None, 20.0),
+                tier(DETENTION, 1, None, 50.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn clock_is_only_projected_from_the_eta_before_arrival() {
+        let conn = two_container_shipment();
+        let before = accrue_shipment(&conn, "SHP-1", date("2024-03-05"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(before.clocks[0].clock_start_source.as_deref(), Some("ETA"));
+        assert_eq!(before.clocks[0].days_used, 0);
+        assert_eq!(before.total_accrued, 0.0);
+        assert_eq!(case(&conn), None);
+    }
+
+    #[test]
+    fn warns_on_the_last_free_days() {
+        let conn = two_container_shipment();
+        record_event(&conn, "ARRIVED_AT_PORT", "2024-03-12 08:00:00");
+        // Day 2 of 3 free port days.
+        let warned = accrue_shipment(&conn, "SHP-1", date("2024-03-13"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(warned.containers, 2);
+        assert_eq!(
+            warned.clocks[0].free_time_ends.as_deref(),
+            Some("2024-03-14")
+        );
+        assert_eq!(warned.clocks[0].days_remaining, 1);
+        assert_eq!(case(&conn), Some(("OPEN".into(), "HIGH".into())));
+    }
+
+    #[test]
+    fn chargeable_days_are_billed_tier_by_tier_per_container() {
+        let conn = two_container_shipment();
+        record_event(&conn, "ARRIVED_AT_PORT", "2024-03-12 08:00:00");
+        // Day 10: 7 chargeable demurrage days = 5 x 10 + 2 x 20 per container, x 2 containers.
+        let late = accrue_shipment(&conn, "SHP-1", date("2024-03-21"))
+            .unwrap()
+            .unwrap();
+        let demurrage = &late.clocks[0];
+        assert_eq!((demurrage.days_used, demurrage.chargeable_days), (10, 7));
+        assert_eq!(demurrage.accrued_amount, 180.0);
+        assert_eq!(demurrage.next_day_charge, 40.0);
+        // Detention: 3 days past 7 free at 50 x 2 containers.
+        assert_eq!(late.clocks[1].accrued_amount, 300.0);
+        assert_eq!(case(&conn), Some(("OPEN".into(), "CRITICAL".into())));
+
+        // The age / SLA recalculation leaves a charging case at CRITICAL.
+        crate::commands::exception_reliability::recalculate_open_exception_priorities(&conn)
+            .unwrap();
+        assert_eq!(case(&conn), Some(("OPEN".into(), "CRITICAL".into())));
+    }
+
+    #[test]
+    fn gate_out_and_empty_return_stop_the_clocks_and_resolve_the_case() {
+        let conn = two_container_shipment();
+        record_event(&conn, "ARRIVED_AT_PORT", "2024-03-12 08:00:00");
+        accrue_shipment(&conn, "SHP-1", date("2024-03-21")).unwrap();
+        record_event(&conn, "GATE_OUT", "2024-03-21 10:00:00");
+        record_event(&conn, "EMPTY_RETURNED", "2024-03-23 16:00:00");
+
+        let done = accrue_shipment(&conn, "SHP-1", date("2024-03-25"))
+            .unwrap()
+            .unwrap();
+        assert!(done.clocks.iter().all(|c| !c.running));
+        assert_eq!(done.clocks[1].accrued_amount, 500.0);
+        assert_eq!(done.total_accrued, 680.0);
+        assert_eq!(case(&conn).map(|c| c.0), Some("RESOLVED".into()));
+
+        // A stopped clock is recorded on the day it stopped.
+        let rows: Vec<(String, String, f64)> = {
+            let mut stmt = conn
+                .prepare("SELECT charge_type, accrual_date, accrued_amount FROM demurrage_accruals ORDER BY charge_type, accrual_date")
+                .unwrap();
+            stmt.query_map([], |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)))
+                .unwrap()
+                .collect::<Result<_, _>>()
+                .unwrap()
+        };
+        assert_eq!(
+            rows,
+            vec![
+                (DEMURRAGE.into(), "2024-03-21".into(), 180.0),
+                (DETENTION.into(), "2024-03-21".into(), 300.0),
+                (DETENTION.into(), "2024-03-23".into(), 500.0),
+            ]
+        );
+    }
+}
@@ -0,0 +1,865 @@
+//! Duty drawback and RoDTEP entitlements: scrip / credit-ledger balances, utilisation against BOEs, expiry.
+
+use crate::commands::boe::{self, map_row_to_saved_boe};
+use crate::commands::bonded_warehouse;
+use crate::commands::dashboard_cache;
+use crate::commands::tariff::{self, normalize_date};
+use crate::commands::utils::generate_id;
+use crate::db::{BoeItemInput, CalculatedDutyItem, CalculationResult, DbState};
+use crate::duty_engine;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use tauri::State;
+
+pub const SCHEME_DRAWBACK: &str = "DRAWBACK";
+pub const SCHEME_RODTEP: &str = "RODTEP";
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DutyCreditEntitlement {
+    pub id: String,
+    /// `DRAWBACK` or `RODTEP`.
+    pub scheme: String,
+    pub shipment_id: String,
+    pub part_no: Option<String>,
+    pub scrip_number: Option<String>,
+    pub entitled_amount: f64,
+    pub utilised_amount: f64,
+    pub balance: f64,
+    pub issue_date: String,
+    pub expiry_date: Option<String>,
+    /// `OPEN`, `EXHAUSTED` or `EXPIRED` as of today.
+    pub status: String,
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DutyCreditEntitlementPayload {
+    /// Existing entitlement to update; a new one is created when absent.
+    pub id: Option<String>,
+    pub scheme: String,
+    pub shipment_id: String,
+    pub part_no: Option<String>,
+    pub scrip_number: Option<String>,
+    pub entitled_amount: f64,
+    pub issue_date: String,
+    pub expiry_date: Option<String>,
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DutyCreditAllocation {
+    pub entitlement_id: String,
+    /// BOE line the credit pays duty for.
+    pub part_no: String,
+    pub amount: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DutyCreditUtilisation {
+    pub id: i64,
+    pub entitlement_id: String,
+    pub scheme: String,
+    pub scrip_number: Option<String>,
+    pub saved_boe_id: String,
+    pub part_no: String,
+    pub amount: f64,
+    pub created_at: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct DutyCreditYearSummary {
+    /// e.g. `FY 2024-25`, by entitlement issue date.
+    pub fiscal_year: String,
+    pub scheme: String,
+    pub claimed: f64,
+    /// Utilised against BOEs.
+    pub realised: f64,
+    /// Unused balance on entitlements past their expiry date.
+    pub expired: f64,
+    /// Unused balance still available.
+    pub open_balance: f64,
+}
+
+const SELECT_ENTITLEMENT: &str = "
+    SELECT e.id, e.scheme, e.shipment_id, e.part_no, e.scrip_number, e.entitled_amount,
+           COALESCE((SELECT SUM(u.amount) FROM duty_credit_utilisations u WHERE u.entitlement_id = e.id), 0),
+           e.issue_date, e.expiry_date, e.notes
+    FROM duty_credit_entitlements e";
+
+fn map_entitlement(row: &rusqlite::Row, today: &str) -> rusqlite::Result<DutyCreditEntitlement> {
+    let entitled_amount: f64 = row.get(5)?;
+    let utilised_amount: f64 = row.get(6)?;
+    let expiry_date: Option<String> = row.get(8)?;
+    let balance = duty_engine::round_paise(entitled_amount - utilised_amount);
+    let status = if balance <= 0.0 {
+        "EXHAUSTED"
+    } else if expiry_date.as_deref().is_some_and(|d| d < today) {
+        "EXPIRED"
+    } else {
+        "OPEN"
+    };
+    Ok(DutyCreditEntitlement {
+        id: row.get(0)?,
+        scheme: row.get(1)?,
+        shipment_id: row.get(2)?,
+        part_no: row.get(3)?,
+        scrip_number: row.get(4)?,
+        entitled_amount,
+        utilised_amount,
+        balance,
+        issue_date: row.get(7)?,
+        expiry_date,
+        status: status.to_string(),
+        notes: row.get(9)?,
+    })
+}
+
+fn query_entitlements(
+    conn: &Connection,
+    filter: &str,
+    args: &[&dyn rusqlite::ToSql],
+    today: &str,
+) -> Result<Vec<DutyCreditEntitlement>, String> {
+    let mut stmt = conn
+        .prepare(&format!(
+            "{SELECT_ENTITLEMENT} {filter} ORDER BY date(e.issue_date), e.id"
+        ))
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(args, |r| map_entitlement(r, today))
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())
+}
+
+/// Credits debited against a saved BOE, in INR.
+pub fn utilised_on_boe(conn: &Connection, saved_boe_id: &str) -> rusqlite::Result<f64> {
+    conn.query_row(
+        "SELECT COALESCE(SUM(amount), 0) FROM duty_credit_utilisations WHERE saved_boe_id = ?1",
+        params![saved_boe_id],
+        |r| r.get(0),
+    )
+}
+
+/// Entitlements with a balance that are usable on `as_of` (issued, not expired).
+pub fn open_entitlements(
+    conn: &Connection,
+    shipment_id: Option<&str>,
+    as_of: &str,
+) -> Result<Vec<DutyCreditEntitlement>, String> {
+    let all = query_entitlements(
+        conn,
+        "WHERE (?1 IS NULL OR e.shipment_id = ?1) AND date(e.issue_date) <= date(?2)
+           AND (e.expiry_date IS NULL OR date(e.expiry_date) >= date(?2))",
+        &[&shipment_id, &as_of],
+        as_of,
+    )?;
+    Ok(all.into_iter().filter(|e| e.balance > 0.0).collect())
+}
+
+/// Duty each BOE part can take credit against, as (BCD open to RoDTEP scrip, every creditable
+/// head); a part billed on several lines adds up across them. Creditable heads are the customs
+/// duties (BCD, AIDC, SWS, ADD, safeguard): IGST and compensation cess come back as input tax
+/// credit, so no scrip is spent on them. Lines calculated with the RoDTEP method already have their
+/// BCD offset by the engine, so scrip cannot be debited against them too.
+fn creditable_by_part<'a>(
+    items: &'a [CalculatedDutyItem],
+    inputs: &[BoeItemInput],
+) -> HashMap<&'a str, (f64, f64)> {
+    let input_of_item = duty_engine::pair_by_part(items, inputs, |i| &i.part_no, |ii| &ii.part_no);
+    let mut caps: HashMap<&str, (f64, f64)> = HashMap::new();
+    for (item, input) in items.iter().zip(input_of_item) {
+        let cap = caps.entry(item.part_no.as_str()).or_default();
+        if !input.is_some_and(|ii| ii.calculation_method == duty_engine::METHOD_RODTEP) {
+            cap.0 += item.bcd_value;
+        }
+        cap.1 += item.bcd_value
+            + item.aidc_value
+            + item.sws_value
+            + item.add_value
+            + item.safeguard_value;
+    }
+    caps
+}
+
+/// Fits the credits already debited against a saved BOE to its recalculated lines. Credit on a
+/// part that is no longer on the BOE is released, and credit above a part's BCD open to scrip
+/// (RoDTEP) or creditable duty is cut back, the latest utilisations first; released credit returns
+/// to the entitlement's balance.
+pub(crate) fn revalidate_utilisations(
+    conn: &Connection,
+    saved_boe_id: &str,
+    items: &[CalculatedDutyItem],
+    inputs: &[BoeItemInput],
+) -> Result<(), String> {
+    let caps = creditable_by_part(items, inputs);
+    let mut stmt = conn
+        .prepare(
+            "SELECT u.id, e.scheme, u.part_no, u.amount
+             FROM duty_credit_utilisations u
+             JOIN duty_credit_entitlements e ON e.id = u.entitlement_id
+             WHERE u.saved_boe_id = ?1 ORDER BY u.id",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![saved_boe_id], |r| {
+            Ok((
+                r.get::<_, i64>(0)?,
+                r.get::<_, String>(1)?,
+                r.get::<_, String>(2)?,
+                r.get::<_, f64>(3)?,
+            ))
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    // Per part: (RoDTEP credit kept, all credit kept).
+    let mut per_line: HashMap<String, (f64, f64)> = HashMap::new();
+    for (id, scheme, part_no, amount) in rows {
+        let (bcd, creditable) = caps.get(part_no.as_str()).copied().unwrap_or_default();
+        let line = per_line.entry(part_no.clone()).or_default();
+        let mut room = creditable - line.1;
+        if scheme == SCHEME_RODTEP {
+            room = room.min(bcd - line.0);
+        }
+        let kept = if amount <= room + 0.005 {
+            amount
+        } else {
+            duty_engine::round_paise(room.max(0.0))
+        };
+        if kept < amount {
+            if kept > 0.0 {
+                conn.execute(
+                    "UPDATE duty_credit_utilisations SET amount = ?2 WHERE id = ?1",
+                    params![id, kept],
+                )
+            } else {
+                conn.execute(
+                    "DELETE FROM duty_credit_utilisations WHERE id = ?1",
+                    params![id],
+                )
+            }
+            .map_err(|e| e.to_string())?;
+            log::warn!(
+                "BOE {saved_boe_id}: released {:.2} of credit on part {part_no} after recalculation",
+                amount - kept
+            );
+        }
+        if scheme == SCHEME_RODTEP {
+            line.0 += kept;
+        }
+        line.1 += kept;
+    }
+    Ok(())
+}
+
+/// Replaces the credits debited against a saved BOE with `allocations`. Each entitlement must be
+/// usable on the BE date and have the balance; RoDTEP pays BCD only, and not on lines calculated
+/// with the RoDTEP method; drawback pays any customs duty head, but not IGST or compensation cess.
+/// Into-bond BOEs defer their duty and take no credit. Returns the BOE's calculation with the
+/// payable duty and interest re-settled on the new credit.
+pub fn apply_credits(
+    conn: &mut Connection,
+    saved_boe_id: &str,
+    allocations: &[DutyCreditAllocation],
+) -> Result<CalculationResult, String> {
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let mut saved = tx
+        .query_row(
+            "SELECT * FROM boe_calculations WHERE id = ?1",
+            params![saved_boe_id],
+            map_row_to_saved_boe,
+        )
+        .optional()
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Saved BOE {saved_boe_id} not found"))?;
+    if saved.boe_type == bonded_warehouse::BOE_TYPE_INTO_BOND {
+        return Err(format!(
+            "BOE {saved_boe_id} is into-bond and pays no duty yet; apply credits to its ex-bond clearances"
+        ));
+    }
+    let as_of = tariff::be_date_for_boe(&tx, saved.boe_id.as_deref()).unwrap_or_else(tariff::today);
+
+    tx.execute(
+        "DELETE FROM duty_credit_utilisations WHERE saved_boe_id = ?1",
+        params![saved_boe_id],
+    )
+    .map_err(|e| e.to_string())?;
+
+    let caps = creditable_by_part(
+        &saved.calculation_result.calculated_items,
+        &saved.item_inputs,
+    );
+    let usable: HashMap<String, DutyCreditEntitlement> = open_entitlements(&tx, None, &as_of)?
+        .into_iter()
+        .map(|e| (e.id.clone(), e))
+        .collect();
+    let mut drawn: HashMap<&str, f64> = HashMap::new();
+    // Per line: (RoDTEP credit, all credit).
+    let mut per_line: HashMap<&str, (f64, f64)> = HashMap::new();
+    for a in allocations {
+        if !(a.amount.is_finite() && a.amount > 0.0) {
+            return Err(format!(
+                "Credit for part {} must be greater than zero",
+                a.part_no
+            ));
+        }
+        let Some(ent) = usable.get(&a.entitlement_id) else {
+            return Err(format!(
+                "Entitlement {} is not usable on {as_of} (unknown, not yet issued, expired or exhausted)",
+                a.entitlement_id
+            ));
+        };
+        let used = drawn.entry(ent.id.as_str()).or_default();
+        *used += a.amount;
+        if *used > ent.balance + 0.005 {
+            return Err(format!(
+                "Entitlement {} has a balance of {:.2}",
+                ent.id, ent.balance
+            ));
+        }
+        let Some(&(bcd, creditable)) = caps.get(a.part_no.as_str()) else {
+            return Err(format!("Part {} is not on this BOE", a.part_no));
+        };
+        let line = per_line.entry(a.part_no.as_str()).or_default();
+        if ent.scheme == SCHEME_RODTEP {
+            line.0 += a.amount;
+        }
+        line.1 += a.amount;
+        if line.0 > bcd + 0.005 {
+            return Err(format!(
+                "RoDTEP credit on part {} exceeds its BCD open to scrip of {:.2} (lines calculated with the RoDTEP method take none)",
+                a.part_no, bcd
+            ));
+        }
+        if line.1 > creditable + 0.005 {
+            return Err(format!(
+                "Credits on part {} exceed its creditable duty of {:.2}",
+                a.part_no, creditable
+            ));
+        }
+        tx.execute(
+            "INSERT INTO duty_credit_utilisations (entitlement_id, saved_boe_id, part_no, amount)
+             VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT(entitlement_id, saved_boe_id, part_no) DO UPDATE SET amount = amount + excluded.amount",
+            params![ent.id, saved_boe_id, a.part_no, a.amount],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+
+    boe::settle_payable(&tx, &mut saved)?;
+    let json = serde_json::to_string(&saved.calculation_result).map_err(|e| e.to_string())?;
+    tx.execute(
+        "UPDATE boe_calculations SET calculation_result_json = ?2 WHERE id = ?1",
+        params![saved_boe_id, json],
+    )
+    .map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(saved.calculation_result)
+}
+
+/// `FY 2024-25` for a fiscal year starting in `start_month`; `FY 2024` for calendar years.
+fn fiscal_year_label(date: &str, start_month: u32) -> Option<String> {
+    use chrono::Datelike;
+    let d = chrono::NaiveDate::parse_from_str(date.get(..10)?, "%Y-%m-%d").ok()?;
+    let start = if d.month() >= start_month {
+        d.year()
+    } else {
+        d.year() - 1
+    };
+    Some(if start_month == 1 {
+        format!("FY {start}")
+    } else {
+        format!("FY {start}-{:02}", (start + 1) % 100)
+    })
+}
+
+/// Claimed versus realised benefit per fiscal year (by issue date) and scheme.
+pub fn fiscal_year_report(
+    conn: &Connection,
+    start_month: u32,
+    today: &str,
+) -> Result<Vec<DutyCreditYearSummary>, String> {
+    let mut by_year: BTreeMap<(String, String), DutyCreditYearSummary> = BTreeMap::new();
+    for e in query_entitlements(conn, "", &[], today)? {
+        let Some(fy) = fiscal_year_label(&e.issue_date, start_month) else {
+            continue;
+        };
+        let row = by_year
+            .entry((fy.clone(), e.scheme.clone()))
+            .or_insert_with(|| DutyCreditYearSummary {
+                fiscal_year: fy,
+                scheme: e.scheme.clone(),
+                ..Default::default()
+            });
+        row.claimed += e.entitled_amount;
+        row.realised += e.utilised_amount;
+        match e.status.as_str() {
+            "EXPIRED" => row.expired += e.balance,
+            "OPEN" => row.open_balance += e.balance,
+            _ => {}
+        }
+    }
+    Ok(by_year
+        .into_values()
+        .map(|mut r| {
+            r.claimed = duty_engine::round_paise(r.claimed);
+            r.realised = duty_engine::round_paise(r.realised);
+            r.expired = duty_engine::round_paise(r.expired);
+            r.open_balance = duty_engine::round_paise(r.open_balance);
+            r
+        })
+        .collect())
+}
+
+#[tauri::command]
+pub fn list_duty_credit_entitlements(
+    shipment_id: Option<String>,
+    state: State<DbState>,
+) -> Result<Vec<DutyCreditEntitlement>, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    query_entitlements(
+        &conn,
+        "WHERE (?1 IS NULL OR e.shipment_id = ?1)",
+        &[&shipment_id],
+        &tariff::today(),
+    )
+}
+
+/// Entitlements with a balance usable on `as_of` (defaults to today).
+#[tauri::command]
+pub fn list_open_duty_credits(
+    shipment_id: Option<String>,
+    as_of: Option<String>,
+    state: State<DbState>,
+) -> Result<Vec<DutyCreditEntitlement>, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    let as_of = as_of
+        .as_deref()
+        .and_then(normalize_date)
+        .unwrap_or_else(tariff::today);
+    open_entitlements(&conn, shipment_id.as_deref(), &as_of)
+}
+
+#[tauri::command]
+pub fn save_duty_credit_entitlement(
+    payload: DutyCreditEntitlementPayload,
+    state: State<DbState>,
+) -> Result<String, String> {
+    let scheme = payload.scheme.trim().to_uppercase();
+    if scheme != SCHEME_DRAWBACK && scheme != SCHEME_RODTEP {
+        return Err(format!("Unknown scheme '{}'", payload.scheme));
+    }
+    if !(payload.entitled_amount.is_finite() && payload.entitled_amount >= 0.0) {
+        return Err("Entitled amount cannot be negative".to_string());
+    }
+    let issue_date =
+        normalize_date(&payload.issue_date).ok_or("Issue date must be a valid date")?;
+    let expiry_date = match payload
+        .expiry_date
+        .as_deref()
+        .filter(|s| !s.trim().is_empty())
+    {
+        Some(raw) => Some(normalize_date(raw).ok_or("Expiry date must be a valid date")?),
+        None => None,
+    };
+    if expiry_date
+        .as_deref()
+        .is_some_and(|e| e < issue_date.as_str())
+    {
+        return Err("Expiry date is before the issue date".to_string());
+    }
+
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    if let Some(part) = payload.part_no.as_deref().filter(|p| !p.is_empty()) {
+        let lines = duty_engine::load_shipment_lines(&conn, &payload.shipment_id, &issue_date)
+            .map_err(|e| e.to_string())?;
+        if !lines.iter().any(|l| l.part_no == part) {
+            return Err(format!(
+                "Part {part} is not on any invoice line of shipment {}",
+                payload.shipment_id
+            ));
+        }
+    }
+
+    let id = match payload.id.filter(|s| !s.is_empty()) {
+        Some(id) => {
+            let utilised: f64 = conn
+                .query_row(
+                    "SELECT COALESCE(SUM(amount), 0) FROM duty_credit_utilisations WHERE entitlement_id = ?1",
+                    params![id],
+                    |r| r.get(0),
+                )
+                .map_err(|e| e.to_string())?;
+            if payload.entitled_amount + 0.005 < utilised {
+                return Err(format!(
+                    "Entitlement {id} has already been utilised for {utilised:.2}"
+                ));
+            }
+            let changed = conn
+                .execute(
+                    "UPDATE duty_credit_entitlements
+                     SET scheme = ?2, shipment_id = ?3, part_no = ?4, scrip_number = ?5, entitled_amount = ?6,
+                         issue_date = ?7, expiry_date = ?8, notes = ?9,
+                         updated_at = strftime('%Y-%m-%d %H:%M:%S', 'now', 'localtime')
+                     WHERE id = ?1",
+                    params![
+                        id,
+                        scheme,
+                        payload.shipment_id,
+                        payload.part_no,
+                        payload.scrip_number,
+                        payload.entitled_amount,
+                        issue_date,
+                        expiry_date,
+                        payload.notes
+                    ],
+                )
+                .map_err(|e| e.to_string())?;
+            if changed == 0 {
+                return Err(format!("Entitlement {id} not found"));
+            }
+            id
+        }
+        None => {
+            let id = generate_id(Some("DCR".to_string()));
+            conn.execute(
+                "INSERT INTO duty_credit_entitlements
+                    (id, scheme, shipment_id, part_no, scrip_number, entitled_amount, issue_date, expiry_date, notes)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+                params![
+                    id,
+                    scheme,
+                    payload.shipment_id,
+                    payload.part_no,
+                    payload.scrip_number,
+                    payload.entitled_amount,
+                    issue_date,
+                    expiry_date,
+                    payload.notes
+                ],
+            )
+            .map_err(|e| e.to_string())?;
+            id
+        }
+    };
+    let _ = dashboard_cache::invalidate_dashboard_metrics_cache(&conn);
+    Ok(id)
+}
+
+#[tauri::command]
+pub fn delete_duty_credit_entitlement(id: String, state: State<DbState>) -> Result<(), String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    let used: i64 = conn
+        .query_row(
+            "SELECT COUNT(*) FROM duty_credit_utilisations WHERE entitlement_id = ?1",
+            params![id],
+            |r| r.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+    if used > 0 {
+        return Err(format!(
+            "Entitlement {id} has been utilised against {used} BOE line(s); remove those first"
+        ));
+    }
+    conn.execute(
+        "DELETE FROM duty_credit_entitlements WHERE id = ?1",
+        params![id],
+    )
+    .map_err(|e| e.to_string())?;
+    let _ = dashboard_cache::invalidate_dashboard_metrics_cache(&conn);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_duty_credit_utilisations(
+    saved_boe_id: Option<String>,
+    entitlement_id: Option<String>,
+    state: State<DbState>,
+) -> Result<Vec<DutyCreditUtilisation>, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT u.id, u.entitlement_id, e.scheme, e.scrip_number, u.saved_boe_id, u.part_no, u.amount, u.created_at
+             FROM duty_credit_utilisations u
+             JOIN duty_credit_entitlements e ON e.id = u.entitlement_id
+             WHERE (?1 IS NULL OR u.saved_boe_id = ?1) AND (?2 IS NULL OR u.entitlement_id = ?2)
+             ORDER BY u.id",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![saved_boe_id, entitlement_id], |r| {
+            Ok(DutyCreditUtilisation {
+                id: r.get(0)?,
+                entitlement_id: r.get(1)?,
+                scheme: r.get(2)?,
+                scrip_number: r.get(3)?,
+                saved_boe_id: r.get(4)?,
+                part_no: r.get(5)?,
+                amount: r.get(6)?,
+                created_at: r.get(7)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())
+}
+
+/// Debits drawback / RoDTEP credits against a saved BOE, replacing any earlier allocation.
+#[tauri::command]
+pub fn apply_duty_credits_to_boe(
+    saved_boe_id: String,
+    allocations: Vec<DutyCreditAllocation>,
+    state: State<DbState>,
+) -> Result<CalculationResult, String> {
+    let mut conn = state.db.lock().map_err(|e| e.to_string())?;
+    let result = apply_credits(&mut conn, &saved_boe_id, &allocations)?;
+    let _ = dashboard_cache::invalidate_dashboard_metrics_cache(&conn);
+    Ok(result)
+}
+
+/// Claimed versus realised benefit per fiscal year; the year starts in April unless told otherwise.
+#[tauri::command]
+pub fn get_duty_credit_fiscal_year_report(
+    fiscal_year_start_month: Option<u8>,
+    state: State<DbState>,
+) -> Result<Vec<DutyCreditYearSummary>, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    let start = u32::from(fiscal_year_start_month.unwrap_or(4).clamp(1, 12));
+    fiscal_year_report(&conn, start, &tariff::today())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::test_support;
+    use crate::db::{CalculatedDutyItem, FormValues};
+
+    #[test]
+    fn credits_respect_balance_expiry_and_line_caps() {
+        let mut c = test_support::migrated_db();
+        test_support::add_shipment(&c, "SHP-1", "2024-02-01", 1.0, "USD", "delivered");
+        test_support::add_shipment(&c, "SHP-2", "2024-06-01", 1.0, "USD", "in-transit");
+        c.execute_batch(
+            "INSERT INTO boe_details (id, be_number, be_date, location, total_assessment_value, duty_amount)
+                 VALUES ('BOE-2', '1234567', '2024-07-10', 'INNSA1', 100000, 20000);
+             INSERT INTO duty_credit_entitlements (id, scheme, shipment_id, scrip_number, entitled_amount, issue_date, expiry_date)
+                 VALUES ('R1', 'RODTEP', 'SHP-1', 'SCRIP-1', 6000, '2024-03-15', '2025-03-14'),
+                        ('D1', 'DRAWBACK', 'SHP-1', 'DBK-1', 5000, '2024-04-20', NULL),
+                        ('R0', 'RODTEP', 'SHP-1', 'SCRIP-0', 900, '2023-04-10', '2024-04-09');",
+        )
+        .unwrap();
+        let item = CalculatedDutyItem {
+            part_no: "P1".to_string(),
+            description: String::new(),
+            assessable_value: 100_000.0,
+            bcd_value: 10_000.0,
+            sws_value: 0.0,
+            igst_value: 19_800.0,
+            aidc_value: 0.0,
+            add_value: 0.0,
+            safeguard_value: 0.0,
+            cess_value: 0.0,
+            valuation: None,
+        };
+        let result = CalculationResult {
+            calculated_items: vec![item],
+            bcd_total: 10_000.0,
+            sws_total: 0.0,
+            igst_total: 19_800.0,
+            aidc_total: 0.0,
+            add_total: 0.0,
+            safeguard_total: 0.0,
+            cess_total: 0.0,
+            interest: 0.0,
+            customs_duty_total: 29_800.0,
+            duty_credit_total: 0.0,
+            statutory_charges: None,
+        };
+        let form = FormValues {
+            supplier_name: "Acme".to_string(),
+            shipment_id: "SHP-2".to_string(),
+            exchange_rate: 80.0,
+            freight_cost: 0.0,
+            exw_cost: 0.0,
+            insurance_rate: 0.0,
+            interest: None,
+            incoterm: None,
+            apportionment_basis: None,
+        };
+        c.execute(
+            "INSERT INTO boe_calculations (id, shipment_id, boe_id, supplier_name, invoice_number, form_values_json, item_inputs_json, calculation_result_json)
+             VALUES ('BC-2', 'SHP-2', 'BOE-2', 'Acme', 'INV-2', ?1, '[]', ?2)",
+            params![
+                serde_json::to_string(&form).unwrap(),
+                serde_json::to_string(&result).unwrap()
+            ],
+        )
+        .unwrap();
+        let alloc = |id: &str, amount: f64| DutyCreditAllocation {
+            entitlement_id: id.to_string(),
+            part_no: "P1".to_string(),
+            amount,
+        };
+
+        assert!(apply_credits(&mut c, "BC-2", &[alloc("R0", 100.0)])
+            .unwrap_err()
+            .contains("not usable"));
+        assert!(
+            apply_credits(&mut c, "BC-2", &[alloc("R1", 6000.0), alloc("R1", 1.0)])
+                .unwrap_err()
+                .contains("balance")
+        );
+        assert!(
+            apply_credits(&mut c, "BC-2", &[alloc("D1", 5000.0), alloc("R1", 6000.0)])
+                .unwrap_err()
+                .contains("creditable duty")
+        );
+
+        let applied =
+            apply_credits(&mut c, "BC-2", &[alloc("R1", 6000.0), alloc("D1", 4000.0)]).unwrap();
+        assert_eq!(applied.duty_credit_total, 10_000.0);
+        // Interest runs on the 19,800 still paid in cash, not on the gross duty.
+        let charges = crate::commands::boe_charges::charges_for_boe(&c, "BOE-2", Some(19_800.0))
+            .unwrap()
+            .unwrap();
+        assert_eq!(applied.interest, charges.late_payment_interest);
+        assert_eq!(applied.statutory_charges, Some(charges.clone()));
+        assert_eq!(
+            applied.customs_duty_total,
+            19_800.0 + charges.late_payment_interest + charges.late_filing_charge
+        );
+        assert!(open_entitlements(&c, None, "2024-07-10")
+            .unwrap()
+            .iter()
+            .all(|e| e.id == "D1" && e.balance == 1000.0));
+
+        let report = fiscal_year_report(&c, 4, "2024-08-01").unwrap();
+        let row = |fy: &str, scheme: &str| {
+            report
+                .iter()
+                .find(|r| r.fiscal_year == fy && r.scheme == scheme)
+                .cloned()
+                .unwrap()
+        };
+        assert_eq!(row("FY 2023-24", "RODTEP").claimed, 6900.0);
+        assert_eq!(row("FY 2023-24", "RODTEP").realised, 6000.0);
+        assert_eq!(row("FY 2023-24", "RODTEP").expired, 900.0);
+        assert_eq!(row("FY 2024-25", "DRAWBACK").open_balance, 1000.0);
+
+        // BCD on the line drops to 7,000 on recalculation: RoDTEP (debited first) keeps 6,000
+        // and drawback is cut back to the remaining 1,000.
+        let mut lower = applied.calculated_items.clone();
+        lower[0].bcd_value = 7_000.0;
+        revalidate_utilisations(&c, "BC-2", &lower, &[]).unwrap();
+        assert_eq!(utilised_on_boe(&c, "BC-2").unwrap(), 7_000.0);
+        assert!(open_entitlements(&c, None, "2024-07-10")
+            .unwrap()
+            .iter()
+            .any(|e| e.id == "D1" && e.balance == 4000.0));
+
+        // The part leaves the BOE altogether: all of its credit is released.
+        lower[0].part_no = "P2".to_string();
+        revalidate_utilisations(&c, "BC-2", &lower, &[]).unwrap();
+        assert_eq!(utilised_on_boe(&c, "BC-2").unwrap(), 0.0);
+    }
+
+    #[test]
+    fn repeated_part_lines_pool_their_creditable_duty() {
+        let items: Vec<CalculatedDutyItem> = [4_000.0, 3_000.0]
+            .iter()
+            .map(|&bcd| CalculatedDutyItem {
+                part_no: "P1".to_string(),
+                description: String::new(),
+                assessable_value: 0.0,
+                bcd_value: bcd,
+                sws_value: bcd / 10.0,
+                igst_value: 1_000.0,
+                aidc_value: 0.0,
+                add_value: 0.0,
+                safeguard_value: 0.0,
+                cess_value: 200.0,
+                valuation: None,
+            })
+            .collect();
+        // IGST and cess are input tax credit, not creditable duty.
+        let caps = creditable_by_part(&items, &[]);
+        assert_eq!(caps.get("P1"), Some(&(7_000.0, 7_700.0)));
+    }
+
+    #[test]
+    fn rodtep_scrip_is_not_debited_on_rodtep_method_lines() {
+        let mut c = test_support::migrated_db();
+        test_support::add_shipment(&c, "SHP-1", "2024-02-01", 1.0, "USD", "in-transit");
+        // P1 is calculated with the RoDTEP method (BCD offset to 0, SWS and IGST on the tariff
+        // BCD of 10,000); P2 pays standard BCD.
+        c.execute_batch(
+            r#"INSERT INTO duty_credit_entitlements (id, scheme, shipment_id, scrip_number, entitled_amount, issue_date, expiry_date)
+                 VALUES ('R1', 'RODTEP', 'SHP-1', 'SCRIP-1', 6000, '2024-03-15', NULL),
+                        ('D1', 'DRAWBACK', 'SHP-1', 'DBK-1', 5000, '2024-03-15', NULL);
+             INSERT INTO boe_calculations (id, shipment_id, boe_id, supplier_name, invoice_number, form_values_json, item_inputs_json, calculation_result_json)
+                 VALUES ('BC-1', 'SHP-1', NULL, 'Acme', 'INV-1',
+                   '{"supplierName":"Acme","shipmentId":"SHP-1","exchangeRate":80,"freightCost":0,"exwCost":0,"insuranceRate":0}',
+                   '[{"partNo":"P1","calculationMethod":"Rodtep","boeBcdRate":0,"boeSwsRate":10,"boeIgstRate":18},
+                     {"partNo":"P2","calculationMethod":"Standard","boeBcdRate":10,"boeSwsRate":10,"boeIgstRate":18}]',
+                   '{"calculatedItems":[
+                      {"partNo":"P1","description":"","assessableValue":100000,"bcdValue":0,"swsValue":1000,"igstValue":19980},
+                      {"partNo":"P2","description":"","assessableValue":100000,"bcdValue":10000,"swsValue":1000,"igstValue":19980}],
+                    "bcdTotal":10000,"swsTotal":2000,"igstTotal":39960,"interest":0,"customsDutyTotal":51960}');"#,
+        )
+        .unwrap();
+        let alloc = |id: &str, part_no: &str, amount: f64| DutyCreditAllocation {
+            entitlement_id: id.to_string(),
+            part_no: part_no.to_string(),
+            amount,
+        };
+
+        let err = apply_credits(&mut c, "BC-1", &[alloc("R1", "P1", 1.0)]).unwrap_err();
+        assert!(err.contains("RoDTEP method"), "{err}");
+
+        c.execute(
+            "UPDATE boe_calculations SET boe_type = 'INTO_BOND' WHERE id = 'BC-1'",
+            [],
+        )
+        .unwrap();
+        let err = apply_credits(&mut c, "BC-1", &[alloc("R1", "P2", 1.0)]).unwrap_err();
+        assert!(err.contains("into-bond"), "{err}");
+        c.execute(
+            "UPDATE boe_calculations SET boe_type = 'HOME_CONSUMPTION' WHERE id = 'BC-1'",
+            [],
+        )
+        .unwrap();
+
+        // Drawback may still pay the SWS on the RoDTEP line; the scrip goes to the standard line.
+        let applied = apply_credits(
+            &mut c,
+            "BC-1",
+            &[alloc("R1", "P2", 6000.0), alloc("D1", "P1", 1000.0)],
+        )
+        .unwrap();
+        assert_eq!(applied.duty_credit_total, 7_000.0);
+
+        let saved: crate::db::SavedBoe = c
+            .query_row(
+                "SELECT * FROM boe_calculations WHERE id = 'BC-1'",
+                [],
+                map_row_to_saved_boe,
+            )
+            .unwrap();
+        revalidate_utilisations(
+            &c,
+            "BC-1",
+            &saved.calculation_result.calculated_items,
+            &saved.item_inputs,
+        )
+        .unwrap();
+        assert_eq!(utilised_on_boe(&c, "BC-1").unwrap(), 7_000.0);
+    }
+}
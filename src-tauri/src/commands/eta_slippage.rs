@@ -0,0 +1,786 @@
+//! ETD / ETA change history, slippage analytics by supplier, shipping line, port and mode, and predicted ETAs.
+//!
+//! A shipment's slippage is measured from its original ETA (the first ETA in its change history,
+//! else the current one) to its `ARRIVED_AT_PORT` milestone, or to the current ETA while it
+//! is still on the water. The shipping line comes from the assigned free-time terms and the port
+//! from the BOE location, as shipments carry neither.
+
+use crate::commands::costing_snapshots;
+use crate::commands::dashboard_cache;
+use crate::commands::shipment_status;
+use crate::commands::tariff;
+use crate::db::DbState;
+use chrono::NaiveDate;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use tauri::State;
+
+pub const FIELD_ETD: &str = "ETD";
+pub const FIELD_ETA: &str = "ETA";
+
+pub const BY_SHIPPING_LINE: &str = "SHIPPING_LINE";
+pub const BY_SUPPLIER: &str = "SUPPLIER";
+pub const BY_PORT: &str = "PORT";
+pub const BY_MODE: &str = "MODE";
+pub const BY_ALL: &str = "ALL";
+pub const BY_NONE: &str = "NONE";
+
+pub const BASIS_ETA: &str = "ETA";
+pub const BASIS_PREDICTED: &str = "PREDICTED";
+const OVERDUE_BASIS_KEY: &str = "overdue_eta_basis";
+
+/// Arrived shipments a grouping needs before its average slippage is used for predictions.
+const MIN_SAMPLES: usize = 3;
+/// Groupings tried for a prediction, most specific first.
+const PREDICTION_ORDER: [&str; 5] = [BY_SHIPPING_LINE, BY_SUPPLIER, BY_PORT, BY_MODE, BY_ALL];
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduleChange {
+    pub id: i64,
+    pub shipment_id: String,
+    /// `ETD` or `ETA`.
+    pub field: String,
+    pub old_value: Option<String>,
+    pub new_value: Option<String>,
+    pub slip_days: Option<i64>,
+    pub source: String,
+    pub reason: Option<String>,
+    pub changed_by: Option<String>,
+    pub changed_at: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SlippageStat {
+    /// The supplier id, shipping line, port code or mode; `None` where the shipment has none.
+    pub key: Option<String>,
+    pub label: String,
+    pub shipments: u32,
+    pub arrived: u32,
+    pub eta_revisions: u32,
+    /// Original ETA to arrival, or to the current ETA while not yet arrived.
+    pub avg_slip_days: f64,
+    pub avg_arrival_slip_days: Option<f64>,
+    pub max_slip_days: i64,
+    /// Arrived on or before the original ETA, of those arrived.
+    pub on_time_percent: Option<f64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct EtaSlippageAnalytics {
+    pub overall: SlippageStat,
+    pub by_supplier: Vec<SlippageStat>,
+    pub by_shipping_line: Vec<SlippageStat>,
+    pub by_port: Vec<SlippageStat>,
+    pub by_mode: Vec<SlippageStat>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct EtaPrediction {
+    pub shipment_id: String,
+    pub original_eta: Option<String>,
+    pub current_eta: String,
+    /// Original ETA plus the expected slippage, never earlier than the current ETA.
+    pub predicted_eta: String,
+    pub expected_slip_days: Option<f64>,
+    /// `SHIPPING_LINE`, `SUPPLIER`, `PORT`, `MODE`, `ALL`, or `NONE` without enough history.
+    pub basis: String,
+    pub basis_key: Option<String>,
+    pub sample_size: u32,
+}
+
+/// One shipment's schedule and where it stands.
+struct ShipmentSlip {
+    shipment_id: String,
+    supplier_id: Option<String>,
+    supplier_name: Option<String>,
+    shipping_line: Option<String>,
+    port: Option<String>,
+    mode: Option<String>,
+    status: Option<String>,
+    original_eta: Option<NaiveDate>,
+    current_eta: Option<NaiveDate>,
+    arrived_on: Option<NaiveDate>,
+    eta_revisions: u32,
+}
+
+impl ShipmentSlip {
+    fn slip_days(&self) -> Option<i64> {
+        let original = self.original_eta?;
+        self.arrived_on
+            .or(self.current_eta)
+            .map(|d| (d - original).num_days())
+    }
+
+    fn arrival_slip_days(&self) -> Option<i64> {
+        Some((self.arrived_on? - self.original_eta?).num_days())
+    }
+
+    fn is_delivered(&self) -> bool {
+        self.status
+            .as_deref()
+            .and_then(shipment_status::normalize_status)
+            == Some(shipment_status::DELIVERED)
+    }
+
+    fn key(&self, dimension: &str) -> Option<String> {
+        match dimension {
+            BY_SUPPLIER => self.supplier_id.clone(),
+            BY_SHIPPING_LINE => self.shipping_line.clone(),
+            BY_PORT => self.port.clone(),
+            BY_MODE => self.mode.clone(),
+            _ => Some(BY_ALL.to_string()),
+        }
+    }
+}
+
+fn parse_date(raw: Option<&str>) -> Option<NaiveDate> {
+    let raw = raw?.trim();
+    let date = tariff::normalize_date(raw.get(..10).unwrap_or(raw))?;
+    NaiveDate::parse_from_str(&date, "%Y-%m-%d").ok()
+}
+
+fn blank_to_none(v: Option<&str>) -> Option<String> {
+    v.map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+}
+
+/// Logs the ETD / ETA values that differ from what the shipment holds now. Call before the
+/// shipment row is updated; returns the number of changes logged.
+pub fn record_schedule_change(
+    conn: &Connection,
+    shipment_id: &str,
+    new_etd: Option<&str>,
+    new_eta: Option<&str>,
+    source: &str,
+    reason: Option<&str>,
+    user_id: Option<&str>,
+) -> Result<u32, String> {
+    let current: Option<(Option<String>, Option<String>)> = conn
+        .query_row(
+            "SELECT etd, eta FROM shipments WHERE id = ?1",
+            params![shipment_id],
+            |r| Ok((r.get(0)?, r.get(1)?)),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+    let Some((old_etd, old_eta)) = current else {
+        return Err(format!("Shipment {shipment_id} not found"));
+    };
+    let mut logged = 0;
+    for (field, old, new) in [(FIELD_ETD, old_etd, new_etd), (FIELD_ETA, old_eta, new_eta)] {
+        let old = blank_to_none(old.as_deref());
+        let new = blank_to_none(new);
+        if old == new {
+            continue;
+        }
+        let slip = parse_date(old.as_deref())
+            .zip(parse_date(new.as_deref()))
+            .map(|(o, n)| (n - o).num_days());
+        conn.execute(
+            "INSERT INTO shipment_schedule_changes (shipment_id, field, old_value, new_value, slip_days, source, reason, changed_by)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                shipment_id,
+                field,
+                old,
+                new,
+                slip,
+                source,
+                blank_to_none(reason),
+                user_id
+            ],
+        )
+        .map_err(|e| e.to_string())?;
+        logged += 1;
+    }
+    Ok(logged)
+}
+
+pub fn list_changes(conn: &Connection, shipment_id: &str) -> Result<Vec<ScheduleChange>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, shipment_id, field, old_value, new_value, slip_days, source, reason, changed_by, changed_at
+             FROM shipment_schedule_changes WHERE shipment_id = ?1 ORDER BY id",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![shipment_id], |r| {
+            Ok(ScheduleChange {
+                id: r.get(0)?,
+                shipment_id: r.get(1)?,
+                field: r.get(2)?,
+                old_value: r.get(3)?,
+                new_value: r.get(4)?,
+                slip_days: r.get(5)?,
+                source: r.get(6)?,
+                reason: r.get(7)?,
+                changed_by: r.get(8)?,
+                changed_at: r.get(9)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())
+}
+
+fn load_slips(conn: &Connection) -> Result<Vec<ShipmentSlip>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT s.id, s.supplier_id, su.supplier_name, NULLIF(TRIM(ft.shipping_line), ''),
+                    (SELECT NULLIF(UPPER(TRIM(bd.location)), '') FROM boe_calculations bc
+                     JOIN boe_details bd ON bd.id = bc.boe_id WHERE bc.shipment_id = s.id LIMIT 1),
+                    NULLIF(LOWER(TRIM(s.shipment_mode)), ''), s.status, s.eta,
+                    (SELECT COALESCE(c.old_value, c.new_value) FROM shipment_schedule_changes c
+                     WHERE c.shipment_id = s.id AND c.field = 'ETA'
+                       AND COALESCE(c.old_value, c.new_value) IS NOT NULL
+                     ORDER BY c.id LIMIT 1),
+                    (SELECT COUNT(*) FROM shipment_schedule_changes c
+                     WHERE c.shipment_id = s.id AND c.field = 'ETA' AND c.old_value IS NOT NULL),
+                    (SELECT e.occurred_at FROM shipment_events e
+                     WHERE e.shipment_id = s.id AND e.event_type = 'ARRIVED_AT_PORT')
+             FROM shipments s
+             LEFT JOIN suppliers su ON su.id = s.supplier_id
+             LEFT JOIN shipment_free_time sft ON sft.shipment_id = s.id
+             LEFT JOIN free_time_terms ft ON ft.id = sft.terms_id",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], |r| {
+            let current_eta: Option<String> = r.get(7)?;
+            let first_eta: Option<String> = r.get(8)?;
+            let arrived: Option<String> = r.get(10)?;
+            let current = parse_date(current_eta.as_deref());
+            Ok(ShipmentSlip {
+                shipment_id: r.get(0)?,
+                supplier_id: r.get(1)?,
+                supplier_name: r.get(2)?,
+                shipping_line: r.get(3)?,
+                port: r.get(4)?,
+                mode: r.get(5)?,
+                status: r.get(6)?,
+                original_eta: parse_date(first_eta.as_deref()).or(current),
+                current_eta: current,
+                arrived_on: parse_date(arrived.as_deref()),
+                eta_revisions: r.get(9)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())
+}
+
+fn round1(v: f64) -> f64 {
+    (v * 10.0).round() / 10.0
+}
+
+fn stat_for(key: Option<String>, label: String, slips: &[&ShipmentSlip]) -> SlippageStat {
+    let slip: Vec<i64> = slips.iter().filter_map(|s| s.slip_days()).collect();
+    let arrival: Vec<i64> = slips.iter().filter_map(|s| s.arrival_slip_days()).collect();
+    let avg =
+        |v: &[i64]| (!v.is_empty()).then(|| round1(v.iter().sum::<i64>() as f64 / v.len() as f64));
+    SlippageStat {
+        key,
+        label,
+        shipments: slip.len() as u32,
+        arrived: arrival.len() as u32,
+        eta_revisions: slips.iter().map(|s| s.eta_revisions).sum(),
+        avg_slip_days: avg(&slip).unwrap_or(0.0),
+        avg_arrival_slip_days: avg(&arrival),
+        max_slip_days: slip.iter().copied().max().unwrap_or(0),
+        on_time_percent: (!arrival.is_empty()).then(|| {
+            round1(
+                arrival.iter().filter(|d| **d <= 0).count() as f64 / arrival.len() as f64 * 100.0,
+            )
+        }),
+    }
+}
+
+fn group_by(slips: &[ShipmentSlip], dimension: &str) -> Vec<SlippageStat> {
+    let mut groups: BTreeMap<Option<String>, Vec<&ShipmentSlip>> = BTreeMap::new();
+    for s in slips.iter().filter(|s| s.original_eta.is_some()) {
+        groups.entry(s.key(dimension)).or_default().push(s);
+    }
+    let mut stats: Vec<SlippageStat> = groups
+        .into_iter()
+        .map(|(key, members)| {
+            let label = match dimension {
+                BY_SUPPLIER => members[0].supplier_name.clone().or_else(|| key.clone()),
+                _ => key.clone(),
+            }
+            .unwrap_or_else(|| "Unknown".to_string());
+            stat_for(key, label, &members)
+        })
+        .collect();
+    stats.sort_by(|a, b| {
+        b.avg_slip_days
+            .total_cmp(&a.avg_slip_days)
+            .then_with(|| a.label.cmp(&b.label))
+    });
+    stats
+}
+
+pub fn analytics(conn: &Connection) -> Result<EtaSlippageAnalytics, String> {
+    let slips = load_slips(conn)?;
+    let all: Vec<&ShipmentSlip> = slips.iter().filter(|s| s.original_eta.is_some()).collect();
+    Ok(EtaSlippageAnalytics {
+        overall: stat_for(Some(BY_ALL.to_string()), "All shipments".to_string(), &all),
+        by_supplier: group_by(&slips, BY_SUPPLIER),
+        by_shipping_line: group_by(&slips, BY_SHIPPING_LINE),
+        by_port: group_by(&slips, BY_PORT),
+        by_mode: group_by(&slips, BY_MODE),
+    })
+}
+
+/// Arrival slippage of arrived shipments, summed once per grouping so each prediction is a lookup.
+struct SlipHistory(HashMap<(&'static str, String), (i64, usize)>);
+
+impl SlipHistory {
+    fn new(slips: &[ShipmentSlip]) -> Self {
+        let mut groups = HashMap::new();
+        for slip in slips {
+            let Some(days) = slip.arrival_slip_days() else {
+                continue;
+            };
+            for dimension in PREDICTION_ORDER {
+                if let Some(key) = slip.key(dimension) {
+                    let (sum, n) = groups.entry((dimension, key)).or_insert((0, 0));
+                    *sum += days;
+                    *n += 1;
+                }
+            }
+        }
+        Self(groups)
+    }
+}
+
+/// Only shipments that have not arrived get a prediction, so their own slippage is never a sample.
+fn predict_one(slip: &ShipmentSlip, history: &SlipHistory) -> Option<EtaPrediction> {
+    let current = slip.current_eta?;
+    if slip.arrived_on.is_some() {
+        return None;
+    }
+    let original = slip.original_eta.unwrap_or(current);
+    let basis = PREDICTION_ORDER.into_iter().find_map(|dimension| {
+        let key = slip.key(dimension)?;
+        let (sum, n) = *history.0.get(&(dimension, key.clone()))?;
+        (n >= MIN_SAMPLES).then(|| (dimension, key, sum as f64 / n as f64, n))
+    });
+    let predicted = match basis {
+        Some((_, _, avg, _)) => {
+            (original + chrono::Duration::days(avg.round() as i64)).max(current)
+        }
+        None => current,
+    };
+    let fmt = |d: NaiveDate| d.format("%Y-%m-%d").to_string();
+    Some(EtaPrediction {
+        shipment_id: slip.shipment_id.clone(),
+        original_eta: slip.original_eta.map(fmt),
+        current_eta: fmt(current),
+        predicted_eta: fmt(predicted),
+        expected_slip_days: basis.as_ref().map(|b| round1(b.2)),
+        basis: basis.as_ref().map_or(BY_NONE, |b| b.0).to_string(),
+        basis_key: basis.as_ref().map(|b| b.1.clone()),
+        sample_size: basis.map_or(0, |b| b.3 as u32),
+    })
+}
+
+pub fn predict(conn: &Connection, shipment_id: &str) -> Result<Option<EtaPrediction>, String> {
+    let slips = load_slips(conn)?;
+    let Some(slip) = slips.iter().find(|s| s.shipment_id == shipment_id) else {
+        return Err(format!("Shipment {shipment_id} not found"));
+    };
+    Ok(predict_one(slip, &SlipHistory::new(&slips)))
+}
+
+fn store_prediction(conn: &Connection, p: &EtaPrediction) -> Result<(), String> {
+    conn.execute(
+        "INSERT OR REPLACE INTO shipment_eta_predictions (shipment_id, original_eta, current_eta,
+            predicted_eta, expected_slip_days, basis, basis_key, sample_size)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        params![
+            p.shipment_id,
+            p.original_eta,
+            p.current_eta,
+            p.predicted_eta,
+            p.expected_slip_days,
+            p.basis,
+            p.basis_key,
+            p.sample_size
+        ],
+    )
+    .map(|_| ())
+    .map_err(|e| e.to_string())
+}
+
+/// Recomputes stored predictions for every shipment not yet arrived or delivered; returns how many
+/// are stored.
+pub fn refresh_predictions(conn: &Connection) -> Result<u32, String> {
+    let slips = load_slips(conn)?;
+    let history = SlipHistory::new(&slips);
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    tx.execute("DELETE FROM shipment_eta_predictions", [])
+        .map_err(|e| e.to_string())?;
+    let mut stored = 0;
+    for slip in slips.iter().filter(|s| !s.is_delivered()) {
+        if let Some(p) = predict_one(slip, &history) {
+            store_prediction(&tx, &p)?;
+            stored += 1;
+        }
+    }
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(stored)
+}
+
+/// Recomputes the stored prediction of one shipment, dropping it once the shipment has arrived or
+/// been delivered. Other shipments pick up its arrival at the next full refresh.
+pub fn refresh_shipment_prediction(conn: &Connection, shipment_id: &str) -> Result<(), String> {
+    let slips = load_slips(conn)?;
+    conn.execute(
+        "DELETE FROM shipment_eta_predictions WHERE shipment_id = ?1",
+        params![shipment_id],
+    )
+    .map_err(|e| e.to_string())?;
+    let prediction = slips
+        .iter()
+        .find(|s| s.shipment_id == shipment_id && !s.is_delivered())
+        .and_then(|s| predict_one(s, &SlipHistory::new(&slips)));
+    match prediction {
+        Some(p) => store_prediction(conn, &p),
+        None => Ok(()),
+    }
+}
+
+/// [`refresh_shipment_prediction`] after an ETD / ETA change or an arrival milestone; failures are
+/// only logged.
+pub fn refresh_after_change(conn: &Connection, shipment_id: &str) {
+    if let Err(e) = refresh_shipment_prediction(conn, shipment_id) {
+        log::warn!("ETA prediction refresh for shipment {shipment_id} failed: {e}");
+    }
+}
+
+/// Nightly full recompute, so open shipments learn from arrivals recorded during the day.
+pub fn run_daily_refresh(conn: &Connection) {
+    if let Err(e) = refresh_predictions(conn) {
+        log::warn!("Daily ETA prediction refresh failed: {e}");
+    }
+}
+
+pub fn overdue_basis(conn: &Connection) -> &'static str {
+    let value: Option<String> = conn
+        .query_row(
+            "SELECT value FROM app_metadata WHERE key = ?1",
+            params![OVERDUE_BASIS_KEY],
+            |r| r.get(0),
+        )
+        .ok();
+    match value.as_deref().map(str::trim) {
+        Some(v) if v.eq_ignore_ascii_case(BASIS_PREDICTED) => BASIS_PREDICTED,
+        _ => BASIS_ETA,
+    }
+}
+
+/// SQL condition on shipment alias `s` for "the expected arrival date has passed": the ETA, or the
+/// stored predicted ETA when the overdue basis is `PREDICTED`.
+pub fn overdue_eta_condition(conn: &Connection) -> &'static str {
+    if overdue_basis(conn) == BASIS_PREDICTED {
+        "date(COALESCE((SELECT p.predicted_eta FROM shipment_eta_predictions p WHERE p.shipment_id = s.id), s.eta)) < date('now')"
+    } else {
+        "date(s.eta) < date('now')"
+    }
+}
+
+/// Changes ETD / ETA outside a full shipment edit (carrier or forwarder updates) and logs them.
+/// A date left out keeps its current value; frozen shipments are refused like any other edit.
+#[tauri::command]
+pub fn update_shipment_schedule(
+    shipment_id: String,
+    etd: Option<String>,
+    eta: Option<String>,
+    source: Option<String>,
+    reason: Option<String>,
+    user_id: Option<String>,
+    state: State<DbState>,
+) -> Result<Vec<ScheduleChange>, String> {
+    let normalize = |label: &str, v: Option<String>| -> Result<Option<String>, String> {
+        match blank_to_none(v.as_deref()) {
+            None => Ok(None),
+            Some(raw) => tariff::normalize_date(&raw)
+                .map(Some)
+                .ok_or_else(|| format!("Invalid {label} '{raw}'")),
+        }
+    };
+    let etd = normalize("ETD", etd)?;
+    let eta = normalize("ETA", eta)?;
+    let source = blank_to_none(source.as_deref())
+        .map(|s| s.to_uppercase())
+        .unwrap_or_else(|| shipment_status::SOURCE_MANUAL.to_string());
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    let (current_etd, current_eta): (Option<String>, Option<String>) = conn
+        .query_row(
+            "SELECT etd, eta FROM shipments WHERE id = ?1",
+            params![shipment_id],
+            |r| Ok((r.get(0)?, r.get(1)?)),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Shipment {shipment_id} not found"))?;
+    costing_snapshots::ensure_not_frozen(&conn, &shipment_id)?;
+    let etd = etd.or(current_etd);
+    let eta = eta.or(current_eta);
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    let logged = record_schedule_change(
+        &tx,
+        &shipment_id,
+        etd.as_deref(),
+        eta.as_deref(),
+        &source,
+        reason.as_deref(),
+        user_id.as_deref(),
+    )?;
+    if logged == 0 {
+        return list_changes(&conn, &shipment_id);
+    }
+    tx.execute(
+        "UPDATE shipments SET etd = ?2, eta = ?3 WHERE id = ?1",
+        params![shipment_id, etd, eta],
+    )
+    .map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+    refresh_after_change(&conn, &shipment_id);
+    let _ = dashboard_cache::invalidate_dashboard_metrics_cache(&conn);
+    list_changes(&conn, &shipment_id)
+}
+
+#[tauri::command]
+pub fn get_shipment_schedule_changes(
+    shipment_id: String,
+    state: State<DbState>,
+) -> Result<Vec<ScheduleChange>, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    list_changes(&conn, &shipment_id)
+}
+
+#[tauri::command]
+pub fn get_eta_slippage_analytics(state: State<DbState>) -> Result<EtaSlippageAnalytics, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    analytics(&conn)
+}
+
+/// `None` once the shipment has arrived or while it has no ETA.
+#[tauri::command]
+pub fn get_predicted_eta(
+    shipment_id: String,
+    state: State<DbState>,
+) -> Result<Option<EtaPrediction>, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    predict(&conn, &shipment_id)
+}
+
+#[tauri::command]
+pub fn refresh_eta_predictions(state: State<DbState>) -> Result<u32, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    let stored = refresh_predictions(&conn)?;
+    let _ = dashboard_cache::invalidate_dashboard_metrics_cache(&conn);
+    Ok(stored)
+}
+
+#[tauri::command]
+pub fn get_overdue_eta_basis(state: State<DbState>) -> Result<String, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    Ok(overdue_basis(&conn).to_string())
+}
+
+/// `ETA` or `PREDICTED`; switching to predicted ETAs refreshes them first.
+#[tauri::command]
+pub fn save_overdue_eta_basis(basis: String, state: State<DbState>) -> Result<String, String> {
+    let basis = basis.trim().to_uppercase();
+    if basis != BASIS_ETA && basis != BASIS_PREDICTED {
+        return Err(format!(
+            "Overdue basis must be {BASIS_ETA} or {BASIS_PREDICTED}"
+        ));
+    }
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    if basis == BASIS_PREDICTED {
+        refresh_predictions(&conn)?;
+    }
+    conn.execute(
+        "INSERT INTO app_metadata (key, value) VALUES (?1, ?2)
+         ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+        params![OVERDUE_BASIS_KEY, basis],
+    )
+    .map_err(|e| e.to_string())?;
+    let _ = dashboard_cache::invalidate_dashboard_metrics_cache(&conn);
+    Ok(basis)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::test_support;
+
+    #[test]
+    fn slippage_is_tracked_and_drives_predicted_and_overdue_etas() {
+        let conn = test_support::migrated_db();
+        for (id, mode, status, eta) in [
+            ("SHP-1", "Sea", "delivered", "2024-02-01"),
+            ("SHP-2", "Sea", "delivered", "2024-02-10"),
+            ("SHP-3", "sea", "delivered", "2024-03-01"),
+            ("SHP-4", "Sea", "in-transit", "2024-04-01"),
+        ] {
+            test_support::add_shipment(&conn, id, "2024-01-01", 1.0, "USD", status);
+            conn.execute(
+                "UPDATE shipments SET shipment_mode = ?2, eta = ?3 WHERE id = ?1",
+                params![id, mode, eta],
+            )
+            .unwrap();
+        }
+        conn.execute_batch(
+            "INSERT INTO shipment_events (shipment_id, event_type, occurred_at)
+                VALUES ('SHP-1', 'ARRIVED_AT_PORT', '2024-02-05 10:00:00'),
+                       ('SHP-2', 'ARRIVED_AT_PORT', '2024-02-16 10:00:00'),
+                       ('SHP-3', 'ARRIVED_AT_PORT', '2024-03-08 10:00:00');",
+        )
+        .unwrap();
+
+        // SHP-1 was first due on 2024-01-28; the line then slipped it to 2024-02-01.
+        conn.execute(
+            "UPDATE shipments SET eta = '2024-01-28' WHERE id = 'SHP-1'",
+            [],
+        )
+        .unwrap();
+        assert_eq!(
+            record_schedule_change(
+                &conn,
+                "SHP-1",
+                None,
+                Some("2024-02-01"),
+                "CARRIER",
+                Some("Port congestion"),
+                None
+            )
+            .unwrap(),
+            1
+        );
+        conn.execute(
+            "UPDATE shipments SET eta = '2024-02-01' WHERE id = 'SHP-1'",
+            [],
+        )
+        .unwrap();
+        assert_eq!(
+            record_schedule_change(
+                &conn,
+                "SHP-1",
+                None,
+                Some(" 2024-02-01 "),
+                "MANUAL",
+                None,
+                None
+            )
+            .unwrap(),
+            0
+        );
+        let changes = list_changes(&conn, "SHP-1").unwrap();
+        assert_eq!(changes.len(), 1);
+        assert_eq!(
+            (changes[0].field.as_str(), changes[0].slip_days),
+            (FIELD_ETA, Some(4))
+        );
+
+        // Arrival slips of 8, 6 and 7 days against the original ETAs.
+        let stats = analytics(&conn).unwrap();
+        assert_eq!(stats.overall.arrived, 3);
+        assert_eq!(stats.overall.avg_arrival_slip_days, Some(7.0));
+        assert_eq!(stats.overall.eta_revisions, 1);
+        assert_eq!(stats.by_mode.len(), 1);
+        assert_eq!(stats.by_supplier[0].label, "Acme");
+        assert_eq!(stats.by_shipping_line[0].label, "Unknown");
+
+        let p = predict(&conn, "SHP-4").unwrap().unwrap();
+        assert_eq!(
+            (p.basis.as_str(), p.basis_key.as_deref()),
+            (BY_SUPPLIER, Some("SUP-1"))
+        );
+        assert_eq!((p.predicted_eta.as_str(), p.sample_size), ("2024-04-08", 3));
+        assert!(predict(&conn, "SHP-1").unwrap().is_none());
+
+        assert_eq!(overdue_eta_condition(&conn), "date(s.eta) < date('now')");
+        conn.execute(
+            "UPDATE app_metadata SET value = 'PREDICTED' WHERE key = 'overdue_eta_basis'",
+            [],
+        )
+        .unwrap();
+        assert_eq!(refresh_predictions(&conn).unwrap(), 1);
+        // The ETA has passed but the predicted one has not, so the shipment is not overdue.
+        conn.execute(
+            "UPDATE shipment_eta_predictions SET predicted_eta = '2999-01-01' WHERE shipment_id = 'SHP-4'",
+            [],
+        )
+        .unwrap();
+        let overdue: i64 = conn
+            .query_row(
+                &format!(
+                    "SELECT COUNT(*) FROM shipments s WHERE s.status = 'in-transit' AND {}",
+                    overdue_eta_condition(&conn)
+                ),
+                [],
+                |r| r.get(0),
+            )
+            .unwrap();
+        assert_eq!(overdue, 0);
+    }
+
+    #[test]
+    fn original_eta_of_shipment_created_without_one_is_its_first_eta() {
+        let conn = test_support::migrated_db();
+        test_support::add_shipment(&conn, "SHP-1", "2024-01-01", 1.0, "USD", "in-transit");
+        let set_eta = |eta: &str| {
+            record_schedule_change(&conn, "SHP-1", None, Some(eta), "MANUAL", None, None).unwrap();
+            conn.execute(
+                "UPDATE shipments SET eta = ?1 WHERE id = 'SHP-1'",
+                params![eta],
+            )
+            .unwrap();
+        };
+        set_eta("2024-03-01");
+        set_eta("2024-03-06");
+        set_eta("2024-03-10");
+
+        let stats = analytics(&conn).unwrap();
+        assert_eq!(stats.overall.avg_slip_days, 9.0);
+        assert_eq!(stats.overall.eta_revisions, 2);
+
+        refresh_shipment_prediction(&conn, "SHP-1").unwrap();
+        let stored: (String, String) = conn
+            .query_row(
+                "SELECT original_eta, predicted_eta FROM shipment_eta_predictions WHERE shipment_id = 'SHP-1'",
+                [],
+                |r| Ok((r.get(0)?, r.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(stored, ("2024-03-01".to_string(), "2024-03-10".to_string()));
+
+        // Once the shipment arrives it no longer carries a prediction.
+        conn.execute(
+            "INSERT INTO shipment_events (shipment_id, event_type, occurred_at) VALUES ('SHP-1', 'ARRIVED_AT_PORT', '2024-03-12 08:00:00')",
+            [],
+        )
+        .unwrap();
+        refresh_shipment_prediction(&conn, "SHP-1").unwrap();
+        let remaining: i64 = conn
+            .query_row("SELECT COUNT(*) FROM shipment_eta_predictions", [], |r| {
+                r.get(0)
+            })
+            .unwrap();
+        assert_eq!(remaining, 0);
+        assert_eq!(
+            analytics(&conn).unwrap().overall.avg_arrival_slip_days,
+            Some(11.0)
+        );
+    }
+}
@@ -400,6 +400,14 @@ pub fn run_workflow_timeout_scan(conn: &Connection) -> Result<i32, String> {
     Ok(n)
 }
 
+/// Case types whose owning sync sets the priority from live data (free time escalates to CRITICAL
+/// once charges accrue, missing documents to HIGH once arrival is due); the age / SLA score below
+/// would otherwise demote them.
+const SYNC_OWNED_PRIORITY: &[&str] = &[
+    crate::commands::demurrage::FREE_TIME_EXPIRING,
+    crate::commands::shipment_documents::MISSING_DOCUMENTS,
+];
+
 pub fn recalculate_open_exception_priorities(conn: &Connection) -> Result<i32, String> {
     let mut stmt = conn
         .prepare(
@@ -429,6 +437,9 @@ pub fn recalculate_open_exception_priorities(conn: &Connection) -> Result<i32, S
 
     let mut updated = 0i32;
     for (id, et, sla, age, rec, cur) in rows {
+        if SYNC_OWNED_PRIORITY.contains(&et.as_str()) {
+            continue;
+        }
         let mut score: i32 = 0;
         if sla == "BREACHED" {
             score += 4;
@@ -732,9 +743,12 @@ pub fn simulate_exception_load(
     })
 }
 
-/// Chained maintenance: revalidate, SLA refresh, integrity scan, escalation, priorities, timeouts, snapshots.
+/// Chained maintenance: free-time accrual, document checklist review, ETA predictions, revalidate, SLA refresh, integrity scan, escalation, priorities, timeouts, snapshots.
 /// Returns count of new integrity issues logged this run.
 pub fn run_daily_exception_workflow_maintenance(conn: &Connection) -> Result<i64, String> {
+    crate::commands::demurrage::run_daily_accrual(conn);
+    crate::commands::shipment_documents::run_daily_review(conn);
+    crate::commands::eta_slippage::run_daily_refresh(conn);
     revalidate_open_exceptions(conn)?;
     refresh_all_open_exception_sla(conn)?;
     let integrity_new = validate_exception_integrity(conn)?;
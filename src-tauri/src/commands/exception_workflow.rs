@@ -1,6 +1,7 @@
 //! Entity-level shipment exceptions, SLA, resolution, lifecycle, notes.
 
 use crate::commands::dashboard_cache;
+use crate::commands::eta_slippage;
 use crate::commands::exception_reliability;
 use crate::commands::utils::dashboard_activity_checksum;
 use crate::db::DbState;
@@ -127,6 +128,12 @@ fn sla_days_for_type(exception_type: &str) -> i64 {
     match exception_type {
         "OVERDUE_ETA" => 2,
         "MISSING_BOE" | "MISSING_EXPENSE" => 1,
+        "BOE_MISMATCH" => 3,
+        "GST_2B_MISMATCH" => 5,
+        "FREE_TIME_EXPIRING" => 1,
+        "PO_VARIANCE" => 3,
+        "GRN_VARIANCE" => 2,
+        "MISSING_DOCUMENTS" => 1,
         _ => 1,
     }
 }
@@ -237,92 +244,133 @@ fn reconcile_cases_for_ids(
         .collect();
 
     for sid in stale_ids {
-        conn.execute(
-            "UPDATE exception_cases SET status = 'RESOLVED', resolved_at = ?2, resolved_by = 'system', updated_at = ?2 WHERE id = ?1",
-            params![&sid, &now],
-        )
-        .map_err(|e| e.to_string())?;
-        let _ = insert_lifecycle(conn, &sid, "RESOLVED", Some("system"), "Auto-resolved: condition cleared");
-        let rid = Uuid::new_v4().to_string();
-        let (et, eid): (String, String) = conn
-            .query_row(
-                "SELECT exception_type, entity_id FROM exception_cases WHERE id = ?1",
-                params![&sid],
-                |r| Ok((r.get(0)?, r.get(1)?)),
-            )
-            .map_err(|e| e.to_string())?;
-        conn.execute(
-            "INSERT INTO exception_resolution_log (resolution_id, exception_case_id, exception_type, entity_id, status, resolved_by, resolved_at, notes)
-             VALUES (?1, ?2, ?3, ?4, 'RESOLVED', 'system', ?5, 'Auto-resolved')",
-            params![&rid, &sid, et, eid, &now],
-        )
-        .map_err(|e| e.to_string())?;
+        resolve_case_as_system(conn, &sid, &now)?;
     }
 
     for entity_id in current_ids {
-        let existing: Option<String> = conn
-            .query_row(
-                "SELECT id FROM exception_cases WHERE exception_type = ?1 AND entity_id = ?2 AND status IN ('OPEN', 'IN_PROGRESS') LIMIT 1",
-                params![exception_type, entity_id],
-                |r| r.get(0),
-            )
-            .optional()
-            .map_err(|e| e.to_string())?;
-
-        if existing.is_some() {
-            continue;
-        }
-
-        if let Err(e) = exception_reliability::ensure_no_duplicate_open_case(
-            conn,
-            exception_type,
-            entity_id,
-        ) {
-            let _ = exception_reliability::log_integrity_issue(
-                conn,
-                "",
-                "DUPLICATE_GUARD",
-                &e.to_string(),
-            );
-            continue;
-        }
-        if let Err(e) = exception_reliability::assert_shipment_entity_exists(conn, entity_id) {
-            let _ = exception_reliability::log_integrity_issue(
-                conn,
-                "",
-                "MISSING_ENTITY_ON_CREATE",
-                &e.to_string(),
-            );
-            continue;
-        }
-
-        let id = Uuid::new_v4().to_string();
-        let deadline = compute_sla_deadline(conn, exception_type)?;
-        let sla = compute_sla_status(Some(&deadline));
         let priority = if exception_type == "OVERDUE_ETA" {
             "HIGH"
         } else {
             "MEDIUM"
         };
-        conn.execute(
-            "INSERT INTO exception_cases (id, exception_type, entity_type, entity_id, status, priority, created_at, updated_at, sla_deadline, sla_status)
-             VALUES (?1, ?2, 'shipment', ?3, 'OPEN', ?4, strftime('%Y-%m-%d %H:%M:%S', 'now', 'localtime'), strftime('%Y-%m-%d %H:%M:%S', 'now', 'localtime'), ?5, ?6)",
-            params![&id, exception_type, entity_id, priority, &deadline, sla],
-        )
-        .map_err(|e| e.to_string())?;
-        insert_lifecycle(
+        open_case_for_entity(
             conn,
-            &id,
-            "CREATED",
-            None,
+            exception_type,
+            entity_id,
+            priority,
             &format!("{{\"exceptionType\":\"{exception_type}\"}}"),
         )?;
-        exception_reliability::bump_recurrence_on_new_open(conn, &id, exception_type, entity_id)?;
     }
 
     Ok(())
 }
 
+fn resolve_case_as_system(conn: &Connection, case_id: &str, now: &str) -> Result<(), String> {
+    conn.execute(
+        "UPDATE exception_cases SET status = 'RESOLVED', resolved_at = ?2, resolved_by = 'system', updated_at = ?2 WHERE id = ?1",
+        params![case_id, now],
+    )
+    .map_err(|e| e.to_string())?;
+    let _ = insert_lifecycle(conn, case_id, "RESOLVED", Some("system"), "Auto-resolved: condition cleared");
+    let rid = Uuid::new_v4().to_string();
+    let (et, eid): (String, String) = conn
+        .query_row(
+            "SELECT exception_type, entity_id FROM exception_cases WHERE id = ?1",
+            params![case_id],
+            |r| Ok((r.get(0)?, r.get(1)?)),
+        )
+        .map_err(|e| e.to_string())?;
+    conn.execute(
+        "INSERT INTO exception_resolution_log (resolution_id, exception_case_id, exception_type, entity_id, status, resolved_by, resolved_at, notes)
+         VALUES (?1, ?2, ?3, ?4, 'RESOLVED', 'system', ?5, 'Auto-resolved')",
+        params![&rid, case_id, et, eid, now],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Id of the open or in-progress case of this type for the entity, without touching it.
+pub(crate) fn open_case_id_for_entity(
+    conn: &Connection,
+    exception_type: &str,
+    entity_id: &str,
+) -> Result<Option<String>, String> {
+    conn.query_row(
+        "SELECT id FROM exception_cases WHERE exception_type = ?1 AND entity_id = ?2 AND status IN ('OPEN', 'IN_PROGRESS') LIMIT 1",
+        params![exception_type, entity_id],
+        |r| r.get(0),
+    )
+    .optional()
+    .map_err(|e| e.to_string())
+}
+
+/// Opens a shipment case of `exception_type` unless one is already open; returns the open case id
+/// (`None` when the duplicate or missing-entity guards refuse to create one). `details` is the JSON
+/// recorded on the CREATED lifecycle event.
+pub(crate) fn open_case_for_entity(
+    conn: &Connection,
+    exception_type: &str,
+    entity_id: &str,
+    priority: &str,
+    details: &str,
+) -> Result<Option<String>, String> {
+    let existing = open_case_id_for_entity(conn, exception_type, entity_id)?;
+
+    if existing.is_some() {
+        return Ok(existing);
+    }
+
+    if let Err(e) = exception_reliability::ensure_no_duplicate_open_case(
+        conn,
+        exception_type,
+        entity_id,
+    ) {
+        let _ = exception_reliability::log_integrity_issue(
+            conn,
+            "",
+            "DUPLICATE_GUARD",
+            &e.to_string(),
+        );
+        return Ok(None);
+    }
+    if let Err(e) = exception_reliability::assert_shipment_entity_exists(conn, entity_id) {
+        let _ = exception_reliability::log_integrity_issue(
+            conn,
+            "",
+            "MISSING_ENTITY_ON_CREATE",
+            &e.to_string(),
+        );
+        return Ok(None);
+    }
+
+    let id = Uuid::new_v4().to_string();
+    let deadline = compute_sla_deadline(conn, exception_type)?;
+    let sla = compute_sla_status(Some(&deadline));
+    conn.execute(
+        "INSERT INTO exception_cases (id, exception_type, entity_type, entity_id, status, priority, created_at, updated_at, sla_deadline, sla_status)
+         VALUES (?1, ?2, 'shipment', ?3, 'OPEN', ?4, strftime('%Y-%m-%d %H:%M:%S', 'now', 'localtime'), strftime('%Y-%m-%d %H:%M:%S', 'now', 'localtime'), ?5, ?6)",
+        params![&id, exception_type, entity_id, priority, &deadline, sla],
+    )
+    .map_err(|e| e.to_string())?;
+    insert_lifecycle(conn, &id, "CREATED", None, details)?;
+    exception_reliability::bump_recurrence_on_new_open(conn, &id, exception_type, entity_id)?;
+    Ok(Some(id))
+}
+
+/// Resolves the open case of `exception_type` for one shipment once its condition has cleared.
+pub(crate) fn auto_resolve_case_for_entity(
+    conn: &Connection,
+    exception_type: &str,
+    entity_id: &str,
+) -> Result<bool, String> {
+    let Some(case_id) = open_case_id_for_entity(conn, exception_type, entity_id)? else {
+        return Ok(false);
+    };
+    let now = chrono::Utc::now().format("%Y-%m-%d %H:%M:%S").to_string();
+    resolve_case_as_system(conn, &case_id, &now)?;
+    Ok(true)
+}
+
 pub fn refresh_all_open_exception_sla(conn: &Connection) -> Result<(), String> {
     conn.execute(
         "UPDATE exception_cases SET sla_status = CASE
@@ -349,8 +397,9 @@ pub fn sync_exception_cases_for_shipment_scope(
          AND s.eta IS NOT NULL AND TRIM(s.eta) != ''
          AND s.status IS NOT NULL AND LOWER(s.status) NOT IN ('delivered', 'completed')
          AND length(s.eta) >= 10 AND s.eta GLOB '[0-9][0-9][0-9][0-9]-[0-9][0-9]-[0-9][0-9]'
-         AND date(s.eta) < date('now')",
-        w = w
+         AND {overdue_cond}",
+        w = w,
+        overdue_cond = eta_slippage::overdue_eta_condition(conn)
     );
     let overdue_ids = query_shipment_ids(conn, &overdue_sql, p_ship)?;
     reconcile_cases_for_ids(conn, "OVERDUE_ETA", &overdue_ids)?;
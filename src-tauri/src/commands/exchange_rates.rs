@@ -0,0 +1,578 @@
+//! Customs exchange-rate master: CBIC fortnightly notification import, BE-date resolution, mismatch checks.
+
+use crate::commands::dashboard_cache;
+use crate::commands::tariff::{be_date_for_boe, normalize_date, today};
+use crate::db::{DbState, FormValues};
+use crate::utils::csv;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+/// Rates are notified to two decimals; anything closer is the same rate.
+const RATE_TOLERANCE: f64 = 0.005;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CustomsExchangeRate {
+    pub id: i64,
+    pub currency: String,
+    /// Rates are quoted per `unit` units of currency (e.g. 100 for JPY in Schedule II).
+    pub unit: i64,
+    pub import_rate: f64,
+    pub export_rate: Option<f64>,
+    pub effective_from: String,
+    pub effective_to: Option<String>,
+    pub notification_ref: Option<String>,
+}
+
+impl CustomsExchangeRate {
+    /// INR for one unit of currency.
+    pub fn per_unit_import_rate(&self) -> f64 {
+        self.import_rate / self.unit as f64
+    }
+}
+
+/// Rate applicable to a shipment: `rate` is INR per one unit of `currency` on `as_of`.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ResolvedExchangeRate {
+    pub shipment_id: String,
+    pub currency: String,
+    pub as_of: String,
+    pub rate: f64,
+    pub notification_ref: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ExchangeRateMismatch {
+    pub saved_boe_id: String,
+    pub shipment_id: String,
+    pub invoice_number: String,
+    pub currency: String,
+    pub as_of: String,
+    pub used_rate: f64,
+    pub notified_rate: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ExchangeRateImportSummary {
+    pub inserted: u32,
+    pub updated: u32,
+    pub errors: Vec<String>,
+}
+
+/// One rate from a notification file. JSON uses camelCase keys; CSV uses the snake_case aliases below.
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+struct NotifiedRateInput {
+    currency: String,
+    unit: Option<i64>,
+    import_rate: Option<f64>,
+    export_rate: Option<f64>,
+    effective_from: Option<String>,
+    effective_to: Option<String>,
+    notification_ref: Option<String>,
+}
+
+/// `{ "notificationRef", "effectiveFrom", "effectiveTo", "rates": [...] }` — header values apply to every rate.
+#[derive(Debug, Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+struct NotificationJson {
+    notification_ref: Option<String>,
+    effective_from: Option<String>,
+    effective_to: Option<String>,
+    rates: Vec<NotifiedRateInput>,
+}
+
+const SELECT_COLUMNS: &str =
+    "id, currency, unit, import_rate, export_rate, effective_from, effective_to, notification_ref";
+
+fn map_rate_row(row: &rusqlite::Row) -> rusqlite::Result<CustomsExchangeRate> {
+    Ok(CustomsExchangeRate {
+        id: row.get(0)?,
+        currency: row.get(1)?,
+        unit: row.get(2)?,
+        import_rate: row.get(3)?,
+        export_rate: row.get(4)?,
+        effective_from: row.get(5)?,
+        effective_to: row.get(6)?,
+        notification_ref: row.get(7)?,
+    })
+}
+
+fn normalize_currency(code: &str) -> Option<String> {
+    let c = code.trim().to_uppercase();
+    (c.len() == 3 && c.chars().all(|ch| ch.is_ascii_alphabetic())).then_some(c)
+}
+
+/// Notified rate in force for `currency` on `as_of` (`YYYY-MM-DD`).
+pub fn rate_as_of(
+    conn: &Connection,
+    currency: &str,
+    as_of: &str,
+) -> rusqlite::Result<Option<CustomsExchangeRate>> {
+    let Some(cur) = normalize_currency(currency) else {
+        return Ok(None);
+    };
+    conn.query_row(
+        &format!(
+            "SELECT {SELECT_COLUMNS} FROM customs_exchange_rates
+             WHERE currency = ?1
+               AND date(effective_from) <= date(?2)
+               AND (effective_to IS NULL OR date(effective_to) >= date(?2))
+             ORDER BY date(effective_from) DESC
+             LIMIT 1"
+        ),
+        params![cur, as_of],
+        map_rate_row,
+    )
+    .optional()
+}
+
+/// Latest BE date that fixes the shipment's exchange rate. Ex-bond BEs keep the into-bond rate,
+/// so only home-consumption and into-bond BEs count.
+fn valuation_be_date(conn: &Connection, shipment_id: &str) -> Option<String> {
+    conn.query_row(
+        "SELECT MAX(bd.be_date) FROM boe_calculations bc
+         JOIN boe_details bd ON bd.id = bc.boe_id
+         WHERE bc.shipment_id = ?1 AND COALESCE(bc.boe_type, 'HOME_CONSUMPTION') != 'EX_BOND'",
+        params![shipment_id],
+        |r| r.get::<_, Option<String>>(0),
+    )
+    .ok()
+    .flatten()
+}
+
+/// Applicable rate for the shipment's `invoice_currency` on `be_date` (or its linked BE date, else today).
+/// INR invoices resolve to 1.0; `None` when the currency has no notified rate for that date.
+pub fn resolve_for_shipment(
+    conn: &Connection,
+    shipment_id: &str,
+    be_date: Option<&str>,
+) -> Result<Option<ResolvedExchangeRate>, String> {
+    let currency: Option<String> = conn
+        .query_row(
+            "SELECT invoice_currency FROM shipments WHERE id = ?1",
+            params![shipment_id],
+            |r| r.get(0),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+    let Some(currency) = currency else {
+        return Ok(None);
+    };
+    let currency = currency.trim().to_uppercase();
+    let as_of = be_date
+        .map(str::to_string)
+        .or_else(|| valuation_be_date(conn, shipment_id))
+        .unwrap_or_else(today);
+    if currency == "INR" {
+        return Ok(Some(ResolvedExchangeRate {
+            shipment_id: shipment_id.to_string(),
+            currency,
+            as_of,
+            rate: 1.0,
+            notification_ref: None,
+        }));
+    }
+    let rate = rate_as_of(conn, &currency, &as_of).map_err(|e| e.to_string())?;
+    Ok(rate.map(|r| ResolvedExchangeRate {
+        shipment_id: shipment_id.to_string(),
+        currency,
+        as_of,
+        rate: r.per_unit_import_rate(),
+        notification_ref: r.notification_ref,
+    }))
+}
+
+pub fn rates_differ(a: f64, b: f64) -> bool {
+    (a - b).abs() > RATE_TOLERANCE
+}
+
+/// Overwrites `form.exchange_rate` with the notified rate when one exists; otherwise the
+/// entered rate stands (currencies not in the schedule, or no notification imported yet).
+pub fn apply_notified_rate(
+    conn: &Connection,
+    shipment_id: &str,
+    be_date: Option<&str>,
+    form: &mut FormValues,
+) -> Result<(), String> {
+    if let Some(resolved) = resolve_for_shipment(conn, shipment_id, be_date)? {
+        if rates_differ(form.exchange_rate, resolved.rate) {
+            log::info!(
+                "Shipment {}: exchange rate {} replaced by notified {} rate {} for {}",
+                shipment_id,
+                form.exchange_rate,
+                resolved.currency,
+                resolved.rate,
+                resolved.as_of
+            );
+        }
+        form.exchange_rate = resolved.rate;
+    }
+    Ok(())
+}
+
+/// SQL expression for the INR-per-unit rate of shipment alias `s` on its BE date (today when no BE is
+/// linked); NULL when no rate is notified. For aggregate queries that cannot call [`resolve_for_shipment`].
+pub fn shipment_inr_rate_sql() -> &'static str {
+    "(CASE WHEN UPPER(TRIM(s.invoice_currency)) = 'INR' THEN 1.0 ELSE (
+        SELECT cer.import_rate / cer.unit FROM customs_exchange_rates cer
+        WHERE cer.currency = UPPER(TRIM(s.invoice_currency))
+          AND date(cer.effective_from) <= date(COALESCE(
+                (SELECT MAX(bd.be_date) FROM boe_calculations bc
+                 JOIN boe_details bd ON bd.id = bc.boe_id
+                 WHERE bc.shipment_id = s.id AND COALESCE(bc.boe_type, 'HOME_CONSUMPTION') != 'EX_BOND'),
+                date('now', 'localtime')))
+          AND (cer.effective_to IS NULL OR date(cer.effective_to) >= date(COALESCE(
+                (SELECT MAX(bd.be_date) FROM boe_calculations bc
+                 JOIN boe_details bd ON bd.id = bc.boe_id
+                 WHERE bc.shipment_id = s.id AND COALESCE(bc.boe_type, 'HOME_CONSUMPTION') != 'EX_BOND'),
+                date('now', 'localtime'))))
+        ORDER BY date(cer.effective_from) DESC LIMIT 1) END)"
+}
+
+/// Saved BOE calculations whose `exchangeRate` differs from the notified rate for their BE date.
+/// Ex-bond BOEs keep the into-bond rate on purpose and are checked through their into-bond BOE.
+pub fn exchange_rate_mismatches(conn: &Connection) -> Result<Vec<ExchangeRateMismatch>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, shipment_id, boe_id, invoice_number,
+                    CAST(json_extract(form_values_json, '$.exchangeRate') AS REAL)
+             FROM boe_calculations
+             WHERE COALESCE(boe_type, 'HOME_CONSUMPTION') != 'EX_BOND'",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], |r| {
+            Ok((
+                r.get::<_, String>(0)?,
+                r.get::<_, String>(1)?,
+                r.get::<_, Option<String>>(2)?,
+                r.get::<_, String>(3)?,
+                r.get::<_, Option<f64>>(4)?,
+            ))
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    let mut out = Vec::new();
+    for (saved_boe_id, shipment_id, boe_id, invoice_number, used) in rows {
+        let Some(used_rate) = used else { continue };
+        let be_date = be_date_for_boe(conn, boe_id.as_deref());
+        let Some(resolved) = resolve_for_shipment(conn, &shipment_id, be_date.as_deref())? else {
+            continue;
+        };
+        if rates_differ(used_rate, resolved.rate) {
+            out.push(ExchangeRateMismatch {
+                saved_boe_id,
+                shipment_id,
+                invoice_number,
+                currency: resolved.currency,
+                as_of: resolved.as_of,
+                used_rate,
+                notified_rate: resolved.rate,
+            });
+        }
+    }
+    Ok(out)
+}
+
+fn parse_csv_rates(text: &str) -> Result<Vec<(usize, NotifiedRateInput)>, String> {
+    let mut out = Vec::new();
+    csv::for_each_row(text, |row| {
+        let input = NotifiedRateInput {
+            currency: row
+                .get(&["currency", "currency_code", "foreign_currency"])
+                .unwrap_or_default()
+                .to_string(),
+            unit: row
+                .get_f64(&["unit", "per_unit", "units"])?
+                .map(|u| u as i64),
+            import_rate: row.get_f64(&["import_rate", "imports", "import"])?,
+            export_rate: row.get_f64(&["export_rate", "exports", "export"])?,
+            effective_from: row
+                .get(&["effective_from", "from", "valid_from"])
+                .map(str::to_string),
+            effective_to: row
+                .get(&["effective_to", "to", "valid_to"])
+                .map(str::to_string),
+            notification_ref: row
+                .get(&["notification_ref", "notification", "notification_no"])
+                .map(str::to_string),
+        };
+        out.push((row.line, input));
+        Ok(())
+    })?;
+    Ok(out)
+}
+
+fn parse_json_rates(text: &str) -> Result<Vec<(usize, NotifiedRateInput)>, String> {
+    let value: serde_json::Value = serde_json::from_str(text).map_err(|e| e.to_string())?;
+    let inputs: Vec<NotifiedRateInput> = if value.is_array() {
+        serde_json::from_value(value).map_err(|e| e.to_string())?
+    } else {
+        let n: NotificationJson = serde_json::from_value(value).map_err(|e| e.to_string())?;
+        n.rates
+            .into_iter()
+            .map(|mut r| {
+                r.effective_from = r.effective_from.or_else(|| n.effective_from.clone());
+                r.effective_to = r.effective_to.or_else(|| n.effective_to.clone());
+                r.notification_ref = r.notification_ref.or_else(|| n.notification_ref.clone());
+                r
+            })
+            .collect()
+    };
+    Ok(inputs
+        .into_iter()
+        .enumerate()
+        .map(|(i, r)| (i + 1, r))
+        .collect())
+}
+
+/// Imports a notification (CSV or JSON, detected from content). Keyed by (currency, effective-from);
+/// an open-ended earlier period for the same currency is closed the day before the new one starts.
+pub fn import_rates(
+    conn: &mut Connection,
+    content: &str,
+) -> Result<ExchangeRateImportSummary, String> {
+    let trimmed = content.trim_start_matches('\u{feff}').trim_start();
+    let inputs = if trimmed.starts_with('[') || trimmed.starts_with('{') {
+        parse_json_rates(trimmed)?
+    } else {
+        parse_csv_rates(content)?
+    };
+
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let mut summary = ExchangeRateImportSummary::default();
+    for (line, input) in inputs {
+        let Some(currency) = normalize_currency(&input.currency) else {
+            summary.errors.push(format!(
+                "entry {line}: invalid currency '{}'",
+                input.currency
+            ));
+            continue;
+        };
+        let Some(import_rate) = input.import_rate.filter(|r| *r > 0.0) else {
+            summary.errors.push(format!(
+                "entry {line}: {currency} has no positive import rate"
+            ));
+            continue;
+        };
+        let Some(from) = input.effective_from.as_deref().and_then(normalize_date) else {
+            summary
+                .errors
+                .push(format!("entry {line}: missing or invalid effective_from"));
+            continue;
+        };
+        let to = match input.effective_to.as_deref() {
+            None => None,
+            Some(raw) => match normalize_date(raw) {
+                Some(d) if d >= from => Some(d),
+                _ => {
+                    summary
+                        .errors
+                        .push(format!("entry {line}: invalid effective_to '{raw}'"));
+                    continue;
+                }
+            },
+        };
+        let unit = input.unit.unwrap_or(1).max(1);
+
+        let existed = tx
+            .query_row(
+                "SELECT COUNT(*) FROM customs_exchange_rates WHERE currency = ?1 AND effective_from = ?2",
+                params![currency, from],
+                |r| r.get::<_, i64>(0),
+            )
+            .map_err(|e| e.to_string())?
+            > 0;
+        tx.execute(
+            "UPDATE customs_exchange_rates
+             SET effective_to = date(?2, '-1 day'),
+                 updated_at = strftime('%Y-%m-%d %H:%M:%S', 'now', 'localtime')
+             WHERE currency = ?1 AND effective_to IS NULL AND date(effective_from) < date(?2)",
+            params![currency, from],
+        )
+        .map_err(|e| e.to_string())?;
+        tx.execute(
+            "INSERT INTO customs_exchange_rates
+                (currency, unit, import_rate, export_rate, effective_from, effective_to, notification_ref)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+             ON CONFLICT(currency, effective_from) DO UPDATE SET
+                unit = excluded.unit,
+                import_rate = excluded.import_rate,
+                export_rate = excluded.export_rate,
+                effective_to = excluded.effective_to,
+                notification_ref = excluded.notification_ref,
+                updated_at = strftime('%Y-%m-%d %H:%M:%S', 'now', 'localtime')",
+            params![
+                currency,
+                unit,
+                import_rate,
+                input.export_rate,
+                from,
+                to,
+                input.notification_ref
+            ],
+        )
+        .map_err(|e| e.to_string())?;
+        if existed {
+            summary.updated += 1;
+        } else {
+            summary.inserted += 1;
+        }
+    }
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(summary)
+}
+
+#[tauri::command]
+pub fn import_customs_exchange_rates(
+    content: String,
+    state: State<DbState>,
+) -> Result<ExchangeRateImportSummary, String> {
+    let mut conn = state.db.lock().map_err(|e| e.to_string())?;
+    let summary = import_rates(&mut conn, &content)?;
+    let _ = dashboard_cache::invalidate_dashboard_metrics_cache(&conn);
+    Ok(summary)
+}
+
+/// Notified rate for a currency on `as_of` (defaults to today).
+#[tauri::command]
+pub fn get_customs_exchange_rate(
+    currency: String,
+    as_of: Option<String>,
+    state: State<DbState>,
+) -> Result<Option<CustomsExchangeRate>, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    let as_of = as_of.unwrap_or_else(today);
+    rate_as_of(&conn, &currency, &as_of).map_err(|e| e.to_string())
+}
+
+/// Notification history, newest period first; optionally for one currency.
+#[tauri::command]
+pub fn list_customs_exchange_rates(
+    currency: Option<String>,
+    state: State<DbState>,
+) -> Result<Vec<CustomsExchangeRate>, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    let cur = currency.as_deref().and_then(normalize_currency);
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {SELECT_COLUMNS} FROM customs_exchange_rates
+             WHERE (?1 IS NULL OR currency = ?1)
+             ORDER BY date(effective_from) DESC, currency
+             LIMIT 5000"
+        ))
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![cur], map_rate_row)
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())
+}
+
+/// Rate the BOE calculation will use for this shipment.
+#[tauri::command]
+pub fn get_shipment_customs_exchange_rate(
+    shipment_id: String,
+    state: State<DbState>,
+) -> Result<Option<ResolvedExchangeRate>, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    resolve_for_shipment(&conn, &shipment_id, None)
+}
+
+#[tauri::command]
+pub fn get_boe_exchange_rate_mismatches(
+    state: State<DbState>,
+) -> Result<Vec<ExchangeRateMismatch>, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    exchange_rate_mismatches(&conn)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn conn() -> Connection {
+        let c = Connection::open_in_memory().unwrap();
+        c.execute_batch(
+            "CREATE TABLE shipments (id TEXT PRIMARY KEY, invoice_currency TEXT NOT NULL);
+             CREATE TABLE boe_details (id TEXT PRIMARY KEY, be_date TEXT NOT NULL);
+             CREATE TABLE boe_calculations (id TEXT PRIMARY KEY, shipment_id TEXT NOT NULL, boe_id TEXT,
+                 invoice_number TEXT NOT NULL, form_values_json TEXT NOT NULL,
+                 boe_type TEXT NOT NULL DEFAULT 'HOME_CONSUMPTION');",
+        )
+        .unwrap();
+        c.execute_batch(include_str!(
+            "../../migrations/V50__customs_exchange_rates.sql"
+        ))
+        .unwrap();
+        c
+    }
+
+    #[test]
+    fn csv_and_json_import_with_periods() {
+        let mut c = conn();
+        let s = import_rates(
+            &mut c,
+            "Currency,Unit,Import Rate,Export Rate,Effective From,Effective To,Notification\n\
+             USD,1,83.45,81.75,2024-03-01,2024-03-15,15/2024-Cus(NT)\n\
+             JPY,100,56.10,54.30,2024-03-01,2024-03-15,15/2024-Cus(NT)\n\
+             XX,1,1,1,2024-03-01,,\n",
+        )
+        .unwrap();
+        assert_eq!((s.inserted, s.errors.len()), (2, 1));
+
+        let s = import_rates(
+            &mut c,
+            r#"{"notificationRef":"18/2024-Cus(NT)","effectiveFrom":"16-03-2024",
+                "rates":[{"currency":"usd","importRate":83.60}]}"#,
+        )
+        .unwrap();
+        assert_eq!(s.inserted, 1);
+
+        let jpy = rate_as_of(&c, "JPY", "2024-03-10").unwrap().unwrap();
+        assert!((jpy.per_unit_import_rate() - 0.561).abs() < 1e-9);
+        assert_eq!(
+            rate_as_of(&c, "USD", "2024-03-15")
+                .unwrap()
+                .unwrap()
+                .import_rate,
+            83.45
+        );
+        let later = rate_as_of(&c, "USD", "2024-04-20").unwrap().unwrap();
+        assert_eq!(later.notification_ref.as_deref(), Some("18/2024-Cus(NT)"));
+    }
+
+    #[test]
+    fn shipment_resolution_and_mismatch() {
+        let mut c = conn();
+        import_rates(&mut c, "currency,import_rate,from\nUSD,83.45,2024-03-01\n").unwrap();
+        c.execute_batch(
+            "INSERT INTO shipments VALUES ('S1', 'usd'), ('S2', 'INR');
+             INSERT INTO boe_details VALUES ('B1', '2024-03-05'), ('B2', '2024-05-20');
+             INSERT INTO boe_calculations (id, shipment_id, boe_id, invoice_number, form_values_json, boe_type) VALUES
+                ('C1', 'S1', 'B1', 'INV-1', '{\"exchangeRate\": 82.0}', 'HOME_CONSUMPTION'),
+                ('C2', 'S1', 'B1', 'INV-1', '{\"exchangeRate\": 83.45}', 'INTO_BOND'),
+                ('C3', 'S1', 'B2', 'INV-1', '{\"exchangeRate\": 83.45}', 'EX_BOND');",
+        )
+        .unwrap();
+        // The later ex-bond BE date does not move the shipment off the into-bond rate.
+        import_rates(&mut c, "currency,import_rate,from\nUSD,84.10,2024-05-01\n").unwrap();
+        let r = resolve_for_shipment(&c, "S1", None).unwrap().unwrap();
+        assert_eq!((r.as_of.as_str(), r.rate), ("2024-03-05", 83.45));
+        assert_eq!(
+            resolve_for_shipment(&c, "S2", None).unwrap().unwrap().rate,
+            1.0
+        );
+
+        let m = exchange_rate_mismatches(&c).unwrap();
+        assert_eq!(m.len(), 1);
+        assert_eq!((m[0].saved_boe_id.as_str(), m[0].used_rate), ("C1", 82.0));
+    }
+}
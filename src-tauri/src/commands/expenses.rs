@@ -1,5 +1,6 @@
 #![allow(clippy::uninlined_format_args)]
 use crate::commands::dashboard_cache;
+use crate::commands::landed_cost;
 use crate::commands::utils::generate_id;
 use crate::db::{
     DbState, Expense, ExpenseAttachment, ExpenseInvoice, ExpenseType, ExpenseWithInvoice,
@@ -69,7 +70,7 @@ pub fn add_service_provider(
 pub fn get_expense_types(state: State<DbState>) -> Result<Vec<ExpenseType>, String> {
     let conn = state.db.lock().unwrap();
     let mut stmt = conn
-        .prepare("SELECT id, name, COALESCE(default_cgst_rate_bp, default_cgst_rate * 100) as default_cgst_rate, COALESCE(default_sgst_rate_bp, default_sgst_rate * 100) as default_sgst_rate, COALESCE(default_igst_rate_bp, default_igst_rate * 100) as default_igst_rate, is_active FROM expense_types ORDER BY name")
+        .prepare("SELECT id, name, COALESCE(default_cgst_rate_bp, default_cgst_rate * 100) as default_cgst_rate, COALESCE(default_sgst_rate_bp, default_sgst_rate * 100) as default_sgst_rate, COALESCE(default_igst_rate_bp, default_igst_rate * 100) as default_igst_rate, is_active, allocation_basis FROM expense_types ORDER BY name")
         .map_err(|e| e.to_string())?;
 
     let iter = stmt
@@ -81,6 +82,7 @@ pub fn get_expense_types(state: State<DbState>) -> Result<Vec<ExpenseType>, Stri
                 default_sgst_rate: row.get(3)?,
                 default_igst_rate: row.get(4)?,
                 is_active: row.get(5)?,
+                allocation_basis: row.get(6)?,
             })
         })
         .map_err(|e| e.to_string())?;
@@ -648,6 +650,7 @@ pub fn add_expense_type(name: String, state: State<DbState>) -> Result<ExpenseTy
         default_sgst_rate: 0,
         default_igst_rate: 0,
         is_active: true,
+        allocation_basis: landed_cost::BASIS_ASSESSABLE_VALUE.to_string(),
     };
 
     db.execute(
@@ -676,6 +679,7 @@ pub fn add_expense_type_with_rates(
         default_sgst_rate: sgst_rate,
         default_igst_rate: igst_rate,
         is_active: true,
+        allocation_basis: landed_cost::BASIS_ASSESSABLE_VALUE.to_string(),
     };
 
     db.execute(
@@ -1208,6 +1212,7 @@ pub fn add_expense_invoice_with_expenses(
 
     // Update the invoice total and fetch the result
     update_invoice_total(&conn, &invoice_id)?;
+    landed_cost::recompute_after_expense_change(&conn, &invoice_id);
 
     // Fetch the created expense invoice
     let mut stmt = conn.prepare("SELECT id, shipment_id, service_provider_id, invoice_no, invoice_date, total_amount, total_cgst_amount, total_sgst_amount, total_igst_amount, remarks, created_by, created_at, updated_at FROM expense_invoices WHERE id = ?1")
@@ -1284,6 +1289,7 @@ pub fn add_expense(payload: ExpensePayload, state: State<'_, DbState>) -> Result
 
     // Update the invoice total
     update_invoice_total(&conn, &payload.expense_invoice_id)?;
+    landed_cost::recompute_after_expense_change(&conn, &payload.expense_invoice_id);
 
     // Fetch the newly created record to get generated values
     let mut stmt = conn.prepare("SELECT id, expense_invoice_id, expense_type_id, amount, cgst_rate, sgst_rate, igst_rate, tds_rate, cgst_amount, sgst_amount, igst_amount, tds_amount, total_amount, remarks, created_by, created_at, updated_at FROM expenses WHERE id = ?1")
@@ -1354,6 +1360,7 @@ pub fn update_expense(
 
     // Update the invoice total
     update_invoice_total(&conn, &expense_invoice_id)?;
+    landed_cost::recompute_after_expense_change(&conn, &expense_invoice_id);
 
     // Fetch the updated record to get generated values
     let mut stmt = conn.prepare("SELECT id, expense_invoice_id, expense_type_id, amount, cgst_rate, sgst_rate, igst_rate, tds_rate, cgst_amount, sgst_amount, igst_amount, tds_amount, total_amount, remarks, created_by, created_at, updated_at FROM expenses WHERE id = ?1")
@@ -1420,6 +1427,7 @@ pub fn delete_expense(id: String, state: State<DbState>) -> Result<(), String> {
 
     // Update the invoice total
     update_invoice_total(&conn, &expense_invoice_id)?;
+    landed_cost::recompute_after_expense_change(&conn, &expense_invoice_id);
 
     let _ = dashboard_cache::invalidate_dashboard_metrics_cache(&conn);
 
@@ -1598,6 +1606,7 @@ pub fn add_expenses_bulk(
     }
 
     tx.commit().map_err(|e| e.to_string())?;
+    landed_cost::recompute_after_change(&conn, &payload.shipment_id);
 
     let _ = dashboard_cache::invalidate_dashboard_metrics_cache(&conn);
 
@@ -1611,6 +1620,13 @@ pub fn add_expenses_bulk(
 #[tauri::command]
 pub fn delete_expense_invoice(invoice_id: String, state: State<DbState>) -> Result<(), String> {
     let mut conn = state.db.lock().unwrap();
+    let shipment_id: Option<String> = conn
+        .query_row(
+            "SELECT shipment_id FROM expense_invoices WHERE id = ?1",
+            params![&invoice_id],
+            |row| row.get(0),
+        )
+        .ok();
     let tx = conn.transaction().map_err(|e| e.to_string())?;
 
     // First, delete all expense attachments for expenses in this invoice
@@ -1646,6 +1662,9 @@ pub fn delete_expense_invoice(invoice_id: String, state: State<DbState>) -> Resu
 
     // Commit the transaction
     tx.commit().map_err(|e| e.to_string())?;
+    if let Some(shipment_id) = shipment_id {
+        landed_cost::recompute_after_change(&conn, &shipment_id);
+    }
 
     let _ = dashboard_cache::invalidate_dashboard_metrics_cache(&conn);
 
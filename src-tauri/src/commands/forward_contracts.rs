@@ -0,0 +1,983 @@
+//! Forex hedge book: forward contracts, their utilisation against supplier payments, exposure cover and MTM.
+
+use crate::commands::dashboard_cache;
+use crate::commands::supplier_payments;
+use crate::commands::tariff;
+use crate::commands::utils::generate_id;
+use crate::db::DbState;
+use crate::duty_engine::round_paise;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use tauri::State;
+
+pub const ACTIVE: &str = "ACTIVE";
+pub const CANCELLED: &str = "CANCELLED";
+
+pub const POSITION_OPEN: &str = "OPEN";
+pub const POSITION_PARTIALLY_UTILISED: &str = "PARTIALLY_UTILISED";
+pub const POSITION_UTILISED: &str = "UTILISED";
+pub const POSITION_EXPIRED: &str = "EXPIRED";
+pub const POSITION_CANCELLED: &str = "CANCELLED";
+
+/// `maturity_month` of a spot rate in `forex_market_rates`.
+pub const SPOT: &str = "SPOT";
+
+const AMOUNT_TOLERANCE: f64 = 0.005;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ForwardContractAllocation {
+    pub payment_id: String,
+    pub payment_date: String,
+    pub shipment_id: Option<String>,
+    pub invoice_number: Option<String>,
+    pub amount: f64,
+    pub allocated_by: Option<String>,
+    pub allocated_at: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ForwardContract {
+    pub id: String,
+    pub contract_number: String,
+    pub bank_name: String,
+    pub currency: String,
+    pub amount: f64,
+    pub forward_rate: f64,
+    pub booking_date: String,
+    pub maturity_from: String,
+    pub maturity_to: String,
+    /// `ACTIVE` or `CANCELLED`.
+    pub status: String,
+    pub utilised_amount: f64,
+    /// Still available for utilisation; 0 once cancelled.
+    pub open_amount: f64,
+    /// `OPEN`, `PARTIALLY_UTILISED`, `UTILISED`, `EXPIRED` (window passed with an open amount) or `CANCELLED`.
+    pub position: String,
+    pub notes: Option<String>,
+    pub created_by: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+    pub allocations: Vec<ForwardContractAllocation>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SaveForwardContractPayload {
+    /// Omit to book a new contract.
+    pub id: Option<String>,
+    pub contract_number: String,
+    pub bank_name: String,
+    pub currency: String,
+    pub amount: f64,
+    pub forward_rate: f64,
+    pub booking_date: String,
+    pub maturity_from: String,
+    pub maturity_to: String,
+    pub notes: Option<String>,
+    pub user_id: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ForexMarketRate {
+    pub currency: String,
+    pub rate_date: String,
+    /// `SPOT` (the default) or the delivery month `YYYY-MM` of a forward rate.
+    #[serde(default)]
+    pub maturity_month: Option<String>,
+    pub rate: f64,
+    pub source: Option<String>,
+}
+
+/// Payables against forward cover for one currency and month.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct HedgeExposureRow {
+    pub currency: String,
+    /// `YYYY-MM`; anything already due falls in the as-of month.
+    pub month: String,
+    pub shipments: u32,
+    /// Outstanding supplier invoices expected to be paid in the month.
+    pub exposure: f64,
+    /// Open forward cover first usable in the month.
+    pub covered: f64,
+    pub uncovered: f64,
+    /// `covered` / `exposure` × 100; `None` without exposure.
+    pub cover_percent: Option<f64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ForwardContractMtm {
+    pub contract_id: String,
+    pub contract_number: String,
+    pub bank_name: String,
+    pub currency: String,
+    pub maturity_to: String,
+    pub position: String,
+    pub open_amount: f64,
+    pub forward_rate: f64,
+    /// Forward rate for the contract's maturity month, else spot, latest on or before the as-of date.
+    pub market_rate: Option<f64>,
+    pub market_rate_date: Option<String>,
+    pub market_rate_basis: Option<String>,
+    /// open amount × (market − forward rate) in INR; positive when the cover is in the money.
+    pub mtm_gain_loss: Option<f64>,
+}
+
+fn round2(v: f64) -> f64 {
+    (v * 100.0).round() / 100.0
+}
+
+fn normalize_currency(code: &str) -> Result<String, String> {
+    let c = code.trim().to_uppercase();
+    if c.len() != 3 || !c.chars().all(|ch| ch.is_ascii_alphabetic()) {
+        return Err(format!("Invalid currency '{code}'"));
+    }
+    if c == "INR" {
+        return Err("INR needs no forward cover".to_string());
+    }
+    Ok(c)
+}
+
+fn month_of(date: &str) -> &str {
+    date.get(..7).unwrap_or(date)
+}
+
+fn position_for(
+    status: &str,
+    amount: f64,
+    utilised: f64,
+    maturity_to: &str,
+    as_of: &str,
+) -> &'static str {
+    let open = amount - utilised;
+    if status == CANCELLED {
+        POSITION_CANCELLED
+    } else if open <= AMOUNT_TOLERANCE {
+        POSITION_UTILISED
+    } else if maturity_to < as_of {
+        POSITION_EXPIRED
+    } else if utilised > AMOUNT_TOLERANCE {
+        POSITION_PARTIALLY_UTILISED
+    } else {
+        POSITION_OPEN
+    }
+}
+
+fn load_allocations(
+    conn: &Connection,
+    contract_id: &str,
+) -> rusqlite::Result<Vec<ForwardContractAllocation>> {
+    let mut stmt = conn.prepare(
+        "SELECT a.payment_id, p.payment_date, p.shipment_id, s.invoice_number, a.amount, a.allocated_by, a.allocated_at
+         FROM forward_contract_allocations a
+         JOIN supplier_payments p ON p.id = a.payment_id
+         LEFT JOIN shipments s ON s.id = p.shipment_id
+         WHERE a.contract_id = ?1
+         ORDER BY p.payment_date, a.id",
+    )?;
+    let rows = stmt.query_map(params![contract_id], |r| {
+        Ok(ForwardContractAllocation {
+            payment_id: r.get(0)?,
+            payment_date: r.get(1)?,
+            shipment_id: r.get(2)?,
+            invoice_number: r.get(3)?,
+            amount: r.get(4)?,
+            allocated_by: r.get(5)?,
+            allocated_at: r.get(6)?,
+        })
+    })?;
+    rows.collect()
+}
+
+/// Contracts, optionally of one currency, latest maturity first, with positions as of `as_of`.
+pub fn list_contracts(
+    conn: &Connection,
+    currency: Option<&str>,
+    as_of: &str,
+) -> Result<Vec<ForwardContract>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, contract_number, bank_name, currency, amount, forward_rate, booking_date, maturity_from,
+                    maturity_to, status, notes, created_by, created_at, updated_at,
+                    COALESCE((SELECT SUM(a.amount) FROM forward_contract_allocations a
+                              JOIN supplier_payments p ON p.id = a.payment_id
+                              WHERE a.contract_id = fc.id), 0)
+             FROM forward_contracts fc
+             WHERE (?1 IS NULL OR currency = ?1)
+             ORDER BY maturity_to DESC, contract_number",
+        )
+        .map_err(|e| e.to_string())?;
+    let mut contracts = stmt
+        .query_map(params![currency.map(|c| c.trim().to_uppercase())], |r| {
+            let amount: f64 = r.get(4)?;
+            let maturity_to: String = r.get(8)?;
+            let status: String = r.get(9)?;
+            let utilised: f64 = r.get(14)?;
+            let position = position_for(&status, amount, utilised, &maturity_to, as_of);
+            Ok(ForwardContract {
+                id: r.get(0)?,
+                contract_number: r.get(1)?,
+                bank_name: r.get(2)?,
+                currency: r.get(3)?,
+                amount,
+                forward_rate: r.get(5)?,
+                booking_date: r.get(6)?,
+                maturity_from: r.get(7)?,
+                open_amount: if status == CANCELLED {
+                    0.0
+                } else {
+                    round2((amount - utilised).max(0.0))
+                },
+                maturity_to,
+                status,
+                utilised_amount: round2(utilised),
+                position: position.to_string(),
+                notes: r.get(10)?,
+                created_by: r.get(11)?,
+                created_at: r.get(12)?,
+                updated_at: r.get(13)?,
+                allocations: Vec::new(),
+            })
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    for c in contracts.iter_mut() {
+        c.allocations = load_allocations(conn, &c.id).map_err(|e| e.to_string())?;
+    }
+    Ok(contracts)
+}
+
+fn load_contract(conn: &Connection, id: &str, as_of: &str) -> Result<ForwardContract, String> {
+    let currency: Option<String> = conn
+        .query_row(
+            "SELECT currency FROM forward_contracts WHERE id = ?1",
+            params![id],
+            |r| r.get(0),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+    let currency = currency.ok_or_else(|| format!("Forward contract {id} not found"))?;
+    list_contracts(conn, Some(&currency), as_of)?
+        .into_iter()
+        .find(|c| c.id == id)
+        .ok_or_else(|| format!("Forward contract {id} not found"))
+}
+
+pub fn upsert_contract(
+    conn: &Connection,
+    payload: SaveForwardContractPayload,
+) -> Result<ForwardContract, String> {
+    let contract_number = payload.contract_number.trim().to_string();
+    if contract_number.is_empty() {
+        return Err("Contract number is required".to_string());
+    }
+    let bank_name = payload.bank_name.trim().to_string();
+    if bank_name.is_empty() {
+        return Err("Bank is required".to_string());
+    }
+    let currency = normalize_currency(&payload.currency)?;
+    if !payload.amount.is_finite() || payload.amount <= 0.0 {
+        return Err("Contract amount must be positive".to_string());
+    }
+    if !payload.forward_rate.is_finite() || payload.forward_rate <= 0.0 {
+        return Err("Forward rate must be positive".to_string());
+    }
+    let date = |label: &str, raw: &str| {
+        tariff::normalize_date(raw).ok_or_else(|| format!("Invalid {label} '{raw}'"))
+    };
+    let booking_date = date("booking date", &payload.booking_date)?;
+    let maturity_from = date("maturity from date", &payload.maturity_from)?;
+    let maturity_to = date("maturity to date", &payload.maturity_to)?;
+    if maturity_from > maturity_to {
+        return Err("The maturity window ends before it starts".to_string());
+    }
+    if booking_date > maturity_to {
+        return Err("The contract matures before it was booked".to_string());
+    }
+    if let Some(id) = payload.id.as_deref() {
+        let existing = load_contract(conn, id, &tariff::today())?;
+        if existing.status == CANCELLED {
+            return Err("Cancelled contracts cannot be edited".to_string());
+        }
+        if !existing.allocations.is_empty() {
+            if existing.currency != currency {
+                return Err("The currency of a utilised contract cannot change".to_string());
+            }
+            if payload.amount + AMOUNT_TOLERANCE < existing.utilised_amount {
+                return Err(format!(
+                    "{} {} is already utilised; the amount cannot go below it",
+                    existing.utilised_amount, existing.currency
+                ));
+            }
+            if let Some(outside) = existing
+                .allocations
+                .iter()
+                .find(|a| a.payment_date < maturity_from || a.payment_date > maturity_to)
+            {
+                return Err(format!(
+                    "The payment of {} falls outside the new maturity window",
+                    outside.payment_date
+                ));
+            }
+        }
+    }
+    let id = payload
+        .id
+        .clone()
+        .unwrap_or_else(|| generate_id(Some("FWD".to_string())));
+    conn.execute(
+        "INSERT INTO forward_contracts (id, contract_number, bank_name, currency, amount, forward_rate, booking_date,
+            maturity_from, maturity_to, notes, created_by)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)
+         ON CONFLICT (id) DO UPDATE SET
+            contract_number = excluded.contract_number,
+            bank_name = excluded.bank_name,
+            currency = excluded.currency,
+            amount = excluded.amount,
+            forward_rate = excluded.forward_rate,
+            booking_date = excluded.booking_date,
+            maturity_from = excluded.maturity_from,
+            maturity_to = excluded.maturity_to,
+            notes = excluded.notes,
+            updated_at = strftime('%Y-%m-%d %H:%M:%S', 'now', 'localtime')",
+        params![
+            id,
+            contract_number,
+            bank_name,
+            currency,
+            payload.amount,
+            payload.forward_rate,
+            booking_date,
+            maturity_from,
+            maturity_to,
+            payload.notes,
+            payload.user_id
+        ],
+    )
+    .map_err(|e| match e {
+        rusqlite::Error::SqliteFailure(ref f, _)
+            if f.code == rusqlite::ErrorCode::ConstraintViolation =>
+        {
+            format!("Contract number {contract_number} already exists")
+        }
+        e => e.to_string(),
+    })?;
+    load_contract(conn, &id, &tariff::today())
+}
+
+/// Utilises `amount` of the contract for a supplier payment in the same currency made within the
+/// maturity window. Re-allocating the same pair replaces the earlier amount.
+pub fn allocate(
+    conn: &Connection,
+    contract_id: &str,
+    payment_id: &str,
+    amount: f64,
+    user_id: Option<&str>,
+) -> Result<ForwardContract, String> {
+    if !amount.is_finite() || amount <= 0.0 {
+        return Err("Allocation amount must be positive".to_string());
+    }
+    let contract = load_contract(conn, contract_id, &tariff::today())?;
+    if contract.status == CANCELLED {
+        return Err(format!(
+            "Contract {} is cancelled",
+            contract.contract_number
+        ));
+    }
+    let payment: Option<(String, String, f64)> = conn
+        .query_row(
+            "SELECT currency, payment_date, amount FROM supplier_payments WHERE id = ?1",
+            params![payment_id],
+            |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+    let Some((currency, payment_date, payment_amount)) = payment else {
+        return Err(format!("Payment {payment_id} not found"));
+    };
+    if !currency.eq_ignore_ascii_case(&contract.currency) {
+        return Err(format!(
+            "Contract {} is in {}; the payment is in {currency}",
+            contract.contract_number, contract.currency
+        ));
+    }
+    if payment_date < contract.maturity_from || payment_date > contract.maturity_to {
+        return Err(format!(
+            "Payment date {payment_date} is outside the contract window {} to {}",
+            contract.maturity_from, contract.maturity_to
+        ));
+    }
+    let previous = contract
+        .allocations
+        .iter()
+        .find(|a| a.payment_id == payment_id)
+        .map_or(0.0, |a| a.amount);
+    let contract_available = contract.amount - contract.utilised_amount + previous;
+    if amount > contract_available + AMOUNT_TOLERANCE {
+        return Err(format!(
+            "Only {:.2} {} is left on contract {}",
+            contract_available, contract.currency, contract.contract_number
+        ));
+    }
+    let covered_elsewhere: f64 = conn
+        .query_row(
+            "SELECT COALESCE(SUM(amount), 0) FROM forward_contract_allocations WHERE payment_id = ?1 AND contract_id <> ?2",
+            params![payment_id, contract_id],
+            |r| r.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+    if amount > payment_amount - covered_elsewhere + AMOUNT_TOLERANCE {
+        return Err(format!(
+            "Only {:.2} {currency} of the payment is not yet covered",
+            (payment_amount - covered_elsewhere).max(0.0)
+        ));
+    }
+    conn.execute(
+        "INSERT INTO forward_contract_allocations (contract_id, payment_id, amount, allocated_by)
+         VALUES (?1, ?2, ?3, ?4)
+         ON CONFLICT (contract_id, payment_id) DO UPDATE SET
+            amount = excluded.amount,
+            allocated_by = excluded.allocated_by,
+            allocated_at = strftime('%Y-%m-%d %H:%M:%S', 'now', 'localtime')",
+        params![contract_id, payment_id, amount, user_id],
+    )
+    .map_err(|e| e.to_string())?;
+    load_contract(conn, contract_id, &tariff::today())
+}
+
+/// Outstanding payables by expected payment month (ETA month, else invoice month) against open cover
+/// by the first month it can be utilised, per currency. Past months roll into the as-of month.
+pub fn exposure(conn: &Connection, as_of: &str) -> Result<Vec<HedgeExposureRow>, String> {
+    let current_month = month_of(as_of).to_string();
+    let mut rows: BTreeMap<(String, String), HedgeExposureRow> = BTreeMap::new();
+    let row = |currency: &str, month: &str| {
+        let month = if month < current_month.as_str() {
+            current_month.clone()
+        } else {
+            month.to_string()
+        };
+        (currency.to_string(), month)
+    };
+
+    let mut etas: HashMap<String, Option<String>> = HashMap::new();
+    {
+        let mut stmt = conn
+            .prepare("SELECT id, eta FROM shipments")
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([], |r| {
+                Ok((r.get::<_, String>(0)?, r.get::<_, Option<String>>(1)?))
+            })
+            .map_err(|e| e.to_string())?;
+        for row in rows {
+            let (id, eta) = row.map_err(|e| e.to_string())?;
+            etas.insert(id, eta);
+        }
+    }
+    let mut buckets: Vec<((String, String), f64, bool)> = Vec::new();
+    for pos in supplier_payments::invoice_positions(conn, None)? {
+        if pos.currency == "INR" || pos.outstanding_amount <= AMOUNT_TOLERANCE {
+            continue;
+        }
+        let due = etas
+            .get(&pos.shipment_id)
+            .and_then(|eta| eta.as_deref())
+            .and_then(|d| tariff::normalize_date(d.get(..10).unwrap_or(d)))
+            .or_else(|| tariff::normalize_date(&pos.invoice_date))
+            .unwrap_or_else(|| as_of.to_string());
+        buckets.push((
+            row(&pos.currency, month_of(&due)),
+            pos.outstanding_amount,
+            true,
+        ));
+    }
+    for c in list_contracts(conn, None, as_of)? {
+        if c.position == POSITION_EXPIRED || c.open_amount <= AMOUNT_TOLERANCE {
+            continue;
+        }
+        buckets.push((
+            row(&c.currency, month_of(&c.maturity_from)),
+            c.open_amount,
+            false,
+        ));
+    }
+    for ((currency, month), amount, is_payable) in buckets {
+        let r = rows
+            .entry((currency.clone(), month.clone()))
+            .or_insert_with(|| HedgeExposureRow {
+                currency,
+                month,
+                shipments: 0,
+                exposure: 0.0,
+                covered: 0.0,
+                uncovered: 0.0,
+                cover_percent: None,
+            });
+        if is_payable {
+            r.shipments += 1;
+            r.exposure = round2(r.exposure + amount);
+        } else {
+            r.covered = round2(r.covered + amount);
+        }
+    }
+    Ok(rows
+        .into_values()
+        .map(|mut r| {
+            r.uncovered = round2((r.exposure - r.covered).max(0.0));
+            r.cover_percent = (r.exposure > 0.0).then(|| round2(r.covered / r.exposure * 100.0));
+            r
+        })
+        .collect())
+}
+
+/// Latest market rate on or before `as_of` for delivery in `month`, else the latest spot rate.
+fn market_rate(
+    conn: &Connection,
+    currency: &str,
+    month: &str,
+    as_of: &str,
+) -> rusqlite::Result<Option<(f64, String, String)>> {
+    for basis in [month, SPOT] {
+        let found = conn
+            .query_row(
+                "SELECT rate, rate_date FROM forex_market_rates
+                 WHERE currency = ?1 AND maturity_month = ?2 AND rate_date <= ?3
+                 ORDER BY rate_date DESC LIMIT 1",
+                params![currency, basis, as_of],
+                |r| Ok((r.get::<_, f64>(0)?, r.get::<_, String>(1)?)),
+            )
+            .optional()?;
+        if let Some((rate, date)) = found {
+            return Ok(Some((rate, date, basis.to_string())));
+        }
+    }
+    Ok(None)
+}
+
+/// Mark-to-market of the open amount of every active contract as of `as_of`.
+pub fn mark_to_market(conn: &Connection, as_of: &str) -> Result<Vec<ForwardContractMtm>, String> {
+    let mut out = Vec::new();
+    for c in list_contracts(conn, None, as_of)? {
+        if c.status == CANCELLED || c.open_amount <= AMOUNT_TOLERANCE {
+            continue;
+        }
+        let market = market_rate(conn, &c.currency, month_of(&c.maturity_to), as_of)
+            .map_err(|e| e.to_string())?;
+        out.push(ForwardContractMtm {
+            mtm_gain_loss: market
+                .as_ref()
+                .map(|(rate, _, _)| round_paise(c.open_amount * (rate - c.forward_rate))),
+            market_rate: market.as_ref().map(|m| m.0),
+            market_rate_date: market.as_ref().map(|m| m.1.clone()),
+            market_rate_basis: market.map(|m| m.2),
+            contract_id: c.id,
+            contract_number: c.contract_number,
+            bank_name: c.bank_name,
+            currency: c.currency,
+            maturity_to: c.maturity_to,
+            position: c.position,
+            open_amount: c.open_amount,
+            forward_rate: c.forward_rate,
+        });
+    }
+    out.sort_by(|a, b| (&a.currency, &a.maturity_to).cmp(&(&b.currency, &b.maturity_to)));
+    Ok(out)
+}
+
+pub fn save_market_rates(conn: &Connection, rates: &[ForexMarketRate]) -> Result<u32, String> {
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    for (i, r) in rates.iter().enumerate() {
+        let n = i + 1;
+        let currency = normalize_currency(&r.currency).map_err(|e| format!("Row {n}: {e}"))?;
+        let rate_date = tariff::normalize_date(&r.rate_date)
+            .ok_or_else(|| format!("Row {n}: invalid rate date '{}'", r.rate_date))?;
+        let maturity_month = match r.maturity_month.as_deref().map(str::trim) {
+            None | Some("") => SPOT.to_string(),
+            Some(m) if m.eq_ignore_ascii_case(SPOT) => SPOT.to_string(),
+            Some(m) => {
+                let valid = m.len() == 7
+                    && tariff::normalize_date(&format!("{m}-01")).as_deref()
+                        == Some(format!("{m}-01").as_str());
+                if !valid {
+                    return Err(format!("Row {n}: maturity month '{m}' must be YYYY-MM"));
+                }
+                m.to_string()
+            }
+        };
+        if !r.rate.is_finite() || r.rate <= 0.0 {
+            return Err(format!("Row {n}: rate must be positive"));
+        }
+        tx.execute(
+            "INSERT INTO forex_market_rates (currency, rate_date, maturity_month, rate, source)
+             VALUES (?1, ?2, ?3, ?4, ?5)
+             ON CONFLICT (currency, rate_date, maturity_month) DO UPDATE SET
+                rate = excluded.rate,
+                source = excluded.source",
+            params![currency, rate_date, maturity_month, r.rate, r.source],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(rates.len() as u32)
+}
+
+#[tauri::command]
+pub fn list_forward_contracts(
+    currency: Option<String>,
+    state: State<DbState>,
+) -> Result<Vec<ForwardContract>, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    list_contracts(&conn, currency.as_deref(), &tariff::today())
+}
+
+#[tauri::command]
+pub fn save_forward_contract(
+    payload: SaveForwardContractPayload,
+    state: State<DbState>,
+) -> Result<ForwardContract, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    let contract = upsert_contract(&conn, payload)?;
+    let _ = dashboard_cache::invalidate_dashboard_metrics_cache(&conn);
+    Ok(contract)
+}
+
+/// Cancels the unutilised part of a contract; utilisations already made stand.
+#[tauri::command]
+pub fn cancel_forward_contract(
+    id: String,
+    state: State<DbState>,
+) -> Result<ForwardContract, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    let updated = conn
+        .execute(
+            "UPDATE forward_contracts SET status = ?2, updated_at = strftime('%Y-%m-%d %H:%M:%S', 'now', 'localtime')
+             WHERE id = ?1 AND status = ?3",
+            params![id, CANCELLED, ACTIVE],
+        )
+        .map_err(|e| e.to_string())?;
+    if updated == 0 {
+        return Err(format!(
+            "Forward contract {id} not found or already cancelled"
+        ));
+    }
+    let _ = dashboard_cache::invalidate_dashboard_metrics_cache(&conn);
+    load_contract(&conn, &id, &tariff::today())
+}
+
+#[tauri::command]
+pub fn allocate_forward_contract(
+    contract_id: String,
+    payment_id: String,
+    amount: f64,
+    user_id: Option<String>,
+    state: State<DbState>,
+) -> Result<ForwardContract, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    let contract = allocate(&conn, &contract_id, &payment_id, amount, user_id.as_deref())?;
+    let _ = dashboard_cache::invalidate_dashboard_metrics_cache(&conn);
+    Ok(contract)
+}
+
+#[tauri::command]
+pub fn remove_forward_contract_allocation(
+    contract_id: String,
+    payment_id: String,
+    state: State<DbState>,
+) -> Result<ForwardContract, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    conn.execute(
+        "DELETE FROM forward_contract_allocations WHERE contract_id = ?1 AND payment_id = ?2",
+        params![contract_id, payment_id],
+    )
+    .map_err(|e| e.to_string())?;
+    let _ = dashboard_cache::invalidate_dashboard_metrics_cache(&conn);
+    load_contract(&conn, &contract_id, &tariff::today())
+}
+
+#[tauri::command]
+pub fn list_forex_market_rates(
+    currency: Option<String>,
+    state: State<DbState>,
+) -> Result<Vec<ForexMarketRate>, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT currency, rate_date, maturity_month, rate, source FROM forex_market_rates
+             WHERE (?1 IS NULL OR currency = ?1)
+             ORDER BY currency, rate_date DESC, maturity_month",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![currency.map(|c| c.trim().to_uppercase())], |r| {
+            Ok(ForexMarketRate {
+                currency: r.get(0)?,
+                rate_date: r.get(1)?,
+                maturity_month: r.get(2)?,
+                rate: r.get(3)?,
+                source: r.get(4)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn save_forex_market_rates(
+    rates: Vec<ForexMarketRate>,
+    state: State<DbState>,
+) -> Result<u32, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    save_market_rates(&conn, &rates)
+}
+
+#[tauri::command]
+pub fn get_hedge_exposure(
+    as_of: Option<String>,
+    state: State<DbState>,
+) -> Result<Vec<HedgeExposureRow>, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    exposure(&conn, &as_of.unwrap_or_else(tariff::today))
+}
+
+#[tauri::command]
+pub fn get_forward_contract_mtm(
+    as_of: Option<String>,
+    state: State<DbState>,
+) -> Result<Vec<ForwardContractMtm>, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    mark_to_market(&conn, &as_of.unwrap_or_else(tariff::today))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::test_support;
+
+    fn contract(
+        number: &str,
+        amount: f64,
+        rate: f64,
+        from: &str,
+        to: &str,
+    ) -> SaveForwardContractPayload {
+        SaveForwardContractPayload {
+            id: None,
+            contract_number: number.into(),
+            bank_name: "Bank".into(),
+            currency: "usd".into(),
+            amount,
+            forward_rate: rate,
+            booking_date: "2024-01-02".into(),
+            maturity_from: from.into(),
+            maturity_to: to.into(),
+            notes: None,
+            user_id: None,
+        }
+    }
+
+    /// USD payables of 1,000 due February (400 already paid as PAY-1 on 2024-02-25) and 2,000 due
+    /// April, plus an INR invoice that needs no cover.
+    fn payables() -> Connection {
+        let conn = test_support::migrated_db();
+        test_support::add_shipment(&conn, "SHP-1", "2024-01-10", 1000.0, "USD", "in-transit");
+        test_support::add_shipment(&conn, "SHP-2", "2024-02-01", 2000.0, "USD", "in-transit");
+        test_support::add_shipment(&conn, "SHP-3", "2024-02-01", 500.0, "INR", "in-transit");
+        conn.execute_batch(
+            "UPDATE shipments SET eta = CASE id WHEN 'SHP-1' THEN '2024-02-20' ELSE '2024-04-05' END;
+             INSERT INTO supplier_payments (id, supplier_id, shipment_id, payment_type, payment_date, currency, amount, exchange_rate)
+                VALUES ('PAY-1', 'SUP-1', 'SHP-1', 'AGAINST_DOCUMENTS', '2024-02-25', 'USD', 400, 83.1);",
+        )
+        .unwrap();
+        conn
+    }
+
+    /// [`payables`] covered by FC-1 (1,000 in February at 83) and FC-2 (1,500 in April at 83.5),
+    /// with 400 of FC-1 utilised on PAY-1.
+    fn hedged() -> Connection {
+        let conn = payables();
+        let feb = upsert_contract(
+            &conn,
+            contract("FC-1", 1000.0, 83.0, "2024-02-01", "2024-02-29"),
+        )
+        .unwrap();
+        upsert_contract(
+            &conn,
+            contract("FC-2", 1500.0, 83.5, "2024-04-01", "2024-04-30"),
+        )
+        .unwrap();
+        allocate(&conn, &feb.id, "PAY-1", 400.0, Some("treasury")).unwrap();
+        conn
+    }
+
+    #[test]
+    fn contracts_need_a_foreign_currency_and_an_ordered_maturity_window() {
+        let conn = payables();
+        let rejected = |payload| upsert_contract(&conn, payload).unwrap_err();
+        assert!(
+            rejected(contract("FC-0", 100.0, 83.0, "2024-03-31", "2024-03-01"))
+                .contains("ends before it starts")
+        );
+        let mut booked_late = contract("FC-0", 100.0, 83.0, "2024-01-01", "2024-01-01");
+        booked_late.booking_date = "2024-01-02".into();
+        assert!(rejected(booked_late).contains("matures before it was booked"));
+        let mut inr = contract("FC-0", 100.0, 83.0, "2024-03-01", "2024-03-31");
+        inr.currency = "inr".into();
+        assert!(rejected(inr).contains("no forward cover"));
+
+        // A single-day window is fine.
+        let one_day = upsert_contract(
+            &conn,
+            contract("FC-0", 100.0, 83.0, "2024-03-01", "2024-03-01"),
+        )
+        .unwrap();
+        assert_eq!(one_day.currency, "USD");
+        assert!(
+            rejected(contract("FC-0", 100.0, 83.0, "2024-03-01", "2024-03-31"))
+                .contains("already exists")
+        );
+    }
+
+    #[test]
+    fn allocations_must_fall_in_the_window_and_fit_both_contract_and_payment() {
+        let conn = payables();
+        let feb = upsert_contract(
+            &conn,
+            contract("FC-1", 1000.0, 83.0, "2024-02-01", "2024-02-29"),
+        )
+        .unwrap();
+        let apr = upsert_contract(
+            &conn,
+            contract("FC-2", 1500.0, 83.5, "2024-04-01", "2024-04-30"),
+        )
+        .unwrap();
+        let small = upsert_contract(
+            &conn,
+            contract("FC-3", 100.0, 83.0, "2024-02-01", "2024-02-29"),
+        )
+        .unwrap();
+        assert!(allocate(&conn, &apr.id, "PAY-1", 100.0, None)
+            .unwrap_err()
+            .contains("outside the contract window"));
+        assert!(allocate(&conn, &feb.id, "PAY-1", 500.0, None)
+            .unwrap_err()
+            .contains("Only 400.00 USD of the payment"));
+        assert!(allocate(&conn, &small.id, "PAY-1", 150.0, None)
+            .unwrap_err()
+            .contains("Only 100.00 USD is left"));
+
+        // Splitting the payment across contracts counts what the other already covers.
+        allocate(&conn, &small.id, "PAY-1", 100.0, None).unwrap();
+        assert!(allocate(&conn, &feb.id, "PAY-1", 400.0, None).is_err());
+        let feb = allocate(&conn, &feb.id, "PAY-1", 300.0, Some("treasury")).unwrap();
+        assert_eq!((feb.utilised_amount, feb.open_amount), (300.0, 700.0));
+        // Re-allocating the same pair replaces the earlier amount.
+        let feb = allocate(&conn, &feb.id, "PAY-1", 250.0, None).unwrap();
+        assert_eq!(feb.utilised_amount, 250.0);
+    }
+
+    #[test]
+    fn utilised_contracts_keep_their_currency_amount_and_window() {
+        let conn = hedged();
+        let fc1 = list_contracts(&conn, Some("USD"), "2024-02-15")
+            .unwrap()
+            .into_iter()
+            .find(|c| c.contract_number == "FC-1")
+            .unwrap();
+        let edit = |amount: f64, from: &str, to: &str| {
+            let mut payload = contract("FC-1", amount, 83.0, from, to);
+            payload.id = Some(fc1.id.clone());
+            upsert_contract(&conn, payload)
+        };
+        assert!(edit(399.0, "2024-02-01", "2024-02-29")
+            .unwrap_err()
+            .contains("already utilised"));
+        assert!(edit(1000.0, "2024-02-01", "2024-02-20")
+            .unwrap_err()
+            .contains("outside the new maturity window"));
+        let mut eur = contract("FC-1", 1000.0, 90.0, "2024-02-01", "2024-02-29");
+        eur.id = Some(fc1.id.clone());
+        eur.currency = "EUR".into();
+        assert!(upsert_contract(&conn, eur).is_err());
+        assert_eq!(
+            edit(400.0, "2024-02-25", "2024-02-25").unwrap().open_amount,
+            0.0
+        );
+    }
+
+    #[test]
+    fn positions_follow_utilisation() {
+        let conn = hedged();
+        let positions: Vec<String> = list_contracts(&conn, Some("usd"), "2024-02-15")
+            .unwrap()
+            .into_iter()
+            .map(|c| c.position)
+            .collect();
+        assert_eq!(positions, [POSITION_OPEN, POSITION_PARTIALLY_UTILISED]);
+    }
+
+    #[test]
+    fn exposure_is_netted_by_month_and_overdue_payables_roll_forward() {
+        let conn = hedged();
+        // As of mid-February: 600 of INV-1 due now against 600 left on FC-1; INV-2 in April
+        // against FC-2. The INR invoice carries no exposure.
+        let rows = exposure(&conn, "2024-02-15").unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(
+            (
+                rows[0].month.as_str(),
+                rows[0].exposure,
+                rows[0].covered,
+                rows[0].uncovered
+            ),
+            ("2024-02", 600.0, 600.0, 0.0)
+        );
+        assert_eq!(
+            (
+                rows[1].month.as_str(),
+                rows[1].uncovered,
+                rows[1].cover_percent
+            ),
+            ("2024-04", 500.0, Some(75.0))
+        );
+        // After February the rest of FC-1 has lapsed and INV-1 rolls into March uncovered.
+        let march = exposure(&conn, "2024-03-10").unwrap();
+        assert_eq!(
+            (march[0].month.as_str(), march[0].uncovered),
+            ("2024-03", 600.0)
+        );
+    }
+
+    #[test]
+    fn mark_to_market_prefers_the_maturity_month_rate_over_spot() {
+        let conn = hedged();
+        let rate = |maturity_month: Option<&str>, rate: f64| ForexMarketRate {
+            currency: "USD".into(),
+            rate_date: "2024-02-14".into(),
+            maturity_month: maturity_month.map(Into::into),
+            rate,
+            source: None,
+        };
+        assert!(save_market_rates(&conn, &[rate(Some("2024-4"), 83.4)]).is_err());
+        save_market_rates(&conn, &[rate(None, 83.2), rate(Some("2024-04"), 83.4)]).unwrap();
+
+        let mtm = mark_to_market(&conn, "2024-02-15").unwrap();
+        // FC-1 has no February forward quote: its 600 open are marked at spot.
+        let fc1 = mtm.iter().find(|m| m.contract_number == "FC-1").unwrap();
+        assert_eq!(
+            (fc1.market_rate_basis.as_deref(), fc1.mtm_gain_loss),
+            (Some(SPOT), Some(120.0))
+        );
+        let fc2 = mtm.iter().find(|m| m.contract_number == "FC-2").unwrap();
+        assert_eq!(
+            (fc2.market_rate, fc2.mtm_gain_loss),
+            (Some(83.4), Some(-150.0))
+        );
+    }
+}
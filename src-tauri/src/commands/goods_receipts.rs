@@ -0,0 +1,1070 @@
+//! Goods receipt notes: received, short, excess and damaged quantities per invoice line, with photos and `GRN_VARIANCE` cases.
+
+use crate::commands::dashboard_cache;
+use crate::commands::exception_workflow;
+use crate::commands::landed_cost;
+use crate::commands::shipment_status;
+use crate::commands::tariff;
+use crate::commands::utils::generate_id;
+use crate::db::DbState;
+use crate::duty_engine::round_paise;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use tauri::{Manager, State};
+
+pub const DRAFT: &str = "DRAFT";
+pub const POSTED: &str = "POSTED";
+pub const CANCELLED: &str = "CANCELLED";
+
+pub const GRN_VARIANCE: &str = "GRN_VARIANCE";
+
+const QTY_EPSILON: f64 = 1e-9;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct GoodsReceiptLine {
+    pub id: String,
+    pub invoice_line_item_id: String,
+    pub item_id: String,
+    pub part_number: Option<String>,
+    /// Invoiced quantity still outstanding when the GRN was posted (live while in draft).
+    pub expected_quantity: f64,
+    /// Units counted in, damaged ones included.
+    pub received_quantity: f64,
+    pub damaged_quantity: f64,
+    pub short_quantity: f64,
+    pub excess_quantity: f64,
+    pub remarks: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct GoodsReceiptPhoto {
+    pub id: String,
+    pub grn_id: String,
+    pub grn_line_id: Option<String>,
+    pub file_name: String,
+    pub file_path: String,
+    pub caption: Option<String>,
+    pub uploaded_by: Option<String>,
+    pub uploaded_at: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct GoodsReceipt {
+    pub id: String,
+    pub grn_number: String,
+    pub shipment_id: String,
+    pub received_date: String,
+    pub warehouse: Option<String>,
+    pub status: String,
+    pub notes: Option<String>,
+    pub received_by: Option<String>,
+    pub posted_at: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+    pub lines: Vec<GoodsReceiptLine>,
+    pub photos: Vec<GoodsReceiptPhoto>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SaveGoodsReceiptLine {
+    pub invoice_line_item_id: String,
+    pub received_quantity: f64,
+    #[serde(default)]
+    pub damaged_quantity: f64,
+    pub remarks: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SaveGoodsReceiptPayload {
+    /// Omit to create a new draft GRN.
+    pub id: Option<String>,
+    /// Defaults to the generated GRN id.
+    pub grn_number: Option<String>,
+    pub shipment_id: String,
+    pub received_date: String,
+    pub warehouse: Option<String>,
+    pub notes: Option<String>,
+    pub user_id: Option<String>,
+    pub lines: Vec<SaveGoodsReceiptLine>,
+}
+
+/// Receipt position of one invoice line across the shipment's posted GRNs.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ReceiptVarianceLine {
+    pub invoice_line_item_id: String,
+    pub item_id: String,
+    pub part_number: Option<String>,
+    pub invoiced_quantity: f64,
+    pub received_quantity: f64,
+    pub damaged_quantity: f64,
+    pub short_quantity: f64,
+    pub excess_quantity: f64,
+    /// Invoice unit price, in the shipment's invoice currency.
+    pub unit_price: f64,
+    /// (short + damaged) × unit price.
+    pub invoice_value_at_risk: f64,
+    pub landed_cost_per_unit: Option<f64>,
+    /// (short + damaged) × landed cost per unit, in INR; `None` until landed cost is computed.
+    pub landed_value_at_risk: Option<f64>,
+}
+
+impl ReceiptVarianceLine {
+    pub fn has_variance(&self) -> bool {
+        self.short_quantity > QTY_EPSILON
+            || self.excess_quantity > QTY_EPSILON
+            || self.damaged_quantity > QTY_EPSILON
+    }
+}
+
+/// What an insurance claim for a shipment's receipt shortfall can draw on.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ReceiptClaimBasis {
+    pub shipment_id: String,
+    pub invoice_number: String,
+    pub invoice_currency: String,
+    pub grn_numbers: Vec<String>,
+    /// Invoice lines that were short, excess or damaged.
+    pub lines: Vec<ReceiptVarianceLine>,
+    pub photos: Vec<GoodsReceiptPhoto>,
+    pub invoice_value_at_risk: f64,
+    pub landed_value_at_risk: f64,
+}
+
+/// Good units (received less damaged) on the invoice line's posted GRNs; `None` if none cover it.
+pub fn good_quantity_received(
+    conn: &Connection,
+    invoice_line_item_id: &str,
+) -> rusqlite::Result<Option<f64>> {
+    let (count, good): (i64, f64) = conn.query_row(
+        "SELECT COUNT(*), COALESCE(SUM(l.received_quantity - l.damaged_quantity), 0)
+         FROM goods_receipt_lines l JOIN goods_receipts g ON g.id = l.grn_id
+         WHERE l.invoice_line_item_id = ?1 AND g.status = ?2",
+        params![invoice_line_item_id, POSTED],
+        |r| Ok((r.get(0)?, r.get(1)?)),
+    )?;
+    Ok((count > 0).then_some(good.max(0.0)))
+}
+
+/// Units received on posted GRNs other than `exclude_grn_id`.
+fn received_elsewhere(
+    conn: &Connection,
+    invoice_line_item_id: &str,
+    exclude_grn_id: &str,
+) -> rusqlite::Result<f64> {
+    conn.query_row(
+        "SELECT COALESCE(SUM(l.received_quantity), 0)
+         FROM goods_receipt_lines l JOIN goods_receipts g ON g.id = l.grn_id
+         WHERE l.invoice_line_item_id = ?1 AND g.status = ?2 AND g.id <> ?3",
+        params![invoice_line_item_id, POSTED, exclude_grn_id],
+        |r| r.get(0),
+    )
+}
+
+/// Units received on GRNs posted before `grn_id`, in posting order.
+fn received_before(
+    conn: &Connection,
+    invoice_line_item_id: &str,
+    grn_id: &str,
+) -> rusqlite::Result<f64> {
+    conn.query_row(
+        "SELECT COALESCE(SUM(l.received_quantity), 0)
+         FROM goods_receipt_lines l
+         JOIN goods_receipts g ON g.id = l.grn_id
+         JOIN goods_receipts this ON this.id = ?3
+         WHERE l.invoice_line_item_id = ?1 AND g.status = ?2
+           AND (g.posted_at, g.rowid) < (this.posted_at, this.rowid)",
+        params![invoice_line_item_id, POSTED, grn_id],
+        |r| r.get(0),
+    )
+}
+
+/// Expects of the line what `before` left outstanding of the invoiced quantity.
+fn set_expected(
+    conn: &Connection,
+    line: &GoodsReceiptLine,
+    invoiced: f64,
+    before: f64,
+) -> rusqlite::Result<()> {
+    let expected = (invoiced - before).max(0.0);
+    conn.execute(
+        "UPDATE goods_receipt_lines SET expected_quantity = ?2, short_quantity = ?3, excess_quantity = ?4 WHERE id = ?1",
+        params![
+            line.id,
+            expected,
+            (expected - line.received_quantity).max(0.0),
+            (line.received_quantity - expected).max(0.0)
+        ],
+    )?;
+    Ok(())
+}
+
+fn invoiced_quantity(
+    conn: &Connection,
+    invoice_line_item_id: &str,
+) -> rusqlite::Result<Option<f64>> {
+    conn.query_row(
+        "SELECT quantity FROM invoice_line_items WHERE id = ?1",
+        params![invoice_line_item_id],
+        |r| r.get(0),
+    )
+    .optional()
+}
+
+fn load_photos(conn: &Connection, grn_id: &str) -> rusqlite::Result<Vec<GoodsReceiptPhoto>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, grn_id, grn_line_id, file_name, file_path, caption, uploaded_by, uploaded_at
+         FROM goods_receipt_photos WHERE grn_id = ?1 ORDER BY uploaded_at, id",
+    )?;
+    let rows = stmt.query_map(params![grn_id], |r| {
+        Ok(GoodsReceiptPhoto {
+            id: r.get(0)?,
+            grn_id: r.get(1)?,
+            grn_line_id: r.get(2)?,
+            file_name: r.get(3)?,
+            file_path: r.get(4)?,
+            caption: r.get(5)?,
+            uploaded_by: r.get(6)?,
+            uploaded_at: r.get(7)?,
+        })
+    })?;
+    rows.collect()
+}
+
+fn load_lines(conn: &Connection, grn_id: &str) -> rusqlite::Result<Vec<GoodsReceiptLine>> {
+    let mut stmt = conn.prepare(
+        "SELECT l.id, l.invoice_line_item_id, l.item_id, i.part_number, l.expected_quantity,
+                l.received_quantity, l.damaged_quantity, l.short_quantity, l.excess_quantity, l.remarks
+         FROM goods_receipt_lines l
+         LEFT JOIN items i ON i.id = l.item_id
+         WHERE l.grn_id = ?1
+         ORDER BY i.part_number, l.id",
+    )?;
+    let rows = stmt.query_map(params![grn_id], |r| {
+        Ok(GoodsReceiptLine {
+            id: r.get(0)?,
+            invoice_line_item_id: r.get(1)?,
+            item_id: r.get(2)?,
+            part_number: r.get(3)?,
+            expected_quantity: r.get(4)?,
+            received_quantity: r.get(5)?,
+            damaged_quantity: r.get(6)?,
+            short_quantity: r.get(7)?,
+            excess_quantity: r.get(8)?,
+            remarks: r.get(9)?,
+        })
+    })?;
+    rows.collect()
+}
+
+const GRN_COLUMNS: &str = "id, grn_number, shipment_id, received_date, warehouse, status, notes, received_by, posted_at, created_at, updated_at";
+
+fn map_grn(r: &rusqlite::Row) -> rusqlite::Result<GoodsReceipt> {
+    Ok(GoodsReceipt {
+        id: r.get(0)?,
+        grn_number: r.get(1)?,
+        shipment_id: r.get(2)?,
+        received_date: r.get(3)?,
+        warehouse: r.get(4)?,
+        status: r.get(5)?,
+        notes: r.get(6)?,
+        received_by: r.get(7)?,
+        posted_at: r.get(8)?,
+        created_at: r.get(9)?,
+        updated_at: r.get(10)?,
+        lines: Vec::new(),
+        photos: Vec::new(),
+    })
+}
+
+pub fn load_goods_receipt(conn: &Connection, id: &str) -> Result<Option<GoodsReceipt>, String> {
+    let grn = conn
+        .query_row(
+            &format!("SELECT {GRN_COLUMNS} FROM goods_receipts WHERE id = ?1"),
+            params![id],
+            map_grn,
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+    let Some(mut grn) = grn else {
+        return Ok(None);
+    };
+    grn.lines = load_lines(conn, id).map_err(|e| e.to_string())?;
+    grn.photos = load_photos(conn, id).map_err(|e| e.to_string())?;
+    Ok(Some(grn))
+}
+
+fn grn_status(conn: &Connection, id: &str) -> Result<String, String> {
+    conn.query_row(
+        "SELECT status FROM goods_receipts WHERE id = ?1",
+        params![id],
+        |r| r.get(0),
+    )
+    .optional()
+    .map_err(|e| e.to_string())?
+    .ok_or_else(|| format!("Goods receipt {id} not found"))
+}
+
+/// Creates or replaces a draft GRN. The shipment must be delivered and every line must be one of
+/// its invoice lines.
+pub fn upsert_goods_receipt(
+    conn: &Connection,
+    payload: SaveGoodsReceiptPayload,
+) -> Result<GoodsReceipt, String> {
+    let status: Option<String> = conn
+        .query_row(
+            "SELECT status FROM shipments WHERE id = ?1",
+            params![payload.shipment_id],
+            |r| r.get(0),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+    match status
+        .as_deref()
+        .and_then(shipment_status::normalize_status)
+    {
+        None => return Err(format!("Shipment {} not found", payload.shipment_id)),
+        Some(shipment_status::DELIVERED) => {}
+        Some(other) => {
+            return Err(format!(
+                "Goods can only be received against a delivered shipment (currently {other})"
+            ))
+        }
+    }
+    if let Some(id) = payload.id.as_deref() {
+        if grn_status(conn, id)? != DRAFT {
+            return Err("Only draft goods receipts can be edited".to_string());
+        }
+    }
+    let received_date = tariff::normalize_date(&payload.received_date)
+        .ok_or_else(|| format!("Invalid received date '{}'", payload.received_date))?;
+    if payload.lines.is_empty() {
+        return Err("A goods receipt needs at least one line".to_string());
+    }
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT ili.id, ili.item_id FROM invoice_line_items ili
+             JOIN invoices inv ON inv.id = ili.invoice_id
+             WHERE inv.shipment_id = ?1",
+        )
+        .map_err(|e| e.to_string())?;
+    let invoice_lines: Vec<(String, String)> = stmt
+        .query_map(params![payload.shipment_id], |r| Ok((r.get(0)?, r.get(1)?)))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())?;
+    let mut seen = BTreeSet::new();
+    for (i, line) in payload.lines.iter().enumerate() {
+        let n = i + 1;
+        if !invoice_lines
+            .iter()
+            .any(|(id, _)| id == &line.invoice_line_item_id)
+        {
+            return Err(format!(
+                "Line {n}: invoice line {} is not on this shipment",
+                line.invoice_line_item_id
+            ));
+        }
+        if !seen.insert(line.invoice_line_item_id.as_str()) {
+            return Err(format!(
+                "Line {n}: invoice line {} is listed twice",
+                line.invoice_line_item_id
+            ));
+        }
+        for q in [line.received_quantity, line.damaged_quantity] {
+            if !q.is_finite() || q < 0.0 {
+                return Err(format!("Line {n}: quantities cannot be negative"));
+            }
+        }
+        if line.damaged_quantity > line.received_quantity + QTY_EPSILON {
+            return Err(format!(
+                "Line {n}: damaged quantity exceeds the quantity received"
+            ));
+        }
+    }
+
+    let id = payload
+        .id
+        .clone()
+        .unwrap_or_else(|| generate_id(Some("GRN".to_string())));
+    let grn_number = payload
+        .grn_number
+        .as_deref()
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+        .unwrap_or_else(|| id.clone());
+
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    tx.execute(
+        "INSERT INTO goods_receipts (id, grn_number, shipment_id, received_date, warehouse, status, notes, received_by)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+         ON CONFLICT (id) DO UPDATE SET
+            grn_number = excluded.grn_number,
+            received_date = excluded.received_date,
+            warehouse = excluded.warehouse,
+            notes = excluded.notes,
+            received_by = excluded.received_by,
+            updated_at = strftime('%Y-%m-%d %H:%M:%S', 'now', 'localtime')",
+        params![
+            id,
+            grn_number,
+            payload.shipment_id,
+            received_date,
+            payload.warehouse,
+            DRAFT,
+            payload.notes,
+            payload.user_id
+        ],
+    )
+    .map_err(|e| match e {
+        rusqlite::Error::SqliteFailure(ref f, _)
+            if f.code == rusqlite::ErrorCode::ConstraintViolation =>
+        {
+            format!("GRN number {grn_number} already exists")
+        }
+        e => e.to_string(),
+    })?;
+    // Lines dropped from the draft lose their photos' line link, not the photos.
+    let mut stmt = tx
+        .prepare("SELECT id, invoice_line_item_id FROM goods_receipt_lines WHERE grn_id = ?1")
+        .map_err(|e| e.to_string())?;
+    let existing: Vec<(String, String)> = stmt
+        .query_map(params![id], |r| Ok((r.get(0)?, r.get(1)?)))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())?;
+    drop(stmt);
+    for (line_id, invoice_line_item_id) in &existing {
+        if seen.contains(invoice_line_item_id.as_str()) {
+            continue;
+        }
+        tx.execute(
+            "UPDATE goods_receipt_photos SET grn_line_id = NULL WHERE grn_line_id = ?1",
+            params![line_id],
+        )
+        .map_err(|e| e.to_string())?;
+        tx.execute(
+            "DELETE FROM goods_receipt_lines WHERE id = ?1",
+            params![line_id],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    for line in &payload.lines {
+        let (_, item_id) = invoice_lines
+            .iter()
+            .find(|(ili, _)| ili == &line.invoice_line_item_id)
+            .expect("validated above");
+        tx.execute(
+            "INSERT INTO goods_receipt_lines (id, grn_id, invoice_line_item_id, item_id, expected_quantity,
+                received_quantity, damaged_quantity, remarks)
+             VALUES (?1, ?2, ?3, ?4,
+                MAX((SELECT quantity FROM invoice_line_items WHERE id = ?3) - ?8, 0), ?5, ?6, ?7)
+             ON CONFLICT (grn_id, invoice_line_item_id) DO UPDATE SET
+                expected_quantity = excluded.expected_quantity,
+                received_quantity = excluded.received_quantity,
+                damaged_quantity = excluded.damaged_quantity,
+                remarks = excluded.remarks",
+            params![
+                generate_id(Some("GRL".to_string())),
+                id,
+                line.invoice_line_item_id,
+                item_id,
+                line.received_quantity,
+                line.damaged_quantity,
+                line.remarks,
+                received_elsewhere(&tx, &line.invoice_line_item_id, &id)
+                    .map_err(|e| e.to_string())?
+            ],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    tx.commit().map_err(|e| e.to_string())?;
+    load_goods_receipt(conn, &id)?.ok_or_else(|| format!("Goods receipt {id} not found"))
+}
+
+/// Fixes each line's expected, short and excess quantities against what earlier posted GRNs
+/// already received, posts the GRN and brings the shipment's variance case and landed cost in line.
+pub fn post_receipt(conn: &Connection, id: &str) -> Result<GoodsReceipt, String> {
+    if grn_status(conn, id)? != DRAFT {
+        return Err("Only draft goods receipts can be posted".to_string());
+    }
+    let grn =
+        load_goods_receipt(conn, id)?.ok_or_else(|| format!("Goods receipt {id} not found"))?;
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    for line in &grn.lines {
+        let invoiced = invoiced_quantity(&tx, &line.invoice_line_item_id)
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| {
+                format!(
+                    "Invoice line {} no longer exists; edit the goods receipt first",
+                    line.invoice_line_item_id
+                )
+            })?;
+        let before =
+            received_elsewhere(&tx, &line.invoice_line_item_id, id).map_err(|e| e.to_string())?;
+        set_expected(&tx, line, invoiced, before).map_err(|e| e.to_string())?;
+    }
+    tx.execute(
+        "UPDATE goods_receipts SET status = ?2,
+            posted_at = strftime('%Y-%m-%d %H:%M:%S', 'now', 'localtime'),
+            updated_at = strftime('%Y-%m-%d %H:%M:%S', 'now', 'localtime')
+         WHERE id = ?1",
+        params![id, POSTED],
+    )
+    .map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+    after_receipt_change(conn, &grn.shipment_id);
+    load_goods_receipt(conn, id)?.ok_or_else(|| format!("Goods receipt {id} not found"))
+}
+
+/// Re-derives the expected, short and excess quantities of the GRNs posted after `cancelled_id`,
+/// which were fixed while its receipt still counted.
+fn rederive_later_receipts(conn: &Connection, cancelled_id: &str) -> rusqlite::Result<()> {
+    let mut stmt = conn.prepare(
+        "SELECT g.id FROM goods_receipts g JOIN goods_receipts c ON c.id = ?1
+         WHERE g.shipment_id = c.shipment_id AND g.status = ?2
+           AND (g.posted_at, g.rowid) > (c.posted_at, c.rowid)
+         ORDER BY g.posted_at, g.rowid",
+    )?;
+    let later = stmt
+        .query_map(params![cancelled_id, POSTED], |r| r.get::<_, String>(0))?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    for grn_id in later {
+        for line in load_lines(conn, &grn_id)? {
+            // A line whose invoice line is gone keeps the figures it was posted with.
+            let Some(invoiced) = invoiced_quantity(conn, &line.invoice_line_item_id)? else {
+                continue;
+            };
+            let before = received_before(conn, &line.invoice_line_item_id, &grn_id)?;
+            set_expected(conn, &line, invoiced, before)?;
+        }
+    }
+    Ok(())
+}
+
+/// Withdraws a draft or posted GRN; a posted one stops counting towards the shipment's receipt and
+/// the GRNs posted after it expect again what it had received.
+pub fn cancel_receipt(conn: &Connection, id: &str) -> Result<GoodsReceipt, String> {
+    let status = grn_status(conn, id)?;
+    if status == CANCELLED {
+        return Err("Goods receipt is already cancelled".to_string());
+    }
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    tx.execute(
+        "UPDATE goods_receipts SET status = ?2, updated_at = strftime('%Y-%m-%d %H:%M:%S', 'now', 'localtime') WHERE id = ?1",
+        params![id, CANCELLED],
+    )
+    .map_err(|e| e.to_string())?;
+    if status == POSTED {
+        rederive_later_receipts(&tx, id).map_err(|e| e.to_string())?;
+    }
+    tx.commit().map_err(|e| e.to_string())?;
+    let grn =
+        load_goods_receipt(conn, id)?.ok_or_else(|| format!("Goods receipt {id} not found"))?;
+    if status == POSTED {
+        after_receipt_change(conn, &grn.shipment_id);
+    }
+    Ok(grn)
+}
+
+/// Receipt position of every invoice line once the shipment has at least one posted GRN; lines no
+/// GRN mentions count as fully short. Empty while nothing has been received.
+pub fn shipment_variances(
+    conn: &Connection,
+    shipment_id: &str,
+) -> Result<Vec<ReceiptVarianceLine>, String> {
+    let posted: i64 = conn
+        .query_row(
+            "SELECT COUNT(*) FROM goods_receipts WHERE shipment_id = ?1 AND status = ?2",
+            params![shipment_id, POSTED],
+            |r| r.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+    if posted == 0 {
+        return Ok(Vec::new());
+    }
+    let mut stmt = conn
+        .prepare(
+            "SELECT ili.id, ili.item_id, i.part_number, ili.quantity, ili.unit_price,
+                    COALESCE((SELECT SUM(l.received_quantity) FROM goods_receipt_lines l
+                              JOIN goods_receipts g ON g.id = l.grn_id
+                              WHERE l.invoice_line_item_id = ili.id AND g.status = ?2), 0),
+                    COALESCE((SELECT SUM(l.damaged_quantity) FROM goods_receipt_lines l
+                              JOIN goods_receipts g ON g.id = l.grn_id
+                              WHERE l.invoice_line_item_id = ili.id AND g.status = ?2), 0),
+                    ilc.landed_cost_per_unit
+             FROM invoices inv
+             JOIN invoice_line_items ili ON ili.invoice_id = inv.id
+             LEFT JOIN items i ON i.id = ili.item_id
+             LEFT JOIN item_landed_costs ilc ON ilc.invoice_line_item_id = ili.id
+             WHERE inv.shipment_id = ?1
+             ORDER BY i.part_number, ili.id",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![shipment_id, POSTED], |r| {
+            let invoiced_quantity: f64 = r.get(3)?;
+            let unit_price: f64 = r.get(4)?;
+            let received_quantity: f64 = r.get(5)?;
+            let damaged_quantity: f64 = r.get(6)?;
+            let landed_cost_per_unit: Option<f64> = r.get(7)?;
+            let short_quantity = (invoiced_quantity - received_quantity).max(0.0);
+            let lost = short_quantity + damaged_quantity;
+            Ok(ReceiptVarianceLine {
+                invoice_line_item_id: r.get(0)?,
+                item_id: r.get(1)?,
+                part_number: r.get(2)?,
+                invoiced_quantity,
+                received_quantity,
+                damaged_quantity,
+                short_quantity,
+                excess_quantity: (received_quantity - invoiced_quantity).max(0.0),
+                unit_price,
+                invoice_value_at_risk: round_paise(lost * unit_price),
+                landed_cost_per_unit,
+                landed_value_at_risk: landed_cost_per_unit.map(|c| round_paise(lost * c)),
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())
+}
+
+/// Opens a `GRN_VARIANCE` case while any invoice line is short, excess or damaged and resolves it once
+/// the receipt squares up.
+pub fn sync_variance_case(
+    conn: &Connection,
+    shipment_id: &str,
+    variances: &[ReceiptVarianceLine],
+) -> Result<Option<String>, String> {
+    let flagged: Vec<&ReceiptVarianceLine> =
+        variances.iter().filter(|v| v.has_variance()).collect();
+    if flagged.is_empty() {
+        exception_workflow::auto_resolve_case_for_entity(conn, GRN_VARIANCE, shipment_id)?;
+        return Ok(None);
+    }
+    let sum = |f: fn(&ReceiptVarianceLine) -> f64| flagged.iter().map(|v| f(v)).sum::<f64>();
+    let short = sum(|v| v.short_quantity);
+    let damaged = sum(|v| v.damaged_quantity);
+    let details = serde_json::json!({
+        "exceptionType": GRN_VARIANCE,
+        "lines": flagged.len(),
+        "shortQuantity": short,
+        "excessQuantity": sum(|v| v.excess_quantity),
+        "damagedQuantity": damaged,
+        "invoiceValueAtRisk": round_paise(sum(|v| v.invoice_value_at_risk)),
+    });
+    // Excess alone costs nothing up front; loss or damage may need a claim.
+    let priority = if short > QTY_EPSILON || damaged > QTY_EPSILON {
+        "HIGH"
+    } else {
+        "MEDIUM"
+    };
+    exception_workflow::open_case_for_entity(
+        conn,
+        GRN_VARIANCE,
+        shipment_id,
+        priority,
+        &details.to_string(),
+    )
+}
+
+/// Re-syncs the variance case and landed cost after a GRN is posted or cancelled; failures are only logged.
+fn after_receipt_change(conn: &Connection, shipment_id: &str) {
+    match shipment_variances(conn, shipment_id) {
+        Ok(variances) => {
+            if let Err(e) = sync_variance_case(conn, shipment_id, &variances) {
+                log::warn!("GRN variance case for shipment {shipment_id} failed: {e}");
+            }
+        }
+        Err(e) => log::warn!("GRN variances for shipment {shipment_id} failed: {e}"),
+    }
+    landed_cost::recompute_after_change(conn, shipment_id);
+}
+
+pub fn claim_basis(conn: &Connection, shipment_id: &str) -> Result<ReceiptClaimBasis, String> {
+    let (invoice_number, invoice_currency): (String, String) = conn
+        .query_row(
+            "SELECT invoice_number, invoice_currency FROM shipments WHERE id = ?1",
+            params![shipment_id],
+            |r| Ok((r.get(0)?, r.get(1)?)),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Shipment {shipment_id} not found"))?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, grn_number FROM goods_receipts WHERE shipment_id = ?1 AND status = ?2
+             ORDER BY received_date, grn_number",
+        )
+        .map_err(|e| e.to_string())?;
+    let grns: Vec<(String, String)> = stmt
+        .query_map(params![shipment_id, POSTED], |r| Ok((r.get(0)?, r.get(1)?)))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())?;
+    let mut photos = Vec::new();
+    for (id, _) in &grns {
+        photos.extend(load_photos(conn, id).map_err(|e| e.to_string())?);
+    }
+    let lines: Vec<ReceiptVarianceLine> = shipment_variances(conn, shipment_id)?
+        .into_iter()
+        .filter(|v| v.has_variance())
+        .collect();
+    Ok(ReceiptClaimBasis {
+        shipment_id: shipment_id.to_string(),
+        invoice_number,
+        invoice_currency,
+        grn_numbers: grns.into_iter().map(|(_, n)| n).collect(),
+        invoice_value_at_risk: round_paise(lines.iter().map(|v| v.invoice_value_at_risk).sum()),
+        landed_value_at_risk: round_paise(
+            lines.iter().filter_map(|v| v.landed_value_at_risk).sum(),
+        ),
+        lines,
+        photos,
+    })
+}
+
+#[tauri::command]
+pub fn list_goods_receipts(
+    shipment_id: Option<String>,
+    state: State<DbState>,
+) -> Result<Vec<GoodsReceipt>, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {GRN_COLUMNS} FROM goods_receipts
+             WHERE (?1 IS NULL OR shipment_id = ?1)
+             ORDER BY received_date DESC, grn_number"
+        ))
+        .map_err(|e| e.to_string())?;
+    let mut grns = stmt
+        .query_map(params![shipment_id], map_grn)
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    for grn in grns.iter_mut() {
+        grn.lines = load_lines(&conn, &grn.id).map_err(|e| e.to_string())?;
+        grn.photos = load_photos(&conn, &grn.id).map_err(|e| e.to_string())?;
+    }
+    Ok(grns)
+}
+
+#[tauri::command]
+pub fn get_goods_receipt(id: String, state: State<DbState>) -> Result<GoodsReceipt, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    load_goods_receipt(&conn, &id)?.ok_or_else(|| format!("Goods receipt {id} not found"))
+}
+
+#[tauri::command]
+pub fn save_goods_receipt(
+    payload: SaveGoodsReceiptPayload,
+    state: State<DbState>,
+) -> Result<GoodsReceipt, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    upsert_goods_receipt(&conn, payload)
+}
+
+#[tauri::command]
+pub fn post_goods_receipt(id: String, state: State<DbState>) -> Result<GoodsReceipt, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    let grn = post_receipt(&conn, &id)?;
+    let _ = dashboard_cache::invalidate_dashboard_metrics_cache(&conn);
+    Ok(grn)
+}
+
+#[tauri::command]
+pub fn cancel_goods_receipt(id: String, state: State<DbState>) -> Result<GoodsReceipt, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    let grn = cancel_receipt(&conn, &id)?;
+    let _ = dashboard_cache::invalidate_dashboard_metrics_cache(&conn);
+    Ok(grn)
+}
+
+/// Copies a photo into app data attachments/grn/<GRN id>/ and records it against the GRN or one of its lines.
+#[tauri::command]
+pub fn attach_goods_receipt_photo(
+    app: tauri::AppHandle,
+    grn_id: String,
+    grn_line_id: Option<String>,
+    src_path: String,
+    caption: Option<String>,
+    user_id: Option<String>,
+    state: State<DbState>,
+) -> Result<GoodsReceiptPhoto, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    let status = grn_status(&conn, &grn_id)?;
+    if status == CANCELLED {
+        return Err("Photos cannot be added to a cancelled goods receipt".to_string());
+    }
+    if let Some(line_id) = grn_line_id.as_deref() {
+        let belongs: bool = conn
+            .query_row(
+                "SELECT EXISTS (SELECT 1 FROM goods_receipt_lines WHERE id = ?1 AND grn_id = ?2)",
+                params![line_id, grn_id],
+                |r| r.get(0),
+            )
+            .map_err(|e| e.to_string())?;
+        if !belongs {
+            return Err(format!("Line {line_id} is not on goods receipt {grn_id}"));
+        }
+    }
+
+    let base = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    let attach_dir = base.join("attachments").join("grn").join(&grn_id);
+    std::fs::create_dir_all(&attach_dir).map_err(|e| e.to_string())?;
+    let file_name = std::path::Path::new(&src_path)
+        .file_name()
+        .and_then(|s| s.to_str())
+        .ok_or("Invalid source file name")?
+        .to_string();
+    let dest_path = attach_dir.join(&file_name);
+    std::fs::copy(&src_path, &dest_path).map_err(|e| e.to_string())?;
+
+    let id = generate_id(Some("GRP".to_string()));
+    conn.execute(
+        "INSERT INTO goods_receipt_photos (id, grn_id, grn_line_id, file_name, file_path, caption, uploaded_by)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![
+            id,
+            grn_id,
+            grn_line_id,
+            file_name,
+            dest_path.to_string_lossy().to_string(),
+            caption,
+            user_id
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    load_photos(&conn, &grn_id)
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .find(|p| p.id == id)
+        .ok_or_else(|| "Photo was not saved".to_string())
+}
+
+#[tauri::command]
+pub fn get_shipment_receipt_variances(
+    shipment_id: String,
+    state: State<DbState>,
+) -> Result<Vec<ReceiptVarianceLine>, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    shipment_variances(&conn, &shipment_id)
+}
+
+#[tauri::command]
+pub fn get_goods_receipt_claim_basis(
+    shipment_id: String,
+    state: State<DbState>,
+) -> Result<ReceiptClaimBasis, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    claim_basis(&conn, &shipment_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::test_support;
+
+    fn line(id: &str, received: f64, damaged: f64) -> SaveGoodsReceiptLine {
+        SaveGoodsReceiptLine {
+            invoice_line_item_id: id.into(),
+            received_quantity: received,
+            damaged_quantity: damaged,
+            remarks: None,
+        }
+    }
+
+    fn draft(lines: Vec<SaveGoodsReceiptLine>) -> SaveGoodsReceiptPayload {
+        SaveGoodsReceiptPayload {
+            id: None,
+            grn_number: None,
+            shipment_id: "SHP-1".into(),
+            received_date: "2024-04-02".into(),
+            warehouse: Some("Main".into()),
+            notes: None,
+            user_id: Some("stores".into()),
+            lines,
+        }
+    }
+
+    fn posted(conn: &Connection, lines: Vec<SaveGoodsReceiptLine>) -> GoodsReceipt {
+        let grn = upsert_goods_receipt(conn, draft(lines)).unwrap();
+        post_receipt(conn, &grn.id).unwrap()
+    }
+
+    fn variance(conn: &Connection, invoice_line_item_id: &str) -> ReceiptVarianceLine {
+        shipment_variances(conn, "SHP-1")
+            .unwrap()
+            .into_iter()
+            .find(|v| v.invoice_line_item_id == invoice_line_item_id)
+            .unwrap()
+    }
+
+    fn case_status(conn: &Connection) -> Option<String> {
+        test_support::exception_case(conn, GRN_VARIANCE, "SHP-1").map(|c| c.0)
+    }
+
+    /// SHP-1 invoiced 10 x part A (line L-A) and 10 x part B (L-B) at 100 each, with 1,000 of
+    /// documentation charges split equally between the lines.
+    fn shipment(status: &str) -> Connection {
+        let conn = test_support::migrated_db();
+        test_support::add_shipment(&conn, "SHP-1", "2024-03-01", 2000.0, "USD", status);
+        test_support::add_item(&conn, "IT-A", "A", "Bearing", None);
+        test_support::add_item(&conn, "IT-B", "B", "Seal", None);
+        conn.execute_batch(
+            "UPDATE shipments SET date_of_delivery = '2024-04-01' WHERE id = 'SHP-1';
+             INSERT INTO invoices (id, shipment_id, status) VALUES ('INVC-1', 'SHP-1', 'Finalized');
+             INSERT INTO invoice_line_items (id, invoice_id, item_id, quantity, unit_price, duty_percent, sws_percent, igst_percent)
+                VALUES ('L-A', 'INVC-1', 'IT-A', 10, 100, 10, 10, 18),
+                       ('L-B', 'INVC-1', 'IT-B', 10, 100, 10, 10, 18);
+             INSERT INTO service_providers (id, name) VALUES ('SP-1', 'Forwarder');
+             INSERT INTO expense_types (id, name, allocation_basis) VALUES ('ET-DOC', 'Documentation Test', 'EQUAL');
+             INSERT INTO expense_invoices (id, shipment_id, service_provider_id, invoice_no, invoice_date, total_amount)
+                VALUES ('EI-1', 'SHP-1', 'SP-1', 'F-1', '2024-03-05', 0);
+             INSERT INTO expenses (id, expense_invoice_id, shipment_id, service_provider_id, invoice_no, invoice_date, expense_type_id, amount)
+                VALUES ('E-DOC', 'EI-1', 'SHP-1', 'SP-1', 'F-1', '2024-03-05', 'ET-DOC', 1000);",
+        )
+        .unwrap();
+        conn
+    }
+
+    #[test]
+    fn goods_are_only_received_against_delivered_shipments() {
+        let conn = shipment("in-transit");
+        let err = upsert_goods_receipt(&conn, draft(vec![line("L-A", 10.0, 0.0)])).unwrap_err();
+        assert!(err.contains("delivered shipment"));
+    }
+
+    #[test]
+    fn receipt_lines_are_validated_against_the_invoice() {
+        let conn = shipment("delivered");
+        let rejected = |lines| upsert_goods_receipt(&conn, draft(lines)).unwrap_err();
+        assert!(rejected(vec![line("L-A", 2.0, 3.0)]).contains("damaged quantity exceeds"));
+        assert!(rejected(vec![line("L-A", -1.0, 0.0)]).contains("cannot be negative"));
+        assert!(rejected(vec![line("L-X", 1.0, 0.0)]).contains("not on this shipment"));
+        assert!(
+            rejected(vec![line("L-A", 4.0, 0.0), line("L-A", 6.0, 0.0)]).contains("listed twice")
+        );
+        assert!(rejected(vec![]).contains("at least one line"));
+    }
+
+    #[test]
+    fn drafts_do_not_count_until_posted() {
+        let conn = shipment("delivered");
+        let grn = upsert_goods_receipt(
+            &conn,
+            draft(vec![line("L-A", 10.0, 2.0), line("L-B", 6.0, 0.0)]),
+        )
+        .unwrap();
+        assert_eq!(grn.status, DRAFT);
+        assert!(shipment_variances(&conn, "SHP-1").unwrap().is_empty());
+        assert_eq!(case_status(&conn), None);
+
+        let grn = post_receipt(&conn, &grn.id).unwrap();
+        let b = grn
+            .lines
+            .iter()
+            .find(|l| l.invoice_line_item_id == "L-B")
+            .unwrap();
+        assert_eq!((b.expected_quantity, b.short_quantity), (10.0, 4.0));
+        assert_eq!(case_status(&conn).as_deref(), Some("OPEN"));
+
+        // Posted receipts are frozen.
+        assert!(post_receipt(&conn, &grn.id).is_err());
+        let mut edit = draft(vec![line("L-B", 10.0, 0.0)]);
+        edit.id = Some(grn.id.clone());
+        assert!(upsert_goods_receipt(&conn, edit)
+            .unwrap_err()
+            .contains("Only draft"));
+    }
+
+    #[test]
+    fn damaged_units_are_costed_out_and_claimed() {
+        let conn = shipment("delivered");
+        posted(&conn, vec![line("L-A", 10.0, 2.0), line("L-B", 6.0, 0.0)]);
+
+        let costs = landed_cost::recompute_shipment(&conn, "SHP-1").unwrap();
+        let a = costs.iter().find(|c| c.part_no == "A").unwrap();
+        // 500 of documentation over 8 good units.
+        assert_eq!(a.received_quantity, Some(8.0));
+        assert_eq!(a.landed_cost_per_received_unit, Some(62.5));
+
+        // 2 damaged A and 4 short B at 100 each.
+        let basis = claim_basis(&conn, "SHP-1").unwrap();
+        assert_eq!(basis.lines.len(), 2);
+        assert_eq!(basis.invoice_value_at_risk, 600.0);
+        assert_eq!(basis.landed_value_at_risk, 300.0);
+    }
+
+    #[test]
+    fn later_receipts_expect_only_what_is_still_outstanding() {
+        let conn = shipment("delivered");
+        posted(&conn, vec![line("L-A", 10.0, 0.0), line("L-B", 6.0, 0.0)]);
+        let second = posted(&conn, vec![line("L-B", 5.0, 0.0)]);
+        assert_eq!(
+            (
+                second.lines[0].expected_quantity,
+                second.lines[0].excess_quantity
+            ),
+            (4.0, 1.0)
+        );
+        let b = variance(&conn, "L-B");
+        assert_eq!((b.short_quantity, b.excess_quantity), (0.0, 1.0));
+    }
+
+    #[test]
+    fn cancelling_an_earlier_receipt_re_derives_the_later_ones() {
+        let conn = shipment("delivered");
+        let first = posted(&conn, vec![line("L-A", 10.0, 0.0), line("L-B", 6.0, 0.0)]);
+        let second = posted(&conn, vec![line("L-B", 5.0, 0.0)]);
+        let third = posted(&conn, vec![line("L-B", 1.0, 0.0)]);
+
+        cancel_receipt(&conn, &first.id).unwrap();
+        // Without the first receipt's 6, the second expects all 10 and the third the 5 left.
+        let figures = |id: &str| {
+            let l = load_goods_receipt(&conn, id).unwrap().unwrap().lines[0].clone();
+            (l.expected_quantity, l.short_quantity, l.excess_quantity)
+        };
+        assert_eq!(figures(&second.id), (10.0, 5.0, 0.0));
+        assert_eq!(figures(&third.id), (5.0, 4.0, 0.0));
+    }
+
+    #[test]
+    fn cancelling_a_posted_receipt_reopens_its_shortfall_until_received_again() {
+        let conn = shipment("delivered");
+        let first = posted(&conn, vec![line("L-A", 10.0, 0.0), line("L-B", 10.0, 0.0)]);
+        assert_eq!(case_status(&conn), None);
+
+        cancel_receipt(&conn, &first.id).unwrap();
+        assert!(cancel_receipt(&conn, &first.id)
+            .unwrap_err()
+            .contains("already cancelled"));
+        // With nothing posted any more the shipment has no receipt position at all.
+        assert!(shipment_variances(&conn, "SHP-1").unwrap().is_empty());
+
+        // A partial repost counts the unlisted line as fully short.
+        let partial = posted(&conn, vec![line("L-B", 10.0, 0.0)]);
+        assert_eq!(variance(&conn, "L-A").short_quantity, 10.0);
+        assert_eq!(case_status(&conn).as_deref(), Some("OPEN"));
+
+        // The cancelled GRN no longer counts towards what a new one should expect.
+        cancel_receipt(&conn, &partial.id).unwrap();
+        let complete = posted(&conn, vec![line("L-A", 10.0, 0.0), line("L-B", 10.0, 0.0)]);
+        assert!(complete.lines.iter().all(|l| (
+            l.expected_quantity,
+            l.short_quantity,
+            l.excess_quantity
+        ) == (10.0, 0.0, 0.0)));
+        assert_eq!(case_status(&conn).as_deref(), Some("RESOLVED"));
+    }
+}
@@ -0,0 +1,702 @@
+//! IGST input-credit reconciliation of our Bills of Entry against GSTR-2B IMPG rows, with `GST_2B_MISMATCH` cases.
+
+use crate::commands::boe_reconciliation;
+use crate::commands::dashboard_cache;
+use crate::commands::exception_workflow;
+use crate::commands::tariff::normalize_date;
+use crate::commands::utils::generate_id;
+use crate::db::{CalculationResult, DbState};
+use crate::duty_engine;
+use crate::gstr2b::{self, Gstr2bImpgRow, ParsedGstr2b};
+use crate::utils::csv::escape_field;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::Path;
+use tauri::State;
+
+pub const GST_2B_MISMATCH: &str = "GST_2B_MISMATCH";
+
+pub const MATCHED: &str = "MATCHED";
+pub const AMOUNT_MISMATCH: &str = "AMOUNT_MISMATCH";
+pub const MISSING_IN_2B: &str = "MISSING_IN_2B";
+pub const MISSING_IN_BOOKS: &str = "MISSING_IN_BOOKS";
+/// The BOE is in our books but has no saved calculation, so its IGST cannot be checked.
+pub const NOT_CALCULATED: &str = "NOT_CALCULATED";
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Gstr2bImportSummary {
+    pub id: String,
+    pub gstin: Option<String>,
+    pub return_period: Option<String>,
+    pub file_name: Option<String>,
+    /// BE date range checked for BOEs missing in 2B.
+    pub period_from: String,
+    pub period_to: String,
+    pub imported_at: String,
+    pub row_count: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Gstr2bReconLine {
+    /// `MATCHED`, `AMOUNT_MISMATCH`, `NOT_CALCULATED`, `MISSING_IN_2B` or `MISSING_IN_BOOKS`.
+    pub status: String,
+    pub be_number: String,
+    pub be_date: String,
+    pub port_code: Option<String>,
+    pub boe_id: Option<String>,
+    pub shipment_id: Option<String>,
+    /// IGST from our saved BOE calculations; `None` when the BOE has no calculation yet.
+    pub books_igst: Option<f64>,
+    pub gstr2b_igst: Option<f64>,
+    pub gstr2b_taxable_value: Option<f64>,
+    /// 2B minus books.
+    pub difference: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Gstr2bReconciliation {
+    pub import: Gstr2bImportSummary,
+    pub matched: usize,
+    pub amount_mismatch: usize,
+    pub not_calculated: usize,
+    pub missing_in_2b: usize,
+    pub missing_in_books: usize,
+    pub lines: Vec<Gstr2bReconLine>,
+    pub warnings: Vec<String>,
+    /// Open `GST_2B_MISMATCH` cases for shipments with differences.
+    pub exception_case_ids: Vec<String>,
+}
+
+/// One of our Bills of Entry with the IGST booked against it.
+struct BookedBoe {
+    id: String,
+    be_number: String,
+    be_date: String,
+    location: String,
+    shipment_id: Option<String>,
+    igst: Option<f64>,
+}
+
+fn be_number_key(raw: &str) -> String {
+    raw.trim().trim_start_matches('0').to_uppercase()
+}
+
+fn port_key(raw: &str) -> String {
+    raw.chars()
+        .filter(|c| !c.is_whitespace())
+        .collect::<String>()
+        .to_uppercase()
+}
+
+/// `location` is free text in older entries; only a six-character customs code is compared.
+fn ports_agree(location: &str, port_code: Option<&str>) -> bool {
+    let ours = port_key(location);
+    let looks_like_code = ours.len() == 6 && ours.chars().all(|c| c.is_ascii_alphanumeric());
+    match port_code {
+        Some(theirs) if looks_like_code => port_key(theirs) == ours,
+        _ => true,
+    }
+}
+
+fn load_booked_boes(conn: &Connection) -> Result<Vec<BookedBoe>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT bd.id, bd.be_number, bd.be_date, bd.location, bc.shipment_id, bc.calculation_result_json
+             FROM boe_details bd
+             LEFT JOIN boe_calculations bc ON bc.boe_id = bd.id
+             -- IGST on a warehousing BE is deferred to the ex-bond BEs and never reaches 2B.
+             WHERE COALESCE(bc.boe_type, 'HOME_CONSUMPTION') != 'INTO_BOND'
+             ORDER BY bd.id",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], |r| {
+            Ok((
+                r.get::<_, String>(0)?,
+                r.get::<_, String>(1)?,
+                r.get::<_, String>(2)?,
+                r.get::<_, String>(3)?,
+                r.get::<_, Option<String>>(4)?,
+                r.get::<_, Option<String>>(5)?,
+            ))
+        })
+        .map_err(|e| e.to_string())?;
+
+    // A BOE may carry several saved calculations (one per shipment); their IGST adds up.
+    let mut out: Vec<BookedBoe> = Vec::new();
+    for row in rows {
+        let (id, be_number, be_date, location, shipment_id, calc_json) =
+            row.map_err(|e| e.to_string())?;
+        let igst = calc_json
+            .and_then(|j| serde_json::from_str::<CalculationResult>(&j).ok())
+            .map(|c| c.igst_total);
+        match out.last_mut() {
+            Some(prev) if prev.id == id => {
+                if let Some(igst) = igst {
+                    prev.igst = Some(prev.igst.unwrap_or(0.0) + igst);
+                }
+                if prev.shipment_id.is_none() {
+                    prev.shipment_id = shipment_id;
+                }
+            }
+            _ => out.push(BookedBoe {
+                be_date: normalize_date(&be_date).unwrap_or(be_date),
+                id,
+                be_number,
+                location,
+                shipment_id,
+                igst,
+            }),
+        }
+    }
+    Ok(out)
+}
+
+/// An amended 2B row supersedes the original for the same BE.
+fn effective_rows(rows: &[Gstr2bImpgRow]) -> Vec<&Gstr2bImpgRow> {
+    let mut by_key: BTreeMap<(String, String, String), &Gstr2bImpgRow> = BTreeMap::new();
+    for r in rows {
+        let key = (
+            be_number_key(&r.be_number),
+            r.be_date.clone(),
+            r.port_code.as_deref().map(port_key).unwrap_or_default(),
+        );
+        match by_key.get(&key) {
+            Some(existing) if existing.is_amended && !r.is_amended => {}
+            _ => {
+                by_key.insert(key, r);
+            }
+        }
+    }
+    by_key.into_values().collect()
+}
+
+/// Matches 2B rows to our BOEs by BE number, date and port code. BOEs dated within
+/// `period_from..=period_to` that 2B does not report are `MISSING_IN_2B`.
+pub fn reconcile(
+    conn: &Connection,
+    rows: &[Gstr2bImpgRow],
+    period_from: &str,
+    period_to: &str,
+) -> Result<Vec<Gstr2bReconLine>, String> {
+    let tolerances = boe_reconciliation::load_tolerances(conn).map_err(|e| e.to_string())?;
+    let booked = load_booked_boes(conn)?;
+    let mut by_key: HashMap<(String, &str), Vec<usize>> = HashMap::new();
+    for (i, b) in booked.iter().enumerate() {
+        by_key
+            .entry((be_number_key(&b.be_number), b.be_date.as_str()))
+            .or_default()
+            .push(i);
+    }
+
+    let mut used = vec![false; booked.len()];
+    let mut lines = Vec::new();
+    for r in effective_rows(rows) {
+        let found = by_key
+            .get(&(be_number_key(&r.be_number), r.be_date.as_str()))
+            .and_then(|idx| {
+                idx.iter()
+                    .copied()
+                    .find(|&i| !used[i] && ports_agree(&booked[i].location, r.port_code.as_deref()))
+            });
+        let Some(i) = found else {
+            lines.push(Gstr2bReconLine {
+                status: MISSING_IN_BOOKS.to_string(),
+                be_number: r.be_number.clone(),
+                be_date: r.be_date.clone(),
+                port_code: r.port_code.clone(),
+                boe_id: None,
+                shipment_id: None,
+                books_igst: None,
+                gstr2b_igst: Some(r.igst),
+                gstr2b_taxable_value: Some(r.taxable_value),
+                difference: duty_engine::round_paise(r.igst),
+            });
+            continue;
+        };
+        used[i] = true;
+        let b = &booked[i];
+        let status = match b.igst {
+            None => NOT_CALCULATED,
+            Some(igst) if !boe_reconciliation::within(&tolerances, "IGST", igst, r.igst) => {
+                AMOUNT_MISMATCH
+            }
+            Some(_) => MATCHED,
+        };
+        lines.push(Gstr2bReconLine {
+            status: status.to_string(),
+            be_number: b.be_number.clone(),
+            be_date: b.be_date.clone(),
+            port_code: r.port_code.clone(),
+            boe_id: Some(b.id.clone()),
+            shipment_id: b.shipment_id.clone(),
+            books_igst: b.igst.map(duty_engine::round_paise),
+            gstr2b_igst: Some(r.igst),
+            gstr2b_taxable_value: Some(r.taxable_value),
+            difference: duty_engine::round_paise(r.igst - b.igst.unwrap_or(0.0)),
+        });
+    }
+
+    for (i, b) in booked.iter().enumerate() {
+        if used[i] || b.be_date.as_str() < period_from || b.be_date.as_str() > period_to {
+            continue;
+        }
+        lines.push(Gstr2bReconLine {
+            status: MISSING_IN_2B.to_string(),
+            be_number: b.be_number.clone(),
+            be_date: b.be_date.clone(),
+            port_code: Some(b.location.clone()),
+            boe_id: Some(b.id.clone()),
+            shipment_id: b.shipment_id.clone(),
+            books_igst: b.igst.map(duty_engine::round_paise),
+            gstr2b_igst: None,
+            gstr2b_taxable_value: None,
+            difference: duty_engine::round_paise(-b.igst.unwrap_or(0.0)),
+        });
+    }
+    lines.sort_by(|a, b| {
+        (a.be_date.as_str(), a.be_number.as_str()).cmp(&(b.be_date.as_str(), b.be_number.as_str()))
+    });
+    Ok(lines)
+}
+
+/// Opens a `GST_2B_MISMATCH` case per shipment with differences and resolves cases for shipments
+/// whose BOEs now all match. Lines without a shipment (missing in our books, or a BOE never
+/// calculated against one) only appear in the report.
+pub fn sync_gst_cases(
+    conn: &Connection,
+    import_id: &str,
+    lines: &[Gstr2bReconLine],
+) -> Result<Vec<String>, String> {
+    let mut open: BTreeMap<&str, BTreeMap<&str, usize>> = BTreeMap::new();
+    let mut clean: BTreeSet<&str> = BTreeSet::new();
+    for l in lines {
+        let Some(shipment_id) = l.shipment_id.as_deref() else {
+            continue;
+        };
+        if l.status == MATCHED {
+            clean.insert(shipment_id);
+        } else {
+            *open
+                .entry(shipment_id)
+                .or_default()
+                .entry(l.status.as_str())
+                .or_default() += 1;
+        }
+    }
+    for shipment_id in clean.iter().filter(|s| !open.contains_key(*s)) {
+        exception_workflow::auto_resolve_case_for_entity(conn, GST_2B_MISMATCH, shipment_id)?;
+    }
+    let mut case_ids = Vec::new();
+    for (shipment_id, by_status) in open {
+        // IGST not in 2B means the credit cannot be taken this period.
+        let priority = if by_status.contains_key(MISSING_IN_2B) {
+            "HIGH"
+        } else {
+            "MEDIUM"
+        };
+        let details = serde_json::json!({
+            "exceptionType": GST_2B_MISMATCH,
+            "importId": import_id,
+            "byStatus": by_status,
+        });
+        if let Some(id) = exception_workflow::open_case_for_entity(
+            conn,
+            GST_2B_MISMATCH,
+            shipment_id,
+            priority,
+            &details.to_string(),
+        )? {
+            case_ids.push(id);
+        }
+    }
+    Ok(case_ids)
+}
+
+fn load_import(conn: &Connection, import_id: &str) -> Result<Gstr2bImportSummary, String> {
+    conn.query_row(
+        "SELECT i.id, i.gstin, i.return_period, i.file_name, i.period_from, i.period_to, i.imported_at,
+                (SELECT COUNT(*) FROM gstr2b_impg_rows r WHERE r.import_id = i.id)
+         FROM gstr2b_imports i WHERE i.id = ?1",
+        params![import_id],
+        map_import,
+    )
+    .optional()
+    .map_err(|e| e.to_string())?
+    .ok_or_else(|| format!("GSTR-2B import {import_id} not found"))
+}
+
+fn map_import(r: &rusqlite::Row) -> rusqlite::Result<Gstr2bImportSummary> {
+    Ok(Gstr2bImportSummary {
+        id: r.get(0)?,
+        gstin: r.get(1)?,
+        return_period: r.get(2)?,
+        file_name: r.get(3)?,
+        period_from: r.get(4)?,
+        period_to: r.get(5)?,
+        imported_at: r.get(6)?,
+        row_count: r.get(7)?,
+    })
+}
+
+fn load_rows(conn: &Connection, import_id: &str) -> Result<Vec<Gstr2bImpgRow>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT be_number, be_date, port_code, reference_date, taxable_value, igst, cess, is_amended
+             FROM gstr2b_impg_rows WHERE import_id = ?1 ORDER BY id",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![import_id], |r| {
+            Ok(Gstr2bImpgRow {
+                be_number: r.get(0)?,
+                be_date: r.get(1)?,
+                port_code: r.get(2)?,
+                reference_date: r.get(3)?,
+                taxable_value: r.get(4)?,
+                igst: r.get(5)?,
+                cess: r.get(6)?,
+                is_amended: r.get::<_, i64>(7)? != 0,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())
+}
+
+/// Stores a parsed 2B file. Without an explicit period, the BE date range of its rows is used.
+pub fn store_import(
+    conn: &mut Connection,
+    doc: &ParsedGstr2b,
+    file_name: Option<&str>,
+    period_from: Option<&str>,
+    period_to: Option<&str>,
+) -> Result<String, String> {
+    let from = period_from
+        .map(str::to_string)
+        .or_else(|| doc.rows.iter().map(|r| r.be_date.clone()).min())
+        .ok_or("The file has no IMPG rows; pass a period to check for BOEs missing in 2B")?;
+    let to = period_to
+        .map(str::to_string)
+        .or_else(|| doc.rows.iter().map(|r| r.be_date.clone()).max())
+        .unwrap_or_else(|| from.clone());
+    if to < from {
+        return Err("Period end is before its start".to_string());
+    }
+
+    let id = generate_id(Some("G2B".to_string()));
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    tx.execute(
+        "INSERT INTO gstr2b_imports (id, gstin, return_period, file_name, period_from, period_to)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![id, doc.gstin, doc.return_period, file_name, from, to],
+    )
+    .map_err(|e| e.to_string())?;
+    for r in &doc.rows {
+        tx.execute(
+            "INSERT INTO gstr2b_impg_rows
+                (import_id, be_number, be_date, port_code, reference_date, taxable_value, igst, cess, is_amended)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                id,
+                r.be_number,
+                r.be_date,
+                r.port_code,
+                r.reference_date,
+                r.taxable_value,
+                r.igst,
+                r.cess,
+                i64::from(r.is_amended)
+            ],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(id)
+}
+
+/// Reconciles a stored import against the current books. Read-only: `exception_case_ids` lists
+/// the cases already open for shipments with differences; [`sync_gst_cases`] opens and resolves them.
+pub fn build_reconciliation(
+    conn: &Connection,
+    import_id: &str,
+    warnings: Vec<String>,
+) -> Result<Gstr2bReconciliation, String> {
+    let import = load_import(conn, import_id)?;
+    let rows = load_rows(conn, import_id)?;
+    let lines = reconcile(conn, &rows, &import.period_from, &import.period_to)?;
+    let differing: BTreeSet<&str> = lines
+        .iter()
+        .filter(|l| l.status != MATCHED)
+        .filter_map(|l| l.shipment_id.as_deref())
+        .collect();
+    let mut exception_case_ids = Vec::new();
+    for shipment_id in differing {
+        if let Some(id) =
+            exception_workflow::open_case_id_for_entity(conn, GST_2B_MISMATCH, shipment_id)?
+        {
+            exception_case_ids.push(id);
+        }
+    }
+    let count = |s: &str| lines.iter().filter(|l| l.status == s).count();
+    Ok(Gstr2bReconciliation {
+        matched: count(MATCHED),
+        amount_mismatch: count(AMOUNT_MISMATCH),
+        not_calculated: count(NOT_CALCULATED),
+        missing_in_2b: count(MISSING_IN_2B),
+        missing_in_books: count(MISSING_IN_BOOKS),
+        import,
+        lines,
+        warnings,
+        exception_case_ids,
+    })
+}
+
+pub fn reconciliation_csv(recon: &Gstr2bReconciliation) -> String {
+    let opt = |v: Option<f64>| v.map(|v| format!("{v:.2}")).unwrap_or_default();
+    let mut out = String::from(
+        "status,be_number,be_date,port_code,boe_id,shipment_id,books_igst,gstr2b_igst,gstr2b_taxable_value,difference\n",
+    );
+    for l in &recon.lines {
+        out.push_str(&format!(
+            "{},{},{},{},{},{},{},{},{},{:.2}\n",
+            l.status,
+            escape_field(&l.be_number),
+            l.be_date,
+            escape_field(l.port_code.as_deref().unwrap_or("")),
+            escape_field(l.boe_id.as_deref().unwrap_or("")),
+            escape_field(l.shipment_id.as_deref().unwrap_or("")),
+            opt(l.books_igst),
+            opt(l.gstr2b_igst),
+            opt(l.gstr2b_taxable_value),
+            l.difference,
+        ));
+    }
+    out
+}
+
+/// Imports a GSTR-2B JSON or Excel download, reconciles its IMPG rows and syncs the cases.
+#[tauri::command]
+pub fn import_gstr2b_impg(
+    file_path: String,
+    period_from: Option<String>,
+    period_to: Option<String>,
+    state: State<DbState>,
+) -> Result<Gstr2bReconciliation, String> {
+    let bytes = std::fs::read(&file_path).map_err(|e| format!("{file_path}: {e}"))?;
+    let doc = gstr2b::parse_bytes(&bytes)?;
+    let normalize = |d: Option<String>| -> Result<Option<String>, String> {
+        d.filter(|s| !s.trim().is_empty())
+            .map(|s| normalize_date(&s).ok_or_else(|| format!("'{s}' is not a valid date")))
+            .transpose()
+    };
+    let (from, to) = (normalize(period_from)?, normalize(period_to)?);
+    let file_name = Path::new(&file_path)
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned());
+
+    let mut conn = state.db.lock().map_err(|e| e.to_string())?;
+    let id = store_import(
+        &mut conn,
+        &doc,
+        file_name.as_deref(),
+        from.as_deref(),
+        to.as_deref(),
+    )?;
+    let mut recon = build_reconciliation(&conn, &id, doc.warnings)?;
+    recon.exception_case_ids = sync_gst_cases(&conn, &id, &recon.lines)?;
+    let _ = dashboard_cache::invalidate_dashboard_metrics_cache(&conn);
+    Ok(recon)
+}
+
+#[tauri::command]
+pub fn list_gstr2b_imports(state: State<DbState>) -> Result<Vec<Gstr2bImportSummary>, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT i.id, i.gstin, i.return_period, i.file_name, i.period_from, i.period_to, i.imported_at,
+                    (SELECT COUNT(*) FROM gstr2b_impg_rows r WHERE r.import_id = i.id)
+             FROM gstr2b_imports i ORDER BY i.imported_at DESC, i.id DESC",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt.query_map([], map_import).map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())
+}
+
+/// Re-runs a stored import against the current books (e.g. after BOEs were corrected) without
+/// touching its exception cases.
+#[tauri::command]
+pub fn get_gstr2b_reconciliation(
+    import_id: String,
+    state: State<DbState>,
+) -> Result<Gstr2bReconciliation, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    build_reconciliation(&conn, &import_id, Vec::new())
+}
+
+#[tauri::command]
+pub fn export_gstr2b_reconciliation_csv(
+    import_id: String,
+    state: State<DbState>,
+) -> Result<String, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    let recon = build_reconciliation(&conn, &import_id, Vec::new())?;
+    Ok(reconciliation_csv(&recon))
+}
+
+#[tauri::command]
+pub fn delete_gstr2b_import(import_id: String, state: State<DbState>) -> Result<(), String> {
+    let mut conn = state.db.lock().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    tx.execute(
+        "DELETE FROM gstr2b_impg_rows WHERE import_id = ?1",
+        params![import_id],
+    )
+    .map_err(|e| e.to_string())?;
+    tx.execute(
+        "DELETE FROM gstr2b_imports WHERE id = ?1",
+        params![import_id],
+    )
+    .map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::test_support;
+
+    fn row(number: &str, date: &str, igst: f64) -> Gstr2bImpgRow {
+        Gstr2bImpgRow {
+            be_number: number.to_string(),
+            be_date: date.to_string(),
+            port_code: Some("INNSA1".to_string()),
+            igst,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn classifies_rows_and_opens_cases() {
+        let mut c = test_support::migrated_db();
+        for (id, date) in [
+            ("SHP-1", "2024-07-01"),
+            ("SHP-2", "2024-07-02"),
+            ("SHP-3", "2024-07-03"),
+            ("SHP-5", "2024-07-04"),
+        ] {
+            test_support::add_shipment(&c, id, date, 1.0, "USD", "delivered");
+        }
+        c.execute_batch(
+            "INSERT INTO boe_details (id, be_number, be_date, location, total_assessment_value, duty_amount) VALUES
+                 ('BOE-1', '1000001', '2024-07-05', 'INNSA1', 1, 1),
+                 ('BOE-2', '1000002', '2024-07-06', 'Nhava Sheva', 1, 1),
+                 ('BOE-3', '1000003', '2024-07-07', 'INMAA1', 1, 1),
+                 ('BOE-4', '1000004', '2024-07-06', 'INNSA1', 1, 1),
+                 ('BOE-5', '1000005', '2024-07-08', 'INNSA1', 1, 1);
+             INSERT INTO boe_calculations (id, shipment_id, boe_id, supplier_name, invoice_number, form_values_json, item_inputs_json, calculation_result_json) VALUES
+                 ('BC-1', 'SHP-1', 'BOE-1', 'Acme', 'INV-1', '{}', '[]', '{\"calculatedItems\":[],\"bcdTotal\":0,\"swsTotal\":0,\"igstTotal\":18000,\"interest\":0,\"customsDutyTotal\":18000}'),
+                 ('BC-2', 'SHP-2', 'BOE-2', 'Acme', 'INV-2', '{}', '[]', '{\"calculatedItems\":[],\"bcdTotal\":0,\"swsTotal\":0,\"igstTotal\":9000,\"interest\":0,\"customsDutyTotal\":9000}'),
+                 ('BC-3', 'SHP-3', 'BOE-3', 'Acme', 'INV-3', '{}', '[]', '{\"calculatedItems\":[],\"bcdTotal\":0,\"swsTotal\":0,\"igstTotal\":5000,\"interest\":0,\"customsDutyTotal\":5000}'),
+                 -- Linked to its shipment but never calculated.
+                 ('BC-5', 'SHP-5', 'BOE-5', 'Acme', 'INV-5', '{}', '[]', '{}');
+             -- Warehousing BE: its IGST is deferred and 2B does not report it.
+             INSERT INTO boe_calculations (id, shipment_id, boe_id, supplier_name, invoice_number, form_values_json, item_inputs_json, calculation_result_json, boe_type) VALUES
+                 ('BC-4', 'SHP-1', 'BOE-4', 'Acme', 'INV-1', '{}', '[]', '{\"calculatedItems\":[],\"bcdTotal\":0,\"swsTotal\":0,\"igstTotal\":7000,\"interest\":0,\"customsDutyTotal\":7000}', 'INTO_BOND');",
+        )
+        .unwrap();
+
+        let mut amended = row("1000002", "2024-07-06", 9000.5);
+        amended.is_amended = true;
+        let doc = ParsedGstr2b {
+            rows: vec![
+                row("1000001", "2024-07-05", 18000.4),
+                row("1000002", "2024-07-06", 8500.0),
+                amended,
+                // Same BE number and date as BOE-3 but another port.
+                row("1000003", "2024-07-07", 5000.0),
+                row("1000005", "2024-07-08", 3000.0),
+            ],
+            ..Default::default()
+        };
+        let id = store_import(&mut c, &doc, None, None, None).unwrap();
+        let recon = build_reconciliation(&c, &id, Vec::new()).unwrap();
+        let status = |n: &str| {
+            recon
+                .lines
+                .iter()
+                .filter(|l| l.be_number == n)
+                .map(|l| l.status.as_str())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(status("1000001"), vec![MATCHED]);
+        assert_eq!(status("1000002"), vec![MATCHED]);
+        assert_eq!(status("1000003"), vec![MISSING_IN_BOOKS, MISSING_IN_2B]);
+        assert!(status("1000004").is_empty());
+        assert_eq!(status("1000005"), vec![NOT_CALCULATED]);
+        assert_eq!(recon.not_calculated, 1);
+
+        // Building the report leaves the cases alone; syncing opens one per differing shipment.
+        assert!(recon.exception_case_ids.is_empty());
+        let case_ids = sync_gst_cases(&c, &id, &recon.lines).unwrap();
+        let case_shipments: Vec<String> = case_ids
+            .iter()
+            .map(|cid| {
+                c.query_row(
+                    "SELECT entity_id FROM exception_cases WHERE id = ?1",
+                    params![cid],
+                    |r| r.get(0),
+                )
+                .unwrap()
+            })
+            .collect();
+        assert_eq!(case_shipments, vec!["SHP-3", "SHP-5"]);
+        assert_eq!(
+            build_reconciliation(&c, &id, Vec::new())
+                .unwrap()
+                .exception_case_ids,
+            case_ids
+        );
+
+        c.execute(
+            "UPDATE boe_details SET location = 'INNSA1' WHERE id = 'BOE-3'",
+            [],
+        )
+        .unwrap();
+        c.execute(
+            "UPDATE gstr2b_impg_rows SET igst = 4000 WHERE be_number = '1000003'",
+            [],
+        )
+        .unwrap();
+        let recon = build_reconciliation(&c, &id, Vec::new()).unwrap();
+        assert_eq!(status_of(&recon, "1000003"), AMOUNT_MISMATCH);
+        assert_eq!(
+            recon
+                .lines
+                .iter()
+                .find(|l| l.be_number == "1000003")
+                .unwrap()
+                .difference,
+            -1000.0
+        );
+        let csv = reconciliation_csv(&recon);
+        assert!(
+            csv.contains("AMOUNT_MISMATCH,1000003,2024-07-07,INNSA1,BOE-3,SHP-3,5000.00,4000.00"),
+            "{csv}"
+        );
+    }
+
+    fn status_of<'a>(recon: &'a Gstr2bReconciliation, number: &str) -> &'a str {
+        recon
+            .lines
+            .iter()
+            .find(|l| l.be_number == number)
+            .map(|l| l.status.as_str())
+            .unwrap()
+    }
+}
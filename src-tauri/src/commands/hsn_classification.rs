@@ -0,0 +1,670 @@
+//! HSN classification assistant: ranked AI candidates cross-checked against the tariff master and past items.
+
+use crate::ai_provider::{resolve_default_provider_label, AiProvider};
+use crate::commands::dashboard_cache;
+use crate::commands::tariff::{normalize_hsn, tariff_rate_as_of, today};
+use crate::db::DbState;
+use crate::deepseek_client::{
+    call_deepseek_chat, load_deepseek_config, strip_code_fences, DeepSeekConfig,
+};
+use crate::ollama_client::{call_ollama_chat, load_ollama_config, OllamaConfig};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use tauri::State;
+
+pub const SOURCE_AI: &str = "AI";
+pub const SOURCE_TARIFF: &str = "TARIFF";
+pub const SOURCE_HISTORY: &str = "HISTORY";
+pub const SOURCE_MANUAL: &str = "MANUAL";
+
+/// Past items below this description similarity are not offered as precedent.
+const MIN_SIMILARITY: f64 = 0.2;
+const MAX_SIMILAR_ITEMS: usize = 5;
+const DEFAULT_MAX_CANDIDATES: usize = 5;
+
+const SYSTEM_HSN_CLASSIFICATION: &str = "You are an Indian customs classification expert.\n\n\
+Classify the imported goods under the 8-digit Indian Customs Tariff (ITC-HS) applying the General Rules for the \
+Interpretation of the Harmonized System.\n\n\
+Return ONLY valid JSON of the form:\n\
+{\"candidates\":[{\"hsnCode\":\"84821011\",\"description\":\"tariff heading text\",\"confidence\":0.8,\"reasoning\":\"why this heading, which GIR applies\"}]}\n\n\
+List up to five candidates, most likely first. hsnCode must be exactly 8 digits. confidence is between 0 and 1. \
+Do not include explanations outside the JSON.";
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct HsnSuggestionRequest {
+    /// Item being classified, left out of its own precedents.
+    #[serde(default)]
+    pub item_id: Option<String>,
+    pub description: String,
+    #[serde(default)]
+    pub technical_write_up: Option<String>,
+    /// `mock`, `deepseek` or `local`; the configured default when omitted.
+    #[serde(default)]
+    pub provider: Option<String>,
+    #[serde(default)]
+    pub max_candidates: Option<usize>,
+}
+
+/// One of our items already classified, with a similar description.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SimilarItem {
+    pub item_id: String,
+    pub part_number: String,
+    pub description: String,
+    pub hsn_code: String,
+    pub similarity: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct HsnCandidate {
+    pub hsn_code: String,
+    pub description: Option<String>,
+    pub reasoning: Option<String>,
+    /// Model's own confidence; `None` for candidates only found in our history.
+    pub ai_confidence: Option<f64>,
+    pub ai_rank: Option<u32>,
+    pub in_tariff_master: bool,
+    pub tariff_description: Option<String>,
+    pub bcd_rate: Option<f64>,
+    pub igst_rate: Option<f64>,
+    /// Our past items classified under this code.
+    pub similar_items: Vec<SimilarItem>,
+    /// AI confidence, tariff presence and precedent combined, 0 to 1; candidates are sorted on it.
+    pub score: f64,
+    pub sources: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct HsnSuggestionResponse {
+    pub provider: String,
+    pub candidates: Vec<HsnCandidate>,
+    /// Set when the model could not be reached or answered unusably; history candidates still come back.
+    pub ai_error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ApplyHsnClassificationPayload {
+    pub item_id: String,
+    pub hsn_code: String,
+    pub rationale: String,
+    #[serde(default)]
+    pub source: Option<String>,
+    #[serde(default)]
+    pub provider: Option<String>,
+    /// Candidates the user chose from, kept for audit.
+    #[serde(default)]
+    pub candidates: Option<Vec<HsnCandidate>>,
+    #[serde(default)]
+    pub user_id: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct HsnClassification {
+    pub id: i64,
+    pub item_id: String,
+    pub hsn_code: String,
+    pub previous_hsn_code: Option<String>,
+    pub rationale: String,
+    pub source: String,
+    pub provider: Option<String>,
+    pub candidates: Option<Vec<HsnCandidate>>,
+    pub classified_by: Option<String>,
+    pub classified_at: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LlmHsnJson {
+    #[serde(default)]
+    candidates: Vec<LlmHsnCandidate>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LlmHsnCandidate {
+    hsn_code: String,
+    #[serde(default)]
+    description: Option<String>,
+    #[serde(default)]
+    confidence: Option<f64>,
+    #[serde(default)]
+    reasoning: Option<String>,
+}
+
+fn tokens(text: &str) -> HashSet<String> {
+    text.split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|w| w.len() >= 3 && !w.chars().all(|c| c.is_ascii_digit()))
+        .map(|w| w.to_ascii_lowercase())
+        .collect()
+}
+
+/// Jaccard overlap of the words in two descriptions.
+fn similarity(a: &HashSet<String>, b: &HashSet<String>) -> f64 {
+    let union = a.union(b).count();
+    if union == 0 {
+        return 0.0;
+    }
+    a.intersection(b).count() as f64 / union as f64
+}
+
+/// Our classified items closest to `description` + `technical_write_up`, best first.
+pub fn similar_items(
+    conn: &Connection,
+    exclude_item_id: Option<&str>,
+    description: &str,
+    technical_write_up: Option<&str>,
+) -> Result<Vec<SimilarItem>, String> {
+    let wanted = tokens(&format!(
+        "{description} {}",
+        technical_write_up.unwrap_or("")
+    ));
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, part_number, item_description, COALESCE(technical_write_up, ''), hsn_code
+             FROM items WHERE TRIM(hsn_code) <> '' AND id IS NOT ?1",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![exclude_item_id], |r| {
+            Ok((
+                r.get::<_, String>(0)?,
+                r.get::<_, String>(1)?,
+                r.get::<_, String>(2)?,
+                r.get::<_, String>(3)?,
+                r.get::<_, String>(4)?,
+            ))
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    let mut similar: Vec<SimilarItem> = rows
+        .into_iter()
+        .filter_map(|(item_id, part_number, desc, write_up, hsn)| {
+            let score = similarity(&wanted, &tokens(&format!("{desc} {write_up}")));
+            (score >= MIN_SIMILARITY).then(|| SimilarItem {
+                item_id,
+                part_number,
+                description: desc,
+                hsn_code: normalize_hsn(&hsn).unwrap_or(hsn),
+                similarity: (score * 1000.0).round() / 1000.0,
+            })
+        })
+        .collect();
+    similar.sort_by(|a, b| b.similarity.total_cmp(&a.similarity));
+    similar.truncate(MAX_SIMILAR_ITEMS);
+    Ok(similar)
+}
+
+fn build_user_prompt(request: &HsnSuggestionRequest, precedents: &[SimilarItem]) -> String {
+    let mut user = format!("Goods description: {}\n", request.description.trim());
+    if let Some(w) = request
+        .technical_write_up
+        .as_deref()
+        .map(str::trim)
+        .filter(|w| !w.is_empty())
+    {
+        user.push_str(&format!("Technical write-up: {w}\n"));
+    }
+    if !precedents.is_empty() {
+        user.push_str("\nSimilar goods this importer has already classified (for consistency; not binding):\n");
+        for p in precedents {
+            user.push_str(&format!(
+                "- {} \"{}\": {}\n",
+                p.part_number, p.description, p.hsn_code
+            ));
+        }
+    }
+    user
+}
+
+/// Parses the model's JSON into candidates, dropping codes that are not 8 digits.
+fn parse_candidates(assistant: &str) -> Result<Vec<LlmHsnCandidate>, String> {
+    let parsed: LlmHsnJson = serde_json::from_str(&strip_code_fences(assistant))
+        .map_err(|e| format!("Model did not return valid JSON for HSN candidates: {e}"))?;
+    Ok(parsed
+        .candidates
+        .into_iter()
+        .filter_map(|mut c| {
+            c.hsn_code = normalize_hsn(&c.hsn_code)?;
+            Some(c)
+        })
+        .collect())
+}
+
+/// Model connection resolved under the database lock, so the call itself can run without it.
+enum ModelClient {
+    /// The mock provider: history candidates only.
+    None,
+    DeepSeek(DeepSeekConfig),
+    Ollama(OllamaConfig),
+}
+
+impl ModelClient {
+    fn load(conn: &Connection, provider: AiProvider) -> Result<Self, String> {
+        Ok(match provider {
+            AiProvider::Mock => ModelClient::None,
+            AiProvider::DeepSeek => ModelClient::DeepSeek(load_deepseek_config(conn)?),
+            AiProvider::LocalOllama => ModelClient::Ollama(load_ollama_config(conn)?),
+        })
+    }
+
+    fn ask(
+        &self,
+        request: &HsnSuggestionRequest,
+        precedents: &[SimilarItem],
+    ) -> Result<Vec<LlmHsnCandidate>, String> {
+        let user = build_user_prompt(request, precedents);
+        let assistant = match self {
+            ModelClient::None => return Ok(Vec::new()),
+            ModelClient::DeepSeek(config) => {
+                call_deepseek_chat(config, SYSTEM_HSN_CLASSIFICATION, &user)?
+            }
+            ModelClient::Ollama(config) => {
+                call_ollama_chat(config, SYSTEM_HSN_CLASSIFICATION, &user)?
+            }
+        };
+        parse_candidates(&assistant)
+    }
+}
+
+/// Merges model candidates with precedent codes, checks each against the tariff master in force
+/// today and ranks them.
+fn cross_check(
+    conn: &Connection,
+    ai: Vec<LlmHsnCandidate>,
+    precedents: &[SimilarItem],
+    max_candidates: usize,
+) -> Result<Vec<HsnCandidate>, String> {
+    let mut candidates: Vec<HsnCandidate> = Vec::new();
+    for (rank, c) in ai.into_iter().enumerate() {
+        if candidates.iter().any(|x| x.hsn_code == c.hsn_code) {
+            continue;
+        }
+        candidates.push(HsnCandidate {
+            hsn_code: c.hsn_code,
+            description: c.description,
+            reasoning: c.reasoning,
+            ai_confidence: Some(c.confidence.unwrap_or(0.5).clamp(0.0, 1.0)),
+            ai_rank: Some(rank as u32 + 1),
+            in_tariff_master: false,
+            tariff_description: None,
+            bcd_rate: None,
+            igst_rate: None,
+            similar_items: Vec::new(),
+            score: 0.0,
+            sources: vec![SOURCE_AI.to_string()],
+        });
+    }
+    for p in precedents {
+        if normalize_hsn(&p.hsn_code).is_none()
+            || candidates.iter().any(|x| x.hsn_code == p.hsn_code)
+        {
+            continue;
+        }
+        candidates.push(HsnCandidate {
+            hsn_code: p.hsn_code.clone(),
+            description: None,
+            reasoning: Some(format!("Used for {} \"{}\"", p.part_number, p.description)),
+            ai_confidence: None,
+            ai_rank: None,
+            in_tariff_master: false,
+            tariff_description: None,
+            bcd_rate: None,
+            igst_rate: None,
+            similar_items: Vec::new(),
+            score: 0.0,
+            sources: Vec::new(),
+        });
+    }
+
+    let as_of = today();
+    for c in &mut candidates {
+        c.similar_items = precedents
+            .iter()
+            .filter(|p| p.hsn_code == c.hsn_code)
+            .cloned()
+            .collect();
+        if !c.similar_items.is_empty() {
+            c.sources.push(SOURCE_HISTORY.to_string());
+        }
+        if let Some(rate) =
+            tariff_rate_as_of(conn, &c.hsn_code, &as_of).map_err(|e| e.to_string())?
+        {
+            c.in_tariff_master = true;
+            c.tariff_description = rate.description;
+            c.bcd_rate = Some(rate.bcd_rate);
+            c.igst_rate = Some(rate.igst_rate);
+            c.sources.push(SOURCE_TARIFF.to_string());
+        }
+        let precedent = c
+            .similar_items
+            .iter()
+            .map(|s| s.similarity)
+            .fold(0.0, f64::max);
+        let score = 0.6 * c.ai_confidence.unwrap_or(0.0)
+            + if c.in_tariff_master { 0.2 } else { 0.0 }
+            + 0.2 * precedent.min(1.0);
+        c.score = (score * 1000.0).round() / 1000.0;
+    }
+    candidates.sort_by(|a, b| {
+        b.score.total_cmp(&a.score).then(
+            a.ai_rank
+                .unwrap_or(u32::MAX)
+                .cmp(&b.ai_rank.unwrap_or(u32::MAX)),
+        )
+    });
+    candidates.truncate(max_candidates);
+    Ok(candidates)
+}
+
+fn resolve_provider(conn: &Connection, requested: Option<&str>) -> Result<AiProvider, String> {
+    match requested.map(str::trim).filter(|p| !p.is_empty()) {
+        Some(p) => AiProvider::from_config_str(p),
+        None => AiProvider::from_config_str(&resolve_default_provider_label(conn)),
+    }
+}
+
+fn provider_label(provider: AiProvider) -> &'static str {
+    match provider {
+        AiProvider::Mock => "mock",
+        AiProvider::DeepSeek => crate::deepseek_client::deepseek_provider_label(),
+        AiProvider::LocalOllama => crate::ollama_client::ollama_provider_label(),
+    }
+}
+
+/// Everything a suggestion needs from the database before the model is asked.
+struct SuggestionContext {
+    provider: AiProvider,
+    precedents: Vec<SimilarItem>,
+    client: Result<ModelClient, String>,
+}
+
+fn prepare(conn: &Connection, request: &HsnSuggestionRequest) -> Result<SuggestionContext, String> {
+    if request.description.trim().is_empty() {
+        return Err("A description is required to suggest an HSN code".to_string());
+    }
+    let provider = resolve_provider(conn, request.provider.as_deref())?;
+    Ok(SuggestionContext {
+        provider,
+        precedents: similar_items(
+            conn,
+            request.item_id.as_deref(),
+            &request.description,
+            request.technical_write_up.as_deref(),
+        )?,
+        client: ModelClient::load(conn, provider),
+    })
+}
+
+/// A model that is unreachable or misconfigured is reported in `ai_error`; precedents still count.
+fn ask(
+    context: &SuggestionContext,
+    request: &HsnSuggestionRequest,
+) -> (Vec<LlmHsnCandidate>, Option<String>) {
+    match context
+        .client
+        .as_ref()
+        .map_err(String::clone)
+        .and_then(|c| c.ask(request, &context.precedents))
+    {
+        Ok(ai) => (ai, None),
+        Err(e) => (Vec::new(), Some(e)),
+    }
+}
+
+fn finish(
+    conn: &Connection,
+    context: SuggestionContext,
+    request: &HsnSuggestionRequest,
+    ai: Vec<LlmHsnCandidate>,
+    ai_error: Option<String>,
+) -> Result<HsnSuggestionResponse, String> {
+    Ok(HsnSuggestionResponse {
+        provider: provider_label(context.provider).to_string(),
+        candidates: cross_check(
+            conn,
+            ai,
+            &context.precedents,
+            request.max_candidates.unwrap_or(DEFAULT_MAX_CANDIDATES),
+        )?,
+        ai_error,
+    })
+}
+
+/// Full suggestion round on one connection.
+pub fn suggest(
+    conn: &Connection,
+    request: &HsnSuggestionRequest,
+) -> Result<HsnSuggestionResponse, String> {
+    let context = prepare(conn, request)?;
+    let (ai, ai_error) = ask(&context, request);
+    finish(conn, context, request, ai, ai_error)
+}
+
+/// Sets the item's HSN code and records the choice with its rationale.
+pub fn apply(
+    conn: &Connection,
+    payload: &ApplyHsnClassificationPayload,
+) -> Result<HsnClassification, String> {
+    let hsn_code = normalize_hsn(&payload.hsn_code)
+        .ok_or_else(|| format!("'{}' is not an 8-digit HSN code", payload.hsn_code))?;
+    let rationale = payload.rationale.trim();
+    if rationale.is_empty() {
+        return Err("A rationale is required for the classification".to_string());
+    }
+    let source = payload
+        .source
+        .as_deref()
+        .map(|s| s.trim().to_uppercase())
+        .unwrap_or_else(|| SOURCE_MANUAL.to_string());
+    if ![SOURCE_AI, SOURCE_TARIFF, SOURCE_HISTORY, SOURCE_MANUAL].contains(&source.as_str()) {
+        return Err(format!("Unknown classification source '{source}'"));
+    }
+    let previous: String = conn
+        .query_row(
+            "SELECT hsn_code FROM items WHERE id = ?1",
+            params![payload.item_id],
+            |r| r.get(0),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Item {} not found", payload.item_id))?;
+    let candidates_json = payload
+        .candidates
+        .as_ref()
+        .map(serde_json::to_string)
+        .transpose()
+        .map_err(|e| e.to_string())?;
+
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    tx.execute(
+        "UPDATE items SET hsn_code = ?2 WHERE id = ?1",
+        params![payload.item_id, hsn_code],
+    )
+    .map_err(|e| e.to_string())?;
+    tx.execute(
+        "INSERT INTO hsn_classifications (item_id, hsn_code, previous_hsn_code, rationale, source, provider, candidates_json, classified_by)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        params![
+            payload.item_id,
+            hsn_code,
+            Some(previous).filter(|p| !p.trim().is_empty()),
+            rationale,
+            source,
+            payload.provider,
+            candidates_json,
+            payload.user_id
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    let id = tx.last_insert_rowid();
+    tx.commit().map_err(|e| e.to_string())?;
+
+    conn.query_row(
+        &format!("SELECT {CLASSIFICATION_COLUMNS} FROM hsn_classifications WHERE id = ?1"),
+        params![id],
+        map_classification,
+    )
+    .map_err(|e| e.to_string())
+}
+
+const CLASSIFICATION_COLUMNS: &str = "id, item_id, hsn_code, previous_hsn_code, rationale, source, provider, candidates_json, classified_by, classified_at";
+
+fn map_classification(r: &rusqlite::Row) -> rusqlite::Result<HsnClassification> {
+    let candidates: Option<String> = r.get(7)?;
+    Ok(HsnClassification {
+        id: r.get(0)?,
+        item_id: r.get(1)?,
+        hsn_code: r.get(2)?,
+        previous_hsn_code: r.get(3)?,
+        rationale: r.get(4)?,
+        source: r.get(5)?,
+        provider: r.get(6)?,
+        candidates: candidates.and_then(|c| serde_json::from_str(&c).ok()),
+        classified_by: r.get(8)?,
+        classified_at: r.get(9)?,
+    })
+}
+
+#[tauri::command]
+pub fn suggest_hsn_codes(
+    request: HsnSuggestionRequest,
+    state: State<DbState>,
+) -> Result<HsnSuggestionResponse, String> {
+    // The model can take a while; the database lock is only held around the lookups.
+    let context = {
+        let conn = state.db.lock().map_err(|e| e.to_string())?;
+        prepare(&conn, &request)?
+    };
+    let (ai, ai_error) = ask(&context, &request);
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    finish(&conn, context, &request, ai, ai_error)
+}
+
+#[tauri::command]
+pub fn apply_hsn_classification(
+    payload: ApplyHsnClassificationPayload,
+    state: State<DbState>,
+) -> Result<HsnClassification, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    let classification = apply(&conn, &payload)?;
+    let _ = dashboard_cache::invalidate_dashboard_metrics_cache(&conn);
+    Ok(classification)
+}
+
+#[tauri::command]
+pub fn get_hsn_classification_history(
+    item_id: String,
+    state: State<DbState>,
+) -> Result<Vec<HsnClassification>, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {CLASSIFICATION_COLUMNS} FROM hsn_classifications WHERE item_id = ?1 ORDER BY id DESC"
+        ))
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![item_id], map_classification)
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::test_support;
+
+    #[test]
+    fn candidates_are_cross_checked_and_the_choice_is_audited() {
+        let conn = test_support::migrated_db();
+        conn.execute_batch(
+            "INSERT INTO items (id, part_number, item_description, unit, currency, unit_price, hsn_code, is_active, technical_write_up)
+                VALUES ('IT-1', 'BRG-6204', 'Deep groove ball bearing 6204', 'PCS', 'USD', 2, '8482.10.11', 1, 'single row radial ball bearing'),
+                       ('IT-2', 'GSK-1', 'Rubber gasket', 'PCS', 'USD', 1, '40169320', 1, NULL),
+                       ('IT-NEW', 'BRG-6305', 'Ball bearing 6305', 'PCS', 'USD', 3, '0000', 1, NULL);
+             INSERT INTO hsn_tariff_rates (hsn_code, description, effective_from, bcd_rate, sws_rate, igst_rate)
+                VALUES ('84821011', 'Ball bearings - radial', '2020-01-01', 10, 10, 18);",
+        )
+        .unwrap();
+
+        // Model output: fenced JSON, one malformed code dropped, one code outside the tariff master.
+        let ai = parse_candidates(
+            "```json\n{\"candidates\":[\
+             {\"hsnCode\":\"8482.10.20\",\"confidence\":0.9,\"reasoning\":\"ball bearing\"},\
+             {\"hsnCode\":\"8482\",\"confidence\":0.8},\
+             {\"hsnCode\":\"84821011\",\"confidence\":0.7,\"reasoning\":\"radial\"}]}\n```",
+        )
+        .unwrap();
+        assert_eq!(ai.len(), 2);
+
+        let precedents = similar_items(
+            &conn,
+            Some("IT-NEW"),
+            "Ball bearing 6305",
+            Some("single row radial"),
+        )
+        .unwrap();
+        assert_eq!(precedents.len(), 1);
+        assert_eq!(precedents[0].hsn_code, "84821011");
+
+        let ranked = cross_check(&conn, ai, &precedents, 5).unwrap();
+        // Tariff presence and our own precedent outrank the model's first pick.
+        assert_eq!(ranked[0].hsn_code, "84821011");
+        assert!(ranked[0].in_tariff_master);
+        assert_eq!(ranked[0].sources, vec!["AI", "HISTORY", "TARIFF"]);
+        assert_eq!(ranked[0].bcd_rate, Some(10.0));
+        assert_eq!(ranked[1].hsn_code, "84821020");
+        assert!(!ranked[1].in_tariff_master);
+
+        // The mock provider still offers the precedent, without a model call.
+        let mock = suggest(
+            &conn,
+            &HsnSuggestionRequest {
+                item_id: Some("IT-NEW".into()),
+                description: "Ball bearing 6305".into(),
+                technical_write_up: Some("single row radial".into()),
+                provider: Some("mock".into()),
+                max_candidates: None,
+            },
+        )
+        .unwrap();
+        assert_eq!(mock.candidates.len(), 1);
+        assert!(mock.ai_error.is_none());
+
+        let payload = ApplyHsnClassificationPayload {
+            item_id: "IT-NEW".into(),
+            hsn_code: "8482.10.11".into(),
+            rationale: "Same construction as BRG-6204".into(),
+            source: Some("history".into()),
+            provider: Some(mock.provider.clone()),
+            candidates: Some(mock.candidates),
+            user_id: Some("u1".into()),
+        };
+        let saved = apply(&conn, &payload).unwrap();
+        assert_eq!(saved.previous_hsn_code.as_deref(), Some("0000"));
+        assert_eq!(saved.source, SOURCE_HISTORY);
+        assert_eq!(saved.candidates.map(|c| c.len()), Some(1));
+        let hsn: String = conn
+            .query_row("SELECT hsn_code FROM items WHERE id = 'IT-NEW'", [], |r| {
+                r.get(0)
+            })
+            .unwrap();
+        assert_eq!(hsn, "84821011");
+        assert!(apply(
+            &conn,
+            &ApplyHsnClassificationPayload {
+                rationale: " ".into(),
+                ..payload
+            }
+        )
+        .is_err());
+    }
+}
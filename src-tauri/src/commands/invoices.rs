@@ -1,4 +1,7 @@
 use crate::commands::dashboard_cache;
+use crate::commands::purchase_orders;
+use crate::commands::shipment_status::{self, TransitionContext};
+use crate::commands::tariff;
 use crate::commands::utils::generate_id;
 use crate::db::{DbState, Invoice, InvoiceLineItem, NewInvoiceLineItemPayload, NewInvoicePayload};
 use rusqlite::{params, Connection, Transaction};
@@ -16,29 +19,42 @@ fn parse_pct_from_item_str(s: &Option<String>) -> f64 {
 fn item_master_tax_strings(
     conn: &Connection,
     item_id: &str,
-) -> (Option<String>, Option<String>, Option<String>) {
+) -> (String, Option<String>, Option<String>, Option<String>) {
     conn.query_row(
-        "SELECT bcd, sws, igst FROM items WHERE id = ?1",
+        "SELECT hsn_code, bcd, sws, igst FROM items WHERE id = ?1",
         params![item_id],
-        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
     )
-    .unwrap_or((None, None, None))
+    .unwrap_or_default()
 }
 
-/// Combine stored line rates (if any) with Item Master fallback.
+/// Default BCD/SWS/IGST for a line: the HSN tariff in force on the shipment's BE date (today if not
+/// filed yet); the free-text Item Master rates are only used for HSNs missing from the tariff master.
+fn default_line_rates(conn: &Connection, item_id: &str, shipment_id: &str) -> (f64, f64, f64) {
+    let (hsn, bcd_s, sws_s, igst_s) = item_master_tax_strings(conn, item_id);
+    let as_of = tariff::be_date_for_shipment(conn, shipment_id).unwrap_or_else(tariff::today);
+    match tariff::tariff_rate_as_of(conn, &hsn, &as_of) {
+        Ok(Some(t)) => (t.bcd_rate, t.sws_rate, t.igst_rate),
+        _ => (
+            parse_pct_from_item_str(&bcd_s),
+            parse_pct_from_item_str(&sws_s),
+            parse_pct_from_item_str(&igst_s),
+        ),
+    }
+}
+
+/// Combine stored line rates (if any) with the tariff / Item Master defaults.
 fn merge_tax_rates(
     duty: Option<f64>,
     sws: Option<f64>,
     igst: Option<f64>,
-    bcd_s: &Option<String>,
-    sws_s: &Option<String>,
-    igst_s: &Option<String>,
+    defaults: impl FnOnce() -> (f64, f64, f64),
 ) -> (f64, f64, f64) {
-    (
-        duty.unwrap_or_else(|| parse_pct_from_item_str(bcd_s)),
-        sws.unwrap_or_else(|| parse_pct_from_item_str(sws_s)),
-        igst.unwrap_or_else(|| parse_pct_from_item_str(igst_s)),
-    )
+    if let (Some(d), Some(s), Some(i)) = (duty, sws, igst) {
+        return (d, s, i);
+    }
+    let (dd, ds, di) = defaults();
+    (duty.unwrap_or(dd), sws.unwrap_or(ds), igst.unwrap_or(di))
 }
 
 fn fetch_invoices(db: &Connection) -> Result<Vec<Invoice>, String> {
@@ -72,7 +88,7 @@ fn fetch_invoices(db: &Connection) -> Result<Vec<Invoice>, String> {
 
         let mut line_item_stmt = db
             .prepare(
-                "SELECT id, item_id, quantity, unit_price, duty_percent, sws_percent, igst_percent \
+                "SELECT id, item_id, quantity, unit_price, duty_percent, sws_percent, igst_percent, po_line_id \
                  FROM invoice_line_items WHERE invoice_id = ?1",
             )
             .map_err(|e| e.to_string())?;
@@ -87,6 +103,7 @@ fn fetch_invoices(db: &Connection) -> Result<Vec<Invoice>, String> {
                     row.get::<_, Option<f64>>(4)?,
                     row.get::<_, Option<f64>>(5)?,
                     row.get::<_, Option<f64>>(6)?,
+                    row.get::<_, Option<String>>(7)?,
                 ))
             })
             .map_err(|e| e.to_string())?;
@@ -97,9 +114,12 @@ fn fetch_invoices(db: &Connection) -> Result<Vec<Invoice>, String> {
 
         let mut line_items: Vec<InvoiceLineItem> = Vec::with_capacity(raw_rows.len());
 
-        for (line_id, item_id, quantity, unit_price, duty_db, sws_db, igst_db) in raw_rows {
-            let (bcd_s, sws_s, igst_s) = item_master_tax_strings(db, &item_id);
-            let (d, s, ig) = merge_tax_rates(duty_db, sws_db, igst_db, &bcd_s, &sws_s, &igst_s);
+        for (line_id, item_id, quantity, unit_price, duty_db, sws_db, igst_db, po_line_id) in
+            raw_rows
+        {
+            let (d, s, ig) = merge_tax_rates(duty_db, sws_db, igst_db, || {
+                default_line_rates(db, &item_id, &shipment_id)
+            });
 
             if duty_db.is_none() || sws_db.is_none() || igst_db.is_none() {
                 db.execute(
@@ -117,6 +137,7 @@ fn fetch_invoices(db: &Connection) -> Result<Vec<Invoice>, String> {
                 duty_percent: d,
                 sws_percent: s,
                 igst_percent: ig,
+                po_line_id,
             });
         }
 
@@ -208,6 +229,7 @@ pub fn bulk_finalize_invoices(
                     duty_percent: Some(li.duty_percent),
                     sws_percent: Some(li.sws_percent),
                     igst_percent: Some(li.igst_percent),
+                    po_line_id: li.po_line_id.clone(),
                 })
                 .collect(),
         };
@@ -260,18 +282,15 @@ pub(crate) fn execute_add_invoice(
 
     for line_item in &payload.line_items {
         let line_item_id = generate_id(Some("ILI".to_string()));
-        let (bcd_s, sws_s, igst_s) = item_master_tax_strings(tx, &line_item.item_id);
         let (d, s, ig) = merge_tax_rates(
             line_item.duty_percent,
             line_item.sws_percent,
             line_item.igst_percent,
-            &bcd_s,
-            &sws_s,
-            &igst_s,
+            || default_line_rates(tx, &line_item.item_id, &payload.shipment_id),
         );
         tx.execute(
-            "INSERT INTO invoice_line_items (id, invoice_id, item_id, quantity, unit_price, duty_percent, sws_percent, igst_percent) \
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            "INSERT INTO invoice_line_items (id, invoice_id, item_id, quantity, unit_price, duty_percent, sws_percent, igst_percent, po_line_id) \
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
             params![
                 line_item_id,
                 &invoice_id,
@@ -280,17 +299,19 @@ pub(crate) fn execute_add_invoice(
                 line_item.unit_price,
                 d,
                 s,
-                ig
+                ig,
+                &line_item.po_line_id
             ],
         )?;
     }
 
     // Automatically update shipment status to "In Transit" when invoice is added
-    // Only update if current status is not "delivered"
-    tx.execute(
-        "UPDATE shipments SET status = 'in-transit' WHERE id = ?1 AND status != 'delivered'",
-        params![&payload.shipment_id],
-    )?;
+    shipment_status::transition_or_log(
+        tx,
+        &payload.shipment_id,
+        shipment_status::IN_TRANSIT,
+        &TransitionContext::system("Invoice added"),
+    );
 
     Ok(invoice_id)
 }
@@ -298,11 +319,13 @@ pub(crate) fn execute_add_invoice(
 #[tauri::command]
 pub fn add_invoice(payload: NewInvoicePayload, state: State<DbState>) -> Result<String, String> {
     let mut db = state.db.lock().unwrap();
+    purchase_orders::validate_invoice_links(&db, &payload)?;
     let tx = db.transaction().map_err(|e| e.to_string())?;
 
     match execute_add_invoice(&tx, &payload) {
         Ok(id) => {
             tx.commit().map_err(|e| e.to_string())?;
+            purchase_orders::review_shipment_after_save(&db, &payload.shipment_id, &[]);
             let _ = dashboard_cache::invalidate_dashboard_metrics_cache(&db);
             Ok(id)
         }
@@ -319,6 +342,9 @@ pub fn add_invoices_bulk(
     state: State<DbState>,
 ) -> Result<Vec<String>, String> {
     let mut db = state.db.lock().unwrap();
+    for payload in &payloads {
+        purchase_orders::validate_invoice_links(&db, payload)?;
+    }
     let tx = db.transaction().map_err(|e| e.to_string())?;
     let mut new_ids = Vec::new();
 
@@ -333,6 +359,9 @@ pub fn add_invoices_bulk(
     }
 
     tx.commit().map_err(|e| e.to_string())?;
+    for payload in &payloads {
+        purchase_orders::review_shipment_after_save(&db, &payload.shipment_id, &[]);
+    }
     let _ = dashboard_cache::invalidate_dashboard_metrics_cache(&db);
     Ok(new_ids)
 }
@@ -354,19 +383,16 @@ fn execute_update_invoice(
 
     for line_item in &payload.line_items {
         let line_item_id = generate_id(Some("ILI".to_string()));
-        let (bcd_s, sws_s, igst_s) = item_master_tax_strings(tx, &line_item.item_id);
         let (d, s, ig) = merge_tax_rates(
             line_item.duty_percent,
             line_item.sws_percent,
             line_item.igst_percent,
-            &bcd_s,
-            &sws_s,
-            &igst_s,
+            || default_line_rates(tx, &line_item.item_id, &payload.shipment_id),
         );
         tx.execute(
-            "INSERT INTO invoice_line_items (id, invoice_id, item_id, quantity, unit_price, duty_percent, sws_percent, igst_percent) \
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
-            params![line_item_id, id, &line_item.item_id, line_item.quantity, line_item.unit_price, d, s, ig],
+            "INSERT INTO invoice_line_items (id, invoice_id, item_id, quantity, unit_price, duty_percent, sws_percent, igst_percent, po_line_id) \
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![line_item_id, id, &line_item.item_id, line_item.quantity, line_item.unit_price, d, s, ig, &line_item.po_line_id],
         )?;
     }
 
@@ -380,11 +406,24 @@ pub fn update_invoice(
     state: State<DbState>,
 ) -> Result<(), String> {
     let mut db = state.db.lock().unwrap();
+    purchase_orders::validate_invoice_links(&db, &payload)?;
+    let previous_shipment: Option<String> = db
+        .query_row(
+            "SELECT shipment_id FROM invoices WHERE id = ?1",
+            params![&id],
+            |row| row.get(0),
+        )
+        .ok();
+    let previous_pos = purchase_orders::invoice_po_ids(&db, &id);
     let tx = db.transaction().map_err(|e| e.to_string())?;
 
     match execute_update_invoice(&tx, &id, &payload) {
         Ok(_) => {
             tx.commit().map_err(|e| e.to_string())?;
+            purchase_orders::review_shipment_after_save(&db, &payload.shipment_id, &previous_pos);
+            if let Some(prev) = previous_shipment.filter(|s| s != &payload.shipment_id) {
+                purchase_orders::review_shipment_after_save(&db, &prev, &[]);
+            }
             let _ = dashboard_cache::invalidate_dashboard_metrics_cache(&db);
             Ok(())
         }
@@ -398,8 +437,19 @@ pub fn update_invoice(
 #[tauri::command]
 pub fn delete_invoice(id: String, state: State<DbState>) -> Result<(), String> {
     let db = state.db.lock().unwrap();
+    let shipment_id: Option<String> = db
+        .query_row(
+            "SELECT shipment_id FROM invoices WHERE id = ?1",
+            params![&id],
+            |row| row.get(0),
+        )
+        .ok();
+    let previous_pos = purchase_orders::invoice_po_ids(&db, &id);
     db.execute("DELETE FROM invoices WHERE id = ?1", params![id])
         .map_err(|e| e.to_string())?;
+    if let Some(shipment_id) = shipment_id {
+        purchase_orders::review_shipment_after_save(&db, &shipment_id, &previous_pos);
+    }
     let _ = dashboard_cache::invalidate_dashboard_metrics_cache(&db);
     Ok(())
 }
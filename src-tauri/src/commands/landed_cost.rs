@@ -0,0 +1,715 @@
+//! Landed cost allocation: spreads each expense line over the shipment's invoice lines and persists landed cost per unit.
+
+use crate::commands::boe::map_row_to_saved_boe;
+use crate::commands::costing_snapshots;
+use crate::commands::dashboard_cache;
+use crate::commands::goods_receipts;
+use crate::db::DbState;
+use crate::duty_engine::{self, round_paise};
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tauri::State;
+
+pub const BASIS_ASSESSABLE_VALUE: &str = "ASSESSABLE_VALUE";
+pub const BASIS_GROSS_WEIGHT: &str = "GROSS_WEIGHT";
+pub const BASIS_NET_WEIGHT: &str = "NET_WEIGHT";
+pub const BASIS_QUANTITY: &str = "QUANTITY";
+pub const BASIS_EQUAL: &str = "EQUAL";
+
+const BASES: [&str; 5] = [
+    BASIS_ASSESSABLE_VALUE,
+    BASIS_GROSS_WEIGHT,
+    BASIS_NET_WEIGHT,
+    BASIS_QUANTITY,
+    BASIS_EQUAL,
+];
+
+/// One invoice line with everything the allocation bases need.
+#[derive(Debug, Clone, Default)]
+struct CostLine {
+    invoice_line_item_id: String,
+    item_id: String,
+    part_no: String,
+    quantity: f64,
+    invoice_value: f64,
+    gross_weight_kg: f64,
+    net_weight_kg: f64,
+    /// From the shipment's saved BOEs; 0 until a BOE covers the line.
+    assessable_value: f64,
+    /// BCD, SWS, AIDC, ADD and safeguard duty; IGST and cess are creditable and stay out.
+    duty_cost: f64,
+    /// Units the BOEs cleared; less than `quantity` while part of the line is still in bond.
+    cleared_quantity: f64,
+    /// Good units on posted GRNs; `None` until the line has been received.
+    received_quantity: Option<f64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ItemLandedCost {
+    pub invoice_line_item_id: String,
+    pub shipment_id: String,
+    pub item_id: String,
+    pub part_no: String,
+    pub quantity: f64,
+    pub assessable_value: f64,
+    pub duty_cost: f64,
+    pub expense_cost: f64,
+    pub landed_cost: f64,
+    /// `None` for a zero-quantity line.
+    pub landed_cost_per_unit: Option<f64>,
+    /// Good units (received less damaged) on posted GRNs; `None` until the goods are received.
+    #[serde(default)]
+    pub received_quantity: Option<f64>,
+    /// Landed cost spread over the good units actually received.
+    #[serde(default)]
+    pub landed_cost_per_received_unit: Option<f64>,
+    #[serde(default)]
+    pub computed_at: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct LandedCostAllocation {
+    pub expense_id: String,
+    pub expense_type: String,
+    pub invoice_line_item_id: String,
+    pub part_no: String,
+    /// Basis actually applied, after any fallback.
+    pub basis: String,
+    pub amount: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ItemCostHistoryEntry {
+    pub shipment_id: String,
+    pub invoice_number: String,
+    pub invoice_date: String,
+    pub quantity: f64,
+    pub assessable_value: f64,
+    pub duty_cost: f64,
+    pub expense_cost: f64,
+    pub landed_cost_per_unit: Option<f64>,
+    pub computed_at: String,
+}
+
+fn basis_weight(line: &CostLine, basis: &str, by_invoice_value: bool) -> f64 {
+    match basis {
+        BASIS_GROSS_WEIGHT => line.gross_weight_kg,
+        BASIS_NET_WEIGHT => line.net_weight_kg,
+        BASIS_QUANTITY => line.quantity,
+        BASIS_EQUAL => 1.0,
+        _ if by_invoice_value => line.invoice_value,
+        _ => line.assessable_value,
+    }
+}
+
+/// Splits `amount` in proportion to `weights` at paise precision; rounding is carried forward so
+/// the parts always add up to `amount`.
+fn split(amount: f64, weights: &[f64]) -> Vec<f64> {
+    let total: f64 = weights.iter().sum();
+    let amount_paise = (amount * 100.0).round();
+    let mut cumulative = 0.0;
+    let mut allocated = 0.0;
+    weights
+        .iter()
+        .map(|w| {
+            cumulative += w;
+            let upto = (amount_paise * cumulative / total).round();
+            let part = upto - allocated;
+            allocated = upto;
+            part / 100.0
+        })
+        .collect()
+}
+
+/// Shares of `amount` per line on `basis`. Lines without the basis data (no weights recorded, no
+/// BOE yet) fall back to assessable value, then to an equal split; the applied basis is returned.
+/// Before any BOE is saved, assessable value is approximated by invoice value.
+fn allocate(lines: &[CostLine], basis: &str, amount: f64) -> (&'static str, Vec<f64>) {
+    let by_invoice_value = lines.iter().all(|l| l.assessable_value <= 0.0);
+    for candidate in [basis, BASIS_ASSESSABLE_VALUE, BASIS_EQUAL] {
+        let Some(applied) = BASES.iter().find(|b| **b == candidate) else {
+            continue;
+        };
+        let weights: Vec<f64> = lines
+            .iter()
+            .map(|l| basis_weight(l, applied, by_invoice_value).max(0.0))
+            .collect();
+        if weights.iter().sum::<f64>() > 0.0 {
+            return (applied, split(amount, &weights));
+        }
+    }
+    (BASIS_EQUAL, vec![0.0; lines.len()])
+}
+
+fn load_lines(conn: &Connection, shipment_id: &str) -> Result<Vec<CostLine>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT ili.id, ili.item_id, i.part_number, ili.quantity, ili.quantity * ili.unit_price,
+                    ili.quantity * COALESCE(i.gross_weight_per_uom_kg, i.net_weight_kg, 0),
+                    ili.quantity * COALESCE(i.net_weight_kg, 0)
+             FROM invoices inv
+             JOIN invoice_line_items ili ON ili.invoice_id = inv.id
+             JOIN items i ON i.id = ili.item_id
+             WHERE inv.shipment_id = ?1
+             ORDER BY i.part_number, ili.rowid",
+        )
+        .map_err(|e| e.to_string())?;
+    let mut lines = stmt
+        .query_map(params![shipment_id], |r| {
+            Ok(CostLine {
+                invoice_line_item_id: r.get(0)?,
+                item_id: r.get(1)?,
+                part_no: r.get(2)?,
+                quantity: r.get(3)?,
+                invoice_value: r.get(4)?,
+                gross_weight_kg: r.get(5)?,
+                net_weight_kg: r.get(6)?,
+                ..CostLine::default()
+            })
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    // Assessed figures per invoice line across the shipment's clearances (home consumption and ex-bond).
+    let mut stmt = conn
+        .prepare("SELECT * FROM boe_calculations WHERE shipment_id = ?1")
+        .map_err(|e| e.to_string())?;
+    let saved_boes = stmt
+        .query_map(params![shipment_id], map_row_to_saved_boe)
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    // Lines are in the duty engine's order, so BOE items pair with the invoice line they assess.
+    let mut by_line: HashMap<String, (f64, f64, f64)> = HashMap::new();
+    for saved in &saved_boes {
+        // Into-bond assessments are settled by the ex-bond BOEs that clear them.
+        if saved.boe_type == crate::commands::bonded_warehouse::BOE_TYPE_INTO_BOND {
+            continue;
+        }
+        let items = &saved.calculation_result.calculated_items;
+        let line_of_item = duty_engine::pair_by_part(items, &lines, |i| &i.part_no, |l| &l.part_no);
+        let input_of_item =
+            duty_engine::pair_by_part(items, &saved.item_inputs, |i| &i.part_no, |ii| &ii.part_no);
+        for ((item, line), input) in items.iter().zip(line_of_item).zip(input_of_item) {
+            let Some(line) = line else {
+                continue;
+            };
+            let cleared = input.and_then(|ii| ii.quantity).unwrap_or(line.quantity);
+            let entry = by_line
+                .entry(line.invoice_line_item_id.clone())
+                .or_default();
+            entry.0 += item.assessable_value;
+            entry.2 += cleared;
+            entry.1 += item.bcd_value
+                + item.sws_value
+                + item.aidc_value
+                + item.add_value
+                + item.safeguard_value;
+        }
+    }
+    for line in &mut lines {
+        if let Some((av, duty, cleared)) = by_line.get(&line.invoice_line_item_id) {
+            line.assessable_value = round_paise(*av);
+            line.duty_cost = round_paise(*duty);
+            line.cleared_quantity = *cleared;
+        }
+    }
+    for line in &mut lines {
+        line.received_quantity =
+            goods_receipts::good_quantity_received(conn, &line.invoice_line_item_id)
+                .map_err(|e| e.to_string())?;
+    }
+    Ok(lines)
+}
+
+/// Landed cost of a shipment as the live data stands, without writing anything.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ShipmentCosting {
+    pub items: Vec<ItemLandedCost>,
+    pub allocations: Vec<LandedCostAllocation>,
+}
+
+/// Allocates every expense line of the shipment over its invoice lines.
+pub(crate) fn compute_shipment(
+    conn: &Connection,
+    shipment_id: &str,
+) -> Result<ShipmentCosting, String> {
+    let lines = load_lines(conn, shipment_id)?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT e.id, COALESCE(et.name, e.expense_type_id), COALESCE(et.allocation_basis, ?2), e.amount
+             FROM expenses e
+             JOIN expense_invoices ei ON ei.id = e.expense_invoice_id
+             LEFT JOIN expense_types et ON et.id = e.expense_type_id
+             WHERE ei.shipment_id = ?1
+             ORDER BY e.id",
+        )
+        .map_err(|e| e.to_string())?;
+    let expenses = stmt
+        .query_map(params![shipment_id, BASIS_ASSESSABLE_VALUE], |r| {
+            Ok((
+                r.get::<_, String>(0)?,
+                r.get::<_, String>(1)?,
+                r.get::<_, String>(2)?,
+                r.get::<_, f64>(3)?,
+            ))
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    let mut costing = ShipmentCosting::default();
+    let mut expense_cost = vec![0.0; lines.len()];
+    if !lines.is_empty() {
+        for (expense_id, expense_type, basis, amount) in &expenses {
+            let (applied, parts) = allocate(&lines, basis, *amount);
+            for ((line, part), total) in lines.iter().zip(parts).zip(expense_cost.iter_mut()) {
+                *total += part;
+                costing.allocations.push(LandedCostAllocation {
+                    expense_id: expense_id.clone(),
+                    expense_type: expense_type.clone(),
+                    invoice_line_item_id: line.invoice_line_item_id.clone(),
+                    part_no: line.part_no.clone(),
+                    basis: applied.to_string(),
+                    amount: part,
+                });
+            }
+        }
+    }
+
+    for (line, expense_cost) in lines.into_iter().zip(expense_cost) {
+        let expense_cost = round_paise(expense_cost);
+        let landed_cost = round_paise(line.assessable_value + line.duty_cost + expense_cost);
+        // Value and duty are spread over the units cleared so far, expenses over the whole line.
+        let landed_cost_per_unit = (line.quantity > 0.0).then(|| {
+            let assessed_per_unit = if line.cleared_quantity > 0.0 {
+                (line.assessable_value + line.duty_cost) / line.cleared_quantity
+            } else {
+                0.0
+            };
+            round_paise(assessed_per_unit + expense_cost / line.quantity)
+        });
+        let landed_cost_per_received_unit = line
+            .received_quantity
+            .filter(|q| *q > 0.0)
+            .map(|q| round_paise(landed_cost / q));
+        costing.items.push(ItemLandedCost {
+            invoice_line_item_id: line.invoice_line_item_id,
+            shipment_id: shipment_id.to_string(),
+            item_id: line.item_id,
+            part_no: line.part_no,
+            quantity: line.quantity,
+            assessable_value: line.assessable_value,
+            duty_cost: line.duty_cost,
+            expense_cost,
+            landed_cost,
+            landed_cost_per_unit,
+            received_quantity: line.received_quantity,
+            landed_cost_per_received_unit,
+            computed_at: None,
+        });
+    }
+    Ok(costing)
+}
+
+/// Re-allocates every expense line of the shipment and rewrites its `landed_cost_allocations` and
+/// `item_landed_costs` rows.
+pub fn recompute_shipment(
+    conn: &Connection,
+    shipment_id: &str,
+) -> Result<Vec<ItemLandedCost>, String> {
+    let costing = compute_shipment(conn, shipment_id)?;
+
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    tx.execute(
+        "DELETE FROM landed_cost_allocations WHERE shipment_id = ?1",
+        params![shipment_id],
+    )
+    .map_err(|e| e.to_string())?;
+    tx.execute(
+        "DELETE FROM item_landed_costs WHERE shipment_id = ?1",
+        params![shipment_id],
+    )
+    .map_err(|e| e.to_string())?;
+    for a in &costing.allocations {
+        tx.execute(
+            "INSERT INTO landed_cost_allocations (expense_id, invoice_line_item_id, shipment_id, basis, amount)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![a.expense_id, a.invoice_line_item_id, shipment_id, a.basis, a.amount],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    for line in &costing.items {
+        tx.execute(
+            "INSERT INTO item_landed_costs (invoice_line_item_id, shipment_id, item_id, part_no, quantity,
+                assessable_value, duty_cost, expense_cost, landed_cost, landed_cost_per_unit,
+                received_quantity, landed_cost_per_received_unit)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+            params![
+                line.invoice_line_item_id,
+                shipment_id,
+                line.item_id,
+                line.part_no,
+                line.quantity,
+                line.assessable_value,
+                line.duty_cost,
+                line.expense_cost,
+                line.landed_cost,
+                line.landed_cost_per_unit,
+                line.received_quantity,
+                line.landed_cost_per_received_unit
+            ],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(costing.items)
+}
+
+/// [`recompute_shipment`] for callers whose own write already succeeded; failures are only logged.
+/// Frozen shipments keep the figures they were frozen with.
+pub fn recompute_after_change(conn: &Connection, shipment_id: &str) {
+    match costing_snapshots::is_frozen(conn, shipment_id) {
+        Ok(true) => return,
+        Ok(false) => {}
+        Err(e) => log::warn!("Frozen check for shipment {shipment_id} failed: {e}"),
+    }
+    if let Err(e) = recompute_shipment(conn, shipment_id) {
+        log::warn!("Landed cost allocation for shipment {shipment_id} failed: {e}");
+    }
+}
+
+/// [`recompute_after_change`] for the shipment an expense invoice belongs to.
+pub fn recompute_after_expense_change(conn: &Connection, expense_invoice_id: &str) {
+    let shipment_id: rusqlite::Result<String> = conn.query_row(
+        "SELECT shipment_id FROM expense_invoices WHERE id = ?1",
+        params![expense_invoice_id],
+        |r| r.get(0),
+    );
+    match shipment_id {
+        Ok(shipment_id) => recompute_after_change(conn, &shipment_id),
+        Err(e) => log::warn!(
+            "Landed cost allocation skipped for expense invoice {expense_invoice_id}: {e}"
+        ),
+    }
+}
+
+#[tauri::command]
+pub fn set_expense_type_allocation_basis(
+    expense_type_id: String,
+    basis: String,
+    state: State<DbState>,
+) -> Result<u32, String> {
+    let basis = basis.trim().to_uppercase();
+    if !BASES.contains(&basis.as_str()) {
+        return Err(format!("Unknown allocation basis '{basis}'"));
+    }
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    let updated = conn
+        .execute(
+            "UPDATE expense_types SET allocation_basis = ?2 WHERE id = ?1",
+            params![expense_type_id, basis],
+        )
+        .map_err(|e| e.to_string())?;
+    if updated == 0 {
+        return Err("Expense type not found".to_string());
+    }
+    // Shipments carrying this expense type are re-allocated straight away.
+    let mut stmt = conn
+        .prepare(
+            "SELECT DISTINCT ei.shipment_id FROM expenses e
+             JOIN expense_invoices ei ON ei.id = e.expense_invoice_id
+             WHERE e.expense_type_id = ?1",
+        )
+        .map_err(|e| e.to_string())?;
+    let shipment_ids = stmt
+        .query_map(params![expense_type_id], |r| r.get::<_, String>(0))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    for shipment_id in &shipment_ids {
+        if !costing_snapshots::is_frozen(&conn, shipment_id)? {
+            recompute_shipment(&conn, shipment_id)?;
+        }
+    }
+    let _ = dashboard_cache::invalidate_dashboard_metrics_cache(&conn);
+    Ok(shipment_ids.len() as u32)
+}
+
+/// Recomputes one shipment, or every unfrozen shipment with invoice lines when `shipment_id` is `None`;
+/// returns how many shipments were processed.
+#[tauri::command]
+pub fn recompute_landed_costs(
+    shipment_id: Option<String>,
+    state: State<DbState>,
+) -> Result<u32, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    let shipment_ids = match shipment_id {
+        Some(id) if costing_snapshots::is_frozen(&conn, &id)? => {
+            return Err(format!(
+                "Shipment {id} is frozen; reopen it before recomputing landed cost"
+            ))
+        }
+        Some(id) => vec![id],
+        None => {
+            let mut stmt = conn
+                .prepare(
+                    "SELECT DISTINCT inv.shipment_id FROM invoices inv
+                     JOIN shipments s ON s.id = inv.shipment_id
+                     WHERE s.is_frozen = 0",
+                )
+                .map_err(|e| e.to_string())?;
+            let ids = stmt
+                .query_map([], |r| r.get::<_, String>(0))
+                .map_err(|e| e.to_string())?
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| e.to_string())?;
+            ids
+        }
+    };
+    for id in &shipment_ids {
+        recompute_shipment(&conn, id)?;
+    }
+    let _ = dashboard_cache::invalidate_dashboard_metrics_cache(&conn);
+    Ok(shipment_ids.len() as u32)
+}
+
+#[tauri::command]
+pub fn get_shipment_landed_costs(
+    shipment_id: String,
+    state: State<DbState>,
+) -> Result<Vec<ItemLandedCost>, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT invoice_line_item_id, shipment_id, item_id, part_no, quantity, assessable_value,
+                    duty_cost, expense_cost, landed_cost, landed_cost_per_unit, computed_at,
+                    received_quantity, landed_cost_per_received_unit
+             FROM item_landed_costs WHERE shipment_id = ?1 ORDER BY part_no, invoice_line_item_id",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![shipment_id], |r| {
+            Ok(ItemLandedCost {
+                invoice_line_item_id: r.get(0)?,
+                shipment_id: r.get(1)?,
+                item_id: r.get(2)?,
+                part_no: r.get(3)?,
+                quantity: r.get(4)?,
+                assessable_value: r.get(5)?,
+                duty_cost: r.get(6)?,
+                expense_cost: r.get(7)?,
+                landed_cost: r.get(8)?,
+                landed_cost_per_unit: r.get(9)?,
+                received_quantity: r.get(11)?,
+                landed_cost_per_received_unit: r.get(12)?,
+                computed_at: r.get(10)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn get_landed_cost_allocations(
+    shipment_id: String,
+    state: State<DbState>,
+) -> Result<Vec<LandedCostAllocation>, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT a.expense_id, COALESCE(et.name, e.expense_type_id), a.invoice_line_item_id,
+                    COALESCE(ilc.part_no, ''), a.basis, a.amount
+             FROM landed_cost_allocations a
+             JOIN expenses e ON e.id = a.expense_id
+             LEFT JOIN expense_types et ON et.id = e.expense_type_id
+             LEFT JOIN item_landed_costs ilc ON ilc.invoice_line_item_id = a.invoice_line_item_id
+             WHERE a.shipment_id = ?1
+             ORDER BY a.expense_id, ilc.part_no",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![shipment_id], |r| {
+            Ok(LandedCostAllocation {
+                expense_id: r.get(0)?,
+                expense_type: r.get(1)?,
+                invoice_line_item_id: r.get(2)?,
+                part_no: r.get(3)?,
+                basis: r.get(4)?,
+                amount: r.get(5)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())
+}
+
+/// Landed cost per unit of an item across shipments, oldest invoice first. Lines not yet on a BOE
+/// are left out since their cost is still incomplete.
+#[tauri::command]
+pub fn get_item_landed_cost_history(
+    item_id: String,
+    state: State<DbState>,
+) -> Result<Vec<ItemCostHistoryEntry>, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT ilc.shipment_id, s.invoice_number, s.invoice_date, ilc.quantity, ilc.assessable_value,
+                    ilc.duty_cost, ilc.expense_cost, ilc.landed_cost_per_unit, ilc.computed_at
+             FROM item_landed_costs ilc
+             JOIN shipments s ON s.id = ilc.shipment_id
+             WHERE ilc.item_id = ?1 AND ilc.assessable_value > 0
+             ORDER BY s.invoice_date, ilc.shipment_id",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![item_id], |r| {
+            Ok(ItemCostHistoryEntry {
+                shipment_id: r.get(0)?,
+                invoice_number: r.get(1)?,
+                invoice_date: r.get(2)?,
+                quantity: r.get(3)?,
+                assessable_value: r.get(4)?,
+                duty_cost: r.get(5)?,
+                expense_cost: r.get(6)?,
+                landed_cost_per_unit: r.get(7)?,
+                computed_at: r.get(8)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::test_support;
+
+    #[test]
+    fn expenses_follow_their_type_basis_and_feed_the_report() {
+        let conn = test_support::migrated_db();
+        test_support::add_shipment(&conn, "SHP-1", "2024-03-01", 3000.0, "USD", "in-transit");
+        test_support::add_invoice(&conn, "INVC-1", "SHP-1");
+        conn.execute_batch(
+            "INSERT INTO items (id, part_number, item_description, unit, currency, unit_price, hsn_code, is_active, net_weight_kg, gross_weight_per_uom_kg)
+                VALUES ('IT-A', 'A', 'Heavy', 'PCS', 'USD', 100, '84821011', 1, 9, 10),
+                       ('IT-B', 'B', 'Light', 'PCS', 'USD', 200, '84821011', 1, 0.5, 1);
+             INSERT INTO invoice_line_items (id, invoice_id, item_id, quantity, unit_price, duty_percent, sws_percent, igst_percent)
+                VALUES ('L-A', 'INVC-1', 'IT-A', 10, 100, 10, 10, 18),
+                       ('L-B', 'INVC-1', 'IT-B', 10, 200, 10, 10, 18);
+             INSERT INTO service_providers (id, name) VALUES ('SP-1', 'Forwarder');
+             INSERT INTO expense_types (id, name, allocation_basis) VALUES
+                ('ET-FRT', 'Ocean Freight Test', 'GROSS_WEIGHT'),
+                ('ET-CHA', 'CHA Test', 'ASSESSABLE_VALUE'),
+                ('ET-DOC', 'Documentation Test', 'EQUAL');
+             INSERT INTO expense_invoices (id, shipment_id, service_provider_id, invoice_no, invoice_date, total_amount)
+                VALUES ('EI-1', 'SHP-1', 'SP-1', 'F-1', '2024-03-05', 0);
+             INSERT INTO expenses (id, expense_invoice_id, shipment_id, service_provider_id, invoice_no, invoice_date, expense_type_id, amount)
+                VALUES ('E-FRT', 'EI-1', 'SHP-1', 'SP-1', 'F-1', '2024-03-05', 'ET-FRT', 1100),
+                       ('E-CHA', 'EI-1', 'SHP-1', 'SP-1', 'F-1', '2024-03-05', 'ET-CHA', 900),
+                       ('E-DOC', 'EI-1', 'SHP-1', 'SP-1', 'F-1', '2024-03-05', 'ET-DOC', 100.01);",
+        )
+        .unwrap();
+
+        // No BOE yet: value-based expenses split on invoice value (1000 : 2000).
+        let before = recompute_shipment(&conn, "SHP-1").unwrap();
+        let a = before.iter().find(|l| l.part_no == "A").unwrap();
+        // Freight 100 kg : 10 kg -> 1000; CHA 300; the odd paisa of documentation goes to the first line.
+        assert_eq!(a.expense_cost, 1350.01);
+        let total: f64 = before.iter().map(|l| l.expense_cost).sum();
+        assert!((total - 2100.01).abs() < 1e-9);
+
+        conn.execute_batch(
+            r#"INSERT INTO boe_calculations (id, shipment_id, boe_id, supplier_name, invoice_number, form_values_json, item_inputs_json, calculation_result_json)
+               VALUES ('BC-1', 'SHP-1', NULL, 'Acme', 'INV-1',
+                 '{"supplierName":"Acme","shipmentId":"SHP-1","exchangeRate":80,"freightCost":0,"exwCost":0,"insuranceRate":0}',
+                 '[]',
+                 '{"calculatedItems":[
+                    {"partNo":"A","description":"Heavy","assessableValue":60000,"bcdValue":6000,"swsValue":600,"igstValue":12000},
+                    {"partNo":"B","description":"Light","assessableValue":180000,"bcdValue":18000,"swsValue":1800,"igstValue":36000}],
+                   "bcdTotal":24000,"swsTotal":2400,"igstTotal":48000,"interest":0,"customsDutyTotal":74400}');"#,
+        )
+        .unwrap();
+        let after = recompute_shipment(&conn, "SHP-1").unwrap();
+        let a = after.iter().find(|l| l.part_no == "A").unwrap();
+        // CHA now follows assessable value 60000 : 180000.
+        assert_eq!(a.expense_cost, 1275.01);
+        assert_eq!(a.duty_cost, 6600.0);
+        assert_eq!(a.landed_cost, 67875.01);
+        assert_eq!(a.landed_cost_per_unit, Some(6787.5));
+
+        let (report_expenses, report_ldc): (f64, f64) = conn
+            .query_row(
+                "SELECT expenses_total, ldc_per_qty FROM report_view WHERE part_no = 'A'",
+                [],
+                |r| Ok((r.get(0)?, r.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(report_expenses, 1275.01);
+        assert_eq!(report_ldc, 6787.5);
+    }
+
+    #[test]
+    fn repeated_part_lines_take_their_own_boe_item() {
+        let conn = test_support::migrated_db();
+        test_support::add_shipment(&conn, "SHP-1", "2024-03-01", 3000.0, "USD", "in-transit");
+        test_support::add_item(&conn, "IT-A", "A", "Bearing", None);
+        test_support::add_invoice(&conn, "INVC-1", "SHP-1");
+        // Part A on two lines; the BOE clears 4 of the first line's 10 units and all 20 of the second.
+        conn.execute_batch(
+            r#"INSERT INTO invoice_line_items (id, invoice_id, item_id, quantity, unit_price, duty_percent, sws_percent, igst_percent)
+                VALUES ('L-A1', 'INVC-1', 'IT-A', 10, 100, 10, 10, 18),
+                       ('L-A2', 'INVC-1', 'IT-A', 20, 100, 10, 10, 18);
+             INSERT INTO boe_calculations (id, shipment_id, boe_id, supplier_name, invoice_number, form_values_json, item_inputs_json, calculation_result_json)
+               VALUES ('BC-1', 'SHP-1', NULL, 'Acme', 'INV-1',
+                 '{"supplierName":"Acme","shipmentId":"SHP-1","exchangeRate":80,"freightCost":0,"exwCost":0,"insuranceRate":0}',
+                 '[{"partNo":"A","calculationMethod":"Standard","boeBcdRate":10,"boeSwsRate":0,"boeIgstRate":18,"quantity":4},
+                   {"partNo":"A","calculationMethod":"Standard","boeBcdRate":10,"boeSwsRate":0,"boeIgstRate":18}]',
+                 '{"calculatedItems":[
+                    {"partNo":"A","description":"Bearing","assessableValue":32000,"bcdValue":3200,"swsValue":0,"igstValue":6336},
+                    {"partNo":"A","description":"Bearing","assessableValue":160000,"bcdValue":16000,"swsValue":0,"igstValue":31680}],
+                   "bcdTotal":19200,"swsTotal":0,"igstTotal":38016,"interest":0,"customsDutyTotal":57216}');"#,
+        )
+        .unwrap();
+
+        let lines = recompute_shipment(&conn, "SHP-1").unwrap();
+        let costs: Vec<(&str, f64, f64, Option<f64>)> = lines
+            .iter()
+            .map(|l| {
+                (
+                    l.invoice_line_item_id.as_str(),
+                    l.assessable_value,
+                    l.duty_cost,
+                    l.landed_cost_per_unit,
+                )
+            })
+            .collect();
+        assert_eq!(
+            costs,
+            vec![
+                ("L-A1", 32_000.0, 3_200.0, Some(8_800.0)),
+                ("L-A2", 160_000.0, 16_000.0, Some(8_800.0)),
+            ]
+        );
+
+        // One report row per BOE item, on the line it assesses.
+        let report: Vec<(f64, f64, f64)> = conn
+            .prepare("SELECT qty, bcd_amount, ldc_per_qty FROM report_view ORDER BY qty")
+            .unwrap()
+            .query_map([], |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(
+            report,
+            vec![(10.0, 3_200.0, 8_800.0), (20.0, 16_000.0, 8_800.0)]
+        );
+    }
+}
@@ -1,15 +1,25 @@
 // Re-export all command modules to maintain the same public API
 pub mod ai_extraction;
+pub mod anti_dumping;
 pub mod app_info;
 pub mod app_metadata;
 pub mod backup_key;
 pub mod boe;
+pub mod boe_charges;
+pub mod boe_import;
+pub mod boe_reconciliation;
+pub mod bonded_warehouse;
+pub mod costing_snapshots;
 pub mod dashboard_cache;
 pub mod dashboard_metrics;
+pub mod demurrage;
 pub mod db_maintenance;
 pub mod db_management;
+pub mod duty_credits;
+pub mod eta_slippage;
 pub mod exception_workflow;
 pub mod exception_reliability;
+pub mod exchange_rates;
 pub mod workflow_observability;
 pub mod workflow_job_monitoring;
 pub mod workflow_automation;
@@ -19,18 +29,33 @@ pub mod deployment_safety;
 pub mod workflow_production_observability;
 pub mod workflow_incident_management;
 pub mod expenses;
+pub mod forward_contracts;
+pub mod goods_receipts;
 pub mod google_drive;
+pub mod gst_reconciliation;
+pub mod hsn_classification;
 pub mod invoices;
 pub mod items;
+pub mod landed_cost;
 pub mod logs;
 pub mod oauth_callback;
 pub mod options;
+pub mod packing_lists;
+pub mod purchase_orders;
 pub mod recycle_bin;
 pub mod reference_scan;
 pub mod reports;
+pub mod shipment_documents;
+pub mod shipment_events;
+pub mod shipment_status;
 pub mod shipments;
+pub mod supplier_payments;
 pub mod suppliers;
+pub mod tariff;
 pub mod test_reset;
+#[cfg(test)]
+pub mod test_support;
+pub mod trade_agreements;
 pub mod utils;
 
 // Re-export all public functions from submodules
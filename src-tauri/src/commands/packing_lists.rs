@@ -0,0 +1,709 @@
+//! Packing-list import: parsed rows matched to the shipment's invoice lines by part number, shipment
+//! gross weight updated, and weights checked against the item master.
+
+use crate::commands::dashboard_cache;
+use crate::commands::utils::generate_id;
+use crate::db::DbState;
+use crate::packing_list_parser::{self, PackingListRow, ParsedPackingList};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use tauri::State;
+
+pub const FORMAT_EXCEL: &str = "EXCEL";
+pub const FORMAT_TEXT: &str = "TEXT";
+pub const FORMAT_OCR: &str = "OCR";
+
+pub const FLAG_UNMATCHED: &str = "UNMATCHED";
+pub const FLAG_QUANTITY_MISMATCH: &str = "QUANTITY_MISMATCH";
+pub const FLAG_NET_WEIGHT_VARIANCE: &str = "NET_WEIGHT_VARIANCE";
+pub const FLAG_GROSS_WEIGHT_VARIANCE: &str = "GROSS_WEIGHT_VARIANCE";
+pub const FLAG_GROSS_BELOW_NET: &str = "GROSS_BELOW_NET";
+pub const FLAG_NO_MASTER_WEIGHT: &str = "NO_MASTER_WEIGHT";
+
+const WEIGHT_TOLERANCE_KEY: &str = "packing_list_weight_tolerance_percent";
+const DEFAULT_WEIGHT_TOLERANCE: f64 = 5.0;
+const QTY_EPSILON: f64 = 1e-6;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PackingListLine {
+    pub part_number: String,
+    pub description: Option<String>,
+    pub invoice_line_item_id: Option<String>,
+    pub item_id: Option<String>,
+    pub invoiced_quantity: Option<f64>,
+    pub quantity: Option<f64>,
+    pub packages: Option<f64>,
+    pub net_weight_kg: Option<f64>,
+    pub gross_weight_kg: Option<f64>,
+    pub dimensions: Option<String>,
+    /// Item master weight per unit × quantity.
+    pub expected_net_weight_kg: Option<f64>,
+    pub expected_gross_weight_kg: Option<f64>,
+    pub flags: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PackingList {
+    pub id: String,
+    pub shipment_id: String,
+    pub file_name: String,
+    /// `EXCEL`, `TEXT` or `OCR`.
+    pub source_format: String,
+    pub total_packages: Option<f64>,
+    pub total_net_weight_kg: Option<f64>,
+    pub total_gross_weight_kg: Option<f64>,
+    pub previous_gross_weight_kg: Option<f64>,
+    pub item_weights_filled: u32,
+    pub warnings: Vec<String>,
+    pub lines: Vec<PackingListLine>,
+    pub created_by: Option<String>,
+    pub created_at: String,
+}
+
+/// Invoice lines of one part number on the shipment, with the item master unit weights.
+struct InvoicePart {
+    line_id: String,
+    item_id: String,
+    quantity: f64,
+    net_per_unit: Option<f64>,
+    gross_per_unit: Option<f64>,
+}
+
+fn round3(v: f64) -> f64 {
+    (v * 1000.0).round() / 1000.0
+}
+
+fn part_key(part_number: &str) -> String {
+    part_number
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_uppercase())
+        .collect()
+}
+
+fn add(a: Option<f64>, b: Option<f64>) -> Option<f64> {
+    match (a, b) {
+        (None, None) => None,
+        (a, b) => Some(a.unwrap_or(0.0) + b.unwrap_or(0.0)),
+    }
+}
+
+pub fn weight_tolerance(conn: &Connection) -> f64 {
+    conn.query_row(
+        "SELECT value FROM app_metadata WHERE key = ?1",
+        params![WEIGHT_TOLERANCE_KEY],
+        |r| r.get::<_, String>(0),
+    )
+    .ok()
+    .and_then(|v| v.trim().parse::<f64>().ok())
+    .filter(|v| v.is_finite() && *v >= 0.0)
+    .unwrap_or(DEFAULT_WEIGHT_TOLERANCE)
+}
+
+fn invoice_parts(
+    conn: &Connection,
+    shipment_id: &str,
+) -> rusqlite::Result<BTreeMap<String, InvoicePart>> {
+    let mut stmt = conn.prepare(
+        "SELECT ili.id, ili.item_id, i.part_number, ili.quantity, i.net_weight_kg, i.gross_weight_per_uom_kg
+         FROM invoice_line_items ili
+         JOIN invoices inv ON inv.id = ili.invoice_id
+         JOIN items i ON i.id = ili.item_id
+         WHERE inv.shipment_id = ?1
+         ORDER BY inv.id, ili.rowid",
+    )?;
+    let rows = stmt.query_map(params![shipment_id], |r| {
+        Ok((
+            r.get::<_, String>(2)?,
+            InvoicePart {
+                line_id: r.get(0)?,
+                item_id: r.get(1)?,
+                quantity: r.get(3)?,
+                net_per_unit: r.get(4)?,
+                gross_per_unit: r.get(5)?,
+            },
+        ))
+    })?;
+    let mut parts: BTreeMap<String, InvoicePart> = BTreeMap::new();
+    for row in rows {
+        let (part_number, line) = row?;
+        parts
+            .entry(part_key(&part_number))
+            .and_modify(|p| p.quantity += line.quantity)
+            .or_insert(line);
+    }
+    Ok(parts)
+}
+
+/// Rows of the same part (split over several cartons) added together, in first-seen order.
+fn merge_rows(rows: &[PackingListRow]) -> Vec<PackingListRow> {
+    let mut merged: Vec<PackingListRow> = Vec::new();
+    for row in rows {
+        let key = part_key(&row.part_number);
+        match merged.iter_mut().find(|m| part_key(&m.part_number) == key) {
+            Some(m) => {
+                m.quantity = add(m.quantity, row.quantity);
+                m.packages = add(m.packages, row.packages);
+                m.net_weight_kg = add(m.net_weight_kg, row.net_weight_kg);
+                m.gross_weight_kg = add(m.gross_weight_kg, row.gross_weight_kg);
+                m.dimensions = match (m.dimensions.take(), row.dimensions.clone()) {
+                    (Some(a), Some(b)) if a != b => Some(format!("{a}; {b}")),
+                    (a, b) => a.or(b),
+                };
+            }
+            None => merged.push(row.clone()),
+        }
+    }
+    merged
+}
+
+fn outside_tolerance(actual: Option<f64>, expected: Option<f64>, tolerance: f64) -> bool {
+    match (actual, expected) {
+        (Some(a), Some(e)) if e > 0.0 => ((a - e) / e * 100.0).abs() > tolerance + 1e-9,
+        _ => false,
+    }
+}
+
+fn check_line(
+    row: PackingListRow,
+    invoice: Option<&InvoicePart>,
+    tolerance: f64,
+) -> PackingListLine {
+    let mut flags = Vec::new();
+    let quantity = row.quantity.or(invoice.map(|p| p.quantity));
+    let expected = |per_unit: Option<f64>| {
+        per_unit
+            .filter(|w| *w > 0.0)
+            .zip(quantity)
+            .map(|(w, q)| round3(w * q))
+    };
+    let expected_net = invoice.and_then(|p| expected(p.net_per_unit));
+    let expected_gross = invoice.and_then(|p| expected(p.gross_per_unit));
+    match invoice {
+        None => flags.push(FLAG_UNMATCHED),
+        Some(p) => {
+            if matches!(row.quantity, Some(q) if (q - p.quantity).abs() > QTY_EPSILON) {
+                flags.push(FLAG_QUANTITY_MISMATCH);
+            }
+            if outside_tolerance(row.net_weight_kg, expected_net, tolerance) {
+                flags.push(FLAG_NET_WEIGHT_VARIANCE);
+            }
+            if outside_tolerance(row.gross_weight_kg, expected_gross, tolerance) {
+                flags.push(FLAG_GROSS_WEIGHT_VARIANCE);
+            }
+            if row.net_weight_kg.is_some() && expected_net.is_none() {
+                flags.push(FLAG_NO_MASTER_WEIGHT);
+            }
+        }
+    }
+    if matches!((row.net_weight_kg, row.gross_weight_kg), (Some(n), Some(g)) if g + QTY_EPSILON < n)
+    {
+        flags.push(FLAG_GROSS_BELOW_NET);
+    }
+    PackingListLine {
+        part_number: row.part_number,
+        description: row.description,
+        invoice_line_item_id: invoice.map(|p| p.line_id.clone()),
+        item_id: invoice.map(|p| p.item_id.clone()),
+        invoiced_quantity: invoice.map(|p| p.quantity),
+        quantity: row.quantity,
+        packages: row.packages,
+        net_weight_kg: row.net_weight_kg.map(round3),
+        gross_weight_kg: row.gross_weight_kg.map(round3),
+        dimensions: row.dimensions,
+        expected_net_weight_kg: expected_net,
+        expected_gross_weight_kg: expected_gross,
+        flags: flags.into_iter().map(str::to_string).collect(),
+    }
+}
+
+/// Stores a parsed packing list against the shipment, sets the shipment's gross weight from it and
+/// fills item master unit weights that are still blank.
+pub fn ingest(
+    conn: &Connection,
+    shipment_id: &str,
+    file_name: &str,
+    source_format: &str,
+    parsed: ParsedPackingList,
+    user_id: Option<&str>,
+) -> Result<PackingList, String> {
+    let previous_gross: Option<Option<f64>> = conn
+        .query_row(
+            "SELECT gross_weight_kg FROM shipments WHERE id = ?1",
+            params![shipment_id],
+            |r| r.get(0),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+    let Some(previous_gross) = previous_gross else {
+        return Err(format!("Shipment {shipment_id} not found"));
+    };
+    let tolerance = weight_tolerance(conn);
+    let invoice = invoice_parts(conn, shipment_id).map_err(|e| e.to_string())?;
+
+    let lines: Vec<PackingListLine> = merge_rows(&parsed.rows)
+        .into_iter()
+        .map(|row| {
+            let part = invoice.get(&part_key(&row.part_number));
+            check_line(row, part, tolerance)
+        })
+        .collect();
+
+    let mut warnings = parsed.warnings.clone();
+    for (key, part) in &invoice {
+        if !lines.iter().any(|l| part_key(&l.part_number) == *key) {
+            warnings.push(format!(
+                "Invoice line {} (item {}) is not on the packing list",
+                part.line_id, part.item_id
+            ));
+        }
+    }
+    // The document's total row wins; the rows only stand in for it when every row has a value,
+    // so a row without a weight cannot understate the shipment's gross weight.
+    let row_sum = |f: fn(&PackingListRow) -> Option<f64>| {
+        if parsed.rows.is_empty() {
+            return None;
+        }
+        parsed.rows.iter().map(f).sum::<Option<f64>>().map(round3)
+    };
+    let rows_net = row_sum(|r| r.net_weight_kg);
+    let rows_gross = row_sum(|r| r.gross_weight_kg);
+    let total_packages = parsed.declared_packages.or(row_sum(|r| r.packages));
+    let total_net = parsed.declared_net_weight_kg.map(round3).or(rows_net);
+    let total_gross = parsed.declared_gross_weight_kg.map(round3).or(rows_gross);
+    for (label, declared, total) in [
+        ("gross weight", parsed.declared_gross_weight_kg, rows_gross),
+        ("net weight", parsed.declared_net_weight_kg, rows_net),
+    ] {
+        if let (Some(d), Some(t)) = (declared, total) {
+            if (d - t).abs() > 0.01 {
+                warnings.push(format!(
+                    "Declared total {label} {d} kg differs from the sum of the rows, {t} kg"
+                ));
+            }
+        }
+    }
+
+    let id = generate_id(Some("PKL".to_string()));
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    let mut filled = 0u32;
+    for line in &lines {
+        let (Some(item_id), Some(qty)) = (line.item_id.as_deref(), line.quantity) else {
+            continue;
+        };
+        if qty <= QTY_EPSILON {
+            continue;
+        }
+        for (column, weight) in [
+            ("net_weight_kg", line.net_weight_kg),
+            ("gross_weight_per_uom_kg", line.gross_weight_kg),
+        ] {
+            let Some(weight) = weight.filter(|w| *w > 0.0) else {
+                continue;
+            };
+            filled += tx
+                .execute(
+                    &format!(
+                        "UPDATE items SET {column} = ?2 WHERE id = ?1 AND ({column} IS NULL OR {column} = 0)"
+                    ),
+                    params![item_id, round3(weight / qty)],
+                )
+                .map_err(|e| e.to_string())? as u32;
+        }
+    }
+    tx.execute(
+        "INSERT INTO packing_lists (id, shipment_id, file_name, source_format, total_packages, total_net_weight_kg,
+            total_gross_weight_kg, previous_gross_weight_kg, item_weights_filled, warnings_json, created_by)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+        params![
+            id,
+            shipment_id,
+            file_name,
+            source_format,
+            total_packages,
+            total_net,
+            total_gross,
+            previous_gross,
+            filled,
+            serde_json::to_string(&warnings).map_err(|e| e.to_string())?,
+            user_id
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    for line in &lines {
+        tx.execute(
+            "INSERT INTO packing_list_lines (packing_list_id, part_number, description, invoice_line_item_id, item_id,
+                invoiced_quantity, quantity, packages, net_weight_kg, gross_weight_kg, dimensions,
+                expected_net_weight_kg, expected_gross_weight_kg, flags)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
+            params![
+                id,
+                line.part_number,
+                line.description,
+                line.invoice_line_item_id,
+                line.item_id,
+                line.invoiced_quantity,
+                line.quantity,
+                line.packages,
+                line.net_weight_kg,
+                line.gross_weight_kg,
+                line.dimensions,
+                line.expected_net_weight_kg,
+                line.expected_gross_weight_kg,
+                line.flags.join(",")
+            ],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    if let Some(gross) = total_gross.filter(|g| *g > 0.0) {
+        tx.execute(
+            "UPDATE shipments SET gross_weight_kg = ?2 WHERE id = ?1",
+            params![shipment_id, gross],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    tx.commit().map_err(|e| e.to_string())?;
+    load_packing_list(conn, &id)
+}
+
+fn load_lines(conn: &Connection, packing_list_id: &str) -> rusqlite::Result<Vec<PackingListLine>> {
+    let mut stmt = conn.prepare(
+        "SELECT part_number, description, invoice_line_item_id, item_id, invoiced_quantity, quantity, packages,
+                net_weight_kg, gross_weight_kg, dimensions, expected_net_weight_kg, expected_gross_weight_kg, flags
+         FROM packing_list_lines WHERE packing_list_id = ?1 ORDER BY id",
+    )?;
+    let rows = stmt.query_map(params![packing_list_id], |r| {
+        let flags: String = r.get(12)?;
+        Ok(PackingListLine {
+            part_number: r.get(0)?,
+            description: r.get(1)?,
+            invoice_line_item_id: r.get(2)?,
+            item_id: r.get(3)?,
+            invoiced_quantity: r.get(4)?,
+            quantity: r.get(5)?,
+            packages: r.get(6)?,
+            net_weight_kg: r.get(7)?,
+            gross_weight_kg: r.get(8)?,
+            dimensions: r.get(9)?,
+            expected_net_weight_kg: r.get(10)?,
+            expected_gross_weight_kg: r.get(11)?,
+            flags: flags
+                .split(',')
+                .filter(|f| !f.is_empty())
+                .map(str::to_string)
+                .collect(),
+        })
+    })?;
+    rows.collect()
+}
+
+const PACKING_LIST_COLUMNS: &str = "id, shipment_id, file_name, source_format, total_packages, total_net_weight_kg,
+    total_gross_weight_kg, previous_gross_weight_kg, item_weights_filled, warnings_json, created_by, created_at";
+
+fn map_packing_list(r: &rusqlite::Row) -> rusqlite::Result<PackingList> {
+    let warnings: String = r.get(9)?;
+    Ok(PackingList {
+        id: r.get(0)?,
+        shipment_id: r.get(1)?,
+        file_name: r.get(2)?,
+        source_format: r.get(3)?,
+        total_packages: r.get(4)?,
+        total_net_weight_kg: r.get(5)?,
+        total_gross_weight_kg: r.get(6)?,
+        previous_gross_weight_kg: r.get(7)?,
+        item_weights_filled: r.get(8)?,
+        warnings: serde_json::from_str(&warnings).unwrap_or_default(),
+        lines: Vec::new(),
+        created_by: r.get(10)?,
+        created_at: r.get(11)?,
+    })
+}
+
+pub fn load_packing_list(conn: &Connection, id: &str) -> Result<PackingList, String> {
+    let mut list = conn
+        .query_row(
+            &format!("SELECT {PACKING_LIST_COLUMNS} FROM packing_lists WHERE id = ?1"),
+            params![id],
+            map_packing_list,
+        )
+        .optional()
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Packing list {id} not found"))?;
+    list.lines = load_lines(conn, id).map_err(|e| e.to_string())?;
+    Ok(list)
+}
+
+/// Picks the parser from the file name: Excel workbooks directly, `.csv` / `.txt` as text, anything
+/// else (PDF, scans) through OCR.
+pub fn parse_file(
+    file_name: &str,
+    file_bytes: &[u8],
+) -> Result<(ParsedPackingList, &'static str), String> {
+    let lower = file_name.trim().to_ascii_lowercase();
+    let ext = lower.rsplit('.').next().unwrap_or("");
+    match ext {
+        "xlsx" | "xlsm" | "xls" | "ods" => {
+            packing_list_parser::parse_packing_list_excel(file_bytes).map(|p| (p, FORMAT_EXCEL))
+        }
+        "csv" | "txt" | "tsv" => {
+            let text = String::from_utf8_lossy(file_bytes);
+            let text = if ext == "csv" {
+                text.replace(',', "\t")
+            } else {
+                text.into_owned()
+            };
+            packing_list_parser::parse_packing_list_text(&text).map(|p| (p, FORMAT_TEXT))
+        }
+        _ => {
+            let text = crate::ocr_engine::run_ocr_on_image(file_bytes)?;
+            packing_list_parser::parse_packing_list_text(&text).map(|p| (p, FORMAT_OCR))
+        }
+    }
+}
+
+/// Parses an Excel, text, PDF or image packing list and applies it to the shipment.
+#[tauri::command]
+pub fn import_packing_list(
+    shipment_id: String,
+    file_name: String,
+    file_bytes: Vec<u8>,
+    user_id: Option<String>,
+    state: State<DbState>,
+) -> Result<PackingList, String> {
+    // Parse (and OCR) before taking the lock.
+    let (parsed, format) = parse_file(&file_name, &file_bytes)?;
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    let list = ingest(
+        &conn,
+        &shipment_id,
+        &file_name,
+        format,
+        parsed,
+        user_id.as_deref(),
+    )?;
+    let _ = dashboard_cache::invalidate_dashboard_metrics_cache(&conn);
+    Ok(list)
+}
+
+/// Packing lists imported for a shipment, latest first.
+#[tauri::command]
+pub fn list_packing_lists(
+    shipment_id: String,
+    state: State<DbState>,
+) -> Result<Vec<PackingList>, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {PACKING_LIST_COLUMNS} FROM packing_lists WHERE shipment_id = ?1 ORDER BY created_at DESC, rowid DESC"
+        ))
+        .map_err(|e| e.to_string())?;
+    let mut lists = stmt
+        .query_map(params![shipment_id], map_packing_list)
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    for list in lists.iter_mut() {
+        list.lines = load_lines(&conn, &list.id).map_err(|e| e.to_string())?;
+    }
+    Ok(lists)
+}
+
+/// Removes an imported list; the shipment gross weight and item master weights it set stay as they are.
+#[tauri::command]
+pub fn delete_packing_list(id: String, state: State<DbState>) -> Result<(), String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    tx.execute(
+        "DELETE FROM packing_list_lines WHERE packing_list_id = ?1",
+        params![id],
+    )
+    .map_err(|e| e.to_string())?;
+    let deleted = tx
+        .execute("DELETE FROM packing_lists WHERE id = ?1", params![id])
+        .map_err(|e| e.to_string())?;
+    if deleted == 0 {
+        return Err(format!("Packing list {id} not found"));
+    }
+    tx.commit().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn get_packing_list_weight_tolerance(state: State<DbState>) -> Result<f64, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    Ok(weight_tolerance(&conn))
+}
+
+#[tauri::command]
+pub fn save_packing_list_weight_tolerance(
+    percent: f64,
+    state: State<DbState>,
+) -> Result<f64, String> {
+    if !percent.is_finite() || percent < 0.0 {
+        return Err("Weight tolerance must be a zero or positive percentage".to_string());
+    }
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    conn.execute(
+        "INSERT INTO app_metadata (key, value) VALUES (?1, ?2)
+         ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+        params![WEIGHT_TOLERANCE_KEY, percent.to_string()],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(percent)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::test_support;
+
+    /// SHP-1 (gross 12 kg on file) invoiced 100 x P-1001 (line L-1, 0.5 kg net each in the item
+    /// master) and 40 x P-2002 (line L-2, no master weight).
+    fn shipment() -> Connection {
+        let conn = test_support::migrated_db();
+        test_support::add_shipment(&conn, "SHP-1", "2024-03-01", 1000.0, "USD", "in-transit");
+        test_support::add_item(&conn, "ITM-1", "P-1001", "Bolt", Some(0.5));
+        test_support::add_item(&conn, "ITM-2", "P-2002", "Nut", None);
+        conn.execute_batch(
+            "UPDATE shipments SET gross_weight_kg = 12 WHERE id = 'SHP-1';
+             INSERT INTO invoices (id, shipment_id, status) VALUES ('INV-1', 'SHP-1', 'Draft');
+             INSERT INTO invoice_line_items (id, invoice_id, item_id, quantity, unit_price)
+                VALUES ('L-1', 'INV-1', 'ITM-1', 100, 1), ('L-2', 'INV-1', 'ITM-2', 40, 1);",
+        )
+        .unwrap();
+        conn
+    }
+
+    /// Imports tab-separated `Part No, Qty, Cartons, Net Wt, Gross Wt` rows.
+    fn import(conn: &Connection, rows: &str) -> PackingList {
+        let text = format!("Part No\tQty\tCartons\tNet Wt\tGross Wt\n{rows}");
+        let parsed = packing_list_parser::parse_packing_list_text(&text).unwrap();
+        ingest(conn, "SHP-1", "pl.txt", FORMAT_TEXT, parsed, Some("ops")).unwrap()
+    }
+
+    #[test]
+    fn duplicate_part_rows_are_merged_and_matched_ignoring_punctuation_and_case() {
+        let conn = shipment();
+        let list = import(
+            &conn,
+            "p1001\t60\t1\t30\t33\nP-1001\t40\t1\t20\t22\nP 2002\t40\t1\t8\t9\n",
+        );
+        assert_eq!(list.lines.len(), 2);
+        let bolt = &list.lines[0];
+        assert_eq!(bolt.invoice_line_item_id.as_deref(), Some("L-1"));
+        assert_eq!(
+            (
+                bolt.quantity,
+                bolt.packages,
+                bolt.net_weight_kg,
+                bolt.gross_weight_kg
+            ),
+            (Some(100.0), Some(2.0), Some(50.0), Some(55.0))
+        );
+        assert!(bolt.flags.is_empty());
+        assert_eq!(list.lines[1].invoice_line_item_id.as_deref(), Some("L-2"));
+    }
+
+    #[test]
+    fn net_weight_tolerance_is_inclusive_of_its_limit() {
+        let conn = shipment();
+        // 100 x 0.5 kg = 50 kg expected; the default tolerance is 5%.
+        let at_limit = import(&conn, "P-1001\t100\t1\t52.5\t55\n");
+        assert_eq!(at_limit.lines[0].expected_net_weight_kg, Some(50.0));
+        assert!(at_limit.lines[0].flags.is_empty());
+        let over = import(&conn, "P-1001\t100\t1\t52.6\t55\n");
+        assert_eq!(over.lines[0].flags, [FLAG_NET_WEIGHT_VARIANCE]);
+
+        conn.execute(
+            "INSERT INTO app_metadata (key, value) VALUES (?1, '10')
+             ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+            params![WEIGHT_TOLERANCE_KEY],
+        )
+        .unwrap();
+        let wider = import(&conn, "P-1001\t100\t1\t55\t58\n");
+        assert!(wider.lines[0].flags.is_empty());
+    }
+
+    #[test]
+    fn unmatched_rows_short_quantities_and_missing_invoice_lines_are_reported() {
+        let conn = shipment();
+        let list = import(&conn, "P-1001\t90\t1\t45\t47\nX-9\t5\t1\t1\t1.2\n");
+        assert_eq!(list.lines[0].flags, [FLAG_QUANTITY_MISMATCH]);
+        assert_eq!(list.lines[0].invoiced_quantity, Some(100.0));
+        // 90 x 0.5 kg: the weight check follows the packed quantity.
+        assert_eq!(list.lines[0].expected_net_weight_kg, Some(45.0));
+        assert_eq!(list.lines[1].flags, [FLAG_UNMATCHED]);
+        assert_eq!(list.lines[1].item_id, None);
+        assert!(list
+            .warnings
+            .iter()
+            .any(|w| w.contains("L-2") && w.contains("not on the packing list")));
+    }
+
+    #[test]
+    fn rows_without_master_weight_or_with_gross_below_net_are_flagged() {
+        let conn = shipment();
+        let list = import(&conn, "P-2002\t40\t1\t8\t7.5\n");
+        assert_eq!(
+            list.lines[0].flags,
+            [FLAG_NO_MASTER_WEIGHT, FLAG_GROSS_BELOW_NET]
+        );
+        assert_eq!(list.lines[0].expected_net_weight_kg, None);
+    }
+
+    #[test]
+    fn import_sets_the_shipment_gross_weight_and_fills_only_blank_item_weights() {
+        let conn = shipment();
+        let list = import(
+            &conn,
+            "P-1001\t100\t2\t56\t61\nP-2002\t40\t1\t8\t9\nX-9\t5\t1\t1\t1.2\n",
+        );
+        assert_eq!(list.total_packages, Some(4.0));
+        assert_eq!(list.total_gross_weight_kg, Some(71.2));
+        assert_eq!(list.previous_gross_weight_kg, Some(12.0));
+
+        let (gross, bolt_net, nut_net, nut_gross): (f64, f64, f64, f64) = conn
+            .query_row(
+                "SELECT s.gross_weight_kg, b.net_weight_kg, n.net_weight_kg, n.gross_weight_per_uom_kg
+                 FROM shipments s, items b, items n
+                 WHERE s.id = 'SHP-1' AND b.id = 'ITM-1' AND n.id = 'ITM-2'",
+                [],
+                |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?, r.get(3)?)),
+            )
+            .unwrap();
+        assert_eq!((gross, nut_net, nut_gross), (71.2, 0.2, 0.225));
+        // The bolt's master net weight is kept even though the list disagrees.
+        assert_eq!(bolt_net, 0.5);
+        // Nut net and gross, and the bolt's blank gross per unit.
+        assert_eq!(list.item_weights_filled, 3);
+    }
+
+    #[test]
+    fn declared_gross_weight_wins_over_rows_missing_a_weight() {
+        let conn = shipment();
+        let declared = import(
+            &conn,
+            "P-1001\t100\t2\t50\t55\nP-2002\t40\t1\t8\t-\nTotal\t140\t3\t58\t65\n",
+        );
+        assert_eq!(declared.total_gross_weight_kg, Some(65.0));
+        assert!(declared
+            .warnings
+            .iter()
+            .all(|w| !w.contains("gross weight")));
+
+        // Without a total row, a partial sum of the rows is no total at all.
+        let partial = import(&conn, "P-1001\t100\t2\t50\t55\nP-2002\t40\t1\t8\t-\n");
+        assert_eq!(partial.total_gross_weight_kg, None);
+        assert_eq!(partial.total_net_weight_kg, Some(58.0));
+        let gross: f64 = conn
+            .query_row(
+                "SELECT gross_weight_kg FROM shipments WHERE id = 'SHP-1'",
+                [],
+                |r| r.get(0),
+            )
+            .unwrap();
+        assert_eq!(gross, 65.0);
+    }
+}
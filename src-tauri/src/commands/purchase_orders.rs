@@ -0,0 +1,1250 @@
+//! Purchase orders: lines with delivery schedules, invoice line links, open quantities and `PO_VARIANCE` cases.
+
+use crate::commands::dashboard_cache;
+use crate::commands::exception_workflow;
+use crate::commands::tariff;
+use crate::commands::utils::generate_id;
+use crate::db::{DbState, NewInvoicePayload};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use tauri::State;
+
+pub const DRAFT: &str = "DRAFT";
+pub const OPEN: &str = "OPEN";
+pub const PARTIALLY_SHIPPED: &str = "PARTIALLY_SHIPPED";
+pub const SHIPPED: &str = "SHIPPED";
+pub const CLOSED: &str = "CLOSED";
+pub const CANCELLED: &str = "CANCELLED";
+
+pub const PO_VARIANCE: &str = "PO_VARIANCE";
+
+pub const PRICE_EXCEEDS: &str = "PRICE_EXCEEDS";
+pub const QUANTITY_EXCEEDS: &str = "QUANTITY_EXCEEDS";
+pub const CURRENCY_DIFFERS: &str = "CURRENCY_DIFFERS";
+
+const PRICE_TOLERANCE_KEY: &str = "po_price_tolerance_percent";
+const QUANTITY_TOLERANCE_KEY: &str = "po_quantity_tolerance_percent";
+const DEFAULT_PRICE_TOLERANCE: f64 = 2.0;
+const DEFAULT_QUANTITY_TOLERANCE: f64 = 0.0;
+
+const QTY_EPSILON: f64 = 1e-9;
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct PoScheduleEntry {
+    pub due_date: String,
+    pub quantity: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PurchaseOrderLine {
+    pub id: String,
+    pub line_no: i64,
+    pub item_id: String,
+    pub part_number: Option<String>,
+    pub quantity: f64,
+    pub unit_price: f64,
+    pub currency: String,
+    pub schedule: Vec<PoScheduleEntry>,
+    /// Quantity on invoice lines that reference this PO line.
+    pub invoiced_quantity: f64,
+    pub open_quantity: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PurchaseOrder {
+    pub id: String,
+    pub po_number: String,
+    pub supplier_id: String,
+    pub supplier_name: Option<String>,
+    pub po_date: String,
+    pub currency: String,
+    pub incoterm: Option<String>,
+    pub status: String,
+    pub notes: Option<String>,
+    pub created_by: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+    pub lines: Vec<PurchaseOrderLine>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SavePurchaseOrderLine {
+    /// Existing line id; omit for a new line.
+    pub id: Option<String>,
+    pub item_id: String,
+    pub quantity: f64,
+    pub unit_price: f64,
+    /// Defaults to the PO currency.
+    pub currency: Option<String>,
+    #[serde(default)]
+    pub schedule: Vec<PoScheduleEntry>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SavePurchaseOrderPayload {
+    /// Omit to create a new (draft) PO.
+    pub id: Option<String>,
+    pub po_number: String,
+    pub supplier_id: String,
+    pub po_date: String,
+    pub currency: String,
+    pub incoterm: Option<String>,
+    pub notes: Option<String>,
+    pub user_id: Option<String>,
+    pub lines: Vec<SavePurchaseOrderLine>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct PoTolerances {
+    /// Invoice unit price may exceed the PO price by this much (%).
+    pub price_percent: f64,
+    /// Invoiced quantity may exceed the ordered quantity by this much (%).
+    pub quantity_percent: f64,
+}
+
+/// An invoice line priced or invoiced beyond its PO line's tolerance.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct PoVariance {
+    pub shipment_id: String,
+    pub invoice_line_id: String,
+    pub po_id: String,
+    pub po_number: String,
+    pub po_line_id: String,
+    pub item_id: String,
+    /// `PRICE_EXCEEDS`, `QUANTITY_EXCEEDS` or `CURRENCY_DIFFERS`.
+    pub kind: String,
+    /// PO unit price, ordered quantity or (for currency) 0.
+    pub po_value: f64,
+    /// Invoice unit price, total invoiced quantity across shipments, or 0.
+    pub invoice_value: f64,
+    pub variance_percent: Option<f64>,
+    pub po_currency: String,
+    pub invoice_currency: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct UnshippedPoLine {
+    pub po_id: String,
+    pub po_number: String,
+    pub po_date: String,
+    pub status: String,
+    pub supplier_id: String,
+    pub supplier_name: Option<String>,
+    pub po_line_id: String,
+    pub line_no: i64,
+    pub item_id: String,
+    pub part_number: Option<String>,
+    pub ordered_quantity: f64,
+    pub invoiced_quantity: f64,
+    pub open_quantity: f64,
+    pub unit_price: f64,
+    pub currency: String,
+    pub open_value: f64,
+    /// Earliest scheduled date whose cumulative quantity is not yet invoiced.
+    pub next_due_date: Option<String>,
+    /// Scheduled on or before today but not yet invoiced.
+    pub overdue_quantity: f64,
+}
+
+fn meta_percent(conn: &Connection, key: &str, default: f64) -> f64 {
+    conn.query_row(
+        "SELECT value FROM app_metadata WHERE key = ?1",
+        params![key],
+        |r| r.get::<_, String>(0),
+    )
+    .ok()
+    .and_then(|v| v.trim().parse::<f64>().ok())
+    .filter(|v| v.is_finite() && *v >= 0.0)
+    .unwrap_or(default)
+}
+
+pub fn load_tolerances(conn: &Connection) -> PoTolerances {
+    PoTolerances {
+        price_percent: meta_percent(conn, PRICE_TOLERANCE_KEY, DEFAULT_PRICE_TOLERANCE),
+        quantity_percent: meta_percent(conn, QUANTITY_TOLERANCE_KEY, DEFAULT_QUANTITY_TOLERANCE),
+    }
+}
+
+fn load_schedule(conn: &Connection, po_line_id: &str) -> rusqlite::Result<Vec<PoScheduleEntry>> {
+    let mut stmt = conn.prepare(
+        "SELECT due_date, quantity FROM purchase_order_schedules WHERE po_line_id = ?1 ORDER BY due_date, id",
+    )?;
+    let rows = stmt.query_map(params![po_line_id], |r| {
+        Ok(PoScheduleEntry {
+            due_date: r.get(0)?,
+            quantity: r.get(1)?,
+        })
+    })?;
+    rows.collect()
+}
+
+fn load_lines(conn: &Connection, po_id: &str) -> rusqlite::Result<Vec<PurchaseOrderLine>> {
+    let mut stmt = conn.prepare(
+        "SELECT pol.id, pol.line_no, pol.item_id, i.part_number, pol.quantity, pol.unit_price, pol.currency,
+                COALESCE((SELECT SUM(ili.quantity) FROM invoice_line_items ili WHERE ili.po_line_id = pol.id), 0)
+         FROM purchase_order_lines pol
+         LEFT JOIN items i ON i.id = pol.item_id
+         WHERE pol.po_id = ?1
+         ORDER BY pol.line_no",
+    )?;
+    let mut lines = stmt
+        .query_map(params![po_id], |r| {
+            let quantity: f64 = r.get(4)?;
+            let invoiced_quantity: f64 = r.get(7)?;
+            Ok(PurchaseOrderLine {
+                id: r.get(0)?,
+                line_no: r.get(1)?,
+                item_id: r.get(2)?,
+                part_number: r.get(3)?,
+                quantity,
+                unit_price: r.get(5)?,
+                currency: r.get(6)?,
+                schedule: Vec::new(),
+                invoiced_quantity,
+                open_quantity: (quantity - invoiced_quantity).max(0.0),
+            })
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    for line in lines.iter_mut() {
+        line.schedule = load_schedule(conn, &line.id)?;
+    }
+    Ok(lines)
+}
+
+const PO_COLUMNS: &str = "po.id, po.po_number, po.supplier_id, s.supplier_name, po.po_date, po.currency, po.incoterm, po.status, po.notes, po.created_by, po.created_at, po.updated_at";
+
+fn map_po(r: &rusqlite::Row) -> rusqlite::Result<PurchaseOrder> {
+    Ok(PurchaseOrder {
+        id: r.get(0)?,
+        po_number: r.get(1)?,
+        supplier_id: r.get(2)?,
+        supplier_name: r.get(3)?,
+        po_date: r.get(4)?,
+        currency: r.get(5)?,
+        incoterm: r.get(6)?,
+        status: r.get(7)?,
+        notes: r.get(8)?,
+        created_by: r.get(9)?,
+        created_at: r.get(10)?,
+        updated_at: r.get(11)?,
+        lines: Vec::new(),
+    })
+}
+
+pub fn load_purchase_order(conn: &Connection, id: &str) -> Result<Option<PurchaseOrder>, String> {
+    let po = conn
+        .query_row(
+            &format!(
+                "SELECT {PO_COLUMNS} FROM purchase_orders po LEFT JOIN suppliers s ON s.id = po.supplier_id WHERE po.id = ?1"
+            ),
+            params![id],
+            map_po,
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+    let Some(mut po) = po else {
+        return Ok(None);
+    };
+    po.lines = load_lines(conn, id).map_err(|e| e.to_string())?;
+    Ok(Some(po))
+}
+
+fn validate_payload(payload: &mut SavePurchaseOrderPayload) -> Result<(), String> {
+    payload.po_number = payload.po_number.trim().to_string();
+    if payload.po_number.is_empty() {
+        return Err("PO number is required".to_string());
+    }
+    payload.po_date = tariff::normalize_date(&payload.po_date)
+        .ok_or_else(|| format!("Invalid PO date '{}'", payload.po_date))?;
+    payload.currency = payload.currency.trim().to_uppercase();
+    if payload.currency.is_empty() {
+        return Err("PO currency is required".to_string());
+    }
+    if payload.lines.is_empty() {
+        return Err("A purchase order needs at least one line".to_string());
+    }
+    for (i, line) in payload.lines.iter_mut().enumerate() {
+        let n = i + 1;
+        if !line.quantity.is_finite() || line.quantity <= 0.0 {
+            return Err(format!("Line {n}: quantity must be positive"));
+        }
+        if line.unit_price < 0.0 || !line.unit_price.is_finite() {
+            return Err(format!("Line {n}: unit price cannot be negative"));
+        }
+        let mut scheduled = 0.0;
+        for entry in line.schedule.iter_mut() {
+            entry.due_date = tariff::normalize_date(&entry.due_date)
+                .ok_or_else(|| format!("Line {n}: invalid due date '{}'", entry.due_date))?;
+            if !entry.quantity.is_finite() || entry.quantity <= 0.0 {
+                return Err(format!("Line {n}: scheduled quantities must be positive"));
+            }
+            scheduled += entry.quantity;
+        }
+        if scheduled > line.quantity + QTY_EPSILON {
+            return Err(format!(
+                "Line {n}: schedule ({scheduled}) exceeds the ordered quantity ({})",
+                line.quantity
+            ));
+        }
+        line.currency = Some(
+            line.currency
+                .as_deref()
+                .map(|c| c.trim().to_uppercase())
+                .filter(|c| !c.is_empty())
+                .unwrap_or_else(|| payload.currency.clone()),
+        );
+    }
+    Ok(())
+}
+
+/// Creates a draft PO or replaces an existing one's header and lines. Lines already referenced by
+/// invoice lines cannot be removed.
+pub fn upsert_purchase_order(
+    conn: &Connection,
+    mut payload: SavePurchaseOrderPayload,
+) -> Result<PurchaseOrder, String> {
+    validate_payload(&mut payload)?;
+    let existing_status: Option<String> = match payload.id.as_deref() {
+        Some(id) => Some(
+            conn.query_row(
+                "SELECT status FROM purchase_orders WHERE id = ?1",
+                params![id],
+                |r| r.get(0),
+            )
+            .optional()
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| format!("Purchase order {id} not found"))?,
+        ),
+        None => None,
+    };
+    if matches!(existing_status.as_deref(), Some(CLOSED) | Some(CANCELLED)) {
+        return Err("Closed or cancelled purchase orders cannot be edited".to_string());
+    }
+    let id = payload
+        .id
+        .clone()
+        .unwrap_or_else(|| generate_id(Some("PO".to_string())));
+
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    tx.execute(
+        "INSERT INTO purchase_orders (id, po_number, supplier_id, po_date, currency, incoterm, status, notes, created_by)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+         ON CONFLICT (id) DO UPDATE SET
+            po_number = excluded.po_number,
+            supplier_id = excluded.supplier_id,
+            po_date = excluded.po_date,
+            currency = excluded.currency,
+            incoterm = excluded.incoterm,
+            notes = excluded.notes,
+            updated_at = strftime('%Y-%m-%d %H:%M:%S', 'now', 'localtime')",
+        params![
+            id,
+            payload.po_number,
+            payload.supplier_id,
+            payload.po_date,
+            payload.currency,
+            payload.incoterm,
+            DRAFT,
+            payload.notes,
+            payload.user_id
+        ],
+    )
+    .map_err(|e| match e {
+        rusqlite::Error::SqliteFailure(ref f, _)
+            if f.code == rusqlite::ErrorCode::ConstraintViolation =>
+        {
+            format!(
+                "PO number {} already exists or the supplier is unknown",
+                payload.po_number
+            )
+        }
+        e => e.to_string(),
+    })?;
+
+    let kept: BTreeSet<&str> = payload
+        .lines
+        .iter()
+        .filter_map(|l| l.id.as_deref())
+        .collect();
+    let mut stmt = tx
+        .prepare(
+            "SELECT pol.id, EXISTS (SELECT 1 FROM invoice_line_items ili WHERE ili.po_line_id = pol.id)
+             FROM purchase_order_lines pol WHERE pol.po_id = ?1",
+        )
+        .map_err(|e| e.to_string())?;
+    let current: Vec<(String, bool)> = stmt
+        .query_map(params![id], |r| Ok((r.get(0)?, r.get(1)?)))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())?;
+    drop(stmt);
+    for (line_id, referenced) in &current {
+        if kept.contains(line_id.as_str()) {
+            continue;
+        }
+        if *referenced {
+            return Err(format!(
+                "PO line {line_id} is referenced by invoice lines and cannot be removed"
+            ));
+        }
+        tx.execute(
+            "DELETE FROM purchase_order_lines WHERE id = ?1",
+            params![line_id],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    // Renumber through a negative range first so the (po_id, line_no) key never collides mid-update.
+    tx.execute(
+        "UPDATE purchase_order_lines SET line_no = -line_no WHERE po_id = ?1",
+        params![id],
+    )
+    .map_err(|e| e.to_string())?;
+    for (i, line) in payload.lines.iter().enumerate() {
+        let line_id = match line.id.as_deref() {
+            Some(line_id) if current.iter().any(|(c, _)| c == line_id) => line_id.to_string(),
+            Some(line_id) => return Err(format!("PO line {line_id} does not belong to this PO")),
+            None => generate_id(Some("POL".to_string())),
+        };
+        tx.execute(
+            "INSERT INTO purchase_order_lines (id, po_id, line_no, item_id, quantity, unit_price, currency)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+             ON CONFLICT (id) DO UPDATE SET
+                line_no = excluded.line_no,
+                item_id = excluded.item_id,
+                quantity = excluded.quantity,
+                unit_price = excluded.unit_price,
+                currency = excluded.currency",
+            params![
+                line_id,
+                id,
+                (i + 1) as i64,
+                line.item_id,
+                line.quantity,
+                line.unit_price,
+                line.currency
+            ],
+        )
+        .map_err(|e| e.to_string())?;
+        tx.execute(
+            "DELETE FROM purchase_order_schedules WHERE po_line_id = ?1",
+            params![line_id],
+        )
+        .map_err(|e| e.to_string())?;
+        for entry in &line.schedule {
+            tx.execute(
+                "INSERT INTO purchase_order_schedules (po_line_id, due_date, quantity) VALUES (?1, ?2, ?3)",
+                params![line_id, entry.due_date, entry.quantity],
+            )
+            .map_err(|e| e.to_string())?;
+        }
+    }
+    tx.commit().map_err(|e| e.to_string())?;
+    refresh_status(conn, &id)?;
+    load_purchase_order(conn, &id)?.ok_or_else(|| format!("Purchase order {id} not found"))
+}
+
+/// Moves an issued PO between OPEN, PARTIALLY_SHIPPED and SHIPPED from its invoiced quantities.
+/// Draft, closed and cancelled POs are left alone.
+pub fn refresh_status(conn: &Connection, po_id: &str) -> Result<(), String> {
+    let status: Option<String> = conn
+        .query_row(
+            "SELECT status FROM purchase_orders WHERE id = ?1",
+            params![po_id],
+            |r| r.get(0),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+    if !matches!(
+        status.as_deref(),
+        Some(OPEN) | Some(PARTIALLY_SHIPPED) | Some(SHIPPED)
+    ) {
+        return Ok(());
+    }
+    let lines = load_lines(conn, po_id).map_err(|e| e.to_string())?;
+    let derived = if lines.iter().all(|l| l.invoiced_quantity <= QTY_EPSILON) {
+        OPEN
+    } else if lines.iter().all(|l| l.open_quantity <= QTY_EPSILON) {
+        SHIPPED
+    } else {
+        PARTIALLY_SHIPPED
+    };
+    if status.as_deref() != Some(derived) {
+        conn.execute(
+            "UPDATE purchase_orders SET status = ?2, updated_at = strftime('%Y-%m-%d %H:%M:%S', 'now', 'localtime') WHERE id = ?1",
+            params![po_id, derived],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+fn linked_po_ids(conn: &Connection, filter: &str, id: &str) -> Result<Vec<String>, String> {
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT DISTINCT pol.po_id
+             FROM invoice_line_items ili
+             JOIN invoices inv ON inv.id = ili.invoice_id
+             JOIN purchase_order_lines pol ON pol.id = ili.po_line_id
+             WHERE {filter} = ?1"
+        ))
+        .map_err(|e| e.to_string())?;
+    let ids = stmt
+        .query_map(params![id], |r| r.get::<_, String>(0))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    Ok(ids)
+}
+
+/// POs the invoice's lines are raised against. Read before an invoice is rewritten or deleted,
+/// so the POs it stops drawing on can be refreshed afterwards.
+pub fn invoice_po_ids(conn: &Connection, invoice_id: &str) -> Vec<String> {
+    linked_po_ids(conn, "inv.id", invoice_id).unwrap_or_else(|e| {
+        log::warn!("PO links of invoice {invoice_id} could not be read: {e}");
+        Vec::new()
+    })
+}
+
+/// Manual status change: issue a draft, short-close an issued PO, or cancel one nothing was invoiced against.
+pub fn set_status(conn: &Connection, po_id: &str, status: &str) -> Result<(), String> {
+    let status = status.trim().to_uppercase();
+    let po = load_purchase_order(conn, po_id)?
+        .ok_or_else(|| format!("Purchase order {po_id} not found"))?;
+    let invoiced = po.lines.iter().any(|l| l.invoiced_quantity > QTY_EPSILON);
+    let allowed = match (po.status.as_str(), status.as_str()) {
+        (DRAFT, OPEN) => true,
+        (OPEN | PARTIALLY_SHIPPED | SHIPPED, CLOSED) => true,
+        (CLOSED, OPEN) => true,
+        (DRAFT | OPEN, CANCELLED) => !invoiced,
+        _ => false,
+    };
+    if !allowed {
+        return Err(format!(
+            "Purchase order {} cannot move from {} to {status}",
+            po.po_number, po.status
+        ));
+    }
+    conn.execute(
+        "UPDATE purchase_orders SET status = ?2, updated_at = strftime('%Y-%m-%d %H:%M:%S', 'now', 'localtime') WHERE id = ?1",
+        params![po_id, status],
+    )
+    .map_err(|e| e.to_string())?;
+    refresh_status(conn, po_id)
+}
+
+/// Checks the PO line references on an invoice before it is saved: the line must exist on an
+/// issued PO of the shipment's supplier, for the same item.
+pub fn validate_invoice_links(
+    conn: &Connection,
+    payload: &NewInvoicePayload,
+) -> Result<(), String> {
+    let shipment_supplier: Option<String> = conn
+        .query_row(
+            "SELECT supplier_id FROM shipments WHERE id = ?1",
+            params![payload.shipment_id],
+            |r| r.get(0),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+    for (i, line) in payload.line_items.iter().enumerate() {
+        let Some(po_line_id) = line.po_line_id.as_deref().filter(|s| !s.trim().is_empty()) else {
+            continue;
+        };
+        let n = i + 1;
+        let row: Option<(String, String, String, String)> = conn
+            .query_row(
+                "SELECT po.po_number, po.supplier_id, po.status, pol.item_id
+                 FROM purchase_order_lines pol JOIN purchase_orders po ON po.id = pol.po_id
+                 WHERE pol.id = ?1",
+                params![po_line_id],
+                |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?, r.get(3)?)),
+            )
+            .optional()
+            .map_err(|e| e.to_string())?;
+        let Some((po_number, supplier_id, status, item_id)) = row else {
+            return Err(format!("Line {n}: PO line {po_line_id} not found"));
+        };
+        if !matches!(status.as_str(), OPEN | PARTIALLY_SHIPPED | SHIPPED) {
+            return Err(format!(
+                "Line {n}: purchase order {po_number} is {status} and cannot be invoiced against"
+            ));
+        }
+        if shipment_supplier.as_deref() != Some(supplier_id.as_str()) {
+            return Err(format!(
+                "Line {n}: purchase order {po_number} belongs to a different supplier than the shipment"
+            ));
+        }
+        if item_id != line.item_id {
+            return Err(format!(
+                "Line {n}: the referenced line of purchase order {po_number} is for a different item"
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn variance_pct(po: f64, invoice: f64) -> Option<f64> {
+    (po.abs() > f64::EPSILON).then(|| ((invoice - po) / po * 10000.0).round() / 100.0)
+}
+
+/// Invoice lines of the shipment that exceed their PO line's price or quantity tolerance.
+pub fn check_shipment(conn: &Connection, shipment_id: &str) -> Result<Vec<PoVariance>, String> {
+    let tolerances = load_tolerances(conn);
+    let mut stmt = conn
+        .prepare(
+            "SELECT ili.id, ili.unit_price, s.invoice_currency,
+                    pol.id, pol.item_id, pol.quantity, pol.unit_price, pol.currency, po.id, po.po_number,
+                    (SELECT SUM(x.quantity) FROM invoice_line_items x WHERE x.po_line_id = pol.id)
+             FROM invoice_line_items ili
+             JOIN invoices inv ON inv.id = ili.invoice_id
+             JOIN shipments s ON s.id = inv.shipment_id
+             JOIN purchase_order_lines pol ON pol.id = ili.po_line_id
+             JOIN purchase_orders po ON po.id = pol.po_id
+             WHERE inv.shipment_id = ?1
+             ORDER BY po.po_number, pol.line_no, ili.id",
+        )
+        .map_err(|e| e.to_string())?;
+    #[allow(clippy::type_complexity)]
+    let rows: Vec<(
+        String,
+        f64,
+        String,
+        String,
+        String,
+        f64,
+        f64,
+        String,
+        String,
+        String,
+        f64,
+    )> = stmt
+        .query_map(params![shipment_id], |r| {
+            Ok((
+                r.get(0)?,
+                r.get(1)?,
+                r.get(2)?,
+                r.get(3)?,
+                r.get(4)?,
+                r.get(5)?,
+                r.get(6)?,
+                r.get(7)?,
+                r.get(8)?,
+                r.get(9)?,
+                r.get(10)?,
+            ))
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())?;
+
+    let mut out = Vec::new();
+    let mut qty_flagged: BTreeSet<String> = BTreeSet::new();
+    for (
+        line_id,
+        invoice_price,
+        invoice_currency,
+        po_line_id,
+        item_id,
+        ordered,
+        po_price,
+        po_currency,
+        po_id,
+        po_number,
+        invoiced_total,
+    ) in rows
+    {
+        let variance = |kind: &str, po_value: f64, invoice_value: f64| PoVariance {
+            shipment_id: shipment_id.to_string(),
+            invoice_line_id: line_id.clone(),
+            po_id: po_id.clone(),
+            po_number: po_number.clone(),
+            po_line_id: po_line_id.clone(),
+            item_id: item_id.clone(),
+            kind: kind.to_string(),
+            po_value,
+            invoice_value,
+            variance_percent: variance_pct(po_value, invoice_value),
+            po_currency: po_currency.clone(),
+            invoice_currency: invoice_currency.clone(),
+        };
+        if !invoice_currency.eq_ignore_ascii_case(&po_currency) {
+            out.push(variance(CURRENCY_DIFFERS, 0.0, 0.0));
+        } else if invoice_price > po_price * (1.0 + tolerances.price_percent / 100.0) + QTY_EPSILON
+        {
+            out.push(variance(PRICE_EXCEEDS, po_price, invoice_price));
+        }
+        if invoiced_total > ordered * (1.0 + tolerances.quantity_percent / 100.0) + QTY_EPSILON
+            && qty_flagged.insert(po_line_id.clone())
+        {
+            out.push(variance(QUANTITY_EXCEEDS, ordered, invoiced_total));
+        }
+    }
+    Ok(out)
+}
+
+/// Opens a `PO_VARIANCE` case when the shipment has variances and resolves it once they clear.
+pub fn sync_variance_case(
+    conn: &Connection,
+    shipment_id: &str,
+    variances: &[PoVariance],
+) -> Result<Option<String>, String> {
+    if variances.is_empty() {
+        exception_workflow::auto_resolve_case_for_entity(conn, PO_VARIANCE, shipment_id)?;
+        return Ok(None);
+    }
+    let po_numbers: BTreeSet<&str> = variances.iter().map(|v| v.po_number.as_str()).collect();
+    let quantity = variances.iter().any(|v| v.kind == QUANTITY_EXCEEDS);
+    let details = serde_json::json!({
+        "exceptionType": PO_VARIANCE,
+        "variances": variances.len(),
+        "purchaseOrders": po_numbers,
+        "quantityExceeded": quantity,
+    });
+    exception_workflow::open_case_for_entity(
+        conn,
+        PO_VARIANCE,
+        shipment_id,
+        if quantity { "HIGH" } else { "MEDIUM" },
+        &details.to_string(),
+    )
+}
+
+/// Re-checks a shipment against its POs, brings its case in line and refreshes the statuses of
+/// the POs its invoice lines draw on, plus `unlinked_po_ids` that a line was just taken off.
+pub fn review_shipment(
+    conn: &Connection,
+    shipment_id: &str,
+    unlinked_po_ids: &[String],
+) -> Result<Vec<PoVariance>, String> {
+    let mut po_ids = linked_po_ids(conn, "inv.shipment_id", shipment_id)?;
+    po_ids.extend_from_slice(unlinked_po_ids);
+    po_ids.sort();
+    po_ids.dedup();
+    for po_id in &po_ids {
+        refresh_status(conn, po_id)?;
+    }
+    let variances = check_shipment(conn, shipment_id)?;
+    sync_variance_case(conn, shipment_id, &variances)?;
+    Ok(variances)
+}
+
+/// [`review_shipment`] for invoice saves that already succeeded; failures are only logged.
+pub fn review_shipment_after_save(
+    conn: &Connection,
+    shipment_id: &str,
+    unlinked_po_ids: &[String],
+) {
+    if let Err(e) = review_shipment(conn, shipment_id, unlinked_po_ids) {
+        log::warn!("PO variance check for shipment {shipment_id} failed: {e}");
+    }
+}
+
+/// Open PO lines of issued purchase orders, oldest PO first.
+pub fn unshipped_lines(
+    conn: &Connection,
+    supplier_id: Option<&str>,
+    today: &str,
+) -> Result<Vec<UnshippedPoLine>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT po.id, po.po_number, po.po_date, po.status, po.supplier_id, s.supplier_name,
+                    pol.id, pol.line_no, pol.item_id, i.part_number, pol.quantity, pol.unit_price, pol.currency,
+                    COALESCE((SELECT SUM(ili.quantity) FROM invoice_line_items ili WHERE ili.po_line_id = pol.id), 0)
+             FROM purchase_orders po
+             JOIN purchase_order_lines pol ON pol.po_id = po.id
+             LEFT JOIN suppliers s ON s.id = po.supplier_id
+             LEFT JOIN items i ON i.id = pol.item_id
+             WHERE po.status IN (?1, ?2)
+               AND (?3 IS NULL OR po.supplier_id = ?3)
+             ORDER BY po.po_date, po.po_number, pol.line_no",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![OPEN, PARTIALLY_SHIPPED, supplier_id], |r| {
+            let ordered_quantity: f64 = r.get(10)?;
+            let unit_price: f64 = r.get(11)?;
+            let invoiced_quantity: f64 = r.get(13)?;
+            let open_quantity = (ordered_quantity - invoiced_quantity).max(0.0);
+            Ok(UnshippedPoLine {
+                po_id: r.get(0)?,
+                po_number: r.get(1)?,
+                po_date: r.get(2)?,
+                status: r.get(3)?,
+                supplier_id: r.get(4)?,
+                supplier_name: r.get(5)?,
+                po_line_id: r.get(6)?,
+                line_no: r.get(7)?,
+                item_id: r.get(8)?,
+                part_number: r.get(9)?,
+                ordered_quantity,
+                invoiced_quantity,
+                open_quantity,
+                unit_price,
+                currency: r.get(12)?,
+                open_value: ((open_quantity * unit_price) * 100.0).round() / 100.0,
+                next_due_date: None,
+                overdue_quantity: 0.0,
+            })
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    let mut out = Vec::new();
+    for mut line in rows.into_iter().filter(|l| l.open_quantity > QTY_EPSILON) {
+        // Invoiced quantity is applied to the schedule in date order.
+        let mut covered = line.invoiced_quantity;
+        for entry in load_schedule(conn, &line.po_line_id).map_err(|e| e.to_string())? {
+            let uncovered = (entry.quantity - covered).max(0.0);
+            covered = (covered - entry.quantity).max(0.0);
+            if uncovered <= QTY_EPSILON {
+                continue;
+            }
+            if line.next_due_date.is_none() {
+                line.next_due_date = Some(entry.due_date.clone());
+            }
+            if entry.due_date.as_str() <= today {
+                line.overdue_quantity += uncovered;
+            }
+        }
+        out.push(line);
+    }
+    Ok(out)
+}
+
+#[tauri::command]
+pub fn list_purchase_orders(
+    supplier_id: Option<String>,
+    status: Option<String>,
+    state: State<DbState>,
+) -> Result<Vec<PurchaseOrder>, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {PO_COLUMNS} FROM purchase_orders po LEFT JOIN suppliers s ON s.id = po.supplier_id
+             WHERE (?1 IS NULL OR po.supplier_id = ?1) AND (?2 IS NULL OR po.status = ?2)
+             ORDER BY po.po_date DESC, po.po_number"
+        ))
+        .map_err(|e| e.to_string())?;
+    let mut pos = stmt
+        .query_map(
+            params![supplier_id, status.map(|s| s.trim().to_uppercase())],
+            map_po,
+        )
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    for po in pos.iter_mut() {
+        po.lines = load_lines(&conn, &po.id).map_err(|e| e.to_string())?;
+    }
+    Ok(pos)
+}
+
+#[tauri::command]
+pub fn get_purchase_order(id: String, state: State<DbState>) -> Result<PurchaseOrder, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    load_purchase_order(&conn, &id)?.ok_or_else(|| format!("Purchase order {id} not found"))
+}
+
+#[tauri::command]
+pub fn save_purchase_order(
+    payload: SavePurchaseOrderPayload,
+    state: State<DbState>,
+) -> Result<PurchaseOrder, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    let po = upsert_purchase_order(&conn, payload)?;
+    let _ = dashboard_cache::invalidate_dashboard_metrics_cache(&conn);
+    Ok(po)
+}
+
+#[tauri::command]
+pub fn set_purchase_order_status(
+    id: String,
+    status: String,
+    state: State<DbState>,
+) -> Result<PurchaseOrder, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    set_status(&conn, &id, &status)?;
+    let _ = dashboard_cache::invalidate_dashboard_metrics_cache(&conn);
+    load_purchase_order(&conn, &id)?.ok_or_else(|| format!("Purchase order {id} not found"))
+}
+
+/// PO lines an invoice on this shipment can reference: issued POs of the shipment's supplier.
+#[tauri::command]
+pub fn get_open_po_lines_for_shipment(
+    shipment_id: String,
+    state: State<DbState>,
+) -> Result<Vec<UnshippedPoLine>, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    let supplier_id: String = conn
+        .query_row(
+            "SELECT supplier_id FROM shipments WHERE id = ?1",
+            params![shipment_id],
+            |r| r.get(0),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Shipment {shipment_id} not found"))?;
+    unshipped_lines(&conn, Some(&supplier_id), &tariff::today())
+}
+
+/// Report of PO lines not yet (fully) shipped.
+#[tauri::command]
+pub fn get_unshipped_po_report(
+    supplier_id: Option<String>,
+    state: State<DbState>,
+) -> Result<Vec<UnshippedPoLine>, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    unshipped_lines(&conn, supplier_id.as_deref(), &tariff::today())
+}
+
+#[tauri::command]
+pub fn get_shipment_po_variances(
+    shipment_id: String,
+    state: State<DbState>,
+) -> Result<Vec<PoVariance>, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    check_shipment(&conn, &shipment_id)
+}
+
+#[tauri::command]
+pub fn get_po_tolerances(state: State<DbState>) -> Result<PoTolerances, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    Ok(load_tolerances(&conn))
+}
+
+/// Saves the tolerances and re-checks every shipment with PO-linked invoice lines.
+#[tauri::command]
+pub fn save_po_tolerances(
+    tolerances: PoTolerances,
+    state: State<DbState>,
+) -> Result<PoTolerances, String> {
+    for v in [tolerances.price_percent, tolerances.quantity_percent] {
+        if !v.is_finite() || v < 0.0 {
+            return Err("Tolerances must be zero or positive percentages".to_string());
+        }
+    }
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    for (key, value) in [
+        (PRICE_TOLERANCE_KEY, tolerances.price_percent),
+        (QUANTITY_TOLERANCE_KEY, tolerances.quantity_percent),
+    ] {
+        conn.execute(
+            "INSERT INTO app_metadata (key, value) VALUES (?1, ?2)
+             ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+            params![key, value.to_string()],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    let mut stmt = conn
+        .prepare(
+            "SELECT DISTINCT inv.shipment_id FROM invoice_line_items ili
+             JOIN invoices inv ON inv.id = ili.invoice_id
+             WHERE ili.po_line_id IS NOT NULL",
+        )
+        .map_err(|e| e.to_string())?;
+    let shipment_ids = stmt
+        .query_map([], |r| r.get::<_, String>(0))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    for id in shipment_ids {
+        review_shipment_after_save(&conn, &id, &[]);
+    }
+    let _ = dashboard_cache::invalidate_dashboard_metrics_cache(&conn);
+    Ok(load_tolerances(&conn))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::invoices::execute_add_invoice;
+    use crate::commands::test_support;
+    use crate::db::NewInvoiceLineItemPayload;
+
+    fn invoice(
+        shipment_id: &str,
+        po_line_id: &str,
+        quantity: f64,
+        unit_price: f64,
+    ) -> NewInvoicePayload {
+        NewInvoicePayload {
+            shipment_id: shipment_id.into(),
+            status: "Draft".into(),
+            line_items: vec![NewInvoiceLineItemPayload {
+                item_id: "ITEM-1".into(),
+                quantity,
+                unit_price,
+                duty_percent: Some(10.0),
+                sws_percent: Some(10.0),
+                igst_percent: Some(18.0),
+                po_line_id: Some(po_line_id.into()),
+            }],
+        }
+    }
+
+    /// Saves an invoice the way the add-invoice command does, review included.
+    fn add_invoice(
+        conn: &Connection,
+        shipment_id: &str,
+        po_line_id: &str,
+        quantity: f64,
+        unit_price: f64,
+    ) {
+        let payload = invoice(shipment_id, po_line_id, quantity, unit_price);
+        validate_invoice_links(conn, &payload).unwrap();
+        let tx = conn.unchecked_transaction().unwrap();
+        execute_add_invoice(&tx, &payload).unwrap();
+        tx.commit().unwrap();
+        review_shipment_after_save(conn, shipment_id, &[]);
+    }
+
+    fn variance_kinds(conn: &Connection, shipment_id: &str) -> Vec<String> {
+        review_shipment(conn, shipment_id, &[])
+            .unwrap()
+            .into_iter()
+            .map(|v| v.kind)
+            .collect()
+    }
+
+    fn case_status(conn: &Connection, shipment_id: &str) -> Option<String> {
+        test_support::exception_case(conn, PO_VARIANCE, shipment_id).map(|c| c.0)
+    }
+
+    fn po_payload(currency: &str, schedule: Vec<PoScheduleEntry>) -> SavePurchaseOrderPayload {
+        SavePurchaseOrderPayload {
+            id: None,
+            po_number: "PO-100".into(),
+            supplier_id: "SUP-1".into(),
+            po_date: "01/02/2024".into(),
+            currency: currency.into(),
+            incoterm: Some("FOB".into()),
+            notes: None,
+            user_id: Some("buyer".into()),
+            lines: vec![SavePurchaseOrderLine {
+                id: None,
+                item_id: "ITEM-1".into(),
+                quantity: 100.0,
+                unit_price: 10.0,
+                currency: None,
+                schedule,
+            }],
+        }
+    }
+
+    fn due(due_date: &str, quantity: f64) -> PoScheduleEntry {
+        PoScheduleEntry {
+            due_date: due_date.into(),
+            quantity,
+        }
+    }
+
+    /// USD shipments SHP-1 and SHP-2 from Acme, SHP-3 from another supplier, and a draft PO for
+    /// 100 x ITEM-1 at 10, due 60 on 2024-03-01 and 40 on 2024-05-01. Returns the PO.
+    fn fixture(currency: &str) -> (Connection, PurchaseOrder) {
+        let conn = test_support::migrated_db();
+        test_support::add_supplier(&conn, "SUP-2", "Other");
+        test_support::add_item(&conn, "ITEM-1", "P-1", "Bearing", None);
+        test_support::add_shipment(&conn, "SHP-1", "2024-03-01", 600.0, "USD", "docs-rcvd");
+        test_support::add_shipment(&conn, "SHP-2", "2024-04-01", 550.0, "USD", "docs-rcvd");
+        test_support::add_shipment(&conn, "SHP-3", "2024-04-01", 10.0, "USD", "docs-rcvd");
+        conn.execute(
+            "UPDATE shipments SET supplier_id = 'SUP-2' WHERE id = 'SHP-3'",
+            [],
+        )
+        .unwrap();
+        let po = upsert_purchase_order(
+            &conn,
+            po_payload(
+                currency,
+                vec![due("2024-03-01", 60.0), due("2024-05-01", 40.0)],
+            ),
+        )
+        .unwrap();
+        (conn, po)
+    }
+
+    fn the_po(conn: &Connection) -> PurchaseOrder {
+        let id: String = conn
+            .query_row("SELECT id FROM purchase_orders", [], |r| r.get(0))
+            .unwrap();
+        load_purchase_order(conn, &id).unwrap().unwrap()
+    }
+
+    fn open_fixture() -> (Connection, String) {
+        let (conn, po) = fixture("usd");
+        set_status(&conn, &po.id, OPEN).unwrap();
+        (conn, po.lines[0].id.clone())
+    }
+
+    #[test]
+    fn new_pos_are_drafts_with_normalised_dates_and_schedules_within_the_order() {
+        let (conn, po) = fixture("usd");
+        assert_eq!(
+            (
+                po.status.as_str(),
+                po.po_date.as_str(),
+                po.currency.as_str()
+            ),
+            (DRAFT, "2024-02-01", "USD")
+        );
+        assert_eq!(po.lines[0].currency, "USD");
+
+        let over = po_payload(
+            "USD",
+            vec![due("2024-03-01", 60.0), due("2024-05-01", 41.0)],
+        );
+        assert!(upsert_purchase_order(&conn, over)
+            .unwrap_err()
+            .contains("exceeds the ordered quantity"));
+    }
+
+    #[test]
+    fn drafts_and_other_suppliers_shipments_cannot_be_invoiced_against() {
+        let (conn, po) = fixture("usd");
+        let line_id = po.lines[0].id.clone();
+        assert!(
+            validate_invoice_links(&conn, &invoice("SHP-1", &line_id, 60.0, 10.0))
+                .unwrap_err()
+                .contains("is DRAFT")
+        );
+        set_status(&conn, &po.id, OPEN).unwrap();
+        assert!(
+            validate_invoice_links(&conn, &invoice("SHP-3", &line_id, 1.0, 10.0))
+                .unwrap_err()
+                .contains("different supplier")
+        );
+        assert!(validate_invoice_links(&conn, &invoice("SHP-1", &line_id, 60.0, 10.0)).is_ok());
+    }
+
+    #[test]
+    fn invoices_draw_down_the_open_quantity_and_schedule() {
+        let (conn, line_id) = open_fixture();
+        add_invoice(&conn, "SHP-1", &line_id, 60.0, 10.1);
+        let po = the_po(&conn);
+        assert_eq!(po.status, PARTIALLY_SHIPPED);
+        assert_eq!(po.lines[0].open_quantity, 40.0);
+
+        let report = unshipped_lines(&conn, None, "2024-04-15").unwrap();
+        assert_eq!(report.len(), 1);
+        assert_eq!(report[0].next_due_date.as_deref(), Some("2024-05-01"));
+        assert_eq!(
+            (report[0].open_value, report[0].overdue_quantity),
+            (400.0, 0.0)
+        );
+        // Past the second due date the remaining 40 are overdue.
+        let later = unshipped_lines(&conn, None, "2024-05-02").unwrap();
+        assert_eq!(later[0].overdue_quantity, 40.0);
+
+        add_invoice(&conn, "SHP-2", &line_id, 40.0, 10.0);
+        assert_eq!(the_po(&conn).status, SHIPPED);
+        assert!(unshipped_lines(&conn, None, "2024-04-15")
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn deleting_an_invoice_refreshes_the_po_it_drew_on() {
+        let (conn, line_id) = open_fixture();
+        add_invoice(&conn, "SHP-1", &line_id, 60.0, 10.0);
+        assert_eq!(the_po(&conn).status, PARTIALLY_SHIPPED);
+
+        let invoice_id: String = conn
+            .query_row(
+                "SELECT id FROM invoices WHERE shipment_id = 'SHP-1'",
+                [],
+                |r| r.get(0),
+            )
+            .unwrap();
+        let unlinked = invoice_po_ids(&conn, &invoice_id);
+        conn.execute_batch(
+            "DELETE FROM invoice_line_items; DELETE FROM invoices WHERE shipment_id = 'SHP-1';",
+        )
+        .unwrap();
+        // The shipment no longer draws on the PO, so only the unlinked id reaches it.
+        review_shipment(&conn, "SHP-1", &[]).unwrap();
+        assert_eq!(the_po(&conn).status, PARTIALLY_SHIPPED);
+        review_shipment(&conn, "SHP-1", &unlinked).unwrap();
+        assert_eq!(the_po(&conn).status, OPEN);
+    }
+
+    #[test]
+    fn price_tolerance_is_inclusive_of_its_limit() {
+        let (conn, line_id) = open_fixture();
+        // Default tolerance is 2%: 10.20 passes, 10.21 does not.
+        add_invoice(&conn, "SHP-1", &line_id, 10.0, 10.2);
+        assert!(variance_kinds(&conn, "SHP-1").is_empty());
+        add_invoice(&conn, "SHP-2", &line_id, 10.0, 10.21);
+        assert_eq!(variance_kinds(&conn, "SHP-2"), vec![PRICE_EXCEEDS]);
+        assert_eq!(case_status(&conn, "SHP-2").as_deref(), Some("OPEN"));
+    }
+
+    #[test]
+    fn quantity_tolerance_applies_to_everything_invoiced_on_the_line() {
+        let (conn, line_id) = open_fixture();
+        conn.execute(
+            "INSERT INTO app_metadata (key, value) VALUES (?1, '5')
+             ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+            params![QUANTITY_TOLERANCE_KEY],
+        )
+        .unwrap();
+        add_invoice(&conn, "SHP-1", &line_id, 60.0, 10.0);
+        add_invoice(&conn, "SHP-2", &line_id, 45.0, 10.0);
+        // 105 of 100 ordered is exactly the 5% allowed.
+        assert!(variance_kinds(&conn, "SHP-2").is_empty());
+        conn.execute(
+            "UPDATE invoice_line_items SET quantity = 46 WHERE invoice_id IN (SELECT id FROM invoices WHERE shipment_id = 'SHP-2')",
+            [],
+        )
+        .unwrap();
+        // The overrun is reported once per PO line, on every shipment invoicing it.
+        assert_eq!(variance_kinds(&conn, "SHP-1"), vec![QUANTITY_EXCEEDS]);
+        assert_eq!(variance_kinds(&conn, "SHP-2"), vec![QUANTITY_EXCEEDS]);
+    }
+
+    #[test]
+    fn a_currency_mismatch_is_flagged_instead_of_comparing_prices() {
+        let (conn, po) = fixture("EUR");
+        set_status(&conn, &po.id, OPEN).unwrap();
+        add_invoice(&conn, "SHP-1", &po.lines[0].id, 10.0, 50.0);
+        let variances = review_shipment(&conn, "SHP-1", &[]).unwrap();
+        assert_eq!(variances.len(), 1);
+        assert_eq!(variances[0].kind, CURRENCY_DIFFERS);
+        assert_eq!(
+            (
+                variances[0].po_currency.as_str(),
+                variances[0].invoice_currency.as_str()
+            ),
+            ("EUR", "USD")
+        );
+    }
+
+    #[test]
+    fn correcting_the_invoice_resolves_the_variance_case() {
+        let (conn, line_id) = open_fixture();
+        add_invoice(&conn, "SHP-1", &line_id, 60.0, 10.0);
+        add_invoice(&conn, "SHP-2", &line_id, 50.0, 11.0);
+        assert_eq!(
+            variance_kinds(&conn, "SHP-2"),
+            vec![PRICE_EXCEEDS, QUANTITY_EXCEEDS]
+        );
+        assert_eq!(case_status(&conn, "SHP-2").as_deref(), Some("OPEN"));
+
+        conn.execute(
+            "UPDATE invoice_line_items SET quantity = 40, unit_price = 10 WHERE invoice_id IN (SELECT id FROM invoices WHERE shipment_id = 'SHP-2')",
+            [],
+        )
+        .unwrap();
+        assert!(variance_kinds(&conn, "SHP-2").is_empty());
+        assert_eq!(case_status(&conn, "SHP-2").as_deref(), Some("RESOLVED"));
+    }
+}
@@ -1,10 +1,13 @@
 #![allow(clippy::uninlined_format_args)]
+use crate::commands::exchange_rates;
+use crate::commands::tariff;
 use crate::db::DbState;
 use crate::expense::{
     ExpenseReportFilters, ExpenseReportResponse, ExpenseService, ExpenseSummaryByMonth,
     ExpenseSummaryByProvider, ExpenseSummaryByShipment, ExpenseSummaryByType,
 };
 
+use std::collections::HashMap;
 use tauri::State;
 
 #[derive(serde::Serialize, Debug)]
@@ -12,6 +15,11 @@ pub struct ReportRow {
     pub supplier: String,
     pub invoice_no: String,
     pub invoice_date: String,
+    pub currency: String,
+    /// Rate stored on the saved BOE calculation.
+    pub exchange_rate: Option<f64>,
+    /// CBIC-notified rate for the BE date; `None` when the currency has no notified rate.
+    pub notified_exchange_rate: Option<f64>,
     pub part_no: String,
     pub description: String,
     pub unit: String,
@@ -32,6 +40,8 @@ pub struct ReportTotals {
     pub bcd_amount: f64,
     pub sws_amount: f64,
     pub igst_amount: f64,
+    /// Drawback / RoDTEP credits debited against the BOE lines in the report.
+    pub duty_credit_amount: f64,
     pub expenses_total: f64,
 }
 
@@ -60,6 +70,7 @@ pub struct ReportResponse {
     pub page_size: u32,
     pub total_rows: u32,
     pub totals: Option<ReportTotals>,
+    pub warnings: Vec<String>,
 }
 
 #[allow(unused_assignments)]
@@ -184,7 +195,8 @@ pub fn get_report(filters: ReportFilters, state: State<DbState>) -> Result<Repor
             printf('%.2f', sws_amount) as sws_amount,
             printf('%.2f', igst_amount) as igst_amount,
             printf('%.2f', expenses_total) as expenses_total,
-            printf('%.2f', ldc_per_qty) as ldc_per_qty
+            printf('%.2f', ldc_per_qty) as ldc_per_qty,
+            shipment_id, boe_id, currency, exchange_rate
         FROM report_view{} ORDER BY {} {} LIMIT ?{} OFFSET ?{}",
         where_sql,
         sort_col,
@@ -219,19 +231,52 @@ pub fn get_report(filters: ReportFilters, state: State<DbState>) -> Result<Repor
                 igst_amount: row.get::<_, String>(11)?.parse::<f64>().unwrap_or(0.0),
                 expenses_total: row.get::<_, String>(12)?.parse::<f64>().unwrap_or(0.0),
                 ldc_per_qty: row.get::<_, String>(13)?.parse::<f64>().unwrap_or(0.0),
+                currency: row.get::<_, Option<String>>(16)?.unwrap_or_default(),
+                exchange_rate: row.get(17)?,
+                notified_exchange_rate: None,
             };
-            Ok(report_row)
+            let shipment_id: String = row.get(14)?;
+            let boe_id: Option<String> = row.get(15)?;
+            Ok((report_row, shipment_id, boe_id))
         })
         .map_err(|e| {
             log::warn!("report query_map failed: {e}");
             e.to_string()
         })?;
 
-    let rows = rows_iter.collect::<Result<Vec<_>, _>>().map_err(|e| {
+    let raw_rows = rows_iter.collect::<Result<Vec<_>, _>>().map_err(|e| {
         log::warn!("report row collect failed: {e}");
         e.to_string()
     })?;
 
+    // Attach the notified rate for each shipment's BE date and flag BOEs saved with another rate
+    let mut notified_by_boe: HashMap<(String, Option<String>), Option<f64>> = HashMap::new();
+    let mut warnings = Vec::new();
+    let mut rows = Vec::with_capacity(raw_rows.len());
+    for (mut row, shipment_id, boe_id) in raw_rows {
+        let key = (shipment_id, boe_id);
+        let notified = match notified_by_boe.get(&key) {
+            Some(rate) => *rate,
+            None => {
+                let be_date = tariff::be_date_for_boe(&conn, key.1.as_deref());
+                let rate = exchange_rates::resolve_for_shipment(&conn, &key.0, be_date.as_deref())?
+                    .map(|r| r.rate);
+                if let (Some(used), Some(notified)) = (row.exchange_rate, rate) {
+                    if exchange_rates::rates_differ(used, notified) {
+                        warnings.push(format!(
+                            "Invoice {}: BOE used exchange rate {:.4} but the notified {} rate is {:.4}",
+                            row.invoice_no, used, row.currency, notified
+                        ));
+                    }
+                }
+                notified_by_boe.insert(key, rate);
+                rate
+            }
+        };
+        row.notified_exchange_rate = notified;
+        rows.push(row);
+    }
+
     // Calculate totals if requested
     let mut totals = None;
     if filters.include_totals.unwrap_or(false) {
@@ -242,7 +287,16 @@ pub fn get_report(filters: ReportFilters, state: State<DbState>) -> Result<Repor
                 printf('%.2f', SUM(bcd_amount)) as total_bcd_amount,
                 printf('%.2f', SUM(sws_amount)) as total_sws_amount,
                 printf('%.2f', SUM(igst_amount)) as total_igst_amount,
-                printf('%.2f', SUM(expenses_total)) as total_expenses_total
+                printf('%.2f', SUM(expenses_total)) as total_expenses_total,
+                printf('%.2f', (
+                    SELECT COALESCE(SUM(u.amount), 0)
+                    FROM duty_credit_utilisations u
+                    JOIN boe_calculations bc ON bc.id = u.saved_boe_id
+                    WHERE EXISTS (
+                        SELECT 1 FROM (SELECT shipment_id, part_no FROM report_view{where_sql}) rv
+                        WHERE rv.shipment_id = bc.shipment_id AND rv.part_no = u.part_no
+                    )
+                )) as total_duty_credit_amount
             FROM report_view{where_sql}"
         );
 
@@ -278,6 +332,11 @@ pub fn get_report(filters: ReportFilters, state: State<DbState>) -> Result<Repor
                     .map_err(|e| e.to_string())?
                     .parse::<f64>()
                     .unwrap_or(0.0),
+                duty_credit_amount: totals_row
+                    .get::<_, String>(6)
+                    .map_err(|e| e.to_string())?
+                    .parse::<f64>()
+                    .unwrap_or(0.0),
                 expenses_total: totals_row
                     .get::<_, String>(5)
                     .map_err(|e| e.to_string())?
@@ -293,6 +352,7 @@ pub fn get_report(filters: ReportFilters, state: State<DbState>) -> Result<Repor
         page_size,
         total_rows,
         totals,
+        warnings,
     })
 }
 
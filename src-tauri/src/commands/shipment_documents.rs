@@ -0,0 +1,1000 @@
+//! Shipment document checklists: templates by mode / incoterm / goods category, per-shipment slots and `MISSING_DOCUMENTS` cases.
+
+use crate::commands::dashboard_cache;
+use crate::commands::exception_workflow;
+use crate::commands::shipment_status;
+use crate::commands::tariff;
+use crate::commands::utils::generate_id;
+use crate::db::DbState;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use tauri::{Manager, State};
+
+pub const PENDING: &str = "PENDING";
+pub const ATTACHED: &str = "ATTACHED";
+pub const NOT_APPLICABLE: &str = "NOT_APPLICABLE";
+pub const MISSING: &str = "MISSING";
+
+pub const SOURCE_TEMPLATE: &str = "TEMPLATE";
+pub const SOURCE_MANUAL: &str = "MANUAL";
+
+pub const MISSING_DOCUMENTS: &str = "MISSING_DOCUMENTS";
+
+/// Days before ETA from which missing mandatory documents raise a case on an in-transit shipment.
+const ETA_LEAD_DAYS: i64 = 3;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DocumentType {
+    pub code: String,
+    pub label: String,
+    pub sort_order: i64,
+    pub is_active: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ChecklistTemplateItem {
+    pub document_type: String,
+    pub is_mandatory: bool,
+    #[serde(default)]
+    pub sort_order: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ChecklistTemplate {
+    pub id: String,
+    pub name: String,
+    /// Filters; `None` matches any shipment.
+    pub shipment_mode: Option<String>,
+    pub incoterm: Option<String>,
+    pub goods_category: Option<String>,
+    pub is_active: bool,
+    pub items: Vec<ChecklistTemplateItem>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SaveChecklistTemplatePayload {
+    /// Omit to create a new template.
+    pub id: Option<String>,
+    pub name: String,
+    pub shipment_mode: Option<String>,
+    pub incoterm: Option<String>,
+    pub goods_category: Option<String>,
+    #[serde(default = "default_true")]
+    pub is_active: bool,
+    pub items: Vec<ChecklistTemplateItem>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ShipmentDocument {
+    pub id: String,
+    pub shipment_id: String,
+    pub document_type: String,
+    pub label: String,
+    pub is_mandatory: bool,
+    /// `TEMPLATE` or `MANUAL`.
+    pub source: String,
+    /// `PENDING`, `ATTACHED`, `NOT_APPLICABLE` or `MISSING`.
+    pub status: String,
+    pub file_name: Option<String>,
+    pub file_path: Option<String>,
+    pub reference_number: Option<String>,
+    pub remarks: Option<String>,
+    pub updated_by: Option<String>,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ShipmentDocumentChecklist {
+    pub shipment_id: String,
+    pub documents: Vec<ShipmentDocument>,
+    pub total: u32,
+    /// Attached or marked not applicable.
+    pub completed: u32,
+    /// `completed` / `total` × 100; 100 for a shipment without slots.
+    pub completeness_percent: f64,
+    /// Document types of mandatory slots still pending or missing.
+    pub mandatory_missing: Vec<String>,
+}
+
+fn blank_to_none(v: Option<&str>) -> Option<String> {
+    v.map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+}
+
+fn filter_matches(filter: Option<&str>, value: Option<&str>) -> bool {
+    match filter {
+        None => true,
+        Some(f) => matches!(value, Some(v) if v.trim().eq_ignore_ascii_case(f.trim())),
+    }
+}
+
+pub fn list_types(conn: &Connection) -> Result<Vec<DocumentType>, String> {
+    let mut stmt = conn
+        .prepare("SELECT code, label, sort_order, is_active FROM document_types ORDER BY sort_order, code")
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], |r| {
+            Ok(DocumentType {
+                code: r.get(0)?,
+                label: r.get(1)?,
+                sort_order: r.get(2)?,
+                is_active: r.get::<_, i64>(3)? != 0,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())
+}
+
+fn type_exists(conn: &Connection, code: &str) -> Result<bool, String> {
+    conn.query_row(
+        "SELECT EXISTS (SELECT 1 FROM document_types WHERE code = ?1)",
+        params![code],
+        |r| r.get(0),
+    )
+    .map_err(|e| e.to_string())
+}
+
+fn normalize_code(code: &str) -> String {
+    code.trim().to_uppercase().replace([' ', '-'], "_")
+}
+
+fn load_template_items(
+    conn: &Connection,
+    template_id: &str,
+) -> rusqlite::Result<Vec<ChecklistTemplateItem>> {
+    let mut stmt = conn.prepare(
+        "SELECT document_type, is_mandatory, sort_order FROM document_checklist_template_items
+         WHERE template_id = ?1 ORDER BY sort_order, document_type",
+    )?;
+    let rows = stmt.query_map(params![template_id], |r| {
+        Ok(ChecklistTemplateItem {
+            document_type: r.get(0)?,
+            is_mandatory: r.get::<_, i64>(1)? != 0,
+            sort_order: r.get(2)?,
+        })
+    })?;
+    rows.collect()
+}
+
+pub fn list_templates(conn: &Connection) -> Result<Vec<ChecklistTemplate>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, name, shipment_mode, incoterm, goods_category, is_active, created_at, updated_at
+             FROM document_checklist_templates ORDER BY name",
+        )
+        .map_err(|e| e.to_string())?;
+    let mut templates = stmt
+        .query_map([], |r| {
+            Ok(ChecklistTemplate {
+                id: r.get(0)?,
+                name: r.get(1)?,
+                shipment_mode: r.get(2)?,
+                incoterm: r.get(3)?,
+                goods_category: r.get(4)?,
+                is_active: r.get::<_, i64>(5)? != 0,
+                items: Vec::new(),
+                created_at: r.get(6)?,
+                updated_at: r.get(7)?,
+            })
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    for t in templates.iter_mut() {
+        t.items = load_template_items(conn, &t.id).map_err(|e| e.to_string())?;
+    }
+    Ok(templates)
+}
+
+pub fn upsert_template(
+    conn: &Connection,
+    payload: SaveChecklistTemplatePayload,
+) -> Result<ChecklistTemplate, String> {
+    let name = payload.name.trim().to_string();
+    if name.is_empty() {
+        return Err("Template name is required".to_string());
+    }
+    if payload.items.is_empty() {
+        return Err("A checklist template needs at least one document".to_string());
+    }
+    let mut items: BTreeMap<String, ChecklistTemplateItem> = BTreeMap::new();
+    for item in &payload.items {
+        let code = normalize_code(&item.document_type);
+        if !type_exists(conn, &code)? {
+            return Err(format!("Unknown document type '{}'", item.document_type));
+        }
+        if items.contains_key(&code) {
+            return Err(format!("{code} is listed twice"));
+        }
+        items.insert(
+            code.clone(),
+            ChecklistTemplateItem {
+                document_type: code,
+                ..item.clone()
+            },
+        );
+    }
+    let id = payload
+        .id
+        .clone()
+        .unwrap_or_else(|| generate_id(Some("DCT".to_string())));
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    tx.execute(
+        "INSERT INTO document_checklist_templates (id, name, shipment_mode, incoterm, goods_category, is_active)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+         ON CONFLICT (id) DO UPDATE SET
+            name = excluded.name,
+            shipment_mode = excluded.shipment_mode,
+            incoterm = excluded.incoterm,
+            goods_category = excluded.goods_category,
+            is_active = excluded.is_active,
+            updated_at = strftime('%Y-%m-%d %H:%M:%S', 'now', 'localtime')",
+        params![
+            id,
+            name,
+            blank_to_none(payload.shipment_mode.as_deref()),
+            blank_to_none(payload.incoterm.as_deref()),
+            blank_to_none(payload.goods_category.as_deref()),
+            payload.is_active as i64
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    tx.execute(
+        "DELETE FROM document_checklist_template_items WHERE template_id = ?1",
+        params![id],
+    )
+    .map_err(|e| e.to_string())?;
+    for item in items.values() {
+        tx.execute(
+            "INSERT INTO document_checklist_template_items (template_id, document_type, is_mandatory, sort_order)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![id, item.document_type, item.is_mandatory as i64, item.sort_order],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    tx.commit().map_err(|e| e.to_string())?;
+    list_templates(conn)?
+        .into_iter()
+        .find(|t| t.id == id)
+        .ok_or_else(|| "Template was not saved".to_string())
+}
+
+struct ShipmentInfo {
+    shipment_mode: Option<String>,
+    incoterm: Option<String>,
+    goods_category: Option<String>,
+    status: Option<String>,
+    eta: Option<String>,
+}
+
+fn load_shipment(conn: &Connection, shipment_id: &str) -> Result<ShipmentInfo, String> {
+    conn.query_row(
+        "SELECT shipment_mode, incoterm, goods_category, status, eta FROM shipments WHERE id = ?1",
+        params![shipment_id],
+        |r| {
+            Ok(ShipmentInfo {
+                shipment_mode: r.get(0)?,
+                incoterm: r.get(1)?,
+                goods_category: r.get(2)?,
+                status: r.get(3)?,
+                eta: r.get(4)?,
+            })
+        },
+    )
+    .optional()
+    .map_err(|e| e.to_string())?
+    .ok_or_else(|| format!("Shipment {shipment_id} not found"))
+}
+
+/// Documents the active templates matching the shipment ask for; mandatory if any template says so.
+fn required_documents(
+    conn: &Connection,
+    shipment: &ShipmentInfo,
+) -> Result<BTreeMap<String, bool>, String> {
+    let mut required = BTreeMap::new();
+    for t in list_templates(conn)? {
+        let applies = t.is_active
+            && filter_matches(
+                t.shipment_mode.as_deref(),
+                shipment.shipment_mode.as_deref(),
+            )
+            && filter_matches(t.incoterm.as_deref(), shipment.incoterm.as_deref())
+            && filter_matches(
+                t.goods_category.as_deref(),
+                shipment.goods_category.as_deref(),
+            );
+        if !applies {
+            continue;
+        }
+        for item in t.items {
+            *required.entry(item.document_type).or_insert(false) |= item.is_mandatory;
+        }
+    }
+    Ok(required)
+}
+
+/// Brings the shipment's template slots in line with the templates that match it now: adds new
+/// ones, updates mandatory flags and drops untouched slots no template asks for any more.
+pub fn sync_slots(conn: &Connection, shipment_id: &str) -> Result<(), String> {
+    let shipment = load_shipment(conn, shipment_id)?;
+    let required = required_documents(conn, &shipment)?;
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    for (document_type, mandatory) in &required {
+        tx.execute(
+            "INSERT INTO shipment_documents (id, shipment_id, document_type, is_mandatory, source)
+             VALUES (?1, ?2, ?3, ?4, ?5)
+             ON CONFLICT (shipment_id, document_type) DO UPDATE SET is_mandatory = excluded.is_mandatory
+             WHERE shipment_documents.source = excluded.source AND shipment_documents.is_mandatory <> excluded.is_mandatory",
+            params![
+                generate_id(Some("SDOC".to_string())),
+                shipment_id,
+                document_type,
+                *mandatory as i64,
+                SOURCE_TEMPLATE
+            ],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    let stale: Vec<String> = {
+        let mut stmt = tx
+            .prepare(
+                "SELECT document_type FROM shipment_documents
+                 WHERE shipment_id = ?1 AND source = ?2 AND status IN (?3, ?4) AND file_path IS NULL",
+            )
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map(
+                params![shipment_id, SOURCE_TEMPLATE, PENDING, MISSING],
+                |r| r.get::<_, String>(0),
+            )
+            .map_err(|e| e.to_string())?;
+        rows.collect::<Result<Vec<_>, _>>()
+            .map_err(|e| e.to_string())?
+    };
+    for document_type in stale.iter().filter(|d| !required.contains_key(*d)) {
+        tx.execute(
+            "DELETE FROM shipment_documents WHERE shipment_id = ?1 AND document_type = ?2",
+            params![shipment_id, document_type],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    tx.commit().map_err(|e| e.to_string())
+}
+
+pub fn load_checklist(
+    conn: &Connection,
+    shipment_id: &str,
+) -> Result<ShipmentDocumentChecklist, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT d.id, d.shipment_id, d.document_type, COALESCE(t.label, d.document_type), d.is_mandatory,
+                    d.source, d.status, d.file_name, d.file_path, d.reference_number, d.remarks, d.updated_by,
+                    d.updated_at
+             FROM shipment_documents d
+             LEFT JOIN document_types t ON t.code = d.document_type
+             WHERE d.shipment_id = ?1
+             ORDER BY COALESCE(t.sort_order, 1000), d.document_type",
+        )
+        .map_err(|e| e.to_string())?;
+    let documents = stmt
+        .query_map(params![shipment_id], |r| {
+            Ok(ShipmentDocument {
+                id: r.get(0)?,
+                shipment_id: r.get(1)?,
+                document_type: r.get(2)?,
+                label: r.get(3)?,
+                is_mandatory: r.get::<_, i64>(4)? != 0,
+                source: r.get(5)?,
+                status: r.get(6)?,
+                file_name: r.get(7)?,
+                file_path: r.get(8)?,
+                reference_number: r.get(9)?,
+                remarks: r.get(10)?,
+                updated_by: r.get(11)?,
+                updated_at: r.get(12)?,
+            })
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    let total = documents.len() as u32;
+    let completed = documents
+        .iter()
+        .filter(|d| d.status == ATTACHED || d.status == NOT_APPLICABLE)
+        .count() as u32;
+    let mandatory_missing = documents
+        .iter()
+        .filter(|d| d.is_mandatory && (d.status == PENDING || d.status == MISSING))
+        .map(|d| d.document_type.clone())
+        .collect();
+    Ok(ShipmentDocumentChecklist {
+        shipment_id: shipment_id.to_string(),
+        completeness_percent: if total == 0 {
+            100.0
+        } else {
+            (completed as f64 / total as f64 * 1000.0).round() / 10.0
+        },
+        documents,
+        total,
+        completed,
+        mandatory_missing,
+    })
+}
+
+/// Whether missing documents should already be chased: the shipment has its documents in, is at
+/// customs, or is within [`ETA_LEAD_DAYS`] of arrival. `None` once it is past clearance.
+fn chase_priority(shipment: &ShipmentInfo, today: &str) -> Option<&'static str> {
+    let status = shipment
+        .status
+        .as_deref()
+        .and_then(shipment_status::normalize_status);
+    let eta = shipment
+        .eta
+        .as_deref()
+        .and_then(|d| tariff::normalize_date(d.get(..10).unwrap_or(d)));
+    let today_date = chrono::NaiveDate::parse_from_str(today, "%Y-%m-%d").ok()?;
+    let days_to_eta = eta
+        .and_then(|d| chrono::NaiveDate::parse_from_str(&d, "%Y-%m-%d").ok())
+        .map(|d| (d - today_date).num_days());
+    match status {
+        Some(shipment_status::CUSTOMS_CLEARANCE) => Some("HIGH"),
+        None | Some(shipment_status::DOCS_RECEIVED) | Some(shipment_status::IN_TRANSIT) => {
+            match days_to_eta {
+                Some(d) if d <= 0 => Some("HIGH"),
+                Some(d) if d <= ETA_LEAD_DAYS => Some("MEDIUM"),
+                _ if status == Some(shipment_status::DOCS_RECEIVED) => Some("MEDIUM"),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Opens a `MISSING_DOCUMENTS` case while mandatory documents are outstanding on a shipment heading
+/// for clearance, raising it to HIGH as arrival nears. Resolves it once they are attached or marked
+/// not applicable, or once the shipment no longer needs chasing (e.g. it is past clearance).
+pub fn sync_case(
+    conn: &Connection,
+    shipment_id: &str,
+    today: &str,
+) -> Result<Option<String>, String> {
+    let checklist = load_checklist(conn, shipment_id)?;
+    if checklist.mandatory_missing.is_empty() {
+        exception_workflow::auto_resolve_case_for_entity(conn, MISSING_DOCUMENTS, shipment_id)?;
+        return Ok(None);
+    }
+    let shipment = load_shipment(conn, shipment_id)?;
+    let Some(priority) = chase_priority(&shipment, today) else {
+        exception_workflow::auto_resolve_case_for_entity(conn, MISSING_DOCUMENTS, shipment_id)?;
+        return Ok(None);
+    };
+    let details = serde_json::json!({
+        "exceptionType": MISSING_DOCUMENTS,
+        "missing": checklist.mandatory_missing,
+        "completenessPercent": checklist.completeness_percent,
+        "eta": shipment.eta,
+    });
+    let case_id = exception_workflow::open_case_for_entity(
+        conn,
+        MISSING_DOCUMENTS,
+        shipment_id,
+        priority,
+        &details.to_string(),
+    )?;
+    // A case opened ahead of arrival escalates once the shipment is due or at customs.
+    if let (Some(id), "HIGH") = (&case_id, priority) {
+        conn.execute(
+            "UPDATE exception_cases SET priority = 'HIGH', updated_at = strftime('%Y-%m-%d %H:%M:%S', 'now', 'localtime')
+             WHERE id = ?1 AND priority NOT IN ('HIGH', 'CRITICAL')",
+            params![id],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    Ok(case_id)
+}
+
+/// Syncs slots and the case after a shipment or document change; failures are only logged.
+pub fn refresh_after_change(conn: &Connection, shipment_id: &str) {
+    if let Err(e) =
+        sync_slots(conn, shipment_id).and_then(|_| sync_case(conn, shipment_id, &tariff::today()))
+    {
+        log::warn!("Document checklist refresh for shipment {shipment_id} failed: {e}");
+    }
+}
+
+/// Syncs every shipment not yet past customs clearance; returns how many have an open case.
+/// Shipments that fail to sync are logged and left for the next run.
+pub fn review_all(conn: &Connection, today: &str) -> Result<u32, String> {
+    let mut stmt = conn
+        .prepare("SELECT id, status FROM shipments")
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], |r| {
+            Ok((r.get::<_, String>(0)?, r.get::<_, Option<String>>(1)?))
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    let mut open = 0;
+    for (id, status) in rows {
+        let before_clearance = matches!(
+            status
+                .as_deref()
+                .and_then(shipment_status::normalize_status),
+            None | Some(shipment_status::DOCS_RECEIVED)
+                | Some(shipment_status::IN_TRANSIT)
+                | Some(shipment_status::CUSTOMS_CLEARANCE)
+        );
+        if !before_clearance {
+            if let Err(e) =
+                exception_workflow::auto_resolve_case_for_entity(conn, MISSING_DOCUMENTS, &id)
+            {
+                log::warn!("Document checklist review for shipment {id} failed: {e}");
+            }
+            continue;
+        }
+        let reviewed = sync_slots(conn, &id).and_then(|_| sync_case(conn, &id, today));
+        match reviewed {
+            Ok(Some(_)) => open += 1,
+            Ok(None) => {}
+            Err(e) => log::warn!("Document checklist review for shipment {id} failed: {e}"),
+        }
+    }
+    Ok(open)
+}
+
+/// Re-checks open checklists each day, since chase priority rises as the ETA approaches.
+pub fn run_daily_review(conn: &Connection) {
+    if let Err(e) = review_all(conn, &tariff::today()) {
+        log::warn!("Daily document checklist review failed: {e}");
+    }
+}
+
+fn slot_id(conn: &Connection, shipment_id: &str, document_type: &str) -> Result<String, String> {
+    conn.query_row(
+        "SELECT id FROM shipment_documents WHERE shipment_id = ?1 AND document_type = ?2",
+        params![shipment_id, document_type],
+        |r| r.get(0),
+    )
+    .optional()
+    .map_err(|e| e.to_string())?
+    .ok_or_else(|| format!("Shipment {shipment_id} has no {document_type} slot"))
+}
+
+/// Marks a slot pending, missing or not applicable; a mandatory document needs remarks to be waived.
+pub fn set_status(
+    conn: &Connection,
+    shipment_id: &str,
+    document_type: &str,
+    status: &str,
+    remarks: Option<&str>,
+    user_id: Option<&str>,
+) -> Result<ShipmentDocumentChecklist, String> {
+    let status = status.trim().to_uppercase();
+    if ![PENDING, MISSING, NOT_APPLICABLE].contains(&status.as_str()) {
+        return Err(format!(
+            "Status must be {PENDING}, {MISSING} or {NOT_APPLICABLE}; attach a file to mark a document {ATTACHED}"
+        ));
+    }
+    let document_type = normalize_code(document_type);
+    let id = slot_id(conn, shipment_id, &document_type)?;
+    let mandatory: bool = conn
+        .query_row(
+            "SELECT is_mandatory FROM shipment_documents WHERE id = ?1",
+            params![id],
+            |r| r.get::<_, i64>(0),
+        )
+        .map_err(|e| e.to_string())?
+        != 0;
+    let remarks = blank_to_none(remarks);
+    if status == NOT_APPLICABLE && mandatory && remarks.is_none() {
+        return Err(format!(
+            "{document_type} is mandatory; give a reason to mark it not applicable"
+        ));
+    }
+    conn.execute(
+        "UPDATE shipment_documents SET status = ?2, file_name = NULL, file_path = NULL, remarks = ?3, updated_by = ?4,
+            updated_at = strftime('%Y-%m-%d %H:%M:%S', 'now', 'localtime')
+         WHERE id = ?1",
+        params![id, status, remarks, user_id],
+    )
+    .map_err(|e| e.to_string())?;
+    sync_case(conn, shipment_id, &tariff::today())?;
+    load_checklist(conn, shipment_id)
+}
+
+fn record_attachment(
+    conn: &Connection,
+    shipment_id: &str,
+    document_type: &str,
+    file_name: &str,
+    file_path: &str,
+    reference_number: Option<&str>,
+    user_id: Option<&str>,
+) -> Result<ShipmentDocumentChecklist, String> {
+    let id = slot_id(conn, shipment_id, document_type)?;
+    conn.execute(
+        "UPDATE shipment_documents SET status = ?2, file_name = ?3, file_path = ?4,
+            reference_number = COALESCE(?5, reference_number), updated_by = ?6,
+            updated_at = strftime('%Y-%m-%d %H:%M:%S', 'now', 'localtime')
+         WHERE id = ?1",
+        params![
+            id,
+            ATTACHED,
+            file_name,
+            file_path,
+            blank_to_none(reference_number),
+            user_id
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    sync_case(conn, shipment_id, &tariff::today())?;
+    load_checklist(conn, shipment_id)
+}
+
+#[tauri::command]
+pub fn list_document_types(state: State<DbState>) -> Result<Vec<DocumentType>, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    list_types(&conn)
+}
+
+#[tauri::command]
+pub fn save_document_type(
+    document_type: DocumentType,
+    state: State<DbState>,
+) -> Result<Vec<DocumentType>, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    let code = normalize_code(&document_type.code);
+    if code.is_empty() || document_type.label.trim().is_empty() {
+        return Err("Document type code and label are required".to_string());
+    }
+    conn.execute(
+        "INSERT INTO document_types (code, label, sort_order, is_active) VALUES (?1, ?2, ?3, ?4)
+         ON CONFLICT (code) DO UPDATE SET
+            label = excluded.label,
+            sort_order = excluded.sort_order,
+            is_active = excluded.is_active",
+        params![
+            code,
+            document_type.label.trim(),
+            document_type.sort_order,
+            document_type.is_active as i64
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    list_types(&conn)
+}
+
+#[tauri::command]
+pub fn list_checklist_templates(state: State<DbState>) -> Result<Vec<ChecklistTemplate>, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    list_templates(&conn)
+}
+
+/// Saves a template; existing shipments pick it up the next time their checklist is synced.
+#[tauri::command]
+pub fn save_checklist_template(
+    payload: SaveChecklistTemplatePayload,
+    state: State<DbState>,
+) -> Result<ChecklistTemplate, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    upsert_template(&conn, payload)
+}
+
+#[tauri::command]
+pub fn delete_checklist_template(id: String, state: State<DbState>) -> Result<(), String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    tx.execute(
+        "DELETE FROM document_checklist_template_items WHERE template_id = ?1",
+        params![id],
+    )
+    .map_err(|e| e.to_string())?;
+    let deleted = tx
+        .execute(
+            "DELETE FROM document_checklist_templates WHERE id = ?1",
+            params![id],
+        )
+        .map_err(|e| e.to_string())?;
+    if deleted == 0 {
+        return Err(format!("Checklist template {id} not found"));
+    }
+    tx.commit().map_err(|e| e.to_string())
+}
+
+/// The shipment's document slots, synced with the current templates.
+#[tauri::command]
+pub fn get_shipment_document_checklist(
+    shipment_id: String,
+    state: State<DbState>,
+) -> Result<ShipmentDocumentChecklist, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    sync_slots(&conn, &shipment_id)?;
+    load_checklist(&conn, &shipment_id)
+}
+
+/// Adds a slot no template asks for (or makes an optional one mandatory).
+#[tauri::command]
+pub fn add_shipment_document_slot(
+    shipment_id: String,
+    document_type: String,
+    is_mandatory: bool,
+    user_id: Option<String>,
+    state: State<DbState>,
+) -> Result<ShipmentDocumentChecklist, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    load_shipment(&conn, &shipment_id)?;
+    let code = normalize_code(&document_type);
+    if !type_exists(&conn, &code)? {
+        return Err(format!("Unknown document type '{document_type}'"));
+    }
+    conn.execute(
+        "INSERT INTO shipment_documents (id, shipment_id, document_type, is_mandatory, source, updated_by)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+         ON CONFLICT (shipment_id, document_type) DO UPDATE SET
+            is_mandatory = MAX(shipment_documents.is_mandatory, excluded.is_mandatory),
+            source = excluded.source,
+            updated_by = excluded.updated_by,
+            updated_at = strftime('%Y-%m-%d %H:%M:%S', 'now', 'localtime')",
+        params![
+            generate_id(Some("SDOC".to_string())),
+            shipment_id,
+            code,
+            is_mandatory as i64,
+            SOURCE_MANUAL,
+            user_id
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    sync_case(&conn, &shipment_id, &tariff::today())?;
+    let _ = dashboard_cache::invalidate_dashboard_metrics_cache(&conn);
+    load_checklist(&conn, &shipment_id)
+}
+
+/// Copies a file into app data attachments/shipments/<shipment id>/ and attaches it to the slot.
+#[tauri::command]
+pub fn attach_shipment_document(
+    app: tauri::AppHandle,
+    shipment_id: String,
+    document_type: String,
+    src_path: String,
+    reference_number: Option<String>,
+    user_id: Option<String>,
+    state: State<DbState>,
+) -> Result<ShipmentDocumentChecklist, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    let code = normalize_code(&document_type);
+    slot_id(&conn, &shipment_id, &code)?;
+
+    let base = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    let attach_dir = base
+        .join("attachments")
+        .join("shipments")
+        .join(&shipment_id);
+    std::fs::create_dir_all(&attach_dir).map_err(|e| e.to_string())?;
+    let file_name = std::path::Path::new(&src_path)
+        .file_name()
+        .and_then(|s| s.to_str())
+        .ok_or("Invalid source file name")?
+        .to_string();
+    let dest_path = attach_dir.join(format!("{}_{file_name}", code.to_lowercase()));
+    std::fs::copy(&src_path, &dest_path).map_err(|e| e.to_string())?;
+
+    let checklist = record_attachment(
+        &conn,
+        &shipment_id,
+        &code,
+        &file_name,
+        &dest_path.to_string_lossy(),
+        reference_number.as_deref(),
+        user_id.as_deref(),
+    )?;
+    let _ = dashboard_cache::invalidate_dashboard_metrics_cache(&conn);
+    Ok(checklist)
+}
+
+#[tauri::command]
+pub fn set_shipment_document_status(
+    shipment_id: String,
+    document_type: String,
+    status: String,
+    remarks: Option<String>,
+    user_id: Option<String>,
+    state: State<DbState>,
+) -> Result<ShipmentDocumentChecklist, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    let checklist = set_status(
+        &conn,
+        &shipment_id,
+        &document_type,
+        &status,
+        remarks.as_deref(),
+        user_id.as_deref(),
+    )?;
+    let _ = dashboard_cache::invalidate_dashboard_metrics_cache(&conn);
+    Ok(checklist)
+}
+
+/// Runs the checklist review now instead of waiting for daily maintenance.
+#[tauri::command]
+pub fn review_shipment_documents(state: State<DbState>) -> Result<u32, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    let open = review_all(&conn, &tariff::today())?;
+    let _ = dashboard_cache::invalidate_dashboard_metrics_cache(&conn);
+    Ok(open)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::test_support;
+
+    fn open_case_priority(conn: &Connection) -> Option<String> {
+        conn.query_row(
+            "SELECT priority FROM exception_cases WHERE exception_type = 'MISSING_DOCUMENTS' AND entity_id = 'SHP-1' AND status = 'OPEN'",
+            [],
+            |r| r.get(0),
+        )
+        .optional()
+        .unwrap()
+    }
+
+    fn case_status(conn: &Connection) -> Option<String> {
+        conn.query_row(
+            "SELECT status FROM exception_cases WHERE exception_type = 'MISSING_DOCUMENTS' AND entity_id = 'SHP-1'",
+            [],
+            |r| r.get(0),
+        )
+        .optional()
+        .unwrap()
+    }
+
+    #[test]
+    fn templates_build_slots_and_missing_mandatory_documents_open_a_case() {
+        let conn = test_support::migrated_db();
+        test_support::add_shipment(&conn, "SHP-1", "2024-03-01", 1000.0, "USD", "in-transit");
+        conn.execute(
+            "UPDATE shipments SET goods_category = 'Electronics', incoterm = 'cif', shipment_mode = 'Sea', eta = '2024-03-20'
+             WHERE id = 'SHP-1'",
+            [],
+        )
+        .unwrap();
+        upsert_template(
+            &conn,
+            SaveChecklistTemplatePayload {
+                id: None,
+                name: "Electronics".into(),
+                shipment_mode: None,
+                incoterm: None,
+                goods_category: Some("electronics".into()),
+                is_active: true,
+                items: vec![ChecklistTemplateItem {
+                    document_type: "test certificate".into(),
+                    is_mandatory: true,
+                    sort_order: 60,
+                }],
+            },
+        )
+        .unwrap();
+
+        sync_slots(&conn, "SHP-1").unwrap();
+        let checklist = load_checklist(&conn, "SHP-1").unwrap();
+        let types: Vec<&str> = checklist
+            .documents
+            .iter()
+            .map(|d| d.document_type.as_str())
+            .collect();
+        assert_eq!(
+            types,
+            [
+                "COMMERCIAL_INVOICE",
+                "PACKING_LIST",
+                "BILL_OF_LADING",
+                "CERTIFICATE_OF_ORIGIN",
+                "INSURANCE_CERTIFICATE",
+                "TEST_CERTIFICATE"
+            ]
+        );
+        assert_eq!(checklist.mandatory_missing.len(), 5);
+
+        // Not chased while the vessel is more than three days out.
+        assert_eq!(sync_case(&conn, "SHP-1", "2024-03-10").unwrap(), None);
+        let case_id = sync_case(&conn, "SHP-1", "2024-03-17").unwrap();
+        assert!(case_id.is_some());
+        assert_eq!(open_case_priority(&conn).as_deref(), Some("MEDIUM"));
+        // Due today: the same case is raised to HIGH.
+        assert_eq!(sync_case(&conn, "SHP-1", "2024-03-20").unwrap(), case_id);
+        assert_eq!(open_case_priority(&conn).as_deref(), Some("HIGH"));
+
+        assert!(set_status(
+            &conn,
+            "SHP-1",
+            "TEST_CERTIFICATE",
+            NOT_APPLICABLE,
+            None,
+            None
+        )
+        .is_err());
+        set_status(
+            &conn,
+            "SHP-1",
+            "TEST_CERTIFICATE",
+            NOT_APPLICABLE,
+            Some("Not a regulated item"),
+            None,
+        )
+        .unwrap();
+        set_status(
+            &conn,
+            "SHP-1",
+            "CERTIFICATE_OF_ORIGIN",
+            NOT_APPLICABLE,
+            None,
+            None,
+        )
+        .unwrap();
+        for code in ["COMMERCIAL_INVOICE", "PACKING_LIST", "BILL_OF_LADING"] {
+            record_attachment(
+                &conn,
+                "SHP-1",
+                code,
+                "a.pdf",
+                "/tmp/a.pdf",
+                None,
+                Some("ops"),
+            )
+            .unwrap();
+        }
+        let checklist = record_attachment(
+            &conn,
+            "SHP-1",
+            "INSURANCE_CERTIFICATE",
+            "ins.pdf",
+            "/tmp/ins.pdf",
+            Some("POL-1"),
+            None,
+        )
+        .unwrap();
+        assert_eq!(checklist.completeness_percent, 100.0);
+        assert!(checklist.mandatory_missing.is_empty());
+        assert_eq!(case_status(&conn).as_deref(), Some("RESOLVED"));
+
+        // Switching to air swaps the untouched slots only.
+        conn.execute(
+            "UPDATE shipments SET shipment_mode = 'AIR' WHERE id = 'SHP-1'",
+            [],
+        )
+        .unwrap();
+        set_status(&conn, "SHP-1", "BILL_OF_LADING", PENDING, None, None).unwrap();
+        sync_slots(&conn, "SHP-1").unwrap();
+        let checklist = load_checklist(&conn, "SHP-1").unwrap();
+        assert_eq!(checklist.mandatory_missing, ["AIRWAY_BILL"]);
+        assert_eq!(checklist.total, 6);
+        assert_eq!(checklist.completeness_percent, 83.3);
+
+        // Past clearance there is nothing left to chase.
+        assert!(sync_case(&conn, "SHP-1", "2024-03-20").unwrap().is_some());
+        conn.execute(
+            "UPDATE shipments SET status = 'delivered' WHERE id = 'SHP-1'",
+            [],
+        )
+        .unwrap();
+        assert_eq!(sync_case(&conn, "SHP-1", "2024-03-21").unwrap(), None);
+        assert_eq!(open_case_priority(&conn), None);
+    }
+}
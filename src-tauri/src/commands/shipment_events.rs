@@ -0,0 +1,614 @@
+//! Shipment milestone timeline: typed events with timestamps, the status they imply, and dwell times.
+
+use crate::commands::bonded_warehouse;
+use crate::commands::dashboard_cache;
+use crate::commands::demurrage;
+use crate::commands::eta_slippage;
+use crate::commands::shipment_status::{self, TransitionContext};
+use crate::db::DbState;
+use chrono::NaiveDateTime;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+pub const BOOKED: &str = "BOOKED";
+pub const DEPARTED: &str = "DEPARTED";
+pub const ARRIVED_AT_PORT: &str = "ARRIVED_AT_PORT";
+pub const IGM_FILED: &str = "IGM_FILED";
+pub const BOE_FILED: &str = "BOE_FILED";
+pub const OUT_OF_CHARGE: &str = "OUT_OF_CHARGE";
+pub const GATE_OUT: &str = "GATE_OUT";
+pub const DELIVERED: &str = "DELIVERED";
+pub const EMPTY_RETURNED: &str = "EMPTY_RETURNED";
+
+/// Milestones in the order a shipment passes them.
+pub const MILESTONES: [&str; 9] = [
+    BOOKED,
+    DEPARTED,
+    ARRIVED_AT_PORT,
+    IGM_FILED,
+    BOE_FILED,
+    OUT_OF_CHARGE,
+    GATE_OUT,
+    DELIVERED,
+    EMPTY_RETURNED,
+];
+
+pub const SOURCE_MANUAL: &str = "MANUAL";
+pub const SOURCE_SYSTEM: &str = "SYSTEM";
+
+const TIMESTAMP_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ShipmentEvent {
+    pub id: i64,
+    pub shipment_id: String,
+    pub event_type: String,
+    pub occurred_at: String,
+    pub source: String,
+    pub notes: Option<String>,
+    pub recorded_by: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordShipmentEventPayload {
+    pub shipment_id: String,
+    pub event_type: String,
+    /// `YYYY-MM-DD` or `YYYY-MM-DD HH:MM[:SS]`.
+    pub occurred_at: String,
+    #[serde(default)]
+    pub source: Option<String>,
+    #[serde(default)]
+    pub notes: Option<String>,
+    #[serde(default)]
+    pub user_id: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ShipmentTimeline {
+    pub shipment_id: String,
+    pub status: Option<String>,
+    /// Furthest milestone reached, which `status` follows.
+    pub current_milestone: Option<String>,
+    /// Recorded milestones in milestone order.
+    pub events: Vec<ShipmentEvent>,
+    /// Milestones not recorded yet, in order.
+    pub pending: Vec<String>,
+}
+
+/// Time spent between two consecutive recorded milestones.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct DwellSegment {
+    pub from_event: String,
+    pub to_event: String,
+    pub from_at: String,
+    pub to_at: String,
+    pub days: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ShipmentDwellTimes {
+    pub shipment_id: String,
+    pub segments: Vec<DwellSegment>,
+    /// First to last recorded milestone.
+    pub total_days: Option<f64>,
+}
+
+/// Dwell between two milestones across shipments.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DwellSummary {
+    pub from_event: String,
+    pub to_event: String,
+    pub shipment_count: u32,
+    pub average_days: Option<f64>,
+    pub min_days: Option<f64>,
+    pub max_days: Option<f64>,
+}
+
+fn milestone_index(event_type: &str) -> Option<usize> {
+    MILESTONES.iter().position(|m| *m == event_type)
+}
+
+fn normalize_event_type(raw: &str) -> Result<&'static str, String> {
+    let wanted = raw.trim().to_uppercase().replace([' ', '-'], "_");
+    MILESTONES
+        .iter()
+        .find(|m| **m == wanted)
+        .copied()
+        .ok_or_else(|| format!("Unknown shipment milestone '{raw}'"))
+}
+
+/// Accepts a date or a date-time; a bare date means midnight.
+pub fn normalize_timestamp(raw: &str) -> Option<String> {
+    let raw = raw.trim();
+    [
+        "%Y-%m-%d %H:%M:%S",
+        "%Y-%m-%dT%H:%M:%S",
+        "%Y-%m-%d %H:%M",
+        "%Y-%m-%dT%H:%M",
+    ]
+    .iter()
+    .find_map(|f| NaiveDateTime::parse_from_str(raw, f).ok())
+    .or_else(|| {
+        crate::commands::tariff::normalize_date(raw).and_then(|d| {
+            NaiveDateTime::parse_from_str(&format!("{d} 00:00:00"), TIMESTAMP_FORMAT).ok()
+        })
+    })
+    .map(|t| t.format(TIMESTAMP_FORMAT).to_string())
+}
+
+/// `shipments.status` a milestone implies. Customs clearance starts with the BOE filing, so
+/// arrival and the IGM leave the shipment in transit. Returning the empty container implies nothing.
+pub fn status_for_milestone(event_type: &str) -> Option<&'static str> {
+    match event_type {
+        BOOKED => Some(shipment_status::DOCS_RECEIVED),
+        DEPARTED | ARRIVED_AT_PORT | IGM_FILED => Some(shipment_status::IN_TRANSIT),
+        BOE_FILED => Some(shipment_status::CUSTOMS_CLEARANCE),
+        OUT_OF_CHARGE | GATE_OUT => Some(shipment_status::READY_FOR_DELIVERY),
+        DELIVERED => Some(shipment_status::DELIVERED),
+        _ => None,
+    }
+}
+
+const EVENT_COLUMNS: &str =
+    "id, shipment_id, event_type, occurred_at, source, notes, recorded_by, created_at, updated_at";
+
+fn map_event(r: &rusqlite::Row) -> rusqlite::Result<ShipmentEvent> {
+    Ok(ShipmentEvent {
+        id: r.get(0)?,
+        shipment_id: r.get(1)?,
+        event_type: r.get(2)?,
+        occurred_at: r.get(3)?,
+        source: r.get(4)?,
+        notes: r.get(5)?,
+        recorded_by: r.get(6)?,
+        created_at: r.get(7)?,
+        updated_at: r.get(8)?,
+    })
+}
+
+/// Recorded milestones of a shipment in milestone order.
+pub fn load_events(conn: &Connection, shipment_id: &str) -> Result<Vec<ShipmentEvent>, String> {
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {EVENT_COLUMNS} FROM shipment_events WHERE shipment_id = ?1"
+        ))
+        .map_err(|e| e.to_string())?;
+    let mut events = stmt
+        .query_map(params![shipment_id], map_event)
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    events.sort_by_key(|e| milestone_index(&e.event_type));
+    Ok(events)
+}
+
+/// Sets `shipments.status` from the furthest status-bearing milestone reached. While goods sit in a bonded
+/// warehouse after the BOE, the warehouse status wins. Shipments without milestones keep their
+/// status, as do shipments the status machine will not move (the refusal is logged).
+pub fn sync_status(conn: &Connection, shipment_id: &str) -> Result<Option<String>, String> {
+    let Some((latest, status)) = load_events(conn, shipment_id)?
+        .into_iter()
+        .rev()
+        .find_map(|e| status_for_milestone(&e.event_type).map(|s| (e, s)))
+    else {
+        return Ok(None);
+    };
+    if latest.event_type == BOE_FILED && bonded_warehouse::sync_shipment_status(conn, shipment_id)?
+    {
+        return conn
+            .query_row(
+                "SELECT status FROM shipments WHERE id = ?1",
+                params![shipment_id],
+                |r| r.get(0),
+            )
+            .map_err(|e| e.to_string());
+    }
+    let reason = format!("Milestone {}", latest.event_type);
+    let delivery_date = latest.occurred_at.get(..10);
+    let ctx = TransitionContext {
+        source: shipment_status::SOURCE_MILESTONE,
+        user_id: latest.recorded_by.as_deref(),
+        reason: Some(&reason),
+        date_of_delivery: delivery_date,
+    };
+    shipment_status::transition_or_log(conn, shipment_id, status, &ctx);
+    conn.query_row(
+        "SELECT status FROM shipments WHERE id = ?1",
+        params![shipment_id],
+        |r| r.get(0),
+    )
+    .map_err(|e| e.to_string())
+}
+
+/// Inserts or replaces the shipment's milestone of this type and re-derives its status.
+pub fn record(
+    conn: &Connection,
+    payload: &RecordShipmentEventPayload,
+) -> Result<ShipmentEvent, String> {
+    let event_type = normalize_event_type(&payload.event_type)?;
+    let occurred_at = normalize_timestamp(&payload.occurred_at)
+        .ok_or_else(|| format!("Invalid milestone time '{}'", payload.occurred_at))?;
+    let exists: bool = conn
+        .query_row(
+            "SELECT 1 FROM shipments WHERE id = ?1",
+            params![payload.shipment_id],
+            |_| Ok(true),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?
+        .unwrap_or(false);
+    if !exists {
+        return Err(format!("Shipment {} not found", payload.shipment_id));
+    }
+    let source = payload
+        .source
+        .as_deref()
+        .map(|s| s.trim().to_uppercase())
+        .filter(|s| !s.is_empty())
+        .unwrap_or_else(|| SOURCE_MANUAL.to_string());
+    conn.execute(
+        "INSERT INTO shipment_events (shipment_id, event_type, occurred_at, source, notes, recorded_by)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+         ON CONFLICT (shipment_id, event_type) DO UPDATE SET
+            occurred_at = excluded.occurred_at,
+            source = excluded.source,
+            notes = excluded.notes,
+            recorded_by = excluded.recorded_by,
+            updated_at = strftime('%Y-%m-%d %H:%M:%S', 'now', 'localtime')",
+        params![
+            payload.shipment_id,
+            event_type,
+            occurred_at,
+            source,
+            payload.notes,
+            payload.user_id
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    sync_status(conn, &payload.shipment_id)?;
+    conn.query_row(
+        &format!(
+            "SELECT {EVENT_COLUMNS} FROM shipment_events WHERE shipment_id = ?1 AND event_type = ?2"
+        ),
+        params![payload.shipment_id, event_type],
+        map_event,
+    )
+    .map_err(|e| e.to_string())
+}
+
+/// Records a milestone the system derived from other data. Milestones entered by a user are left
+/// alone; returns whether anything changed.
+fn record_system(
+    conn: &Connection,
+    shipment_id: &str,
+    event_type: &str,
+    occurred_at: &str,
+) -> Result<bool, String> {
+    let Some(occurred_at) = normalize_timestamp(occurred_at) else {
+        return Ok(false);
+    };
+    let changed = conn
+        .execute(
+            "INSERT INTO shipment_events (shipment_id, event_type, occurred_at, source)
+             VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT (shipment_id, event_type) DO UPDATE SET
+                occurred_at = excluded.occurred_at,
+                updated_at = strftime('%Y-%m-%d %H:%M:%S', 'now', 'localtime')
+             WHERE shipment_events.source = ?4 AND shipment_events.occurred_at <> excluded.occurred_at",
+            params![shipment_id, event_type, occurred_at, SOURCE_SYSTEM],
+        )
+        .map_err(|e| e.to_string())?;
+    Ok(changed > 0)
+}
+
+/// Arrival and BOE filing milestones from the Bills of Entry linked to the shipment's saved BOE
+/// calculations. Failures are logged; the BOE save itself already succeeded.
+pub fn record_boe_milestones(conn: &Connection, shipment_id: &str) {
+    let result = (|| -> Result<(), String> {
+        let (be_date, arrival): (Option<String>, Option<String>) = conn
+            .query_row(
+                "SELECT MIN(bd.be_date), MIN(bd.arrival_date)
+                 FROM boe_calculations bc JOIN boe_details bd ON bd.id = bc.boe_id
+                 WHERE bc.shipment_id = ?1",
+                params![shipment_id],
+                |r| Ok((r.get(0)?, r.get(1)?)),
+            )
+            .map_err(|e| e.to_string())?;
+        let mut changed = false;
+        if let Some(arrival) = arrival {
+            if record_system(conn, shipment_id, ARRIVED_AT_PORT, &arrival)? {
+                eta_slippage::refresh_after_change(conn, shipment_id);
+                changed = true;
+            }
+        }
+        if let Some(be_date) = be_date {
+            changed |= record_system(conn, shipment_id, BOE_FILED, &be_date)?;
+        }
+        if changed {
+            sync_status(conn, shipment_id)?;
+        }
+        Ok(())
+    })();
+    if let Err(e) = result {
+        log::warn!("Recording BOE milestones for shipment {shipment_id} failed: {e}");
+    }
+}
+
+/// Delivery milestone on `date` (today when `None`); failures are logged.
+pub fn record_delivery(conn: &Connection, shipment_id: &str, date: Option<&str>) {
+    let now = chrono::Local::now().format(TIMESTAMP_FORMAT).to_string();
+    let result =
+        record_system(conn, shipment_id, DELIVERED, date.unwrap_or(&now)).and_then(|changed| {
+            if changed {
+                sync_status(conn, shipment_id).map(|_| ())
+            } else {
+                Ok(())
+            }
+        });
+    if let Err(e) = result {
+        log::warn!("Recording delivery for shipment {shipment_id} failed: {e}");
+    }
+}
+
+fn days_between(from: &str, to: &str) -> Option<f64> {
+    let from = NaiveDateTime::parse_from_str(from, TIMESTAMP_FORMAT).ok()?;
+    let to = NaiveDateTime::parse_from_str(to, TIMESTAMP_FORMAT).ok()?;
+    Some(((to - from).num_minutes() as f64 / 1440.0 * 100.0).round() / 100.0)
+}
+
+pub fn dwell_times(conn: &Connection, shipment_id: &str) -> Result<ShipmentDwellTimes, String> {
+    let events = load_events(conn, shipment_id)?;
+    let segments = events
+        .windows(2)
+        .filter_map(|pair| {
+            Some(DwellSegment {
+                from_event: pair[0].event_type.clone(),
+                to_event: pair[1].event_type.clone(),
+                from_at: pair[0].occurred_at.clone(),
+                to_at: pair[1].occurred_at.clone(),
+                days: days_between(&pair[0].occurred_at, &pair[1].occurred_at)?,
+            })
+        })
+        .collect();
+    let total_days = match (events.first(), events.last()) {
+        (Some(first), Some(last)) if events.len() > 1 => {
+            days_between(&first.occurred_at, &last.occurred_at)
+        }
+        _ => None,
+    };
+    Ok(ShipmentDwellTimes {
+        shipment_id: shipment_id.to_string(),
+        segments,
+        total_days,
+    })
+}
+
+/// Average, min and max days from `from_event` to `to_event` over shipments that have both,
+/// optionally limited to `from_event` occurring within `date_from`..=`date_to`.
+pub fn dwell_summary(
+    conn: &Connection,
+    from_event: &str,
+    to_event: &str,
+    date_from: Option<&str>,
+    date_to: Option<&str>,
+) -> Result<DwellSummary, String> {
+    let from_event = normalize_event_type(from_event)?;
+    let to_event = normalize_event_type(to_event)?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT a.occurred_at, b.occurred_at
+             FROM shipment_events a
+             JOIN shipment_events b ON b.shipment_id = a.shipment_id AND b.event_type = ?2
+             WHERE a.event_type = ?1
+               AND (?3 IS NULL OR date(a.occurred_at) >= date(?3))
+               AND (?4 IS NULL OR date(a.occurred_at) <= date(?4))",
+        )
+        .map_err(|e| e.to_string())?;
+    let days: Vec<f64> = stmt
+        .query_map(params![from_event, to_event, date_from, date_to], |r| {
+            Ok((r.get::<_, String>(0)?, r.get::<_, String>(1)?))
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .filter_map(|(a, b)| days_between(&a, &b))
+        .collect();
+    let count = days.len();
+    Ok(DwellSummary {
+        from_event: from_event.to_string(),
+        to_event: to_event.to_string(),
+        shipment_count: count as u32,
+        average_days: (count > 0)
+            .then(|| (days.iter().sum::<f64>() / count as f64 * 100.0).round() / 100.0),
+        min_days: days.iter().copied().reduce(f64::min),
+        max_days: days.iter().copied().reduce(f64::max),
+    })
+}
+
+#[tauri::command]
+pub fn record_shipment_event(
+    payload: RecordShipmentEventPayload,
+    state: State<DbState>,
+) -> Result<ShipmentEvent, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    let event = record(&conn, &payload)?;
+    demurrage::refresh_after_change(&conn, &event.shipment_id);
+    if event.event_type == ARRIVED_AT_PORT {
+        eta_slippage::refresh_after_change(&conn, &event.shipment_id);
+    }
+    let _ = dashboard_cache::invalidate_dashboard_metrics_cache(&conn);
+    Ok(event)
+}
+
+#[tauri::command]
+pub fn delete_shipment_event(id: i64, state: State<DbState>) -> Result<(), String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    let (shipment_id, event_type): (String, String) = conn
+        .query_row(
+            "SELECT shipment_id, event_type FROM shipment_events WHERE id = ?1",
+            params![id],
+            |r| Ok((r.get(0)?, r.get(1)?)),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Shipment event {id} not found"))?;
+    conn.execute("DELETE FROM shipment_events WHERE id = ?1", params![id])
+        .map_err(|e| e.to_string())?;
+    sync_status(&conn, &shipment_id)?;
+    demurrage::refresh_after_change(&conn, &shipment_id);
+    if event_type == ARRIVED_AT_PORT {
+        eta_slippage::refresh_after_change(&conn, &shipment_id);
+    }
+    let _ = dashboard_cache::invalidate_dashboard_metrics_cache(&conn);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_shipment_timeline(
+    shipment_id: String,
+    state: State<DbState>,
+) -> Result<ShipmentTimeline, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    let status: Option<String> = conn
+        .query_row(
+            "SELECT status FROM shipments WHERE id = ?1",
+            params![shipment_id],
+            |r| r.get(0),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Shipment {shipment_id} not found"))?;
+    let events = load_events(&conn, &shipment_id)?;
+    let pending = MILESTONES
+        .iter()
+        .filter(|m| !events.iter().any(|e| e.event_type == **m))
+        .map(|m| m.to_string())
+        .collect();
+    Ok(ShipmentTimeline {
+        current_milestone: events.last().map(|e| e.event_type.clone()),
+        shipment_id,
+        status,
+        events,
+        pending,
+    })
+}
+
+#[tauri::command]
+pub fn get_shipment_dwell_times(
+    shipment_id: String,
+    state: State<DbState>,
+) -> Result<ShipmentDwellTimes, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    dwell_times(&conn, &shipment_id)
+}
+
+#[tauri::command]
+pub fn get_milestone_dwell_summary(
+    from_event: String,
+    to_event: String,
+    date_from: Option<String>,
+    date_to: Option<String>,
+    state: State<DbState>,
+) -> Result<DwellSummary, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    dwell_summary(
+        &conn,
+        &from_event,
+        &to_event,
+        date_from.as_deref(),
+        date_to.as_deref(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::test_support;
+
+    fn status(conn: &Connection) -> String {
+        conn.query_row("SELECT status FROM shipments WHERE id = 'SHP-1'", [], |r| {
+            r.get(0)
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn milestones_drive_status_and_dwell_times() {
+        let conn = test_support::migrated_db();
+        test_support::add_shipment(&conn, "SHP-1", "2024-03-01", 1000.0, "USD", "docs-rcvd");
+        let event = |event_type: &str, at: &str| RecordShipmentEventPayload {
+            shipment_id: "SHP-1".into(),
+            event_type: event_type.into(),
+            occurred_at: at.into(),
+            source: None,
+            notes: None,
+            user_id: None,
+        };
+
+        record(&conn, &event("departed", "2024-03-02")).unwrap();
+        assert_eq!(status(&conn), "in-transit");
+        // Recorded out of order: the furthest milestone still decides the status.
+        record(&conn, &event("ARRIVED_AT_PORT", "2024-03-20 06:00")).unwrap();
+        record(&conn, &event("BOOKED", "2024-02-25")).unwrap();
+        assert_eq!(status(&conn), "in-transit");
+        assert!(record(&conn, &event("SAILED", "2024-03-02")).is_err());
+        assert!(record(&conn, &event("BOOKED", "soon")).is_err());
+
+        // A linked BOE adds its filing date as a system milestone.
+        conn.execute_batch(
+            r#"INSERT INTO boe_details (id, be_number, be_date, location, total_assessment_value, duty_amount)
+                VALUES ('BOE-1', '1234567', '2024-03-21', 'INNSA1', 0, 0);
+             INSERT INTO boe_calculations (id, shipment_id, boe_id, supplier_name, invoice_number, form_values_json, item_inputs_json, calculation_result_json)
+                VALUES ('BC-1', 'SHP-1', 'BOE-1', 'Acme', 'INV-1',
+                  '{"supplierName":"Acme","shipmentId":"SHP-1","exchangeRate":80,"freightCost":0,"exwCost":0,"insuranceRate":0}',
+                  '[]',
+                  '{"calculatedItems":[],"bcdTotal":0,"swsTotal":0,"igstTotal":0,"interest":0,"customsDutyTotal":0}');"#,
+        )
+        .unwrap();
+        record_boe_milestones(&conn, "SHP-1");
+        let out_of_charge = record(&conn, &event("out-of-charge", "2024-03-22 18:00")).unwrap();
+        assert_eq!(status(&conn), "ready-dly");
+
+        let dwell = dwell_times(&conn, "SHP-1").unwrap();
+        let steps: Vec<(&str, f64)> = dwell
+            .segments
+            .iter()
+            .map(|s| (s.to_event.as_str(), s.days))
+            .collect();
+        assert_eq!(
+            steps,
+            vec![
+                (DEPARTED, 6.0),
+                (ARRIVED_AT_PORT, 18.25),
+                (BOE_FILED, 0.75),
+                (OUT_OF_CHARGE, 1.75)
+            ]
+        );
+        assert_eq!(dwell.total_days, Some(26.75));
+
+        let summary = dwell_summary(&conn, "ARRIVED_AT_PORT", "OUT_OF_CHARGE", None, None).unwrap();
+        assert_eq!(
+            (summary.shipment_count, summary.average_days),
+            (1, Some(2.5))
+        );
+
+        // Removing the latest milestone steps the status back.
+        conn.execute(
+            "DELETE FROM shipment_events WHERE id = ?1",
+            params![out_of_charge.id],
+        )
+        .unwrap();
+        sync_status(&conn, "SHP-1").unwrap();
+        assert_eq!(status(&conn), "customs-clearance");
+    }
+}
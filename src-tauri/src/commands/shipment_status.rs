@@ -0,0 +1,627 @@
+//! Shipment status state machine: allowed transitions, their guards, and the transition log.
+
+use crate::commands::bonded_warehouse::{
+    BOE_TYPE_INTO_BOND, STATUS_PARTIALLY_CLEARED, STATUS_WAREHOUSED,
+};
+use crate::commands::dashboard_cache;
+use crate::db::DbState;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+pub const DOCS_RECEIVED: &str = "docs-rcvd";
+pub const IN_TRANSIT: &str = "in-transit";
+pub const CUSTOMS_CLEARANCE: &str = "customs-clearance";
+pub const WAREHOUSED: &str = STATUS_WAREHOUSED;
+pub const PARTIALLY_CLEARED: &str = STATUS_PARTIALLY_CLEARED;
+pub const READY_FOR_DELIVERY: &str = "ready-dly";
+pub const DELIVERED: &str = "delivered";
+
+/// Pre-standardisation spelling of `docs-rcvd`, still accepted on input.
+const LEGACY_DOCS_RECEIVED: &str = "docu-received";
+
+pub const STATUSES: [&str; 7] = [
+    DOCS_RECEIVED,
+    IN_TRANSIT,
+    CUSTOMS_CLEARANCE,
+    WAREHOUSED,
+    PARTIALLY_CLEARED,
+    READY_FOR_DELIVERY,
+    DELIVERED,
+];
+
+pub const SOURCE_MANUAL: &str = "MANUAL";
+pub const SOURCE_SYSTEM: &str = "SYSTEM";
+pub const SOURCE_MILESTONE: &str = "MILESTONE";
+
+/// Statuses reachable from `from`. Air and courier consignments may go straight from in transit to
+/// delivered without a recorded customs step; other modes need a BOE for that (see `check_guard`).
+/// Backward moves are corrections (an invoice or BOE removed, a milestone deleted); leaving
+/// `delivered` additionally needs a reason.
+pub fn allowed_from(from: &str) -> &'static [&'static str] {
+    match from {
+        DOCS_RECEIVED => &[IN_TRANSIT, CUSTOMS_CLEARANCE, WAREHOUSED],
+        IN_TRANSIT => &[DOCS_RECEIVED, CUSTOMS_CLEARANCE, WAREHOUSED, DELIVERED],
+        CUSTOMS_CLEARANCE => &[
+            IN_TRANSIT,
+            WAREHOUSED,
+            PARTIALLY_CLEARED,
+            READY_FOR_DELIVERY,
+            DELIVERED,
+        ],
+        WAREHOUSED => &[PARTIALLY_CLEARED, CUSTOMS_CLEARANCE],
+        PARTIALLY_CLEARED => &[WAREHOUSED, CUSTOMS_CLEARANCE],
+        READY_FOR_DELIVERY => &[CUSTOMS_CLEARANCE, DELIVERED],
+        DELIVERED => &[READY_FOR_DELIVERY, CUSTOMS_CLEARANCE],
+        _ => &STATUSES,
+    }
+}
+
+/// Canonical spelling of a status, or `None` if it is not part of the machine.
+pub fn normalize_status(status: &str) -> Option<&'static str> {
+    let status = status.trim().to_ascii_lowercase();
+    if status == LEGACY_DOCS_RECEIVED {
+        return Some(DOCS_RECEIVED);
+    }
+    STATUSES.iter().copied().find(|s| *s == status)
+}
+
+/// Why a status change was refused. Serialized as-is to the frontend.
+#[derive(Debug, Serialize, Clone, PartialEq, thiserror::Error)]
+#[serde(rename_all = "camelCase")]
+#[error("{message}")]
+pub struct TransitionError {
+    /// SHIPMENT_NOT_FOUND, UNKNOWN_STATUS, TRANSITION_NOT_ALLOWED, BOE_REQUIRED,
+    /// INTO_BOND_BOE_REQUIRED, DIRECT_DELIVERY_NOT_ALLOWED, DELIVERY_DATE_REQUIRED,
+    /// REASON_REQUIRED or DATABASE.
+    pub code: &'static str,
+    pub shipment_id: String,
+    pub from: Option<&'static str>,
+    pub to: String,
+    pub message: String,
+    /// Statuses the shipment may move to from its current one.
+    pub allowed: &'static [&'static str],
+}
+
+impl TransitionError {
+    fn new(
+        code: &'static str,
+        shipment_id: &str,
+        from: Option<&'static str>,
+        to: &str,
+        message: String,
+    ) -> Self {
+        Self {
+            code,
+            shipment_id: shipment_id.to_string(),
+            from,
+            to: to.to_string(),
+            message,
+            allowed: from.map_or(&STATUSES, allowed_from),
+        }
+    }
+
+    fn database(shipment_id: &str, to: &str, e: rusqlite::Error) -> Self {
+        Self::new("DATABASE", shipment_id, None, to, e.to_string())
+    }
+}
+
+/// Who asked for a transition and why.
+#[derive(Debug, Clone, Default)]
+pub struct TransitionContext<'a> {
+    pub source: &'a str,
+    pub user_id: Option<&'a str>,
+    pub reason: Option<&'a str>,
+    /// Written to `shipments.date_of_delivery` when moving to `delivered`.
+    pub date_of_delivery: Option<&'a str>,
+}
+
+impl<'a> TransitionContext<'a> {
+    pub fn system(reason: &'a str) -> Self {
+        Self {
+            source: SOURCE_SYSTEM,
+            reason: Some(reason),
+            ..Self::default()
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct StatusTransition {
+    pub id: i64,
+    pub shipment_id: String,
+    pub from_status: Option<String>,
+    pub to_status: String,
+    pub reason: Option<String>,
+    pub source: String,
+    pub changed_by: Option<String>,
+    pub changed_at: String,
+}
+
+fn has_boe(conn: &Connection, shipment_id: &str) -> rusqlite::Result<bool> {
+    conn.query_row(
+        "SELECT EXISTS (SELECT 1 FROM boe_calculations WHERE shipment_id = ?1)",
+        params![shipment_id],
+        |r| r.get(0),
+    )
+}
+
+/// Modes whose consignments are commonly cleared by the carrier and delivered straight from transit.
+fn delivers_direct(shipment_mode: Option<&str>) -> bool {
+    matches!(
+        shipment_mode
+            .map(|m| m.trim().to_ascii_lowercase())
+            .as_deref(),
+        Some("air" | "courier")
+    )
+}
+
+fn has_into_bond_boe(conn: &Connection, shipment_id: &str) -> rusqlite::Result<bool> {
+    conn.query_row(
+        "SELECT EXISTS (SELECT 1 FROM boe_calculations WHERE shipment_id = ?1 AND boe_type = ?2)",
+        params![shipment_id, BOE_TYPE_INTO_BOND],
+        |r| r.get(0),
+    )
+}
+
+/// The precondition `to` places on the shipment, as (code, message) when it is not met.
+fn check_guard(
+    conn: &Connection,
+    shipment_id: &str,
+    from: Option<&str>,
+    to: &str,
+    ctx: &TransitionContext,
+    stored_delivery_date: Option<&str>,
+    shipment_mode: Option<&str>,
+) -> rusqlite::Result<Option<(&'static str, String)>> {
+    let blank = |v: Option<&str>| !matches!(v, Some(s) if !s.trim().is_empty());
+    if from == Some(DELIVERED) && blank(ctx.reason) {
+        return Ok(Some((
+            "REASON_REQUIRED",
+            "A reason is required to move a delivered shipment back".to_string(),
+        )));
+    }
+    if from == Some(IN_TRANSIT)
+        && to == DELIVERED
+        && !delivers_direct(shipment_mode)
+        && !has_boe(conn, shipment_id)?
+    {
+        return Ok(Some((
+            "DIRECT_DELIVERY_NOT_ALLOWED",
+            "Only air and courier shipments can be delivered straight from in-transit without a Bill of Entry".to_string(),
+        )));
+    }
+    let failure = match to {
+        CUSTOMS_CLEARANCE | READY_FOR_DELIVERY if !has_boe(conn, shipment_id)? => Some((
+            "BOE_REQUIRED",
+            format!("Shipment needs a Bill of Entry before it can move to {to}"),
+        )),
+        WAREHOUSED | PARTIALLY_CLEARED if !has_into_bond_boe(conn, shipment_id)? => Some((
+            "INTO_BOND_BOE_REQUIRED",
+            format!("Shipment needs an into-bond Bill of Entry before it can move to {to}"),
+        )),
+        DELIVERED if blank(ctx.date_of_delivery) && blank(stored_delivery_date) => Some((
+            "DELIVERY_DATE_REQUIRED",
+            "A delivery date is required to mark a shipment delivered".to_string(),
+        )),
+        _ => None,
+    };
+    Ok(failure)
+}
+
+/// Moves a shipment to `to` if the transition is allowed and its guard holds, and logs it.
+/// Returns `false` when the shipment already had that status (nothing is logged then).
+pub fn transition(
+    conn: &Connection,
+    shipment_id: &str,
+    to: &str,
+    ctx: &TransitionContext,
+) -> Result<bool, TransitionError> {
+    let Some(target) = normalize_status(to) else {
+        return Err(TransitionError::new(
+            "UNKNOWN_STATUS",
+            shipment_id,
+            None,
+            to,
+            format!("Unknown shipment status '{to}'"),
+        ));
+    };
+    let row: Option<(Option<String>, Option<String>, Option<String>)> = conn
+        .query_row(
+            "SELECT status, date_of_delivery, shipment_mode FROM shipments WHERE id = ?1",
+            params![shipment_id],
+            |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)),
+        )
+        .optional()
+        .map_err(|e| TransitionError::database(shipment_id, target, e))?;
+    let Some((current, stored_delivery_date, shipment_mode)) = row else {
+        return Err(TransitionError::new(
+            "SHIPMENT_NOT_FOUND",
+            shipment_id,
+            None,
+            target,
+            format!("Shipment {shipment_id} not found"),
+        ));
+    };
+    let from = current.as_deref().and_then(normalize_status);
+    if from == Some(target) && current.as_deref() == Some(target) {
+        return Ok(false);
+    }
+    if let Some(from) = from.filter(|f| *f != target) {
+        if !allowed_from(from).contains(&target) {
+            return Err(TransitionError::new(
+                "TRANSITION_NOT_ALLOWED",
+                shipment_id,
+                Some(from),
+                target,
+                format!("Shipment cannot move from {from} to {target}"),
+            ));
+        }
+    }
+    if let Some((code, message)) = check_guard(
+        conn,
+        shipment_id,
+        from,
+        target,
+        ctx,
+        stored_delivery_date.as_deref(),
+        shipment_mode.as_deref(),
+    )
+    .map_err(|e| TransitionError::database(shipment_id, target, e))?
+    {
+        return Err(TransitionError::new(
+            code,
+            shipment_id,
+            from,
+            target,
+            message,
+        ));
+    }
+
+    let delivery_date = ctx
+        .date_of_delivery
+        .filter(|d| target == DELIVERED && !d.trim().is_empty());
+    // Callers already inside a transaction (invoice or BOE saves) keep the write in theirs.
+    let tx = if conn.is_autocommit() {
+        Some(conn.unchecked_transaction())
+    } else {
+        None
+    }
+    .transpose()
+    .map_err(|e| TransitionError::database(shipment_id, target, e))?;
+    conn.execute(
+        "UPDATE shipments SET status = ?2, date_of_delivery = COALESCE(?3, date_of_delivery) WHERE id = ?1",
+        params![shipment_id, target, delivery_date],
+    )
+    .and_then(|_| {
+        conn.execute(
+            "INSERT INTO shipment_status_transitions (shipment_id, from_status, to_status, reason, source, changed_by)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                shipment_id,
+                from,
+                target,
+                ctx.reason.map(str::trim).filter(|r| !r.is_empty()),
+                ctx.source,
+                ctx.user_id
+            ],
+        )
+    })
+    .and_then(|_| tx.map_or(Ok(()), |tx| tx.commit()))
+    .map_err(|e| TransitionError::database(shipment_id, target, e))?;
+    Ok(true)
+}
+
+/// Automatic transition (invoice, BOE, bonded warehouse, delivery check): a refusal is logged and
+/// leaves the status as it was, since the change that triggered it has already been saved.
+pub fn transition_or_log(
+    conn: &Connection,
+    shipment_id: &str,
+    to: &str,
+    ctx: &TransitionContext,
+) -> bool {
+    match transition(conn, shipment_id, to, ctx) {
+        Ok(changed) => changed,
+        Err(e) => {
+            log::warn!(
+                "Automatic status change of shipment {shipment_id} to {to} refused: {}",
+                e.message
+            );
+            false
+        }
+    }
+}
+
+/// Moves every eligible shipment whose BOE was filed at least a week ago to ready-for-delivery.
+/// Returns the ids that moved.
+pub fn advance_ready_for_delivery(conn: &Connection, today: &str) -> Result<Vec<String>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT DISTINCT s.id
+             FROM shipments s
+             JOIN boe_calculations bc ON bc.shipment_id = s.id
+             JOIN boe_details bd ON bd.id = bc.boe_id
+             WHERE s.status = ?1
+               AND bd.be_date IS NOT NULL
+               AND date(bd.be_date, '+7 days') <= date(?2)",
+        )
+        .map_err(|e| e.to_string())?;
+    let ids = stmt
+        .query_map(params![CUSTOMS_CLEARANCE, today], |r| r.get::<_, String>(0))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    let ctx = TransitionContext::system("BOE filed 7 or more days ago");
+    Ok(ids
+        .into_iter()
+        .filter(|id| transition_or_log(conn, id, READY_FOR_DELIVERY, &ctx))
+        .collect())
+}
+
+pub fn list_transitions(
+    conn: &Connection,
+    shipment_id: &str,
+) -> Result<Vec<StatusTransition>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, shipment_id, from_status, to_status, reason, source, changed_by, changed_at
+             FROM shipment_status_transitions WHERE shipment_id = ?1 ORDER BY id",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![shipment_id], |r| {
+            Ok(StatusTransition {
+                id: r.get(0)?,
+                shipment_id: r.get(1)?,
+                from_status: r.get(2)?,
+                to_status: r.get(3)?,
+                reason: r.get(4)?,
+                source: r.get(5)?,
+                changed_by: r.get(6)?,
+                changed_at: r.get(7)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())
+}
+
+/// Manual status change; refusals come back as a structured [`TransitionError`].
+#[tauri::command]
+pub fn update_shipment_status(
+    state: State<DbState>,
+    shipment_id: String,
+    status: String,
+    date_of_delivery: Option<String>,
+    reason: Option<String>,
+    user_id: Option<String>,
+) -> Result<(), TransitionError> {
+    let conn = state.db.lock().map_err(|e| {
+        TransitionError::new("DATABASE", &shipment_id, None, &status, e.to_string())
+    })?;
+    let ctx = TransitionContext {
+        source: SOURCE_MANUAL,
+        user_id: user_id.as_deref(),
+        reason: reason.as_deref(),
+        date_of_delivery: date_of_delivery.as_deref(),
+    };
+    if transition(&conn, &shipment_id, &status, &ctx)? {
+        if normalize_status(&status) == Some(DELIVERED) {
+            crate::commands::shipment_events::record_delivery(
+                &conn,
+                &shipment_id,
+                date_of_delivery.as_deref(),
+            );
+        }
+        crate::commands::shipment_documents::refresh_after_change(&conn, &shipment_id);
+        let _ = dashboard_cache::invalidate_dashboard_metrics_cache(&conn);
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_shipment_status_transitions(
+    state: State<DbState>,
+    shipment_id: String,
+) -> Result<Vec<StatusTransition>, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    list_transitions(&conn, &shipment_id)
+}
+
+/// Statuses the shipment may move to next, before guards are checked.
+#[tauri::command]
+pub fn get_allowed_status_transitions(
+    state: State<DbState>,
+    shipment_id: String,
+) -> Result<Vec<String>, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    let current: Option<String> = conn
+        .query_row(
+            "SELECT status FROM shipments WHERE id = ?1",
+            params![shipment_id],
+            |r| r.get(0),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Shipment {shipment_id} not found"))?;
+    let from = current.as_deref().and_then(normalize_status);
+    Ok(from
+        .map_or(&STATUSES[..], allowed_from)
+        .iter()
+        .map(|s| s.to_string())
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::test_support;
+
+    fn status(conn: &Connection) -> String {
+        conn.query_row("SELECT status FROM shipments WHERE id = 'SHP-1'", [], |r| {
+            r.get(0)
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn transitions_are_guarded_and_logged() {
+        let conn = test_support::migrated_db();
+        test_support::add_shipment(&conn, "SHP-1", "2024-03-01", 1000.0, "USD", "docu-received");
+        let manual = |reason: Option<&'static str>, date: Option<&'static str>| TransitionContext {
+            source: SOURCE_MANUAL,
+            user_id: Some("ops"),
+            reason,
+            date_of_delivery: date,
+        };
+
+        let err = transition(&conn, "SHP-1", "customs-clearance", &manual(None, None)).unwrap_err();
+        assert_eq!(err.code, "BOE_REQUIRED");
+        assert_eq!(err.from, Some(DOCS_RECEIVED));
+        let err = transition(&conn, "SHP-1", "delivered", &manual(None, None)).unwrap_err();
+        assert_eq!(err.code, "TRANSITION_NOT_ALLOWED");
+        assert_eq!(err.allowed, [IN_TRANSIT, CUSTOMS_CLEARANCE, WAREHOUSED]);
+        assert_eq!(
+            transition(&conn, "SHP-1", "shipped", &manual(None, None))
+                .unwrap_err()
+                .code,
+            "UNKNOWN_STATUS"
+        );
+        assert_eq!(status(&conn), "docu-received");
+
+        assert!(transition(&conn, "SHP-1", "In-Transit", &manual(Some("Sailed"), None)).unwrap());
+        assert!(!transition(&conn, "SHP-1", IN_TRANSIT, &manual(None, None)).unwrap());
+
+        conn.execute_batch(
+            "INSERT INTO boe_details (id, be_number, be_date, location, total_assessment_value, duty_amount)
+                VALUES ('BOE-1', '1234567', '2024-03-10', 'INNSA1', 0, 0);
+             INSERT INTO boe_calculations (id, shipment_id, boe_id, supplier_name, invoice_number, form_values_json, item_inputs_json, calculation_result_json)
+                VALUES ('BC-1', 'SHP-1', 'BOE-1', 'Acme', 'INV-1', '{}', '[]', '{}');",
+        )
+        .unwrap();
+        assert!(transition(&conn, "SHP-1", CUSTOMS_CLEARANCE, &manual(None, None)).unwrap());
+        assert_eq!(
+            transition(&conn, "SHP-1", WAREHOUSED, &manual(None, None))
+                .unwrap_err()
+                .code,
+            "INTO_BOND_BOE_REQUIRED"
+        );
+
+        // The weekly check only moves shipments a week past their BOE date.
+        assert!(advance_ready_for_delivery(&conn, "2024-03-16")
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            advance_ready_for_delivery(&conn, "2024-03-17").unwrap(),
+            vec!["SHP-1".to_string()]
+        );
+
+        assert_eq!(
+            transition(&conn, "SHP-1", DELIVERED, &manual(None, None))
+                .unwrap_err()
+                .code,
+            "DELIVERY_DATE_REQUIRED"
+        );
+        transition(&conn, "SHP-1", DELIVERED, &manual(None, Some("2024-03-20"))).unwrap();
+        let delivered_on: String = conn
+            .query_row(
+                "SELECT date_of_delivery FROM shipments WHERE id = 'SHP-1'",
+                [],
+                |r| r.get(0),
+            )
+            .unwrap();
+        assert_eq!(delivered_on, "2024-03-20");
+        assert_eq!(
+            transition(&conn, "SHP-1", READY_FOR_DELIVERY, &manual(None, None))
+                .unwrap_err()
+                .code,
+            "REASON_REQUIRED"
+        );
+
+        let log: Vec<(Option<String>, String, String, Option<String>)> =
+            list_transitions(&conn, "SHP-1")
+                .unwrap()
+                .into_iter()
+                .map(|t| (t.from_status, t.to_status, t.source, t.changed_by))
+                .collect();
+        assert_eq!(
+            log,
+            vec![
+                (
+                    Some(DOCS_RECEIVED.into()),
+                    IN_TRANSIT.into(),
+                    SOURCE_MANUAL.into(),
+                    Some("ops".into())
+                ),
+                (
+                    Some(IN_TRANSIT.into()),
+                    CUSTOMS_CLEARANCE.into(),
+                    SOURCE_MANUAL.into(),
+                    Some("ops".into())
+                ),
+                (
+                    Some(CUSTOMS_CLEARANCE.into()),
+                    READY_FOR_DELIVERY.into(),
+                    SOURCE_SYSTEM.into(),
+                    None
+                ),
+                (
+                    Some(READY_FOR_DELIVERY.into()),
+                    DELIVERED.into(),
+                    SOURCE_MANUAL.into(),
+                    Some("ops".into())
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn in_transit_consignment_can_be_marked_delivered() {
+        let conn = test_support::migrated_db();
+        test_support::add_shipment(&conn, "SHP-1", "2024-03-01", 1000.0, "USD", "in-transit");
+        conn.execute(
+            "UPDATE shipments SET shipment_mode = 'Air' WHERE id = 'SHP-1'",
+            [],
+        )
+        .unwrap();
+        let ctx = |date: Option<&'static str>| TransitionContext {
+            source: SOURCE_MANUAL,
+            user_id: None,
+            reason: None,
+            date_of_delivery: date,
+        };
+        let err = transition(&conn, "SHP-1", DELIVERED, &ctx(None)).unwrap_err();
+        assert_eq!(err.code, "DELIVERY_DATE_REQUIRED");
+        assert!(transition(&conn, "SHP-1", DELIVERED, &ctx(Some("2024-03-05"))).unwrap());
+        assert_eq!(status(&conn), DELIVERED);
+    }
+
+    #[test]
+    fn in_transit_sea_shipment_needs_a_boe_to_be_delivered() {
+        let conn = test_support::migrated_db();
+        test_support::add_shipment(&conn, "SHP-1", "2024-03-01", 1000.0, "USD", "in-transit");
+        conn.execute(
+            "UPDATE shipments SET shipment_mode = 'Sea' WHERE id = 'SHP-1'",
+            [],
+        )
+        .unwrap();
+        let ctx = TransitionContext {
+            source: SOURCE_MANUAL,
+            user_id: None,
+            reason: None,
+            date_of_delivery: Some("2024-03-20"),
+        };
+        let err = transition(&conn, "SHP-1", DELIVERED, &ctx).unwrap_err();
+        assert_eq!(err.code, "DIRECT_DELIVERY_NOT_ALLOWED");
+        assert_eq!(status(&conn), IN_TRANSIT);
+
+        conn.execute_batch(
+            "INSERT INTO boe_calculations (id, shipment_id, supplier_name, invoice_number, form_values_json, item_inputs_json, calculation_result_json)
+                VALUES ('BC-1', 'SHP-1', 'Acme', 'INV-1', '{}', '[]', '{}');",
+        )
+        .unwrap();
+        assert!(transition(&conn, "SHP-1", DELIVERED, &ctx).unwrap());
+        assert_eq!(status(&conn), DELIVERED);
+    }
+}
@@ -1,4 +1,9 @@
+use crate::commands::bonded_warehouse;
+use crate::commands::costing_snapshots;
 use crate::commands::dashboard_cache;
+use crate::commands::eta_slippage;
+use crate::commands::shipment_events;
+use crate::commands::shipment_status::{self, TransitionContext};
 use crate::DbState;
 use crate::Shipment;
 use rusqlite::params;
@@ -103,7 +108,11 @@ pub fn add_shipment(state: State<DbState>, shipment: Shipment) -> Result<(), Str
     let conn = state.db.lock().unwrap();
 
     // Set initial status to "docs-rcvd" if not provided
-    let initial_status = shipment.status.as_deref().unwrap_or("docs-rcvd");
+    let initial_status = match shipment.status.as_deref() {
+        Some(status) => shipment_status::normalize_status(status)
+            .ok_or_else(|| format!("Unknown shipment status '{status}'"))?,
+        None => shipment_status::DOCS_RECEIVED,
+    };
 
     conn.execute(
         "INSERT INTO shipments (id, supplier_id, invoice_number, invoice_date, goods_category, invoice_value, invoice_currency, incoterm, shipment_mode, shipment_type, bl_awb_number, bl_awb_date, vessel_name, container_number, gross_weight_kg, etd, eta, status, date_of_delivery, is_frozen) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20)",
@@ -138,10 +147,37 @@ pub fn add_shipment(state: State<DbState>, shipment: Shipment) -> Result<(), Str
 }
 
 #[tauri::command]
-pub fn update_shipment(state: State<DbState>, shipment: Shipment) -> Result<(), String> {
+pub fn update_shipment(
+    state: State<DbState>,
+    shipment: Shipment,
+    reason: Option<String>,
+    user_id: Option<String>,
+) -> Result<(), String> {
     let conn = state.db.lock().unwrap();
-    conn.execute(
-        "UPDATE shipments SET supplier_id = ?2, invoice_number = ?3, invoice_date = ?4, goods_category = ?5, invoice_value = ?6, invoice_currency = ?7, incoterm = ?8, shipment_mode = ?9, shipment_type = ?10, bl_awb_number = ?11, bl_awb_date = ?12, vessel_name = ?13, container_number = ?14, gross_weight_kg = ?15, etd = ?16, eta = ?17, status = ?18, date_of_delivery = ?19, is_frozen = ?20 WHERE id = ?1",
+    // Freezing and reopening go through freeze_shipment / reopen_shipment, never a plain edit.
+    costing_snapshots::ensure_not_frozen(&conn, &shipment.id)?;
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    // A status edit is a transition like any other; the row update below then keeps it.
+    if let Some(status) = shipment.status.as_deref() {
+        let ctx = TransitionContext {
+            source: shipment_status::SOURCE_MANUAL,
+            user_id: user_id.as_deref(),
+            reason: reason.as_deref(),
+            date_of_delivery: shipment.date_of_delivery.as_deref(),
+        };
+        shipment_status::transition(&tx, &shipment.id, status, &ctx).map_err(|e| e.message)?;
+    }
+    let schedule_changes = eta_slippage::record_schedule_change(
+        &tx,
+        &shipment.id,
+        shipment.etd.as_deref(),
+        shipment.eta.as_deref(),
+        shipment_status::SOURCE_MANUAL,
+        None,
+        user_id.as_deref(),
+    )?;
+    tx.execute(
+        "UPDATE shipments SET supplier_id = ?2, invoice_number = ?3, invoice_date = ?4, goods_category = ?5, invoice_value = ?6, invoice_currency = ?7, incoterm = ?8, shipment_mode = ?9, shipment_type = ?10, bl_awb_number = ?11, bl_awb_date = ?12, vessel_name = ?13, container_number = ?14, gross_weight_kg = ?15, etd = ?16, eta = ?17, status = COALESCE(?18, status), date_of_delivery = ?19 WHERE id = ?1",
         params![
             shipment.id,
             shipment.supplier_id,
@@ -160,12 +196,25 @@ pub fn update_shipment(state: State<DbState>, shipment: Shipment) -> Result<(),
             shipment.gross_weight_kg,
             shipment.etd,
             shipment.eta,
-            shipment.status,
+            shipment
+                .status
+                .as_deref()
+                .and_then(shipment_status::normalize_status),
             shipment.date_of_delivery,
-            shipment.is_frozen,
         ],
     )
     .map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+    if let Some(date) = shipment
+        .date_of_delivery
+        .as_deref()
+        .filter(|d| !d.trim().is_empty())
+    {
+        shipment_events::record_delivery(&conn, &shipment.id, Some(date));
+    }
+    if schedule_changes > 0 {
+        eta_slippage::refresh_after_change(&conn, &shipment.id);
+    }
 
     let _ = dashboard_cache::invalidate_dashboard_metrics_cache(&conn);
 
@@ -191,12 +240,12 @@ pub fn update_shipment_status_on_invoice_add(
 ) -> Result<(), String> {
     let conn = state.db.lock().unwrap();
 
-    // Only update if current status is not "delivered"
-    conn.execute(
-        "UPDATE shipments SET status = 'in-transit' WHERE id = ?1 AND status != 'delivered'",
-        params![shipment_id],
-    )
-    .map_err(|e| e.to_string())?;
+    shipment_status::transition_or_log(
+        &conn,
+        &shipment_id,
+        shipment_status::IN_TRANSIT,
+        &TransitionContext::system("Invoice added"),
+    );
 
     let _ = dashboard_cache::invalidate_dashboard_metrics_cache(&conn);
 
@@ -210,12 +259,15 @@ pub fn update_shipment_status_on_boe_add(
 ) -> Result<(), String> {
     let conn = state.db.lock().unwrap();
 
-    // Only update if current status is not "delivered"
-    conn.execute(
-        "UPDATE shipments SET status = 'customs-clearance' WHERE id = ?1 AND status != 'delivered'",
-        params![shipment_id],
-    )
-    .map_err(|e| e.to_string())?;
+    // Bonded shipments reflect partial clearance
+    if !bonded_warehouse::sync_shipment_status(&conn, &shipment_id)? {
+        shipment_status::transition_or_log(
+            &conn,
+            &shipment_id,
+            shipment_status::CUSTOMS_CLEARANCE,
+            &TransitionContext::system("Bill of Entry added"),
+        );
+    }
 
     let _ = dashboard_cache::invalidate_dashboard_metrics_cache(&conn);
 
@@ -226,30 +278,13 @@ pub fn update_shipment_status_on_boe_add(
 pub fn check_and_update_ready_for_delivery(state: State<DbState>) -> Result<(), String> {
     let conn = state.db.lock().unwrap();
 
-    // Get current date
     let today = chrono::Utc::now().date_naive();
+    // Shipments in customs clearance whose BOE was filed at least a week ago
+    let moved = shipment_status::advance_ready_for_delivery(&conn, &today.to_string())?;
 
-    // Update shipments to "ready-dly" if:
-    // 1. They have BOE entries with dates
-    // 2. BOE date + 7 days <= today
-    // 3. Current status is not "delivered"
-    let sql = "
-        UPDATE shipments 
-        SET status = 'ready-dly'
-        WHERE id IN (
-            SELECT DISTINCT s.id 
-            FROM shipments s
-            INNER JOIN boe_details bd ON s.id = bd.shipment_id
-            WHERE s.status != 'delivered'
-              AND bd.be_date IS NOT NULL
-              AND date(bd.be_date, '+7 days') <= date(?1)
-        )
-    ";
-
-    conn.execute(sql, params![today.to_string()])
-        .map_err(|e| e.to_string())?;
-
-    let _ = dashboard_cache::invalidate_dashboard_metrics_cache(&conn);
+    if !moved.is_empty() {
+        let _ = dashboard_cache::invalidate_dashboard_metrics_cache(&conn);
+    }
 
     Ok(())
 }
@@ -259,11 +294,19 @@ pub fn migrate_shipment_statuses(state: State<DbState>) -> Result<(), String> {
     let conn = state.db.lock().unwrap();
 
     // Update legacy status values to new standardized values
-    conn.execute(
-        "UPDATE shipments SET status = 'docs-rcvd' WHERE status = 'docu-received'",
-        [],
-    )
-    .map_err(|e| e.to_string())?;
+    let mut stmt = conn
+        .prepare("SELECT id FROM shipments WHERE status = 'docu-received'")
+        .map_err(|e| e.to_string())?;
+    let ids = stmt
+        .query_map([], |row| row.get::<_, String>(0))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    let ctx = TransitionContext::system("Legacy status renamed");
+    for id in ids {
+        shipment_status::transition(&conn, &id, shipment_status::DOCS_RECEIVED, &ctx)
+            .map_err(|e| e.message)?;
+    }
 
     let _ = dashboard_cache::invalidate_dashboard_metrics_cache(&conn);
 
@@ -323,6 +366,18 @@ pub fn add_shipments_bulk(state: State<DbState>, shipments: Vec<Shipment>) -> Re
     let conn = state.db.lock().unwrap();
 
     for shipment in shipments {
+        let status = shipment
+            .status
+            .as_deref()
+            .map(|status| {
+                shipment_status::normalize_status(status).ok_or_else(|| {
+                    format!(
+                        "Shipment {}: unknown status '{status}'",
+                        shipment.invoice_number
+                    )
+                })
+            })
+            .transpose()?;
         conn.execute(
             "INSERT INTO shipments (
                 id, supplier_id, invoice_number, invoice_date, goods_category, 
@@ -348,7 +403,7 @@ pub fn add_shipments_bulk(state: State<DbState>, shipments: Vec<Shipment>) -> Re
                 shipment.gross_weight_kg,
                 shipment.etd,
                 shipment.eta,
-                shipment.status,
+                status,
                 shipment.date_of_delivery,
                 shipment.is_frozen,
             ],
@@ -410,31 +465,13 @@ pub fn get_unfinalized_shipments(state: State<DbState>) -> Result<Vec<Shipment>,
 }
 
 #[tauri::command]
-pub fn freeze_shipment(state: State<DbState>, id: String) -> Result<(), String> {
-    let conn = state.db.lock().unwrap();
-    conn.execute(
-        "UPDATE shipments SET is_frozen = 1 WHERE id = ?1",
-        params![id],
-    )
-    .map_err(|e| e.to_string())?;
-
-    let _ = dashboard_cache::invalidate_dashboard_metrics_cache(&conn);
-
-    Ok(())
-}
-
-#[tauri::command]
-pub fn update_shipment_status(
+pub fn freeze_shipment(
     state: State<DbState>,
-    id: String,
-    status: String,
+    shipment_id: String,
+    user_id: Option<String>,
 ) -> Result<(), String> {
     let conn = state.db.lock().unwrap();
-    conn.execute(
-        "UPDATE shipments SET status = ?1 WHERE id = ?2",
-        params![status, id],
-    )
-    .map_err(|e| e.to_string())?;
+    costing_snapshots::freeze(&conn, &shipment_id, user_id.as_deref())?;
 
     let _ = dashboard_cache::invalidate_dashboard_metrics_cache(&conn);
 
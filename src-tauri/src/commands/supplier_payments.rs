@@ -0,0 +1,923 @@
+//! Supplier payments: remittances against import invoices, outstanding balances, realised forex, ledger and ageing.
+
+use crate::commands::dashboard_cache;
+use crate::commands::exchange_rates;
+use crate::commands::tariff;
+use crate::commands::utils::generate_id;
+use crate::db::DbState;
+use crate::duty_engine::round_paise;
+use chrono::NaiveDate;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use tauri::State;
+
+pub const ADVANCE: &str = "ADVANCE";
+pub const AGAINST_DOCUMENTS: &str = "AGAINST_DOCUMENTS";
+
+pub const UNPAID: &str = "UNPAID";
+pub const PARTIALLY_PAID: &str = "PARTIALLY_PAID";
+pub const PAID: &str = "PAID";
+pub const OVERPAID: &str = "OVERPAID";
+
+/// Amounts within a cent of each other are settled.
+const AMOUNT_TOLERANCE: f64 = 0.005;
+
+/// Upper bounds (days since invoice date) of the ageing buckets; the last bucket is open-ended.
+const AGEING_BUCKETS: [i64; 4] = [30, 60, 90, 180];
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SupplierPayment {
+    pub id: String,
+    pub supplier_id: String,
+    pub supplier_name: Option<String>,
+    pub shipment_id: Option<String>,
+    pub invoice_number: Option<String>,
+    pub payment_type: String,
+    pub payment_date: String,
+    pub currency: String,
+    pub amount: f64,
+    pub exchange_rate: f64,
+    /// `amount` × `exchange_rate`.
+    pub inr_amount: f64,
+    pub bank_charges: f64,
+    pub bank_reference: Option<String>,
+    pub bank_name: Option<String>,
+    pub notes: Option<String>,
+    pub created_by: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+    /// Customs rate of the linked invoice; `None` for an unlinked advance or an unnotified currency.
+    pub customs_rate: Option<f64>,
+    /// `amount` × (customs rate − remittance rate); positive is a gain.
+    pub realised_fx_gain_loss: Option<f64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SaveSupplierPaymentPayload {
+    /// Omit to record a new payment.
+    pub id: Option<String>,
+    pub supplier_id: String,
+    pub shipment_id: Option<String>,
+    pub payment_type: String,
+    pub payment_date: String,
+    pub currency: String,
+    pub amount: f64,
+    pub exchange_rate: f64,
+    #[serde(default)]
+    pub bank_charges: f64,
+    pub bank_reference: Option<String>,
+    pub bank_name: Option<String>,
+    pub notes: Option<String>,
+    pub user_id: Option<String>,
+}
+
+/// Payment position of one import invoice (shipment).
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct InvoicePaymentPosition {
+    pub shipment_id: String,
+    pub supplier_id: String,
+    pub supplier_name: Option<String>,
+    pub invoice_number: String,
+    pub invoice_date: String,
+    pub currency: String,
+    pub invoice_value: f64,
+    pub paid_amount: f64,
+    pub outstanding_amount: f64,
+    /// `UNPAID`, `PARTIALLY_PAID`, `PAID` or `OVERPAID`.
+    pub payment_status: String,
+    pub payments: u32,
+    pub last_payment_date: Option<String>,
+    /// INR per unit on the BE date (today while no BE is linked); `None` if not notified.
+    pub customs_rate: Option<f64>,
+    pub customs_rate_as_of: Option<String>,
+    /// INR actually remitted for the paid amount.
+    pub remitted_inr: f64,
+    pub realised_fx_gain_loss: Option<f64>,
+    pub bank_charges: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct LedgerEntry {
+    pub date: String,
+    /// `INVOICE` or `PAYMENT`.
+    pub entry_type: String,
+    pub reference: String,
+    pub shipment_id: Option<String>,
+    pub payment_id: Option<String>,
+    pub invoice_amount: f64,
+    pub payment_amount: f64,
+    /// Owed to the supplier after this entry.
+    pub balance: f64,
+}
+
+/// Supplier statement in one currency; payments not yet set against an invoice reduce the balance too.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SupplierStatement {
+    pub supplier_id: String,
+    pub supplier_name: Option<String>,
+    pub currency: String,
+    pub from_date: Option<String>,
+    pub to_date: Option<String>,
+    pub opening_balance: f64,
+    pub entries: Vec<LedgerEntry>,
+    pub closing_balance: f64,
+    /// Advances not yet linked to an invoice, up to `to_date`.
+    pub unapplied_advances: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct PayablesAgeingRow {
+    pub supplier_id: String,
+    pub supplier_name: Option<String>,
+    pub currency: String,
+    pub invoices: u32,
+    pub days_0_to_30: f64,
+    pub days_31_to_60: f64,
+    pub days_61_to_90: f64,
+    pub days_91_to_180: f64,
+    pub over_180: f64,
+    pub total_outstanding: f64,
+    /// Outstanding at each invoice's customs rate; `None` if any invoice lacks one.
+    pub total_outstanding_inr: Option<f64>,
+}
+
+fn round2(v: f64) -> f64 {
+    (v * 100.0).round() / 100.0
+}
+
+fn parse_date(raw: &str) -> Option<NaiveDate> {
+    let iso = tariff::normalize_date(raw.get(..10).unwrap_or(raw))?;
+    NaiveDate::parse_from_str(&iso, "%Y-%m-%d").ok()
+}
+
+fn customs_rate(
+    conn: &Connection,
+    shipment_id: &str,
+) -> Result<Option<exchange_rates::ResolvedExchangeRate>, String> {
+    exchange_rates::resolve_for_shipment(conn, shipment_id, None)
+}
+
+fn fx_gain_loss(amount: f64, customs: Option<f64>, remittance_rate: f64) -> Option<f64> {
+    customs.map(|c| round_paise(amount * (c - remittance_rate)))
+}
+
+const PAYMENT_COLUMNS: &str = "p.id, p.supplier_id, su.supplier_name, p.shipment_id, s.invoice_number, p.payment_type,
+    p.payment_date, p.currency, p.amount, p.exchange_rate, p.bank_charges, p.bank_reference, p.bank_name, p.notes,
+    p.created_by, p.created_at, p.updated_at";
+
+fn map_payment(r: &rusqlite::Row) -> rusqlite::Result<SupplierPayment> {
+    let amount: f64 = r.get(8)?;
+    let exchange_rate: f64 = r.get(9)?;
+    Ok(SupplierPayment {
+        id: r.get(0)?,
+        supplier_id: r.get(1)?,
+        supplier_name: r.get(2)?,
+        shipment_id: r.get(3)?,
+        invoice_number: r.get(4)?,
+        payment_type: r.get(5)?,
+        payment_date: r.get(6)?,
+        currency: r.get(7)?,
+        amount,
+        exchange_rate,
+        inr_amount: round_paise(amount * exchange_rate),
+        bank_charges: r.get(10)?,
+        bank_reference: r.get(11)?,
+        bank_name: r.get(12)?,
+        notes: r.get(13)?,
+        created_by: r.get(14)?,
+        created_at: r.get(15)?,
+        updated_at: r.get(16)?,
+        customs_rate: None,
+        realised_fx_gain_loss: None,
+    })
+}
+
+/// Payments of a supplier and/or invoice, oldest first, with realised forex against the invoice's customs rate.
+pub fn list_payments(
+    conn: &Connection,
+    supplier_id: Option<&str>,
+    shipment_id: Option<&str>,
+) -> Result<Vec<SupplierPayment>, String> {
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {PAYMENT_COLUMNS}
+             FROM supplier_payments p
+             LEFT JOIN suppliers su ON su.id = p.supplier_id
+             LEFT JOIN shipments s ON s.id = p.shipment_id
+             WHERE (?1 IS NULL OR p.supplier_id = ?1) AND (?2 IS NULL OR p.shipment_id = ?2)
+             ORDER BY p.payment_date, p.created_at, p.id"
+        ))
+        .map_err(|e| e.to_string())?;
+    let mut payments = stmt
+        .query_map(params![supplier_id, shipment_id], map_payment)
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    let mut rates: BTreeMap<String, Option<f64>> = BTreeMap::new();
+    for p in payments.iter_mut() {
+        let Some(shipment_id) = p.shipment_id.clone() else {
+            continue;
+        };
+        let rate = match rates.get(&shipment_id) {
+            Some(rate) => *rate,
+            None => {
+                let rate = customs_rate(conn, &shipment_id)?.map(|r| r.rate);
+                rates.insert(shipment_id, rate);
+                rate
+            }
+        };
+        p.customs_rate = rate;
+        p.realised_fx_gain_loss = fx_gain_loss(p.amount, rate, p.exchange_rate);
+    }
+    Ok(payments)
+}
+
+fn load_payment(conn: &Connection, id: &str) -> Result<SupplierPayment, String> {
+    let supplier_id: Option<String> = conn
+        .query_row(
+            "SELECT supplier_id FROM supplier_payments WHERE id = ?1",
+            params![id],
+            |r| r.get(0),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+    let supplier_id = supplier_id.ok_or_else(|| format!("Payment {id} not found"))?;
+    list_payments(conn, Some(&supplier_id), None)?
+        .into_iter()
+        .find(|p| p.id == id)
+        .ok_or_else(|| format!("Payment {id} not found"))
+}
+
+/// Records or replaces a payment. A linked payment must be in the invoice's currency, for the
+/// invoice's supplier, and may not take total payments past the invoice value.
+pub fn upsert_payment(
+    conn: &Connection,
+    payload: SaveSupplierPaymentPayload,
+) -> Result<SupplierPayment, String> {
+    let payment_type = payload.payment_type.trim().to_uppercase();
+    if payment_type != ADVANCE && payment_type != AGAINST_DOCUMENTS {
+        return Err(format!("Unknown payment type '{}'", payload.payment_type));
+    }
+    let payment_date = tariff::normalize_date(&payload.payment_date)
+        .ok_or_else(|| format!("Invalid payment date '{}'", payload.payment_date))?;
+    let currency = payload.currency.trim().to_uppercase();
+    if currency.is_empty() {
+        return Err("Payment currency is required".to_string());
+    }
+    if !payload.amount.is_finite() || payload.amount <= 0.0 {
+        return Err("Payment amount must be positive".to_string());
+    }
+    if !payload.exchange_rate.is_finite() || payload.exchange_rate <= 0.0 {
+        return Err("Remittance exchange rate must be positive".to_string());
+    }
+    if !payload.bank_charges.is_finite() || payload.bank_charges < 0.0 {
+        return Err("Bank charges cannot be negative".to_string());
+    }
+    let supplier_exists: bool = conn
+        .query_row(
+            "SELECT EXISTS (SELECT 1 FROM suppliers WHERE id = ?1)",
+            params![payload.supplier_id],
+            |r| r.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+    if !supplier_exists {
+        return Err(format!("Supplier {} not found", payload.supplier_id));
+    }
+    if let Some(id) = payload.id.as_deref() {
+        let existing = load_payment(conn, id)?;
+        let hedged: f64 = conn
+            .query_row(
+                "SELECT COALESCE(SUM(amount), 0) FROM forward_contract_allocations WHERE payment_id = ?1",
+                params![id],
+                |r| r.get(0),
+            )
+            .map_err(|e| e.to_string())?;
+        if hedged > 0.0 {
+            if existing.currency != currency {
+                return Err(
+                    "The currency of a payment covered by forward contracts cannot change"
+                        .to_string(),
+                );
+            }
+            if payload.amount + AMOUNT_TOLERANCE < hedged {
+                return Err(format!(
+                    "{hedged} {currency} of this payment is covered by forward contracts; the amount cannot go below it"
+                ));
+            }
+        }
+    }
+    let id = payload
+        .id
+        .clone()
+        .unwrap_or_else(|| generate_id(Some("PAY".to_string())));
+
+    let shipment_id = payload
+        .shipment_id
+        .as_deref()
+        .map(str::trim)
+        .filter(|s| !s.is_empty());
+    match shipment_id {
+        None if payment_type == AGAINST_DOCUMENTS => {
+            return Err("A payment against documents must name the invoice it pays".to_string())
+        }
+        None => {}
+        Some(shipment_id) => {
+            let shipment: Option<(String, String, String, f64)> = conn
+                .query_row(
+                    "SELECT supplier_id, invoice_number, invoice_currency, invoice_value FROM shipments WHERE id = ?1",
+                    params![shipment_id],
+                    |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?, r.get(3)?)),
+                )
+                .optional()
+                .map_err(|e| e.to_string())?;
+            let Some((supplier_id, invoice_number, invoice_currency, invoice_value)) = shipment
+            else {
+                return Err(format!("Shipment {shipment_id} not found"));
+            };
+            if supplier_id != payload.supplier_id {
+                return Err(format!(
+                    "Invoice {invoice_number} belongs to a different supplier"
+                ));
+            }
+            if !invoice_currency.trim().eq_ignore_ascii_case(&currency) {
+                return Err(format!(
+                    "Invoice {invoice_number} is in {invoice_currency}; the payment is in {currency}"
+                ));
+            }
+            let paid_elsewhere: f64 = conn
+                .query_row(
+                    "SELECT COALESCE(SUM(amount), 0) FROM supplier_payments WHERE shipment_id = ?1 AND id <> ?2",
+                    params![shipment_id, id],
+                    |r| r.get(0),
+                )
+                .map_err(|e| e.to_string())?;
+            let outstanding = invoice_value - paid_elsewhere;
+            if payload.amount > outstanding + AMOUNT_TOLERANCE {
+                return Err(format!(
+                    "Payment of {} {currency} exceeds the {:.2} {currency} outstanding on invoice {invoice_number}",
+                    payload.amount,
+                    outstanding.max(0.0)
+                ));
+            }
+        }
+    }
+
+    conn.execute(
+        "INSERT INTO supplier_payments (id, supplier_id, shipment_id, payment_type, payment_date, currency, amount,
+            exchange_rate, bank_charges, bank_reference, bank_name, notes, created_by)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)
+         ON CONFLICT (id) DO UPDATE SET
+            supplier_id = excluded.supplier_id,
+            shipment_id = excluded.shipment_id,
+            payment_type = excluded.payment_type,
+            payment_date = excluded.payment_date,
+            currency = excluded.currency,
+            amount = excluded.amount,
+            exchange_rate = excluded.exchange_rate,
+            bank_charges = excluded.bank_charges,
+            bank_reference = excluded.bank_reference,
+            bank_name = excluded.bank_name,
+            notes = excluded.notes,
+            updated_at = strftime('%Y-%m-%d %H:%M:%S', 'now', 'localtime')",
+        params![
+            id,
+            payload.supplier_id,
+            shipment_id,
+            payment_type,
+            payment_date,
+            currency,
+            payload.amount,
+            payload.exchange_rate,
+            payload.bank_charges,
+            payload.bank_reference,
+            payload.bank_name,
+            payload.notes,
+            payload.user_id
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    load_payment(conn, &id)
+}
+
+/// Payment position of every import invoice, optionally for one supplier, oldest invoice first.
+pub fn invoice_positions(
+    conn: &Connection,
+    supplier_id: Option<&str>,
+) -> Result<Vec<InvoicePaymentPosition>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT s.id, s.supplier_id, su.supplier_name, s.invoice_number, s.invoice_date,
+                    UPPER(TRIM(s.invoice_currency)), s.invoice_value,
+                    COALESCE(SUM(p.amount), 0), COUNT(p.id), MAX(p.payment_date),
+                    COALESCE(SUM(p.amount * p.exchange_rate), 0), COALESCE(SUM(p.bank_charges), 0)
+             FROM shipments s
+             LEFT JOIN suppliers su ON su.id = s.supplier_id
+             LEFT JOIN supplier_payments p ON p.shipment_id = s.id
+             WHERE (?1 IS NULL OR s.supplier_id = ?1)
+             GROUP BY s.id
+             ORDER BY s.invoice_date, s.invoice_number",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![supplier_id], |r| {
+            let invoice_value: f64 = r.get(6)?;
+            let paid_amount: f64 = r.get(7)?;
+            let outstanding = invoice_value - paid_amount;
+            let payments: i64 = r.get(8)?;
+            let payment_status = if outstanding < -AMOUNT_TOLERANCE {
+                OVERPAID
+            } else if outstanding <= AMOUNT_TOLERANCE {
+                PAID
+            } else if payments > 0 {
+                PARTIALLY_PAID
+            } else {
+                UNPAID
+            };
+            Ok(InvoicePaymentPosition {
+                shipment_id: r.get(0)?,
+                supplier_id: r.get(1)?,
+                supplier_name: r.get(2)?,
+                invoice_number: r.get(3)?,
+                invoice_date: r.get(4)?,
+                currency: r.get(5)?,
+                invoice_value,
+                paid_amount: round2(paid_amount),
+                outstanding_amount: round2(outstanding),
+                payment_status: payment_status.to_string(),
+                payments: payments as u32,
+                last_payment_date: r.get(9)?,
+                customs_rate: None,
+                customs_rate_as_of: None,
+                remitted_inr: round_paise(r.get(10)?),
+                realised_fx_gain_loss: None,
+                bank_charges: round_paise(r.get(11)?),
+            })
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    let mut out = Vec::with_capacity(rows.len());
+    for mut pos in rows {
+        if let Some(rate) = customs_rate(conn, &pos.shipment_id)? {
+            if pos.payments > 0 {
+                // Booked at the customs rate, paid at the remittance rates.
+                pos.realised_fx_gain_loss =
+                    Some(round_paise(pos.paid_amount * rate.rate - pos.remitted_inr));
+            }
+            pos.customs_rate = Some(rate.rate);
+            pos.customs_rate_as_of = Some(rate.as_of);
+        }
+        out.push(pos);
+    }
+    Ok(out)
+}
+
+/// Per-currency statements for a supplier between `from` and `to` (inclusive, either optional).
+pub fn supplier_statement(
+    conn: &Connection,
+    supplier_id: &str,
+    from: Option<&str>,
+    to: Option<&str>,
+) -> Result<Vec<SupplierStatement>, String> {
+    let from = from
+        .map(|d| tariff::normalize_date(d).ok_or_else(|| format!("Invalid from date '{d}'")))
+        .transpose()?;
+    let to = to
+        .map(|d| tariff::normalize_date(d).ok_or_else(|| format!("Invalid to date '{d}'")))
+        .transpose()?;
+    let supplier_name: Option<String> = conn
+        .query_row(
+            "SELECT supplier_name FROM suppliers WHERE id = ?1",
+            params![supplier_id],
+            |r| r.get(0),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+    if supplier_name.is_none() {
+        return Err(format!("Supplier {supplier_id} not found"));
+    }
+
+    // (currency, date, order within the day, entry); invoices sort ahead of same-day payments.
+    let mut raw: Vec<(String, String, u8, LedgerEntry)> = Vec::new();
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, invoice_number, invoice_date, UPPER(TRIM(invoice_currency)), invoice_value
+             FROM shipments WHERE supplier_id = ?1",
+        )
+        .map_err(|e| e.to_string())?;
+    let invoices = stmt
+        .query_map(params![supplier_id], |r| {
+            Ok((
+                r.get::<_, String>(0)?,
+                r.get::<_, String>(1)?,
+                r.get::<_, String>(2)?,
+                r.get::<_, String>(3)?,
+                r.get::<_, f64>(4)?,
+            ))
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    for (shipment_id, invoice_number, invoice_date, currency, value) in invoices {
+        let date = tariff::normalize_date(&invoice_date).unwrap_or(invoice_date);
+        raw.push((
+            currency,
+            date.clone(),
+            0,
+            LedgerEntry {
+                date,
+                entry_type: "INVOICE".to_string(),
+                reference: invoice_number,
+                shipment_id: Some(shipment_id),
+                payment_id: None,
+                invoice_amount: value,
+                payment_amount: 0.0,
+                balance: 0.0,
+            },
+        ));
+    }
+    let mut unapplied: BTreeMap<String, f64> = BTreeMap::new();
+    for p in list_payments(conn, Some(supplier_id), None)? {
+        if p.shipment_id.is_none()
+            && !matches!(to.as_deref(), Some(t) if p.payment_date.as_str() > t)
+        {
+            *unapplied.entry(p.currency.clone()).or_default() += p.amount;
+        }
+        let reference = p
+            .bank_reference
+            .clone()
+            .or_else(|| p.invoice_number.clone())
+            .unwrap_or_else(|| p.id.clone());
+        raw.push((
+            p.currency.clone(),
+            p.payment_date.clone(),
+            1,
+            LedgerEntry {
+                date: p.payment_date,
+                entry_type: "PAYMENT".to_string(),
+                reference,
+                shipment_id: p.shipment_id,
+                payment_id: Some(p.id),
+                invoice_amount: 0.0,
+                payment_amount: p.amount,
+                balance: 0.0,
+            },
+        ));
+    }
+    raw.sort_by(|a, b| (&a.0, &a.1, a.2).cmp(&(&b.0, &b.1, b.2)));
+
+    let mut statements: BTreeMap<String, SupplierStatement> = BTreeMap::new();
+    for (currency, date, _, mut entry) in raw {
+        let st = statements
+            .entry(currency.clone())
+            .or_insert_with(|| SupplierStatement {
+                supplier_id: supplier_id.to_string(),
+                supplier_name: supplier_name.clone(),
+                currency: currency.clone(),
+                from_date: from.clone(),
+                to_date: to.clone(),
+                opening_balance: 0.0,
+                entries: Vec::new(),
+                closing_balance: 0.0,
+                unapplied_advances: round2(unapplied.get(&currency).copied().unwrap_or(0.0)),
+            });
+        let delta = entry.invoice_amount - entry.payment_amount;
+        if from.as_deref().is_some_and(|f| date.as_str() < f) {
+            st.opening_balance = round2(st.opening_balance + delta);
+            st.closing_balance = st.opening_balance;
+            continue;
+        }
+        if to.as_deref().is_some_and(|t| date.as_str() > t) {
+            continue;
+        }
+        st.closing_balance = round2(st.closing_balance + delta);
+        entry.balance = st.closing_balance;
+        st.entries.push(entry);
+    }
+    Ok(statements.into_values().collect())
+}
+
+/// Outstanding import invoices bucketed by days since invoice date as of `as_of`, per supplier and currency.
+pub fn payables_ageing(conn: &Connection, as_of: &str) -> Result<Vec<PayablesAgeingRow>, String> {
+    let as_of_date = parse_date(as_of).ok_or_else(|| format!("Invalid as-of date '{as_of}'"))?;
+    let mut rows: BTreeMap<(String, String), PayablesAgeingRow> = BTreeMap::new();
+    for pos in invoice_positions(conn, None)? {
+        if pos.outstanding_amount <= AMOUNT_TOLERANCE {
+            continue;
+        }
+        let Some(invoice_date) = parse_date(&pos.invoice_date) else {
+            log::warn!(
+                "Ageing skipped invoice {} with unreadable date '{}'",
+                pos.invoice_number,
+                pos.invoice_date
+            );
+            continue;
+        };
+        if invoice_date > as_of_date {
+            continue;
+        }
+        let age = (as_of_date - invoice_date).num_days();
+        let row = rows
+            .entry((pos.supplier_id.clone(), pos.currency.clone()))
+            .or_insert_with(|| PayablesAgeingRow {
+                supplier_id: pos.supplier_id.clone(),
+                supplier_name: pos.supplier_name.clone(),
+                currency: pos.currency.clone(),
+                invoices: 0,
+                days_0_to_30: 0.0,
+                days_31_to_60: 0.0,
+                days_61_to_90: 0.0,
+                days_91_to_180: 0.0,
+                over_180: 0.0,
+                total_outstanding: 0.0,
+                total_outstanding_inr: Some(0.0),
+            });
+        let amount = pos.outstanding_amount;
+        let bucket = match AGEING_BUCKETS.iter().position(|limit| age <= *limit) {
+            Some(0) => &mut row.days_0_to_30,
+            Some(1) => &mut row.days_31_to_60,
+            Some(2) => &mut row.days_61_to_90,
+            Some(3) => &mut row.days_91_to_180,
+            _ => &mut row.over_180,
+        };
+        *bucket = round2(*bucket + amount);
+        row.invoices += 1;
+        row.total_outstanding = round2(row.total_outstanding + amount);
+        row.total_outstanding_inr = match (row.total_outstanding_inr, pos.customs_rate) {
+            (Some(total), Some(rate)) => Some(round_paise(total + amount * rate)),
+            _ => None,
+        };
+    }
+    Ok(rows.into_values().collect())
+}
+
+#[tauri::command]
+pub fn list_supplier_payments(
+    supplier_id: Option<String>,
+    shipment_id: Option<String>,
+    state: State<DbState>,
+) -> Result<Vec<SupplierPayment>, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    list_payments(&conn, supplier_id.as_deref(), shipment_id.as_deref())
+}
+
+#[tauri::command]
+pub fn save_supplier_payment(
+    payload: SaveSupplierPaymentPayload,
+    state: State<DbState>,
+) -> Result<SupplierPayment, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    let payment = upsert_payment(&conn, payload)?;
+    let _ = dashboard_cache::invalidate_dashboard_metrics_cache(&conn);
+    Ok(payment)
+}
+
+#[tauri::command]
+pub fn delete_supplier_payment(id: String, state: State<DbState>) -> Result<(), String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    let deleted = conn
+        .execute("DELETE FROM supplier_payments WHERE id = ?1", params![id])
+        .map_err(|e| e.to_string())?;
+    if deleted == 0 {
+        return Err(format!("Payment {id} not found"));
+    }
+    conn.execute(
+        "DELETE FROM forward_contract_allocations WHERE payment_id = ?1",
+        params![id],
+    )
+    .map_err(|e| e.to_string())?;
+    let _ = dashboard_cache::invalidate_dashboard_metrics_cache(&conn);
+    Ok(())
+}
+
+/// Outstanding balance and realised forex per import invoice; settled invoices only when asked for.
+#[tauri::command]
+pub fn get_invoice_payment_positions(
+    supplier_id: Option<String>,
+    include_settled: Option<bool>,
+    state: State<DbState>,
+) -> Result<Vec<InvoicePaymentPosition>, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    let positions = invoice_positions(&conn, supplier_id.as_deref())?;
+    Ok(if include_settled.unwrap_or(false) {
+        positions
+    } else {
+        positions
+            .into_iter()
+            .filter(|p| p.payment_status != PAID)
+            .collect()
+    })
+}
+
+#[tauri::command]
+pub fn get_supplier_ledger_statement(
+    supplier_id: String,
+    from_date: Option<String>,
+    to_date: Option<String>,
+    state: State<DbState>,
+) -> Result<Vec<SupplierStatement>, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    supplier_statement(
+        &conn,
+        &supplier_id,
+        from_date.as_deref(),
+        to_date.as_deref(),
+    )
+}
+
+#[tauri::command]
+pub fn get_payables_ageing(
+    as_of: Option<String>,
+    state: State<DbState>,
+) -> Result<Vec<PayablesAgeingRow>, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    payables_ageing(&conn, &as_of.unwrap_or_else(tariff::today))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::test_support;
+
+    fn payment(
+        shipment_id: Option<&str>,
+        payment_type: &str,
+        date: &str,
+        amount: f64,
+        rate: f64,
+    ) -> SaveSupplierPaymentPayload {
+        SaveSupplierPaymentPayload {
+            id: None,
+            supplier_id: "SUP-1".into(),
+            shipment_id: shipment_id.map(Into::into),
+            payment_type: payment_type.into(),
+            payment_date: date.into(),
+            currency: "USD".into(),
+            amount,
+            exchange_rate: rate,
+            bank_charges: 500.0,
+            bank_reference: Some(format!("TT-{date}")),
+            bank_name: Some("Bank".into()),
+            notes: None,
+            user_id: None,
+        }
+    }
+
+    fn paid_on(conn: &Connection, payment_date: &str) -> SupplierPayment {
+        list_payments(conn, Some("SUP-1"), None)
+            .unwrap()
+            .into_iter()
+            .find(|p| p.payment_date == payment_date)
+            .unwrap()
+    }
+
+    /// Invoices INV-1 (USD 1,000, January), INV-2 (USD 400, March) and INV-3 (EUR 9,000, March);
+    /// USD is notified at 83 and EUR not at all.
+    fn invoices() -> Connection {
+        let conn = test_support::migrated_db();
+        test_support::add_shipment(&conn, "SHP-1", "2024-01-10", 1000.0, "USD", "delivered");
+        test_support::add_shipment(&conn, "SHP-2", "2024-03-20", 400.0, "USD", "in-transit");
+        test_support::add_shipment(&conn, "SHP-3", "2024-03-25", 9000.0, "EUR", "in-transit");
+        conn.execute(
+            "INSERT INTO customs_exchange_rates (currency, unit, import_rate, effective_from) VALUES ('USD', 1, 83.0, '2023-01-01')",
+            [],
+        )
+        .unwrap();
+        conn
+    }
+
+    /// [`invoices`] with EUR 100 paid on INV-3, an advance of USD 300 and USD 500 against
+    /// documents on INV-1, and an unlinked USD 50 advance.
+    fn remittances() -> Connection {
+        let conn = invoices();
+        let mut eur = payment(Some("SHP-3"), AGAINST_DOCUMENTS, "2024-03-26", 100.0, 90.0);
+        eur.currency = "EUR".into();
+        for p in [
+            eur,
+            payment(Some("SHP-1"), ADVANCE, "2024-01-05", 300.0, 82.0),
+            payment(Some("SHP-1"), AGAINST_DOCUMENTS, "2024-02-15", 500.0, 83.5),
+            payment(None, ADVANCE, "2024-03-01", 50.0, 83.0),
+        ] {
+            upsert_payment(&conn, p).unwrap();
+        }
+        conn
+    }
+
+    #[test]
+    fn payments_against_documents_must_name_their_invoice() {
+        let conn = invoices();
+        let err = upsert_payment(
+            &conn,
+            payment(None, AGAINST_DOCUMENTS, "2024-01-05", 100.0, 82.0),
+        )
+        .unwrap_err();
+        assert!(err.contains("must name the invoice"));
+        // An advance may wait to be tied to an invoice.
+        upsert_payment(&conn, payment(None, ADVANCE, "2024-01-05", 100.0, 82.0)).unwrap();
+    }
+
+    #[test]
+    fn payments_must_be_in_the_invoice_currency() {
+        let conn = invoices();
+        let mut eur = payment(Some("SHP-3"), AGAINST_DOCUMENTS, "2024-03-26", 100.0, 90.0);
+        let err = upsert_payment(&conn, eur.clone()).unwrap_err();
+        assert!(err.contains("is in EUR; the payment is in USD"));
+        eur.currency = "eur".into();
+        assert_eq!(upsert_payment(&conn, eur).unwrap().currency, "EUR");
+    }
+
+    #[test]
+    fn payments_cannot_exceed_what_is_outstanding_on_the_invoice() {
+        let conn = invoices();
+        let first = upsert_payment(
+            &conn,
+            payment(Some("SHP-1"), AGAINST_DOCUMENTS, "2024-02-15", 800.0, 83.0),
+        )
+        .unwrap();
+        let over = payment(Some("SHP-1"), AGAINST_DOCUMENTS, "2024-02-20", 200.01, 83.0);
+        assert!(upsert_payment(&conn, over)
+            .unwrap_err()
+            .contains("exceeds the 200.00 USD outstanding"));
+        upsert_payment(
+            &conn,
+            payment(Some("SHP-1"), AGAINST_DOCUMENTS, "2024-02-20", 200.0, 83.0),
+        )
+        .unwrap();
+        let inv1 = invoice_positions(&conn, Some("SUP-1"))
+            .unwrap()
+            .into_iter()
+            .find(|p| p.shipment_id == "SHP-1")
+            .unwrap();
+        assert_eq!(
+            (inv1.payment_status.as_str(), inv1.outstanding_amount),
+            (PAID, 0.0)
+        );
+
+        // An edit is checked against the balance left by the other payments only.
+        let mut edit = payment(Some("SHP-1"), AGAINST_DOCUMENTS, "2024-02-15", 800.0, 83.0);
+        edit.id = Some(first.id.clone());
+        upsert_payment(&conn, edit.clone()).unwrap();
+        edit.amount = 800.01;
+        assert!(upsert_payment(&conn, edit).is_err());
+    }
+
+    #[test]
+    fn remittances_realise_forex_against_the_customs_rate() {
+        let conn = remittances();
+        let advance = paid_on(&conn, "2024-01-05");
+        assert_eq!(advance.inr_amount, 24600.0);
+        // Booked at 83, remitted at 82.
+        assert_eq!(advance.realised_fx_gain_loss, Some(300.0));
+        // EUR has no notified rate to measure against.
+        assert_eq!(paid_on(&conn, "2024-03-26").realised_fx_gain_loss, None);
+
+        let positions = invoice_positions(&conn, Some("SUP-1")).unwrap();
+        let inv1 = positions.iter().find(|p| p.shipment_id == "SHP-1").unwrap();
+        assert_eq!(inv1.payment_status, PARTIALLY_PAID);
+        assert_eq!((inv1.paid_amount, inv1.outstanding_amount), (800.0, 200.0));
+        // Booked 800 × 83 = 66400; remitted 24600 + 41750 = 66350.
+        assert_eq!(inv1.realised_fx_gain_loss, Some(50.0));
+        assert_eq!(inv1.bank_charges, 1000.0);
+    }
+
+    #[test]
+    fn statement_carries_the_opening_balance_and_keeps_unapplied_advances_apart() {
+        let conn = remittances();
+        let statements =
+            supplier_statement(&conn, "SUP-1", Some("2024-02-01"), Some("2024-03-31")).unwrap();
+        let usd = statements.iter().find(|s| s.currency == "USD").unwrap();
+        // INV-1 less the January advance.
+        assert_eq!(usd.opening_balance, 700.0);
+        let balances: Vec<f64> = usd.entries.iter().map(|e| e.balance).collect();
+        assert_eq!(balances, vec![200.0, 150.0, 550.0]);
+        assert_eq!((usd.closing_balance, usd.unapplied_advances), (550.0, 50.0));
+    }
+
+    #[test]
+    fn ageing_buckets_outstanding_invoices_and_converts_only_notified_currencies() {
+        let conn = remittances();
+        let ageing = payables_ageing(&conn, "2024-04-30").unwrap();
+        let usd = ageing.iter().find(|r| r.currency == "USD").unwrap();
+        assert_eq!(
+            (usd.days_31_to_60, usd.over_180, usd.days_91_to_180),
+            (400.0, 0.0, 200.0)
+        );
+        assert_eq!(usd.total_outstanding_inr, Some(49800.0));
+        let eur = ageing.iter().find(|r| r.currency == "EUR").unwrap();
+        assert_eq!(
+            (
+                eur.days_0_to_30,
+                eur.days_31_to_60,
+                eur.total_outstanding_inr
+            ),
+            (0.0, 8900.0, None)
+        );
+    }
+}
@@ -0,0 +1,408 @@
+//! Effective-dated HSN tariff master: CSV import, "as of" rate lookup, duty-rate resolution for lines.
+
+use crate::commands::dashboard_cache;
+use crate::db::DbState;
+use crate::utils::csv;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct HsnTariffRate {
+    pub id: i64,
+    pub hsn_code: String,
+    pub description: Option<String>,
+    pub effective_from: String,
+    pub effective_to: Option<String>,
+    pub bcd_rate: f64,
+    pub sws_rate: f64,
+    pub igst_rate: f64,
+    pub cess_rate: f64,
+    pub notification_ref: Option<String>,
+    pub aidc_rate: f64,
+    pub safeguard_rate: f64,
+    /// Goods exempt from Social Welfare Surcharge.
+    pub sws_exempt: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct TariffImportSummary {
+    pub inserted: u32,
+    pub updated: u32,
+    pub errors: Vec<String>,
+}
+
+/// Duty-head percentages used for a duty line, and where they came from. AIDC, safeguard and cess
+/// only exist in the tariff master, so they are zero on the fallback path.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ResolvedDutyRates {
+    pub bcd: f64,
+    pub sws: f64,
+    pub igst: f64,
+    pub aidc: f64,
+    pub safeguard: f64,
+    pub cess: f64,
+    pub sws_exempt: bool,
+    pub from_tariff: bool,
+}
+
+const SELECT_COLUMNS: &str = "id, hsn_code, description, effective_from, effective_to, bcd_rate, sws_rate, igst_rate, cess_rate, notification_ref, aidc_rate, safeguard_rate, sws_exempt";
+
+fn map_tariff_row(row: &rusqlite::Row) -> rusqlite::Result<HsnTariffRate> {
+    Ok(HsnTariffRate {
+        id: row.get(0)?,
+        hsn_code: row.get(1)?,
+        description: row.get(2)?,
+        effective_from: row.get(3)?,
+        effective_to: row.get(4)?,
+        bcd_rate: row.get(5)?,
+        sws_rate: row.get(6)?,
+        igst_rate: row.get(7)?,
+        cess_rate: row.get(8)?,
+        notification_ref: row.get(9)?,
+        aidc_rate: row.get(10)?,
+        safeguard_rate: row.get(11)?,
+        sws_exempt: row.get::<_, i64>(12)? != 0,
+    })
+}
+
+/// Strips separators (`8482.10.11`, `8482 1011`) and requires exactly eight digits.
+pub fn normalize_hsn(code: &str) -> Option<String> {
+    let digits: String = code
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != '.')
+        .collect();
+    (digits.len() == 8 && digits.chars().all(|c| c.is_ascii_digit())).then_some(digits)
+}
+
+pub(crate) fn normalize_date(raw: &str) -> Option<String> {
+    let raw = raw.trim();
+    ["%Y-%m-%d", "%d-%m-%Y", "%d/%m/%Y", "%d.%m.%Y"]
+        .iter()
+        .find_map(|f| chrono::NaiveDate::parse_from_str(raw, f).ok())
+        .map(|d| d.format("%Y-%m-%d").to_string())
+}
+
+pub(crate) fn today() -> String {
+    chrono::Local::now().format("%Y-%m-%d").to_string()
+}
+
+/// Tariff row in force for `hsn_code` on `as_of` (`YYYY-MM-DD`); latest `effective_from` wins on overlap.
+pub fn tariff_rate_as_of(
+    conn: &Connection,
+    hsn_code: &str,
+    as_of: &str,
+) -> rusqlite::Result<Option<HsnTariffRate>> {
+    let Some(hsn) = normalize_hsn(hsn_code) else {
+        return Ok(None);
+    };
+    conn.query_row(
+        &format!(
+            "SELECT {SELECT_COLUMNS} FROM hsn_tariff_rates
+             WHERE hsn_code = ?1
+               AND date(effective_from) <= date(?2)
+               AND (effective_to IS NULL OR date(effective_to) >= date(?2))
+             ORDER BY date(effective_from) DESC
+             LIMIT 1"
+        ),
+        params![hsn, as_of],
+        map_tariff_row,
+    )
+    .optional()
+}
+
+/// Tariff rates for `hsn_code` on `as_of`, falling back to the percentages already stored on the line.
+pub fn resolve_duty_rates(
+    conn: &Connection,
+    hsn_code: &str,
+    as_of: &str,
+    fallback: (Option<f64>, Option<f64>, Option<f64>),
+) -> ResolvedDutyRates {
+    match tariff_rate_as_of(conn, hsn_code, as_of) {
+        Ok(Some(t)) => ResolvedDutyRates {
+            bcd: t.bcd_rate,
+            sws: t.sws_rate,
+            igst: t.igst_rate,
+            aidc: t.aidc_rate,
+            safeguard: t.safeguard_rate,
+            cess: t.cess_rate,
+            sws_exempt: t.sws_exempt,
+            from_tariff: true,
+        },
+        _ => ResolvedDutyRates {
+            bcd: fallback.0.unwrap_or(0.0),
+            sws: fallback.1.unwrap_or(0.0),
+            igst: fallback.2.unwrap_or(0.0),
+            aidc: 0.0,
+            safeguard: 0.0,
+            cess: 0.0,
+            sws_exempt: false,
+            from_tariff: false,
+        },
+    }
+}
+
+/// BE date of a `boe_details` row, if the id is set and the row exists.
+pub fn be_date_for_boe(conn: &Connection, boe_id: Option<&str>) -> Option<String> {
+    let boe_id = boe_id.filter(|s| !s.trim().is_empty())?;
+    conn.query_row(
+        "SELECT be_date FROM boe_details WHERE id = ?1",
+        params![boe_id],
+        |r| r.get(0),
+    )
+    .optional()
+    .ok()
+    .flatten()
+}
+
+/// Latest BE date linked to the shipment through its BOE calculations.
+pub fn be_date_for_shipment(conn: &Connection, shipment_id: &str) -> Option<String> {
+    conn.query_row(
+        "SELECT MAX(bd.be_date) FROM boe_calculations bc
+         JOIN boe_details bd ON bd.id = bc.boe_id
+         WHERE bc.shipment_id = ?1",
+        params![shipment_id],
+        |r| r.get::<_, Option<String>>(0),
+    )
+    .ok()
+    .flatten()
+}
+
+/// Upserts rows keyed by (HSN, effective-from). An open-ended earlier period for the same HSN is closed
+/// the day before the new one starts. Rows with errors are reported and skipped; valid rows still import.
+pub fn import_tariff_csv(
+    conn: &mut Connection,
+    csv_text: &str,
+) -> Result<TariffImportSummary, String> {
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let mut summary = TariffImportSummary::default();
+
+    csv::for_each_row(csv_text, |row| {
+        let line = row.line;
+        let Some(hsn) = row.get(&["hsn_code", "hsn", "cth", "tariff_item"]) else {
+            summary
+                .errors
+                .push(format!("line {line}: missing HSN code"));
+            return Ok(());
+        };
+        let Some(hsn) = normalize_hsn(hsn) else {
+            summary
+                .errors
+                .push(format!("line {line}: '{hsn}' is not an 8-digit HSN"));
+            return Ok(());
+        };
+        let Some(from) = row
+            .get(&["effective_from", "from", "valid_from"])
+            .and_then(normalize_date)
+        else {
+            summary
+                .errors
+                .push(format!("line {line}: missing or invalid effective_from"));
+            return Ok(());
+        };
+        let to = match row.get(&["effective_to", "to", "valid_to"]) {
+            None => None,
+            Some(raw) => match normalize_date(raw) {
+                Some(d) if d >= from => Some(d),
+                _ => {
+                    summary
+                        .errors
+                        .push(format!("line {line}: invalid effective_to '{raw}'"));
+                    return Ok(());
+                }
+            },
+        };
+        let rates = (|| -> Result<[f64; 6], String> {
+            Ok([
+                row.get_f64(&["bcd", "bcd_rate"])?.unwrap_or(0.0),
+                row.get_f64(&["sws", "sws_rate"])?.unwrap_or(0.0),
+                row.get_f64(&["igst", "igst_rate"])?.unwrap_or(0.0),
+                row.get_f64(&["cess", "cess_rate", "compensation_cess"])?
+                    .unwrap_or(0.0),
+                row.get_f64(&["aidc", "aidc_rate"])?.unwrap_or(0.0),
+                row.get_f64(&["safeguard", "safeguard_rate", "sgd"])?
+                    .unwrap_or(0.0),
+            ])
+        })();
+        let [bcd, sws, igst, cess, aidc, safeguard] = match rates {
+            Ok(r) => r,
+            Err(e) => {
+                summary.errors.push(e);
+                return Ok(());
+            }
+        };
+        let sws_exempt = row
+            .get(&["sws_exempt", "sws_exemption"])
+            .is_some_and(|v| matches!(v.to_ascii_lowercase().as_str(), "1" | "y" | "yes" | "true"));
+        let description = row.get(&["description", "desc"]);
+        let notification = row.get(&["notification_ref", "notification", "notification_no"]);
+
+        let existed: bool = tx
+            .query_row(
+                "SELECT COUNT(*) FROM hsn_tariff_rates WHERE hsn_code = ?1 AND effective_from = ?2",
+                params![hsn, from],
+                |r| r.get::<_, i64>(0),
+            )
+            .map_err(|e| e.to_string())?
+            > 0;
+        tx.execute(
+            "UPDATE hsn_tariff_rates
+             SET effective_to = date(?2, '-1 day'),
+                 updated_at = strftime('%Y-%m-%d %H:%M:%S', 'now', 'localtime')
+             WHERE hsn_code = ?1 AND effective_to IS NULL AND date(effective_from) < date(?2)",
+            params![hsn, from],
+        )
+        .map_err(|e| e.to_string())?;
+        tx.execute(
+            "INSERT INTO hsn_tariff_rates
+                (hsn_code, description, effective_from, effective_to, bcd_rate, sws_rate, igst_rate, cess_rate, notification_ref,
+                 aidc_rate, safeguard_rate, sws_exempt)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)
+             ON CONFLICT(hsn_code, effective_from) DO UPDATE SET
+                description = COALESCE(excluded.description, description),
+                effective_to = excluded.effective_to,
+                bcd_rate = excluded.bcd_rate,
+                sws_rate = excluded.sws_rate,
+                igst_rate = excluded.igst_rate,
+                cess_rate = excluded.cess_rate,
+                notification_ref = excluded.notification_ref,
+                aidc_rate = excluded.aidc_rate,
+                safeguard_rate = excluded.safeguard_rate,
+                sws_exempt = excluded.sws_exempt,
+                updated_at = strftime('%Y-%m-%d %H:%M:%S', 'now', 'localtime')",
+            params![
+                hsn,
+                description,
+                from,
+                to,
+                bcd,
+                sws,
+                igst,
+                cess,
+                notification,
+                aidc,
+                safeguard,
+                sws_exempt
+            ],
+        )
+        .map_err(|e| e.to_string())?;
+        if existed {
+            summary.updated += 1;
+        } else {
+            summary.inserted += 1;
+        }
+        Ok(())
+    })?;
+
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(summary)
+}
+
+#[tauri::command]
+pub fn import_hsn_tariff_csv(
+    csv_content: String,
+    state: State<DbState>,
+) -> Result<TariffImportSummary, String> {
+    let mut conn = state.db.lock().map_err(|e| e.to_string())?;
+    let summary = import_tariff_csv(&mut conn, &csv_content)?;
+    let _ = dashboard_cache::invalidate_dashboard_metrics_cache(&conn);
+    Ok(summary)
+}
+
+/// Rate in force on `as_of` (defaults to today).
+#[tauri::command]
+pub fn get_hsn_tariff_rate(
+    hsn_code: String,
+    as_of: Option<String>,
+    state: State<DbState>,
+) -> Result<Option<HsnTariffRate>, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    let as_of = as_of.unwrap_or_else(today);
+    tariff_rate_as_of(&conn, &hsn_code, &as_of).map_err(|e| e.to_string())
+}
+
+/// All periods for one HSN (or the whole master when `hsn_code` is empty), newest first.
+#[tauri::command]
+pub fn list_hsn_tariff_rates(
+    hsn_code: Option<String>,
+    state: State<DbState>,
+) -> Result<Vec<HsnTariffRate>, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    let hsn = hsn_code.as_deref().and_then(normalize_hsn);
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {SELECT_COLUMNS} FROM hsn_tariff_rates
+             WHERE (?1 IS NULL OR hsn_code = ?1)
+             ORDER BY hsn_code, date(effective_from) DESC
+             LIMIT 5000"
+        ))
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![hsn], map_tariff_row)
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn conn() -> Connection {
+        let c = Connection::open_in_memory().unwrap();
+        c.execute_batch(include_str!("../../migrations/V49__hsn_tariff_master.sql"))
+            .unwrap();
+        c.execute_batch(include_str!(
+            "../../migrations/V52__additional_duty_heads.sql"
+        ))
+        .unwrap();
+        c
+    }
+
+    #[test]
+    fn import_closes_previous_period_and_resolves_as_of() {
+        let mut c = conn();
+        let s = import_tariff_csv(
+            &mut c,
+            "HSN Code,Effective From,BCD,SWS,IGST,Notification\n\
+             8482.10.11,01-04-2023,10%,10,18,50/2017-Cus\n\
+             84821011,2024-02-01,7.5,10,18,02/2024-Cus\n\
+             1234,2024-01-01,5,0,5,\n",
+        )
+        .unwrap();
+        assert_eq!((s.inserted, s.updated, s.errors.len()), (2, 0, 1));
+
+        let old = tariff_rate_as_of(&c, "84821011", "2024-01-31")
+            .unwrap()
+            .unwrap();
+        assert_eq!(old.bcd_rate, 10.0);
+        assert_eq!(old.effective_to.as_deref(), Some("2024-01-31"));
+        let new = tariff_rate_as_of(&c, "8482 1011", "2024-02-01")
+            .unwrap()
+            .unwrap();
+        assert_eq!(new.bcd_rate, 7.5);
+        assert_eq!(new.notification_ref.as_deref(), Some("02/2024-Cus"));
+        assert!(tariff_rate_as_of(&c, "84821011", "2023-03-31")
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn reimport_updates_in_place_and_fallback_applies() {
+        let mut c = conn();
+        import_tariff_csv(&mut c, "hsn,from,bcd\n84821011,2024-01-01,5\n").unwrap();
+        let s = import_tariff_csv(&mut c, "hsn,from,bcd\n84821011,2024-01-01,7.5\n").unwrap();
+        assert_eq!((s.inserted, s.updated), (0, 1));
+
+        let r = resolve_duty_rates(&c, "84821011", "2024-06-01", (Some(1.0), None, None));
+        assert!(r.from_tariff);
+        assert_eq!(r.bcd, 7.5);
+        let r = resolve_duty_rates(&c, "99999999", "2024-06-01", (Some(1.0), None, Some(18.0)));
+        assert_eq!(
+            (r.bcd, r.sws, r.igst, r.from_tariff),
+            (1.0, 0.0, 18.0, false)
+        );
+    }
+}
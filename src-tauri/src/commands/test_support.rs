@@ -0,0 +1,85 @@
+//! Fixtures shared by the command modules' unit tests.
+
+use crate::migrations::DatabaseMigrations;
+use rusqlite::{params, Connection, OptionalExtension};
+
+/// A migrated in-memory database with supplier `SUP-1` (Acme).
+pub fn migrated_db() -> Connection {
+    let mut conn = Connection::open_in_memory().unwrap();
+    DatabaseMigrations::run_migrations_test(&mut conn).unwrap();
+    add_supplier(&conn, "SUP-1", "Acme");
+    conn
+}
+
+pub fn add_supplier(conn: &Connection, id: &str, name: &str) {
+    conn.execute(
+        "INSERT INTO suppliers (id, supplier_name, country, email, is_active) VALUES (?1, ?2, 'DE', 'a@x', 1)",
+        params![id, name],
+    )
+    .unwrap();
+}
+
+/// A `Parts` shipment from `SUP-1` on FOB terms; `SHP-n` is invoiced as `INV-n`.
+pub fn add_shipment(
+    conn: &Connection,
+    id: &str,
+    invoice_date: &str,
+    invoice_value: f64,
+    currency: &str,
+    status: &str,
+) {
+    conn.execute(
+        "INSERT INTO shipments (id, supplier_id, invoice_number, invoice_date, goods_category, invoice_value, invoice_currency, incoterm, status)
+         VALUES (?1, 'SUP-1', ?2, ?3, 'Parts', ?4, ?5, 'FOB', ?6)",
+        params![
+            id,
+            id.replace("SHP", "INV"),
+            invoice_date,
+            invoice_value,
+            currency,
+            status
+        ],
+    )
+    .unwrap();
+}
+
+/// A finalized invoice `id` on the shipment.
+pub fn add_invoice(conn: &Connection, id: &str, shipment_id: &str) {
+    conn.execute(
+        "INSERT INTO invoices (id, shipment_id, status) VALUES (?1, ?2, 'Finalized')",
+        params![id, shipment_id],
+    )
+    .unwrap();
+}
+
+/// An active USD item priced at 1 per piece.
+pub fn add_item(
+    conn: &Connection,
+    id: &str,
+    part_number: &str,
+    description: &str,
+    net_weight_kg: Option<f64>,
+) {
+    conn.execute(
+        "INSERT INTO items (id, part_number, item_description, unit, currency, unit_price, hsn_code, is_active, net_weight_kg)
+         VALUES (?1, ?2, ?3, 'PCS', 'USD', 1, '84821011', 1, ?4)",
+        params![id, part_number, description, net_weight_kg],
+    )
+    .unwrap();
+}
+
+/// Status and priority of the exception case of `exception_type` raised on `entity_id`.
+pub fn exception_case(
+    conn: &Connection,
+    exception_type: &str,
+    entity_id: &str,
+) -> Option<(String, String)> {
+    conn.query_row(
+        "SELECT status, priority FROM exception_cases WHERE exception_type = ?1 AND entity_id = ?2
+         ORDER BY created_at DESC LIMIT 1",
+        params![exception_type, entity_id],
+        |r| Ok((r.get(0)?, r.get(1)?)),
+    )
+    .optional()
+    .unwrap()
+}
@@ -0,0 +1,780 @@
+//! Preferential trade (FTA/CEPA): agreements and member countries, concessional BCD per HSN, certificates of origin.
+
+use crate::commands::dashboard_cache;
+use crate::commands::tariff::{normalize_date, normalize_hsn, today};
+use crate::commands::utils::generate_id;
+use crate::db::{BoeItemInput, DbState};
+use crate::duty_engine::{self, ShipmentDutyLine, METHOD_CEPA};
+use crate::utils::csv;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tauri::State;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct AgreementMember {
+    pub country_code: String,
+    pub country_name: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TradeAgreement {
+    pub code: String,
+    pub name: String,
+    pub notification_ref: Option<String>,
+    pub effective_from: String,
+    pub effective_to: Option<String>,
+    #[serde(default)]
+    pub members: Vec<AgreementMember>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ConcessionalRate {
+    pub id: i64,
+    pub agreement_code: String,
+    pub hsn_code: String,
+    pub concessional_bcd_rate: f64,
+    pub effective_from: String,
+    pub effective_to: Option<String>,
+    pub notification_ref: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CertificateOfOrigin {
+    /// Omitted for new certificates (server assigns id).
+    pub id: Option<String>,
+    pub shipment_id: String,
+    pub agreement_code: String,
+    pub certificate_number: String,
+    pub country_of_origin: String,
+    pub issue_date: String,
+    pub valid_until: Option<String>,
+    pub attachment_path: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ConcessionImportSummary {
+    pub inserted: u32,
+    pub updated: u32,
+    pub errors: Vec<String>,
+}
+
+/// The agreement, certificate and concession backing a preferential BOE line.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct PreferenceClaim {
+    pub agreement_code: String,
+    pub certificate_number: String,
+    pub concessional_bcd_rate: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct LineEligibility {
+    pub part_no: String,
+    pub hs_code: String,
+    pub country_of_origin: Option<String>,
+    pub claim: Option<PreferenceClaim>,
+    /// Why the line does not qualify; `None` when `claim` is present.
+    pub reason: Option<String>,
+}
+
+/// Case- and punctuation-insensitive key, so "S.Korea" and "s korea" compare equal.
+pub(crate) fn country_key(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .collect::<String>()
+        .to_uppercase()
+}
+
+/// ISO code of `country` as a member of agreement `code`, matched against the code or any listed spelling.
+fn member_country_code(
+    conn: &Connection,
+    code: &str,
+    country: &str,
+) -> rusqlite::Result<Option<String>> {
+    let key = country_key(country);
+    let mut stmt = conn.prepare(
+        "SELECT country_code, country_name FROM trade_agreement_members WHERE agreement_code = ?1",
+    )?;
+    let rows = stmt.query_map(params![code], |r| {
+        Ok((r.get::<_, String>(0)?, r.get::<_, String>(1)?))
+    })?;
+    for r in rows {
+        let (cc, name) = r?;
+        if country_key(&cc) == key || country_key(&name) == key {
+            return Ok(Some(cc));
+        }
+    }
+    Ok(None)
+}
+
+fn map_rate_row(row: &rusqlite::Row) -> rusqlite::Result<ConcessionalRate> {
+    Ok(ConcessionalRate {
+        id: row.get(0)?,
+        agreement_code: row.get(1)?,
+        hsn_code: row.get(2)?,
+        concessional_bcd_rate: row.get(3)?,
+        effective_from: row.get(4)?,
+        effective_to: row.get(5)?,
+        notification_ref: row.get(6)?,
+    })
+}
+
+const RATE_COLUMNS: &str =
+    "id, agreement_code, hsn_code, concessional_bcd_rate, effective_from, effective_to, notification_ref";
+
+/// Concession in force under `agreement_code` for `hsn_code` on `as_of`.
+pub fn concessional_rate_as_of(
+    conn: &Connection,
+    agreement_code: &str,
+    hsn_code: &str,
+    as_of: &str,
+) -> rusqlite::Result<Option<ConcessionalRate>> {
+    let Some(hsn) = normalize_hsn(hsn_code) else {
+        return Ok(None);
+    };
+    conn.query_row(
+        &format!(
+            "SELECT {RATE_COLUMNS} FROM trade_agreement_rates
+             WHERE agreement_code = ?1 AND hsn_code = ?2
+               AND date(effective_from) <= date(?3)
+               AND (effective_to IS NULL OR date(effective_to) >= date(?3))
+             ORDER BY date(effective_from) DESC
+             LIMIT 1"
+        ),
+        params![agreement_code, hsn, as_of],
+        map_rate_row,
+    )
+    .optional()
+}
+
+fn map_certificate_row(row: &rusqlite::Row) -> rusqlite::Result<CertificateOfOrigin> {
+    Ok(CertificateOfOrigin {
+        id: Some(row.get(0)?),
+        shipment_id: row.get(1)?,
+        agreement_code: row.get(2)?,
+        certificate_number: row.get(3)?,
+        country_of_origin: row.get(4)?,
+        issue_date: row.get(5)?,
+        valid_until: row.get(6)?,
+        attachment_path: row.get(7)?,
+    })
+}
+
+const CERTIFICATE_COLUMNS: &str = "c.id, c.shipment_id, c.agreement_code, c.certificate_number, c.country_of_origin, c.issue_date, c.valid_until, c.attachment_path";
+
+fn certificates_for_shipment(
+    conn: &Connection,
+    shipment_id: &str,
+) -> rusqlite::Result<Vec<CertificateOfOrigin>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {CERTIFICATE_COLUMNS} FROM certificates_of_origin c
+         WHERE c.shipment_id = ?1
+         ORDER BY date(c.issue_date) DESC, c.certificate_number"
+    ))?;
+    let rows = stmt.query_map(params![shipment_id], map_certificate_row)?;
+    rows.collect()
+}
+
+/// Certificates issued on or before `as_of`, not expired, under an agreement in force on `as_of`.
+fn valid_certificates(
+    conn: &Connection,
+    shipment_id: &str,
+    as_of: &str,
+) -> rusqlite::Result<Vec<CertificateOfOrigin>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {CERTIFICATE_COLUMNS} FROM certificates_of_origin c
+         JOIN trade_agreements a ON a.code = c.agreement_code
+         WHERE c.shipment_id = ?1
+           AND date(c.issue_date) <= date(?2)
+           AND (c.valid_until IS NULL OR date(c.valid_until) >= date(?2))
+           AND date(a.effective_from) <= date(?2)
+           AND (a.effective_to IS NULL OR date(a.effective_to) >= date(?2))
+         ORDER BY date(c.issue_date) DESC"
+    ))?;
+    let rows = stmt.query_map(params![shipment_id, as_of], map_certificate_row)?;
+    rows.collect()
+}
+
+/// Finds the certificate and concession that make `line` eligible for preferential BCD on `as_of`.
+/// The error explains the first missing piece (origin, certificate, membership, or HSN concession).
+pub fn check_preference(
+    conn: &Connection,
+    shipment_id: &str,
+    line: &ShipmentDutyLine,
+    as_of: &str,
+) -> Result<PreferenceClaim, String> {
+    let Some(country) = line
+        .country_of_origin
+        .as_deref()
+        .filter(|c| !c.trim().is_empty())
+    else {
+        return Err(format!("Part {} has no country of origin", line.part_no));
+    };
+    let certificates = valid_certificates(conn, shipment_id, as_of).map_err(|e| e.to_string())?;
+    if certificates.is_empty() {
+        return Err(format!(
+            "No certificate of origin valid on {as_of} is on file for this shipment"
+        ));
+    }
+
+    let mut covered = false;
+    for cert in &certificates {
+        let line_member =
+            member_country_code(conn, &cert.agreement_code, country).map_err(|e| e.to_string())?;
+        let cert_member = member_country_code(conn, &cert.agreement_code, &cert.country_of_origin)
+            .map_err(|e| e.to_string())?;
+        if line_member.is_none() || line_member != cert_member {
+            continue;
+        }
+        covered = true;
+        if let Some(rate) =
+            concessional_rate_as_of(conn, &cert.agreement_code, &line.hs_code, as_of)
+                .map_err(|e| e.to_string())?
+        {
+            return Ok(PreferenceClaim {
+                agreement_code: cert.agreement_code.clone(),
+                certificate_number: cert.certificate_number.clone(),
+                concessional_bcd_rate: rate.concessional_bcd_rate,
+            });
+        }
+    }
+    if covered {
+        Err(format!(
+            "HSN {} of part {} has no concessional rate under the certified agreement",
+            line.hs_code, line.part_no
+        ))
+    } else {
+        Err(format!(
+            "Part {} originates in {}, which no certificate of origin on file covers",
+            line.part_no, country
+        ))
+    }
+}
+
+/// Rejects CEPA lines that are not backed by origin, a valid certificate and a concession, or that claim
+/// BCD below the concessional rate. Returns the claim per part number.
+pub fn validate_preferential_claims(
+    conn: &Connection,
+    shipment_id: &str,
+    as_of: &str,
+    lines: &[ShipmentDutyLine],
+    inputs: &[BoeItemInput],
+) -> Result<HashMap<String, PreferenceClaim>, String> {
+    let mut claims = HashMap::new();
+    let line_of_input = duty_engine::pair_by_part(inputs, lines, |ii| &ii.part_no, |l| &l.part_no);
+    for (input, line) in inputs.iter().zip(line_of_input) {
+        if input.calculation_method != METHOD_CEPA {
+            continue;
+        }
+        let Some(line) = line else {
+            continue;
+        };
+        let claim = check_preference(conn, shipment_id, line, as_of)
+            .map_err(|e| format!("CEPA not allowed for part {}: {e}", input.part_no))?;
+        if input.boe_bcd_rate + 1e-9 < claim.concessional_bcd_rate {
+            return Err(format!(
+                "Part {} claims {}% BCD but the {} concession is {}%",
+                input.part_no,
+                input.boe_bcd_rate,
+                claim.agreement_code,
+                claim.concessional_bcd_rate
+            ));
+        }
+        claims.insert(input.part_no.clone(), claim);
+    }
+    Ok(claims)
+}
+
+fn save_members(conn: &Connection, code: &str, members: &[AgreementMember]) -> Result<(), String> {
+    conn.execute(
+        "DELETE FROM trade_agreement_members WHERE agreement_code = ?1",
+        params![code],
+    )
+    .map_err(|e| e.to_string())?;
+    for m in members {
+        conn.execute(
+            "INSERT OR IGNORE INTO trade_agreement_members (agreement_code, country_code, country_name)
+             VALUES (?1, ?2, ?3)",
+            params![code, m.country_code.trim().to_uppercase(), m.country_name.trim()],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// Upserts concessions keyed by (agreement, HSN, effective-from); an open-ended earlier period for the
+/// same pair is closed the day before the new one starts.
+pub fn import_concessions_csv(
+    conn: &mut Connection,
+    csv_text: &str,
+) -> Result<ConcessionImportSummary, String> {
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let mut summary = ConcessionImportSummary::default();
+
+    csv::for_each_row(csv_text, |row| {
+        let line = row.line;
+        let Some(code) = row
+            .get(&["agreement", "agreement_code", "fta"])
+            .map(str::to_uppercase)
+        else {
+            summary
+                .errors
+                .push(format!("line {line}: missing agreement"));
+            return Ok(());
+        };
+        let known: bool = tx
+            .query_row(
+                "SELECT COUNT(*) FROM trade_agreements WHERE code = ?1",
+                params![code],
+                |r| r.get::<_, i64>(0),
+            )
+            .map_err(|e| e.to_string())?
+            > 0;
+        if !known {
+            summary
+                .errors
+                .push(format!("line {line}: unknown agreement '{code}'"));
+            return Ok(());
+        }
+        let Some(hsn) = row
+            .get(&["hsn_code", "hsn", "tariff_item"])
+            .and_then(normalize_hsn)
+        else {
+            summary
+                .errors
+                .push(format!("line {line}: missing or invalid 8-digit HSN"));
+            return Ok(());
+        };
+        let rate = match row.get_f64(&["concessional_bcd_rate", "concessional_bcd", "bcd", "rate"])
+        {
+            Ok(Some(r)) if r >= 0.0 => r,
+            Ok(_) => {
+                summary
+                    .errors
+                    .push(format!("line {line}: missing concessional BCD rate"));
+                return Ok(());
+            }
+            Err(e) => {
+                summary.errors.push(e);
+                return Ok(());
+            }
+        };
+        let Some(from) = row
+            .get(&["effective_from", "from", "valid_from"])
+            .and_then(normalize_date)
+        else {
+            summary
+                .errors
+                .push(format!("line {line}: missing or invalid effective_from"));
+            return Ok(());
+        };
+        let to = match row.get(&["effective_to", "to", "valid_to"]) {
+            None => None,
+            Some(raw) => match normalize_date(raw) {
+                Some(d) if d >= from => Some(d),
+                _ => {
+                    summary
+                        .errors
+                        .push(format!("line {line}: invalid effective_to '{raw}'"));
+                    return Ok(());
+                }
+            },
+        };
+        let notification = row.get(&["notification_ref", "notification", "notification_no"]);
+
+        let existed: bool = tx
+            .query_row(
+                "SELECT COUNT(*) FROM trade_agreement_rates
+                 WHERE agreement_code = ?1 AND hsn_code = ?2 AND effective_from = ?3",
+                params![code, hsn, from],
+                |r| r.get::<_, i64>(0),
+            )
+            .map_err(|e| e.to_string())?
+            > 0;
+        tx.execute(
+            "UPDATE trade_agreement_rates
+             SET effective_to = date(?3, '-1 day'),
+                 updated_at = strftime('%Y-%m-%d %H:%M:%S', 'now', 'localtime')
+             WHERE agreement_code = ?1 AND hsn_code = ?2
+               AND effective_to IS NULL AND date(effective_from) < date(?3)",
+            params![code, hsn, from],
+        )
+        .map_err(|e| e.to_string())?;
+        tx.execute(
+            "INSERT INTO trade_agreement_rates
+                (agreement_code, hsn_code, concessional_bcd_rate, effective_from, effective_to, notification_ref)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+             ON CONFLICT(agreement_code, hsn_code, effective_from) DO UPDATE SET
+                concessional_bcd_rate = excluded.concessional_bcd_rate,
+                effective_to = excluded.effective_to,
+                notification_ref = excluded.notification_ref,
+                updated_at = strftime('%Y-%m-%d %H:%M:%S', 'now', 'localtime')",
+            params![code, hsn, rate, from, to, notification],
+        )
+        .map_err(|e| e.to_string())?;
+        if existed {
+            summary.updated += 1;
+        } else {
+            summary.inserted += 1;
+        }
+        Ok(())
+    })?;
+
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(summary)
+}
+
+#[tauri::command]
+pub fn list_trade_agreements(state: State<DbState>) -> Result<Vec<TradeAgreement>, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT code, name, notification_ref, effective_from, effective_to
+             FROM trade_agreements ORDER BY name",
+        )
+        .map_err(|e| e.to_string())?;
+    let mut agreements = stmt
+        .query_map([], |r| {
+            Ok(TradeAgreement {
+                code: r.get(0)?,
+                name: r.get(1)?,
+                notification_ref: r.get(2)?,
+                effective_from: r.get(3)?,
+                effective_to: r.get(4)?,
+                members: Vec::new(),
+            })
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    let mut member_stmt = conn
+        .prepare(
+            "SELECT country_code, country_name FROM trade_agreement_members
+             WHERE agreement_code = ?1 ORDER BY country_code, country_name",
+        )
+        .map_err(|e| e.to_string())?;
+    for a in &mut agreements {
+        a.members = member_stmt
+            .query_map(params![a.code], |r| {
+                Ok(AgreementMember {
+                    country_code: r.get(0)?,
+                    country_name: r.get(1)?,
+                })
+            })
+            .map_err(|e| e.to_string())?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(agreements)
+}
+
+/// Creates or updates an agreement; `members` replaces the stored member list.
+#[tauri::command]
+pub fn save_trade_agreement(
+    agreement: TradeAgreement,
+    state: State<DbState>,
+) -> Result<(), String> {
+    let code = agreement.code.trim().to_uppercase();
+    if code.is_empty() || agreement.name.trim().is_empty() {
+        return Err("Agreement code and name are required".to_string());
+    }
+    let from = normalize_date(&agreement.effective_from)
+        .ok_or_else(|| format!("Invalid effective-from date '{}'", agreement.effective_from))?;
+    let to = match agreement
+        .effective_to
+        .as_deref()
+        .filter(|s| !s.trim().is_empty())
+    {
+        None => None,
+        Some(raw) => match normalize_date(raw) {
+            Some(d) if d >= from => Some(d),
+            _ => return Err(format!("Invalid effective-to date '{raw}'")),
+        },
+    };
+
+    let mut conn = state.db.lock().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    tx.execute(
+        "INSERT INTO trade_agreements (code, name, notification_ref, effective_from, effective_to)
+         VALUES (?1, ?2, ?3, ?4, ?5)
+         ON CONFLICT(code) DO UPDATE SET
+            name = excluded.name,
+            notification_ref = excluded.notification_ref,
+            effective_from = excluded.effective_from,
+            effective_to = excluded.effective_to,
+            updated_at = strftime('%Y-%m-%d %H:%M:%S', 'now', 'localtime')",
+        params![
+            code,
+            agreement.name.trim(),
+            agreement.notification_ref,
+            from,
+            to
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    save_members(&tx, &code, &agreement.members)?;
+    tx.commit().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn import_concessional_rates_csv(
+    csv_content: String,
+    state: State<DbState>,
+) -> Result<ConcessionImportSummary, String> {
+    let mut conn = state.db.lock().map_err(|e| e.to_string())?;
+    let summary = import_concessions_csv(&mut conn, &csv_content)?;
+    let _ = dashboard_cache::invalidate_dashboard_metrics_cache(&conn);
+    Ok(summary)
+}
+
+#[tauri::command]
+pub fn list_concessional_rates(
+    agreement_code: Option<String>,
+    hsn_code: Option<String>,
+    state: State<DbState>,
+) -> Result<Vec<ConcessionalRate>, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    let hsn = hsn_code.as_deref().and_then(normalize_hsn);
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {RATE_COLUMNS} FROM trade_agreement_rates
+             WHERE (?1 IS NULL OR agreement_code = ?1) AND (?2 IS NULL OR hsn_code = ?2)
+             ORDER BY agreement_code, hsn_code, date(effective_from) DESC
+             LIMIT 5000"
+        ))
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![agreement_code, hsn], map_rate_row)
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn get_certificates_of_origin(
+    shipment_id: String,
+    state: State<DbState>,
+) -> Result<Vec<CertificateOfOrigin>, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    certificates_for_shipment(&conn, &shipment_id).map_err(|e| e.to_string())
+}
+
+/// Adds or updates a certificate of origin; returns its id. `attachment_path` is the file saved via
+/// `save_boe_attachment_file`.
+#[tauri::command]
+pub fn save_certificate_of_origin(
+    certificate: CertificateOfOrigin,
+    state: State<DbState>,
+) -> Result<String, String> {
+    let number = certificate.certificate_number.trim();
+    if number.is_empty() {
+        return Err("Certificate number is required".to_string());
+    }
+    if certificate.country_of_origin.trim().is_empty() {
+        return Err("Country of origin is required".to_string());
+    }
+    let agreement_code = certificate.agreement_code.trim().to_uppercase();
+    let issue_date = normalize_date(&certificate.issue_date)
+        .ok_or_else(|| format!("Invalid issue date '{}'", certificate.issue_date))?;
+    let valid_until = match certificate
+        .valid_until
+        .as_deref()
+        .filter(|s| !s.trim().is_empty())
+    {
+        None => None,
+        Some(raw) => match normalize_date(raw) {
+            Some(d) if d >= issue_date => Some(d),
+            _ => return Err(format!("Invalid validity date '{raw}'")),
+        },
+    };
+
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    if member_country_code(&conn, &agreement_code, &certificate.country_of_origin)
+        .map_err(|e| e.to_string())?
+        .is_none()
+    {
+        return Err(format!(
+            "{} is not a member of agreement {agreement_code}",
+            certificate.country_of_origin
+        ));
+    }
+    let id = certificate
+        .id
+        .clone()
+        .unwrap_or_else(|| generate_id(Some("COO".to_string())));
+    conn.execute(
+        "INSERT INTO certificates_of_origin
+            (id, shipment_id, agreement_code, certificate_number, country_of_origin, issue_date, valid_until, attachment_path)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+         ON CONFLICT(id) DO UPDATE SET
+            agreement_code = excluded.agreement_code,
+            certificate_number = excluded.certificate_number,
+            country_of_origin = excluded.country_of_origin,
+            issue_date = excluded.issue_date,
+            valid_until = excluded.valid_until,
+            attachment_path = excluded.attachment_path,
+            updated_at = strftime('%Y-%m-%d %H:%M:%S', 'now', 'localtime')",
+        params![
+            id,
+            certificate.shipment_id,
+            agreement_code,
+            number,
+            certificate.country_of_origin.trim(),
+            issue_date,
+            valid_until,
+            certificate.attachment_path
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    let _ = dashboard_cache::invalidate_dashboard_metrics_cache(&conn);
+    Ok(id)
+}
+
+#[tauri::command]
+pub fn delete_certificate_of_origin(id: String, state: State<DbState>) -> Result<(), String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    conn.execute(
+        "DELETE FROM certificates_of_origin WHERE id = ?1",
+        params![id],
+    )
+    .map_err(|e| e.to_string())?;
+    let _ = dashboard_cache::invalidate_dashboard_metrics_cache(&conn);
+    Ok(())
+}
+
+/// Per-line CEPA eligibility for a shipment on its BE date (today when not filed), for the BOE entry screen.
+#[tauri::command]
+pub fn get_preferential_eligibility(
+    shipment_id: String,
+    state: State<DbState>,
+) -> Result<Vec<LineEligibility>, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    let as_of =
+        crate::commands::tariff::be_date_for_shipment(&conn, &shipment_id).unwrap_or_else(today);
+    let lines = crate::duty_engine::load_shipment_lines(&conn, &shipment_id, &as_of)
+        .map_err(|e| e.to_string())?;
+    Ok(lines
+        .iter()
+        .map(|line| {
+            let result = check_preference(&conn, &shipment_id, line, &as_of);
+            LineEligibility {
+                part_no: line.part_no.clone(),
+                hs_code: line.hs_code.clone(),
+                country_of_origin: line.country_of_origin.clone(),
+                reason: result.as_ref().err().cloned(),
+                claim: result.ok(),
+            }
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn conn() -> Connection {
+        let c = Connection::open_in_memory().unwrap();
+        c.execute_batch("CREATE TABLE shipments (id TEXT PRIMARY KEY);")
+            .unwrap();
+        c.execute_batch(include_str!(
+            "../../migrations/V51__preferential_trade_agreements.sql"
+        ))
+        .unwrap();
+        c.execute_batch("INSERT INTO shipments VALUES ('S1');")
+            .unwrap();
+        c
+    }
+
+    fn line(country: Option<&str>) -> ShipmentDutyLine {
+        ShipmentDutyLine {
+            part_no: "P1".into(),
+            description: "Bearing".into(),
+            qty: 1.0,
+            unit_price: 100.0,
+            hs_code: "84821011".into(),
+            country_of_origin: country.map(str::to_string),
+            line_total: 100.0,
+            weight_kg: 0.0,
+            actual_bcd_rate: 10.0,
+            actual_sws_rate: 10.0,
+            actual_igst_rate: 18.0,
+            actual_aidc_rate: 0.0,
+            actual_safeguard_rate: 0.0,
+            actual_cess_rate: 0.0,
+            sws_exempt: false,
+            add_rate: 0.0,
+            add_per_unit: 0.0,
+        }
+    }
+
+    fn cepa_input(bcd: f64) -> BoeItemInput {
+        BoeItemInput {
+            part_no: "P1".into(),
+            calculation_method: METHOD_CEPA.into(),
+            boe_bcd_rate: bcd,
+            boe_sws_rate: 10.0,
+            boe_igst_rate: 18.0,
+            boe_aidc_rate: None,
+            boe_add_rate: None,
+            boe_safeguard_rate: None,
+            boe_cess_rate: None,
+            quantity: None,
+        }
+    }
+
+    #[test]
+    fn cepa_requires_origin_certificate_and_concession() {
+        let mut c = conn();
+        let s = import_concessions_csv(
+            &mut c,
+            "Agreement,HSN Code,Concessional BCD,Effective From,Effective To\n\
+             in-kr-cepa,8482.10.11,2.5,2024-01-01,\n\
+             XX,84821011,0,2024-01-01,\n\
+             IN-KR-CEPA,84821012,2.5,2024-03-01,2024-02-01\n",
+        )
+        .unwrap();
+        assert_eq!((s.inserted, s.errors.len()), (1, 2));
+        assert!(
+            s.errors[1].contains("invalid effective_to"),
+            "{:?}",
+            s.errors
+        );
+
+        let lines = [line(Some("S.Korea"))];
+        let err = validate_preferential_claims(&c, "S1", "2024-06-01", &lines, &[cepa_input(2.5)])
+            .unwrap_err();
+        assert!(err.contains("No certificate of origin"), "{err}");
+
+        c.execute(
+            "INSERT INTO certificates_of_origin
+                (id, shipment_id, agreement_code, certificate_number, country_of_origin, issue_date, valid_until)
+             VALUES ('C1', 'S1', 'IN-KR-CEPA', 'KR-001', 'South Korea', '2024-05-01', '2025-04-30')",
+            [],
+        )
+        .unwrap();
+        let claims =
+            validate_preferential_claims(&c, "S1", "2024-06-01", &lines, &[cepa_input(2.5)])
+                .unwrap();
+        assert_eq!(claims["P1"].certificate_number, "KR-001");
+
+        // Below the concession, expired certificate, and non-member origin are all rejected.
+        assert!(
+            validate_preferential_claims(&c, "S1", "2024-06-01", &lines, &[cepa_input(0.0)])
+                .is_err()
+        );
+        assert!(
+            validate_preferential_claims(&c, "S1", "2025-06-01", &lines, &[cepa_input(2.5)])
+                .is_err()
+        );
+        let err = check_preference(&c, "S1", &line(Some("China")), "2024-06-01").unwrap_err();
+        assert!(err.contains("China"), "{err}");
+    }
+}
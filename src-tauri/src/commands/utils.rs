@@ -2,10 +2,7 @@ use crate::db::{DbState, SelectOption};
 use rusqlite::params;
 use tauri::State;
 
-use std::collections::HashMap;
-
 // Type aliases to reduce complexity
-pub type BoeShipmentMap = HashMap<String, (String, f64, f64, String, f64, f64, f64, f64)>;
 pub type ExpenseTypeRow = (
     String,
     String,
@@ -2,8 +2,10 @@
 
 use crate::commands::dashboard_cache;
 use crate::commands::exception_workflow::{insert_lifecycle, refresh_all_open_exception_sla};
-use crate::commands::workflow_production_observability::{log_structured_event, record_performance_timing};
 use crate::commands::workflow_multienv::{active_execution_environment_id, active_tenant_id};
+use crate::commands::workflow_production_observability::{
+    log_structured_event, record_performance_timing,
+};
 use crate::commands::workflow_rule_deployment::canary_allows_case_for_rule;
 use crate::db::DbState;
 use rusqlite::types::ToSql;
@@ -275,13 +277,9 @@ pub fn auto_resolve_exception_cases(conn: &Connection) -> Result<i32, String> {
     if enabled_rules_of_type(conn, "AUTO_RESOLVE") == 0 {
         return Ok(0);
     }
-    let max_h = meta_i64(
-        conn,
-        "automation_max_auto_resolve_per_hour",
-        40,
-    )
-    .max(1)
-    .min(500);
+    let max_h = meta_i64(conn, "automation_max_auto_resolve_per_hour", 40)
+        .max(1)
+        .min(500);
     let used = hourly_action_count(conn, "AUTO_RESOLVE")?;
     if used >= max_h {
         pause_automation(conn, "max_auto_resolve_per_hour exceeded")?;
@@ -310,7 +308,15 @@ pub fn auto_resolve_exception_cases(conn: &Connection) -> Result<i32, String> {
         if budget <= 0 {
             break;
         }
-        resolve_one_case_auto(conn, &cid, &et, &eid, &now, "rule-auto-resolve-overdue-delivered", "shipment_delivered")?;
+        resolve_one_case_auto(
+            conn,
+            &cid,
+            &et,
+            &eid,
+            &now,
+            "rule-auto-resolve-overdue-delivered",
+            "shipment_delivered",
+        )?;
         budget -= 1;
         total += 1;
     }
@@ -332,7 +338,15 @@ pub fn auto_resolve_exception_cases(conn: &Connection) -> Result<i32, String> {
         if budget <= 0 {
             break;
         }
-        resolve_one_case_auto(conn, &cid, &et, &eid, &now, "rule-auto-resolve-boe-present", "has_boe")?;
+        resolve_one_case_auto(
+            conn,
+            &cid,
+            &et,
+            &eid,
+            &now,
+            "rule-auto-resolve-boe-present",
+            "has_boe",
+        )?;
         budget -= 1;
         total += 1;
     }
@@ -548,6 +562,12 @@ fn sla_base_hours(exception_type: &str) -> f64 {
     match exception_type {
         "OVERDUE_ETA" => 48.0,
         "MISSING_BOE" | "MISSING_EXPENSE" => 24.0,
+        "BOE_MISMATCH" => 72.0,
+        "GST_2B_MISMATCH" => 120.0,
+        "FREE_TIME_EXPIRING" => 24.0,
+        "PO_VARIANCE" => 72.0,
+        "GRN_VARIANCE" => 48.0,
+        "MISSING_DOCUMENTS" => 24.0,
         _ => 24.0,
     }
 }
@@ -555,7 +575,17 @@ fn sla_base_hours(exception_type: &str) -> f64 {
 /// Record adaptive SLA recommendations; optionally applies new deadlines when `automation_adaptive_sla_apply` = 1.
 pub fn apply_adaptive_sla_engine(conn: &Connection) -> Result<i32, String> {
     let d = today_date();
-    let types = ["OVERDUE_ETA", "MISSING_BOE", "MISSING_EXPENSE"];
+    let types = [
+        "OVERDUE_ETA",
+        "MISSING_BOE",
+        "MISSING_EXPENSE",
+        "BOE_MISMATCH",
+        "GST_2B_MISMATCH",
+        "FREE_TIME_EXPIRING",
+        "PO_VARIANCE",
+        "GRN_VARIANCE",
+        "MISSING_DOCUMENTS",
+    ];
     let mut rows = 0i32;
     for et in types {
         let avg_h: Option<f64> = conn
@@ -644,13 +674,9 @@ pub fn run_priority_adjust_rules(conn: &Connection) -> Result<i32, String> {
     if enabled_rules_of_type(conn, "PRIORITY_ADJUST") == 0 {
         return Ok(0);
     }
-    let cap = meta_i64(
-        conn,
-        "automation_max_priority_adjust_per_cycle",
-        80,
-    )
-    .max(1)
-    .min(500);
+    let cap = meta_i64(conn, "automation_max_priority_adjust_per_cycle", 80)
+        .max(1)
+        .min(500);
     let mut n = 0i32;
     let mut stmt = conn
         .prepare(
@@ -729,8 +755,16 @@ fn repair_missing_lifecycle_events(conn: &Connection) -> Result<i64, String> {
             "resolvedAt": ra,
         })
         .to_string();
-        let ev = if st == "IGNORED" { "IGNORED" } else { "RESOLVED" };
-        let uid = if rb.is_empty() { None } else { Some(rb.as_str()) };
+        let ev = if st == "IGNORED" {
+            "IGNORED"
+        } else {
+            "RESOLVED"
+        };
+        let uid = if rb.is_empty() {
+            None
+        } else {
+            Some(rb.as_str())
+        };
         insert_lifecycle(conn, &cid, ev, uid, &det)?;
         n += 1;
     }
@@ -1683,11 +1717,7 @@ pub fn refresh_rule_safety_index_for_date(
     Ok(n)
 }
 
-fn stability_alert_recent(
-    conn: &Connection,
-    alert_type: &str,
-    hours: i64,
-) -> Result<i64, String> {
+fn stability_alert_recent(conn: &Connection, alert_type: &str, hours: i64) -> Result<i64, String> {
     let off = format!("-{hours} hours");
     let cnt: i64 = conn
         .query_row(
@@ -1971,7 +2001,12 @@ pub fn compute_automation_roi_snapshot(conn: &Connection) -> Result<(), String>
     Ok(())
 }
 
-fn dry_run_automation_counts(conn: &Connection, auto_resolve_on: bool, auto_assign_on: bool, priority_on: bool) -> serde_json::Value {
+fn dry_run_automation_counts(
+    conn: &Connection,
+    auto_resolve_on: bool,
+    auto_assign_on: bool,
+    priority_on: bool,
+) -> serde_json::Value {
     let overdue = if auto_resolve_on {
         conn.query_row(
             "SELECT COUNT(*) FROM exception_cases c JOIN shipments s ON s.id = c.entity_id
@@ -2108,7 +2143,9 @@ pub fn simulate_multiple_rule_sets(
 }
 
 /// Rule optimization and expansion suggestions from metrics and manual resolution patterns.
-pub fn generate_rule_optimization_recommendations(conn: &Connection) -> Result<serde_json::Value, String> {
+pub fn generate_rule_optimization_recommendations(
+    conn: &Connection,
+) -> Result<serde_json::Value, String> {
     let mut suggestions: Vec<serde_json::Value> = Vec::new();
     let tid = active_tenant_id(conn);
     let mut stmt = conn
@@ -2211,7 +2248,9 @@ pub fn generate_rule_optimization_recommendations(conn: &Connection) -> Result<s
 }
 
 /// Mine manual resolutions for repeated patterns (automation expansion hints).
-pub fn generate_automation_learning_suggestions(conn: &Connection) -> Result<serde_json::Value, String> {
+pub fn generate_automation_learning_suggestions(
+    conn: &Connection,
+) -> Result<serde_json::Value, String> {
     let mut ideas: Vec<serde_json::Value> = Vec::new();
     let top_manual_et: Option<(String, i64)> = conn
         .query_row(
@@ -2285,7 +2324,9 @@ pub fn analyze_workflow_efficiency(conn: &Connection) -> Result<serde_json::Valu
 
     let mut recs: Vec<String> = Vec::new();
     if slowest.unwrap_or(0.0) > 72.0 {
-        recs.push("Slowest recent resolutions exceed 72h — review bottleneck exception types.".into());
+        recs.push(
+            "Slowest recent resolutions exceed 72h — review bottleneck exception types.".into(),
+        );
     }
     if !top_esc.is_empty() {
         recs.push(format!(
@@ -2304,7 +2345,9 @@ pub fn analyze_workflow_efficiency(conn: &Connection) -> Result<serde_json::Valu
         )
         .unwrap_or(0);
     if dup > 0 {
-        recs.push(format!("{dup} duplicate open groups detected — enforce single-open policy."));
+        recs.push(format!(
+            "{dup} duplicate open groups detected — enforce single-open policy."
+        ));
     }
 
     Ok(json!({
@@ -2374,6 +2417,33 @@ pub fn suggest_resolution_actions(conn: &Connection, case_id: &str) -> Result<Ve
                 out.push("Record expenses against this shipment.".into());
             }
         }
+        "BOE_MISMATCH" => {
+            out.push("Open the BOE reconciliation to see which lines and duty heads are out of tolerance.".into());
+            out.push("Correct the BOE entry, or file an amendment with customs if the assessment is wrong.".into());
+        }
+        "GST_2B_MISMATCH" => {
+            out.push(
+                "Compare the BOE's IGST with the GSTR-2B IMPG row in the latest 2B reconciliation."
+                    .into(),
+            );
+            out.push("If 2B is short or missing the BE, raise it with ICEGATE / the broker before claiming credit.".into());
+        }
+        "FREE_TIME_EXPIRING" => {
+            out.push("Check the shipment's free-time position for the last free day and the charge per extra day.".into());
+            out.push("Expedite clearance and gate-out, or return the empty container, before charges start; ask the line for a free-time extension if that is not possible.".into());
+        }
+        "PO_VARIANCE" => {
+            out.push("Open the shipment's PO variances to see which invoice lines exceed the PO price or ordered quantity.".into());
+            out.push("Get a credit note or revised invoice from the supplier, or amend the PO if the change was agreed.".into());
+        }
+        "GRN_VARIANCE" => {
+            out.push("Compare the shipment's goods receipts with the invoice to confirm which lines arrived short, excess or damaged.".into());
+            out.push("Notify the insurer and carrier within the policy window, using the GRN photos and claim basis; debit the supplier for excess or wrong supply.".into());
+        }
+        "MISSING_DOCUMENTS" => {
+            out.push("Open the shipment's document checklist to see which mandatory documents are still pending or missing.".into());
+            out.push("Chase the supplier or forwarder for originals before the CHA files the BOE; mark a document not applicable, with a reason, if this shipment does not need it.".into());
+        }
         _ => out.push("Review entity data and apply standard resolution checklist.".into()),
     }
     if sla == "BREACHED" {
@@ -2409,9 +2479,7 @@ fn normalize_role(role: &str) -> String {
 
 fn can_view_automation_console(role: &str) -> bool {
     let n = normalize_role(role);
-    n.contains("admin")
-        || n.contains("automationmanager")
-        || n.contains("viewer")
+    n.contains("admin") || n.contains("automationmanager") || n.contains("viewer")
 }
 
 fn can_mutate_automation_rules(role: &str) -> bool {
@@ -2561,7 +2629,8 @@ pub fn list_workflow_decision_rules(
             })
         })
         .map_err(|e| e.to_string())?;
-    rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
+    rows.collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
@@ -2624,14 +2693,7 @@ pub fn set_workflow_automation_master_enabled(
     let prev = meta_get(&conn, "workflow_automation_master_enabled");
     let v = if enabled { "1" } else { "0" };
     meta_set(&conn, "workflow_automation_master_enabled", v)?;
-    log_workflow_rule_change(
-        &conn,
-        "",
-        &changed_by,
-        "MASTER_SWITCH",
-        &prev,
-        v,
-    )?;
+    log_workflow_rule_change(&conn, "", &changed_by, "MASTER_SWITCH", &prev, v)?;
     log_automation(
         &conn,
         "",
@@ -2677,11 +2739,7 @@ pub fn set_automation_guardrails(
     }
     if let Some(n) = input.automation_pause_duration_minutes {
         let n = n.max(5).min(24 * 60);
-        meta_set(
-            &conn,
-            "automation_pause_duration_minutes",
-            &n.to_string(),
-        )?;
+        meta_set(&conn, "automation_pause_duration_minutes", &n.to_string())?;
     }
     log_automation(
         &conn,
@@ -2705,14 +2763,7 @@ pub fn set_adaptive_sla_apply_enabled(
     let prev = meta_get(&conn, "automation_adaptive_sla_apply");
     let v = if enabled { "1" } else { "0" };
     meta_set(&conn, "automation_adaptive_sla_apply", v)?;
-    log_workflow_rule_change(
-        &conn,
-        "",
-        &changed_by,
-        "ADAPTIVE_SLA_APPLY_FLAG",
-        &prev,
-        v,
-    )?;
+    log_workflow_rule_change(&conn, "", &changed_by, "ADAPTIVE_SLA_APPLY_FLAG", &prev, v)?;
     log_automation(
         &conn,
         "",
@@ -2850,7 +2901,8 @@ pub fn query_workflow_automation_log(
             })
         })
         .map_err(|e| e.to_string())?;
-    rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
+    rows.collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
@@ -2961,7 +3013,8 @@ pub fn list_adaptive_sla_adjustments(
             })
         })
         .map_err(|e| e.to_string())?;
-    rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
+    rows.collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
@@ -2992,7 +3045,8 @@ pub fn list_workflow_rule_change_log(
             })
         })
         .map_err(|e| e.to_string())?;
-    rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
+    rows.collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
@@ -3135,9 +3189,7 @@ pub fn rollback_automation_action(
                 )
                 .map_err(|e| e.to_string())?;
             if st != "RESOLVED" || rb != "automation" {
-                return Err(
-                    "case is not in automation-resolved state — rollback refused".into(),
-                );
+                return Err("case is not in automation-resolved state — rollback refused".into());
             }
             conn.execute(
                 "UPDATE exception_cases SET status = 'OPEN', resolved_at = NULL, resolved_by = NULL,
@@ -3263,7 +3315,8 @@ pub fn list_rule_effectiveness_metrics(
             })
         })
         .map_err(|e| e.to_string())?;
-    rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
+    rows.collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
@@ -3339,7 +3392,9 @@ pub fn get_rule_performance_dashboard(
     low.truncate(8);
     let unused: Vec<serde_json::Value> = scored
         .iter()
-        .filter(|x| x["enabled"].as_i64().unwrap_or(0) == 1 && x["actions14d"].as_i64().unwrap_or(0) == 0)
+        .filter(|x| {
+            x["enabled"].as_i64().unwrap_or(0) == 1 && x["actions14d"].as_i64().unwrap_or(0) == 0
+        })
         .cloned()
         .collect();
     let high_failure: Vec<serde_json::Value> = scored
@@ -3390,7 +3445,8 @@ pub fn list_automation_decision_feedback(
             })
         })
         .map_err(|e| e.to_string())?;
-    rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
+    rows.collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
@@ -3420,7 +3476,8 @@ pub fn list_rule_safety_index(
             })
         })
         .map_err(|e| e.to_string())?;
-    rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
+    rows.collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
@@ -3454,7 +3511,8 @@ pub fn list_automation_stability_alerts(
             })
         })
         .map_err(|e| e.to_string())?;
-    rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
+    rows.collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
@@ -3500,7 +3558,8 @@ pub fn list_automation_benchmark_history(
             })
         })
         .map_err(|e| e.to_string())?;
-    rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
+    rows.collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
@@ -3531,7 +3590,8 @@ pub fn list_automation_roi_metrics(
             })
         })
         .map_err(|e| e.to_string())?;
-    rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
+    rows.collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
@@ -3662,7 +3722,8 @@ pub fn list_rule_execution_cost_estimates(
             })
         })
         .map_err(|e| e.to_string())?;
-    rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
+    rows.collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())
 }
 
 #[derive(Debug, Deserialize)]
@@ -3822,7 +3883,8 @@ pub fn list_rule_cost_efficiency_metrics(
             })
         })
         .map_err(|e| e.to_string())?;
-    rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
+    rows.collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
@@ -3858,7 +3920,8 @@ pub fn list_automation_capacity_load(
             })
         })
         .map_err(|e| e.to_string())?;
-    rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
+    rows.collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
@@ -3888,7 +3951,8 @@ pub fn list_daily_automation_economics_index(
             })
         })
         .map_err(|e| e.to_string())?;
-    rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
+    rows.collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
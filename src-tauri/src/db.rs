@@ -92,6 +92,8 @@ pub struct InvoiceLineItem {
     pub duty_percent: f64,
     pub sws_percent: f64,
     pub igst_percent: f64,
+    /// Purchase order line this invoice line ships against, if any.
+    pub po_line_id: Option<String>,
 }
 
 #[derive(Debug, serde::Serialize, Clone)]
@@ -128,6 +130,8 @@ pub struct NewInvoiceLineItemPayload {
     pub sws_percent: Option<f64>,
     #[serde(default)]
     pub igst_percent: Option<f64>,
+    #[serde(default)]
+    pub po_line_id: Option<String>,
 }
 
 // --- BOE STRUCTS ---
@@ -145,6 +149,14 @@ pub struct BoeDetails {
     pub challan_number: Option<String>,
     pub ref_id: Option<String>,
     pub transaction_id: Option<String>,
+    /// Vessel / aircraft arrival date; late-filing charges run from the day before it.
+    #[serde(default)]
+    pub arrival_date: Option<String>,
+    /// Computed by the backend from the configured slabs; read-only for the client.
+    #[serde(default)]
+    pub late_payment_interest: f64,
+    #[serde(default)]
+    pub late_filing_charge: f64,
 }
 
 // NEW struct for receiving new BOE data from the frontend (without ID)
@@ -161,6 +173,8 @@ pub struct NewBoePayload {
     pub challan_number: Option<String>,
     pub ref_id: Option<String>,
     pub transaction_id: Option<String>,
+    #[serde(default)]
+    pub arrival_date: Option<String>,
 }
 
 // ============================================================================
@@ -188,6 +202,12 @@ pub struct FormValues {
     pub exw_cost: f64,
     pub insurance_rate: f64,
     pub interest: Option<f64>,
+    /// Shipment incoterm the valuation follows; `None` adds freight, EXW and insurance as entered.
+    #[serde(default)]
+    pub incoterm: Option<String>,
+    /// How freight and EXW charges are spread over lines: `VALUE` (default), `WEIGHT` or `QUANTITY`.
+    #[serde(default)]
+    pub apportionment_basis: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -198,6 +218,18 @@ pub struct BoeItemInput {
     pub boe_bcd_rate: f64,
     pub boe_sws_rate: f64,
     pub boe_igst_rate: f64,
+    /// Extra duty heads as declared on the BOE; `None` applies the tariff / anti-dumping notification.
+    #[serde(default)]
+    pub boe_aidc_rate: Option<f64>,
+    #[serde(default)]
+    pub boe_add_rate: Option<f64>,
+    #[serde(default)]
+    pub boe_safeguard_rate: Option<f64>,
+    #[serde(default)]
+    pub boe_cess_rate: Option<f64>,
+    /// Units warehoused (into-bond) or cleared (ex-bond, partial home consumption); `None` is the full invoice quantity.
+    #[serde(default)]
+    pub quantity: Option<f64>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -209,6 +241,40 @@ pub struct CalculatedDutyItem {
     pub bcd_value: f64,
     pub sws_value: f64,
     pub igst_value: f64,
+    #[serde(default)]
+    pub aidc_value: f64,
+    #[serde(default)]
+    pub add_value: f64,
+    #[serde(default)]
+    pub safeguard_value: f64,
+    #[serde(default)]
+    pub cess_value: f64,
+    #[serde(default)]
+    pub valuation: Option<LineValuation>,
+}
+
+/// How a line's assessable value was built; amounts are in invoice currency until `assessable_value`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct LineValuation {
+    pub incoterm: Option<String>,
+    pub apportionment_basis: String,
+    /// The line's share of invoice-level freight and EXW charges.
+    pub share: f64,
+    pub invoice_value: f64,
+    pub exw_charges: f64,
+    pub fob_value: f64,
+    pub freight: f64,
+    /// Freight is the 20% notional amount rather than the actual cost.
+    pub freight_notional: bool,
+    /// Percent of FOB.
+    pub insurance_rate: f64,
+    pub insurance: f64,
+    pub insurance_notional: bool,
+    pub cif_value: f64,
+    pub exchange_rate: f64,
+    /// CIF in INR.
+    pub assessable_value: f64,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -218,8 +284,54 @@ pub struct CalculationResult {
     pub bcd_total: f64,
     pub sws_total: f64,
     pub igst_total: f64,
+    #[serde(default)]
+    pub aidc_total: f64,
+    #[serde(default)]
+    pub add_total: f64,
+    #[serde(default)]
+    pub safeguard_total: f64,
+    #[serde(default)]
+    pub cess_total: f64,
     pub interest: f64,
+    /// Duty payable in cash: every duty head less `duty_credit_total`, plus interest and any
+    /// late-filing charge.
     pub customs_duty_total: f64,
+    /// Drawback / RoDTEP credits debited against this BOE.
+    #[serde(default)]
+    pub duty_credit_total: f64,
+    /// Statutory interest and late-filing charges behind `interest`, when the BOE has its BE details.
+    #[serde(default)]
+    pub statutory_charges: Option<StatutoryCharges>,
+}
+
+/// Late-payment interest and late-filing charges for one Bill of Entry, slab by slab.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct StatutoryCharges {
+    /// Last day duty can be paid without interest.
+    pub payment_due_date: String,
+    /// Payment date, or the date interest was computed to while duty is unpaid.
+    pub interest_until: String,
+    pub duty_paid: bool,
+    pub days_paid_late: i64,
+    pub late_payment_interest: f64,
+    /// Last day the BE could be filed on time; `None` without an arrival date.
+    pub filing_deadline: Option<String>,
+    pub days_filed_late: i64,
+    pub late_filing_charge: f64,
+    pub lines: Vec<StatutoryChargeLine>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct StatutoryChargeLine {
+    /// `LATE_PAYMENT_INTEREST` or `LATE_FILING`.
+    pub charge_type: String,
+    pub from_day: i64,
+    pub to_day: Option<i64>,
+    pub days: i64,
+    pub rate: f64,
+    pub amount: f64,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -235,6 +347,16 @@ pub struct SavedBoe {
     pub item_inputs: Vec<BoeItemInput>,
     pub calculation_result: CalculationResult,
     pub attachments: Option<Vec<Attachment>>,
+    /// `HOME_CONSUMPTION`, `INTO_BOND` or `EX_BOND`.
+    #[serde(default = "default_boe_type")]
+    pub boe_type: String,
+    /// For ex-bond BOEs, the saved into-bond BOE being cleared.
+    #[serde(default)]
+    pub into_bond_boe_id: Option<String>,
+}
+
+fn default_boe_type() -> String {
+    "HOME_CONSUMPTION".to_string()
 }
 
 // --- Structs for the specialized BOE Entry command ---
@@ -279,13 +401,37 @@ pub struct ReconciledItemRow {
     pub actual_bcd: f64,
     pub actual_sws: f64,
     pub actual_igst: f64,
+    #[serde(default)]
+    pub actual_aidc: f64,
+    #[serde(default)]
+    pub actual_add: f64,
+    #[serde(default)]
+    pub actual_safeguard: f64,
+    #[serde(default)]
+    pub actual_cess: f64,
     pub actual_total: f64,
     pub boe_bcd: f64,
     pub boe_sws: f64,
     pub boe_igst: f64,
+    #[serde(default)]
+    pub boe_aidc: f64,
+    #[serde(default)]
+    pub boe_add: f64,
+    #[serde(default)]
+    pub boe_safeguard: f64,
+    #[serde(default)]
+    pub boe_cess: f64,
     pub boe_total: f64,
     pub method: String,
     pub savings: f64,
+    /// Agreement and certificate backing a CEPA line's savings.
+    #[serde(default)]
+    pub agreement_code: Option<String>,
+    #[serde(default)]
+    pub certificate_number: Option<String>,
+    /// Set when a CEPA line is no longer backed by origin, certificate or concession data.
+    #[serde(default)]
+    pub preference_issue: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -305,6 +451,26 @@ pub struct BoeReconciliationReport {
     pub invoice_number: String,
     pub items: Vec<ReconciledItemRow>,
     pub totals: ReconciliationTotals,
+    /// Lines and duty heads outside the configured tolerances; empty when the BOE reconciles.
+    #[serde(default)]
+    pub mismatches: Vec<ReconciliationMismatch>,
+    /// Open `BOE_MISMATCH` exception case for the shipment, if any.
+    #[serde(default)]
+    pub exception_case_id: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ReconciliationMismatch {
+    pub part_no: String,
+    /// `RATE_DIFFERS`, `ASSESSABLE_VALUE_DIFFERS`, `MISSING_ON_BOE` or `MISSING_ON_INVOICE`.
+    pub kind: String,
+    /// Duty head for rate differences (`BCD`, `SWS`, ...); `ASSESSABLE_VALUE` for value differences.
+    pub duty_head: Option<String>,
+    /// Amount expected from the invoice and tariff, in INR.
+    pub expected: f64,
+    /// Amount on the BOE, in INR.
+    pub boe: f64,
 }
 
 // --- NEW EXPENSE MODULE STRUCTS ---
@@ -330,6 +496,13 @@ pub struct ExpenseType {
     pub default_sgst_rate: i32, // Now in basis points (900 = 9.00%)
     pub default_igst_rate: i32, // Now in basis points (900 = 9.00%)
     pub is_active: bool,
+    /// How expenses of this type are spread over invoice lines; see `landed_cost`.
+    #[serde(default = "default_allocation_basis")]
+    pub allocation_basis: String,
+}
+
+fn default_allocation_basis() -> String {
+    "ASSESSABLE_VALUE".to_string()
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -1086,6 +1259,7 @@ pub fn init_schema(conn: &Connection) -> Result<()> {
             SELECT
                 bc.id AS boe_calc_id,
                 bc.shipment_id,
+                bc.boe_id,
                 bc.supplier_name,
                 bc.invoice_number,
                 json_extract(item.value, '$.partNo') AS part_no,
@@ -1093,9 +1267,29 @@ pub fn init_schema(conn: &Connection) -> Result<()> {
                 CAST(json_extract(item.value, '$.assessableValue') AS REAL) AS boe_assessable_value,
                 CAST(json_extract(item.value, '$.bcdValue') AS REAL) AS boe_bcd_amount,
                 CAST(json_extract(item.value, '$.swsValue') AS REAL) AS boe_sws_amount,
-                CAST(json_extract(item.value, '$.igstValue') AS REAL) AS boe_igst_amount
+                CAST(json_extract(item.value, '$.igstValue') AS REAL) AS boe_igst_amount,
+                COALESCE(CAST(json_extract(item.value, '$.aidcValue') AS REAL), 0.0) AS boe_aidc_amount,
+                COALESCE(CAST(json_extract(item.value, '$.addValue') AS REAL), 0.0) AS boe_add_amount,
+                COALESCE(CAST(json_extract(item.value, '$.safeguardValue') AS REAL), 0.0) AS boe_safeguard_amount,
+                COALESCE(CAST(json_extract(item.value, '$.cessValue') AS REAL), 0.0) AS boe_cess_amount,
+                CAST(json_extract(bc.form_values_json, '$.exchangeRate') AS REAL) AS boe_exchange_rate,
+                -- Position among the BOE's items for the same part; pairs the item with its invoice line.
+                ROW_NUMBER() OVER (
+                    PARTITION BY bc.id, json_extract(item.value, '$.partNo') ORDER BY CAST(item.key AS INTEGER)
+                ) AS part_seq
             FROM boe_calculations bc
             JOIN json_each(json_extract(bc.calculation_result_json, '$.calculatedItems')) AS item
+            -- Into-bond BOEs defer duty; their goods are reported through the ex-bond clearances.
+            WHERE COALESCE(bc.boe_type, 'HOME_CONSUMPTION') <> 'INTO_BOND'
+        ),
+        -- Invoice lines in the duty engine's order, so a part billed on several lines keeps one row per line.
+        shipment_lines AS (
+            SELECT inv.shipment_id, ili.id AS invoice_line_item_id, ili.item_id, i.part_number AS part_no,
+                   ili.quantity, ili.unit_price,
+                   ROW_NUMBER() OVER (PARTITION BY inv.shipment_id, i.part_number ORDER BY ili.rowid) AS part_seq
+            FROM invoices inv
+            JOIN invoice_line_items ili ON ili.invoice_id = inv.id
+            JOIN items i ON i.id = ili.item_id
         ),
         shipment_expenses AS (
             SELECT ei.shipment_id, 
@@ -1115,29 +1309,46 @@ pub fn init_schema(conn: &Connection) -> Result<()> {
             s.supplier_id AS supplier_id,
             s.invoice_number AS invoice_no,
             s.invoice_date AS invoice_date,
+            bi.shipment_id AS shipment_id,
+            bi.boe_id AS boe_id,
+            s.invoice_currency AS currency,
+            bi.boe_exchange_rate AS exchange_rate,
             bi.part_no AS part_no,
             COALESCE(i.item_description, bi.boe_description) AS description,
             i.unit AS unit,
-            ili.quantity AS qty,
-            ili.unit_price AS unit_price,
+            sl.quantity AS qty,
+            sl.unit_price AS unit_price,
             bi.boe_assessable_value AS assessable_value,
             bi.boe_bcd_amount AS bcd_amount,
             bi.boe_sws_amount AS sws_amount,
             bi.boe_igst_amount AS igst_amount,
-            -- Expense allocation proportional by BOE assessable value per shipment (BASIC VALUE - excluding GST)
-            COALESCE(se.shipment_expenses_basic, 0.0) * 
-              (bi.boe_assessable_value / NULLIF(ba.shipment_boe_assessable_total, 0)) AS expenses_total,
-            -- LDC per qty: (assessable + bcd + sws + expenses_basic) / qty
-            (
-              (bi.boe_assessable_value + bi.boe_bcd_amount + bi.boe_sws_amount
-               + (COALESCE(se.shipment_expenses_basic, 0.0) * (bi.boe_assessable_value / NULLIF(ba.shipment_boe_assessable_total, 0))))
-            ) / NULLIF(ili.quantity, 0) AS ldc_per_qty
+            bi.boe_aidc_amount AS aidc_amount,
+            bi.boe_add_amount AS add_amount,
+            bi.boe_safeguard_amount AS safeguard_amount,
+            bi.boe_cess_amount AS cess_amount,
+            -- Allocated expenses (BASIC VALUE - excluding GST) from item_landed_costs; shipments not yet
+            -- allocated fall back to a split proportional to BOE assessable value
+            COALESCE(
+              ilc.expense_cost * COALESCE(bi.boe_assessable_value / NULLIF(ilc.assessable_value, 0), 1.0),
+              COALESCE(se.shipment_expenses_basic, 0.0) * 
+                (bi.boe_assessable_value / NULLIF(ba.shipment_boe_assessable_total, 0))
+            ) AS expenses_total,
+            -- LDC per qty: (assessable + non-creditable duties + expenses_basic) / qty; IGST and cess are creditable
+            COALESCE(
+              ilc.landed_cost_per_unit,
+              (
+                (bi.boe_assessable_value + bi.boe_bcd_amount + bi.boe_sws_amount
+                 + bi.boe_aidc_amount + bi.boe_add_amount + bi.boe_safeguard_amount
+                 + (COALESCE(se.shipment_expenses_basic, 0.0) * (bi.boe_assessable_value / NULLIF(ba.shipment_boe_assessable_total, 0))))
+              ) / NULLIF(sl.quantity, 0)
+            ) AS ldc_per_qty
         FROM boe_items bi
         JOIN shipments s ON s.id = bi.shipment_id
         JOIN suppliers sup ON sup.id = s.supplier_id
-        JOIN invoices inv ON inv.shipment_id = s.id
-        JOIN items i ON i.part_number = bi.part_no
-        JOIN invoice_line_items ili ON ili.invoice_id = inv.id AND ili.item_id = i.id
+        JOIN shipment_lines sl
+            ON sl.shipment_id = bi.shipment_id AND sl.part_no = bi.part_no AND sl.part_seq = bi.part_seq
+        JOIN items i ON i.id = sl.item_id
+        LEFT JOIN item_landed_costs ilc ON ilc.invoice_line_item_id = sl.invoice_line_item_id
         LEFT JOIN shipment_expenses se ON se.shipment_id = s.id
         LEFT JOIN boe_assessable ba ON ba.shipment_id = s.id;
         "#,
@@ -3,16 +3,16 @@
 use std::collections::HashSet;
 use std::time::Duration;
 
-use base64::Engine;
 use base64::engine::general_purpose::STANDARD as B64;
+use base64::Engine;
 use reqwest::blocking::Client;
-use reqwest::header::{AUTHORIZATION, CONTENT_TYPE, HeaderMap, HeaderValue};
+use reqwest::header::{HeaderMap, HeaderValue, AUTHORIZATION, CONTENT_TYPE};
 use serde::Deserialize;
 use serde_json::json;
 
-use crate::app_settings::{get_app_setting, KEY_DEEPSEEK_API_KEY};
 use crate::ai_prompt_builder::build_invoice_extraction_prompt;
 use crate::ai_prompt_builder::InvoiceExtractionPrompts;
+use crate::app_settings::{get_app_setting, KEY_DEEPSEEK_API_KEY};
 use crate::retry_engine;
 use rusqlite::Connection;
 
@@ -268,7 +268,9 @@ fn dedupe_line_items_preserve_order(items: Vec<ParsedLineItem>) -> Vec<ParsedLin
 }
 
 /// Parse the assistant’s JSON (invoice fields) into structured data. Used for tests and production.
-pub fn parse_extraction_from_assistant_text(assistant: &str) -> Result<ParsedInvoiceExtraction, String> {
+pub fn parse_extraction_from_assistant_text(
+    assistant: &str,
+) -> Result<ParsedInvoiceExtraction, String> {
     let cleaned = strip_code_fences(assistant);
     let l: LlmInvoiceJson = serde_json::from_str(&cleaned)
         .map_err(|e| format!("Model did not return valid JSON for invoice fields: {e}"))?;
@@ -287,10 +289,7 @@ pub fn parse_extraction_from_assistant_text(assistant: &str) -> Result<ParsedInv
         })
         .collect();
     let line_items = dedupe_line_items_preserve_order(line_items);
-    log::debug!(
-        "Line items after deduplication: {}",
-        line_items.len()
-    );
+    log::debug!("Line items after deduplication: {}", line_items.len());
     Ok(ParsedInvoiceExtraction {
         supplier_name: supplier.to_string(),
         invoice_number: l.invoice_number,
@@ -312,10 +311,11 @@ fn build_http_client() -> Result<Client, String> {
         .map_err(|e| format!("HTTP client: {e}"))
 }
 
-fn run_deepseek_invoice_request_once(
+/// POST `body` and return the assistant text together with the raw response body.
+fn run_deepseek_chat_once(
     config: &DeepSeekConfig,
     body: &serde_json::Value,
-) -> Result<ParsedInvoiceExtraction, String> {
+) -> Result<(String, String), String> {
     let client = build_http_client()?;
     let mut headers = HeaderMap::new();
     headers.insert(
@@ -323,10 +323,7 @@ fn run_deepseek_invoice_request_once(
         HeaderValue::from_str(&format!("Bearer {}", config.api_key))
             .map_err(|e| format!("Invalid API key for header: {e}"))?,
     );
-    headers.insert(
-        CONTENT_TYPE,
-        HeaderValue::from_static("application/json"),
-    );
+    headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
     let resp = client
         .post(&config.endpoint)
         .headers(headers)
@@ -340,23 +337,29 @@ fn run_deepseek_invoice_request_once(
     };
     let status = resp.status();
     let text = read_response_text(resp);
-    log::debug!(
-        "AI response length: {} characters",
-        text.len()
-    );
+    log::debug!("AI response length: {} characters", text.len());
     if !status.is_success() {
         let msg = parse_api_error_from_body(&text).unwrap_or_else(|| text.clone());
         let friendly = format!("DeepSeek API error (HTTP {}): {}", status.as_u16(), msg);
         return Err(friendly);
     }
-    let assist = match assistant_text_from_openai_response(&text) {
-        Ok(a) => a,
-        Err(e) => {
-            return Err(format!("{e}. Body (truncated): {} ", truncate(&text, 500)));
-        }
-    };
-    let mut parsed = parse_extraction_from_assistant_text(&assist)
-        .map_err(|e| format!("{e} — assistant output (truncated): {}", truncate(&assist, 800)))?;
+    match assistant_text_from_openai_response(&text) {
+        Ok(a) => Ok((a, text)),
+        Err(e) => Err(format!("{e}. Body (truncated): {} ", truncate(&text, 500))),
+    }
+}
+
+fn run_deepseek_invoice_request_once(
+    config: &DeepSeekConfig,
+    body: &serde_json::Value,
+) -> Result<ParsedInvoiceExtraction, String> {
+    let (assist, text) = run_deepseek_chat_once(config, body)?;
+    let mut parsed = parse_extraction_from_assistant_text(&assist).map_err(|e| {
+        format!(
+            "{e} — assistant output (truncated): {}",
+            truncate(&assist, 800)
+        )
+    })?;
     parsed.raw_api_response = text;
     Ok(parsed)
 }
@@ -390,12 +393,7 @@ pub fn call_deepseek_vision(
         }
     };
     let prompts = build_invoice_extraction_prompt(supplier_hint, file_name_for_prompt);
-    let body = build_request_json_vision(
-        &config.model_name,
-        &b64,
-        mime,
-        &prompts,
-    );
+    let body = build_request_json_vision(&config.model_name, &b64, mime, &prompts);
     run_deepseek_invoice_request(config, &body)
 }
 
@@ -447,6 +445,20 @@ pub fn call_deepseek_ocr_text(
     run_deepseek_invoice_request(config, &body)
 }
 
+/// Plain text-in / text-out chat for non-invoice prompts; same retries as extraction.
+pub fn call_deepseek_chat(
+    config: &DeepSeekConfig,
+    system: &str,
+    user: &str,
+) -> Result<String, String> {
+    let body = build_request_json_text(&config.model_name, system, user);
+    retry_engine::execute_with_retry(
+        || run_deepseek_chat_once(config, &body).map(|(assist, _)| assist),
+        retry_engine::DEFAULT_MAX_RETRIES,
+        retry_engine::is_retriable_network_timeout_or_5xx,
+    )
+}
+
 fn read_response_text(resp: reqwest::blocking::Response) -> String {
     let status = resp.status();
     let mut r = match resp.text() {
@@ -0,0 +1,566 @@
+//! Authoritative BOE duty computation (CIF assessable value, then BCD → SWS → IGST) shared by BOE save and reconciliation.
+
+use crate::commands::{anti_dumping, tariff, trade_agreements};
+use crate::db::{BoeItemInput, CalculatedDutyItem, CalculationResult, FormValues};
+use crate::valuation;
+use rusqlite::{params, Connection};
+use std::collections::HashMap;
+
+/// Calculation methods accepted on a BOE line (`BoeItemInput.calculation_method`).
+pub const METHOD_STANDARD: &str = "Standard";
+pub const METHOD_CEPA: &str = "CEPA";
+pub const METHOD_RODTEP: &str = "Rodtep";
+
+/// One invoice line of a shipment as seen by the engine. `line_total` is in invoice currency;
+/// `actual_*_rate` are the tariff percentages used for reconciliation. `add_*` is the anti-dumping
+/// duty notified for the line's origin and supplier.
+#[derive(Debug, Clone)]
+pub struct ShipmentDutyLine {
+    pub part_no: String,
+    pub description: String,
+    pub qty: f64,
+    pub unit_price: f64,
+    pub hs_code: String,
+    pub country_of_origin: Option<String>,
+    pub line_total: f64,
+    /// Gross weight of the line in kg (0 when the item has no weight recorded).
+    pub weight_kg: f64,
+    pub actual_bcd_rate: f64,
+    pub actual_sws_rate: f64,
+    pub actual_igst_rate: f64,
+    pub actual_aidc_rate: f64,
+    pub actual_safeguard_rate: f64,
+    pub actual_cess_rate: f64,
+    pub sws_exempt: bool,
+    pub add_rate: f64,
+    /// Specific anti-dumping duty per unit, in invoice currency.
+    pub add_per_unit: f64,
+}
+
+/// Per-line duty heads in INR, each rounded to paise.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct LineDuty {
+    pub bcd: f64,
+    pub aidc: f64,
+    pub sws: f64,
+    pub add: f64,
+    pub safeguard: f64,
+    pub igst: f64,
+    pub cess: f64,
+}
+
+impl LineDuty {
+    pub fn total(&self) -> f64 {
+        self.bcd + self.aidc + self.sws + self.add + self.safeguard + self.igst + self.cess
+    }
+}
+
+/// Line-level values (assessable value, duty heads) are carried to the paisa.
+pub fn round_paise(value: f64) -> f64 {
+    (value * 100.0).round() / 100.0
+}
+
+/// Section 154A of the Customs Act: duty and interest payable are rounded off to the nearest rupee.
+pub fn round_rupee(value: f64) -> f64 {
+    value.round()
+}
+
+/// Invoice lines for a shipment. Actual rates come from the HSN tariff master in force on `as_of`
+/// (the BE date), falling back to the percentages stored on the invoice line; anti-dumping duty is
+/// looked up by origin country and the shipment's supplier.
+pub fn load_shipment_lines(
+    conn: &Connection,
+    shipment_id: &str,
+    as_of: &str,
+) -> rusqlite::Result<Vec<ShipmentDutyLine>> {
+    let sql = "
+        SELECT
+            i.part_number, i.item_description,
+            ili.quantity, ili.unit_price,
+            i.hsn_code,
+            (ili.quantity * ili.unit_price) as line_total,
+            ili.duty_percent, ili.sws_percent, ili.igst_percent,
+            i.country_of_origin, s.supplier_id,
+            ili.quantity * COALESCE(i.gross_weight_per_uom_kg, i.net_weight_kg, 0) as line_weight
+        FROM invoices inv
+        JOIN invoice_line_items ili ON ili.invoice_id = inv.id
+        JOIN items i ON ili.item_id = i.id
+        LEFT JOIN shipments s ON s.id = inv.shipment_id
+        WHERE inv.shipment_id = ?1
+        ORDER BY i.part_number, ili.rowid
+    ";
+    let mut stmt = conn.prepare(sql)?;
+    let rows = stmt.query_map(params![shipment_id], |row| {
+        Ok((
+            ShipmentDutyLine {
+                part_no: row.get(0)?,
+                description: row.get(1)?,
+                qty: row.get(2)?,
+                unit_price: row.get(3)?,
+                hs_code: row.get(4)?,
+                country_of_origin: row.get(9)?,
+                line_total: row.get(5)?,
+                weight_kg: row.get::<_, Option<f64>>(11)?.unwrap_or(0.0),
+                actual_bcd_rate: 0.0,
+                actual_sws_rate: 0.0,
+                actual_igst_rate: 0.0,
+                actual_aidc_rate: 0.0,
+                actual_safeguard_rate: 0.0,
+                actual_cess_rate: 0.0,
+                sws_exempt: false,
+                add_rate: 0.0,
+                add_per_unit: 0.0,
+            },
+            (
+                row.get::<_, Option<f64>>(6)?,
+                row.get::<_, Option<f64>>(7)?,
+                row.get::<_, Option<f64>>(8)?,
+            ),
+            row.get::<_, Option<String>>(10)?,
+        ))
+    })?;
+    let mut lines = Vec::new();
+    for r in rows {
+        let (mut line, stored, supplier_id) = r?;
+        let rates = tariff::resolve_duty_rates(conn, &line.hs_code, as_of, stored);
+        line.actual_bcd_rate = rates.bcd;
+        line.actual_sws_rate = rates.sws;
+        line.actual_igst_rate = rates.igst;
+        line.actual_aidc_rate = rates.aidc;
+        line.actual_safeguard_rate = rates.safeguard;
+        line.actual_cess_rate = rates.cess;
+        line.sws_exempt = rates.sws_exempt;
+        if let Some(add) = anti_dumping::duty_for(
+            conn,
+            &line.hs_code,
+            line.country_of_origin.as_deref(),
+            supplier_id.as_deref(),
+            as_of,
+        )? {
+            line.add_rate = add.rate_percent;
+            line.add_per_unit = add.specific_per_unit;
+        }
+        lines.push(line);
+    }
+    Ok(lines)
+}
+
+fn validate_form_values(form: &FormValues) -> Result<(), String> {
+    if !form.exchange_rate.is_finite() || form.exchange_rate <= 0.0 {
+        return Err("Exchange rate must be greater than zero".to_string());
+    }
+    for (label, v) in [
+        ("Freight cost", form.freight_cost),
+        ("EXW cost", form.exw_cost),
+        ("Insurance rate", form.insurance_rate),
+        ("Interest", form.interest.unwrap_or(0.0)),
+    ] {
+        if !v.is_finite() || v < 0.0 {
+            return Err(format!("{label} cannot be negative"));
+        }
+    }
+    Ok(())
+}
+
+/// CIF assessable value in INR for one line of the invoice `lines`; see [`valuation::value_line`].
+pub fn line_assessable_value(
+    line: &ShipmentDutyLine,
+    lines: &[ShipmentDutyLine],
+    form: &FormValues,
+) -> f64 {
+    valuation::value_line(line, lines, form).assessable_value
+}
+
+/// The line cut down to `quantity` units (a warehoused, ex-bond or partial clearance) and the share of
+/// the line's value that portion carries. `None` is the whole line.
+pub fn line_portion(line: &ShipmentDutyLine, quantity: Option<f64>) -> (ShipmentDutyLine, f64) {
+    match quantity {
+        Some(q) if line.qty > 0.0 => (
+            ShipmentDutyLine {
+                qty: q,
+                ..line.clone()
+            },
+            q / line.qty,
+        ),
+        _ => (line.clone(), 1.0),
+    }
+}
+
+/// BCD, AIDC, anti-dumping and safeguard duty on assessable value; SWS on BCD only (AIDC, ADD and
+/// safeguard duty are exempt from SWS, and SWS-exempt goods pay none); IGST and compensation cess on
+/// AV plus every customs duty. For RoDTEP lines BCD is paid at the BOE rate (scrip debit) but SWS and
+/// IGST are still levied on the tariff BCD. Specific ADD is converted at `exchange_rate`.
+pub fn compute_line_duty(
+    assessable_value: f64,
+    exchange_rate: f64,
+    input: &BoeItemInput,
+    line: &ShipmentDutyLine,
+) -> Result<LineDuty, String> {
+    let pct = |rate: f64| assessable_value * rate / 100.0;
+    let bcd = pct(input.boe_bcd_rate);
+    let bcd_base = match input.calculation_method.as_str() {
+        METHOD_STANDARD | METHOD_CEPA => bcd,
+        METHOD_RODTEP => pct(line.actual_bcd_rate),
+        other => return Err(format!("Unknown calculation method '{other}'")),
+    };
+    let aidc = pct(input.boe_aidc_rate.unwrap_or(line.actual_aidc_rate));
+    let sws = if line.sws_exempt {
+        0.0
+    } else {
+        bcd_base * input.boe_sws_rate / 100.0
+    };
+    let add = match input.boe_add_rate {
+        Some(rate) => pct(rate),
+        None => pct(line.add_rate) + line.qty * line.add_per_unit * exchange_rate,
+    };
+    let safeguard = pct(input
+        .boe_safeguard_rate
+        .unwrap_or(line.actual_safeguard_rate));
+    let igst_base = assessable_value + bcd_base + aidc + sws + add + safeguard;
+    let igst = igst_base * input.boe_igst_rate / 100.0;
+    let cess = igst_base * input.boe_cess_rate.unwrap_or(line.actual_cess_rate) / 100.0;
+    Ok(LineDuty {
+        bcd: round_paise(bcd),
+        aidc: round_paise(aidc),
+        sws: round_paise(sws),
+        add: round_paise(add),
+        safeguard: round_paise(safeguard),
+        igst: round_paise(igst),
+        cess: round_paise(cess),
+    })
+}
+
+/// Pairs each of `items` with the entry of `candidates` for the same part at the same position among
+/// that part's entries, so a part invoiced on several lines keeps each line's quantity and price.
+pub fn pair_by_part<'a, 'b, A, B>(
+    items: &'b [A],
+    candidates: &'a [B],
+    item_part: fn(&A) -> &str,
+    candidate_part: fn(&B) -> &str,
+) -> Vec<Option<&'a B>> {
+    let mut seen: HashMap<&'b str, usize> = HashMap::new();
+    items
+        .iter()
+        .map(|item| {
+            let part = item_part(item);
+            let n = seen.entry(part).or_insert(0);
+            let found = candidates
+                .iter()
+                .filter(|c| candidate_part(c) == part)
+                .nth(*n);
+            *n += 1;
+            found
+        })
+        .collect()
+}
+
+/// Full BOE calculation for the given shipment lines. Every item input must match a shipment line,
+/// inputs for a repeated part pairing with its lines in order; lines without an input are left out
+/// of the BOE.
+pub fn calculate(
+    lines: &[ShipmentDutyLine],
+    form: &FormValues,
+    inputs: &[BoeItemInput],
+) -> Result<CalculationResult, String> {
+    validate_form_values(form)?;
+    let line_of_input = pair_by_part(inputs, lines, |ii| &ii.part_no, |l| &l.part_no);
+    if let Some((orphan, _)) = inputs
+        .iter()
+        .zip(&line_of_input)
+        .find(|(_, line)| line.is_none())
+    {
+        let invoiced = lines.iter().filter(|l| l.part_no == orphan.part_no).count();
+        return Err(if invoiced == 0 {
+            format!(
+                "Part {} is not on any invoice line of this shipment",
+                orphan.part_no
+            )
+        } else {
+            format!(
+                "Part {} has more BOE items than its {invoiced} invoice line(s)",
+                orphan.part_no
+            )
+        });
+    }
+
+    let mut calculated_items = Vec::new();
+    let input_of_line = pair_by_part(lines, inputs, |l| &l.part_no, |ii| &ii.part_no);
+    for (line, input) in lines.iter().zip(input_of_line) {
+        let Some(input) = input else {
+            continue;
+        };
+        if let Some(q) = input.quantity {
+            if !q.is_finite() || q <= 0.0 || q > line.qty + 1e-9 {
+                return Err(format!(
+                    "Quantity {q} for part {} must be above zero and at most the invoiced {}",
+                    line.part_no, line.qty
+                ));
+            }
+        }
+        // Freight and EXW are apportioned over the whole invoice before the portion is taken.
+        let (portion, share) = line_portion(line, input.quantity);
+        let valuation = valuation::value_line(line, lines, form).portion(share);
+        let assessable_value = valuation.assessable_value;
+        let duty = compute_line_duty(assessable_value, form.exchange_rate, input, &portion)?;
+        calculated_items.push(CalculatedDutyItem {
+            part_no: line.part_no.clone(),
+            description: line.description.clone(),
+            assessable_value,
+            bcd_value: duty.bcd,
+            sws_value: duty.sws,
+            igst_value: duty.igst,
+            aidc_value: duty.aidc,
+            add_value: duty.add,
+            safeguard_value: duty.safeguard,
+            cess_value: duty.cess,
+            valuation: Some(valuation),
+        });
+    }
+
+    let total =
+        |head: fn(&CalculatedDutyItem) -> f64| round_rupee(calculated_items.iter().map(head).sum());
+    let bcd_total = total(|i| i.bcd_value);
+    let sws_total = total(|i| i.sws_value);
+    let igst_total = total(|i| i.igst_value);
+    let aidc_total = total(|i| i.aidc_value);
+    let add_total = total(|i| i.add_value);
+    let safeguard_total = total(|i| i.safeguard_value);
+    let cess_total = total(|i| i.cess_value);
+    let interest = round_rupee(form.interest.unwrap_or(0.0));
+    Ok(CalculationResult {
+        calculated_items,
+        bcd_total,
+        sws_total,
+        igst_total,
+        aidc_total,
+        add_total,
+        safeguard_total,
+        cess_total,
+        interest,
+        customs_duty_total: bcd_total
+            + sws_total
+            + igst_total
+            + aidc_total
+            + add_total
+            + safeguard_total
+            + cess_total
+            + interest,
+        duty_credit_total: 0.0,
+        statutory_charges: None,
+    })
+}
+
+/// Loads the shipment's invoice lines with tariff rates as of `be_date` (today when the BOE is not
+/// filed yet), checks CEPA lines against origin and certificates, and runs [`calculate`].
+pub fn calculate_for_shipment(
+    conn: &Connection,
+    shipment_id: &str,
+    be_date: Option<&str>,
+    form: &FormValues,
+    inputs: &[BoeItemInput],
+) -> Result<CalculationResult, String> {
+    let as_of = be_date.map(str::to_string).unwrap_or_else(tariff::today);
+    let lines = load_shipment_lines(conn, shipment_id, &as_of).map_err(|e| e.to_string())?;
+    if lines.is_empty() {
+        return Err(format!("Shipment {shipment_id} has no invoice lines"));
+    }
+    trade_agreements::validate_preferential_claims(conn, shipment_id, &as_of, &lines, inputs)?;
+    calculate(&lines, form, inputs)
+}
+
+/// Tariff-rate duty for a line (what the goods would pay without any concession), used by reconciliation.
+pub fn standard_line_duty(
+    assessable_value: f64,
+    exchange_rate: f64,
+    line: &ShipmentDutyLine,
+) -> LineDuty {
+    let input = BoeItemInput {
+        part_no: line.part_no.clone(),
+        calculation_method: METHOD_STANDARD.to_string(),
+        boe_bcd_rate: line.actual_bcd_rate,
+        boe_sws_rate: line.actual_sws_rate,
+        boe_igst_rate: line.actual_igst_rate,
+        boe_aidc_rate: None,
+        boe_add_rate: None,
+        boe_safeguard_rate: None,
+        boe_cess_rate: None,
+        quantity: None,
+    };
+    compute_line_duty(assessable_value, exchange_rate, &input, line).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn form(exchange_rate: f64, freight: f64, exw: f64, insurance: f64) -> FormValues {
+        FormValues {
+            supplier_name: "ACME".into(),
+            shipment_id: "SHP-1".into(),
+            exchange_rate,
+            freight_cost: freight,
+            exw_cost: exw,
+            insurance_rate: insurance,
+            interest: None,
+            incoterm: None,
+            apportionment_basis: None,
+        }
+    }
+
+    fn line(part: &str, total: f64, bcd: f64) -> ShipmentDutyLine {
+        ShipmentDutyLine {
+            part_no: part.into(),
+            description: format!("{part} desc"),
+            qty: 1.0,
+            unit_price: total,
+            hs_code: "84821011".into(),
+            country_of_origin: None,
+            line_total: total,
+            weight_kg: 0.0,
+            actual_bcd_rate: bcd,
+            actual_sws_rate: 10.0,
+            actual_igst_rate: 18.0,
+            actual_aidc_rate: 0.0,
+            actual_safeguard_rate: 0.0,
+            actual_cess_rate: 0.0,
+            sws_exempt: false,
+            add_rate: 0.0,
+            add_per_unit: 0.0,
+        }
+    }
+
+    fn input(part: &str, method: &str, bcd: f64) -> BoeItemInput {
+        BoeItemInput {
+            part_no: part.into(),
+            calculation_method: method.into(),
+            boe_bcd_rate: bcd,
+            boe_sws_rate: 10.0,
+            boe_igst_rate: 18.0,
+            boe_aidc_rate: None,
+            boe_add_rate: None,
+            boe_safeguard_rate: None,
+            boe_cess_rate: None,
+            quantity: None,
+        }
+    }
+
+    #[test]
+    fn cif_apportions_freight_and_exw_then_converts() {
+        // 1000 USD line of a 2000 USD invoice: half of 200 freight and 100 EXW.
+        // FOB 1050, insurance 1.125% = 11.8125, CIF 1161.8125 × 83 = 96430.44
+        let lines = vec![line("A", 1000.0, 10.0), line("B", 1000.0, 10.0)];
+        let av = line_assessable_value(&lines[0], &lines, &form(83.0, 200.0, 100.0, 1.125));
+        assert_eq!(av, 96430.44);
+    }
+
+    #[test]
+    fn standard_duty_cascade() {
+        let l = line("P1", 1000.0, 10.0);
+        let d = compute_line_duty(100000.0, 83.0, &input("P1", METHOD_STANDARD, 10.0), &l).unwrap();
+        assert_eq!(d.bcd, 10000.0);
+        assert_eq!(d.sws, 1000.0);
+        assert_eq!(d.igst, 19980.0);
+    }
+
+    #[test]
+    fn rodtep_levies_sws_and_igst_on_tariff_bcd() {
+        let l = line("P1", 1000.0, 10.0);
+        let d = compute_line_duty(100000.0, 83.0, &input("P1", METHOD_RODTEP, 0.0), &l).unwrap();
+        assert_eq!(d.bcd, 0.0);
+        assert_eq!(d.sws, 1000.0);
+        assert_eq!(d.igst, 19980.0);
+    }
+
+    #[test]
+    fn totals_are_rounded_to_rupee() {
+        let lines = vec![line("A", 333.33, 7.5), line("B", 666.67, 7.5)];
+        let inputs = vec![
+            input("A", METHOD_STANDARD, 7.5),
+            input("B", METHOD_STANDARD, 7.5),
+        ];
+        let r = calculate(&lines, &form(83.17, 0.0, 0.0, 0.0), &inputs).unwrap();
+        assert_eq!(r.calculated_items.len(), 2);
+        assert_eq!(r.bcd_total, r.bcd_total.round());
+        assert_eq!(
+            r.customs_duty_total,
+            r.bcd_total + r.sws_total + r.igst_total
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_part_and_bad_rate() {
+        let lines = vec![line("A", 100.0, 10.0)];
+        let err = calculate(
+            &lines,
+            &form(83.0, 0.0, 0.0, 0.0),
+            &[input("Z", METHOD_STANDARD, 10.0)],
+        )
+        .unwrap_err();
+        assert!(err.contains("Part Z"));
+        assert!(calculate(&lines, &form(0.0, 0.0, 0.0, 0.0), &[]).is_err());
+    }
+
+    #[test]
+    fn repeated_part_lines_keep_their_own_inputs() {
+        let mut second = line("A", 300.0, 10.0);
+        second.qty = 3.0;
+        second.unit_price = 100.0;
+        let lines = vec![line("A", 100.0, 10.0), second, line("B", 100.0, 10.0)];
+        let inputs = vec![
+            input("A", METHOD_STANDARD, 10.0),
+            input("A", METHOD_RODTEP, 0.0),
+            input("B", METHOD_STANDARD, 10.0),
+        ];
+        let r = calculate(&lines, &form(80.0, 0.0, 0.0, 0.0), &inputs).unwrap();
+        let av: Vec<f64> = r
+            .calculated_items
+            .iter()
+            .map(|i| i.assessable_value)
+            .collect();
+        assert_eq!(av, vec![8000.0, 24000.0, 8000.0]);
+        assert_eq!(r.calculated_items[0].bcd_value, 800.0);
+        assert_eq!(r.calculated_items[1].bcd_value, 0.0);
+
+        let err = calculate(
+            &lines,
+            &form(80.0, 0.0, 0.0, 0.0),
+            &[inputs[0].clone(), inputs[1].clone(), inputs[0].clone()],
+        )
+        .unwrap_err();
+        assert!(err.contains("more BOE items than its 2"));
+    }
+
+    #[test]
+    fn extra_heads_use_their_own_bases() {
+        let mut l = line("P1", 1000.0, 10.0);
+        l.actual_aidc_rate = 5.0;
+        l.actual_cess_rate = 1.0;
+        l.add_rate = 20.0;
+        let d = compute_line_duty(100000.0, 83.0, &input("P1", METHOD_STANDARD, 10.0), &l).unwrap();
+        // SWS on BCD only; IGST and cess on AV + BCD + AIDC + SWS + ADD = 136000
+        assert_eq!(
+            (d.bcd, d.aidc, d.sws, d.add),
+            (10000.0, 5000.0, 1000.0, 20000.0)
+        );
+        assert_eq!((d.igst, d.cess), (24480.0, 1360.0));
+
+        // Specific ADD (0.5 per unit × 10 units × 83) and an SWS exemption.
+        l.add_rate = 0.0;
+        l.add_per_unit = 0.5;
+        l.qty = 10.0;
+        l.sws_exempt = true;
+        let d = compute_line_duty(100000.0, 83.0, &input("P1", METHOD_STANDARD, 10.0), &l).unwrap();
+        assert_eq!((d.sws, d.add), (0.0, 415.0));
+
+        let r = calculate(
+            &[l],
+            &form(83.0, 0.0, 0.0, 0.0),
+            &[input("P1", METHOD_STANDARD, 10.0)],
+        )
+        .unwrap();
+        assert_eq!(
+            r.customs_duty_total,
+            r.bcd_total + r.aidc_total + r.add_total + r.igst_total + r.cess_total
+        );
+    }
+}
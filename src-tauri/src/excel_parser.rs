@@ -38,7 +38,7 @@ pub fn parse_excel_invoice(file_bytes: &[u8]) -> Result<String, String> {
 }
 
 /// First worksheet in document order that is not hidden/very hidden and is a data worksheet.
-fn first_visible_worksheet_name<RS: std::io::Read + std::io::Seek>(
+pub(crate) fn first_visible_worksheet_name<RS: std::io::Read + std::io::Seek>(
     workbook: &Sheets<RS>,
 ) -> Option<String> {
     for sheet in workbook.sheets_metadata() {
@@ -83,7 +83,7 @@ fn format_row(row: &[Data]) -> Option<String> {
     }
 }
 
-fn cell_to_display(cell: &Data) -> String {
+pub(crate) fn cell_to_display(cell: &Data) -> String {
     // Avoid excessive decimal noise for whole floats.
     if let Data::Float(f) = cell {
         if f.fract() == 0.0 && f.is_finite() {
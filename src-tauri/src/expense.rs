@@ -1805,7 +1805,10 @@ pub async fn create_expense_invoice(
     state: State<'_, DbState>,
 ) -> Result<ExpenseInvoiceResponse, String> {
     let mut conn = state.db.lock().unwrap();
-    ExpenseService::create_or_update_invoice(&mut conn, payload).map_err(|e| e.to_string())
+    let response =
+        ExpenseService::create_or_update_invoice(&mut conn, payload).map_err(|e| e.to_string())?;
+    crate::commands::landed_cost::recompute_after_expense_change(&conn, &response.invoice_id);
+    Ok(response)
 }
 
 #[tauri::command]
@@ -1825,7 +1828,10 @@ pub async fn combine_expense_duplicates(
 ) -> Result<ExpenseInvoiceResponse, String> {
     let mut conn = state.db.lock().unwrap();
     let separator = request.separator.as_deref().unwrap_or("; ");
-    ExpenseService::combine_duplicates(&mut conn, &invoice_id, separator).map_err(|e| e.to_string())
+    let response = ExpenseService::combine_duplicates(&mut conn, &invoice_id, separator)
+        .map_err(|e| e.to_string())?;
+    crate::commands::landed_cost::recompute_after_expense_change(&conn, &invoice_id);
+    Ok(response)
 }
 
 #[tauri::command]
@@ -0,0 +1,334 @@
+//! GSTR-2B parser for the IMPG (imports of goods) section, from the portal JSON download or the Excel workbook.
+
+use crate::commands::tariff::normalize_date;
+use calamine::{open_workbook_auto_from_rs, Data, Reader, Sheets};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::io::Cursor;
+
+/// Zip container (`.xlsx`) and OLE compound file (`.xls`) signatures.
+const XLSX_HEADER: &[u8; 4] = b"PK\x03\x04";
+const XLS_HEADER: &[u8; 4] = b"\xD0\xCF\x11\xE0";
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ParsedGstr2b {
+    pub gstin: Option<String>,
+    /// `MMYYYY` return period, when the file carries it.
+    pub return_period: Option<String>,
+    pub rows: Vec<Gstr2bImpgRow>,
+    /// `"json"` or `"excel"`.
+    pub source_format: String,
+    pub warnings: Vec<String>,
+}
+
+/// One Bill of Entry as reported in GSTR-2B; amounts in INR.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Gstr2bImpgRow {
+    pub be_number: String,
+    /// `YYYY-MM-DD`.
+    pub be_date: String,
+    pub port_code: Option<String>,
+    /// Date ICEGATE reported the BE to GSTN.
+    pub reference_date: Option<String>,
+    pub taxable_value: f64,
+    pub igst: f64,
+    pub cess: f64,
+    pub is_amended: bool,
+}
+
+/// Parses a GSTR-2B download; JSON and Excel are told apart by content, not extension.
+pub fn parse_bytes(file_bytes: &[u8]) -> Result<ParsedGstr2b, String> {
+    if file_bytes.starts_with(XLSX_HEADER) || file_bytes.starts_with(XLS_HEADER) {
+        return parse_excel(file_bytes);
+    }
+    let text = String::from_utf8_lossy(file_bytes);
+    let text = text.trim_start_matches('\u{feff}').trim_start();
+    if text.starts_with('{') {
+        return parse_json(text);
+    }
+    Err("Unrecognised GSTR-2B file; expected the portal JSON or Excel download".to_string())
+}
+
+fn parse_date(raw: &str) -> Option<String> {
+    let raw = raw.trim();
+    normalize_date(raw).or_else(|| {
+        chrono::NaiveDate::parse_from_str(raw, "%d-%b-%Y")
+            .ok()
+            .map(|d| d.format("%Y-%m-%d").to_string())
+    })
+}
+
+/// Depth-first search for the first value stored under `key`.
+fn find_key<'a>(v: &'a Value, key: &str) -> Option<&'a Value> {
+    match v {
+        Value::Object(map) => map
+            .get(key)
+            .or_else(|| map.values().find_map(|c| find_key(c, key))),
+        Value::Array(items) => items.iter().find_map(|c| find_key(c, key)),
+        _ => None,
+    }
+}
+
+fn json_str(obj: &Value, keys: &[&str]) -> Option<String> {
+    keys.iter().find_map(|k| match obj.get(*k)? {
+        Value::String(s) if !s.trim().is_empty() => Some(s.trim().to_string()),
+        Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    })
+}
+
+fn json_amount(obj: &Value, keys: &[&str]) -> f64 {
+    keys.iter()
+        .find_map(|k| match obj.get(*k)? {
+            Value::Number(n) => n.as_f64(),
+            Value::String(s) => s.trim().replace(',', "").parse().ok(),
+            _ => None,
+        })
+        .unwrap_or(0.0)
+}
+
+pub fn parse_json(text: &str) -> Result<ParsedGstr2b, String> {
+    let root: Value =
+        serde_json::from_str(text).map_err(|e| format!("Invalid GSTR-2B JSON: {e}"))?;
+    let mut doc = ParsedGstr2b {
+        gstin: find_key(&root, "gstin")
+            .and_then(Value::as_str)
+            .map(str::to_string),
+        return_period: find_key(&root, "rtnprd")
+            .and_then(Value::as_str)
+            .map(str::to_string),
+        source_format: "json".to_string(),
+        ..Default::default()
+    };
+    let Some(entries) = find_key(&root, "impg").and_then(Value::as_array) else {
+        doc.warnings
+            .push("No IMPG section in the file; nothing to reconcile".to_string());
+        return Ok(doc);
+    };
+    for (i, e) in entries.iter().enumerate() {
+        let number = json_str(e, &["boenum", "benum", "boe_num"]);
+        let date = json_str(e, &["boedt", "bedt", "boe_dt"]).and_then(|d| parse_date(&d));
+        let (Some(be_number), Some(be_date)) = (number, date) else {
+            doc.warnings.push(format!(
+                "IMPG entry {}: missing BE number or date, skipped",
+                i + 1
+            ));
+            continue;
+        };
+        doc.rows.push(Gstr2bImpgRow {
+            be_number,
+            be_date,
+            port_code: json_str(e, &["portcode", "portcd", "port_code"]),
+            reference_date: json_str(e, &["refdt"]).and_then(|d| parse_date(&d)),
+            taxable_value: json_amount(e, &["txval"]),
+            igst: json_amount(e, &["igst", "iamt"]),
+            cess: json_amount(e, &["cess", "csamt"]),
+            is_amended: json_str(e, &["isamd"]).is_some_and(|a| a.eq_ignore_ascii_case("Y")),
+        });
+    }
+    Ok(doc)
+}
+
+/// Excel stores dates as days since 1899-12-30.
+fn excel_serial_to_date(serial: f64) -> Option<String> {
+    let epoch = chrono::NaiveDate::from_ymd_opt(1899, 12, 30)?;
+    epoch
+        .checked_add_signed(chrono::Duration::days(serial.trunc() as i64))
+        .map(|d| d.format("%Y-%m-%d").to_string())
+}
+
+fn cell_text(cell: &Data) -> String {
+    match cell {
+        Data::Float(f) if f.fract() == 0.0 && f.is_finite() => format!("{f:.0}"),
+        Data::DateTime(d) => excel_serial_to_date(d.as_f64()).unwrap_or_default(),
+        other => other.to_string().trim().to_string(),
+    }
+}
+
+fn cell_amount(cell: Option<&Data>) -> f64 {
+    match cell {
+        Some(Data::Float(f)) => *f,
+        Some(Data::Int(i)) => *i as f64,
+        Some(Data::String(s)) => s.trim().replace(',', "").parse().unwrap_or(0.0),
+        _ => 0.0,
+    }
+}
+
+/// Column positions in the IMPG sheet; the portal writes two header rows (group, then field).
+#[derive(Default)]
+struct ImpgColumns {
+    reference_date: Option<usize>,
+    port_code: Option<usize>,
+    be_number: Option<usize>,
+    be_date: Option<usize>,
+    taxable_value: Option<usize>,
+    igst: Option<usize>,
+    cess: Option<usize>,
+    amended: Option<usize>,
+}
+
+impl ImpgColumns {
+    fn from_labels(labels: &[String]) -> Self {
+        let find = |pred: &dyn Fn(&str) -> bool| labels.iter().position(|l| pred(l));
+        ImpgColumns {
+            reference_date: find(&|l| l.contains("reference")),
+            port_code: find(&|l| l.contains("port")),
+            be_number: find(&|l| l.contains("number") && !l.contains("reference")),
+            be_date: find(&|l| l.contains("date") && !l.contains("reference")),
+            taxable_value: find(&|l| l.contains("taxable")),
+            igst: find(&|l| l.contains("integrated")),
+            cess: find(&|l| l.contains("cess")),
+            amended: find(&|l| l.contains("amend")),
+        }
+    }
+}
+
+pub fn parse_excel(file_bytes: &[u8]) -> Result<ParsedGstr2b, String> {
+    let cursor = Cursor::new(file_bytes.to_vec());
+    let mut workbook: Sheets<_> = open_workbook_auto_from_rs(cursor)
+        .map_err(|e| format!("Failed to open GSTR-2B workbook: {e}"))?;
+    let sheet = workbook
+        .sheet_names()
+        .into_iter()
+        .find(|n| n.trim().eq_ignore_ascii_case("IMPG"))
+        .ok_or("The workbook has no IMPG sheet")?;
+    let range = workbook
+        .worksheet_range(&sheet)
+        .map_err(|e| format!("Failed to read the IMPG sheet: {e}"))?;
+    let rows: Vec<&[Data]> = range.rows().collect();
+
+    let mut doc = ParsedGstr2b {
+        source_format: "excel".to_string(),
+        ..Default::default()
+    };
+    let Some(header) = rows.iter().position(|r| {
+        r.iter()
+            .any(|c| cell_text(c).to_lowercase().contains("port code"))
+    }) else {
+        doc.warnings
+            .push("IMPG sheet has no header row; nothing to reconcile".to_string());
+        return Ok(doc);
+    };
+    let width = rows[header].len();
+    let labels: Vec<String> = (0..width)
+        .map(|i| {
+            let top = rows[header].get(i).map(cell_text).unwrap_or_default();
+            let sub = rows
+                .get(header + 1)
+                .and_then(|r| r.get(i))
+                .map(cell_text)
+                .unwrap_or_default();
+            format!("{top} {sub}").to_lowercase()
+        })
+        .collect();
+    let cols = ImpgColumns::from_labels(&labels);
+    let (Some(num_col), Some(date_col)) = (cols.be_number, cols.be_date) else {
+        return Err("IMPG sheet is missing the Bill of Entry number or date column".to_string());
+    };
+
+    for (i, row) in rows.iter().enumerate().skip(header + 1) {
+        let text = |c: Option<usize>| {
+            c.and_then(|c| row.get(c))
+                .map(cell_text)
+                .filter(|s| !s.is_empty())
+        };
+        let Some(be_number) = text(Some(num_col)) else {
+            continue;
+        };
+        // The second header row ("Number", "Date") has no parseable date.
+        let Some(be_date) = text(Some(date_col)).and_then(|d| parse_date(&d)) else {
+            if i > header + 1 {
+                doc.warnings
+                    .push(format!("IMPG row {}: unreadable BE date, skipped", i + 1));
+            }
+            continue;
+        };
+        doc.rows.push(Gstr2bImpgRow {
+            be_number,
+            be_date,
+            port_code: text(cols.port_code),
+            reference_date: text(cols.reference_date).and_then(|d| parse_date(&d)),
+            taxable_value: cell_amount(cols.taxable_value.and_then(|c| row.get(c))),
+            igst: cell_amount(cols.igst.and_then(|c| row.get(c))),
+            cess: cell_amount(cols.cess.and_then(|c| row.get(c))),
+            is_amended: text(cols.amended).is_some_and(|a| a.eq_ignore_ascii_case("yes")),
+        });
+    }
+    Ok(doc)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_xlsxwriter::Workbook;
+
+    #[test]
+    fn json_and_excel_impg_rows() {
+        let json = r#"{"chksum":"x","data":{"gstin":"27AAAAA0000A1Z5","rtnprd":"072024","docdata":{
+            "impg":[{"refdt":"12-07-2024","recdt":"13-07-2024","portcode":"INNSA1","boenum":"1234567",
+                     "boedt":"10-07-2024","isamd":"N","txval":111000.5,"igst":19980.09,"cess":0}],
+            "impgsez":[{"boenum":"7654321","boedt":"11-07-2024","igst":10}]}}}"#;
+        let doc = parse_bytes(json.as_bytes()).unwrap();
+        assert_eq!(doc.gstin.as_deref(), Some("27AAAAA0000A1Z5"));
+        assert_eq!(doc.return_period.as_deref(), Some("072024"));
+        assert_eq!(doc.rows.len(), 1);
+        assert_eq!(doc.rows[0].be_date, "2024-07-10");
+        assert_eq!(doc.rows[0].port_code.as_deref(), Some("INNSA1"));
+        assert_eq!(doc.rows[0].igst, 19980.09);
+
+        let mut wb = Workbook::new();
+        let sheet = wb.add_worksheet().set_name("IMPG").unwrap();
+        sheet
+            .write_string(0, 0, "Goods imported from overseas")
+            .unwrap();
+        for (c, h) in [
+            "Reference date (ICEGATE)",
+            "Port code",
+            "Bill of Entry Details",
+            "",
+            "Taxable Value (₹)",
+            "Amount of tax (₹)",
+            "",
+            "Amended (Yes)",
+        ]
+        .iter()
+        .enumerate()
+        {
+            sheet.write_string(2, c as u16, *h).unwrap();
+        }
+        for (c, h) in [
+            (2, "Number"),
+            (3, "Date"),
+            (5, "Integrated Tax(₹)"),
+            (6, "Cess(₹)"),
+        ] {
+            sheet.write_string(3, c, h).unwrap();
+        }
+        sheet.write_string(4, 0, "12-07-2024").unwrap();
+        sheet.write_string(4, 1, "INNSA1").unwrap();
+        sheet.write_number(4, 2, 1234567.0).unwrap();
+        sheet.write_string(4, 3, "10/07/2024").unwrap();
+        sheet.write_number(4, 4, 111000.5).unwrap();
+        sheet.write_number(4, 5, 19980.09).unwrap();
+        sheet.write_number(4, 6, 0.0).unwrap();
+        sheet.write_string(4, 7, "Yes").unwrap();
+        let doc = parse_bytes(&wb.save_to_buffer().unwrap()).unwrap();
+        assert_eq!(doc.source_format, "excel");
+        assert_eq!(
+            doc.rows,
+            vec![Gstr2bImpgRow {
+                be_number: "1234567".to_string(),
+                be_date: "2024-07-10".to_string(),
+                port_code: Some("INNSA1".to_string()),
+                reference_date: Some("2024-07-12".to_string()),
+                taxable_value: 111000.5,
+                igst: 19980.09,
+                cess: 0.0,
+                is_amended: true,
+            }]
+        );
+        assert!(doc.warnings.is_empty(), "{:?}", doc.warnings);
+    }
+}
@@ -0,0 +1,627 @@
+//! Bill of Entry parser for broker documents: ICEGATE checklist / BE PDF text and delimited flat files.
+//!
+//! PDFs are converted with Poppler's `pdftotext -layout` (install on `PATH`), then read as checklist text.
+
+use crate::commands::tariff::{normalize_date, normalize_hsn};
+use crate::utils::csv::{self, CsvRow};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use std::process::Command;
+use uuid::Uuid;
+
+const PDF_HEADER: &[u8; 4] = b"%PDF";
+
+/// Field separators seen in ICEGATE / broker flat files, in order of preference.
+const DELIMITERS: [char; 4] = ['\u{1d}', '|', '\t', ','];
+
+/// Amounts tolerated between item sums and BE totals before a warning is raised (rupee rounding).
+const TOTAL_TOLERANCE: f64 = 1.0;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ParsedBillOfEntry {
+    pub be_number: Option<String>,
+    /// `YYYY-MM-DD`.
+    pub be_date: Option<String>,
+    pub port_code: Option<String>,
+    pub iec: Option<String>,
+    pub invoice_number: Option<String>,
+    pub currency: Option<String>,
+    pub exchange_rate: Option<f64>,
+    pub total_assessable_value: Option<f64>,
+    pub total_duty: Option<f64>,
+    pub interest: Option<f64>,
+    pub items: Vec<ParsedBoeItem>,
+    /// `"pdf"` or `"flat-file"`.
+    pub source_format: String,
+    pub warnings: Vec<String>,
+}
+
+/// One BE item with duty heads as assessed, in INR.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ParsedBoeItem {
+    pub serial: u32,
+    pub part_no: Option<String>,
+    pub hsn_code: Option<String>,
+    pub description: Option<String>,
+    pub quantity: Option<f64>,
+    pub assessable_value: f64,
+    pub bcd: f64,
+    pub aidc: f64,
+    pub sws: f64,
+    pub add: f64,
+    pub safeguard: f64,
+    pub igst: f64,
+    pub cess: f64,
+}
+
+impl ParsedBoeItem {
+    pub fn duty_total(&self) -> f64 {
+        self.bcd + self.aidc + self.sws + self.add + self.safeguard + self.igst + self.cess
+    }
+}
+
+fn is_pdf_bytes(data: &[u8]) -> bool {
+    data.len() >= 4 && data[0..4] == *PDF_HEADER
+}
+
+/// Extracts text from a PDF with `pdftotext -layout`, keeping the column layout item rows rely on.
+pub fn pdf_to_text(file_bytes: &[u8]) -> Result<String, String> {
+    if !is_pdf_bytes(file_bytes) {
+        return Err("not a PDF".to_string());
+    }
+    let pdf_path: PathBuf = std::env::temp_dir().join(format!("im_boe_{}.pdf", Uuid::new_v4()));
+    fs::write(&pdf_path, file_bytes).map_err(|e| e.to_string())?;
+    let output = Command::new("pdftotext")
+        .arg("-layout")
+        .arg(pdf_path.as_os_str())
+        .arg("-")
+        .output();
+    if let Err(e) = fs::remove_file(&pdf_path) {
+        log::debug!(
+            target: "import_manager::icegate",
+            "Could not remove temp PDF {}: {}",
+            pdf_path.display(),
+            e
+        );
+    }
+    let output = output.map_err(|e| {
+        if e.kind() == ErrorKind::NotFound {
+            "pdftotext not found (install Poppler and add pdftotext to PATH)".to_string()
+        } else {
+            e.to_string()
+        }
+    })?;
+    if !output.status.success() {
+        return Err(format!(
+            "pdftotext failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Parses a BE document as read from disk: PDFs go through `pdftotext`, anything else is read as text.
+pub fn parse_bytes(file_bytes: &[u8]) -> Result<ParsedBillOfEntry, String> {
+    if is_pdf_bytes(file_bytes) {
+        let mut doc = parse_checklist_text(&pdf_to_text(file_bytes)?);
+        doc.source_format = "pdf".to_string();
+        return finish(doc);
+    }
+    parse_document(&String::from_utf8_lossy(file_bytes))
+}
+
+/// Parses checklist text or a flat file, detected from the first line.
+pub fn parse_document(text: &str) -> Result<ParsedBillOfEntry, String> {
+    let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+    let doc = match flat_file_delimiter(text) {
+        Some(delimiter) => parse_flat_file(text, delimiter),
+        None => {
+            let mut doc = parse_checklist_text(text);
+            doc.source_format = "pdf".to_string();
+            doc
+        }
+    };
+    finish(doc)
+}
+
+const BE_NUMBER: &[&str] = &["be_no", "be_number", "bill_of_entry_no", "be_num"];
+const BE_DATE: &[&str] = &["be_date", "bill_of_entry_date"];
+const PORT: &[&str] = &["port_code", "port", "location", "customs_station"];
+const IEC: &[&str] = &["iec", "iec_code", "importer_code"];
+const INVOICE: &[&str] = &["invoice_no", "invoice_number", "inv_no"];
+const CURRENCY: &[&str] = &[
+    "currency",
+    "invoice_currency",
+    "inv_currency",
+    "currency_code",
+];
+const EXCHANGE_RATE: &[&str] = &["exchange_rate", "exch_rate", "exrate"];
+const TOTAL_AV: &[&str] = &[
+    "total_assessable_value",
+    "total_ass_value",
+    "tot_ass_val",
+    "total_av",
+];
+const TOTAL_DUTY: &[&str] = &["total_duty", "tot_duty", "total_duty_amount", "duty_amount"];
+const INTEREST: &[&str] = &["interest", "interest_amount"];
+const ITEM_AV: &[&str] = &[
+    "assessable_value",
+    "ass_value",
+    "item_assessable_value",
+    "av",
+];
+const ITEM_HSN: &[&str] = &["cth", "hsn", "hsn_code", "ritc", "tariff_item"];
+
+/// A flat file starts with a `<TABLE>` marker or a delimited header row naming known BE columns.
+fn flat_file_delimiter(text: &str) -> Option<char> {
+    let mut lines = text.lines().map(str::trim).filter(|l| !l.is_empty());
+    let first = if text.to_ascii_uppercase().contains("<TABLE>") {
+        lines
+            .skip_while(|l| !l.to_ascii_uppercase().starts_with("<TABLE>"))
+            .nth(1)?
+    } else {
+        lines.next()?
+    };
+    let delimiter = DELIMITERS.into_iter().find(|d| first.contains(*d))?;
+    let known = [BE_NUMBER, BE_DATE, ITEM_AV, ITEM_HSN, TOTAL_AV, INVOICE];
+    first
+        .split(delimiter)
+        .map(csv::normalize_header)
+        .any(|h| known.iter().any(|names| names.contains(&h.as_str())))
+        .then_some(delimiter)
+}
+
+/// Splits the file into tables. ICEGATE flat files wrap each table in `<TABLE>name` / `<END-TABLE>`,
+/// each with its own header row; files without markers are a single table.
+fn flat_file_tables(text: &str, delimiter: char) -> Vec<Vec<Vec<String>>> {
+    let split = |lines: &[&str]| -> Vec<Vec<String>> {
+        if delimiter == ',' {
+            return csv::parse_records(&lines.join("\n"));
+        }
+        lines
+            .iter()
+            .filter(|l| !l.trim().is_empty())
+            .map(|l| l.split(delimiter).map(|f| f.trim().to_string()).collect())
+            .collect()
+    };
+    let lines: Vec<&str> = text.lines().map(|l| l.trim_end_matches('\r')).collect();
+    if !lines
+        .iter()
+        .any(|l| l.trim().to_ascii_uppercase().starts_with("<TABLE>"))
+    {
+        return vec![split(&lines)];
+    }
+    let mut tables = Vec::new();
+    let mut current: Option<Vec<&str>> = None;
+    for line in lines {
+        let marker = line.trim().to_ascii_uppercase();
+        if marker.starts_with("<TABLE>") {
+            current = Some(Vec::new());
+        } else if marker.starts_with("<END-TABLE>") || marker.starts_with("<END TABLE>") {
+            if let Some(t) = current.take() {
+                tables.push(split(&t));
+            }
+        } else if let Some(t) = current.as_mut() {
+            t.push(line);
+        }
+    }
+    if let Some(t) = current {
+        tables.push(split(&t));
+    }
+    tables
+}
+
+fn parse_flat_file(text: &str, delimiter: char) -> ParsedBillOfEntry {
+    let mut doc = ParsedBillOfEntry {
+        source_format: "flat-file".to_string(),
+        ..Default::default()
+    };
+    for table in flat_file_tables(text, delimiter) {
+        if table.len() < 2 {
+            continue;
+        }
+        let _ = csv::for_each_record(&table, |row| {
+            read_flat_row(&mut doc, row);
+            Ok(())
+        });
+    }
+    doc
+}
+
+fn row_amount(row: &CsvRow, names: &[&str], warnings: &mut Vec<String>) -> Option<f64> {
+    row.get_f64(names).unwrap_or_else(|e| {
+        warnings.push(e);
+        None
+    })
+}
+
+fn read_flat_row(doc: &mut ParsedBillOfEntry, row: &CsvRow) {
+    let text = |names: &[&str]| row.get(names).map(str::to_string);
+    let w = &mut doc.warnings;
+    doc.be_number = doc.be_number.take().or_else(|| text(BE_NUMBER));
+    doc.be_date = doc
+        .be_date
+        .take()
+        .or_else(|| row.get(BE_DATE).and_then(parse_date));
+    doc.port_code = doc.port_code.take().or_else(|| text(PORT));
+    doc.iec = doc.iec.take().or_else(|| text(IEC));
+    doc.invoice_number = doc.invoice_number.take().or_else(|| text(INVOICE));
+    doc.currency = doc
+        .currency
+        .take()
+        .or_else(|| text(CURRENCY).map(|c| c.to_uppercase()));
+    if doc.exchange_rate.is_none() {
+        doc.exchange_rate = row_amount(row, EXCHANGE_RATE, w);
+    }
+    if doc.total_assessable_value.is_none() {
+        doc.total_assessable_value = row_amount(row, TOTAL_AV, w);
+    }
+    if doc.total_duty.is_none() {
+        doc.total_duty = row_amount(row, TOTAL_DUTY, w);
+    }
+    if doc.interest.is_none() {
+        doc.interest = row_amount(row, INTEREST, w);
+    }
+
+    if row.get(ITEM_AV).is_none() && row.get(ITEM_HSN).is_none() {
+        return;
+    }
+    let serial = row
+        .get(&["item_sno", "item_no", "sno", "s_no", "serial_no"])
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(doc.items.len() as u32 + 1);
+    let quantity = row_amount(row, &["qty", "quantity"], w);
+    let assessable_value = row_amount(row, ITEM_AV, w).unwrap_or(0.0);
+    let mut amount = |names: &[&str]| row_amount(row, names, w).unwrap_or(0.0);
+    let item = ParsedBoeItem {
+        serial,
+        part_no: text(&["part_no", "part_number", "item_code", "product_code"]),
+        hsn_code: row.get(ITEM_HSN).and_then(normalize_hsn),
+        description: text(&["description", "item_description", "goods_description"]),
+        quantity,
+        assessable_value,
+        bcd: amount(&["bcd", "bcd_amount", "bcd_amt", "basic_duty"]),
+        aidc: amount(&["aidc", "aidc_amount", "aidc_amt"]),
+        sws: amount(&["sws", "sws_amount", "sws_amt"]),
+        add: amount(&["add", "add_amount", "add_amt", "anti_dumping_duty"]),
+        safeguard: amount(&["sgd", "safeguard", "safeguard_duty", "sgd_amount"]),
+        igst: amount(&["igst", "igst_amount", "igst_amt"]),
+        cess: amount(&["cess", "cess_amount", "comp_cess", "gst_cess"]),
+    };
+    doc.items.push(item);
+}
+
+fn parse_date(raw: &str) -> Option<String> {
+    normalize_date(raw).or_else(|| {
+        ["%d-%b-%Y", "%d/%b/%Y", "%d %b %Y"]
+            .iter()
+            .find_map(|f| chrono::NaiveDate::parse_from_str(raw.trim(), f).ok())
+            .map(|d| d.format("%Y-%m-%d").to_string())
+    })
+}
+
+fn parse_amount(raw: &str) -> Option<f64> {
+    let cleaned = raw.trim().trim_end_matches('%').replace(',', "");
+    if cleaned.is_empty() || !cleaned.bytes().any(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    cleaned.parse().ok()
+}
+
+fn first_token(value: &str) -> &str {
+    value.split_whitespace().next().unwrap_or("")
+}
+
+/// Finds `label` (case-insensitive, on word boundaries) in any line and parses the text after it,
+/// up to the next column gap of two or more spaces. The first line that parses wins.
+fn find_labelled<T>(
+    lines: &[&str],
+    labels: &[&str],
+    parse: impl Fn(&str) -> Option<T>,
+) -> Option<T> {
+    lines.iter().find_map(|line| {
+        let lower = line.to_ascii_lowercase();
+        labels.iter().find_map(|label| {
+            lower.match_indices(label).find_map(|(at, _)| {
+                let end = at + label.len();
+                let bounded = |c: Option<char>| !matches!(c, Some(c) if c.is_ascii_alphanumeric());
+                if !bounded(lower[..at].chars().next_back())
+                    || !bounded(lower[end..].chars().next())
+                {
+                    return None;
+                }
+                let rest = line[end..].trim_start_matches([':', '-', '.', ' ', '\t']);
+                let value = rest.split("  ").next().unwrap_or("").trim();
+                (!value.is_empty()).then(|| parse(value)).flatten()
+            })
+        })
+    })
+}
+
+/// `"1 USD = 83.45 INR"`, `"USD 83.45"` or a bare `"83.45"`.
+fn parse_exchange(value: &str) -> Option<(Option<String>, f64)> {
+    let currency = value
+        .split(|c: char| !c.is_ascii_alphabetic())
+        .find(|t| t.len() == 3 && t.bytes().all(|b| b.is_ascii_uppercase()) && *t != "INR")
+        .map(str::to_string);
+    let rate_part = value.split_once('=').map_or(value, |(_, r)| r);
+    let rate = rate_part.split_whitespace().find_map(parse_amount)?;
+    Some((currency, rate))
+}
+
+/// Reads checklist text. Item rows are lines carrying an 8-digit CTH and end with the columns
+/// `[qty] assessable BCD SWS IGST`; serial and part number may precede the CTH. AIDC, ADD, safeguard
+/// and cess appear on their own labelled lines below the item they belong to.
+fn parse_checklist_text(text: &str) -> ParsedBillOfEntry {
+    let lines: Vec<&str> = text.lines().collect();
+    let mut doc = ParsedBillOfEntry::default();
+
+    let combined = find_labelled(
+        &lines,
+        &["be no/date", "be no / date", "b/e no/date"],
+        |v| {
+            let compact: String = v.split_whitespace().collect();
+            let (number, date) = compact.split_once('/')?;
+            Some((number.to_string(), parse_date(date)))
+        },
+    );
+    if let Some((number, date)) = combined {
+        doc.be_number = Some(number);
+        doc.be_date = date;
+    }
+    if doc.be_number.is_none() {
+        doc.be_number = find_labelled(
+            &lines,
+            &[
+                "be no",
+                "be number",
+                "bill of entry no",
+                "bill of entry number",
+                "b/e no",
+            ],
+            |v| {
+                let t = first_token(v);
+                t.bytes().any(|b| b.is_ascii_digit()).then(|| t.to_string())
+            },
+        );
+    }
+    if doc.be_date.is_none() {
+        doc.be_date = find_labelled(
+            &lines,
+            &["be date", "b/e date", "bill of entry date"],
+            |v| parse_date(first_token(v)),
+        );
+    }
+    doc.port_code = find_labelled(
+        &lines,
+        &["port code", "port of import", "custom house"],
+        |v| {
+            let t = first_token(v);
+            (t.len() >= 3 && t.bytes().all(|b| b.is_ascii_alphanumeric())).then(|| t.to_uppercase())
+        },
+    );
+    doc.iec = find_labelled(&lines, &["iec code", "iec no", "iec"], |v| {
+        let t = first_token(v);
+        (t.len() == 10 && t.bytes().all(|b| b.is_ascii_alphanumeric())).then(|| t.to_uppercase())
+    });
+    doc.invoice_number = find_labelled(&lines, &["invoice no", "invoice number", "inv no"], |v| {
+        Some(first_token(v).to_string())
+    });
+    let exchange = find_labelled(
+        &lines,
+        &["exchange rate", "exch rate", "exch. rate"],
+        parse_exchange,
+    );
+    doc.currency = find_labelled(
+        &lines,
+        &["invoice currency", "inv currency", "currency"],
+        |v| {
+            let t = first_token(v);
+            (t.len() == 3 && t.bytes().all(|b| b.is_ascii_alphabetic())).then(|| t.to_uppercase())
+        },
+    )
+    .or_else(|| exchange.as_ref().and_then(|(c, _)| c.clone()));
+    doc.exchange_rate = exchange.map(|(_, r)| r);
+    let amount = |v: &str| parse_amount(first_token(v));
+    doc.total_assessable_value = find_labelled(
+        &lines,
+        &[
+            "total assessable value",
+            "total ass. value",
+            "tot. ass. value",
+            "tot ass value",
+        ],
+        amount,
+    );
+    doc.total_duty = find_labelled(
+        &lines,
+        &["total duty amount", "total duty", "tot. duty", "tot duty"],
+        amount,
+    );
+    doc.interest = find_labelled(&lines, &["interest amount", "interest"], amount);
+
+    for line in &lines {
+        if let Some(item) = parse_item_line(line, doc.items.len() as u32 + 1) {
+            doc.items.push(item);
+            continue;
+        }
+        let Some(item) = doc.items.last_mut() else {
+            continue;
+        };
+        let lower = line.trim_start().to_ascii_lowercase();
+        if lower.starts_with("tot") {
+            continue;
+        }
+        let one = [*line];
+        let head = |labels: &[&str]| find_labelled(&one, labels, amount);
+        if let Some(v) = head(&["aidc"]) {
+            item.aidc += v;
+        } else if let Some(v) = head(&["anti-dumping duty", "anti dumping duty", "add"]) {
+            item.add += v;
+        } else if let Some(v) = head(&["safeguard duty", "safeguard", "sgd"]) {
+            item.safeguard += v;
+        } else if let Some(v) = head(&["comp cess", "gst cess", "cess"]) {
+            item.cess += v;
+        }
+    }
+    doc
+}
+
+fn parse_item_line(line: &str, next_serial: u32) -> Option<ParsedBoeItem> {
+    let tokens: Vec<&str> = line.split_whitespace().collect();
+    let hsn_at = tokens.iter().position(|t| {
+        let digits = t.replace('.', "");
+        digits.len() == 8 && digits.bytes().all(|b| b.is_ascii_digit())
+    })?;
+    let after = &tokens[hsn_at + 1..];
+    let numeric_tail = after
+        .iter()
+        .rev()
+        .take_while(|t| parse_amount(t).is_some())
+        .count();
+    if numeric_tail < 4 {
+        return None;
+    }
+    let amounts: Vec<f64> = after[after.len() - numeric_tail..]
+        .iter()
+        .filter_map(|t| parse_amount(t))
+        .collect();
+    let [av, bcd, sws, igst] = amounts[amounts.len() - 4..] else {
+        return None;
+    };
+    let quantity = (amounts.len() > 4).then(|| amounts[amounts.len() - 5]);
+
+    let before = &tokens[..hsn_at];
+    let (serial, part) = match before.split_first() {
+        Some((first, rest)) if first.parse::<u32>().is_ok() => (first.parse().ok(), rest),
+        _ => (None, before),
+    };
+    let joined = |t: &[&str]| (!t.is_empty()).then(|| t.join(" "));
+    Some(ParsedBoeItem {
+        serial: serial.unwrap_or(next_serial),
+        part_no: joined(part),
+        hsn_code: normalize_hsn(tokens[hsn_at]),
+        description: joined(&after[..after.len() - numeric_tail]),
+        quantity,
+        assessable_value: av,
+        bcd,
+        sws,
+        igst,
+        ..Default::default()
+    })
+}
+
+/// Fills totals the document left out and flags totals that disagree with the items.
+fn finish(mut doc: ParsedBillOfEntry) -> Result<ParsedBillOfEntry, String> {
+    if doc.be_number.is_none() && doc.items.is_empty() {
+        return Err(
+            "No Bill of Entry number or item lines found; is this an ICEGATE BE checklist or flat file?"
+                .to_string(),
+        );
+    }
+    let av_sum: f64 = doc.items.iter().map(|i| i.assessable_value).sum();
+    let duty_sum: f64 = doc.items.iter().map(ParsedBoeItem::duty_total).sum();
+    if !doc.items.is_empty() {
+        match doc.total_assessable_value {
+            Some(total) if (total - av_sum).abs() > TOTAL_TOLERANCE => doc.warnings.push(format!(
+                "Item assessable values add up to {av_sum:.2} but the BE total is {total:.2}"
+            )),
+            Some(_) => {}
+            None => doc.total_assessable_value = Some(av_sum),
+        }
+        let interest = doc.interest.unwrap_or(0.0);
+        match doc.total_duty {
+            Some(total)
+                if (total - duty_sum).abs() > TOTAL_TOLERANCE
+                    && (total - duty_sum - interest).abs() > TOTAL_TOLERANCE =>
+            {
+                doc.warnings.push(format!(
+                    "Item duties add up to {duty_sum:.2} but the BE total duty is {total:.2}"
+                ))
+            }
+            Some(_) => {}
+            None => doc.total_duty = Some(duty_sum),
+        }
+    } else {
+        doc.warnings.push("No item lines found".to_string());
+    }
+    if doc.be_number.is_none() {
+        doc.warnings.push("BE number not found".to_string());
+    }
+    if doc.be_date.is_none() {
+        doc.warnings.push("BE date not found".to_string());
+    }
+    Ok(doc)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flat_file_with_header_and_item_tables() {
+        let text = "HREC|ZZ|ICES\n\
+            <TABLE>BE_HEADER\n\
+            BE_NO|BE_DATE|PORT_CODE|IEC|INVOICE_NO|CURRENCY|EXCH_RATE|TOTAL_ASS_VALUE|TOTAL_DUTY\n\
+            2345678|12/03/2024|INNSA1|0512345678|INV-77|usd|83.45|150000.00|41010.00\n\
+            <END-TABLE>\n\
+            <TABLE>BE_ITEM\n\
+            ITEM_SNO|PART_NO|CTH|QTY|ASSESSABLE_VALUE|BCD_AMT|SWS_AMT|IGST_AMT|ADD_AMT\n\
+            1|BRG-6205|84821011|500|100000.00|10000.00|1000.00|20340.00|3000.00\n\
+            2|SEAL-01|40169320|100|50000.00|5000.00|500.00|1170.00|0\n\
+            <END-TABLE>\n\
+            TREC|ZZ\n";
+        let doc = parse_document(text).unwrap();
+        assert_eq!(doc.source_format, "flat-file");
+        assert_eq!(doc.be_number.as_deref(), Some("2345678"));
+        assert_eq!(doc.be_date.as_deref(), Some("2024-03-12"));
+        assert_eq!(doc.port_code.as_deref(), Some("INNSA1"));
+        assert_eq!(doc.currency.as_deref(), Some("USD"));
+        assert_eq!(doc.exchange_rate, Some(83.45));
+        assert_eq!(doc.items.len(), 2);
+        assert_eq!(doc.items[0].part_no.as_deref(), Some("BRG-6205"));
+        assert_eq!(doc.items[0].add, 3000.0);
+        assert_eq!(doc.items[1].hsn_code.as_deref(), Some("40169320"));
+        assert!(doc.warnings.is_empty(), "{:?}", doc.warnings);
+    }
+
+    #[test]
+    fn checklist_text_header_items_and_extra_heads() {
+        let text = "\
+            INDIAN CUSTOMS EDI SYSTEM - IMPORTS            CHECK LIST\n\
+            Port Code : INMAA1        BE No/Date : 7654321 / 05-Jan-2025\n\
+            IEC : AAACB1234C          Invoice No : EXP/2024/991\n\
+            Exchange Rate : 1 USD = 84.10 INR\n\
+            Total Assessable Value : 2,00,000.00     Total Duty : 60,800.00\n\
+            \n\
+            S.No  Part No   CTH        Description          Qty   Ass. Value   BCD        SWS       IGST\n\
+            1     BRG-6205  84821011   DEEP GROOVE BEARING  500   2,00,000.00  20,000.00  2,000.00  38,000.00\n\
+                  AIDC          800.00\n\
+            Total Cess : 0.00\n";
+        let doc = parse_document(text).unwrap();
+        assert_eq!(doc.source_format, "pdf");
+        assert_eq!(doc.be_number.as_deref(), Some("7654321"));
+        assert_eq!(doc.be_date.as_deref(), Some("2025-01-05"));
+        assert_eq!(doc.port_code.as_deref(), Some("INMAA1"));
+        assert_eq!(doc.invoice_number.as_deref(), Some("EXP/2024/991"));
+        assert_eq!(doc.currency.as_deref(), Some("USD"));
+        assert_eq!(doc.exchange_rate, Some(84.10));
+        assert_eq!(doc.total_assessable_value, Some(200000.0));
+
+        let item = &doc.items[..];
+        assert_eq!(item.len(), 1);
+        assert_eq!(item[0].serial, 1);
+        assert_eq!(item[0].part_no.as_deref(), Some("BRG-6205"));
+        assert_eq!(item[0].description.as_deref(), Some("DEEP GROOVE BEARING"));
+        assert_eq!(item[0].quantity, Some(500.0));
+        assert_eq!(
+            (item[0].bcd, item[0].sws, item[0].igst, item[0].aidc),
+            (20000.0, 2000.0, 38000.0, 800.0)
+        );
+        assert!(doc.warnings.is_empty(), "{:?}", doc.warnings);
+    }
+}
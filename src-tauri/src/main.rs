@@ -8,10 +8,15 @@ mod ai_prompt_builder;
 mod commands;
 mod deepseek_client;
 mod excel_parser;
+mod packing_list_parser;
 mod ollama_client;
 mod ocr_engine;
 mod confidence_engine;
 mod duplicate_detector;
+mod duty_engine;
+mod valuation;
+mod icegate;
+mod gstr2b;
 mod retry_engine;
 mod batch_processor;
 mod ai_analytics;
@@ -223,6 +228,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             // BOE Calculation commands
             commands::get_boe_calculations,
             commands::get_shipment_ids_with_boe_calculations,
+            commands::calculate_boe_duties,
             commands::add_boe_calculation,
             commands::update_boe_calculation,
             commands::delete_boe_calculation,
@@ -232,6 +238,152 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             commands::add_boe_attachment,
             commands::get_boe_reconciliation,
             commands::save_boe_attachment_file,
+            // HSN tariff master
+            commands::tariff::import_hsn_tariff_csv,
+            commands::tariff::get_hsn_tariff_rate,
+            commands::tariff::list_hsn_tariff_rates,
+            // Customs exchange-rate master
+            commands::exchange_rates::import_customs_exchange_rates,
+            commands::exchange_rates::get_customs_exchange_rate,
+            commands::exchange_rates::list_customs_exchange_rates,
+            commands::exchange_rates::get_shipment_customs_exchange_rate,
+            commands::exchange_rates::get_boe_exchange_rate_mismatches,
+            // Preferential trade agreements and certificates of origin
+            commands::trade_agreements::list_trade_agreements,
+            commands::trade_agreements::save_trade_agreement,
+            commands::trade_agreements::import_concessional_rates_csv,
+            commands::trade_agreements::list_concessional_rates,
+            commands::trade_agreements::get_certificates_of_origin,
+            commands::trade_agreements::save_certificate_of_origin,
+            commands::trade_agreements::delete_certificate_of_origin,
+            commands::trade_agreements::get_preferential_eligibility,
+            // Anti-dumping duty notifications
+            commands::anti_dumping::import_anti_dumping_csv,
+            commands::anti_dumping::list_anti_dumping_duties,
+            commands::anti_dumping::get_anti_dumping_duty,
+            // ICEGATE Bill of Entry import
+            commands::boe_import::preview_icegate_boe,
+            commands::boe_import::import_icegate_boe,
+            // BOE reconciliation tolerances and mismatch cases
+            commands::boe_reconciliation::list_boe_reconciliation_tolerances,
+            commands::boe_reconciliation::save_boe_reconciliation_tolerance,
+            commands::boe_reconciliation::recheck_boe_reconciliations,
+            // Duty drawback and RoDTEP credits
+            commands::duty_credits::list_duty_credit_entitlements,
+            commands::duty_credits::list_open_duty_credits,
+            commands::duty_credits::save_duty_credit_entitlement,
+            commands::duty_credits::delete_duty_credit_entitlement,
+            commands::duty_credits::get_duty_credit_utilisations,
+            commands::duty_credits::apply_duty_credits_to_boe,
+            commands::duty_credits::get_duty_credit_fiscal_year_report,
+            // GSTR-2B IMPG reconciliation
+            commands::gst_reconciliation::import_gstr2b_impg,
+            commands::gst_reconciliation::list_gstr2b_imports,
+            commands::gst_reconciliation::get_gstr2b_reconciliation,
+            commands::gst_reconciliation::export_gstr2b_reconciliation_csv,
+            commands::gst_reconciliation::delete_gstr2b_import,
+            // Bonded warehouse (into-bond / ex-bond) stock
+            commands::bonded_warehouse::get_bonded_stock,
+            // BOE statutory charges (late-payment interest, late filing)
+            commands::boe_charges::list_boe_charge_slabs,
+            commands::boe_charges::save_boe_charge_slabs,
+            commands::boe_charges::get_boe_charge_settings,
+            commands::boe_charges::save_boe_charge_settings,
+            commands::boe_charges::get_boe_charges,
+            commands::boe_charges::recompute_boe_charges,
+            // Landed cost allocation
+            commands::landed_cost::set_expense_type_allocation_basis,
+            commands::landed_cost::recompute_landed_costs,
+            commands::landed_cost::get_shipment_landed_costs,
+            commands::landed_cost::get_landed_cost_allocations,
+            commands::landed_cost::get_item_landed_cost_history,
+            // Frozen costing snapshots
+            commands::costing_snapshots::list_costing_snapshots,
+            commands::costing_snapshots::get_costing_snapshot,
+            commands::costing_snapshots::diff_costing_snapshot,
+            commands::costing_snapshots::reopen_shipment,
+            commands::costing_snapshots::list_shipment_reopenings,
+            // HSN classification assistant
+            commands::hsn_classification::suggest_hsn_codes,
+            commands::hsn_classification::apply_hsn_classification,
+            commands::hsn_classification::get_hsn_classification_history,
+            // Shipment milestone timeline
+            commands::shipment_events::record_shipment_event,
+            commands::shipment_events::delete_shipment_event,
+            commands::shipment_events::get_shipment_timeline,
+            commands::shipment_events::get_shipment_dwell_times,
+            commands::shipment_events::get_milestone_dwell_summary,
+            // Shipment status state machine
+            commands::shipment_status::get_shipment_status_transitions,
+            commands::shipment_status::get_allowed_status_transitions,
+            // Demurrage and detention free time
+            commands::demurrage::list_free_time_terms,
+            commands::demurrage::save_free_time_terms,
+            commands::demurrage::assign_shipment_free_time_terms,
+            commands::demurrage::get_shipment_free_time,
+            commands::demurrage::get_demurrage_accruals,
+            commands::demurrage::run_free_time_accrual,
+            // Purchase orders
+            commands::purchase_orders::list_purchase_orders,
+            commands::purchase_orders::get_purchase_order,
+            commands::purchase_orders::save_purchase_order,
+            commands::purchase_orders::set_purchase_order_status,
+            commands::purchase_orders::get_open_po_lines_for_shipment,
+            commands::purchase_orders::get_unshipped_po_report,
+            commands::purchase_orders::get_shipment_po_variances,
+            commands::purchase_orders::get_po_tolerances,
+            commands::purchase_orders::save_po_tolerances,
+            // Goods receipt notes
+            commands::goods_receipts::list_goods_receipts,
+            commands::goods_receipts::get_goods_receipt,
+            commands::goods_receipts::save_goods_receipt,
+            commands::goods_receipts::post_goods_receipt,
+            commands::goods_receipts::cancel_goods_receipt,
+            commands::goods_receipts::attach_goods_receipt_photo,
+            commands::goods_receipts::get_shipment_receipt_variances,
+            commands::goods_receipts::get_goods_receipt_claim_basis,
+            // Supplier payments and payables
+            commands::supplier_payments::list_supplier_payments,
+            commands::supplier_payments::save_supplier_payment,
+            commands::supplier_payments::delete_supplier_payment,
+            commands::supplier_payments::get_invoice_payment_positions,
+            commands::supplier_payments::get_supplier_ledger_statement,
+            commands::supplier_payments::get_payables_ageing,
+            // Forward contracts and hedge book
+            commands::forward_contracts::list_forward_contracts,
+            commands::forward_contracts::save_forward_contract,
+            commands::forward_contracts::cancel_forward_contract,
+            commands::forward_contracts::allocate_forward_contract,
+            commands::forward_contracts::remove_forward_contract_allocation,
+            commands::forward_contracts::list_forex_market_rates,
+            commands::forward_contracts::save_forex_market_rates,
+            commands::forward_contracts::get_hedge_exposure,
+            commands::forward_contracts::get_forward_contract_mtm,
+            // Shipment document checklists
+            commands::shipment_documents::list_document_types,
+            commands::shipment_documents::save_document_type,
+            commands::shipment_documents::list_checklist_templates,
+            commands::shipment_documents::save_checklist_template,
+            commands::shipment_documents::delete_checklist_template,
+            commands::shipment_documents::get_shipment_document_checklist,
+            commands::shipment_documents::add_shipment_document_slot,
+            commands::shipment_documents::attach_shipment_document,
+            commands::shipment_documents::set_shipment_document_status,
+            commands::shipment_documents::review_shipment_documents,
+            // Packing lists
+            commands::packing_lists::import_packing_list,
+            commands::packing_lists::list_packing_lists,
+            commands::packing_lists::delete_packing_list,
+            commands::packing_lists::get_packing_list_weight_tolerance,
+            commands::packing_lists::save_packing_list_weight_tolerance,
+            // ETA slippage and predictions
+            commands::eta_slippage::update_shipment_schedule,
+            commands::eta_slippage::get_shipment_schedule_changes,
+            commands::eta_slippage::get_eta_slippage_analytics,
+            commands::eta_slippage::get_predicted_eta,
+            commands::eta_slippage::refresh_eta_predictions,
+            commands::eta_slippage::get_overdue_eta_basis,
+            commands::eta_slippage::save_overdue_eta_basis,
             commands::save_item_photo_file,
 
             // --- Generic and Specific Option Commands ---
@@ -465,7 +617,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             // Freeze shipment
             commands::freeze_shipment,
             // Update shipment status
-            commands::update_shipment_status,
+            commands::shipment_status::update_shipment_status,
             commands::update_shipment_status_on_invoice_add,
             commands::update_shipment_status_on_boe_add,
             commands::check_and_update_ready_for_delivery,
@@ -683,11 +683,43 @@ mod tests {
             "expected migration head at least V48 (performance indexes), got {max_v}"
         );
 
-        assert!(
-            user_table_exists(&conn, "app_settings")
-                .map_err(|e| format!("app_settings: {e}"))?,
-            "app_settings must exist after migrations"
-        );
+        for table in [
+            "app_settings",
+            "hsn_tariff_rates",
+            "customs_exchange_rates",
+            "certificates_of_origin",
+            "anti_dumping_duties",
+            "boe_reconciliation_tolerances",
+            "duty_credit_entitlements",
+            "gstr2b_impg_rows",
+            "boe_charge_slabs",
+            "landed_cost_allocations",
+            "item_landed_costs",
+            "shipment_costing_snapshots",
+            "shipment_reopenings",
+            "hsn_classifications",
+            "shipment_events",
+            "shipment_status_transitions",
+            "free_time_terms",
+            "demurrage_accruals",
+            "purchase_orders",
+            "purchase_order_lines",
+            "goods_receipts",
+            "goods_receipt_lines",
+            "supplier_payments",
+            "forward_contracts",
+            "forex_market_rates",
+            "document_checklist_templates",
+            "shipment_documents",
+            "packing_list_lines",
+            "shipment_schedule_changes",
+            "shipment_eta_predictions",
+        ] {
+            assert!(
+                user_table_exists(&conn, table).map_err(|e| format!("{table}: {e}"))?,
+                "{table} must exist after migrations"
+            );
+        }
 
         Ok(())
     }
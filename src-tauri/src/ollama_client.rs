@@ -152,10 +152,11 @@ fn truncate(s: &str, max: usize) -> String {
     }
 }
 
-fn run_ollama_invoice_request_once(
+/// POST `body` and return the assistant text together with the raw response body.
+fn run_ollama_chat_once(
     config: &OllamaConfig,
     body: &serde_json::Value,
-) -> Result<ParsedInvoiceExtraction, String> {
+) -> Result<(String, String), String> {
     let client = build_http_client()?;
     let resp = client
         .post(&config.endpoint)
@@ -183,12 +184,17 @@ fn run_ollama_invoice_request_once(
         let friendly = format!("Ollama error (HTTP {}): {}", status.as_u16(), detail);
         return Err(friendly);
     }
-    let assist = match assistant_text_from_ollama_response_body(&text) {
-        Ok(a) => a,
-        Err(e) => {
-            return Err(format!("{e}. Body (truncated): {}", truncate(&text, 500)));
-        }
-    };
+    match assistant_text_from_ollama_response_body(&text) {
+        Ok(a) => Ok((a, text)),
+        Err(e) => Err(format!("{e}. Body (truncated): {}", truncate(&text, 500))),
+    }
+}
+
+fn run_ollama_invoice_request_once(
+    config: &OllamaConfig,
+    body: &serde_json::Value,
+) -> Result<ParsedInvoiceExtraction, String> {
+    let (assist, text) = run_ollama_chat_once(config, body)?;
     let mut parsed = parse_extraction_from_assistant_text(&assist)
         .map_err(|e| format!("{e} — assistant output (truncated): {}", truncate(&assist, 800)))?;
     parsed.raw_api_response = text;
@@ -275,6 +281,16 @@ pub fn call_ollama_ocr_text(
     run_ollama_invoice_request(config, &body)
 }
 
+/// Plain text-in / text-out chat for non-invoice prompts; same retries as extraction.
+pub fn call_ollama_chat(config: &OllamaConfig, system: &str, user: &str) -> Result<String, String> {
+    let body = build_ollama_request_text(&config.model_name, system, user);
+    retry_engine::execute_with_retry(
+        || run_ollama_chat_once(config, &body).map(|(assist, _)| assist),
+        retry_engine::DEFAULT_MAX_RETRIES,
+        retry_engine::is_retriable_network_timeout_or_5xx,
+    )
+}
+
 /// Stored in `ai_extraction_log.provider_used` for the local Ollama path.
 pub fn ollama_provider_label() -> &'static str {
     "local"
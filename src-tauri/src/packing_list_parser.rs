@@ -0,0 +1,327 @@
+//! Deterministic packing-list parsing: per-part quantities, package counts, net / gross weights and
+//! dimensions from an Excel sheet or from OCR / plain text laid out in columns.
+//!
+//! The header row is located by its captions (part number plus at least one weight or package
+//! column); rows below it are read positionally until a "total" row. Weights in pounds are
+//! converted to kilograms from the header caption.
+
+use std::io::Cursor;
+
+use calamine::{open_workbook_auto_from_rs, Reader, Sheets};
+use serde::{Deserialize, Serialize};
+
+use crate::excel_parser::{cell_to_display, first_visible_worksheet_name, EXCEL_PARSE_ERR};
+
+const KG_PER_LB: f64 = 0.453_592_37;
+
+/// One packing-list row; weights are row totals in kilograms.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct PackingListRow {
+    pub part_number: String,
+    pub description: Option<String>,
+    pub quantity: Option<f64>,
+    pub packages: Option<f64>,
+    pub net_weight_kg: Option<f64>,
+    pub gross_weight_kg: Option<f64>,
+    pub dimensions: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ParsedPackingList {
+    pub rows: Vec<PackingListRow>,
+    /// Totals as printed on the document's "total" row, when there is one.
+    pub declared_packages: Option<f64>,
+    pub declared_net_weight_kg: Option<f64>,
+    pub declared_gross_weight_kg: Option<f64>,
+    /// Rows that could not be read, and similar notes for the user.
+    pub warnings: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Column {
+    Part,
+    Description,
+    Quantity,
+    Packages,
+    NetWeight,
+    GrossWeight,
+    Dimensions,
+}
+
+fn classify_header(caption: &str) -> Option<Column> {
+    let c = caption.trim().to_lowercase();
+    let has = |needles: &[&str]| needles.iter().any(|n| c.contains(n));
+    if c.is_empty() {
+        None
+    } else if has(&["desc"]) {
+        Some(Column::Description)
+    } else if has(&["net", "n.w", "n/w"]) && !has(&["gross"]) {
+        Some(Column::NetWeight)
+    } else if has(&["gross", "g.w", "g/w"]) {
+        Some(Column::GrossWeight)
+    } else if has(&["dimension", "measurement", "lxwxh", "l x w x h", "size"]) {
+        Some(Column::Dimensions)
+    } else if has(&["carton", "ctn", "package", "pkg", "pallet", "box", "case"]) {
+        Some(Column::Packages)
+    } else if has(&["qty", "quantity", "pcs"]) {
+        Some(Column::Quantity)
+    } else if has(&[
+        "part",
+        "item code",
+        "item no",
+        "article",
+        "model",
+        "sku",
+        "p/n",
+        "material",
+    ]) {
+        Some(Column::Part)
+    } else {
+        None
+    }
+}
+
+struct Header {
+    columns: Vec<Option<Column>>,
+    net_factor: f64,
+    gross_factor: f64,
+}
+
+impl Header {
+    fn detect(cells: &[String]) -> Option<Header> {
+        let columns: Vec<Option<Column>> = cells.iter().map(|c| classify_header(c)).collect();
+        let has = |col: Column| columns.contains(&Some(col));
+        let usable = has(Column::Part)
+            && (has(Column::NetWeight) || has(Column::GrossWeight) || has(Column::Packages));
+        if !usable {
+            return None;
+        }
+        let factor = |col: Column| {
+            cells
+                .iter()
+                .zip(&columns)
+                .find(|(_, c)| **c == Some(col))
+                .map_or(1.0, |(caption, _)| {
+                    let caption = caption.to_lowercase();
+                    if caption.contains("lb") {
+                        KG_PER_LB
+                    } else {
+                        1.0
+                    }
+                })
+        };
+        Some(Header {
+            net_factor: factor(Column::NetWeight),
+            gross_factor: factor(Column::GrossWeight),
+            columns,
+        })
+    }
+
+    fn cell<'a>(&self, cells: &'a [String], col: Column) -> Option<&'a str> {
+        let i = self.columns.iter().position(|c| *c == Some(col))?;
+        cells.get(i).map(|s| s.trim()).filter(|s| !s.is_empty())
+    }
+
+    fn number(&self, cells: &[String], col: Column) -> Option<f64> {
+        self.cell(cells, col).and_then(parse_number)
+    }
+}
+
+/// Leading number of a cell such as "1,234.5 kg" or "12 CTNS"; commas are thousands separators.
+pub fn parse_number(raw: &str) -> Option<f64> {
+    let cleaned: String = raw.trim().chars().filter(|c| *c != ',').collect();
+    let end = cleaned
+        .char_indices()
+        .find(|(i, c)| !(c.is_ascii_digit() || *c == '.' || (*i == 0 && *c == '-')))
+        .map_or(cleaned.len(), |(i, _)| i);
+    cleaned[..end].parse::<f64>().ok().filter(|v| v.is_finite())
+}
+
+fn is_total_row(cells: &[String]) -> bool {
+    cells.iter().any(|c| {
+        let c = c.trim().to_lowercase();
+        c.starts_with("total") || c.starts_with("grand total")
+    })
+}
+
+/// Reads rows below the first recognisable header row of a grid of cell texts.
+pub fn parse_grid(grid: &[Vec<String>]) -> Result<ParsedPackingList, String> {
+    let Some((header_at, header)) = grid
+        .iter()
+        .enumerate()
+        .find_map(|(i, row)| Header::detect(row).map(|h| (i, h)))
+    else {
+        return Err(
+            "No packing-list header found (need a part number column and a weight or package column)"
+                .to_string(),
+        );
+    };
+    let mut parsed = ParsedPackingList::default();
+    for (offset, cells) in grid[header_at + 1..].iter().enumerate() {
+        let line = header_at + offset + 2;
+        if cells.iter().all(|c| c.trim().is_empty()) {
+            continue;
+        }
+        if is_total_row(cells) {
+            // Totals are only trusted when the row lines up with the header.
+            if cells.len() != header.columns.len() {
+                break;
+            }
+            parsed.declared_packages = header.number(cells, Column::Packages);
+            parsed.declared_net_weight_kg = header
+                .number(cells, Column::NetWeight)
+                .map(|w| w * header.net_factor);
+            parsed.declared_gross_weight_kg = header
+                .number(cells, Column::GrossWeight)
+                .map(|w| w * header.gross_factor);
+            break;
+        }
+        if cells.len() != header.columns.len() {
+            parsed.warnings.push(format!(
+                "Row {line} does not line up with the header columns and was skipped"
+            ));
+            continue;
+        }
+        let Some(part_number) = header.cell(cells, Column::Part) else {
+            continue;
+        };
+        let row = PackingListRow {
+            part_number: part_number.to_string(),
+            description: header.cell(cells, Column::Description).map(str::to_string),
+            quantity: header.number(cells, Column::Quantity),
+            packages: header.number(cells, Column::Packages),
+            net_weight_kg: header
+                .number(cells, Column::NetWeight)
+                .map(|w| w * header.net_factor),
+            gross_weight_kg: header
+                .number(cells, Column::GrossWeight)
+                .map(|w| w * header.gross_factor),
+            dimensions: header.cell(cells, Column::Dimensions).map(str::to_string),
+        };
+        if row.net_weight_kg.is_none() && row.gross_weight_kg.is_none() && row.packages.is_none() {
+            parsed.warnings.push(format!(
+                "Row {line} ({}) has no weight or package count",
+                row.part_number
+            ));
+        }
+        parsed.rows.push(row);
+    }
+    if parsed.rows.is_empty() {
+        return Err("The packing list has a header but no part rows".to_string());
+    }
+    Ok(parsed)
+}
+
+/// First visible worksheet of an Excel packing list.
+pub fn parse_packing_list_excel(file_bytes: &[u8]) -> Result<ParsedPackingList, String> {
+    let cursor = Cursor::new(file_bytes.to_vec());
+    let mut workbook: Sheets<_> =
+        open_workbook_auto_from_rs(cursor).map_err(|_| EXCEL_PARSE_ERR.to_string())?;
+    let name =
+        first_visible_worksheet_name(&workbook).ok_or_else(|| EXCEL_PARSE_ERR.to_string())?;
+    let range = workbook
+        .worksheet_range(&name)
+        .map_err(|_| EXCEL_PARSE_ERR.to_string())?;
+    let grid: Vec<Vec<String>> = range
+        .rows()
+        .map(|row| row.iter().map(cell_to_display).collect())
+        .collect();
+    parse_grid(&grid)
+}
+
+/// OCR or plain text: cells are separated by tabs, `|`, `;` or runs of two or more spaces.
+pub fn parse_packing_list_text(text: &str) -> Result<ParsedPackingList, String> {
+    let grid: Vec<Vec<String>> = text.lines().map(split_text_line).collect();
+    parse_grid(&grid)
+}
+
+fn split_text_line(line: &str) -> Vec<String> {
+    let mut cells = Vec::new();
+    let mut current = String::new();
+    let mut spaces = 0;
+    for ch in line.trim().chars() {
+        match ch {
+            '\t' | '|' | ';' => {
+                cells.push(std::mem::take(&mut current));
+                spaces = 0;
+            }
+            ' ' => spaces += 1,
+            _ => {
+                if spaces >= 2 {
+                    cells.push(std::mem::take(&mut current));
+                } else if spaces == 1 && !current.is_empty() {
+                    current.push(' ');
+                }
+                spaces = 0;
+                current.push(ch);
+            }
+        }
+    }
+    cells.push(current);
+    cells.into_iter().map(|c| c.trim().to_string()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rust_xlsxwriter::Workbook;
+
+    #[test]
+    fn excel_rows_are_read_below_the_header_until_the_total() {
+        let mut workbook = Workbook::new();
+        let sheet = workbook.add_worksheet();
+        sheet.write_string(0, 0, "PACKING LIST").unwrap();
+        let header = [
+            "Part No.",
+            "Description",
+            "Qty (pcs)",
+            "Cartons",
+            "Net Wt (lbs)",
+            "Gross Wt (kg)",
+            "Dimensions (cm)",
+        ];
+        for (c, h) in header.iter().enumerate() {
+            sheet.write_string(2, c as u16, *h).unwrap();
+        }
+        sheet.write_string(3, 0, "P-1001").unwrap();
+        sheet.write_string(3, 1, "Bolt").unwrap();
+        sheet.write_number(3, 2, 100.0).unwrap();
+        sheet.write_number(3, 3, 2.0).unwrap();
+        sheet.write_number(3, 4, 10.0).unwrap();
+        sheet.write_number(3, 5, 5.5).unwrap();
+        sheet.write_string(3, 6, "40x30x20").unwrap();
+        sheet.write_string(4, 0, "Total").unwrap();
+        sheet.write_number(4, 3, 2.0).unwrap();
+        sheet.write_number(4, 5, 5.5).unwrap();
+        sheet.write_string(5, 0, "Signed").unwrap();
+        let buf = workbook.save_to_buffer().unwrap();
+
+        let parsed = parse_packing_list_excel(&buf).unwrap();
+        assert_eq!(parsed.rows.len(), 1);
+        let row = &parsed.rows[0];
+        assert_eq!(row.part_number, "P-1001");
+        assert_eq!((row.quantity, row.packages), (Some(100.0), Some(2.0)));
+        assert!((row.net_weight_kg.unwrap() - 4.5359237).abs() < 1e-9);
+        assert_eq!(row.gross_weight_kg, Some(5.5));
+        assert_eq!(row.dimensions.as_deref(), Some("40x30x20"));
+        assert_eq!(parsed.declared_gross_weight_kg, Some(5.5));
+    }
+
+    #[test]
+    fn text_columns_are_split_on_wide_gaps() {
+        let text = "Invoice 123\n\
+                    Item Code   Qty   Ctns   N.W. (kg)   Gross Weight   Measurement\n\
+                    AB 12       50    1      1,020.5     1,100 kg       120 x 80 x 90\n\
+                    GRAND TOTAL        1      1,020.5     1,100\n";
+        let parsed = parse_packing_list_text(text).unwrap();
+        assert_eq!(parsed.rows.len(), 1);
+        assert_eq!(parsed.rows[0].part_number, "AB 12");
+        assert_eq!(parsed.rows[0].net_weight_kg, Some(1020.5));
+        assert_eq!(parsed.rows[0].gross_weight_kg, Some(1100.0));
+        assert_eq!(parsed.rows[0].dimensions.as_deref(), Some("120 x 80 x 90"));
+        assert!(parse_packing_list_text("no table here").is_err());
+    }
+}
@@ -0,0 +1,153 @@
+//! Minimal RFC 4180 CSV reader for master-data imports (quoted fields, doubled quotes, CRLF), plus field escaping for exports.
+
+use std::collections::HashMap;
+
+/// Splits CSV text into records. Blank lines are dropped; a leading UTF-8 BOM is ignored.
+pub fn parse_records(text: &str) -> Vec<Vec<String>> {
+    let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if in_quotes {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    field.push('"');
+                    chars.next();
+                }
+                '"' => in_quotes = false,
+                _ => field.push(c),
+            }
+            continue;
+        }
+        match c {
+            '"' => in_quotes = true,
+            ',' => record.push(std::mem::take(&mut field)),
+            '\r' => {}
+            '\n' => {
+                record.push(std::mem::take(&mut field));
+                if record.iter().any(|f| !f.trim().is_empty()) {
+                    records.push(std::mem::take(&mut record));
+                } else {
+                    record.clear();
+                }
+            }
+            _ => field.push(c),
+        }
+    }
+    record.push(field);
+    if record.iter().any(|f| !f.trim().is_empty()) {
+        records.push(record);
+    }
+    records
+}
+
+/// A data row addressed by normalized header name (lowercase, spaces/dashes → `_`).
+pub struct CsvRow<'a> {
+    /// 1-based line number of the record in the file (header is line 1).
+    pub line: usize,
+    headers: &'a HashMap<String, usize>,
+    values: &'a [String],
+}
+
+impl CsvRow<'_> {
+    /// First non-empty value among `names` (aliases), trimmed.
+    pub fn get(&self, names: &[&str]) -> Option<&str> {
+        names.iter().find_map(|n| {
+            self.headers
+                .get(*n)
+                .and_then(|&i| self.values.get(i))
+                .map(|v| v.trim())
+                .filter(|v| !v.is_empty())
+        })
+    }
+
+    /// Parses a numeric column, tolerating `%` suffixes and thousands separators.
+    pub fn get_f64(&self, names: &[&str]) -> Result<Option<f64>, String> {
+        match self.get(names) {
+            None => Ok(None),
+            Some(raw) => {
+                let cleaned = raw.trim_end_matches('%').replace(',', "");
+                cleaned
+                    .trim()
+                    .parse::<f64>()
+                    .map(Some)
+                    .map_err(|_| format!("line {}: '{}' is not a number", self.line, raw))
+            }
+        }
+    }
+}
+
+pub fn normalize_header(h: &str) -> String {
+    h.trim().to_lowercase().replace([' ', '-', '.'], "_")
+}
+
+/// Parses `text` with a header row and calls `f` for each data row.
+pub fn for_each_row<F>(text: &str, f: F) -> Result<(), String>
+where
+    F: FnMut(&CsvRow) -> Result<(), String>,
+{
+    for_each_record(&parse_records(text), f)
+}
+
+/// Same as [`for_each_row`] for records already split by another reader (first record is the header).
+pub fn for_each_record<F>(records: &[Vec<String>], mut f: F) -> Result<(), String>
+where
+    F: FnMut(&CsvRow) -> Result<(), String>,
+{
+    let Some((header, rows)) = records.split_first() else {
+        return Err("CSV is empty".to_string());
+    };
+    let headers: HashMap<String, usize> = header
+        .iter()
+        .enumerate()
+        .map(|(i, h)| (normalize_header(h), i))
+        .collect();
+    for (i, values) in rows.iter().enumerate() {
+        f(&CsvRow {
+            line: i + 2,
+            headers: &headers,
+            values,
+        })?;
+    }
+    Ok(())
+}
+
+/// Quotes a field for CSV output when it contains a delimiter, quote or line break.
+pub fn escape_field(s: &str) -> String {
+    if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quoted_fields_and_blank_lines() {
+        let r = parse_records("a,b\r\n\"x, y\",\"say \"\"hi\"\"\"\n\n1,2");
+        assert_eq!(r.len(), 3);
+        assert_eq!(r[1], vec!["x, y".to_string(), "say \"hi\"".to_string()]);
+        assert_eq!(r[2], vec!["1".to_string(), "2".to_string()]);
+    }
+
+    #[test]
+    fn rows_by_header_alias() {
+        let mut seen = Vec::new();
+        for_each_row("HSN Code,BCD Rate\n84821011,7.5%\n", |row| {
+            seen.push((
+                row.get(&["hsn", "hsn_code"]).map(str::to_string),
+                row.get_f64(&["bcd_rate"])?,
+            ));
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(seen, vec![(Some("84821011".to_string()), Some(7.5))]);
+    }
+}
@@ -1,2 +1,3 @@
 pub mod backup_keyring;
+pub mod csv;
 pub mod encryption;
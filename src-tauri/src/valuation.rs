@@ -0,0 +1,275 @@
+//! Customs valuation: builds each line's CIF assessable value from the invoice according to its incoterm.
+
+use crate::db::{FormValues, LineValuation};
+use crate::duty_engine::{round_paise, ShipmentDutyLine};
+use rusqlite::{params, Connection, OptionalExtension};
+
+/// Rule 10(2) of the Customs Valuation Rules, 2007: freight at 20% of FOB when the actual cost is not ascertainable.
+pub const NOTIONAL_FREIGHT_PERCENT: f64 = 20.0;
+/// Rule 10(2): insurance at 1.125% of FOB when the actual cost is not ascertainable.
+pub const NOTIONAL_INSURANCE_PERCENT: f64 = 1.125;
+
+pub const BASIS_VALUE: &str = "VALUE";
+pub const BASIS_WEIGHT: &str = "WEIGHT";
+pub const BASIS_QUANTITY: &str = "QUANTITY";
+
+/// Which cost elements still have to be added to the invoice price to reach CIF.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MissingElements {
+    /// Pre-carriage from the seller's premises to the port of shipment (`exw_cost`).
+    pub exw: bool,
+    pub freight: bool,
+    pub insurance: bool,
+}
+
+/// `None` for an unknown or blank incoterm: every cost entered on the form is added as-is and no
+/// notional amount is applied.
+pub fn missing_elements(incoterm: &str) -> Option<MissingElements> {
+    let all = |exw| MissingElements {
+        exw,
+        freight: true,
+        insurance: true,
+    };
+    let term: String = incoterm
+        .trim()
+        .to_uppercase()
+        .chars()
+        .filter(|c| c.is_ascii_alphabetic())
+        .collect();
+    match term.as_str() {
+        "EXW" | "FCA" => Some(all(true)),
+        "FAS" | "FOB" => Some(all(false)),
+        "CFR" | "CNF" | "CF" | "CPT" => Some(MissingElements {
+            exw: false,
+            freight: false,
+            insurance: true,
+        }),
+        // CIF / CIP invoices already carry both; D-terms deliver to India at the seller's cost.
+        "CIF" | "CIP" | "DAP" | "DPU" | "DAT" | "DDP" | "DDU" => Some(MissingElements {
+            exw: false,
+            freight: false,
+            insurance: false,
+        }),
+        _ => None,
+    }
+}
+
+fn basis_of(form: &FormValues) -> &'static str {
+    match form
+        .apportionment_basis
+        .as_deref()
+        .map(|b| b.trim().to_uppercase())
+        .as_deref()
+    {
+        Some(BASIS_WEIGHT) => BASIS_WEIGHT,
+        Some(BASIS_QUANTITY) => BASIS_QUANTITY,
+        _ => BASIS_VALUE,
+    }
+}
+
+fn basis_amount(line: &ShipmentDutyLine, basis: &str) -> f64 {
+    match basis {
+        BASIS_WEIGHT => line.weight_kg,
+        BASIS_QUANTITY => line.qty,
+        _ => line.line_total,
+    }
+}
+
+/// Valuation of `line` within the invoice `lines`. Freight and EXW charges are invoice totals spread
+/// by the form's apportionment basis (falling back to value when no line has a weight or quantity);
+/// insurance is a rate on the line's FOB. Zero freight or insurance on the form means "not
+/// ascertained" and triggers the notional rule when the incoterm needs that element.
+pub fn value_line(
+    line: &ShipmentDutyLine,
+    lines: &[ShipmentDutyLine],
+    form: &FormValues,
+) -> LineValuation {
+    let incoterm = form
+        .incoterm
+        .as_deref()
+        .map(str::trim)
+        .filter(|t| !t.is_empty());
+    let missing = incoterm.and_then(missing_elements);
+
+    let mut basis = basis_of(form);
+    if basis != BASIS_VALUE && lines.iter().map(|l| basis_amount(l, basis)).sum::<f64>() <= 0.0 {
+        basis = BASIS_VALUE;
+    }
+    let basis_total: f64 = lines.iter().map(|l| basis_amount(l, basis)).sum();
+    let share = if basis_total > 0.0 {
+        basis_amount(line, basis) / basis_total
+    } else {
+        0.0
+    };
+    let invoice_total: f64 = lines.iter().map(|l| l.line_total).sum();
+
+    let add_exw = !matches!(missing, Some(m) if !m.exw);
+    let exw_charges = if add_exw { form.exw_cost * share } else { 0.0 };
+    let fob_value = line.line_total + exw_charges;
+
+    let (freight, freight_notional) = match missing {
+        Some(m) if !m.freight => (0.0, false),
+        Some(_) if form.freight_cost <= 0.0 => {
+            let invoice_fob = invoice_total + if add_exw { form.exw_cost } else { 0.0 };
+            (invoice_fob * NOTIONAL_FREIGHT_PERCENT / 100.0 * share, true)
+        }
+        _ => (form.freight_cost * share, false),
+    };
+    let (insurance_rate, insurance_notional) = match missing {
+        Some(m) if !m.insurance => (0.0, false),
+        Some(_) if form.insurance_rate <= 0.0 => (NOTIONAL_INSURANCE_PERCENT, true),
+        _ => (form.insurance_rate, false),
+    };
+    let insurance = fob_value * insurance_rate / 100.0;
+    let cif_value = fob_value + freight + insurance;
+
+    LineValuation {
+        incoterm: incoterm.map(str::to_string),
+        apportionment_basis: basis.to_string(),
+        share,
+        invoice_value: line.line_total,
+        exw_charges,
+        fob_value,
+        freight,
+        freight_notional,
+        insurance_rate,
+        insurance,
+        insurance_notional,
+        cif_value,
+        exchange_rate: form.exchange_rate,
+        assessable_value: round_paise(cif_value * form.exchange_rate),
+    }
+}
+
+impl LineValuation {
+    /// The valuation of `share` of the line (a warehoused or partial clearance): amounts scale,
+    /// rates and the apportionment share do not.
+    pub fn portion(self, share: f64) -> Self {
+        if (share - 1.0).abs() < f64::EPSILON {
+            return self;
+        }
+        LineValuation {
+            invoice_value: self.invoice_value * share,
+            exw_charges: self.exw_charges * share,
+            fob_value: self.fob_value * share,
+            freight: self.freight * share,
+            insurance: self.insurance * share,
+            cif_value: self.cif_value * share,
+            assessable_value: round_paise(self.assessable_value * share),
+            ..self
+        }
+    }
+}
+
+/// Sets `form.incoterm` from the shipment, so the stored form reproduces the valuation on its own.
+pub fn apply_shipment_incoterm(
+    conn: &Connection,
+    shipment_id: &str,
+    form: &mut FormValues,
+) -> Result<(), String> {
+    let incoterm: Option<String> = conn
+        .query_row(
+            "SELECT incoterm FROM shipments WHERE id = ?1",
+            params![shipment_id],
+            |r| r.get(0),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?
+        .flatten();
+    if let Some(term) = incoterm.filter(|t| !t.trim().is_empty()) {
+        form.incoterm = Some(term.trim().to_uppercase());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn form(
+        incoterm: Option<&str>,
+        basis: Option<&str>,
+        freight: f64,
+        insurance: f64,
+    ) -> FormValues {
+        FormValues {
+            supplier_name: "ACME".into(),
+            shipment_id: "SHP-1".into(),
+            exchange_rate: 80.0,
+            freight_cost: freight,
+            exw_cost: 100.0,
+            insurance_rate: insurance,
+            interest: None,
+            incoterm: incoterm.map(str::to_string),
+            apportionment_basis: basis.map(str::to_string),
+        }
+    }
+
+    fn line(part: &str, total: f64, qty: f64, weight: f64) -> ShipmentDutyLine {
+        ShipmentDutyLine {
+            part_no: part.into(),
+            description: part.into(),
+            qty,
+            unit_price: total / qty,
+            hs_code: "84821011".into(),
+            country_of_origin: None,
+            line_total: total,
+            weight_kg: weight,
+            actual_bcd_rate: 0.0,
+            actual_sws_rate: 0.0,
+            actual_igst_rate: 0.0,
+            actual_aidc_rate: 0.0,
+            actual_safeguard_rate: 0.0,
+            actual_cess_rate: 0.0,
+            sws_exempt: false,
+            add_rate: 0.0,
+            add_per_unit: 0.0,
+        }
+    }
+
+    #[test]
+    fn incoterm_decides_which_elements_are_added() {
+        let lines = vec![line("A", 1000.0, 10.0, 30.0), line("B", 3000.0, 10.0, 10.0)];
+
+        // FOB with no freight or insurance on record: 20% freight on 4000 FOB, 1.125% insurance.
+        let fob = value_line(&lines[0], &lines, &form(Some("FOB"), None, 0.0, 0.0));
+        assert_eq!(fob.exw_charges, 0.0);
+        assert!(fob.freight_notional && fob.insurance_notional);
+        assert_eq!(fob.freight, 200.0);
+        assert_eq!(fob.insurance, 11.25);
+        assert_eq!(fob.assessable_value, 96_900.0);
+
+        // CFR: actual freight on the form is already in the price; only insurance is added.
+        let cfr = value_line(&lines[0], &lines, &form(Some("CFR"), None, 400.0, 1.0));
+        assert_eq!((cfr.freight, cfr.insurance), (0.0, 10.0));
+        assert!(!cfr.insurance_notional);
+
+        // CIF and DDP add nothing.
+        for term in ["CIF", "DDP"] {
+            let v = value_line(&lines[0], &lines, &form(Some(term), None, 400.0, 1.0));
+            assert_eq!(v.cif_value, 1000.0);
+        }
+
+        // EXW by weight: line A carries 30 of 40 kg of the 400 freight and 100 EXW charges.
+        let exw = value_line(
+            &lines[0],
+            &lines,
+            &form(Some("EXW"), Some("WEIGHT"), 400.0, 1.0),
+        );
+        assert_eq!(exw.apportionment_basis, BASIS_WEIGHT);
+        assert_eq!((exw.exw_charges, exw.freight), (75.0, 300.0));
+        assert_eq!(exw.insurance, 10.75);
+
+        // By quantity the lines split evenly.
+        let qty = value_line(
+            &lines[1],
+            &lines,
+            &form(Some("FOB"), Some("QUANTITY"), 400.0, 1.0),
+        );
+        assert_eq!(qty.freight, 200.0);
+
+        // Unknown incoterm keeps the form's figures as entered.
+        let legacy = value_line(&lines[0], &lines, &form(None, None, 0.0, 0.0));
+        assert_eq!(legacy.cif_value, 1025.0);
+    }
+}
@@ -1018,6 +1018,7 @@ export function BoeSummaryClient({
               <SelectContent>
                 {[
                   'All',
+                  'Draft',
                   'Awaiting BOE Data',
                   'Discrepancy Found',
                   'Reconciled',
@@ -1092,6 +1093,7 @@ export function BoeSummaryClient({
                       </SelectTrigger>
                       <SelectContent>
                         {[
+                          'Draft',
                           'Awaiting BOE Data',
                           'Discrepancy Found',
                           'Reconciled',
@@ -10,6 +10,7 @@ const statusToVariant: Record<
     label: string;
   }
 > = {
+  Draft: { variant: 'neutral', label: 'Draft' },
   'Awaiting BOE Data': { variant: 'info', label: 'Awaiting BOE Data' },
   'Discrepancy Found': { variant: 'destructive', label: 'Discrepancy Found' },
   Reconciled: { variant: 'success', label: 'Reconciled' },
@@ -121,7 +121,7 @@ const ShipmentSelector: React.FC<ShipmentSelectorProps> = ({
 
   const freezeShipment = async (shipmentId: string) => {
     try {
-      await invoke('freeze_shipment', { shipmentId, frozen: true });
+      await invoke('freeze_shipment', { shipmentId });
       await refresh();
       if (selectedShipment?.id === shipmentId) setSelectedShipment(null);
     } catch (error) {
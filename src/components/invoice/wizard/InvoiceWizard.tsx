@@ -1227,10 +1227,7 @@ export function InvoiceWizard({
                     if (!sh) return toast.error('Select a shipment first');
                     setFinalizing(true);
                     try {
-                      await invoke('freeze_shipment', {
-                        shipmentId: sh.id,
-                        frozen: true,
-                      });
+                      await invoke('freeze_shipment', { shipmentId: sh.id });
                       setShipmentFrozen(true);
                       toast.success('Shipment frozen');
                     } catch (e) {
@@ -317,6 +317,14 @@ export const getShipmentColumns = (
             label: 'Customs Clearance',
             color: 'bg-purple-100 text-purple-800 border-purple-200',
           },
+          warehoused: {
+            label: 'In Bonded Warehouse',
+            color: 'bg-indigo-100 text-indigo-800 border-indigo-200',
+          },
+          'partially-cleared': {
+            label: 'Partially Cleared',
+            color: 'bg-indigo-100 text-indigo-800 border-indigo-200',
+          },
           'ready-dly': {
             label: 'Ready for Delivery',
             color: 'bg-orange-100 text-orange-800 border-orange-200',
@@ -651,12 +651,8 @@ export function ProfessionalShipmentForm({
                       type="checkbox"
                       id="isFrozen"
                       checked={formData.isFrozen || false}
-                      onChange={e =>
-                        setFormData(prev => ({
-                          ...prev,
-                          isFrozen: e.target.checked,
-                        }))
-                      }
+                      disabled
+                      readOnly
                       className="text-primary focus:ring-primary h-4 w-4 rounded border-gray-300"
                     />
                     <Label htmlFor="isFrozen" className="text-sm font-medium">
@@ -665,7 +661,8 @@ export function ProfessionalShipmentForm({
                   </div>
                   <p className="text-muted-foreground text-xs">
                     Frozen shipments cannot be modified and are locked for
-                    processing
+                    processing. Freeze from the invoice wizard; reopen with a
+                    reason from Frozen Shipments.
                   </p>
 
                   {formData.isFrozen && (
@@ -400,10 +400,16 @@ export async function invoke<T = unknown>(
       return undefined as T;
     case 'freeze_shipment': {
       const shipmentId = String(args?.shipmentId ?? '');
-      const frozen = Boolean(args?.frozen);
       const idx = stubShipments.findIndex(s => s.id === shipmentId);
       if (idx >= 0)
-        stubShipments[idx] = { ...stubShipments[idx], isFrozen: frozen };
+        stubShipments[idx] = { ...stubShipments[idx], isFrozen: true };
+      return undefined as T;
+    }
+    case 'reopen_shipment': {
+      const shipmentId = String(args?.shipmentId ?? '');
+      const idx = stubShipments.findIndex(s => s.id === shipmentId);
+      if (idx >= 0)
+        stubShipments[idx] = { ...stubShipments[idx], isFrozen: false };
       return undefined as T;
     }
     case 'get_invoices':
@@ -11,6 +11,7 @@ import {
   CardHeader,
   CardTitle,
 } from '@/components/ui/card';
+import { Input } from '@/components/ui/input';
 import {
   Table,
   TableBody,
@@ -24,6 +25,7 @@ import type { Shipment } from '@/types/shipment';
 const FrozenShipmentsPage = () => {
   const [shipments, setShipments] = useState<Shipment[]>([]);
   const [loading, setLoading] = useState(true);
+  const [reasons, setReasons] = useState<Record<string, string>>({});
 
   const refresh = async () => {
     try {
@@ -42,14 +44,20 @@ const FrozenShipmentsPage = () => {
     refresh();
   }, []);
 
-  const handleUnfreeze = async (id: string) => {
+  const handleReopen = async (id: string) => {
+    const reason = (reasons[id] ?? '').trim();
+    if (!reason) {
+      toast.error('Enter a reason to reopen the shipment');
+      return;
+    }
     try {
-      await invoke('freeze_shipment', { shipmentId: id, frozen: false });
-      toast.success('Shipment unfrozen');
+      await invoke('reopen_shipment', { shipmentId: id, reason });
+      toast.success('Shipment reopened');
+      setReasons(prev => ({ ...prev, [id]: '' }));
       await refresh();
     } catch (e) {
       console.error(e);
-      toast.error('Failed to unfreeze');
+      toast.error(`Failed to reopen: ${e}`);
     }
   };
 
@@ -73,6 +81,7 @@ const FrozenShipmentsPage = () => {
                 <TableRow>
                   <TableHead>Invoice #</TableHead>
                   <TableHead>Status</TableHead>
+                  <TableHead>Reopen reason</TableHead>
                   <TableHead>Actions</TableHead>
                 </TableRow>
               </TableHeader>
@@ -83,15 +92,31 @@ const FrozenShipmentsPage = () => {
                       <TableCell>{s.invoiceNumber}</TableCell>
                       <TableCell>{s.status}</TableCell>
                       <TableCell>
-                        <Button size="sm" onClick={() => handleUnfreeze(s.id)}>
-                          Unfreeze
+                        <Input
+                          value={reasons[s.id] ?? ''}
+                          placeholder="e.g. Late CHA invoice"
+                          onChange={e =>
+                            setReasons(prev => ({
+                              ...prev,
+                              [s.id]: e.target.value,
+                            }))
+                          }
+                        />
+                      </TableCell>
+                      <TableCell>
+                        <Button
+                          size="sm"
+                          disabled={!(reasons[s.id] ?? '').trim()}
+                          onClick={() => handleReopen(s.id)}
+                        >
+                          Reopen
                         </Button>
                       </TableCell>
                     </TableRow>
                   ))
                 ) : (
                   <TableRow>
-                    <TableCell colSpan={3} className="text-center">
+                    <TableCell colSpan={4} className="text-center">
                       No frozen shipments
                     </TableCell>
                   </TableRow>
@@ -311,7 +311,7 @@ export default function ReportsPage() {
             <CardTitle>Totals</CardTitle>
           </CardHeader>
           <CardContent>
-            <div className="grid grid-cols-2 gap-4 md:grid-cols-6">
+            <div className="grid grid-cols-2 gap-4 md:grid-cols-7">
               <div>
                 <Label className="text-sm font-medium">Total Qty</Label>
                 <p className="text-2xl font-bold">
@@ -344,6 +344,12 @@ export default function ReportsPage() {
                   {totals.igst_amount?.toFixed(2) || '0.00'}
                 </p>
               </div>
+              <div>
+                <Label className="text-sm font-medium">Duty Credits Used</Label>
+                <p className="text-2xl font-bold">
+                  {totals.duty_credit_amount?.toFixed(2) || '0.00'}
+                </p>
+              </div>
               <div>
                 <Label className="text-sm font-medium">
                   Total Expenses (Basic)
@@ -95,7 +95,7 @@ import { useSettings } from '@/lib/use-settings';
 import { useUser } from '@/lib/user-context';
 import { useResponsiveContext } from '@/providers/ResponsiveProvider';
 import type { Option } from '@/types/options';
-import type { Shipment } from '@/types/shipment';
+import type { Shipment, ShipmentStatusTransitionError } from '@/types/shipment';
 import type { Supplier } from '@/types/supplier';
 
 /** URL path for shipment view or edit (bookmarkable). */
@@ -331,7 +331,8 @@ const ShipmentPage = () => {
         fetchShipments();
       } catch (error) {
         console.error('Failed to mark shipment as delivered:', error);
-        notifications.shipment.error('mark as delivered', String(error));
+        const message = (error as Partial<ShipmentStatusTransitionError>)?.message;
+        notifications.shipment.error('mark as delivered', message ?? String(error));
       }
     },
     [fetchShipments, notifications.shipment]
@@ -892,6 +893,8 @@ const ShipmentPage = () => {
       case 'ready-dly':
         return 'warning';
       case 'customs-clearance':
+      case 'warehoused':
+      case 'partially-cleared':
         return 'secondary';
       case 'docs-rcvd':
       case 'docu-received':
@@ -912,6 +915,10 @@ const ShipmentPage = () => {
         return 'Ready for Delivery';
       case 'customs-clearance':
         return 'Customs Clearance';
+      case 'warehoused':
+        return 'In Bonded Warehouse';
+      case 'partially-cleared':
+        return 'Partially Cleared';
       case 'docs-rcvd':
       case 'docu-received':
         return 'Document Received';
@@ -22,6 +22,11 @@ export interface BoeDetails {
   challanNumber?: string;
   refId?: string;
   transactionId?: string;
+  /** Vessel / aircraft arrival; the BE is late if filed after the day before. */
+  arrivalDate?: string;
+  /** Computed from the configured charge slabs. */
+  latePaymentInterest?: number;
+  lateFilingCharge?: number;
 }
 
 export interface InvoiceItem {
@@ -58,6 +63,8 @@ export interface BoeItemInput {
   boeBcdRate: number;
   boeSwsRate: number;
   boeIgstRate: number;
+  /** Units warehoused or cleared; omitted means the full invoice quantity. */
+  quantity?: number;
 }
 
 export interface CalculatedDutyItem {
@@ -67,6 +74,25 @@ export interface CalculatedDutyItem {
   bcdValue: number;
   swsValue: number;
   igstValue: number;
+  valuation?: LineValuation | null;
+}
+
+/** How a line's assessable value was built; amounts in invoice currency until `assessableValue`. */
+export interface LineValuation {
+  incoterm?: string | null;
+  apportionmentBasis: string;
+  share: number;
+  invoiceValue: number;
+  exwCharges: number;
+  fobValue: number;
+  freight: number;
+  freightNotional: boolean;
+  insuranceRate: number;
+  insurance: number;
+  insuranceNotional: boolean;
+  cifValue: number;
+  exchangeRate: number;
+  assessableValue: number;
 }
 
 export interface CalculationResult {
@@ -76,10 +102,33 @@ export interface CalculationResult {
   igstTotal: number;
   interest: number;
   customsDutyTotal: number;
+  statutoryCharges?: StatutoryCharges | null;
+}
+
+export interface StatutoryChargeLine {
+  chargeType: 'LATE_PAYMENT_INTEREST' | 'LATE_FILING';
+  fromDay: number;
+  toDay?: number | null;
+  days: number;
+  rate: number;
+  amount: number;
+}
+
+export interface StatutoryCharges {
+  paymentDueDate: string;
+  interestUntil: string;
+  dutyPaid: boolean;
+  daysPaidLate: number;
+  latePaymentInterest: number;
+  filingDeadline?: string | null;
+  daysFiledLate: number;
+  lateFilingCharge: number;
+  lines: StatutoryChargeLine[];
 }
 
 // --- Phase 3: Operational Hub additions ---
 export type BoeStatus =
+  | 'Draft'
   | 'Awaiting BOE Data'
   | 'Discrepancy Found'
   | 'Reconciled'
@@ -111,8 +160,16 @@ export interface SavedBoe {
     exwCost: number;
     insuranceRate: number;
     interest?: number;
+    /** Filled from the shipment by the backend; drives which cost elements are added. */
+    incoterm?: string | null;
+    apportionmentBasis?: 'VALUE' | 'WEIGHT' | 'QUANTITY' | null;
   };
   itemInputs: BoeItemInput[];
   calculationResult: CalculationResult;
   attachments?: Attachment[]; // NEW: linked documents
+  boeType?: BoeType;
+  /** Into-bond BOE an ex-bond BOE clears goods from. */
+  intoBondBoeId?: string;
 }
+
+export type BoeType = 'HOME_CONSUMPTION' | 'INTO_BOND' | 'EX_BOND';
@@ -11,4 +11,9 @@ export interface BoeDetails {
   challanNumber?: string;
   refId?: string;
   transactionId?: string;
+  /** Vessel / aircraft arrival; the BE is late if filed after the day before. */
+  arrivalDate?: string;
+  /** Computed from the configured charge slabs. */
+  latePaymentInterest?: number;
+  lateFilingCharge?: number;
 }
@@ -0,0 +1,49 @@
+export type ScheduleField = 'ETD' | 'ETA';
+export type EtaPredictionBasis = 'SHIPPING_LINE' | 'SUPPLIER' | 'PORT' | 'MODE' | 'ALL' | 'NONE';
+export type OverdueEtaBasis = 'ETA' | 'PREDICTED';
+
+export interface ScheduleChange {
+  id: number;
+  shipmentId: string;
+  field: ScheduleField;
+  oldValue?: string | null;
+  newValue?: string | null;
+  /** New minus old in days; positive is a delay. */
+  slipDays?: number | null;
+  source: string;
+  reason?: string | null;
+  changedBy?: string | null;
+  changedAt: string;
+}
+
+export interface SlippageStat {
+  key?: string | null;
+  label: string;
+  shipments: number;
+  arrived: number;
+  etaRevisions: number;
+  /** Original ETA to arrival, or to the current ETA while not yet arrived. */
+  avgSlipDays: number;
+  avgArrivalSlipDays?: number | null;
+  maxSlipDays: number;
+  onTimePercent?: number | null;
+}
+
+export interface EtaSlippageAnalytics {
+  overall: SlippageStat;
+  bySupplier: SlippageStat[];
+  byShippingLine: SlippageStat[];
+  byPort: SlippageStat[];
+  byMode: SlippageStat[];
+}
+
+export interface EtaPrediction {
+  shipmentId: string;
+  originalEta?: string | null;
+  currentEta: string;
+  predictedEta: string;
+  expectedSlipDays?: number | null;
+  basis: EtaPredictionBasis;
+  basisKey?: string | null;
+  sampleSize: number;
+}
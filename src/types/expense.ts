@@ -15,8 +15,17 @@ export interface ExpenseType {
   defaultSgstRate: number; // Now in basis points (900 = 9.00%)
   defaultIgstRate: number; // Now in basis points (900 = 9.00%)
   isActive: boolean;
+  allocationBasis?: AllocationBasis;
 }
 
+// How an expense type is spread over a shipment's invoice lines
+export type AllocationBasis =
+  | 'ASSESSABLE_VALUE'
+  | 'GROSS_WEIGHT'
+  | 'NET_WEIGHT'
+  | 'QUANTITY'
+  | 'EQUAL';
+
 // NEW: Expense Invoice interface
 export interface ExpenseInvoice {
   id: string;
@@ -325,3 +334,32 @@ export type ExpenseReportType =
  * Export format options
  */
 export type ExpenseExportFormat = 'csv' | 'excel' | 'pdf';
+
+/**
+ * Landed cost of one invoice line (assessable value + non-creditable duty + allocated expenses)
+ */
+export interface ItemLandedCost {
+  invoiceLineItemId: string;
+  shipmentId: string;
+  itemId: string;
+  partNo: string;
+  quantity: number;
+  assessableValue: number;
+  dutyCost: number;
+  expenseCost: number;
+  landedCost: number;
+  landedCostPerUnit: number | null;
+  /** Good units (received less damaged) on posted GRNs; null until received. */
+  receivedQuantity?: number | null;
+  landedCostPerReceivedUnit?: number | null;
+  computedAt?: string | null;
+}
+
+export interface LandedCostAllocation {
+  expenseId: string;
+  expenseType: string;
+  invoiceLineItemId: string;
+  partNo: string;
+  basis: AllocationBasis;
+  amount: number;
+}
@@ -0,0 +1,89 @@
+export type ForwardContractStatus = 'ACTIVE' | 'CANCELLED';
+export type ForwardContractPosition =
+  | 'OPEN'
+  | 'PARTIALLY_UTILISED'
+  | 'UTILISED'
+  | 'EXPIRED'
+  | 'CANCELLED';
+
+export interface ForwardContractAllocation {
+  paymentId: string;
+  paymentDate: string;
+  shipmentId?: string | null;
+  invoiceNumber?: string | null;
+  amount: number;
+  allocatedBy?: string | null;
+  allocatedAt: string;
+}
+
+export interface ForwardContract {
+  id: string;
+  contractNumber: string;
+  bankName: string;
+  currency: string;
+  amount: number;
+  /** INR per unit of currency. */
+  forwardRate: number;
+  bookingDate: string;
+  maturityFrom: string;
+  maturityTo: string;
+  status: ForwardContractStatus;
+  utilisedAmount: number;
+  openAmount: number;
+  position: ForwardContractPosition;
+  notes?: string | null;
+  createdBy?: string | null;
+  createdAt: string;
+  updatedAt: string;
+  allocations: ForwardContractAllocation[];
+}
+
+export interface SaveForwardContractPayload {
+  id?: string | null;
+  contractNumber: string;
+  bankName: string;
+  currency: string;
+  amount: number;
+  forwardRate: number;
+  bookingDate: string;
+  maturityFrom: string;
+  maturityTo: string;
+  notes?: string | null;
+  userId?: string | null;
+}
+
+export interface ForexMarketRate {
+  currency: string;
+  rateDate: string;
+  /** 'SPOT' or the delivery month (YYYY-MM) of a forward rate. */
+  maturityMonth?: string | null;
+  rate: number;
+  source?: string | null;
+}
+
+export interface HedgeExposureRow {
+  currency: string;
+  /** YYYY-MM */
+  month: string;
+  shipments: number;
+  exposure: number;
+  covered: number;
+  uncovered: number;
+  coverPercent?: number | null;
+}
+
+export interface ForwardContractMtm {
+  contractId: string;
+  contractNumber: string;
+  bankName: string;
+  currency: string;
+  maturityTo: string;
+  position: ForwardContractPosition;
+  openAmount: number;
+  forwardRate: number;
+  marketRate?: number | null;
+  marketRateDate?: string | null;
+  marketRateBasis?: string | null;
+  /** INR; positive when the cover is in the money. */
+  mtmGainLoss?: number | null;
+}
@@ -0,0 +1,86 @@
+export type GoodsReceiptStatus = 'DRAFT' | 'POSTED' | 'CANCELLED';
+
+export interface GoodsReceiptLine {
+  id: string;
+  invoiceLineItemId: string;
+  itemId: string;
+  partNumber?: string | null;
+  expectedQuantity: number;
+  /** Units counted in, damaged ones included. */
+  receivedQuantity: number;
+  damagedQuantity: number;
+  shortQuantity: number;
+  excessQuantity: number;
+  remarks?: string | null;
+}
+
+export interface GoodsReceiptPhoto {
+  id: string;
+  grnId: string;
+  grnLineId?: string | null;
+  fileName: string;
+  filePath: string;
+  caption?: string | null;
+  uploadedBy?: string | null;
+  uploadedAt: string;
+}
+
+export interface GoodsReceipt {
+  id: string;
+  grnNumber: string;
+  shipmentId: string;
+  receivedDate: string;
+  warehouse?: string | null;
+  status: GoodsReceiptStatus;
+  notes?: string | null;
+  receivedBy?: string | null;
+  postedAt?: string | null;
+  createdAt: string;
+  updatedAt: string;
+  lines: GoodsReceiptLine[];
+  photos: GoodsReceiptPhoto[];
+}
+
+export interface SaveGoodsReceiptLine {
+  invoiceLineItemId: string;
+  receivedQuantity: number;
+  damagedQuantity?: number;
+  remarks?: string | null;
+}
+
+export interface SaveGoodsReceiptPayload {
+  id?: string | null;
+  grnNumber?: string | null;
+  shipmentId: string;
+  receivedDate: string;
+  warehouse?: string | null;
+  notes?: string | null;
+  userId?: string | null;
+  lines: SaveGoodsReceiptLine[];
+}
+
+export interface ReceiptVarianceLine {
+  invoiceLineItemId: string;
+  itemId: string;
+  partNumber?: string | null;
+  invoicedQuantity: number;
+  receivedQuantity: number;
+  damagedQuantity: number;
+  shortQuantity: number;
+  excessQuantity: number;
+  unitPrice: number;
+  invoiceValueAtRisk: number;
+  landedCostPerUnit?: number | null;
+  landedValueAtRisk?: number | null;
+}
+
+export interface ReceiptClaimBasis {
+  shipmentId: string;
+  invoiceNumber: string;
+  invoiceCurrency: string;
+  grnNumbers: string[];
+  lines: ReceiptVarianceLine[];
+  photos: GoodsReceiptPhoto[];
+  invoiceValueAtRisk: number;
+  landedValueAtRisk: number;
+}
@@ -9,6 +9,8 @@ export interface InvoiceLineItem {
   dutyPercent?: number;
   swsPercent?: number;
   igstPercent?: number;
+  /** Purchase order line this invoice line ships against. */
+  poLineId?: string | null;
 }
 
 export interface Invoice {
@@ -26,3 +26,48 @@ export interface Option {
   value: string;
   label: string;
 }
+
+export type HsnClassificationSource = 'AI' | 'TARIFF' | 'HISTORY' | 'MANUAL';
+
+export interface SimilarClassifiedItem {
+  itemId: string;
+  partNumber: string;
+  description: string;
+  hsnCode: string;
+  similarity: number;
+}
+
+// Ranked HSN candidate from suggest_hsn_codes
+export interface HsnCandidate {
+  hsnCode: string;
+  description?: string | null;
+  reasoning?: string | null;
+  aiConfidence?: number | null;
+  aiRank?: number | null;
+  inTariffMaster: boolean;
+  tariffDescription?: string | null;
+  bcdRate?: number | null;
+  igstRate?: number | null;
+  similarItems: SimilarClassifiedItem[];
+  score: number;
+  sources: HsnClassificationSource[];
+}
+
+export interface HsnSuggestionResponse {
+  provider: string;
+  candidates: HsnCandidate[];
+  aiError?: string | null;
+}
+
+export interface HsnClassification {
+  id: number;
+  itemId: string;
+  hsnCode: string;
+  previousHsnCode?: string | null;
+  rationale: string;
+  source: HsnClassificationSource;
+  provider?: string | null;
+  candidates?: HsnCandidate[] | null;
+  classifiedBy?: string | null;
+  classifiedAt: string;
+}
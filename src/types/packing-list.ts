@@ -0,0 +1,41 @@
+export type PackingListSourceFormat = 'EXCEL' | 'TEXT' | 'OCR';
+export type PackingListLineFlag =
+  | 'UNMATCHED'
+  | 'QUANTITY_MISMATCH'
+  | 'NET_WEIGHT_VARIANCE'
+  | 'GROSS_WEIGHT_VARIANCE'
+  | 'GROSS_BELOW_NET'
+  | 'NO_MASTER_WEIGHT';
+
+export interface PackingListLine {
+  partNumber: string;
+  description?: string | null;
+  invoiceLineItemId?: string | null;
+  itemId?: string | null;
+  invoicedQuantity?: number | null;
+  quantity?: number | null;
+  packages?: number | null;
+  netWeightKg?: number | null;
+  grossWeightKg?: number | null;
+  dimensions?: string | null;
+  /** Item master weight per unit × quantity. */
+  expectedNetWeightKg?: number | null;
+  expectedGrossWeightKg?: number | null;
+  flags: PackingListLineFlag[];
+}
+
+export interface PackingList {
+  id: string;
+  shipmentId: string;
+  fileName: string;
+  sourceFormat: PackingListSourceFormat;
+  totalPackages?: number | null;
+  totalNetWeightKg?: number | null;
+  totalGrossWeightKg?: number | null;
+  previousGrossWeightKg?: number | null;
+  itemWeightsFilled: number;
+  warnings: string[];
+  lines: PackingListLine[];
+  createdBy?: string | null;
+  createdAt: string;
+}
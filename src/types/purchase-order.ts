@@ -0,0 +1,103 @@
+export type PurchaseOrderStatus =
+  | 'DRAFT'
+  | 'OPEN'
+  | 'PARTIALLY_SHIPPED'
+  | 'SHIPPED'
+  | 'CLOSED'
+  | 'CANCELLED';
+
+export interface PoScheduleEntry {
+  dueDate: string;
+  quantity: number;
+}
+
+export interface PurchaseOrderLine {
+  id: string;
+  lineNo: number;
+  itemId: string;
+  partNumber?: string | null;
+  quantity: number;
+  unitPrice: number;
+  currency: string;
+  schedule: PoScheduleEntry[];
+  invoicedQuantity: number;
+  openQuantity: number;
+}
+
+export interface PurchaseOrder {
+  id: string;
+  poNumber: string;
+  supplierId: string;
+  supplierName?: string | null;
+  poDate: string;
+  currency: string;
+  incoterm?: string | null;
+  status: PurchaseOrderStatus;
+  notes?: string | null;
+  createdBy?: string | null;
+  createdAt: string;
+  updatedAt: string;
+  lines: PurchaseOrderLine[];
+}
+
+export interface SavePurchaseOrderLine {
+  id?: string | null;
+  itemId: string;
+  quantity: number;
+  unitPrice: number;
+  currency?: string | null;
+  schedule?: PoScheduleEntry[];
+}
+
+export interface SavePurchaseOrderPayload {
+  id?: string | null;
+  poNumber: string;
+  supplierId: string;
+  poDate: string;
+  currency: string;
+  incoterm?: string | null;
+  notes?: string | null;
+  userId?: string | null;
+  lines: SavePurchaseOrderLine[];
+}
+
+export interface PoTolerances {
+  pricePercent: number;
+  quantityPercent: number;
+}
+
+export interface PoVariance {
+  shipmentId: string;
+  invoiceLineId: string;
+  poId: string;
+  poNumber: string;
+  poLineId: string;
+  itemId: string;
+  kind: 'PRICE_EXCEEDS' | 'QUANTITY_EXCEEDS' | 'CURRENCY_DIFFERS';
+  poValue: number;
+  invoiceValue: number;
+  variancePercent?: number | null;
+  poCurrency: string;
+  invoiceCurrency: string;
+}
+
+export interface UnshippedPoLine {
+  poId: string;
+  poNumber: string;
+  poDate: string;
+  status: PurchaseOrderStatus;
+  supplierId: string;
+  supplierName?: string | null;
+  poLineId: string;
+  lineNo: number;
+  itemId: string;
+  partNumber?: string | null;
+  orderedQuantity: number;
+  invoicedQuantity: number;
+  openQuantity: number;
+  unitPrice: number;
+  currency: string;
+  openValue: number;
+  nextDueDate?: string | null;
+  overdueQuantity: number;
+}
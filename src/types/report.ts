@@ -21,6 +21,7 @@ export interface ReportTotals {
   bcd_amount: number;
   sws_amount: number;
   igst_amount: number;
+  duty_credit_amount: number;
   expenses_total: number;
 }
 
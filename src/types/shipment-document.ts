@@ -0,0 +1,64 @@
+export type ShipmentDocumentStatus = 'PENDING' | 'ATTACHED' | 'NOT_APPLICABLE' | 'MISSING';
+export type ShipmentDocumentSource = 'TEMPLATE' | 'MANUAL';
+
+export interface DocumentType {
+  code: string;
+  label: string;
+  sortOrder: number;
+  isActive: boolean;
+}
+
+export interface ChecklistTemplateItem {
+  documentType: string;
+  isMandatory: boolean;
+  sortOrder?: number;
+}
+
+export interface ChecklistTemplate {
+  id: string;
+  name: string;
+  /** Filters; null matches any shipment. */
+  shipmentMode?: string | null;
+  incoterm?: string | null;
+  goodsCategory?: string | null;
+  isActive: boolean;
+  items: ChecklistTemplateItem[];
+  createdAt: string;
+  updatedAt: string;
+}
+
+export interface SaveChecklistTemplatePayload {
+  id?: string | null;
+  name: string;
+  shipmentMode?: string | null;
+  incoterm?: string | null;
+  goodsCategory?: string | null;
+  isActive?: boolean;
+  items: ChecklistTemplateItem[];
+}
+
+export interface ShipmentDocument {
+  id: string;
+  shipmentId: string;
+  documentType: string;
+  label: string;
+  isMandatory: boolean;
+  source: ShipmentDocumentSource;
+  status: ShipmentDocumentStatus;
+  fileName?: string | null;
+  filePath?: string | null;
+  referenceNumber?: string | null;
+  remarks?: string | null;
+  updatedBy?: string | null;
+  updatedAt: string;
+}
+
+export interface ShipmentDocumentChecklist {
+  shipmentId: string;
+  documents: ShipmentDocument[];
+  total: number;
+  completed: number;
+  completenessPercent: number;
+  /** Document type codes of mandatory slots still pending or missing. */
+  mandatoryMissing: string[];
+}
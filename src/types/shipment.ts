@@ -1,3 +1,5 @@
+import type { ItemLandedCost, LandedCostAllocation } from './expense';
+
 export interface Shipment {
   id: string; // "SHP-0001"
   supplierId: string;
@@ -20,3 +22,237 @@ export interface Shipment {
   dateOfDelivery?: string;
   isFrozen: boolean;
 }
+
+export interface SnapshotTotals {
+  assessableValue: number;
+  dutyCost: number;
+  expenseCost: number;
+  landedCost: number;
+}
+
+// Immutable costing record written when a shipment is frozen
+export interface CostingSnapshot {
+  id: string;
+  shipmentId: string;
+  version: number;
+  contentHash: string;
+  totalLandedCost: number;
+  frozenBy?: string | null;
+  frozenAt: string;
+  hashVerified: boolean;
+  content?: {
+    shipmentId: string;
+    invoiceNumber: string;
+    invoiceCurrency: string;
+    boes: Array<{
+      boeCalculationId: string;
+      boeId?: string | null;
+      boeType: string;
+      exchangeRate: number;
+      assessableValue: number;
+      bcd: number;
+      sws: number;
+      igst: number;
+      aidc: number;
+      add: number;
+      safeguard: number;
+      cess: number;
+      interest: number;
+      customsDutyTotal: number;
+    }>;
+    items: ItemLandedCost[];
+    allocations: LandedCostAllocation[];
+    totals: SnapshotTotals;
+  } | null;
+}
+
+export interface SnapshotDiff {
+  snapshotId: string;
+  snapshotHash: string;
+  liveHash: string;
+  unchanged: boolean;
+  items: Array<{
+    invoiceLineItemId: string;
+    partNo: string;
+    snapshot?: ItemLandedCost | null;
+    live?: ItemLandedCost | null;
+    landedCostDelta: number;
+    landedCostPerUnitDelta?: number | null;
+  }>;
+  boesChanged: string[];
+  snapshotTotals: SnapshotTotals;
+  liveTotals: SnapshotTotals;
+}
+
+export interface ShipmentReopening {
+  id: number;
+  shipmentId: string;
+  snapshotId?: string | null;
+  reason: string;
+  reopenedBy?: string | null;
+  reopenedAt: string;
+}
+
+export type ShipmentMilestone =
+  | 'BOOKED'
+  | 'DEPARTED'
+  | 'ARRIVED_AT_PORT'
+  | 'IGM_FILED'
+  | 'BOE_FILED'
+  | 'OUT_OF_CHARGE'
+  | 'GATE_OUT'
+  | 'DELIVERED'
+  | 'EMPTY_RETURNED';
+
+export interface ShipmentEvent {
+  id: number;
+  shipmentId: string;
+  eventType: ShipmentMilestone;
+  occurredAt: string;
+  source: string;
+  notes?: string | null;
+  recordedBy?: string | null;
+  createdAt: string;
+  updatedAt: string;
+}
+
+export interface ShipmentTimeline {
+  shipmentId: string;
+  status?: string | null;
+  currentMilestone?: ShipmentMilestone | null;
+  events: ShipmentEvent[];
+  pending: ShipmentMilestone[];
+}
+
+export interface DwellSegment {
+  fromEvent: ShipmentMilestone;
+  toEvent: ShipmentMilestone;
+  fromAt: string;
+  toAt: string;
+  days: number;
+}
+
+export interface ShipmentDwellTimes {
+  shipmentId: string;
+  segments: DwellSegment[];
+  totalDays?: number | null;
+}
+
+export interface DwellSummary {
+  fromEvent: ShipmentMilestone;
+  toEvent: ShipmentMilestone;
+  shipmentCount: number;
+  averageDays?: number | null;
+  minDays?: number | null;
+  maxDays?: number | null;
+}
+
+export type ShipmentStatusTransitionErrorCode =
+  | 'SHIPMENT_NOT_FOUND'
+  | 'UNKNOWN_STATUS'
+  | 'TRANSITION_NOT_ALLOWED'
+  | 'BOE_REQUIRED'
+  | 'INTO_BOND_BOE_REQUIRED'
+  | 'DELIVERY_DATE_REQUIRED'
+  | 'REASON_REQUIRED'
+  | 'DATABASE';
+
+/** Rejection returned by `update_shipment_status`. */
+export interface ShipmentStatusTransitionError {
+  code: ShipmentStatusTransitionErrorCode;
+  shipmentId: string;
+  from?: string | null;
+  to: string;
+  message: string;
+  allowed: string[];
+}
+
+export interface ShipmentStatusTransition {
+  id: number;
+  shipmentId: string;
+  fromStatus?: string | null;
+  toStatus: string;
+  reason?: string | null;
+  source: 'MANUAL' | 'SYSTEM' | 'MILESTONE';
+  changedBy?: string | null;
+  changedAt: string;
+}
+
+export type FreeTimeChargeType = 'DEMURRAGE' | 'DETENTION';
+
+export interface FreeTimeRateTier {
+  chargeType: FreeTimeChargeType;
+  fromDay: number;
+  toDay?: number | null;
+  /** Per container per day. */
+  ratePerDay: number;
+}
+
+export interface FreeTimeTerms {
+  id: string;
+  name: string;
+  shippingLine?: string | null;
+  serviceProviderId?: string | null;
+  portFreeDays: number;
+  detentionFreeDays: number;
+  currency: string;
+  isActive: boolean;
+  notes?: string | null;
+  tiers: FreeTimeRateTier[];
+}
+
+export interface SaveFreeTimeTermsPayload
+  extends Omit<FreeTimeTerms, 'id' | 'currency' | 'isActive'> {
+  id?: string;
+  currency?: string;
+  isActive?: boolean;
+}
+
+export interface TierCharge {
+  fromDay: number;
+  toDay?: number | null;
+  days: number;
+  ratePerDay: number;
+  amount: number;
+}
+
+export interface FreeTimeClock {
+  chargeType: FreeTimeChargeType;
+  clockStart?: string | null;
+  clockStartSource?: 'MILESTONE' | 'ETA' | null;
+  clockEnd?: string | null;
+  running: boolean;
+  freeDays: number;
+  freeTimeEnds?: string | null;
+  daysUsed: number;
+  daysRemaining: number;
+  chargeableDays: number;
+  tiers: TierCharge[];
+  accruedAmount: number;
+  nextDayCharge: number;
+}
+
+export interface ShipmentFreeTime {
+  shipmentId: string;
+  termsId: string;
+  termsName: string;
+  currency: string;
+  containerNumbers: string[];
+  containers: number;
+  asOf: string;
+  clocks: FreeTimeClock[];
+  totalAccrued: number;
+}
+
+export interface DemurrageAccrual {
+  shipmentId: string;
+  chargeType: FreeTimeChargeType;
+  accrualDate: string;
+  containers: number;
+  daysUsed: number;
+  freeDays: number;
+  chargeableDays: number;
+  accruedAmount: number;
+  nextDayCharge: number;
+  currency: string;
+}
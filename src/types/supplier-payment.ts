@@ -0,0 +1,100 @@
+export type SupplierPaymentType = 'ADVANCE' | 'AGAINST_DOCUMENTS';
+export type InvoicePaymentStatus = 'UNPAID' | 'PARTIALLY_PAID' | 'PAID' | 'OVERPAID';
+
+export interface SupplierPayment {
+  id: string;
+  supplierId: string;
+  supplierName?: string | null;
+  shipmentId?: string | null;
+  invoiceNumber?: string | null;
+  paymentType: SupplierPaymentType;
+  paymentDate: string;
+  currency: string;
+  amount: number;
+  /** INR per unit of currency applied by the bank. */
+  exchangeRate: number;
+  inrAmount: number;
+  bankCharges: number;
+  bankReference?: string | null;
+  bankName?: string | null;
+  notes?: string | null;
+  createdBy?: string | null;
+  createdAt: string;
+  updatedAt: string;
+  customsRate?: number | null;
+  /** Positive is a gain against the customs rate. */
+  realisedFxGainLoss?: number | null;
+}
+
+export interface SaveSupplierPaymentPayload {
+  id?: string | null;
+  supplierId: string;
+  shipmentId?: string | null;
+  paymentType: SupplierPaymentType;
+  paymentDate: string;
+  currency: string;
+  amount: number;
+  exchangeRate: number;
+  bankCharges?: number;
+  bankReference?: string | null;
+  bankName?: string | null;
+  notes?: string | null;
+  userId?: string | null;
+}
+
+export interface InvoicePaymentPosition {
+  shipmentId: string;
+  supplierId: string;
+  supplierName?: string | null;
+  invoiceNumber: string;
+  invoiceDate: string;
+  currency: string;
+  invoiceValue: number;
+  paidAmount: number;
+  outstandingAmount: number;
+  paymentStatus: InvoicePaymentStatus;
+  payments: number;
+  lastPaymentDate?: string | null;
+  customsRate?: number | null;
+  customsRateAsOf?: string | null;
+  remittedInr: number;
+  realisedFxGainLoss?: number | null;
+  bankCharges: number;
+}
+
+export interface LedgerEntry {
+  date: string;
+  entryType: 'INVOICE' | 'PAYMENT';
+  reference: string;
+  shipmentId?: string | null;
+  paymentId?: string | null;
+  invoiceAmount: number;
+  paymentAmount: number;
+  balance: number;
+}
+
+export interface SupplierStatement {
+  supplierId: string;
+  supplierName?: string | null;
+  currency: string;
+  fromDate?: string | null;
+  toDate?: string | null;
+  openingBalance: number;
+  entries: LedgerEntry[];
+  closingBalance: number;
+  unappliedAdvances: number;
+}
+
+export interface PayablesAgeingRow {
+  supplierId: string;
+  supplierName?: string | null;
+  currency: string;
+  invoices: number;
+  days0To30: number;
+  days31To60: number;
+  days61To90: number;
+  days91To180: number;
+  over180: number;
+  totalOutstanding: number;
+  totalOutstandingInr?: number | null;
+}